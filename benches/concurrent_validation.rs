@@ -0,0 +1,87 @@
+//! Request validation throughput of one `ApiValidator` shared across threads
+//!
+//! Run with `cargo bench --bench concurrent_validation`.
+
+mod fixtures;
+
+use api_spec_drift_monitor_poc::{
+    build_api_validator, ApiValidator, Exchange, HttpMethod, ObservedRequest, ObservedResponse,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use openapiv3::OpenAPI;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::thread;
+
+/// Exchanges each thread validates per iteration
+const EXCHANGES_PER_THREAD: usize = 256;
+
+fn validator(spec: &OpenAPI) -> Arc<ApiValidator> {
+    Arc::new(build_api_validator(spec).expect("spec builds"))
+}
+
+/// A mix of clean and drifting traffic across the demo spec's operations
+fn exchanges() -> Vec<Exchange> {
+    let user = r#"{"id":"u1","email":"a@example.com","name":"Ada","age":36,"profile":{"bio":"hi"}}"#;
+    vec![
+        Exchange::new(
+            ObservedRequest::new(HttpMethod::GET, "/users?limit=10&status=active"),
+            ObservedResponse::new(200).with_body(format!(r#"{{"users":[{},{}]}}"#, user, user)),
+        ),
+        Exchange::new(
+            ObservedRequest::new(HttpMethod::POST, "/users")
+                .with_body(r#"{"email":"a@example.com","name":"Ada"}"#),
+            ObservedResponse::new(201).with_body(user),
+        ),
+        Exchange::new(
+            ObservedRequest::new(HttpMethod::GET, "/users/u1"),
+            ObservedResponse::new(200).with_body(r#"{"id":1,"email":"a@example.com","name":null}"#),
+        ),
+        Exchange::new(
+            ObservedRequest::new(HttpMethod::GET, "/users/missing"),
+            ObservedResponse::new(404).with_body(r#"{"code":"not_found","message":"No such user"}"#),
+        ),
+    ]
+}
+
+fn validate_batch(validator: &ApiValidator, exchanges: &[Exchange]) {
+    for exchange in exchanges.iter().cycle().take(EXCHANGES_PER_THREAD) {
+        black_box(validator.validate_exchange(black_box(exchange)).ok());
+    }
+}
+
+fn concurrent_validation(c: &mut Criterion) {
+    bench_threads(c, "validate_exchange", validator(&fixtures::small()), exchanges());
+    bench_threads(
+        c,
+        "validate_exchange_storefront",
+        validator(&fixtures::medium()),
+        fixtures::storefront_traffic(""),
+    );
+}
+
+fn bench_threads(c: &mut Criterion, name: &str, validator: Arc<ApiValidator>, exchanges: Vec<Exchange>) {
+    let mut thread_counts = vec![1, 2, 4, thread::available_parallelism().map(|n| n.get()).unwrap_or(1)];
+    thread_counts.sort_unstable();
+    thread_counts.dedup();
+
+    let mut group = c.benchmark_group(name);
+    for threads in thread_counts {
+        group.throughput(Throughput::Elements((threads * EXCHANGES_PER_THREAD) as u64));
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, &threads| {
+            b.iter(|| {
+                thread::scope(|scope| {
+                    for _ in 0..threads {
+                        let validator = Arc::clone(&validator);
+                        let exchanges = &exchanges;
+                        scope.spawn(move || validate_batch(&validator, exchanges));
+                    }
+                });
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, concurrent_validation);
+criterion_main!(benches);
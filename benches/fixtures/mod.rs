@@ -0,0 +1,147 @@
+//! Specs and traffic shared by the benchmarks
+//!
+//! Three spec sizes:
+//! - small: the demo spec at the crate root (a handful of operations)
+//! - medium: `storefront.yaml`, about 30 operations over shared components
+//! - huge: the storefront copied under `/t{k}` prefixes with its schemas
+//!   renamed per copy, so nothing is shared between copies and every
+//!   operation's schemas compile on their own
+
+#![allow(dead_code)]
+
+use api_spec_drift_monitor_poc::{
+    load_openapi_spec, load_spec_document, Exchange, HttpMethod, ObservedRequest, ObservedResponse,
+};
+use openapiv3::OpenAPI;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+/// Copies of the storefront making up the huge spec
+pub const HUGE_COPIES: usize = 40;
+
+/// The spec sizes benchmarked, by name
+pub fn specs() -> Vec<(&'static str, OpenAPI)> {
+    vec![("small", small()), ("medium", medium()), ("huge", huge())]
+}
+
+pub fn small() -> OpenAPI {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-api-spec.yaml");
+    load_openapi_spec(&path).expect("demo spec loads")
+}
+
+pub fn medium() -> OpenAPI {
+    load_openapi_spec(&storefront_path()).expect("storefront spec loads")
+}
+
+pub fn huge() -> OpenAPI {
+    let mut document = load_spec_document(&storefront_path()).expect("storefront spec loads");
+    let paths = document["paths"].as_object().cloned().unwrap_or_default();
+    let schemas = document["components"]["schemas"].as_object().cloned().unwrap_or_default();
+    let copy = Value::Object(Map::from_iter([
+        ("paths".to_string(), Value::Object(paths)),
+        ("schemas".to_string(), Value::Object(schemas)),
+    ]))
+    .to_string();
+
+    for k in 0..HUGE_COPIES {
+        let renamed = copy.replace("#/components/schemas/", &format!("#/components/schemas/t{}_", k));
+        let renamed: Value = serde_json::from_str(&renamed).expect("renamed copy parses");
+        for (path, item) in renamed["paths"].as_object().into_iter().flatten() {
+            document["paths"][format!("/t{}{}", k, path)] = rename_operation_ids(item.clone(), k);
+        }
+        for (name, schema) in renamed["schemas"].as_object().into_iter().flatten() {
+            document["components"]["schemas"][format!("t{}_{}", k, name)] = schema.clone();
+        }
+    }
+    serde_json::from_value(document).expect("huge spec is valid OpenAPI")
+}
+
+/// Keeps operation ids unique across copies
+fn rename_operation_ids(mut item: Value, k: usize) -> Value {
+    for operation in item.as_object_mut().into_iter().flat_map(|item| item.values_mut()) {
+        if let Some(id) = operation.get("operationId").and_then(Value::as_str) {
+            operation["operationId"] = Value::String(format!("t{}_{}", k, id));
+        }
+    }
+    item
+}
+
+fn storefront_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/storefront.yaml")
+}
+
+/// A product as the storefront returns it, with `variants` variants
+pub fn product(id: usize, variants: usize) -> String {
+    let variants: Vec<String> = (0..variants)
+        .map(|v| {
+            format!(
+                r#"{{"id":"var_{id:08}{v}","sku":"SKU-{id}-{v}","title":"Size {v}","price":{{"amount":1999,"currency":"EUR"}},"inventory":{{"available":{v},"reserved":0}},"options":{{"size":"{v}"}}}}"#
+            )
+        })
+        .collect();
+    format!(
+        r#"{{"id":"prod_{id:08}","name":"Product {id}","description":null,"status":"active","price":{{"amount":1999,"currency":"EUR"}},"categories":["shoes","sale"],"images":[{{"url":"https://cdn.example.com/{id}.jpg","alt":"Product {id}"}}],"variants":[{}],"attributes":{{"material":"canvas"}},"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-02-01T00:00:00Z"}}"#,
+        variants.join(",")
+    )
+}
+
+/// A page of `products` products, as `GET /products` returns it
+pub fn product_page(products: usize) -> String {
+    let data: Vec<String> = (0..products).map(|id| product(id, 3)).collect();
+    format!(r#"{{"data":[{}],"has_more":true,"next_cursor":"c2"}}"#, data.join(","))
+}
+
+fn authorized(request: ObservedRequest) -> ObservedRequest {
+    request.with_header("Authorization", "Bearer token")
+}
+
+/// `GET /products` conforming to the storefront spec
+pub fn list_products(prefix: &str) -> Exchange {
+    Exchange::new(
+        authorized(ObservedRequest::new(
+            HttpMethod::GET,
+            &format!("{}/products?limit=20&status=active&sort=-price", prefix),
+        )),
+        ObservedResponse::new(200)
+            .with_header("Content-Type", "application/json")
+            .with_header("X-RateLimit-Limit", "100")
+            .with_header("X-RateLimit-Remaining", "99")
+            .with_body(product_page(20)),
+    )
+}
+
+/// `POST /orders` conforming to the storefront spec
+pub fn create_order(prefix: &str) -> Exchange {
+    let address = r#"{"line1":"1 Main St","city":"Berlin","postal_code":"10115","country":"DE"}"#;
+    let order = format!(
+        r#"{{"id":"ord_12345678","status":"pending","customer_id":"cus_12345678","lines":[{{"variant_id":"var_00000001","quantity":2,"total":{{"amount":3998,"currency":"EUR"}}}}],"total":{{"amount":3998,"currency":"EUR"}},"shipping_address":{address},"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}}"#
+    );
+    Exchange::new(
+        authorized(
+            ObservedRequest::new(HttpMethod::POST, &format!("{}/orders", prefix))
+                .with_header("Content-Type", "application/json")
+                .with_body(format!(
+                    r#"{{"cart_id":"9b2f7c1e-3d4a-4b5c-8d6e-7f8091a2b3c4","shipping_address":{address}}}"#
+                )),
+        ),
+        ObservedResponse::new(201).with_header("Content-Type", "application/json").with_body(order),
+    )
+}
+
+/// `GET /products/{productId}` with drift in the response: a wrong type,
+/// an undocumented enum value and a missing required field
+pub fn drifting_product(prefix: &str) -> Exchange {
+    let body = product(1, 2)
+        .replace(r#""status":"active""#, r#""status":"discontinued""#)
+        .replace(r#""amount":1999"#, r#""amount":"19.99""#)
+        .replace(r#""name":"Product 1","#, "");
+    Exchange::new(
+        authorized(ObservedRequest::new(HttpMethod::GET, &format!("{}/products/prod_00000001", prefix))),
+        ObservedResponse::new(200).with_header("Content-Type", "application/json").with_body(body),
+    )
+}
+
+/// A mix of clean and drifting storefront traffic
+pub fn storefront_traffic(prefix: &str) -> Vec<Exchange> {
+    vec![list_products(prefix), create_order(prefix), drifting_product(prefix)]
+}
@@ -0,0 +1,54 @@
+#!/usr/bin/env bash
+# Compares benchmark results of the working tree against a git ref
+#
+#   benches/regression.sh [REF] [BENCH...]
+#
+# REF defaults to `main`; with no BENCH every benchmark runs. The ref is
+# checked out in a temporary worktree and benchmarked first, saving a
+# criterion baseline named after it; the working tree then runs against
+# that baseline. Each side runs its own benches and fixtures, so REF must
+# already have the benchmarks compared. Exits 1 when criterion reports any
+# benchmark as regressed beyond its noise threshold, so the script can
+# gate a merge.
+#
+# Extra criterion arguments can be passed through CRITERION_ARGS, e.g.
+# CRITERION_ARGS="--measurement-time 3" for a quicker, noisier run.
+
+set -euo pipefail
+
+ref="${1:-main}"
+shift || true
+benches=("$@")
+
+root="$(git rev-parse --show-toplevel)"
+baseline="$(echo "$ref" | tr -c '[:alnum:]_-' '_')"
+worktree="$(mktemp -d)"
+output="$(mktemp)"
+trap 'git -C "$root" worktree remove --force "$worktree" >/dev/null 2>&1 || true; rm -f "$output"' EXIT
+
+# Both runs share one target directory so criterion finds the saved baseline
+export CARGO_TARGET_DIR="${CARGO_TARGET_DIR:-$root/target}"
+
+bench_args=()
+for bench in "${benches[@]}"; do
+    bench_args+=(--bench "$bench")
+done
+
+git -C "$root" worktree add --detach "$worktree" "$ref" >/dev/null
+# Cargo.lock isn't committed; pin the ref to the same dependency versions
+if [ -f "$root/Cargo.lock" ]; then
+    cp "$root/Cargo.lock" "$worktree/Cargo.lock"
+fi
+
+echo "==> Benchmarking $ref"
+(cd "$worktree" && cargo bench "${bench_args[@]}" -- --save-baseline "$baseline" ${CRITERION_ARGS:-})
+
+echo "==> Benchmarking working tree against $ref"
+(cd "$root" && cargo bench "${bench_args[@]}" -- --baseline "$baseline" ${CRITERION_ARGS:-}) | tee "$output"
+
+if grep -q "Performance has regressed" "$output"; then
+    echo "==> Regressions against $ref:"
+    awk '/^[^ ]/ && !/^Benchmarking/ { name = $1 } /Performance has regressed/ { print "    " name }' "$output"
+    exit 1
+fi
+echo "==> No regressions against $ref"
@@ -0,0 +1,34 @@
+//! Time to build an `ApiValidator` from small, medium and huge specs
+//!
+//! Run with `cargo bench --bench spec_build`. Building is done once at
+//! startup, but a proxy that reloads its spec pays it again on every change.
+
+mod fixtures;
+
+use api_spec_drift_monitor_poc::{build_api_validator_with_options, BuildOptions};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+
+fn spec_build(c: &mut Criterion) {
+    let specs = fixtures::specs();
+    let eager = BuildOptions::default();
+    let lazy = BuildOptions {
+        lazy_compilation: true,
+        ..BuildOptions::default()
+    };
+
+    let mut group = c.benchmark_group("build_api_validator");
+    group.sample_size(10);
+    for (size, spec) in &specs {
+        group.bench_with_input(BenchmarkId::new("eager", size), spec, |b, spec| {
+            b.iter(|| black_box(build_api_validator_with_options(black_box(spec), &eager).expect("spec builds")));
+        });
+        group.bench_with_input(BenchmarkId::new("lazy", size), spec, |b, spec| {
+            b.iter(|| black_box(build_api_validator_with_options(black_box(spec), &lazy).expect("spec builds")));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, spec_build);
+criterion_main!(benches);
@@ -0,0 +1,69 @@
+//! Latency of validating a single request or exchange
+//!
+//! Run with `cargo bench --bench validation_latency`. Each benchmark
+//! validates one message on one thread, the cost added to a request when
+//! validating inline in a proxy; throughput across threads is measured by
+//! the `concurrent_validation` bench.
+
+mod fixtures;
+
+use api_spec_drift_monitor_poc::{build_api_validator, ApiValidator, Exchange};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+
+fn validate(validator: &ApiValidator, exchange: &Exchange) {
+    black_box(validator.validate_exchange(black_box(exchange)).ok());
+}
+
+fn validation_latency(c: &mut Criterion) {
+    let medium = build_api_validator(&fixtures::medium()).expect("storefront spec builds");
+
+    let mut group = c.benchmark_group("validate_request");
+    let order = fixtures::create_order("");
+    group.bench_function("create_order", |b| {
+        b.iter(|| black_box(medium.validate_request(black_box(&order.request)).ok()))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("validate_exchange");
+    for (name, exchange) in [
+        ("list_products", fixtures::list_products("")),
+        ("create_order", fixtures::create_order("")),
+        ("drifting_product", fixtures::drifting_product("")),
+    ] {
+        group.bench_function(name, |b| b.iter(|| validate(&medium, &exchange)));
+    }
+    group.finish();
+
+    // Body size dominates for list endpoints
+    let mut group = c.benchmark_group("validate_exchange_body_size");
+    for products in [1, 10, 100, 1000] {
+        let mut exchange = fixtures::list_products("");
+        let body = fixtures::product_page(products).into_bytes();
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        exchange.response.body = Some(body);
+        group.bench_with_input(BenchmarkId::from_parameter(products), &exchange, |b, exchange| {
+            b.iter(|| validate(&medium, exchange))
+        });
+    }
+    group.finish();
+
+    // Routing cost grows with the number of path templates
+    let huge = build_api_validator(&fixtures::huge()).expect("huge spec builds");
+    let last = format!("/t{}", fixtures::HUGE_COPIES - 1);
+    let mut group = c.benchmark_group("huge_spec");
+    group.bench_function("path_template", |b| {
+        let path = format!("{}/products/prod_00000001/variants/var_00000001", last);
+        b.iter(|| black_box(huge.path_template(black_box(&path))))
+    });
+    for (name, exchange) in [
+        ("first_copy", fixtures::list_products("/t0")),
+        ("last_copy", fixtures::list_products(&last)),
+    ] {
+        group.bench_function(name, |b| b.iter(|| validate(&huge, &exchange)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, validation_latency);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Packet captures, pcap or pcapng, reassembled into exchanges and
+//! validated against the demo spec
+
+#![no_main]
+
+use api_spec_drift_monitor_poc::{build_api_validator, parse_openapi_spec, ApiValidator, PcapReader};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+fn validator() -> &'static ApiValidator {
+    static VALIDATOR: OnceLock<ApiValidator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let spec = parse_openapi_spec(include_str!("../../test-api-spec.yaml")).expect("demo spec parses");
+        build_api_validator(&spec).expect("demo spec builds")
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(reader) = PcapReader::new(data) else {
+        return;
+    };
+    for record in reader.flatten() {
+        let _ = record.timestamp_ms();
+        if let Ok(exchange) = record.to_exchange() {
+            let _ = validator().validate_exchange(&exchange);
+        }
+    }
+});
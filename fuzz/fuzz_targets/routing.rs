@@ -0,0 +1,57 @@
+//! Path templates and request paths, routed through a validator built from
+//! the templates and through the template helpers directly
+
+#![no_main]
+
+use api_spec_drift_monitor_poc::{
+    build_api_validator_with_options, overlapping_templates, parse_openapi_spec, BuildOptions, HttpMethod,
+    ObservedRequest, PathTemplate,
+};
+use libfuzzer_sys::fuzz_target;
+use serde_json::{json, Map, Value};
+
+const METHODS: [HttpMethod; 5] =
+    [HttpMethod::GET, HttpMethod::POST, HttpMethod::PUT, HttpMethod::PATCH, HttpMethod::DELETE];
+
+fuzz_target!(|input: (Vec<String>, Option<String>, String, u8, bool)| {
+    let (templates, base_path, path, method, case_insensitive) = input;
+    let paths: Map<String, Value> = templates
+        .iter()
+        .map(|template| {
+            let parameters: Vec<Value> = PathTemplate::parse(template)
+                .parameters()
+                .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+                .collect();
+            let operation = json!({"parameters": parameters, "responses": {"200": {"description": "ok"}}});
+            (template.clone(), json!({"get": operation, "post": operation}))
+        })
+        .collect();
+
+    let templates: Vec<&str> = templates.iter().map(String::as_str).collect();
+    let _ = overlapping_templates(&templates);
+    for template in &templates {
+        let parsed = PathTemplate::parse(template);
+        let _ = parsed.router_syntax();
+        if let Ok(pattern) = parsed.pattern() {
+            let _ = pattern.captures(&path, case_insensitive);
+        }
+    }
+
+    let mut spec = json!({"openapi": "3.0.3", "info": {"title": "fuzz", "version": "1"}, "paths": paths});
+    if let Some(base_path) = &base_path {
+        spec["servers"] = json!([{"url": base_path}]);
+    }
+    let Ok(spec) = parse_openapi_spec(&spec.to_string()) else {
+        return;
+    };
+    let options = BuildOptions {
+        lenient: true,
+        ..BuildOptions::default()
+    };
+    let Ok(validator) = build_api_validator_with_options(&spec, &options) else {
+        return;
+    };
+    let _ = validator.path_template(&path);
+    let method = METHODS[usize::from(method) % METHODS.len()];
+    let _ = validator.validate_request(&ObservedRequest::new(method, &path));
+});
@@ -0,0 +1,30 @@
+//! Arbitrary YAML or JSON loaded as a spec, and built into a validator
+//! when it parses
+
+#![no_main]
+
+use api_spec_drift_monitor_poc::{
+    build_api_validator_with_options, parse_openapi_spec, BuildOptions, HttpMethod, ObservedRequest,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(spec) = parse_openapi_spec(source) else {
+        return;
+    };
+    for lenient in [false, true] {
+        let options = BuildOptions {
+            lenient,
+            ..BuildOptions::default()
+        };
+        if let Ok(validator) = build_api_validator_with_options(&spec, &options) {
+            for path in spec.paths.paths.keys() {
+                let _ = validator.path_template(path);
+                let _ = validator.validate_request(&ObservedRequest::new(HttpMethod::GET, path));
+            }
+        }
+    }
+});
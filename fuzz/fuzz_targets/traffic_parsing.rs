@@ -0,0 +1,43 @@
+//! Log lines in each traffic format, parsed into records and validated
+//! against the demo spec
+
+#![no_main]
+
+use api_spec_drift_monitor_poc::traffic::LogFormat;
+use api_spec_drift_monitor_poc::{
+    build_api_validator, parse_openapi_spec, ApiValidator, EnvoyFormat, JsonlFormat, LogReader, NginxFormat,
+};
+use libfuzzer_sys::fuzz_target;
+use std::sync::OnceLock;
+
+fn validator() -> &'static ApiValidator {
+    static VALIDATOR: OnceLock<ApiValidator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let spec = parse_openapi_spec(include_str!("../../test-api-spec.yaml")).expect("demo spec parses");
+        build_api_validator(&spec).expect("demo spec builds")
+    })
+}
+
+fn validate<F: LogFormat>(format: F, data: &[u8]) {
+    for record in LogReader::new(data, format).flatten() {
+        let _ = record.timestamp_ms();
+        if let Ok(exchange) = record.to_exchange() {
+            let _ = validator().validate_exchange(&exchange);
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    validate(JsonlFormat, data);
+    validate(EnvoyFormat::default(), data);
+    validate(NginxFormat::default(), data);
+
+    // The first line as an nginx `log_format`, the rest as lines in it
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (log_format, lines) = text.split_once('\n').unwrap_or((text, ""));
+    if let Ok(format) = NginxFormat::parse(log_format) {
+        validate(format, lines.as_bytes());
+    }
+});
@@ -0,0 +1,51 @@
+/*
+ * C ABI of api-spec-drift-monitor-poc, built with the `ffi` feature.
+ *
+ * Functions returning pointers return NULL on failure; drift_last_error()
+ * then describes the failure, a panic inside the library included
+ * ("PANIC: ..."). Strings returned by the library are freed with
+ * drift_string_free(). See src/ffi.rs for the full contract.
+ */
+
+#ifndef DRIFT_MONITOR_H
+#define DRIFT_MONITOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define DRIFT_ABI_VERSION 1
+
+typedef struct DriftValidator DriftValidator;
+
+uint32_t drift_abi_version(void);
+
+/* Valid until the next call on the same thread; not to be freed */
+const char *drift_last_error(void);
+
+/* config_path may be NULL */
+DriftValidator *drift_validator_load(const char *spec_path, const char *config_path);
+DriftValidator *drift_validator_parse(const char *spec, const char *config_path);
+void drift_validator_free(DriftValidator *validator);
+
+/*
+ * Headers are "Name: value" lines separated by "\n" or "\r\n", or NULL.
+ * A NULL body is absent. Both return a JSON array of drift events.
+ */
+char *drift_validate_request(const DriftValidator *validator, const char *method, const char *path,
+                             const char *headers, const uint8_t *body, size_t body_len);
+char *drift_validate_exchange(const DriftValidator *validator, const char *method, const char *path,
+                              const char *request_headers, const uint8_t *request_body,
+                              size_t request_body_len, uint16_t status, const char *response_headers,
+                              const uint8_t *response_body, size_t response_body_len);
+
+void drift_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DRIFT_MONITOR_H */
@@ -0,0 +1,253 @@
+//! Time-windowed drift aggregates and occurrence rates
+//!
+//! Raw counts say little without traffic volume: 300 missing fields is an
+//! outage on a quiet endpoint and noise on a busy one. [`DriftWindows`]
+//! counts validated exchanges alongside drift, per time bucket and
+//! operation, so each finding comes with the share of exchanges it affected.
+//! Under [`crate::sampling`], exchanges sampled out are counted too, and the
+//! share is estimated from the validated ones of each [`Stratum`].
+
+use crate::baseline::normalize_location;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::sampling::Stratum;
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// Bucket width used when none is given
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Drift finding within an operation: drift type and normalized location
+type FindingKey = (DriftType, String);
+
+/// Exchanges and drift seen for one operation in one bucket
+#[derive(Debug, Clone, Default)]
+struct OperationWindow {
+    strata: BTreeMap<Stratum, StratumWindow>,
+}
+
+/// Exchanges of one stratum, observed and validated, and their drift
+#[derive(Debug, Clone, Default)]
+struct StratumWindow {
+    seen: u64,
+    validated: u64,
+    /// Validated exchanges with at least one event of each finding
+    findings: BTreeMap<FindingKey, u64>,
+}
+
+impl OperationWindow {
+    fn merge(&mut self, other: &OperationWindow) {
+        for (stratum, window) in &other.strata {
+            let total = self.strata.entry(*stratum).or_default();
+            total.seen += window.seen;
+            total.validated += window.validated;
+            for (finding, count) in &window.findings {
+                *total.findings.entry(finding.clone()).or_default() += count;
+            }
+        }
+    }
+
+    fn exchanges(&self) -> u64 {
+        self.strata.values().map(|window| window.seen).sum()
+    }
+
+    fn validated(&self) -> u64 {
+        self.strata.values().map(|window| window.validated).sum()
+    }
+}
+
+/// How often a finding occurred among an operation's exchanges in a window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriftRate {
+    /// Start of the window, in milliseconds since the Unix epoch
+    pub window_start_ms: u64,
+    pub window_ms: u64,
+    /// Operation label, e.g. `GET /users/{userId}`
+    pub operation: String,
+    pub drift_type: DriftType,
+    /// Where the drift was found, with array indices collapsed to `*`
+    pub location: String,
+    /// Validated exchanges showing the drift
+    pub occurrences: u64,
+    /// Exchanges validated against the operation
+    pub validated: u64,
+    /// Exchanges observed on the operation, sampled out or not
+    pub exchanges: u64,
+    /// Estimated share of the exchanges showing the drift, between 0 and 1
+    ///
+    /// `occurrences / validated` when the exchanges were sampled alike;
+    /// otherwise each stratum's drift counts in proportion to its traffic.
+    pub rate: f64,
+}
+
+impl DriftRate {
+    /// One-line summary, e.g. "3.2% of GET /users exchanges: RESPONSE_BODY_MISSING_REQUIRED at body/*/email"
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.1}% of {} exchanges: {} at {}",
+            self.rate * 100.0,
+            self.operation,
+            self.drift_type.as_str(),
+            self.location
+        )
+    }
+}
+
+/// Exchanges and drift rolled into fixed-width time buckets per operation
+///
+/// Serializes as the list of [`DriftRate`]s across all buckets.
+#[derive(Debug, Clone)]
+pub struct DriftWindows {
+    bucket_ms: u64,
+    /// Most recent buckets kept; older ones are dropped as new ones open
+    retention: Option<usize>,
+    buckets: BTreeMap<u64, BTreeMap<String, OperationWindow>>,
+}
+
+impl Default for DriftWindows {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+impl DriftWindows {
+    pub fn new(bucket_width: Duration) -> Self {
+        Self {
+            bucket_ms: (bucket_width.as_millis() as u64).max(1),
+            retention: None,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Keeps only the `buckets` most recent buckets, bounding memory for long-running monitors
+    pub fn with_retention(mut self, buckets: usize) -> Self {
+        self.retention = Some(buckets.max(1));
+        self
+    }
+
+    pub fn bucket_width(&self) -> Duration {
+        Duration::from_millis(self.bucket_ms)
+    }
+
+    /// Counts one exchange validated against `operation` at `timestamp_ms`
+    /// and the drift found in it, which may be none
+    pub fn record_exchange(&mut self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        self.record_sampled(operation, timestamp_ms, Stratum::Success, Some(events));
+    }
+
+    /// Counts one exchange on `operation` at `timestamp_ms` with the drift
+    /// found in it, or `None` when it was sampled out rather than validated
+    pub fn record_sampled(
+        &mut self,
+        operation: &str,
+        timestamp_ms: u64,
+        stratum: Stratum,
+        events: Option<&[DriftEvent]>,
+    ) {
+        let bucket = timestamp_ms - timestamp_ms % self.bucket_ms;
+        let window = self
+            .buckets
+            .entry(bucket)
+            .or_default()
+            .entry(operation.to_string())
+            .or_default()
+            .strata
+            .entry(stratum)
+            .or_default();
+        window.seen += 1;
+        if let Some(events) = events {
+            window.validated += 1;
+            let findings: BTreeSet<FindingKey> = events
+                .iter()
+                .map(|event| (event.drift_type, normalize_location(&event.location)))
+                .collect();
+            for finding in findings {
+                *window.findings.entry(finding).or_default() += 1;
+            }
+        }
+
+        if let Some(retention) = self.retention {
+            while self.buckets.len() > retention {
+                self.buckets.pop_first();
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Rates per bucket, oldest first, then by operation and finding
+    pub fn rates(&self) -> Vec<DriftRate> {
+        self.buckets
+            .iter()
+            .flat_map(|(&start_ms, operations)| rates_of(start_ms, self.bucket_ms, operations))
+            .collect()
+    }
+
+    /// Rates in the most recent bucket
+    pub fn latest_rates(&self) -> Vec<DriftRate> {
+        self.buckets
+            .last_key_value()
+            .map(|(&start_ms, operations)| rates_of(start_ms, self.bucket_ms, operations))
+            .unwrap_or_default()
+    }
+
+    /// Rates over every bucket kept, as one window spanning them, highest first
+    pub fn overall_rates(&self) -> Vec<DriftRate> {
+        let (Some(first), Some(last)) = (self.buckets.first_key_value(), self.buckets.last_key_value()) else {
+            return Vec::new();
+        };
+        let mut merged: BTreeMap<String, OperationWindow> = BTreeMap::new();
+        for operations in self.buckets.values() {
+            for (operation, window) in operations {
+                merged.entry(operation.clone()).or_default().merge(window);
+            }
+        }
+        let mut rates = rates_of(*first.0, last.0 - first.0 + self.bucket_ms, &merged);
+        rates.sort_by(|a, b| b.rate.total_cmp(&a.rate).then(b.occurrences.cmp(&a.occurrences)));
+        rates
+    }
+}
+
+impl Serialize for DriftWindows {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.rates().serialize(serializer)
+    }
+}
+
+fn rates_of(start_ms: u64, window_ms: u64, operations: &BTreeMap<String, OperationWindow>) -> Vec<DriftRate> {
+    operations
+        .iter()
+        .flat_map(|(operation, window)| {
+            let exchanges = window.exchanges();
+            let validated = window.validated();
+            let findings: BTreeSet<&FindingKey> =
+                window.strata.values().flat_map(|stratum| stratum.findings.keys()).collect();
+            findings.into_iter().map(move |finding| {
+                let mut occurrences = 0;
+                let mut estimated = 0.0;
+                // Exchanges of strata with some validated, the only ones the estimate covers
+                let mut covered = 0;
+                for stratum in window.strata.values().filter(|stratum| stratum.validated > 0) {
+                    let count = stratum.findings.get(finding).copied().unwrap_or(0);
+                    occurrences += count;
+                    estimated += count as f64 * stratum.seen as f64 / stratum.validated as f64;
+                    covered += stratum.seen;
+                }
+                DriftRate {
+                    window_start_ms: start_ms,
+                    window_ms,
+                    operation: operation.clone(),
+                    drift_type: finding.0,
+                    location: finding.1.clone(),
+                    occurrences,
+                    validated,
+                    exchanges,
+                    rate: estimated / covered.max(1) as f64,
+                }
+            })
+        })
+        .collect()
+}
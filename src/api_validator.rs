@@ -1,11 +1,34 @@
+use crate::array_sampling::ArraySampling;
+use crate::content_encoding::{self, DecodeError};
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::drift_types::DriftType;
 use crate::error::ValidationError;
-use crate::validators::{ParametersValidator, RequestBodyValidator, ResponseValidator};
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse};
+use crate::exchange_filter::ExchangeFilter;
+use crate::redaction::Redaction;
+use crate::routing::{fold_template, BasePath, Overlap, PathNormalization, PathTemplate, TemplatePattern};
+use crate::shallow::ShallowBody;
+use crate::instrumentation::{self, SpanTimer};
+use crate::ownership::Ownership;
+use crate::suppression::Suppressions;
+use crate::truncation::TruncatedBody;
+use crate::validator_config::{DriftPolicy, DEFAULT_MAX_DECOMPRESSED_BYTES};
+use crate::validation_helpers::gather_drift_events;
+use crate::validators::response::{media_type_essence, media_type_matches};
+use crate::validators::{
+    DeprecationValidator, GraphQlValidator, IdempotencyValidator, ParametersValidator, RateLimitValidator,
+    RequestBodyValidator, ResponseValidator, SecurityValidator,
+};
 use matchit::Router;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// HTTP methods supported by OpenAPI
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum HttpMethod {
     GET,
     POST,
@@ -52,9 +75,33 @@ impl FromStr for HttpMethod {
 
 /// Validator for a single API operation (path + method combination)
 pub struct OperationValidator {
+    pub operation_id: Option<String>,
+    /// The spec's `tags` for the operation
+    pub tags: Vec<String>,
+    /// The spec's `summary` of the operation
+    pub summary: Option<String>,
     pub request_body: Option<RequestBodyValidator>,
     pub responses: ResponseValidator,
     pub parameters: ParametersValidator,
+    pub rate_limits: Option<RateLimitValidator>,
+    pub idempotency: Option<IdempotencyValidator>,
+    pub security: Option<SecurityValidator>,
+    /// Which items of large arrays in JSON bodies get validated
+    pub array_sampling: ArraySampling,
+    /// Check the parseable prefix of bodies cut off mid-JSON instead of reporting them as malformed
+    pub truncated_captures: bool,
+    /// Set for GraphQL-over-HTTP endpoints, whose bodies bypass schema validation
+    pub graphql: Option<GraphQlValidator>,
+    /// Bodies larger than this many bytes are not validated, or only partially
+    pub max_body_bytes: Option<usize>,
+    /// Nesting depth bodies over `max_body_bytes` are validated to; `None` skips them
+    pub oversized_body_depth: Option<usize>,
+    /// Compressed bodies decoding to more than this many bytes are reported undecodable
+    pub max_decompressed_bytes: usize,
+    /// Media types whose bodies are validated; empty validates every body
+    pub content_types: Vec<String>,
+    /// Set when the operation, or any of its parameters or request body fields, is deprecated
+    pub deprecation: Option<DeprecationValidator>,
 }
 
 impl OperationValidator {
@@ -64,53 +111,945 @@ impl OperationValidator {
         parameters: ParametersValidator,
     ) -> Self {
         Self {
+            operation_id: None,
+            tags: Vec::new(),
+            summary: None,
             request_body,
             responses,
             parameters,
+            rate_limits: None,
+            idempotency: None,
+            security: None,
+            array_sampling: ArraySampling::All,
+            truncated_captures: false,
+            graphql: None,
+            max_body_bytes: None,
+            oversized_body_depth: None,
+            max_decompressed_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+            content_types: Vec::new(),
+            deprecation: None,
         }
     }
+
+    /// Sets the spec's `operationId`, attached to every drift event for this operation
+    pub fn with_operation_id(mut self, operation_id: Option<String>) -> Self {
+        self.operation_id = operation_id;
+        self
+    }
+
+    /// Sets the spec's `tags` and `summary`, attached to every drift event for this operation
+    pub fn with_metadata(mut self, tags: Vec<String>, summary: Option<String>) -> Self {
+        self.tags = tags;
+        self.summary = summary;
+        self
+    }
+
+    /// Enables rate-limit header contract checks for this operation
+    pub fn with_rate_limits(mut self, rate_limits: RateLimitValidator) -> Self {
+        self.rate_limits = Some(rate_limits);
+        self
+    }
+
+    /// Enables `Idempotency-Key` contract checks for this operation
+    pub fn with_idempotency(mut self, idempotency: IdempotencyValidator) -> Self {
+        self.idempotency = Some(idempotency);
+        self
+    }
+
+    /// Enables checks that requests carry the credentials the spec's `security` declares
+    pub fn with_security(mut self, security: SecurityValidator) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// Reports requests using what the spec marks deprecated
+    pub fn with_deprecation(mut self, deprecation: DeprecationValidator) -> Self {
+        self.deprecation = Some(deprecation);
+        self
+    }
+
+    /// Validates a sample of each large array in JSON bodies instead of every item
+    pub fn with_array_sampling(mut self, array_sampling: ArraySampling) -> Self {
+        self.array_sampling = array_sampling;
+        self
+    }
+
+    /// Checks the parseable prefix of bodies truncated by the capture source,
+    /// marking findings `from_truncated_capture`
+    pub fn with_truncated_captures(mut self, truncated_captures: bool) -> Self {
+        self.truncated_captures = truncated_captures;
+        self
+    }
+
+    /// Skips validating bodies larger than `limit` bytes
+    pub fn with_max_body_bytes(mut self, limit: Option<usize>) -> Self {
+        self.max_body_bytes = limit;
+        self
+    }
+
+    /// Validates bodies over the size limit down to `depth` levels of nesting
+    /// instead of skipping them, marking findings with the depth
+    pub fn with_oversized_body_depth(mut self, depth: Option<usize>) -> Self {
+        self.oversized_body_depth = depth;
+        self
+    }
+
+    /// Reports compressed bodies decoding to more than `limit` bytes as undecodable instead of validating them
+    pub fn with_max_decompressed_bytes(mut self, limit: usize) -> Self {
+        self.max_decompressed_bytes = limit;
+        self
+    }
+
+    /// Only validates bodies labelled with one of `content_types`, wildcards allowed
+    pub fn with_content_types(mut self, content_types: Vec<String>) -> Self {
+        self.content_types = content_types;
+        self
+    }
+
+    /// Classifies this operation as a GraphQL endpoint
+    pub fn with_graphql(mut self, graphql: GraphQlValidator) -> Self {
+        self.graphql = Some(graphql);
+        self
+    }
+
+    /// Whether this operation is a GraphQL-over-HTTP endpoint
+    pub fn is_graphql(&self) -> bool {
+        self.graphql.is_some()
+    }
+
+    /// Collects drift events for the request half of an exchange
+    pub fn request_drift_events(
+        &self,
+        request: &ObservedRequest,
+        path_params: &HashMap<String, String>,
+    ) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.request_drift_events_with(request, path_params, emit))
+    }
+
+    /// Passes each drift event for the request half of an exchange to `emit`
+    pub fn request_drift_events_with(
+        &self,
+        request: &ObservedRequest,
+        path_params: &HashMap<String, String>,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        instrumentation::check("parameters", emit, |emit| {
+            self.parameters.path_drift_events_with(path_params, emit);
+            self.parameters.query_string_drift_events_with(request.query.as_deref(), emit);
+        });
+
+        if let Some(idempotency) = &self.idempotency {
+            instrumentation::check("idempotency", emit, |emit| idempotency.request_drift_events_with(request, emit));
+        }
+        if let Some(deprecation) = &self.deprecation {
+            instrumentation::check("deprecation", emit, |emit| {
+                deprecation.request_drift_events_with(request, path_params, emit)
+            });
+        }
+
+        let content_type = request.header("content-type");
+        let content_encoding = request.header("content-encoding");
+        let body = match self.decoded_body(content_type, content_encoding, request.body.as_deref()) {
+            Ok(body) => body,
+            Err(e) => return emit(undecodable_body_event(DriftType::RequestBodyUndecodable, content_encoding, &e)),
+        };
+        let body = body.as_deref();
+        if !self.validates_body(content_type, body) {
+            return;
+        }
+        if let Some(graphql) = &self.graphql {
+            if graphql.checks_envelope() && !self.is_oversized(body) {
+                instrumentation::check("graphql", emit, |emit| match parse_json_body(body) {
+                    Ok(body) => graphql.request_drift_events_with(body.as_ref(), emit),
+                    Err(e) => emit(malformed_body_event(DriftType::RequestBodyMalformedJson, &e)),
+                });
+            }
+        } else if let Some(request_body) = &self.request_body {
+            instrumentation::check("request_body", emit, |emit| {
+                self.body_drift_events_with(
+                    body,
+                    DriftType::RequestBodyMalformedJson,
+                    emit,
+                    |body, emit| {
+                        request_body.drift_events_with(body, emit);
+                        if let Some(deprecation) = &self.deprecation {
+                            deprecation.body_drift_events_with(body, emit);
+                        }
+                    },
+                )
+            });
+        }
+    }
+
+    /// Collects drift events for the response half of an exchange
+    pub fn response_drift_events(&self, response: &ObservedResponse) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.response_drift_events_with(response, emit))
+    }
+
+    /// Passes each drift event for the response half of an exchange to `emit`
+    pub fn response_drift_events_with(&self, response: &ObservedResponse, emit: &mut dyn FnMut(DriftEvent)) {
+        let content_type = response.header("content-type");
+
+        // A body served in an undeclared non-JSON format (an HTML error page)
+        // would only add a malformed JSON finding on top
+        let has_body = response.body.as_deref().is_some_and(|body| !body.iter().all(u8::is_ascii_whitespace));
+        let undeclared_media_type = self
+            .responses
+            .content_type_drift_events_with(response.status, content_type, has_body, emit);
+        let skip_body = undeclared_media_type
+            && !content_type.is_some_and(|content_type| content_type.to_ascii_lowercase().contains("json"));
+
+        if !skip_body {
+            let content_encoding = response.header("content-encoding");
+            match self.decoded_body(content_type, content_encoding, response.body.as_deref()) {
+                Ok(body) if self.validates_body(content_type, body.as_deref()) => {
+                    instrumentation::check("response_body", emit, |emit| {
+                        self.response_body_drift_events_with(response.status, body.as_deref(), content_type, emit)
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => emit(undecodable_body_event(DriftType::ResponseBodyUndecodable, content_encoding, &e)),
+            }
+        }
+
+        if let Some(rate_limits) = &self.rate_limits {
+            instrumentation::check("rate_limits", emit, |emit| rate_limits.drift_events_with(response, emit));
+        }
+    }
+
+    /// Validates the response body in whichever format the operation documents for it
+    fn response_body_drift_events_with(
+        &self,
+        status: u16,
+        body: Option<&[u8]>,
+        content_type: Option<&str>,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        let stream = self.responses.event_stream_for(status, content_type);
+
+        if let Some(graphql) = &self.graphql {
+            if graphql.checks_envelope() && !self.is_oversized(body) {
+                match parse_json_body(body) {
+                    Ok(body) => graphql.response_drift_events_with(body.as_ref(), emit),
+                    Err(e) => emit(malformed_body_event(DriftType::ResponseBodyMalformedJson, &e)),
+                }
+            }
+        } else if let Some(stream) = stream {
+            if !self.is_oversized(body) {
+                stream.drift_events_with(body.unwrap_or_default(), emit);
+            }
+        } else {
+            self.body_drift_events_with(
+                body,
+                DriftType::ResponseBodyMalformedJson,
+                emit,
+                |body, emit| self.responses.drift_events_with(status, body, emit),
+            );
+        }
+    }
+
+    /// The body with its `Content-Encoding` undone
+    ///
+    /// Bodies of media types that aren't validated are left as they are.
+    fn decoded_body<'a>(
+        &self,
+        content_type: Option<&str>,
+        content_encoding: Option<&str>,
+        body: Option<&'a [u8]>,
+    ) -> Result<Option<Cow<'a, [u8]>>, DecodeError> {
+        let (Some(content_encoding), Some(bytes)) = (content_encoding, body) else {
+            return Ok(body.map(Cow::Borrowed));
+        };
+        if !self.validates_media_type(content_type) {
+            return Ok(Some(Cow::Borrowed(bytes)));
+        }
+        content_encoding::decode(content_encoding, bytes, self.max_decompressed_bytes).map(Some)
+    }
+
+    /// Whether a body served as `content_type` is within the configured size and media types
+    ///
+    /// Absent bodies are always checked, so required ones are still reported
+    /// missing; oversized ones only when they're validated to a depth.
+    fn validates_body(&self, content_type: Option<&str>, body: Option<&[u8]>) -> bool {
+        if body.is_none() {
+            return true;
+        }
+        if self.is_oversized(body) && self.oversized_body_depth.is_none() {
+            return false;
+        }
+        self.validates_media_type(content_type)
+    }
+
+    /// Whether bodies served as `content_type` are validated at all
+    fn validates_media_type(&self, content_type: Option<&str>) -> bool {
+        match content_type.map(media_type_essence) {
+            Some(observed) if !self.content_types.is_empty() && !observed.is_empty() => self
+                .content_types
+                .iter()
+                .any(|media_type| media_type_matches(media_type, &observed)),
+            _ => true,
+        }
+    }
+
+    fn is_oversized(&self, body: Option<&[u8]>) -> bool {
+        body.zip(self.max_body_bytes).is_some_and(|(body, limit)| body.len() > limit)
+    }
+
+    /// Parses a JSON body and runs a check against it
+    ///
+    /// With truncated captures enabled, a body that ends mid-JSON is checked
+    /// on its parseable prefix instead of being reported as malformed.
+    /// Oversized bodies are streamed into their outer levels and checked on those.
+    fn body_drift_events_with(
+        &self,
+        raw: Option<&[u8]>,
+        malformed: DriftType,
+        emit: &mut dyn FnMut(DriftEvent),
+        check: impl FnOnce(Option<&Value>, &mut dyn FnMut(DriftEvent)),
+    ) {
+        if let (Some(bytes), Some(depth)) = (raw.filter(|_| self.is_oversized(raw)), self.oversized_body_depth) {
+            match ShallowBody::parse(bytes, depth) {
+                Ok(shallow) => {
+                    let emit_shallow = &mut |event| {
+                        if let Some(event) = shallow.annotate(event) {
+                            emit(event)
+                        }
+                    };
+                    return self.sampled_drift_events_with(Some(&shallow.value), emit_shallow, check);
+                }
+                // A cut-off capture is left to the prefix check below
+                Err(e) if e.is_eof() && self.truncated_captures => {}
+                Err(e) => return emit(malformed_body_event(malformed, &e)),
+            }
+        }
+        match parse_json_body(raw) {
+            Ok(body) => self.sampled_drift_events_with(body.as_ref(), emit, check),
+            Err(e) if e.is_eof() && self.truncated_captures => {
+                // A cut-off body with no complete value in it has nothing to check
+                if let Some(truncated) = raw.and_then(TruncatedBody::repair) {
+                    let emit_truncated = &mut |event| {
+                        if let Some(event) = truncated.annotate(event) {
+                            emit(event)
+                        }
+                    };
+                    self.sampled_drift_events_with(Some(&truncated.value), emit_truncated, check);
+                }
+            }
+            Err(e) => emit(malformed_body_event(malformed, &e)),
+        }
+    }
+
+    /// Runs a body check against the body with its large arrays sampled
+    fn sampled_drift_events_with(
+        &self,
+        body: Option<&Value>,
+        emit: &mut dyn FnMut(DriftEvent),
+        check: impl FnOnce(Option<&Value>, &mut dyn FnMut(DriftEvent)),
+    ) {
+        match body.and_then(|body| self.array_sampling.sample(body)) {
+            Some(sampled) => check(Some(&sampled.value), &mut |event| {
+                if let Some(event) = sampled.annotate(event) {
+                    emit(event)
+                }
+            }),
+            None => check(body, emit),
+        }
+    }
+
+    /// Collects drift events for a full exchange, including checks spanning both halves
+    pub fn exchange_drift_events(
+        &self,
+        exchange: &Exchange,
+        path_params: &HashMap<String, String>,
+    ) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.exchange_drift_events_with(exchange, path_params, emit))
+    }
+
+    /// Passes each drift event for a full exchange to `emit`
+    pub fn exchange_drift_events_with(
+        &self,
+        exchange: &Exchange,
+        path_params: &HashMap<String, String>,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        self.request_drift_events_with(&exchange.request, path_params, emit);
+        self.response_drift_events_with(&exchange.response, emit);
+
+        if let Some(idempotency) = &self.idempotency {
+            instrumentation::check("idempotency", emit, |emit| {
+                idempotency.replay_drift_events_with(&exchange.request, &exchange.response, emit)
+            });
+        }
+
+        if let Some(security) = &self.security {
+            instrumentation::check("security", emit, |emit| {
+                security.drift_events_with(&exchange.request, &exchange.response, emit)
+            });
+        }
+    }
+}
+
+/// Parses a raw body as JSON, treating an empty body as absent
+fn parse_json_body(body: Option<&[u8]>) -> Result<Option<Value>, serde_json::Error> {
+    match body {
+        None => Ok(None),
+        Some(bytes) if bytes.iter().all(u8::is_ascii_whitespace) => Ok(None),
+        Some(bytes) => serde_json::from_slice(bytes).map(Some),
+    }
+}
+
+fn malformed_body_event(drift_type: DriftType, error: &serde_json::Error) -> DriftEvent {
+    DriftEvent::new(drift_type, "body", format!("Body is not valid JSON: {}", error))
+}
+
+fn undecodable_body_event(drift_type: DriftType, content_encoding: Option<&str>, error: &DecodeError) -> DriftEvent {
+    let message = format!(
+        "Body with Content-Encoding '{}' could not be decoded: {}",
+        content_encoding.unwrap_or_default(),
+        error
+    );
+    DriftEvent::new(drift_type, "body", message)
 }
 
 /// Map of HTTP methods to their operation validators
 type OperationMap = HashMap<HttpMethod, OperationValidator>;
 
+/// Operations registered for a single spec path
+struct PathEntry {
+    template: PathTemplate,
+    /// The template in router syntax; unset when matched by a fallback pattern
+    route: Option<String>,
+    operations: OperationMap,
+}
+
 /// Top-level API validator that validates requests/responses against an OpenAPI spec
+///
+/// Validation takes `&self` and the validator is `Send + Sync`, so one
+/// instance can be shared behind an `Arc` by every worker thread of a proxy
+/// or middleware. Per-operation state (idempotency keys, query caches) is
+/// guarded internally.
+#[derive(Default)]
 pub struct ApiValidator {
-    router: Router<OperationMap>,
+    /// Maps concrete paths to indices into `paths`
+    router: Router<usize>,
+    paths: Vec<PathEntry>,
+    /// Exchanges skipped before routing
+    filter: ExchangeFilter,
+    /// Values masked on every emitted event
+    redaction: Redaction,
+    /// Drift types dropped or reclassified before events are emitted
+    policy: DriftPolicy,
+    /// Accepted drift dropped before events are emitted
+    suppressions: Suppressions,
+    /// Events dropped by `suppressions` so far
+    suppressed: AtomicU64,
+    /// Resolves the owner of every emitted event
+    ownership: Ownership,
+    /// Prefixes stripped from request paths before routing, longest first
+    base_paths: Vec<BasePath>,
+    /// Route paths under undeclared prefixes by dropping leading segments, reporting the prefix
+    report_undeclared_base_paths: bool,
+    /// Rewrites applied to request paths before routing
+    normalization: PathNormalization,
+    /// `router` with literal segments lowercased, filled for case-insensitive routing
+    folded_router: Router<usize>,
+    /// Templates the router can't represent, most concrete first
+    fallback_routes: Vec<FallbackRoute>,
+}
+
+/// A template matched by regex because the router can't represent it
+struct FallbackRoute {
+    pattern: TemplatePattern,
+    /// Index into `paths`
+    index: usize,
+    /// Router templates this one is more concrete than, so it is tried
+    /// first for paths routing to them
+    shadows: Vec<usize>,
 }
 
 impl ApiValidator {
     pub fn new() -> Self {
-        Self {
-            router: Router::new(),
+        Self::default()
+    }
+
+    /// Skips exchanges the filter rejects (by method, status or origin) before any validation
+    pub fn with_exchange_filter(mut self, filter: ExchangeFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// The pre-filter applied to every exchange
+    pub fn exchange_filter(&self) -> &ExchangeFilter {
+        &self.filter
+    }
+
+    /// Masks the fields and headers `redaction` names on every event before it is emitted
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Drops or reclassifies drift types per `policy` before events are emitted
+    pub fn with_drift_policy(mut self, policy: DriftPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Drops events any of `suppressions` matches before they are emitted
+    pub fn with_suppressions(mut self, suppressions: Suppressions) -> Self {
+        self.suppressions = suppressions;
+        self
+    }
+
+    /// Attaches the owner `ownership` resolves to every event before it is emitted
+    pub fn with_ownership(mut self, ownership: Ownership) -> Self {
+        self.ownership = ownership;
+        self
+    }
+
+    /// Events suppressed since the validator was built
+    ///
+    /// Read it before and after a run to count the run's suppressions.
+    pub fn suppressed_events(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    /// Whether `event` is suppressed, counting it if so
+    fn suppress(&self, event: &DriftEvent) -> bool {
+        let suppressed = self.suppressions.suppresses(event);
+        if suppressed {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
         }
+        suppressed
+    }
+
+    /// Strips `base_paths` (e.g. the spec's server paths) from request paths before routing
+    pub fn with_base_paths(mut self, base_paths: Vec<BasePath>) -> Self {
+        self.base_paths = base_paths;
+        self
+    }
+
+    /// Also strips `base_path` from request paths before routing
+    pub fn add_base_path(&mut self, base_path: BasePath) {
+        if base_path.is_root() || self.base_paths.contains(&base_path) {
+            return;
+        }
+        // Longest first, so `/api/v2` is tried before `/api`
+        let position = self
+            .base_paths
+            .iter()
+            .position(|existing| existing.depth() < base_path.depth())
+            .unwrap_or(self.base_paths.len());
+        self.base_paths.insert(position, base_path);
+    }
+
+    /// Cleans up request paths per `normalization` before routing
+    pub fn with_path_normalization(mut self, normalization: PathNormalization) -> Self {
+        self.normalization = normalization;
+        self.folded_router = Router::new();
+        if normalization.case_insensitive {
+            for (index, entry) in self.paths.iter().enumerate() {
+                // Templates differing only in case keep the first
+                if let Some(route) = &entry.route {
+                    let _ = self.folded_router.insert(fold_template(route), index);
+                }
+            }
+        }
+        self
+    }
+
+    /// Routes paths under a prefix no base path declares, reporting the prefix as drift
+    pub fn with_undeclared_base_paths_reported(mut self, report: bool) -> Self {
+        self.report_undeclared_base_paths = report;
+        self
+    }
+
+    /// The redaction applied to every event
+    pub fn redaction(&self) -> &Redaction {
+        &self.redaction
     }
 
     /// Adds all operations for a path at once
+    ///
+    /// Templates the router can't represent are matched by a regex instead;
+    /// the reason is returned so it can be reported. Fails if `path` is
+    /// ambiguous with a template already added: some path would match
+    /// both, and neither is more concrete.
     pub fn add_path_operations(
         &mut self,
         path: &str,
         operations: HashMap<HttpMethod, OperationValidator>,
-    ) -> Result<(), ValidationError> {
-        self.router.insert(path, operations).map_err(|e| {
-            ValidationError::SchemaCompilationError(format!(
-                "Failed to add route '{}': {}",
-                path, e
-            ))
+    ) -> Result<Option<String>, ValidationError> {
+        let index = self.paths.len();
+        let template = PathTemplate::parse(path);
+        let overlaps: Vec<(usize, Overlap)> = self
+            .paths
+            .iter()
+            .enumerate()
+            .filter_map(|(other, entry)| template.overlap(&entry.template).map(|overlap| (other, overlap)))
+            .collect();
+        if let Some((other, _)) = overlaps.iter().find(|(_, overlap)| *overlap == Overlap::Ambiguous) {
+            return Err(ValidationError::RouteConflict {
+                path: path.to_string(),
+                reason: format!(
+                    "ambiguous with {}: some paths match both and neither is more concrete",
+                    self.paths[*other].template
+                ),
+            });
+        }
+        let inserted = match template.router_syntax() {
+            // Unambiguous templates can still conflict in the router, e.g.
+            // parameters named differently in the same segment
+            Ok(route) => match self.router.insert(route.as_str(), index) {
+                Ok(()) => Ok(route),
+                Err(e) => Err(e.to_string()),
+            },
+            Err(reason) => Err(reason),
+        };
+        let (route, fallback_reason) = match inserted {
+            Ok(route) => {
+                if self.normalization.case_insensitive {
+                    let _ = self.folded_router.insert(fold_template(&route), index);
+                }
+                // Fallback templates more concrete than this one take its paths
+                for (other, overlap) in &overlaps {
+                    if *overlap == Overlap::ShadowedBy {
+                        if let Some(fallback) = self.fallback_routes.iter_mut().find(|route| route.index == *other) {
+                            fallback.shadows.push(index);
+                        }
+                    }
+                }
+                (Some(route), None)
+            }
+            Err(reason) => {
+                let pattern = template.pattern().map_err(|pattern_error| ValidationError::RouteConflict {
+                    path: path.to_string(),
+                    reason: format!("{}; {}", reason, pattern_error),
+                })?;
+                let shadows_route = |other: usize| overlaps.contains(&(other, Overlap::Shadows));
+                let shadows = overlaps
+                    .iter()
+                    .filter(|(other, overlap)| *overlap == Overlap::Shadows && self.paths[*other].route.is_some())
+                    .map(|(other, _)| *other)
+                    .collect();
+                let position = self
+                    .fallback_routes
+                    .iter()
+                    .position(|route| shadows_route(route.index))
+                    .unwrap_or(self.fallback_routes.len());
+                self.fallback_routes.insert(
+                    position,
+                    FallbackRoute {
+                        pattern,
+                        index,
+                        shadows,
+                    },
+                );
+                (None, Some(reason))
+            }
+        };
+        self.paths.push(PathEntry {
+            template,
+            route,
+            operations,
+        });
+        Ok(fallback_reason)
+    }
+
+    /// Every registered operation as (path template, method, validator)
+    pub fn operations(&self) -> impl Iterator<Item = (&str, HttpMethod, &OperationValidator)> {
+        self.paths.iter().flat_map(|entry| {
+            entry
+                .operations
+                .iter()
+                .map(move |(method, operation)| (entry.template.as_str(), *method, operation))
         })
     }
 
-    /// Finds the operation validator for a given path and method
-    pub fn find_operation<'a>(
-        &'a self,
-        path: &'a str,
+    /// Finds the operation validator for a given path and method, with the path parameters it binds
+    pub fn find_operation(
+        &self,
+        path: &str,
         method: HttpMethod,
-    ) -> Result<(&'a OperationValidator, matchit::Params<'a, 'a>), ValidationError> {
-        let matched = self.router.at(path).map_err(|_| {
+    ) -> Result<(&OperationValidator, HashMap<String, String>), ValidationError> {
+        let (_, operation, params, _) = self.route(path, method)?;
+        Ok((operation, params))
+    }
+
+    /// Validates an observed request against its operation
+    pub fn validate_request(&self, request: &ObservedRequest) -> Result<Vec<DriftEvent>, ValidationError> {
+        self.validate_request_with_context(request, &EventContext::new())
+    }
+
+    /// Validates an observed request, attaching `context` to every resulting event
+    pub fn validate_request_with_context(
+        &self,
+        request: &ObservedRequest,
+        context: &EventContext,
+    ) -> Result<Vec<DriftEvent>, ValidationError> {
+        let mut events = Vec::new();
+        self.request_events(request, context, &mut |event| events.push(event))?;
+        Ok(events)
+    }
+
+    /// Validates an observed request, invoking `on_event` for each finding as it is discovered
+    pub fn validate_request_with(
+        &self,
+        request: &ObservedRequest,
+        mut on_event: impl FnMut(DriftEvent),
+    ) -> Result<(), ValidationError> {
+        self.request_events(request, &EventContext::new(), &mut on_event)
+    }
+
+    /// Validates an observed request/response pair against its operation
+    pub fn validate_exchange(&self, exchange: &Exchange) -> Result<Vec<DriftEvent>, ValidationError> {
+        self.validate_exchange_with_context(exchange, &EventContext::new())
+    }
+
+    /// Validates an observed request/response pair, attaching `context` to every resulting event
+    pub fn validate_exchange_with_context(
+        &self,
+        exchange: &Exchange,
+        context: &EventContext,
+    ) -> Result<Vec<DriftEvent>, ValidationError> {
+        let mut events = Vec::new();
+        self.exchange_events(exchange, context, &mut |event| events.push(event))?;
+        Ok(events)
+    }
+
+    /// Validates an observed request/response pair, invoking `on_event` for each finding
+    ///
+    /// Events are handed over as they are discovered, without collecting them
+    /// first, so hot middleware can forward them without intermediate allocation.
+    pub fn validate_exchange_with(
+        &self,
+        exchange: &Exchange,
+        mut on_event: impl FnMut(DriftEvent),
+    ) -> Result<(), ValidationError> {
+        self.exchange_events(exchange, &EventContext::new(), &mut on_event)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "validate_request",
+            level = "debug",
+            skip_all,
+            fields(
+                method = request.method.as_str(),
+                path = %request.path,
+                operation = tracing::field::Empty,
+                drift_count = tracing::field::Empty,
+                duration_us = tracing::field::Empty,
+            )
+        )
+    )]
+    fn request_events(
+        &self,
+        request: &ObservedRequest,
+        context: &EventContext,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) -> Result<(), ValidationError> {
+        let _timer = SpanTimer::start();
+        if !self.filter.allows_request(request) {
+            return Ok(());
+        }
+        let (template, operation, path_params, undeclared_base_path) = self.route(&request.path, request.method)?;
+        instrumentation::record("operation", template);
+
+        let mut drift_count = 0;
+        let on_event = &mut |event| {
+            let Some(event) = self.policy.apply(event) else {
+                return;
+            };
+            let mut event = annotate(event, request, template, operation).with_context(context);
+            if self.suppress(&event) {
+                return;
+            }
+            self.redaction.redact_event(&mut event);
+            self.ownership.assign(&mut event);
+            drift_count += 1;
+            emit(event)
+        };
+        if let Some(base_path) = &undeclared_base_path {
+            on_event(undeclared_base_path_event(base_path));
+        }
+        operation.request_drift_events_with(request, &path_params, on_event);
+        instrumentation::record_count("drift_count", drift_count);
+        Ok(())
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "validate_exchange",
+            level = "debug",
+            skip_all,
+            fields(
+                method = exchange.request.method.as_str(),
+                path = %exchange.request.path,
+                status = exchange.response.status,
+                operation = tracing::field::Empty,
+                drift_count = tracing::field::Empty,
+                duration_us = tracing::field::Empty,
+            )
+        )
+    )]
+    fn exchange_events(
+        &self,
+        exchange: &Exchange,
+        context: &EventContext,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) -> Result<(), ValidationError> {
+        let _timer = SpanTimer::start();
+        if !self.filter.allows(exchange) {
+            return Ok(());
+        }
+        let request = &exchange.request;
+        let status = exchange.response.status;
+        let origin = self.filter.response_origin(&exchange.response);
+        let (template, operation, path_params, undeclared_base_path) = self.route(&request.path, request.method)?;
+        instrumentation::record("operation", template);
+
+        let mut drift_count = 0;
+        let on_event = &mut |event| {
+            let Some(event) = self.policy.apply(event) else {
+                return;
+            };
+            let mut event = annotate(event, request, template, operation)
+                .with_status(status)
+                .with_context(context);
+            if self.suppress(&event) {
+                return;
+            }
+            self.redaction.redact_event(&mut event);
+            self.ownership.assign(&mut event);
+            drift_count += 1;
+            emit(match origin {
+                Some(origin) => event.with_response_origin(origin),
+                None => event,
+            })
+        };
+        if let Some(base_path) = &undeclared_base_path {
+            on_event(undeclared_base_path_event(base_path));
+        }
+        operation.exchange_drift_events_with(exchange, &path_params, on_event);
+        instrumentation::record_count("drift_count", drift_count);
+        Ok(())
+    }
+
+    /// Spec path template a concrete path routes to, if any
+    pub fn path_template(&self, path: &str) -> Option<&str> {
+        self.resolve(path).map(|(index, _, _)| self.paths[index].template.as_str())
+    }
+
+    /// Matches a concrete path, trying it without each base path before as is
+    ///
+    /// When reporting undeclared base paths, a path that doesn't route
+    /// otherwise is retried without its leading segments; the segments
+    /// dropped are returned alongside the match.
+    fn resolve(&self, path: &str) -> Option<Resolved> {
+        let normalized = self.normalization.normalize(path);
+        let path = normalized.as_ref();
+        let candidates = self.base_paths.iter().filter_map(|base_path| base_path.strip(path));
+        for candidate in candidates.chain(std::iter::once(path)) {
+            if let Some((index, params)) = self.lookup(candidate) {
+                return Some((index, params, None));
+            }
+        }
+        if !self.report_undeclared_base_paths {
+            return None;
+        }
+        let mut rest = path;
+        while let Some(next) = rest.get(1..).and_then(|after| after.find('/')).map(|end| &rest[end + 1..]) {
+            rest = next;
+            if let Some((index, params)) = self.lookup(rest) {
+                return Some((index, params, Some(path[..path.len() - rest.len()].to_string())));
+            }
+        }
+        None
+    }
+
+    /// Matches a normalized path, without its trailing slash or case as enabled
+    fn lookup(&self, path: &str) -> Option<(usize, HashMap<String, String>)> {
+        let (index, mut params) = self.lookup_exact(path)?;
+        if self.normalization.percent_decode {
+            // Escaped slashes survive normalization so they can't split segments
+            for value in params.values_mut().filter(|value| value.contains('%')) {
+                *value = value.replace("%2F", "/").replace("%2f", "/");
+            }
+        }
+        Some((index, params))
+    }
+
+    /// Tries the router, then the templates it couldn't represent
+    fn lookup_exact(&self, path: &str) -> Option<(usize, HashMap<String, String>)> {
+        let trimmed = path
+            .strip_suffix('/')
+            .filter(|trimmed| self.normalization.trailing_slash && !trimmed.is_empty());
+        let case_insensitive = self.normalization.case_insensitive;
+        for candidate in std::iter::once(path).chain(trimmed) {
+            if let Some((index, params)) = self.lookup_router(candidate) {
+                let preferred = self
+                    .fallback_routes
+                    .iter()
+                    .filter(|route| route.shadows.contains(&index))
+                    .find_map(|route| Some((route.index, route.pattern.captures(candidate, case_insensitive)?)));
+                return Some(preferred.unwrap_or((index, params)));
+            }
+        }
+        for candidate in std::iter::once(path).chain(trimmed) {
+            for route in &self.fallback_routes {
+                if let Some(params) = route.pattern.captures(candidate, case_insensitive) {
+                    return Some((route.index, params));
+                }
+            }
+        }
+        None
+    }
+
+    fn lookup_router(&self, path: &str) -> Option<(usize, HashMap<String, String>)> {
+        if let Ok(matched) = self.router.at(path) {
+            return Some((*matched.value, collect_params(&matched.params)));
+        }
+        if !self.normalization.case_insensitive {
+            return None;
+        }
+        let folded = path.to_ascii_lowercase();
+        let matched = self.folded_router.at(&folded).ok()?;
+        // ASCII lowercasing keeps byte offsets, so parameter values are read
+        // back from the path with their case intact
+        let params = matched
+            .params
+            .iter()
+            .map(|(key, value)| {
+                let start = value.as_ptr() as usize - folded.as_ptr() as usize;
+                (key.to_string(), path[start..start + value.len()].to_string())
+            })
+            .collect();
+        Some((*matched.value, params))
+    }
+
+    /// Resolves a path and method to the path template and operation validator
+    ///
+    /// Also returns the path parameters and the undeclared base path the
+    /// path arrived under, if any.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self, method),
+            fields(method = method.as_str(), template = tracing::field::Empty)
+        )
+    )]
+    fn route(&self, path: &str, method: HttpMethod) -> Result<Routed<'_>, ValidationError> {
+        let (index, params, undeclared_base_path) = self.resolve(path).ok_or_else(|| {
             ValidationError::ValidationFailed(format!("No route found for path: {}", path))
         })?;
 
-        let operation = matched.value.get(&method).ok_or_else(|| {
+        let entry = &self.paths[index];
+        instrumentation::record("template", entry.template.as_str());
+        let operation = entry.operations.get(&method).ok_or_else(|| {
             ValidationError::ValidationFailed(format!(
                 "Method {} not allowed for path: {}",
                 method.as_str(),
@@ -118,6 +1057,60 @@ impl ApiValidator {
             ))
         })?;
 
-        Ok((operation, matched.params))
+        Ok((entry.template.as_str(), operation, params, undeclared_base_path))
     }
 }
+
+/// A matched path: index into `paths`, path parameters and any undeclared base path
+type Resolved = (usize, HashMap<String, String>, Option<String>);
+
+/// A routed path: template, operation, path parameters and any undeclared base path
+type Routed<'a> = (&'a str, &'a OperationValidator, HashMap<String, String>, Option<String>);
+
+fn undeclared_base_path_event(base_path: &str) -> DriftEvent {
+    DriftEvent::new(
+        DriftType::UndeclaredBasePath,
+        "path",
+        format!("Request arrived under {}, which no server or configured base path declares", base_path),
+    )
+    .with_observed(&Value::String(base_path.to_string()))
+}
+
+// Fails to compile if any validator stops being shareable across threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ApiValidator>();
+    assert_send_sync::<OperationValidator>();
+    assert_send_sync::<GraphQlValidator>();
+    assert_send_sync::<IdempotencyValidator>();
+    assert_send_sync::<ParametersValidator>();
+    assert_send_sync::<RateLimitValidator>();
+    assert_send_sync::<RequestBodyValidator>();
+    assert_send_sync::<ResponseValidator>();
+    assert_send_sync::<SecurityValidator>();
+    assert_send_sync::<DeprecationValidator>();
+    assert_send_sync::<crate::validators::EventStreamValidator>();
+    assert_send_sync::<crate::validators::ParameterValidator>();
+};
+
+/// Attaches the routed operation to an event
+fn annotate(
+    event: DriftEvent,
+    request: &ObservedRequest,
+    template: &str,
+    operation: &OperationValidator,
+) -> DriftEvent {
+    event
+        .with_operation(request.method, &request.path, template)
+        .with_operation_id(operation.operation_id.as_deref())
+        .with_tags(&operation.tags)
+        .with_operation_summary(operation.summary.as_deref())
+}
+
+/// Copies router params into an owned map
+fn collect_params(params: &matchit::Params<'_, '_>) -> HashMap<String, String> {
+    params
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
@@ -0,0 +1,231 @@
+//! Item sampling for very large arrays in bodies
+//!
+//! Validating every item of a 50,000-element response costs more latency
+//! than a proxy can spare. A sampling strategy validates a subset of each
+//! large array instead; drift found in the sample is reported at the item's
+//! original index, with the sampling decision attached to the event.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+
+/// Which items of large arrays get validated
+///
+/// Arrays no longer than the sample size are always validated in full.
+/// Array-level constraints (`minItems`, `maxItems`, `uniqueItems`, ...) say
+/// nothing about the sample, so they go unchecked on sampled arrays.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArraySampling {
+    /// Validate every item
+    #[default]
+    All,
+    /// Validate the first `n` items
+    First(usize),
+    /// Validate `k` items picked at random, in their original order
+    Random(usize),
+    /// Validate up to `per_value` items for each value of `property`, so
+    /// rare variants of a polymorphic array aren't crowded out by common ones
+    Stratified { property: String, per_value: usize },
+}
+
+impl ArraySampling {
+    /// Short description recorded on findings, e.g. `first 100`
+    pub fn describe(&self) -> String {
+        match self {
+            Self::All => "all".to_string(),
+            Self::First(n) => format!("first {}", n),
+            Self::Random(k) => format!("random {}", k),
+            Self::Stratified { property, per_value } => format!("{} per {}", per_value, property),
+        }
+    }
+
+    /// Copies `value` with its large arrays reduced to their samples
+    ///
+    /// Returns `None` when no array needed sampling.
+    pub(crate) fn sample(&self, value: &Value) -> Option<SampledBody> {
+        if *self == Self::All {
+            return None;
+        }
+        let mut sampled = SampledBody {
+            value: Value::Null,
+            indices: HashMap::new(),
+            summary: ArraySample {
+                strategy: self.describe(),
+                arrays_sampled: 0,
+                items_validated: 0,
+                items_total: 0,
+            },
+        };
+        let mut rng = SplitMix64::seeded();
+        sampled.value = self.sample_value(value, &mut String::new(), &mut sampled, &mut rng);
+        (sampled.summary.arrays_sampled > 0).then_some(sampled)
+    }
+
+    fn sample_value(
+        &self,
+        value: &Value,
+        pointer: &mut String,
+        sampled: &mut SampledBody,
+        rng: &mut SplitMix64,
+    ) -> Value {
+        match value {
+            Value::Array(items) => {
+                let kept = self.pick(items, rng);
+                let indices: Vec<usize> = match &kept {
+                    Some(kept) => kept.clone(),
+                    None => (0..items.len()).collect(),
+                };
+                let reduced = indices
+                    .iter()
+                    .enumerate()
+                    .map(|(position, &index)| {
+                        let len = pointer.len();
+                        pointer.push_str(&format!("/{}", position));
+                        let item = self.sample_value(&items[index], pointer, sampled, rng);
+                        pointer.truncate(len);
+                        item
+                    })
+                    .collect();
+                if let Some(kept) = kept {
+                    sampled.summary.arrays_sampled += 1;
+                    sampled.summary.items_validated += kept.len();
+                    sampled.summary.items_total += items.len();
+                    sampled.indices.insert(pointer.clone(), kept);
+                }
+                Value::Array(reduced)
+            }
+            Value::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(key, member)| {
+                        let len = pointer.len();
+                        pointer.push('/');
+                        pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                        let member = self.sample_value(member, pointer, sampled, rng);
+                        pointer.truncate(len);
+                        (key.clone(), member)
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Original indices of the items to validate, ascending, or `None` to validate all
+    fn pick(&self, items: &[Value], rng: &mut SplitMix64) -> Option<Vec<usize>> {
+        match self {
+            Self::All => None,
+            Self::First(n) => (items.len() > *n).then(|| (0..*n).collect()),
+            Self::Random(k) => (items.len() > *k).then(|| {
+                // Partial Fisher-Yates over the index range
+                let mut indices: Vec<usize> = (0..items.len()).collect();
+                for i in 0..*k {
+                    let j = i + (rng.next() % (items.len() - i) as u64) as usize;
+                    indices.swap(i, j);
+                }
+                indices.truncate(*k);
+                indices.sort_unstable();
+                indices
+            }),
+            Self::Stratified { property, per_value } => {
+                let mut counts: HashMap<Option<String>, usize> = HashMap::new();
+                let kept: Vec<usize> = items
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, item)| {
+                        let value = item.get(property.as_str()).map(Value::to_string);
+                        let count = counts.entry(value).or_default();
+                        *count += 1;
+                        *count <= *per_value
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                (kept.len() < items.len()).then_some(kept)
+            }
+        }
+    }
+}
+
+/// How a body's arrays were sampled, as recorded on findings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArraySample {
+    /// The strategy used, e.g. `first 100`
+    pub strategy: String,
+    pub arrays_sampled: usize,
+    /// Items validated across the sampled arrays
+    pub items_validated: usize,
+    /// Items present across the sampled arrays
+    pub items_total: usize,
+}
+
+/// A body with its large arrays reduced to their samples
+pub(crate) struct SampledBody {
+    pub(crate) value: Value,
+    /// Original item indices per sampled array, keyed by the array's JSON pointer in the sample
+    indices: HashMap<String, Vec<usize>>,
+    summary: ArraySample,
+}
+
+impl SampledBody {
+    /// Points an event found in the sample back at the original body
+    ///
+    /// Returns `None` for array-level constraint violations of a sampled
+    /// array, which describe the sample rather than the body.
+    pub(crate) fn annotate(&self, mut event: DriftEvent) -> Option<DriftEvent> {
+        let array_level = matches!(
+            event.drift_type,
+            DriftType::RequestBodyArrayConstraintViolation | DriftType::ResponseBodyArrayConstraintViolation
+        );
+        if array_level && self.is_sampled_array(&event.location) {
+            return None;
+        }
+        event.location = self.original_location(&event.location);
+        Some(event.with_array_sample(self.summary.clone()))
+    }
+
+    fn is_sampled_array(&self, location: &str) -> bool {
+        let pointer = location.find('/').map_or("", |start| &location[start..]);
+        self.indices.contains_key(pointer)
+    }
+
+    /// Rewrites item positions in a location (`body/items/3/id`) to original indices
+    fn original_location(&self, location: &str) -> String {
+        let mut segments = location.split('/');
+        let mut original: Vec<String> = segments.next().map(str::to_string).into_iter().collect();
+        let mut pointer = String::new();
+        for segment in segments {
+            let mapped = self
+                .indices
+                .get(&pointer)
+                .and_then(|indices| indices.get(segment.parse::<usize>().ok()?))
+                .map(usize::to_string);
+            original.push(mapped.unwrap_or_else(|| segment.to_string()));
+            pointer.push('/');
+            pointer.push_str(segment);
+        }
+        original.join("/")
+    }
+}
+
+/// Small non-cryptographic PRNG, seeded from the standard library's per-process randomness
+#[derive(Debug)]
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn seeded() -> Self {
+        Self(RandomState::new().build_hasher().finish())
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
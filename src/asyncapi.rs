@@ -0,0 +1,405 @@
+//! Validation of event-driven traffic against AsyncAPI documents
+//!
+//! Services publishing to brokers describe their channels and message
+//! payloads in AsyncAPI. An [`AsyncApiValidator`] compiles the payload and
+//! header schemas of every message a 2.x or 3.x document declares, matches
+//! observed messages to their channel (parameterized addresses such as
+//! `user/{userId}/signedup` included) and reports payloads that don't match
+//! as `MESSAGE_PAYLOAD_*` drift events, tagged with the channel in place of
+//! an HTTP path.
+//!
+//! Payloads in formats other than JSON Schema (Avro, Protobuf, ...) aren't
+//! validated; their channels and message types still are.
+
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::drift_types::{DriftType, ValidationContext};
+use crate::error::ValidationError;
+use crate::routing::{PathTemplate, TemplatePattern};
+use crate::sinks::DriftSink;
+use crate::spec::builder::registry_from_document;
+use crate::spec::load_spec_document;
+use crate::traffic::{IngestSummary, Timestamp, MAX_KEPT_ERRORS};
+use crate::validation_helpers::{
+    drift_events_with, format_instance_location, gather_drift_events, CompileSchema, SchemaValidator,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::BufRead;
+use std::path::Path;
+
+/// Hops followed through `$ref` chains before giving up
+const MAX_REF_HOPS: usize = 32;
+
+/// A message observed on a broker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObservedMessage {
+    /// The concrete channel address, e.g. a topic name
+    pub channel: String,
+    /// The message's type, when the transport carries it (e.g. a `type` header)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub payload: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+}
+
+impl ObservedMessage {
+    pub fn new(channel: impl Into<String>, payload: Value) -> Self {
+        Self {
+            channel: channel.into(),
+            message: None,
+            payload,
+            headers: None,
+            timestamp: None,
+        }
+    }
+
+    pub fn with_message(mut self, name: impl Into<String>) -> Self {
+        self.message = Some(name.into());
+        self
+    }
+
+    pub fn with_headers(mut self, headers: Value) -> Self {
+        self.headers = Some(headers);
+        self
+    }
+}
+
+/// One message type a channel carries
+struct MessageValidator {
+    name: String,
+    /// `None` when the payload isn't described in JSON Schema
+    payload: Option<SchemaValidator>,
+    headers: Option<SchemaValidator>,
+}
+
+impl MessageValidator {
+    fn drift_events(&self, message: &ObservedMessage) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| {
+            if let Some(payload) = &self.payload {
+                drift_events_with(payload, &message.payload, ValidationContext::MessagePayload, |path| {
+                    format_instance_location(path, "payload")
+                }, emit);
+            }
+            if let (Some(schema), Some(headers)) = (&self.headers, &message.headers) {
+                drift_events_with(schema, headers, ValidationContext::MessagePayload, |path| {
+                    format_instance_location(path, "headers")
+                }, emit);
+            }
+        })
+    }
+}
+
+struct ChannelValidator {
+    address: String,
+    /// Set for addresses with parameters
+    pattern: Option<TemplatePattern>,
+    /// Operations sending or receiving on the channel
+    operation_ids: Vec<String>,
+    messages: Vec<MessageValidator>,
+}
+
+impl ChannelValidator {
+    fn matches(&self, channel: &str) -> bool {
+        match &self.pattern {
+            // Patterns are built for slash-led paths
+            Some(pattern) => pattern.captures(&format!("/{}", channel.trim_start_matches('/')), false).is_some(),
+            None => self.address == channel,
+        }
+    }
+}
+
+/// Checks observed messages against the channels and messages an AsyncAPI document declares
+pub struct AsyncApiValidator {
+    channels: Vec<ChannelValidator>,
+}
+
+impl AsyncApiValidator {
+    /// Loads an AsyncAPI document (YAML or JSON) and compiles its message schemas
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        Self::from_document(&load_spec_document(path)?)
+    }
+
+    /// Compiles the message schemas of a parsed AsyncAPI 2.x or 3.x document
+    pub fn from_document(document: &Value) -> Result<Self, ValidationError> {
+        let version = document
+            .get("asyncapi")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ValidationError::SpecParse {
+                reason: "not an AsyncAPI document: missing `asyncapi` version".to_string(),
+            })?;
+        let registry = registry_from_document(document.clone())?;
+        let mut channels = match version.starts_with("2.") {
+            true => channels_v2(document),
+            false => channels_v3(document),
+        };
+        let channels = channels
+            .iter_mut()
+            .map(|(address, operation_ids, messages)| {
+                let messages = messages
+                    .iter()
+                    .map(|(name, message)| {
+                        let context = format!("message {} on {}", name, address);
+                        Ok(MessageValidator {
+                            name: name.clone(),
+                            payload: payload_schema(message)
+                                .map(|schema| registry.compile_schema(schema, &context))
+                                .transpose()?,
+                            headers: message
+                                .get("headers")
+                                .map(|schema| registry.compile_schema(resolve(document, schema), &context))
+                                .transpose()?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ValidationError>>()?;
+                let template = PathTemplate::parse(address);
+                let parameterized = template.parameters().next().is_some();
+                let pattern = parameterized
+                    .then(|| template.pattern())
+                    .transpose()
+                    .map_err(|reason| ValidationError::SpecParse {
+                        reason: format!("channel {}: {}", address, reason),
+                    })?;
+                Ok(ChannelValidator {
+                    pattern,
+                    address: std::mem::take(address),
+                    operation_ids: std::mem::take(operation_ids),
+                    messages,
+                })
+            })
+            .collect::<Result<Vec<_>, ValidationError>>()?;
+        Ok(Self { channels })
+    }
+
+    /// Channel addresses as declared, e.g. `user/{userId}/signedup`
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.channels.iter().map(|channel| channel.address.as_str())
+    }
+
+    /// The declared address `channel` was published on; literal addresses win over parameterized ones
+    pub fn channel_template(&self, channel: &str) -> Option<&str> {
+        self.channel(channel).map(|validator| validator.address.as_str())
+    }
+
+    fn channel(&self, channel: &str) -> Option<&ChannelValidator> {
+        self.channels
+            .iter()
+            .find(|validator| validator.pattern.is_none() && validator.address == channel)
+            .or_else(|| self.channels.iter().find(|validator| validator.matches(channel)))
+    }
+
+    /// Validates a message against its channel's message types
+    pub fn validate_message(&self, message: &ObservedMessage) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.validate_message_with(message, &EventContext::new(), emit))
+    }
+
+    /// Like [`Self::validate_message`], attaching `context` to every event
+    /// and passing each to `emit` as it is found
+    ///
+    /// A message naming its type is validated as that type; otherwise it
+    /// must match one of the types its channel carries, and drift is
+    /// reported against the closest.
+    pub fn validate_message_with(&self, message: &ObservedMessage, context: &EventContext, emit: &mut dyn FnMut(DriftEvent)) {
+        let Some(channel) = self.channel(&message.channel) else {
+            emit(
+                DriftEvent::new(
+                    DriftType::MessageUnknownChannel,
+                    "channel",
+                    format!("Received a message on {}, which no channel declares", message.channel),
+                )
+                .with_channel(&message.channel, &message.channel)
+                .with_context(context),
+            );
+            return;
+        };
+        let mut emit = |event: DriftEvent| {
+            emit(
+                event
+                    .with_channel(&message.channel, &channel.address)
+                    .with_operation_id(channel.operation_ids.first().map(String::as_str))
+                    .with_context(context),
+            )
+        };
+
+        let events = match &message.message {
+            Some(name) => match channel.messages.iter().find(|declared| &declared.name == name) {
+                Some(declared) => declared.drift_events(message),
+                None => {
+                    emit(DriftEvent::new(
+                        DriftType::MessageUnknownType,
+                        "message",
+                        format!("Channel {} doesn't declare message type '{}'", channel.address, name),
+                    ));
+                    return;
+                }
+            },
+            None => {
+                let mut closest: Option<Vec<DriftEvent>> = None;
+                for declared in &channel.messages {
+                    let events = declared.drift_events(message);
+                    if events.is_empty() {
+                        return;
+                    }
+                    if closest.as_ref().is_none_or(|closest| events.len() < closest.len()) {
+                        closest = Some(events);
+                    }
+                }
+                closest.unwrap_or_default()
+            }
+        };
+        events.into_iter().for_each(&mut emit);
+    }
+}
+
+type ChannelMessages<'d> = (String, Vec<String>, Vec<(String, &'d Value)>);
+
+/// Channels of a 2.x document: keyed by address, with `publish` and `subscribe` operations
+fn channels_v2(document: &Value) -> Vec<ChannelMessages<'_>> {
+    let Some(channels) = document.get("channels").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    channels
+        .iter()
+        .map(|(address, channel)| {
+            let channel = resolve(document, channel);
+            let mut operation_ids = Vec::new();
+            let mut messages = Vec::new();
+            for operation in ["publish", "subscribe"].iter().filter_map(|kind| channel.get(*kind)) {
+                let operation = resolve(document, operation);
+                operation_ids.extend(operation.get("operationId").and_then(Value::as_str).map(str::to_string));
+                let Some(message) = operation.get("message") else {
+                    continue;
+                };
+                let alternatives = match resolve(document, message).get("oneOf").and_then(Value::as_array) {
+                    Some(alternatives) => alternatives.iter().collect(),
+                    None => vec![message],
+                };
+                for alternative in alternatives {
+                    let name = message_name(document, alternative, messages.len());
+                    if !messages.iter().any(|(existing, _)| *existing == name) {
+                        messages.push((name, resolve(document, alternative)));
+                    }
+                }
+            }
+            (address.clone(), operation_ids, messages)
+        })
+        .collect()
+}
+
+/// Channels of a 3.x document: with an `address` and `messages`, sent and
+/// received by top-level `operations` that reference them
+fn channels_v3(document: &Value) -> Vec<ChannelMessages<'_>> {
+    let Some(channels) = document.get("channels").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let operations = document.get("operations").and_then(Value::as_object);
+    channels
+        .iter()
+        .map(|(key, channel)| {
+            let channel = resolve(document, channel);
+            let address = channel.get("address").and_then(Value::as_str).unwrap_or(key).to_string();
+            let pointer = format!("#/channels/{}", key.replace('~', "~0").replace('/', "~1"));
+            let operation_ids = operations
+                .into_iter()
+                .flatten()
+                .filter(|(_, operation)| {
+                    resolve(document, operation)
+                        .pointer("/channel/$ref")
+                        .and_then(Value::as_str)
+                        == Some(pointer.as_str())
+                })
+                .map(|(id, _)| id.clone())
+                .collect();
+            let messages = channel
+                .get("messages")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+                .map(|(key, message)| {
+                    let resolved = resolve(document, message);
+                    let name = resolved.get("name").and_then(Value::as_str).unwrap_or(key);
+                    (name.to_string(), resolved)
+                })
+                .collect();
+            (address, operation_ids, messages)
+        })
+        .collect()
+}
+
+/// A message's `name` or `messageId`, else the component it references, else its position
+fn message_name(document: &Value, message: &Value, index: usize) -> String {
+    let resolved = resolve(document, message);
+    ["name", "messageId"]
+        .iter()
+        .find_map(|key| resolved.get(*key).and_then(Value::as_str))
+        .or_else(|| message.get("$ref").and_then(Value::as_str).and_then(|reference| reference.rsplit('/').next()))
+        .map_or_else(|| format!("message{}", index), str::to_string)
+}
+
+/// The JSON Schema describing a message's payload, if it is described in one
+fn payload_schema(message: &Value) -> Option<&Value> {
+    let payload = message.get("payload")?;
+    // 3.x wraps schemas in other formats as `{schemaFormat, schema}`
+    let (format, schema) = match (payload.get("schemaFormat"), payload.get("schema")) {
+        (Some(format), Some(schema)) => (Some(format), schema),
+        _ => (message.get("schemaFormat"), payload),
+    };
+    let is_json_schema = format.and_then(Value::as_str).is_none_or(|format| {
+        format.starts_with("application/vnd.aai.asyncapi")
+            || format.starts_with("application/schema+json")
+            || format.starts_with("application/schema+yaml")
+    });
+    is_json_schema.then_some(schema)
+}
+
+/// Follows local `$ref`s, e.g. to `#/components/messages/...`
+fn resolve<'d>(document: &'d Value, value: &'d Value) -> &'d Value {
+    let mut current = value;
+    for _ in 0..MAX_REF_HOPS {
+        let Some(target) = current
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|pointer| document.pointer(pointer))
+        else {
+            return current;
+        };
+        current = target;
+    }
+    current
+}
+
+/// Validates every message logged in `reader`, one JSON [`ObservedMessage`]
+/// per line, sending drift to `sink` and adding it to the summary's report
+///
+/// Malformed lines are skipped and counted; read and sink failures are returned.
+pub fn ingest(validator: &AsyncApiValidator, reader: impl BufRead, sink: &dyn DriftSink) -> Result<IngestSummary, ValidationError> {
+    let mut summary = IngestSummary::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| ValidationError::TrafficError(format!("line {}: {}", index + 1, e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.records += 1;
+        let message: ObservedMessage = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                summary.malformed += 1;
+                if summary.errors.len() < MAX_KEPT_ERRORS {
+                    summary.errors.push(format!("line {}: {}", index + 1, e));
+                }
+                continue;
+            }
+        };
+        let mut events = validator.validate_message(&message);
+        if let Some(timestamp_ms) = message.timestamp.as_ref().and_then(Timestamp::as_millis) {
+            events.iter_mut().for_each(|event| event.timestamp_ms = timestamp_ms);
+        }
+        sink.record_all(events.clone())?;
+        summary.report.extend(events);
+    }
+    sink.flush()?;
+    Ok(summary)
+}
@@ -0,0 +1,365 @@
+//! API design audit over the spec and observed traffic
+//!
+//! Audit findings are about API design consistency, not about traffic
+//! disagreeing with the spec, so they are reported separately from drift
+//! for the API governance team.
+
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::drift_types::Severity;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::sync::{Mutex, MutexGuard};
+
+/// Operation key: (method, path template)
+type OperationKey = (HttpMethod, String);
+
+/// Nesting depth below which body field names are no longer collected
+const MAX_FIELD_DEPTH: usize = 8;
+
+/// Nouns whose plural doesn't end in `s`
+const IRREGULAR_PLURALS: &[&str] = &[
+    "children", "criteria", "data", "feet", "geese", "indices", "matrices", "media", "men",
+    "metadata", "mice", "people", "series", "species", "teeth", "women",
+];
+
+/// A design rule the audit checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuditRule {
+    /// Path segments naming a collection (followed by an ID) are plural
+    PluralCollectionNouns,
+    /// Body fields use one casing convention across all operations
+    ConsistentFieldCasing,
+    /// Error responses share one envelope shape across all operations
+    ConsistentErrorEnvelope,
+}
+
+impl AuditRule {
+    pub const ALL: &'static [AuditRule] = &[
+        AuditRule::PluralCollectionNouns,
+        AuditRule::ConsistentFieldCasing,
+        AuditRule::ConsistentErrorEnvelope,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PluralCollectionNouns => "PLURAL_COLLECTION_NOUNS",
+            Self::ConsistentFieldCasing => "CONSISTENT_FIELD_CASING",
+            Self::ConsistentErrorEnvelope => "CONSISTENT_ERROR_ENVELOPE",
+        }
+    }
+}
+
+/// A rule violation
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    pub rule: AuditRule,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    pub message: String,
+}
+
+/// Audits API design using the spec's operations and the traffic they receive
+///
+/// Path naming is checked against the spec alone; field casing and error
+/// envelopes against the JSON bodies observed, since those show what
+/// clients actually deal with. The tracker is shared across worker threads
+/// like a sink.
+#[derive(Debug)]
+pub struct AuditTracker {
+    rules: BTreeSet<AuditRule>,
+    operations: Vec<OperationKey>,
+    observed: Mutex<Observed>,
+}
+
+#[derive(Debug, Default)]
+struct Observed {
+    exchanges: u64,
+    /// Operations each body field name was seen in
+    fields: BTreeMap<String, BTreeSet<OperationKey>>,
+    /// Top-level keys of error bodies per operation, with counts
+    error_shapes: BTreeMap<OperationKey, BTreeMap<ErrorShape, u64>>,
+}
+
+/// Sorted top-level keys of an error body; empty for bodies that aren't JSON objects
+type ErrorShape = Vec<String>;
+
+impl AuditTracker {
+    /// Creates a tracker auditing every operation registered in a validator, with all rules enabled
+    pub fn new(validator: &ApiValidator) -> Self {
+        Self {
+            rules: AuditRule::ALL.iter().copied().collect(),
+            operations: validator
+                .operations()
+                .map(|(template, method, _)| (method, template.to_string()))
+                .collect(),
+            observed: Mutex::new(Observed::default()),
+        }
+    }
+
+    /// Restricts the audit to the given rules
+    pub fn with_rules(mut self, rules: &[AuditRule]) -> Self {
+        self.rules = rules.iter().copied().collect();
+        self
+    }
+
+    /// Records the bodies of an exchange, routing it with the validator the tracker was built from
+    pub fn record_exchange(&self, validator: &ApiValidator, exchange: &Exchange) {
+        let request = &exchange.request;
+        let Some(template) = validator.path_template(&request.path) else {
+            return;
+        };
+        let key = (request.method, template.to_string());
+        let request_body = parse_body(request.body.as_deref());
+        let response_body = parse_body(exchange.response.body.as_deref());
+
+        let mut observed = self.lock();
+        observed.exchanges += 1;
+        for body in request_body.iter().chain(&response_body) {
+            collect_fields(body, &key, &mut observed.fields, 0);
+        }
+        if exchange.response.status >= 400 {
+            let shape = match &response_body {
+                Some(Value::Object(members)) => members.keys().cloned().collect(),
+                _ => ErrorShape::new(),
+            };
+            *observed.error_shapes.entry(key).or_default().entry(shape).or_default() += 1;
+        }
+    }
+
+    /// Evaluates the enabled rules against the spec and the traffic so far
+    pub fn report(&self) -> AuditReport {
+        let observed = self.lock();
+        let mut findings = Vec::new();
+        if self.rules.contains(&AuditRule::PluralCollectionNouns) {
+            self.plural_collection_nouns(&mut findings);
+        }
+        if self.rules.contains(&AuditRule::ConsistentFieldCasing) {
+            field_casing(&observed.fields, &mut findings);
+        }
+        if self.rules.contains(&AuditRule::ConsistentErrorEnvelope) {
+            error_envelopes(&observed.error_shapes, &mut findings);
+        }
+        AuditReport {
+            exchanges_audited: observed.exchanges,
+            findings,
+        }
+    }
+
+    fn plural_collection_nouns(&self, findings: &mut Vec<AuditFinding>) {
+        let mut reported = BTreeSet::new();
+        for (method, template) in &self.operations {
+            let segments: Vec<&str> = template.split('/').filter(|s| !s.is_empty()).collect();
+            for pair in segments.windows(2) {
+                let (noun, next) = (pair[0], pair[1]);
+                let collection = next.starts_with('{') && !noun.starts_with('{');
+                if collection && !is_plural(noun) && reported.insert((template.clone(), noun)) {
+                    findings.push(AuditFinding {
+                        rule: AuditRule::PluralCollectionNouns,
+                        severity: Severity::Info,
+                        method: Some(*method),
+                        path_template: Some(template.clone()),
+                        message: format!("Collection segment '{}' should be a plural noun", noun),
+                    });
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Observed> {
+        self.observed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn parse_body(body: Option<&[u8]>) -> Option<Value> {
+    body.and_then(|bytes| serde_json::from_slice(bytes).ok())
+}
+
+fn collect_fields(
+    value: &Value,
+    key: &OperationKey,
+    fields: &mut BTreeMap<String, BTreeSet<OperationKey>>,
+    depth: usize,
+) {
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+    match value {
+        Value::Object(members) => {
+            for (name, member) in members {
+                fields.entry(name.clone()).or_default().insert(key.clone());
+                collect_fields(member, key, fields, depth + 1);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_fields(item, key, fields, depth + 1)),
+        _ => {}
+    }
+}
+
+fn is_plural(noun: &str) -> bool {
+    let noun = noun.to_ascii_lowercase();
+    // Version prefixes and file-like segments aren't nouns
+    let not_a_noun = noun.contains('.')
+        || (noun.starts_with('v') && noun.len() > 1 && noun[1..].bytes().all(|b| b.is_ascii_digit()));
+    not_a_noun || noun.ends_with('s') || IRREGULAR_PLURALS.contains(&noun.as_str())
+}
+
+/// Naming convention of a field name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Casing {
+    Camel,
+    Snake,
+    Kebab,
+    Pascal,
+    /// A single lowercase word, valid under camel and snake case alike
+    Flat,
+    Other,
+}
+
+impl Casing {
+    fn of(name: &str) -> Self {
+        let has_upper = name.bytes().any(|b| b.is_ascii_uppercase());
+        let starts_upper = name.bytes().next().is_some_and(|b| b.is_ascii_uppercase());
+        match (name.contains('_'), name.contains('-'), has_upper) {
+            (false, false, false) if name.bytes().all(|b| b.is_ascii_alphanumeric()) => Self::Flat,
+            (true, false, false) => Self::Snake,
+            (false, true, false) => Self::Kebab,
+            (false, false, true) if starts_upper => Self::Pascal,
+            (false, false, true) => Self::Camel,
+            _ => Self::Other,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Camel => "camelCase",
+            Self::Snake => "snake_case",
+            Self::Kebab => "kebab-case",
+            Self::Pascal => "PascalCase",
+            Self::Flat => "lowercase",
+            Self::Other => "mixed casing",
+        }
+    }
+}
+
+/// Flags fields whose casing differs from the convention most fields follow
+fn field_casing(fields: &BTreeMap<String, BTreeSet<OperationKey>>, findings: &mut Vec<AuditFinding>) {
+    let mut by_casing: BTreeMap<Casing, usize> = BTreeMap::new();
+    for name in fields.keys() {
+        let casing = Casing::of(name);
+        if casing != Casing::Flat {
+            *by_casing.entry(casing).or_default() += 1;
+        }
+    }
+    let Some((dominant, _)) = by_casing.iter().max_by_key(|(_, count)| **count) else {
+        return;
+    };
+
+    for (name, operations) in fields {
+        let casing = Casing::of(name);
+        if casing == Casing::Flat || casing == *dominant {
+            continue;
+        }
+        for (method, template) in operations {
+            findings.push(AuditFinding {
+                rule: AuditRule::ConsistentFieldCasing,
+                severity: Severity::Warning,
+                method: Some(*method),
+                path_template: Some(template.clone()),
+                message: format!(
+                    "Field '{}' is {} while most fields are {}",
+                    name,
+                    casing.as_str(),
+                    dominant.as_str()
+                ),
+            });
+        }
+    }
+}
+
+/// Flags operations whose usual error body shape differs from the one most operations use
+fn error_envelopes(shapes: &BTreeMap<OperationKey, BTreeMap<ErrorShape, u64>>, findings: &mut Vec<AuditFinding>) {
+    let usual: BTreeMap<&OperationKey, &ErrorShape> = shapes
+        .iter()
+        .filter_map(|(key, counts)| Some((key, counts.iter().max_by_key(|(_, count)| **count)?.0)))
+        .collect();
+    let mut operations_per_shape: BTreeMap<&ErrorShape, usize> = BTreeMap::new();
+    for shape in usual.values() {
+        *operations_per_shape.entry(shape).or_default() += 1;
+    }
+    let Some((dominant, _)) = operations_per_shape.iter().max_by_key(|(_, count)| **count) else {
+        return;
+    };
+
+    for ((method, template), shape) in usual {
+        if shape != *dominant {
+            findings.push(AuditFinding {
+                rule: AuditRule::ConsistentErrorEnvelope,
+                severity: Severity::Warning,
+                method: Some(*method),
+                path_template: Some(template.clone()),
+                message: format!(
+                    "Error responses use {} while most operations use {}",
+                    describe_shape(shape),
+                    describe_shape(dominant)
+                ),
+            });
+        }
+    }
+}
+
+fn describe_shape(shape: &ErrorShape) -> String {
+    if shape.is_empty() {
+        "a non-object body".to_string()
+    } else {
+        format!("{{{}}}", shape.join(", "))
+    }
+}
+
+/// Outcome of an audit
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    pub exchanges_audited: u64,
+    pub findings: Vec<AuditFinding>,
+}
+
+impl AuditReport {
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            ValidationError::ReportError(format!("Failed to serialize audit report: {}", e))
+        })
+    }
+
+    /// Renders the findings as Markdown, grouped by rule
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::from("# API Design Audit\n\n");
+        let _ = writeln!(
+            out,
+            "{} findings over {} exchanges.",
+            self.findings.len(),
+            self.exchanges_audited
+        );
+        for rule in AuditRule::ALL {
+            let findings: Vec<&AuditFinding> = self.findings.iter().filter(|f| f.rule == *rule).collect();
+            if findings.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n## {}\n", rule.as_str());
+            for finding in findings {
+                out.push_str("- ");
+                if let (Some(method), Some(path)) = (finding.method, &finding.path_template) {
+                    let _ = write!(out, "`{} {}`: ", method.as_str(), path);
+                }
+                let _ = writeln!(out, "{}", finding.message);
+            }
+        }
+        out
+    }
+}
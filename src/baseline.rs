@@ -0,0 +1,312 @@
+use crate::api_validator::HttpMethod;
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Default file name for a persisted baseline
+pub const DEFAULT_BASELINE_PATH: &str = "drift-baseline.json";
+
+/// Format version written to baseline files
+const BASELINE_VERSION: u32 = 1;
+
+/// Identity of a drift finding, independent of when and with which values it occurred
+///
+/// Array indices in the location are collapsed to `*`, so drift on
+/// `body/users/0/email` and `body/users/7/email` is the same known drift.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineKey {
+    pub drift_type: DriftType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    pub location: String,
+    /// Event context entries a custom [`Fingerprint`] tells findings apart by,
+    /// e.g. the consumer; the default fingerprint leaves this empty
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: EventContext,
+}
+
+impl BaselineKey {
+    /// The default fingerprint: drift type, operation, status and normalized location
+    pub fn for_event(event: &DriftEvent) -> Self {
+        Self {
+            drift_type: event.drift_type,
+            method: event.method,
+            path_template: event.path_template.clone(),
+            status_code: event.status_code,
+            location: normalize_location(&event.location),
+            context: EventContext::new(),
+        }
+    }
+
+    /// Adds the event's value for a context key, if it has one, as a dimension
+    pub fn with_context_key(mut self, event: &DriftEvent, key: &str) -> Self {
+        if let Some(value) = event.context.get(key) {
+            self.context.insert(key.to_string(), value.clone());
+        }
+        self
+    }
+}
+
+/// Decides which drift events are the same finding
+///
+/// Baselines, reproducer capture and the drift store all dedupe by the key
+/// this returns. Implement it, or pass a closure, to drop or add dimensions:
+/// returning `BaselineKey { status_code: None, ..BaselineKey::for_event(event) }`
+/// merges findings across statuses, and [`BaselineKey::with_context_key`]
+/// tells them apart by consumer.
+///
+/// Keys are persisted in baseline files, so a baseline must be read with
+/// the fingerprint that wrote it.
+pub trait Fingerprint: Send + Sync {
+    fn fingerprint(&self, event: &DriftEvent) -> BaselineKey;
+}
+
+impl fmt::Debug for dyn Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Fingerprint")
+    }
+}
+
+/// [`BaselineKey::for_event`], used unless another fingerprint is configured
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultFingerprint;
+
+impl Fingerprint for DefaultFingerprint {
+    fn fingerprint(&self, event: &DriftEvent) -> BaselineKey {
+        BaselineKey::for_event(event)
+    }
+}
+
+/// A fingerprint over a chosen subset of the default dimensions, plus context keys
+///
+/// The default value matches [`DefaultFingerprint`]; drift type and
+/// location are always part of the key.
+#[derive(Debug, Clone)]
+pub struct FingerprintDimensions {
+    pub method: bool,
+    pub path_template: bool,
+    pub status_code: bool,
+    /// Event context keys whose values tell findings apart, e.g. `consumer`
+    pub context_keys: Vec<String>,
+}
+
+impl Default for FingerprintDimensions {
+    fn default() -> Self {
+        Self {
+            method: true,
+            path_template: true,
+            status_code: true,
+            context_keys: Vec::new(),
+        }
+    }
+}
+
+impl Fingerprint for FingerprintDimensions {
+    fn fingerprint(&self, event: &DriftEvent) -> BaselineKey {
+        let mut key = BaselineKey::for_event(event);
+        if !self.method {
+            key.method = None;
+        }
+        if !self.path_template {
+            key.path_template = None;
+        }
+        if !self.status_code {
+            key.status_code = None;
+        }
+        self.context_keys
+            .iter()
+            .fold(key, |key, context_key| key.with_context_key(event, context_key))
+    }
+}
+
+impl<F> Fingerprint for F
+where
+    F: Fn(&DriftEvent) -> BaselineKey + Send + Sync,
+{
+    fn fingerprint(&self, event: &DriftEvent) -> BaselineKey {
+        self(event)
+    }
+}
+
+/// The fingerprint used when none is configured
+pub(crate) fn default_fingerprint() -> Arc<dyn Fingerprint> {
+    Arc::new(DefaultFingerprint)
+}
+
+/// A known drift entry and how often it occurred when the baseline was taken
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    #[serde(flatten)]
+    pub key: BaselineKey,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    entries: Vec<BaselineEntry>,
+}
+
+/// Aggregated drift that is known and tolerated
+///
+/// Large legacy APIs carry drift nobody is going to fix soon. Persist it
+/// once with [`DriftBaseline::save`], then only report drift that is not in
+/// the baseline via [`DriftBaseline::new_drift`] or [`BaselineFilter`].
+#[derive(Debug, Clone)]
+pub struct DriftBaseline {
+    entries: BTreeMap<BaselineKey, u64>,
+    fingerprint: Arc<dyn Fingerprint>,
+}
+
+impl Default for DriftBaseline {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            fingerprint: default_fingerprint(),
+        }
+    }
+}
+
+impl DriftBaseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches events to entries by `fingerprint` instead of [`DefaultFingerprint`]
+    pub fn with_fingerprint(mut self, fingerprint: impl Fingerprint + 'static) -> Self {
+        self.fingerprint = Arc::new(fingerprint);
+        self
+    }
+
+    /// Aggregates events into a baseline
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a DriftEvent>) -> Self {
+        let mut baseline = Self::new();
+        events.into_iter().for_each(|event| baseline.add(event));
+        baseline
+    }
+
+    /// Records an event as known drift
+    pub fn add(&mut self, event: &DriftEvent) {
+        *self.entries.entry(self.fingerprint.fingerprint(event)).or_default() += 1;
+    }
+
+    /// Whether an event matches known drift
+    pub fn contains(&self, event: &DriftEvent) -> bool {
+        self.entries.contains_key(&self.fingerprint.fingerprint(event))
+    }
+
+    /// Keeps only events not present in the baseline
+    pub fn new_drift(&self, events: impl IntoIterator<Item = DriftEvent>) -> Vec<DriftEvent> {
+        events.into_iter().filter(|event| !self.contains(event)).collect()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = BaselineEntry> + '_ {
+        self.entries.iter().map(|(key, &count)| BaselineEntry {
+            key: key.clone(),
+            count,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reads a baseline written by [`DriftBaseline::save`]
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ValidationError::BaselineError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let file: BaselineFile = serde_json::from_str(&contents).map_err(|e| {
+            ValidationError::BaselineError(format!("Failed to parse {}: {}", path.display(), e))
+        })?;
+        if file.version != BASELINE_VERSION {
+            return Err(ValidationError::BaselineError(format!(
+                "Unsupported baseline version {} in {}",
+                file.version,
+                path.display()
+            )));
+        }
+        Ok(Self {
+            entries: file.entries.into_iter().map(|entry| (entry.key, entry.count)).collect(),
+            ..Self::default()
+        })
+    }
+
+    /// Writes the baseline as pretty-printed JSON, sorted for stable diffs
+    pub fn save(&self, path: &Path) -> Result<(), ValidationError> {
+        let file = BaselineFile {
+            version: BASELINE_VERSION,
+            entries: self.entries().collect(),
+        };
+        let contents = serde_json::to_string_pretty(&file).map_err(|e| {
+            ValidationError::BaselineError(format!("Failed to serialize baseline: {}", e))
+        })?;
+        fs::write(path, contents + "\n").map_err(|e| {
+            ValidationError::BaselineError(format!("Failed to write {}: {}", path.display(), e))
+        })
+    }
+}
+
+/// Sink wrapper forwarding only drift that is not in a baseline
+pub struct BaselineFilter<S> {
+    baseline: DriftBaseline,
+    inner: S,
+}
+
+impl<S: DriftSink> BaselineFilter<S> {
+    pub fn new(baseline: DriftBaseline, inner: S) -> Self {
+        Self { baseline, inner }
+    }
+}
+
+impl<S: DriftSink> DriftSink for BaselineFilter<S> {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        if self.baseline.contains(&event) {
+            return Ok(());
+        }
+        self.inner.record(event)
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        self.inner.flush()
+    }
+
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        let new_drift: Vec<DriftEvent> = events
+            .iter()
+            .filter(|event| !self.baseline.contains(event))
+            .cloned()
+            .collect();
+        self.inner.observe_exchange(operation, timestamp_ms, &new_drift);
+    }
+}
+
+/// Replaces array index segments (`/0/`) with `*`
+pub(crate) fn normalize_location(location: &str) -> String {
+    location
+        .split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,200 @@
+//! Consumer-facing API changelogs
+//!
+//! A [`SpecDiff`] says what changed between two spec versions; drift
+//! history says when traffic actually started doing it. Combining them
+//! dates each documented change by when it first appeared in production,
+//! ready to paste into release notes.
+
+use crate::api_validator::HttpMethod;
+use crate::diff::{normalize_template, SpecChange, SpecDiff};
+use crate::drift_event::{now_ms, utc_date, DriftEvent};
+use crate::drift_types::Severity;
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// A single line of the changelog
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    /// `YYYY-MM-DD` the change was first observed in traffic, or the release date
+    pub date: String,
+    pub severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    /// e.g. `field shipment.eta in 200 responses: type changed from string to string, null`
+    pub summary: String,
+    /// Drift events in the history that this change documents
+    pub observed_events: usize,
+}
+
+/// Changes between two spec versions, newest first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Changelog {
+    pub entries: Vec<ChangelogEntry>,
+}
+
+impl Changelog {
+    /// Builds the changelog for a spec diff
+    ///
+    /// Changes that resolve drift in `history` (same operation, location and
+    /// drift type) are dated by the first such event; the rest by `released`
+    /// (`YYYY-MM-DD`, today in UTC if unset). Changes to component schemas
+    /// alone are left out, as they reach consumers through the operations
+    /// using them.
+    pub fn generate(diff: &SpecDiff, history: &[DriftEvent], released: Option<&str>) -> Self {
+        let released = released.map(str::to_string).unwrap_or_else(|| utc_date(now_ms()));
+        let mut entries: Vec<ChangelogEntry> = diff
+            .changes
+            .iter()
+            .filter(|change| change.method.is_some())
+            .map(|change| {
+                let resolved: Vec<&DriftEvent> =
+                    history.iter().filter(|event| documents(change, event)).collect();
+                let date = resolved
+                    .iter()
+                    .map(|event| event.timestamp_ms)
+                    .min()
+                    .map(utc_date)
+                    .unwrap_or_else(|| released.clone());
+                ChangelogEntry {
+                    date,
+                    severity: change.severity,
+                    method: change.method,
+                    path_template: change.path_template.clone(),
+                    summary: summarize(change),
+                    observed_events: resolved.len(),
+                }
+            })
+            .collect();
+
+        // Stable, so entries of the same day keep the diff's order
+        entries.sort_by(|a, b| b.date.cmp(&a.date));
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Renders the changelog as Markdown, one section per day
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::from("# API Changelog\n");
+        let mut current_date = None;
+        for entry in &self.entries {
+            if current_date != Some(&entry.date) {
+                let _ = writeln!(out, "\n## {}\n", entry.date);
+                current_date = Some(&entry.date);
+            }
+            out.push_str("- ");
+            if entry.severity == Severity::Breaking {
+                out.push_str("**Breaking:** ");
+            }
+            if let (Some(method), Some(path)) = (entry.method, &entry.path_template) {
+                let _ = write!(out, "`{} {}`: ", method.as_str(), path);
+            }
+            out.push_str(&entry.summary);
+            if entry.observed_events > 0 {
+                let _ = write!(out, " (seen in traffic {} times before it was documented)", entry.observed_events);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Whether a spec change documents the drift an event recorded
+fn documents(change: &SpecChange, event: &DriftEvent) -> bool {
+    if change.drift_type != Some(event.drift_type) || change.method != event.method {
+        return false;
+    }
+    let same_operation = match (&change.path_template, &event.path_template) {
+        (Some(changed), Some(observed)) => normalize_template(changed) == normalize_template(observed),
+        _ => false,
+    };
+    if !same_operation {
+        return false;
+    }
+
+    let drift_type = event.drift_type.as_str();
+    if drift_type.starts_with("PARAMETER_") {
+        // Parameter events are located by name alone (`limit` for `query/limit`)
+        return change
+            .location
+            .split_once('/')
+            .is_some_and(|(_, name)| name.eq_ignore_ascii_case(&event.location));
+    }
+    let location = schema_location(&event.location);
+    match event.status_code {
+        Some(status) if drift_type.starts_with("RESPONSE_") => {
+            change.location == format!("response/{}/{}", status, location)
+        }
+        _ => change.location == location,
+    }
+}
+
+/// Maps an instance location (`body/items/0/id`) onto the diff's schema location (`body/items/items/id`)
+fn schema_location(instance_location: &str) -> String {
+    instance_location
+        .split('/')
+        .map(|segment| {
+            if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+                "items"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Phrases a change for API consumers
+fn summarize(change: &SpecChange) -> String {
+    let message = lowercase_first(&change.message);
+    match subject(&change.location) {
+        Some(subject) => format!("{}: {}", subject, message),
+        None => message,
+    }
+}
+
+/// What a change location refers to, in consumer terms
+fn subject(location: &str) -> Option<String> {
+    let segments: Vec<&str> = location.split('/').collect();
+    match segments.as_slice() {
+        ["operation"] => None,
+        ["response", status] => Some(format!("{} response", status)),
+        ["response", status, "body"] => Some(format!("{} response body", status)),
+        ["response", status, "body", field @ ..] => {
+            Some(format!("field `{}` in {} responses", field_path(field), status))
+        }
+        ["body"] => Some("request body".to_string()),
+        ["body", field @ ..] => Some(format!("request field `{}`", field_path(field))),
+        ["path", _] => Some("path parameter".to_string()),
+        [kind @ ("query" | "header" | "cookie"), name] => Some(format!("{} parameter `{}`", kind, name)),
+        _ => Some(format!("`{}`", location)),
+    }
+}
+
+/// Dotted field path, with `[]` marking array items (`lines[].sku`)
+fn field_path(segments: &[&str]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        if *segment == "items" && !path.is_empty() {
+            path.push_str("[]");
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+    }
+    path
+}
+
+fn lowercase_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,116 @@
+//! Gating deploys on the drift found in recorded traffic
+//!
+//! CI replays a corpus of staging traffic and needs a yes or no: does the
+//! drift stay within what the team tolerates? [`CheckThresholds`] caps the
+//! number of events per severity; [`CheckResult`] holds the counts against
+//! the caps and renders them as the summary table CI logs show.
+
+use crate::drift_types::Severity;
+use crate::report::DriftReport;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Most drift events tolerated per severity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckThresholds {
+    /// Lowest severity of which any event fails the check
+    pub fail_on: Option<Severity>,
+    /// Events tolerated per severity, overriding `fail_on` for that severity
+    pub max_events: BTreeMap<Severity, usize>,
+}
+
+impl CheckThresholds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails on any event of `severity` or above
+    pub fn fail_on(mut self, severity: Severity) -> Self {
+        self.fail_on = Some(severity);
+        self
+    }
+
+    /// Tolerates at most `count` events of `severity`
+    pub fn max(mut self, severity: Severity, count: usize) -> Self {
+        self.max_events.insert(severity, count);
+        self
+    }
+
+    /// Events of `severity` tolerated, `None` for no limit
+    pub fn limit(&self, severity: Severity) -> Option<usize> {
+        let failing = self.fail_on.is_some_and(|fail_on| severity >= fail_on).then_some(0);
+        self.max_events.get(&severity).copied().or(failing)
+    }
+
+    /// Counts the report's events against the thresholds
+    pub fn check(&self, report: &DriftReport) -> CheckResult {
+        let mut by_drift_type: BTreeMap<(Severity, &str), usize> = BTreeMap::new();
+        for event in &report.events {
+            *by_drift_type.entry((event.severity, event.drift_type.as_str())).or_default() += 1;
+        }
+        let severities = [Severity::Breaking, Severity::Warning, Severity::Info]
+            .into_iter()
+            .map(|severity| SeverityCheck {
+                severity,
+                events: report.events.iter().filter(|event| event.severity == severity).count(),
+                limit: self.limit(severity),
+            })
+            .collect();
+        let mut drift_types: Vec<(String, Severity, usize)> = by_drift_type
+            .into_iter()
+            .map(|((severity, drift_type), events)| (drift_type.to_string(), severity, events))
+            .collect();
+        drift_types.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        CheckResult {
+            severities,
+            drift_types,
+        }
+    }
+}
+
+/// Drift events of one severity against their threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeverityCheck {
+    pub severity: Severity,
+    pub events: usize,
+    pub limit: Option<usize>,
+}
+
+impl SeverityCheck {
+    pub fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.events > limit)
+    }
+}
+
+/// The outcome of checking a run's drift against [`CheckThresholds`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// One per severity, most severe first
+    pub severities: Vec<SeverityCheck>,
+    /// Events per drift type, most severe first
+    pub drift_types: Vec<(String, Severity, usize)>,
+}
+
+impl CheckResult {
+    pub fn passed(&self) -> bool {
+        !self.severities.iter().any(SeverityCheck::exceeded)
+    }
+
+    /// Renders the counts as a plain-text table, followed by the events per drift type
+    pub fn render_table(&self) -> String {
+        let mut out = format!("{:<10} {:>8} {:>8}  {}\n", "SEVERITY", "EVENTS", "ALLOWED", "RESULT");
+        for check in &self.severities {
+            let allowed = check.limit.map_or_else(|| "-".to_string(), |limit| limit.to_string());
+            let result = if check.exceeded() { "FAIL" } else { "ok" };
+            let _ = writeln!(out, "{:<10} {:>8} {:>8}  {}", check.severity.as_str(), check.events, allowed, result);
+        }
+        if !self.drift_types.is_empty() {
+            let width = self.drift_types.iter().map(|(drift_type, _, _)| drift_type.len()).max().unwrap_or(0);
+            let _ = writeln!(out, "\n{:<width$} {:<10} {:>8}", "DRIFT TYPE", "SEVERITY", "EVENTS");
+            for (drift_type, severity, events) in &self.drift_types {
+                let _ = writeln!(out, "{:<width$} {:<10} {:>8}", drift_type, severity.as_str(), events);
+            }
+        }
+        out
+    }
+}
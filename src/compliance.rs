@@ -0,0 +1,379 @@
+//! Compliance export of drift affecting regulated data
+//!
+//! Fields, properties and parameters are classified in the spec with the
+//! `x-data-classification` extension (`pii`, `phi`, `pci`, ...). Schema drift
+//! on such fields may have to be reported to regulators, so it is pulled out
+//! of the general drift stream into a dedicated report listing what drifted,
+//! how, when, and which consumers were involved.
+
+use crate::api_validator::HttpMethod;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::spec::builder::{components_document, extract_json_schema};
+use crate::spec::{BuildOptions, ResolveReference};
+use openapiv3::OpenAPI;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// Spec extension holding a field's data classification
+pub const CLASSIFICATION_EXTENSION: &str = "x-data-classification";
+
+/// Event context key naming the consumer (client application, tenant) behind an exchange
+pub const DEFAULT_CONSUMER_KEY: &str = "consumer";
+
+/// Which part of an exchange a classified field belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldScope {
+    Parameter,
+    RequestBody,
+    ResponseBody,
+}
+
+impl FieldScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Parameter => "parameter",
+            Self::RequestBody => "request body",
+            Self::ResponseBody => "response body",
+        }
+    }
+}
+
+/// A field the spec classifies as regulated data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassifiedField {
+    pub scope: FieldScope,
+    /// Location as reported by drift events, with `*` for array items and
+    /// map values (e.g. `body/patients/*/ssn`, `email`)
+    pub location: String,
+    pub classification: String,
+}
+
+impl ClassifiedField {
+    /// Whether drift at `location` concerns this field or something nested in it
+    fn covers(&self, location: &str) -> bool {
+        if self.scope == FieldScope::Parameter {
+            return location == self.location
+                || location.strip_prefix(self.location.as_str()).is_some_and(|rest| rest.starts_with('['));
+        }
+        let mut observed = location.split('/');
+        self.location
+            .split('/')
+            .all(|segment| observed.next().is_some_and(|actual| segment == "*" || segment == actual))
+    }
+}
+
+/// Classified fields of every operation in a spec
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceIndex {
+    fields: BTreeMap<(HttpMethod, String), Vec<ClassifiedField>>,
+}
+
+impl ComplianceIndex {
+    /// Collects the classified parameters and JSON body fields of every operation
+    ///
+    /// A classification on an object or array covers everything nested in
+    /// it. `$ref`s are followed; recursive schemas are expanded once.
+    pub fn from_spec(spec: &OpenAPI, options: &BuildOptions) -> Result<Self, ValidationError> {
+        let components = components_document(spec, None, options)?;
+        let mut fields = BTreeMap::new();
+
+        for (path, path_item) in spec.paths.paths.iter().filter_map(|(path, item)| Some((path, item.as_item()?))) {
+            for (method_str, operation) in path_item.iter() {
+                let Ok(method) = HttpMethod::from_str(method_str) else {
+                    continue;
+                };
+
+                let mut classified = Vec::new();
+                for parameter_ref in path_item.parameters.iter().chain(&operation.parameters) {
+                    let parameter_data = parameter_ref.resolve(spec)?.parameter_data_ref();
+                    let schema = match &parameter_data.format {
+                        openapiv3::ParameterSchemaOrContent::Schema(schema) => serde_json::to_value(schema).ok(),
+                        openapiv3::ParameterSchemaOrContent::Content(_) => None,
+                    };
+                    let classification = parameter_data
+                        .extensions
+                        .get(CLASSIFICATION_EXTENSION)
+                        .or_else(|| classification_of(schema.as_ref()?, &components))
+                        .and_then(Value::as_str);
+                    if let Some(classification) = classification {
+                        classified.push(ClassifiedField {
+                            scope: FieldScope::Parameter,
+                            location: parameter_data.name.clone(),
+                            classification: classification.to_string(),
+                        });
+                    }
+                }
+
+                let mut bodies = Vec::new();
+                if let Some(request_body_ref) = &operation.request_body {
+                    let request_body = request_body_ref.resolve(spec)?;
+                    if let Ok(schema) = extract_json_schema(&request_body.content, "request body", spec, None, options) {
+                        bodies.push((FieldScope::RequestBody, schema));
+                    }
+                }
+                for response_ref in operation.responses.responses.values().chain(&operation.responses.default) {
+                    let response = response_ref.resolve(spec)?;
+                    if let Ok(schema) = extract_json_schema(&response.content, "response", spec, None, options) {
+                        bodies.push((FieldScope::ResponseBody, schema));
+                    }
+                }
+                for (scope, schema) in &bodies {
+                    classify_schema(schema, *scope, "body", &components, &mut Vec::new(), &mut classified);
+                }
+
+                classified.sort_by(|a, b| (a.scope, &a.location).cmp(&(b.scope, &b.location)));
+                classified.dedup();
+                if !classified.is_empty() {
+                    fields.insert((method, path.clone()), classified);
+                }
+            }
+        }
+
+        Ok(Self { fields })
+    }
+
+    /// Every classified field, by operation
+    pub fn fields(&self) -> impl Iterator<Item = (HttpMethod, &str, &ClassifiedField)> {
+        self.fields.iter().flat_map(|((method, template), fields)| {
+            fields.iter().map(move |field| (*method, template.as_str(), field))
+        })
+    }
+
+    /// The classified field an event's drift concerns, if any
+    ///
+    /// Missing required properties are reported at the enclosing object, so
+    /// the missing property named in the message is taken into account.
+    pub fn classify(&self, event: &DriftEvent) -> Option<&ClassifiedField> {
+        let (Some(method), Some(template)) = (event.method, event.path_template.as_deref()) else {
+            return None;
+        };
+        let fields = self.fields.get(&(method, template.to_string()))?;
+        let scope = if event.location.starts_with("body") {
+            if event.drift_type.concerns_response() {
+                FieldScope::ResponseBody
+            } else {
+                FieldScope::RequestBody
+            }
+        } else {
+            FieldScope::Parameter
+        };
+        let location = match missing_property(event) {
+            Some(property) => format!("{}/{}", event.location, property),
+            None => event.location.clone(),
+        };
+        fields
+            .iter()
+            .filter(|field| field.scope == scope)
+            .find(|field| field.covers(&location))
+    }
+}
+
+/// The property named in a missing-required-property message (`"ssn" is a required property`)
+fn missing_property(event: &DriftEvent) -> Option<&str> {
+    if !matches!(
+        event.drift_type,
+        DriftType::RequestBodyMissingRequired | DriftType::ResponseBodyMissingRequired
+    ) {
+        return None;
+    }
+    let quoted = event.message.split_once('"')?.1;
+    Some(quoted.split_once('"')?.0)
+}
+
+/// Looks up a local `$ref` such as `#/components/schemas/Patient`
+fn resolve_ref<'a>(components: &'a Value, reference: &str) -> Option<&'a Value> {
+    components.pointer(reference.strip_prefix('#')?)
+}
+
+/// The classification of a schema, or of the schema its `$ref` points to
+fn classification_of<'a>(schema: &'a Value, components: &'a Value) -> Option<&'a Value> {
+    schema.get(CLASSIFICATION_EXTENSION).or_else(|| {
+        let reference = schema.get("$ref")?.as_str()?;
+        resolve_ref(components, reference)?.get(CLASSIFICATION_EXTENSION)
+    })
+}
+
+/// Records the classified nodes of a body schema, stopping at the first classification on each branch
+fn classify_schema<'a>(
+    schema: &'a Value,
+    scope: FieldScope,
+    location: &str,
+    components: &'a Value,
+    refs: &mut Vec<&'a str>,
+    classified: &mut Vec<ClassifiedField>,
+) {
+    if let Some(classification) = schema.get(CLASSIFICATION_EXTENSION).and_then(Value::as_str) {
+        classified.push(ClassifiedField {
+            scope,
+            location: location.to_string(),
+            classification: classification.to_string(),
+        });
+        return;
+    }
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if refs.contains(&reference) {
+            return;
+        }
+        if let Some(resolved) = resolve_ref(components, reference) {
+            refs.push(reference);
+            classify_schema(resolved, scope, location, components, refs, classified);
+            refs.pop();
+        }
+        return;
+    }
+
+    for keyword in ["allOf", "oneOf", "anyOf"] {
+        for branch in schema.get(keyword).and_then(Value::as_array).into_iter().flatten() {
+            classify_schema(branch, scope, location, components, refs, classified);
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, subschema) in properties {
+            let property_location = format!("{}/{}", location, name);
+            classify_schema(subschema, scope, &property_location, components, refs, classified);
+        }
+    }
+    for keyword in ["additionalProperties", "items"] {
+        if let Some(subschema) = schema.get(keyword).filter(|s| s.is_object()) {
+            classify_schema(subschema, scope, &format!("{}/*", location), components, refs, classified);
+        }
+    }
+}
+
+/// Drift on one classified field of one operation
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceFinding {
+    pub method: HttpMethod,
+    pub path_template: String,
+    #[serde(flatten)]
+    pub field: ClassifiedField,
+    pub occurrences: u64,
+    pub by_drift_type: BTreeMap<DriftType, u64>,
+    /// Consumers named in the events' context
+    pub consumers: BTreeSet<String>,
+    /// Occurrences whose context names no consumer
+    pub unattributed: u64,
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+}
+
+/// Drift affecting regulated-data fields, grouped by field
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ComplianceReport {
+    /// Occurrences per classification (`pii`, `phi`, ...)
+    pub by_classification: BTreeMap<String, u64>,
+    pub findings: Vec<ComplianceFinding>,
+}
+
+impl ComplianceReport {
+    /// Keeps the events concerning classified fields; `consumer_key` names the
+    /// event context entry identifying the consumer (see [`DEFAULT_CONSUMER_KEY`])
+    pub fn from_events<'a>(
+        index: &ComplianceIndex,
+        events: impl IntoIterator<Item = &'a DriftEvent>,
+        consumer_key: &str,
+    ) -> Self {
+        let mut findings: BTreeMap<(HttpMethod, String, FieldScope, String), ComplianceFinding> = BTreeMap::new();
+        for event in events {
+            let Some(field) = index.classify(event) else {
+                continue;
+            };
+            let template = event.path_template.clone().unwrap_or_default();
+            let Some(method) = event.method else {
+                continue;
+            };
+            let finding = findings
+                .entry((method, template.clone(), field.scope, field.location.clone()))
+                .or_insert_with(|| ComplianceFinding {
+                    method,
+                    path_template: template,
+                    field: field.clone(),
+                    occurrences: 0,
+                    by_drift_type: BTreeMap::new(),
+                    consumers: BTreeSet::new(),
+                    unattributed: 0,
+                    first_seen_ms: event.timestamp_ms,
+                    last_seen_ms: event.timestamp_ms,
+                });
+            finding.occurrences += 1;
+            *finding.by_drift_type.entry(event.drift_type).or_default() += 1;
+            match event.context.get(consumer_key) {
+                Some(consumer) => {
+                    finding.consumers.insert(consumer.clone());
+                }
+                None => finding.unattributed += 1,
+            }
+            finding.first_seen_ms = finding.first_seen_ms.min(event.timestamp_ms);
+            finding.last_seen_ms = finding.last_seen_ms.max(event.timestamp_ms);
+        }
+
+        let findings: Vec<ComplianceFinding> = findings.into_values().collect();
+        let mut by_classification = BTreeMap::new();
+        for finding in &findings {
+            *by_classification.entry(finding.field.classification.clone()).or_default() += finding.occurrences;
+        }
+        Self {
+            by_classification,
+            findings,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ValidationError::ReportError(format!("Failed to serialize compliance report: {}", e)))
+    }
+
+    /// Renders the report as Markdown for inclusion in regulatory filings
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::from("# Regulated Data Drift Report\n\n");
+        if self.findings.is_empty() {
+            out.push_str("No drift affected classified fields.\n");
+            return out;
+        }
+
+        out.push_str("| Classification | Occurrences |\n|---|---:|\n");
+        for (classification, count) in &self.by_classification {
+            let _ = writeln!(out, "| {} | {} |", classification, count);
+        }
+        out.push_str(
+            "\n## Findings\n\n| Operation | Field | Classification | Drift | Occurrences | Consumers | First seen | Last seen |\n\
+             |---|---|---|---|---:|---|---:|---:|\n",
+        );
+        for finding in &self.findings {
+            let drift: Vec<String> = finding
+                .by_drift_type
+                .iter()
+                .map(|(drift_type, count)| format!("{} ×{}", drift_type.as_str(), count))
+                .collect();
+            let mut consumers: Vec<String> = finding.consumers.iter().cloned().collect();
+            if finding.unattributed > 0 {
+                consumers.push(format!("unattributed ×{}", finding.unattributed));
+            }
+            let _ = writeln!(
+                out,
+                "| `{} {}` | {} `{}` | {} | {} | {} | {} | {} | {} |",
+                finding.method.as_str(),
+                finding.path_template,
+                finding.field.scope.as_str(),
+                finding.field.location,
+                finding.field.classification,
+                drift.join(", "),
+                finding.occurrences,
+                consumers.join(", "),
+                finding.first_seen_ms,
+                finding.last_seen_ms
+            );
+        }
+        out
+    }
+}
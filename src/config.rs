@@ -0,0 +1,450 @@
+//! Monitor configuration files and built-in presets
+//!
+//! A configuration wires together where traffic comes from, which of it is
+//! validated, where findings go, and what fails a run. Three deployment
+//! shapes keep coming up, so each ships as a [`Preset`] that a config file
+//! can start from (`preset: k8s-sidecar`) and override key by key.
+
+use crate::api_validator::HttpMethod;
+use crate::array_sampling::ArraySampling;
+use crate::baseline::DriftBaseline;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::Severity;
+use crate::error::ValidationError;
+use crate::exchange_filter::{ExchangeFilter, GatewayMarker, StatusRange};
+use crate::metrics::{spawn_metrics_server, DriftMetrics};
+use crate::notify::WebhookConfig;
+use crate::ownership::Ownership;
+use crate::report::{DriftReport, ReportFormat, ReportProfile};
+use crate::redaction::Redaction;
+use crate::routing::RoutingConfig;
+use crate::sampling::Sampling;
+use crate::validator_config::ValidatorConfig;
+use crate::sinks::{DriftSink, RotatingFileSink, StdoutJsonlSink};
+use crate::spec::{BuildOptions, SchemaLimits};
+use crate::suppression::{Suppressions, DEFAULT_SUPPRESSIONS_PATH};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// How the monitor is wired: traffic source, filtering, sampling, sinks and policy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// OpenAPI spec validated against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec: Option<PathBuf>,
+    /// Where observed traffic comes from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceConfig>,
+    /// Exchanges skipped before validation
+    pub exchange_filter: ExchangeFilter,
+    /// Which items of large arrays in JSON bodies get validated
+    pub array_sampling: ArraySampling,
+    /// Which exchanges get validated at all, for traffic too heavy to validate in full
+    pub sampling: Sampling,
+    /// Fields and headers masked before drift reaches any sink, report or store
+    pub redaction: Redaction,
+    /// How strictly traffic is held to the spec
+    pub validation: ValidatorConfig,
+    /// How request paths are matched to the spec's path templates
+    pub routing: RoutingConfig,
+    /// Validate the parseable prefix of bodies truncated by the capture source
+    pub truncated_captures: bool,
+    /// Report query parameters an operation doesn't declare, except those matching these patterns
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub undocumented_query_parameters: Option<Vec<String>>,
+    /// Accepted drift never reported; rules in a `.driftignore` file next
+    /// to the configuration file are added to these
+    #[serde(skip_serializing_if = "Suppressions::is_empty")]
+    pub suppressions: Suppressions,
+    /// Who owns drift, by drift type, spec tag and path prefix
+    #[serde(skip_serializing_if = "Ownership::is_empty")]
+    pub ownership: Ownership,
+    /// Leave out operations that fail to build instead of failing the whole build
+    pub lenient: bool,
+    /// Bounds on schema complexity, past which an operation fails the build or is skipped
+    pub limits: SchemaLimits,
+    /// Where drift events are written
+    pub sinks: Vec<SinkConfig>,
+    /// What counts as a failed run
+    pub policy: PolicyConfig,
+}
+
+/// Where observed traffic comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum SourceConfig {
+    /// Replays recorded exchanges from a JSONL file
+    Replay { path: PathBuf },
+    /// Proxies traffic in front of a co-located service
+    Sidecar { listen: String, upstream: String },
+    /// Consumes exchange records published by a gateway to a Kafka topic
+    Kafka {
+        brokers: Vec<String>,
+        topic: String,
+        group_id: String,
+    },
+}
+
+/// A destination for drift events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum SinkConfig {
+    /// JSON lines on standard output
+    Stdout,
+    /// JSON lines in a size-rotated file
+    File {
+        path: PathBuf,
+        max_bytes: u64,
+        max_files: usize,
+    },
+    /// Prometheus counters served on `listen`
+    Metrics { listen: String },
+    /// JSON messages published to a Kafka topic (needs the `kafka` feature)
+    Kafka { brokers: Vec<String>, topic: String },
+    /// Distinct findings kept in a SQLite database (needs the `sqlite` feature)
+    Sqlite { path: PathBuf },
+    /// Alerts posted to a Slack or generic HTTP webhook (needs the `webhooks` feature)
+    Webhook(WebhookConfig),
+}
+
+impl SinkConfig {
+    /// Opens the sink, starting the metrics endpoint for [`SinkConfig::Metrics`]
+    pub fn open(&self) -> Result<Box<dyn DriftSink>, ValidationError> {
+        Ok(match self {
+            Self::Stdout => Box::new(StdoutJsonlSink::new()),
+            Self::File {
+                path,
+                max_bytes,
+                max_files,
+            } => Box::new(RotatingFileSink::new(path, *max_bytes, *max_files)?),
+            Self::Metrics { listen } => {
+                let metrics = Arc::new(DriftMetrics::new());
+                spawn_metrics_server(listen.as_str(), Arc::clone(&metrics)).map_err(|e| {
+                    ValidationError::SinkError(format!("Failed to serve metrics on {}: {}", listen, e))
+                })?;
+                Box::new(metrics)
+            }
+            #[cfg(feature = "kafka")]
+            Self::Kafka { brokers, topic } => Box::new(crate::stream::KafkaSink::connect(brokers, topic)?),
+            #[cfg(not(feature = "kafka"))]
+            Self::Kafka { .. } => {
+                return Err(ValidationError::UnsupportedFeature {
+                    feature: "Kafka sinks (build with the `kafka` feature)".to_string(),
+                })
+            }
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite { path } => Box::new(crate::store::DriftStore::open(path)?),
+            #[cfg(not(feature = "sqlite"))]
+            Self::Sqlite { .. } => {
+                return Err(ValidationError::UnsupportedFeature {
+                    feature: "SQLite drift stores (build with the `sqlite` feature)".to_string(),
+                })
+            }
+            #[cfg(feature = "webhooks")]
+            Self::Webhook(config) => Box::new(crate::notify::WebhookNotifier::new(config.clone())?),
+            #[cfg(not(feature = "webhooks"))]
+            Self::Webhook(_) => {
+                return Err(ValidationError::UnsupportedFeature {
+                    feature: "webhook alerts (build with the `webhooks` feature)".to_string(),
+                })
+            }
+        })
+    }
+}
+
+/// What counts as a failed run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+    /// Lowest severity of new drift that fails the run; `None` never fails
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_on: Option<Severity>,
+    /// Known drift excluded from the verdict
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<PathBuf>,
+    /// Reports written at the end of the run, e.g. a full one for the owning
+    /// team and a redacted one for org-wide dashboards
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reports: Vec<ReportOutput>,
+}
+
+/// A report file written at the end of a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportOutput {
+    pub path: PathBuf,
+    pub format: ReportFormat,
+    #[serde(default)]
+    pub profile: ReportProfile,
+}
+
+/// Outcome of applying a policy to the drift of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolicyVerdict {
+    /// Events not in the baseline
+    pub new_drift: usize,
+    /// New events at or above the failing severity
+    pub failing: usize,
+}
+
+impl PolicyVerdict {
+    pub fn passed(&self) -> bool {
+        self.failing == 0
+    }
+}
+
+impl PolicyConfig {
+    /// Decides whether a run with these drift events passes
+    pub fn evaluate(&self, events: &[DriftEvent]) -> Result<PolicyVerdict, ValidationError> {
+        let new_drift = match &self.baseline {
+            Some(path) => DriftBaseline::load(path)?.new_drift(events.iter().cloned()),
+            None => events.to_vec(),
+        };
+        let failing = self
+            .fail_on
+            .map_or(0, |fail_on| new_drift.iter().filter(|event| event.severity >= fail_on).count());
+        Ok(PolicyVerdict {
+            new_drift: new_drift.len(),
+            failing,
+        })
+    }
+
+    /// Writes every configured report, returning their paths
+    pub fn write_reports(&self, report: &DriftReport) -> Result<Vec<PathBuf>, ValidationError> {
+        self.reports
+            .iter()
+            .map(|output| {
+                let rendered = report.for_profile(output.profile).render(output.format)?;
+                fs::write(&output.path, rendered).map_err(|e| {
+                    ValidationError::ReportError(format!("Failed to write {}: {}", output.path.display(), e))
+                })?;
+                Ok(output.path.clone())
+            })
+            .collect()
+    }
+}
+
+impl MonitorConfig {
+    /// Reads a YAML (or JSON) configuration file
+    ///
+    /// A top-level `preset` key starts from that preset; every other
+    /// top-level key in the file replaces the preset's value wholesale.
+    /// Suppression rules in a `.driftignore` file in the same directory are
+    /// added to the configuration's own.
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ValidationError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let config = Self::from_yaml(&contents)
+            .map_err(|e| ValidationError::ConfigError(format!("{}: {}", path.display(), e)))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        config.with_suppressions_in(directory)
+    }
+
+    /// Adds the suppression rules of the `.driftignore` file in `directory`, if there is one
+    pub fn with_suppressions_in(mut self, directory: &Path) -> Result<Self, ValidationError> {
+        let path = directory.join(DEFAULT_SUPPRESSIONS_PATH);
+        if path.is_file() {
+            self.suppressions.extend(Suppressions::load(&path)?);
+        }
+        Ok(self)
+    }
+
+    /// Parses a YAML (or JSON) configuration, see [`MonitorConfig::load`]
+    pub fn from_yaml(contents: &str) -> Result<Self, String> {
+        let mut overrides: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+        let Value::Object(overrides) = &mut overrides else {
+            return Err("expected a mapping at the top level".to_string());
+        };
+
+        let mut config = match overrides.remove("preset") {
+            Some(Value::String(name)) => serde_json::to_value(name.parse::<Preset>()?.config()),
+            Some(other) => return Err(format!("invalid preset: {}", other)),
+            None => serde_json::to_value(Self::default()),
+        }
+        .map_err(|e| e.to_string())?;
+        if let Value::Object(config) = &mut config {
+            config.extend(std::mem::take(overrides));
+        }
+        serde_json::from_value(config).map_err(|e| e.to_string())
+    }
+
+    /// Renders the configuration as YAML, e.g. to start a config file from a preset
+    pub fn to_yaml(&self) -> Result<String, ValidationError> {
+        serde_yaml::to_string(self)
+            .map_err(|e| ValidationError::ConfigError(format!("Failed to serialize configuration: {}", e)))
+    }
+
+    /// The validator build options this configuration implies
+    pub fn build_options(&self) -> BuildOptions {
+        BuildOptions {
+            array_sampling: self.array_sampling.clone(),
+            exchange_filter: self.exchange_filter.clone(),
+            redaction: self.redaction.clone(),
+            validation: self.validation.clone(),
+            routing: self.routing.clone(),
+            truncated_captures: self.truncated_captures,
+            undocumented_query_parameters: self.undocumented_query_parameters.clone(),
+            suppressions: self.suppressions.clone(),
+            ownership: self.ownership.clone(),
+            lenient: self.lenient,
+            limits: self.limits.clone(),
+            ..BuildOptions::default()
+        }
+    }
+}
+
+/// Ready-made configurations for the common deployment shapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Replays recorded traffic in CI and gates the build on new breaking drift
+    CiReplay,
+    /// Runs next to a service in its pod, exporting metrics without ever failing
+    K8sSidecar,
+    /// Consumes gateway access logs from Kafka, ignoring gateway-generated responses
+    GatewayKafka,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Self::CiReplay, Self::K8sSidecar, Self::GatewayKafka];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CiReplay => "ci-replay",
+            Self::K8sSidecar => "k8s-sidecar",
+            Self::GatewayKafka => "gateway-kafka",
+        }
+    }
+
+    pub fn config(&self) -> MonitorConfig {
+        match self {
+            // Every recorded exchange is validated: replay volume is small
+            // and a CI verdict shouldn't depend on a sample
+            Self::CiReplay => MonitorConfig {
+                spec: Some(PathBuf::from("openapi.yaml")),
+                source: Some(SourceConfig::Replay {
+                    path: PathBuf::from("traffic.jsonl"),
+                }),
+                exchange_filter: ExchangeFilter::new().skip_method(HttpMethod::OPTIONS),
+                array_sampling: ArraySampling::All,
+                sampling: Sampling::default(),
+                redaction: Redaction::default(),
+                validation: ValidatorConfig::default(),
+                routing: RoutingConfig::default(),
+                truncated_captures: false,
+                undocumented_query_parameters: None,
+                suppressions: Suppressions::default(),
+                ownership: Ownership::default(),
+                lenient: false,
+                limits: SchemaLimits::default(),
+                sinks: vec![SinkConfig::File {
+                    path: PathBuf::from("drift-events.jsonl"),
+                    max_bytes: 64 * 1024 * 1024,
+                    max_files: 1,
+                }],
+                policy: PolicyConfig {
+                    fail_on: Some(Severity::Breaking),
+                    baseline: Some(PathBuf::from(crate::baseline::DEFAULT_BASELINE_PATH)),
+                    reports: vec![ReportOutput {
+                        path: PathBuf::from("drift-report.xml"),
+                        format: ReportFormat::JUnit,
+                        profile: ReportProfile::Full,
+                    }],
+                },
+            },
+            // In the request path, so large arrays are sampled and each
+            // operation's validation is capped to bound latency; errors are
+            // rare enough to check them all. An operation that fails to build
+            // is left out rather than keeping the sidecar from starting
+            Self::K8sSidecar => MonitorConfig {
+                spec: Some(PathBuf::from("/etc/api-drift/openapi.yaml")),
+                source: Some(SourceConfig::Sidecar {
+                    listen: "0.0.0.0:8081".to_string(),
+                    upstream: "127.0.0.1:8080".to_string(),
+                }),
+                exchange_filter: ExchangeFilter::new()
+                    .skip_method(HttpMethod::OPTIONS)
+                    .skip_status(StatusRange::class(5)),
+                array_sampling: ArraySampling::First(100),
+                sampling: Sampling::new().max_per_second(50).always_errors(),
+                redaction: Redaction::default(),
+                validation: ValidatorConfig::default(),
+                routing: RoutingConfig::default(),
+                truncated_captures: false,
+                undocumented_query_parameters: None,
+                suppressions: Suppressions::default(),
+                ownership: Ownership::default(),
+                lenient: true,
+                limits: SchemaLimits::default(),
+                sinks: vec![
+                    SinkConfig::Stdout,
+                    SinkConfig::Metrics {
+                        listen: "0.0.0.0:9464".to_string(),
+                    },
+                ],
+                policy: PolicyConfig::default(),
+            },
+            // Log pipelines truncate bodies, and the gateway answers 429s and
+            // 5xx on the API's behalf. The specs of the APIs behind it are
+            // often someone else's, so broken operations are left out
+            Self::GatewayKafka => MonitorConfig {
+                spec: Some(PathBuf::from("openapi.yaml")),
+                source: Some(SourceConfig::Kafka {
+                    brokers: vec!["localhost:9092".to_string()],
+                    topic: "api-gateway-access-logs".to_string(),
+                    group_id: "api-spec-drift-monitor".to_string(),
+                }),
+                exchange_filter: ExchangeFilter::new()
+                    .skip_method(HttpMethod::OPTIONS)
+                    .gateway_marker(GatewayMarker::HeaderAbsent {
+                        header: "x-upstream-latency".to_string(),
+                    })
+                    .skip_gateway_responses(),
+                array_sampling: ArraySampling::Random(50),
+                sampling: Sampling::default(),
+                redaction: Redaction::default(),
+                validation: ValidatorConfig::default(),
+                routing: RoutingConfig::default(),
+                truncated_captures: true,
+                undocumented_query_parameters: None,
+                suppressions: Suppressions::default(),
+                ownership: Ownership::default(),
+                lenient: true,
+                limits: SchemaLimits::default(),
+                sinks: vec![
+                    SinkConfig::File {
+                        path: PathBuf::from("drift-events.jsonl"),
+                        max_bytes: 256 * 1024 * 1024,
+                        max_files: 5,
+                    },
+                    SinkConfig::Metrics {
+                        listen: "0.0.0.0:9464".to_string(),
+                    },
+                ],
+                policy: PolicyConfig::default(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|preset| preset.as_str() == s).ok_or_else(|| {
+            let names: Vec<&str> = Self::ALL.iter().map(Preset::as_str).collect();
+            format!("unknown preset '{}' (expected one of {})", s, names.join(", "))
+        })
+    }
+}
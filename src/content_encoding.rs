@@ -0,0 +1,99 @@
+//! Decoding of compressed bodies
+//!
+//! Proxies and middleware see bodies as they went over the wire, so a
+//! client or server that negotiated compression leaves them `gzip`- or
+//! `br`-encoded. Bodies are decoded per their `Content-Encoding` before being
+//! parsed, up to a limit on the decoded size so a small body can't inflate
+//! without bound. Bodies that can't be decoded are reported rather than
+//! parsed as malformed JSON.
+
+use std::borrow::Cow;
+use std::fmt;
+#[cfg(feature = "compression")]
+use std::io::Read;
+
+/// Why a body couldn't be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(not(feature = "compression"), allow(dead_code))]
+pub(crate) enum DecodeError {
+    /// A coding this build can't undo (`zstd`, `compress`, ...)
+    Unsupported(String),
+    /// The decoded body would exceed the limit, in bytes
+    TooLarge(usize),
+    /// The body isn't valid for its coding
+    Corrupt { coding: String, message: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(coding) => write!(f, "unsupported content coding '{}'", coding),
+            Self::TooLarge(limit) => write!(f, "decompresses to more than {} bytes", limit),
+            Self::Corrupt { coding, message } => write!(f, "invalid {} data: {}", coding, message),
+        }
+    }
+}
+
+/// Undoes the codings of a `Content-Encoding` header value on `body`
+///
+/// Codings are listed in the order they were applied and are undone last
+/// to first. An empty body (a `HEAD` or `304` response keeping the header)
+/// and one with only `identity` are returned as they are.
+pub(crate) fn decode<'a>(content_encoding: &str, body: &'a [u8], limit: usize) -> Result<Cow<'a, [u8]>, DecodeError> {
+    let mut decoded = Cow::Borrowed(body);
+    if body.is_empty() {
+        return Ok(decoded);
+    }
+    for coding in content_encoding.rsplit(',').map(str::trim).filter(|coding| !coding.is_empty()) {
+        let coding = coding.to_ascii_lowercase();
+        if coding != "identity" {
+            decoded = Cow::Owned(decode_coding(&coding, &decoded, limit)?);
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(feature = "compression")]
+fn decode_coding(coding: &str, body: &[u8], limit: usize) -> Result<Vec<u8>, DecodeError> {
+    use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+
+    match coding {
+        "gzip" | "x-gzip" => read_limited(coding, MultiGzDecoder::new(body), limit),
+        // `deflate` is meant to be zlib-wrapped, but some servers send raw deflate
+        "deflate" if is_zlib(body) => read_limited(coding, ZlibDecoder::new(body), limit),
+        "deflate" => read_limited(coding, DeflateDecoder::new(body), limit),
+        "br" => read_limited(coding, brotli_decompressor::Decompressor::new(body, 4096), limit),
+        _ => Err(DecodeError::Unsupported(coding.to_string())),
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn decode_coding(coding: &str, _body: &[u8], _limit: usize) -> Result<Vec<u8>, DecodeError> {
+    Err(DecodeError::Unsupported(coding.to_string()))
+}
+
+/// Reads a decoder to the end, failing once it yields more than `limit` bytes
+#[cfg(feature = "compression")]
+fn read_limited(coding: &str, decoder: impl Read, limit: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut decoded = Vec::new();
+    decoder
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| DecodeError::Corrupt {
+            coding: coding.to_string(),
+            message: e.to_string(),
+        })?;
+    if decoded.len() > limit {
+        return Err(DecodeError::TooLarge(limit));
+    }
+    Ok(decoded)
+}
+
+/// Whether `body` starts with a zlib header: deflate method and a valid check value
+#[cfg(feature = "compression")]
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
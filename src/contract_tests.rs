@@ -0,0 +1,329 @@
+//! Contract tests generated from observed drift
+//!
+//! A report's [`Reproducer`]s hold the first exchange each drift finding was
+//! seen on. [`ContractTests::generate`] turns them into test cases pairing
+//! that request with the schema the spec expects and the violation observed,
+//! rendered either as JSON fixtures or as Rust tests that fail while the
+//! exchange still drifts, ready to drop into the service's repository.
+
+use crate::api_validator::HttpMethod;
+use crate::baseline::BaselineKey;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::reproducer::{Reproducer, ReproducerExchange};
+use crate::spec::builder::{components_document, schema_to_json};
+use crate::spec::transform::Direction;
+use crate::spec::{BuildOptions, ResolveReference};
+use openapiv3::{OpenAPI, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+/// The request a contract test sends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractRequest {
+    pub method: HttpMethod,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+/// What the spec documents for the drifting part of the exchange
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractExpectation {
+    /// Documented status of the response, for response drift
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// The body schema with its `$ref`s inlined, when the drift is in a documented body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
+/// The violation the exchange showed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractViolation {
+    pub drift_type: DriftType,
+    pub location: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<Value>,
+}
+
+/// One drift finding as a reproducible test case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContractTest {
+    /// Unique within a generated set and usable as a Rust function name
+    pub name: String,
+    /// e.g. `GET /users/{id}`
+    pub operation: String,
+    pub request: ContractRequest,
+    pub expected: ContractExpectation,
+    pub observed: ContractViolation,
+}
+
+/// Contract tests for a set of drift findings
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContractTests {
+    pub tests: Vec<ContractTest>,
+}
+
+impl ContractTests {
+    /// Builds one test per reproducer, taking the violation's message from
+    /// the first of `events` with the same fingerprint and its expected
+    /// schema from `spec`
+    pub fn generate(spec: &OpenAPI, reproducers: &[Reproducer], events: &[DriftEvent]) -> Self {
+        let options = BuildOptions::default();
+        let documents = [Direction::Request, Direction::Response].map(|direction| {
+            components_document(spec, Some(direction), &options).unwrap_or_else(|_| serde_json::json!({ "components": {} }))
+        });
+        let mut names = BTreeSet::new();
+        let tests = reproducers
+            .iter()
+            .map(|reproducer| {
+                let key = &reproducer.key;
+                let exchange = &reproducer.exchange;
+                let message = events
+                    .iter()
+                    .find(|event| BaselineKey::for_event(event) == *key)
+                    .map(|event| event.message.clone())
+                    .unwrap_or_default();
+                let template = key.path_template.as_deref().unwrap_or(&exchange.path);
+                let (direction, document) = match key.drift_type.concerns_response() {
+                    false => (Direction::Request, &documents[0]),
+                    true => (Direction::Response, &documents[1]),
+                };
+                let schema = key
+                    .location
+                    .starts_with("body")
+                    .then(|| body_schema(spec, exchange, template, direction))
+                    .flatten()
+                    .map(|schema| inline_refs(&schema, document, &mut Vec::new()));
+
+                ContractTest {
+                    name: unique_name(&mut names, exchange.method, template, key),
+                    operation: format!("{} {}", exchange.method.as_str(), template),
+                    request: ContractRequest {
+                        method: exchange.method,
+                        path: exchange.path.clone(),
+                        query: exchange.query.clone(),
+                        headers: exchange.request_headers.clone(),
+                        body: exchange.request_body.clone(),
+                    },
+                    expected: ContractExpectation {
+                        status: (direction == Direction::Response).then_some(exchange.status),
+                        schema,
+                    },
+                    observed: ContractViolation {
+                        drift_type: key.drift_type,
+                        location: key.location.clone(),
+                        message,
+                        status: exchange.status,
+                        response_headers: exchange.response_headers.clone(),
+                        response_body: exchange.response_body.clone(),
+                    },
+                }
+            })
+            .collect();
+        Self { tests }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+
+    /// Renders the tests as a JSON array of fixtures
+    pub fn render_json(&self) -> String {
+        serde_json::to_string_pretty(&self.tests).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Renders the tests as a Rust integration test file
+    ///
+    /// Each test replays its recorded exchange through a validator built
+    /// from `spec_path` and fails while the drift is still found. Replacing
+    /// the recorded response with a call to the service under test keeps
+    /// the test meaningful once the drift is fixed.
+    pub fn render_rust(&self, spec_path: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "//! Contract tests generated from observed drift");
+        let _ = writeln!(out, "//!");
+        let _ = writeln!(out, "//! Each test replays the exchange a drift finding was first seen on and");
+        let _ = writeln!(out, "//! fails while the spec at SPEC_PATH still reports that drift for it.");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "use api_spec_drift_monitor_poc::baseline::BaselineKey;");
+        let _ = writeln!(out, "use api_spec_drift_monitor_poc::{{");
+        let _ = writeln!(
+            out,
+            "    build_api_validator, load_openapi_spec, ApiValidator, DriftType, Exchange, HttpMethod, ObservedRequest, ObservedResponse,"
+        );
+        let _ = writeln!(out, "}};");
+        let _ = writeln!(out, "use std::path::Path;");
+        let _ = writeln!(out, "use std::sync::OnceLock;");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "const SPEC_PATH: &str = {:?};", spec_path);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "fn validator() -> &'static ApiValidator {{");
+        let _ = writeln!(out, "    static VALIDATOR: OnceLock<ApiValidator> = OnceLock::new();");
+        let _ = writeln!(out, "    VALIDATOR.get_or_init(|| {{");
+        let _ = writeln!(out, "        let spec = load_openapi_spec(Path::new(SPEC_PATH)).expect(\"spec loads\");");
+        let _ = writeln!(out, "        build_api_validator(&spec).expect(\"spec compiles\")");
+        let _ = writeln!(out, "    }})");
+        let _ = writeln!(out, "}}");
+        let _ = writeln!(out);
+        let _ = writeln!(out, "fn assert_no_drift(exchange: Exchange, drift_type: DriftType, location: &str) {{");
+        let _ = writeln!(out, "    let events = validator().validate_exchange(&exchange).expect(\"exchange routes to an operation\");");
+        let _ = writeln!(out, "    if let Some(event) = events");
+        let _ = writeln!(out, "        .iter()");
+        let _ = writeln!(out, "        .find(|event| event.drift_type == drift_type && BaselineKey::for_event(event).location == location)");
+        let _ = writeln!(out, "    {{");
+        let _ = writeln!(out, "        panic!(\"{{}} at {{}}: {{}}\", drift_type.as_str(), event.location, event.message);");
+        let _ = writeln!(out, "    }}");
+        let _ = writeln!(out, "}}");
+
+        for test in &self.tests {
+            let request = &test.request;
+            let observed = &test.observed;
+            let _ = writeln!(out);
+            let _ = writeln!(out, "/// {} at {} on {}", observed.drift_type.as_str(), observed.location, test.operation);
+            if !observed.message.is_empty() {
+                let _ = writeln!(out, "///");
+                let _ = writeln!(out, "/// Observed: {}", observed.message.replace('\n', " "));
+            }
+            let _ = writeln!(out, "#[test]");
+            let _ = writeln!(out, "fn {}() {{", test.name);
+            let target = match &request.query {
+                Some(query) => format!("{}?{}", request.path, query),
+                None => request.path.clone(),
+            };
+            let _ = write!(out, "    let request = ObservedRequest::new(HttpMethod::{}, {:?})", request.method.as_str(), target);
+            for (name, value) in &request.headers {
+                let _ = write!(out, "\n        .with_header({:?}, {:?})", name, value);
+            }
+            if let Some(body) = &request.body {
+                let _ = write!(out, "\n        .with_body({})", raw_string(&body_text(body)));
+            }
+            let _ = writeln!(out, ";");
+            let _ = write!(out, "    let response = ObservedResponse::new({})", observed.status);
+            for (name, value) in &observed.response_headers {
+                let _ = write!(out, "\n        .with_header({:?}, {:?})", name, value);
+            }
+            if let Some(body) = &observed.response_body {
+                let _ = write!(out, "\n        .with_body({})", raw_string(&body_text(body)));
+            }
+            let _ = writeln!(out, ";");
+            let _ = writeln!(
+                out,
+                "    assert_no_drift(Exchange::new(request, response), DriftType::{:?}, {:?});",
+                observed.drift_type, observed.location
+            );
+            let _ = writeln!(out, "}}");
+        }
+        out
+    }
+}
+
+/// The documented body schema the drifting body was checked against
+fn body_schema(spec: &OpenAPI, exchange: &ReproducerExchange, template: &str, direction: Direction) -> Option<Value> {
+    let path_item = spec.paths.paths.get(template)?.as_item()?;
+    let operation = path_item
+        .iter()
+        .find(|(method, _)| method.eq_ignore_ascii_case(exchange.method.as_str()))?
+        .1;
+    let (content, content_type) = match direction {
+        Direction::Request => (
+            &operation.request_body.as_ref()?.resolve(spec).ok()?.content,
+            exchange.request_headers.get("content-type"),
+        ),
+        Direction::Response => {
+            let responses = &operation.responses;
+            let response_ref = responses
+                .responses
+                .get(&StatusCode::Code(exchange.status))
+                .or_else(|| responses.responses.get(&StatusCode::Range(exchange.status / 100)))
+                .or(responses.default.as_ref())?;
+            (&response_ref.resolve(spec).ok()?.content, exchange.response_headers.get("content-type"))
+        }
+    };
+    let essence = content_type.and_then(|content_type| content_type.split(';').next()).map(str::trim);
+    let media_type = essence
+        .and_then(|essence| content.iter().find(|(name, _)| name.eq_ignore_ascii_case(essence)))
+        .or_else(|| content.iter().find(|(name, _)| name.contains("json")))
+        .or_else(|| content.iter().next())?
+        .1;
+    schema_to_json(media_type.schema.as_ref()?, "contract test", spec, Some(direction), &BuildOptions::default()).ok()
+}
+
+/// Replaces local `$ref`s with what they point at, so a fixture's schema stands alone
+///
+/// References back into a schema being inlined are kept as they are.
+fn inline_refs(schema: &Value, document: &Value, expanding: &mut Vec<String>) -> Value {
+    match schema {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                let target = reference.strip_prefix('#').and_then(|pointer| document.pointer(pointer));
+                if let Some(target) = target.filter(|_| !expanding.iter().any(|outer| outer == reference)) {
+                    expanding.push(reference.to_string());
+                    let inlined = inline_refs(target, document, expanding);
+                    expanding.pop();
+                    return inlined;
+                }
+                return schema.clone();
+            }
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), inline_refs(value, document, expanding)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| inline_refs(item, document, expanding)).collect()),
+        other => other.clone(),
+    }
+}
+
+/// e.g. `get_users_id_response_body_type_mismatch_body_email`, suffixed when already taken
+fn unique_name(taken: &mut BTreeSet<String>, method: HttpMethod, template: &str, key: &BaselineKey) -> String {
+    let raw = format!("{}_{}_{}_{}", method.as_str(), template, key.drift_type.as_str(), key.location);
+    let mut base = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            base.push(c.to_ascii_lowercase());
+        } else if !base.ends_with('_') && !base.is_empty() {
+            base.push('_');
+        }
+    }
+    let base = base.trim_end_matches('_').to_string();
+    let mut name = base.clone();
+    let mut suffix = 2;
+    while !taken.insert(name.clone()) {
+        name = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+/// A captured body as sent: raw text stays as is, JSON is serialized
+fn body_text(body: &Value) -> String {
+    match body {
+        Value::String(text) if serde_json::from_str::<Value>(text).is_err() => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A Rust raw string literal holding `text`
+fn raw_string(text: &str) -> String {
+    let mut hashes = 1;
+    while text.contains(&format!("\"{}", "#".repeat(hashes))) {
+        hashes += 1;
+    }
+    let hashes = "#".repeat(hashes);
+    format!("r{}\"{}\"{}", hashes, text, hashes)
+}
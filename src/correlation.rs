@@ -0,0 +1,228 @@
+//! Pairing requests and responses that are captured separately
+//!
+//! Middleware hooks and access logs often see a request and its response
+//! as two events, possibly on different threads and interleaved with
+//! other exchanges. [`TrafficCorrelator`] holds each half under the
+//! correlation id the capture assigns it (a request id header, a span id)
+//! until the other half arrives, then validates the complete exchange.
+//! Halves whose partner never shows up are evicted after a timeout.
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+/// How long a half waits for its partner unless configured otherwise
+pub const DEFAULT_CORRELATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pairs request and response halves by correlation id and validates each completed exchange
+///
+/// Halves can arrive in either order and from any thread. Nothing is
+/// evicted in the background: call [`evict_expired`](Self::evict_expired)
+/// periodically, and bound memory with [`with_max_pending`](Self::with_max_pending).
+pub struct TrafficCorrelator {
+    validator: Arc<ApiValidator>,
+    timeout: Duration,
+    max_pending: Option<usize>,
+    pending: Mutex<Pending>,
+}
+
+/// One side of an exchange, waiting for the other
+#[derive(Debug, Clone)]
+pub enum ExchangeHalf {
+    Request(ObservedRequest),
+    Response(ObservedResponse),
+}
+
+/// A half evicted before its partner arrived
+#[derive(Debug, Clone)]
+pub struct UnpairedHalf {
+    pub correlation_id: String,
+    pub half: ExchangeHalf,
+    /// How long the half waited
+    pub waited: Duration,
+}
+
+#[derive(Default)]
+struct Pending {
+    halves: HashMap<String, WaitingHalf>,
+    /// Correlation ids with their arrival sequence, oldest first; entries
+    /// whose half was paired or replaced since are skipped when reached
+    arrivals: VecDeque<(u64, String)>,
+    next_sequence: u64,
+    /// Halves dropped to stay within `max_pending`
+    dropped: u64,
+}
+
+struct WaitingHalf {
+    half: ExchangeHalf,
+    arrived: Instant,
+    sequence: u64,
+}
+
+impl TrafficCorrelator {
+    pub fn new(validator: Arc<ApiValidator>) -> Self {
+        Self {
+            validator,
+            timeout: DEFAULT_CORRELATION_TIMEOUT,
+            max_pending: None,
+            pending: Mutex::default(),
+        }
+    }
+
+    /// Evicts halves that waited longer than `timeout` for their partner
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Holds at most `max_pending` halves, dropping the oldest to make room
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = Some(max_pending);
+        self
+    }
+
+    /// Records a request, validating the exchange if its response already arrived
+    ///
+    /// Returns `None` while the response is outstanding. A request already
+    /// waiting under the same id is replaced.
+    pub fn request(
+        &self,
+        correlation_id: impl Into<String>,
+        request: ObservedRequest,
+    ) -> Result<Option<Vec<DriftEvent>>, ValidationError> {
+        self.half(correlation_id.into(), ExchangeHalf::Request(request))
+    }
+
+    /// Records a response, validating the exchange if its request already arrived
+    ///
+    /// Returns `None` while the request is outstanding. A response already
+    /// waiting under the same id is replaced.
+    pub fn response(
+        &self,
+        correlation_id: impl Into<String>,
+        response: ObservedResponse,
+    ) -> Result<Option<Vec<DriftEvent>>, ValidationError> {
+        self.half(correlation_id.into(), ExchangeHalf::Response(response))
+    }
+
+    /// Removes and returns the halves that waited longer than the timeout, oldest first
+    ///
+    /// Evicted requests can still be checked on their own with
+    /// [`ApiValidator::validate_request`].
+    pub fn evict_expired(&self) -> Vec<UnpairedHalf> {
+        let now = Instant::now();
+        let mut pending = self.lock();
+        let mut evicted = Vec::new();
+        while let Some((sequence, correlation_id)) = pending.arrivals.front().cloned() {
+            match pending.halves.get(&correlation_id) {
+                Some(waiting) if waiting.sequence == sequence => {
+                    let waited = now.saturating_duration_since(waiting.arrived);
+                    if waited <= self.timeout {
+                        break;
+                    }
+                    let waiting = pending.halves.remove(&correlation_id).expect("half is pending");
+                    evicted.push(UnpairedHalf {
+                        correlation_id,
+                        half: waiting.half,
+                        waited,
+                    });
+                }
+                // Paired or replaced since
+                _ => {}
+            }
+            pending.arrivals.pop_front();
+        }
+        evicted
+    }
+
+    /// Halves waiting for their partner
+    pub fn pending(&self) -> usize {
+        self.lock().halves.len()
+    }
+
+    /// Halves dropped so far to stay within the pending limit
+    pub fn dropped(&self) -> u64 {
+        self.lock().dropped
+    }
+
+    fn half(&self, correlation_id: String, half: ExchangeHalf) -> Result<Option<Vec<DriftEvent>>, ValidationError> {
+        let exchange = {
+            let mut pending = self.lock();
+            let partner = match pending.halves.remove(&correlation_id) {
+                // A second half of the same kind replaces the first
+                Some(waiting) => match (waiting.half, half) {
+                    (ExchangeHalf::Request(request), ExchangeHalf::Response(response))
+                    | (ExchangeHalf::Response(response), ExchangeHalf::Request(request)) => {
+                        Ok(Exchange::new(request, response))
+                    }
+                    (_, half) => Err(half),
+                },
+                None => Err(half),
+            };
+            pending.compact();
+            match partner {
+                Ok(exchange) => exchange,
+                Err(half) => {
+                    pending.wait(correlation_id, half, self.max_pending);
+                    return Ok(None);
+                }
+            }
+        };
+        // Validate outside the lock so other exchanges keep pairing meanwhile
+        self.validator.validate_exchange(&exchange).map(Some)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Pending {
+    fn wait(&mut self, correlation_id: String, half: ExchangeHalf, max_pending: Option<usize>) {
+        if let Some(max_pending) = max_pending {
+            while self.halves.len() >= max_pending && self.drop_oldest() {}
+        }
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.arrivals.push_back((sequence, correlation_id.clone()));
+        self.halves.insert(
+            correlation_id,
+            WaitingHalf {
+                half,
+                arrived: Instant::now(),
+                sequence,
+            },
+        );
+    }
+
+    /// Forgets arrivals of halves paired or replaced since, once they
+    /// outnumber the halves still waiting
+    fn compact(&mut self) {
+        if self.arrivals.len() > 2 * self.halves.len() + 1 {
+            let halves = &self.halves;
+            self.arrivals.retain(|(sequence, correlation_id)| {
+                halves.get(correlation_id).is_some_and(|waiting| waiting.sequence == *sequence)
+            });
+        }
+    }
+
+    /// Drops the half that has waited longest, if any
+    fn drop_oldest(&mut self) -> bool {
+        while let Some((sequence, correlation_id)) = self.arrivals.pop_front() {
+            if self.halves.get(&correlation_id).is_some_and(|waiting| waiting.sequence == sequence) {
+                self.halves.remove(&correlation_id);
+                self.dropped += 1;
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,200 @@
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::exchange::Exchange;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Operation key: (method, path template)
+type OperationKey = (HttpMethod, String);
+
+/// Tracks which spec operations and status codes receive traffic
+///
+/// Operations that never see traffic are stale or untested, and status
+/// codes observed but never documented are drift the schema checks can't
+/// see. The tracker is shared across worker threads like a sink.
+#[derive(Debug)]
+pub struct CoverageTracker {
+    declared: BTreeMap<OperationKey, DeclaredOperation>,
+    observed: Mutex<Observed>,
+}
+
+#[derive(Debug)]
+struct DeclaredOperation {
+    operation_id: Option<String>,
+    documented_statuses: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Observed {
+    exchanges: BTreeMap<OperationKey, u64>,
+    statuses: BTreeMap<OperationKey, BTreeMap<u16, u64>>,
+    undocumented_statuses: BTreeMap<OperationKey, Vec<u16>>,
+    unmatched: u64,
+}
+
+impl CoverageTracker {
+    /// Creates a tracker for every operation registered in a validator
+    pub fn new(validator: &ApiValidator) -> Self {
+        let declared = validator
+            .operations()
+            .map(|(template, method, operation)| {
+                (
+                    (method, template.to_string()),
+                    DeclaredOperation {
+                        operation_id: operation.operation_id.clone(),
+                        documented_statuses: operation.responses.documented_statuses(),
+                    },
+                )
+            })
+            .collect();
+        Self {
+            declared,
+            observed: Mutex::new(Observed::default()),
+        }
+    }
+
+    /// Records traffic for an exchange, routing it with the validator the tracker was built from
+    pub fn record_exchange(&self, validator: &ApiValidator, exchange: &Exchange) {
+        let request = &exchange.request;
+        let routed = validator
+            .find_operation(&request.path, request.method)
+            .ok()
+            .zip(validator.path_template(&request.path));
+
+        match routed {
+            Some(((operation, _), template)) => {
+                let status = exchange.response.status;
+                let documented = operation.responses.is_documented(status);
+                self.record(request.method, template, Some(status));
+                if !documented {
+                    let mut observed = self.lock();
+                    let statuses = observed
+                        .undocumented_statuses
+                        .entry((request.method, template.to_string()))
+                        .or_default();
+                    if !statuses.contains(&status) {
+                        statuses.push(status);
+                    }
+                }
+            }
+            None => self.lock().unmatched += 1,
+        }
+    }
+
+    /// Records one request to an operation, with the response status if known
+    pub fn record(&self, method: HttpMethod, path_template: &str, status: Option<u16>) {
+        let key = (method, path_template.to_string());
+        let mut observed = self.lock();
+        if !self.declared.contains_key(&key) {
+            observed.unmatched += 1;
+            return;
+        }
+        if let Some(status) = status {
+            *observed.statuses.entry(key.clone()).or_default().entry(status).or_default() += 1;
+        }
+        *observed.exchanges.entry(key).or_default() += 1;
+    }
+
+    /// Snapshot of coverage so far
+    pub fn report(&self) -> CoverageReport {
+        let observed = self.lock();
+        let operations: Vec<OperationCoverage> = self
+            .declared
+            .iter()
+            .map(|(key, declared)| {
+                let observed_statuses = observed.statuses.get(key).cloned().unwrap_or_default();
+                let mut undocumented_statuses =
+                    observed.undocumented_statuses.get(key).cloned().unwrap_or_default();
+                undocumented_statuses.sort_unstable();
+                let unobserved_statuses = declared
+                    .documented_statuses
+                    .iter()
+                    .filter(|status| !is_observed(status, &observed_statuses))
+                    .cloned()
+                    .collect();
+
+                OperationCoverage {
+                    method: key.0,
+                    path_template: key.1.clone(),
+                    operation_id: declared.operation_id.clone(),
+                    exchanges: observed.exchanges.get(key).copied().unwrap_or(0),
+                    documented_statuses: declared.documented_statuses.clone(),
+                    observed_statuses,
+                    unobserved_statuses,
+                    undocumented_statuses,
+                }
+            })
+            .collect();
+
+        CoverageReport {
+            operations_total: operations.len(),
+            operations_observed: operations.iter().filter(|op| op.exchanges > 0).count(),
+            unmatched_requests: observed.unmatched,
+            operations,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Observed> {
+        self.observed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Whether a documented status (`200`, `4XX`, `default`) was seen
+fn is_observed(documented: &str, observed: &BTreeMap<u16, u64>) -> bool {
+    if documented == "default" {
+        return false;
+    }
+    match documented.strip_suffix("XX") {
+        Some(class) => class
+            .parse::<u16>()
+            .map(|class| observed.keys().any(|status| status / 100 == class))
+            .unwrap_or(false),
+        None => documented
+            .parse::<u16>()
+            .map(|status| observed.contains_key(&status))
+            .unwrap_or(false),
+    }
+}
+
+/// Traffic seen for a single spec operation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationCoverage {
+    pub method: HttpMethod,
+    pub path_template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    pub exchanges: u64,
+    /// Responses documented in the spec (`200`, `4XX`, `default`)
+    pub documented_statuses: Vec<String>,
+    /// Response status codes seen, with counts
+    pub observed_statuses: BTreeMap<u16, u64>,
+    /// Documented responses never seen (`default` is always listed here)
+    pub unobserved_statuses: Vec<String>,
+    /// Status codes seen that the spec doesn't document
+    pub undocumented_statuses: Vec<u16>,
+}
+
+/// Coverage of the spec by observed traffic
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub operations_total: usize,
+    pub operations_observed: usize,
+    /// Requests that matched no spec operation
+    pub unmatched_requests: u64,
+    pub operations: Vec<OperationCoverage>,
+}
+
+impl CoverageReport {
+    /// Fraction of operations that received traffic, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        if self.operations_total == 0 {
+            return 1.0;
+        }
+        self.operations_observed as f64 / self.operations_total as f64
+    }
+
+    /// Operations that received no traffic
+    pub fn never_hit(&self) -> impl Iterator<Item = &OperationCoverage> {
+        self.operations.iter().filter(|op| op.exchanges == 0)
+    }
+}
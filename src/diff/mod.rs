@@ -0,0 +1,555 @@
+//! Static drift detection between two versions of an OpenAPI document
+//!
+//! Where validators find drift between a spec and observed traffic, this
+//! module finds drift between two specs. Every change is classified from the
+//! point of view of existing clients and, where it maps onto one, tagged with
+//! the [`DriftType`] that traffic written against the old spec would raise
+//! when validated against the new one.
+
+mod schema;
+
+use crate::api_validator::HttpMethod;
+use crate::drift_types::{DriftType, Severity, ValidationContext};
+use crate::error::ValidationError;
+use crate::spec::ResolveReference;
+use openapiv3::{OpenAPI, Operation, Parameter, ParameterSchemaOrContent, ReferenceOr, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// What happened to a spec element between the two documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A single difference between two specs
+#[derive(Debug, Clone, Serialize)]
+pub struct SpecChange {
+    pub kind: ChangeKind,
+    pub severity: Severity,
+    /// The traffic drift clients of the old spec would trigger, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift_type: Option<DriftType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    /// Path template of the operation, as written in the new spec when it exists
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    /// Where in the operation the change is (e.g. `query/limit`, `response/200/body/id`)
+    pub location: String,
+    pub message: String,
+}
+
+impl SpecChange {
+    pub fn is_breaking(&self) -> bool {
+        self.severity == Severity::Breaking
+    }
+}
+
+impl fmt::Display for SpecChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.severity.as_str().to_uppercase())?;
+        if let (Some(method), Some(path)) = (self.method, &self.path_template) {
+            write!(f, " {} {}", method.as_str(), path)?;
+        }
+        write!(f, " at {} - {}", self.location, self.message)
+    }
+}
+
+/// All differences found between an old and a new spec
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpecDiff {
+    pub changes: Vec<SpecChange>,
+}
+
+impl SpecDiff {
+    /// Changes that break clients written against the old spec
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &SpecChange> {
+        self.changes.iter().filter(|change| change.is_breaking())
+    }
+
+    pub fn has_breaking_changes(&self) -> bool {
+        self.breaking_changes().next().is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Compares two OpenAPI documents, old first
+///
+/// Operations are matched by method and path template, ignoring the names
+/// of path parameters so that `/users/{id}` and `/users/{userId}` pair up.
+pub fn diff_specs(old: &OpenAPI, new: &OpenAPI) -> Result<SpecDiff, ValidationError> {
+    let mut differ = Differ {
+        old_spec: old,
+        new_spec: new,
+        old_json: spec_to_json(old)?,
+        new_json: spec_to_json(new)?,
+        changes: Vec::new(),
+    };
+
+    let old_operations = collect_operations(old)?;
+    let new_operations = collect_operations(new)?;
+
+    for (key, (template, operation)) in &old_operations {
+        match new_operations.get(key) {
+            Some((new_template, new_operation)) => {
+                differ.operation(key.0, (template, new_template), operation, new_operation)?
+            }
+            None => differ.push(
+                Scope::operation(key.0, template),
+                ChangeKind::Removed,
+                Severity::Breaking,
+                None,
+                "operation",
+                "Operation was removed",
+            ),
+        }
+    }
+    for (key, (template, _)) in &new_operations {
+        if !old_operations.contains_key(key) {
+            differ.push(
+                Scope::operation(key.0, template),
+                ChangeKind::Added,
+                Severity::Info,
+                None,
+                "operation",
+                "Operation was added",
+            );
+        }
+    }
+
+    differ.component_schemas();
+    Ok(SpecDiff { changes: differ.changes })
+}
+
+/// Operations keyed by method and normalized path template
+type Operations<'a> = BTreeMap<(HttpMethod, String), (&'a str, &'a Operation)>;
+
+fn collect_operations(spec: &OpenAPI) -> Result<Operations<'_>, ValidationError> {
+    let mut operations = BTreeMap::new();
+    for (path, path_item) in &spec.paths.paths {
+        let Some(path_item) = path_item.as_item() else {
+            continue;
+        };
+        for (method, operation) in path_item.iter() {
+            let method = HttpMethod::from_str(method).map_err(|_| {
+                ValidationError::UnsupportedFeature {
+                    feature: format!("HTTP method {}", method),
+                }
+            })?;
+            operations.insert((method, normalize_template(path)), (path.as_str(), operation));
+        }
+    }
+    Ok(operations)
+}
+
+/// Replaces every `{name}` segment with `{}`
+pub(crate) fn normalize_template(path: &str) -> String {
+    let mut normalized = String::with_capacity(path.len());
+    let mut in_param = false;
+    for c in path.chars() {
+        match c {
+            '{' => {
+                in_param = true;
+                normalized.push('{');
+            }
+            '}' => {
+                in_param = false;
+                normalized.push('}');
+            }
+            _ if in_param => {}
+            _ => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+fn spec_to_json(spec: &OpenAPI) -> Result<Value, ValidationError> {
+    serde_json::to_value(spec).map_err(|e| {
+        ValidationError::SchemaCompilationError(format!("Failed to convert spec to JSON: {}", e))
+    })
+}
+
+/// The operation a change belongs to
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    method: Option<HttpMethod>,
+    path_template: Option<&'a str>,
+}
+
+impl<'a> Scope<'a> {
+    fn operation(method: HttpMethod, path_template: &'a str) -> Self {
+        Self {
+            method: Some(method),
+            path_template: Some(path_template),
+        }
+    }
+
+    fn spec() -> Self {
+        Self {
+            method: None,
+            path_template: None,
+        }
+    }
+}
+
+struct Differ<'a> {
+    old_spec: &'a OpenAPI,
+    new_spec: &'a OpenAPI,
+    old_json: Value,
+    new_json: Value,
+    changes: Vec<SpecChange>,
+}
+
+impl Differ<'_> {
+    fn push(
+        &mut self,
+        scope: Scope<'_>,
+        kind: ChangeKind,
+        severity: Severity,
+        drift_type: Option<DriftType>,
+        location: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.changes.push(SpecChange {
+            kind,
+            severity,
+            drift_type,
+            method: scope.method,
+            path_template: scope.path_template.map(str::to_string),
+            location: location.into(),
+            message: message.into(),
+        });
+    }
+
+    fn operation(
+        &mut self,
+        method: HttpMethod,
+        (old_template, new_template): (&str, &str),
+        old: &Operation,
+        new: &Operation,
+    ) -> Result<(), ValidationError> {
+        let scope = Scope::operation(method, new_template);
+        let old_parameters = index_parameters(&old.parameters, self.old_spec, old_template)?;
+        let new_parameters = index_parameters(&new.parameters, self.new_spec, new_template)?;
+        self.parameters(scope, &old_parameters, &new_parameters)?;
+        self.request_body(scope, old.request_body.as_ref(), new.request_body.as_ref())?;
+        self.responses(scope, &old.responses, &new.responses)
+    }
+
+    fn parameters(
+        &mut self,
+        scope: Scope<'_>,
+        old: &BTreeMap<String, &Parameter>,
+        new: &BTreeMap<String, &Parameter>,
+    ) -> Result<(), ValidationError> {
+        for (location, old_param) in old {
+            let Some(new_param) = new.get(location) else {
+                self.push(
+                    scope,
+                    ChangeKind::Removed,
+                    Severity::Warning,
+                    None,
+                    location.clone(),
+                    "Parameter was removed",
+                );
+                continue;
+            };
+
+            let (old_data, new_data) = (old_param.parameter_data_ref(), new_param.parameter_data_ref());
+            if !old_data.required && new_data.required {
+                self.push(
+                    scope,
+                    ChangeKind::Changed,
+                    Severity::Breaking,
+                    Some(DriftType::ParameterMissingRequired),
+                    location.clone(),
+                    "Parameter became required",
+                );
+            } else if old_data.required && !new_data.required {
+                self.push(
+                    scope,
+                    ChangeKind::Changed,
+                    Severity::Info,
+                    None,
+                    location.clone(),
+                    "Parameter is no longer required",
+                );
+            }
+
+            if let (Some(old_schema), Some(new_schema)) =
+                (parameter_schema(old_param)?, parameter_schema(new_param)?)
+            {
+                self.schema(scope, ValidationContext::Parameter, location, &old_schema, &new_schema);
+            }
+        }
+
+        for (location, new_param) in new {
+            if old.contains_key(location) {
+                continue;
+            }
+            if new_param.parameter_data_ref().required {
+                self.push(
+                    scope,
+                    ChangeKind::Added,
+                    Severity::Breaking,
+                    Some(DriftType::ParameterMissingRequired),
+                    location.clone(),
+                    "Required parameter was added",
+                );
+            } else {
+                self.push(
+                    scope,
+                    ChangeKind::Added,
+                    Severity::Info,
+                    None,
+                    location.clone(),
+                    "Optional parameter was added",
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn request_body(
+        &mut self,
+        scope: Scope<'_>,
+        old: Option<&ReferenceOr<openapiv3::RequestBody>>,
+        new: Option<&ReferenceOr<openapiv3::RequestBody>>,
+    ) -> Result<(), ValidationError> {
+        let old = old.map(|body| body.resolve(self.old_spec)).transpose()?;
+        let new = new.map(|body| body.resolve(self.new_spec)).transpose()?;
+
+        match (old, new) {
+            (None, None) => {}
+            (Some(_), None) => self.push(
+                scope,
+                ChangeKind::Removed,
+                Severity::Warning,
+                None,
+                "body",
+                "Request body was removed",
+            ),
+            (None, Some(new)) => {
+                let (severity, drift_type, message) = if new.required {
+                    (
+                        Severity::Breaking,
+                        Some(DriftType::RequestBodyMissingRequired),
+                        "Required request body was added",
+                    )
+                } else {
+                    (Severity::Info, None, "Optional request body was added")
+                };
+                self.push(scope, ChangeKind::Added, severity, drift_type, "body", message);
+            }
+            (Some(old), Some(new)) => {
+                if !old.required && new.required {
+                    self.push(
+                        scope,
+                        ChangeKind::Changed,
+                        Severity::Breaking,
+                        Some(DriftType::RequestBodyMissingRequired),
+                        "body",
+                        "Request body became required",
+                    );
+                }
+                if let (Some(old_schema), Some(new_schema)) =
+                    (json_schema(&old.content)?, json_schema(&new.content)?)
+                {
+                    self.schema(scope, ValidationContext::RequestBody, "body", &old_schema, &new_schema);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn responses(
+        &mut self,
+        scope: Scope<'_>,
+        old: &openapiv3::Responses,
+        new: &openapiv3::Responses,
+    ) -> Result<(), ValidationError> {
+        let old = self.index_responses(old, self.old_spec)?;
+        let new = self.index_responses(new, self.new_spec)?;
+
+        for (status, old_response) in &old {
+            let location = format!("response/{}", status);
+            let Some(new_response) = new.get(status) else {
+                self.push(
+                    scope,
+                    ChangeKind::Removed,
+                    Severity::Info,
+                    None,
+                    location,
+                    "Response was removed",
+                );
+                continue;
+            };
+            if let (Some(old_schema), Some(new_schema)) =
+                (json_schema(&old_response.content)?, json_schema(&new_response.content)?)
+            {
+                self.schema(
+                    scope,
+                    ValidationContext::ResponseBody,
+                    &format!("{}/body", location),
+                    &old_schema,
+                    &new_schema,
+                );
+            }
+        }
+        for status in new.keys().filter(|status| !old.contains_key(*status)) {
+            self.push(
+                scope,
+                ChangeKind::Added,
+                Severity::Warning,
+                None,
+                format!("response/{}", status),
+                "Response was added; existing clients may not handle it",
+            );
+        }
+        Ok(())
+    }
+
+    /// Resolves responses and keys them by status code (`200`, `4XX`, `default`)
+    fn index_responses<'s>(
+        &self,
+        responses: &'s openapiv3::Responses,
+        spec: &'s OpenAPI,
+    ) -> Result<BTreeMap<String, &'s openapiv3::Response>, ValidationError> {
+        let mut indexed = BTreeMap::new();
+        for (status, response) in &responses.responses {
+            let status = match status {
+                StatusCode::Code(code) => code.to_string(),
+                StatusCode::Range(range) => format!("{}XX", range),
+            };
+            indexed.insert(status, response.resolve(spec)?);
+        }
+        if let Some(default) = &responses.default {
+            indexed.insert("default".to_string(), default.resolve(spec)?);
+        }
+        Ok(indexed)
+    }
+
+    /// Reports components schemas that were added or removed
+    ///
+    /// Changes inside schemas are reported where operations use them.
+    fn component_schemas(&mut self) {
+        let old_names = component_schema_names(self.old_spec);
+        let new_names = component_schema_names(self.new_spec);
+
+        for name in old_names.iter().filter(|name| !new_names.contains(name)) {
+            self.push(
+                Scope::spec(),
+                ChangeKind::Removed,
+                Severity::Info,
+                None,
+                format!("#/components/schemas/{}", name),
+                "Schema was removed",
+            );
+        }
+        for name in new_names.iter().filter(|name| !old_names.contains(name)) {
+            self.push(
+                Scope::spec(),
+                ChangeKind::Added,
+                Severity::Info,
+                None,
+                format!("#/components/schemas/{}", name),
+                "Schema was added",
+            );
+        }
+    }
+
+    fn schema(
+        &mut self,
+        scope: Scope<'_>,
+        context: ValidationContext,
+        location: &str,
+        old: &Value,
+        new: &Value,
+    ) {
+        let findings = schema::diff(&self.old_json, &self.new_json, old, new, context, location);
+        for finding in findings {
+            self.push(
+                scope,
+                ChangeKind::Changed,
+                finding.severity,
+                finding.drift_type,
+                finding.location,
+                finding.message,
+            );
+        }
+    }
+}
+
+fn component_schema_names(spec: &OpenAPI) -> Vec<&str> {
+    spec.components
+        .iter()
+        .flat_map(|components| components.schemas.keys())
+        .map(String::as_str)
+        .collect()
+}
+
+/// Resolves parameters and keys them by `{in}/{name}`
+///
+/// Path parameters are keyed by their position in the template instead, so
+/// renaming one is not reported as a removal plus an addition.
+fn index_parameters<'s>(
+    parameters: &'s [ReferenceOr<Parameter>],
+    spec: &'s OpenAPI,
+    template: &str,
+) -> Result<BTreeMap<String, &'s Parameter>, ValidationError> {
+    let path_params: Vec<&str> = template
+        .split('{')
+        .skip(1)
+        .filter_map(|segment| segment.split_once('}').map(|(name, _)| name))
+        .collect();
+
+    parameters
+        .iter()
+        .map(|parameter| {
+            let parameter = parameter.resolve(spec)?;
+            let name = &parameter.parameter_data_ref().name;
+            let location = match parameter {
+                Parameter::Query { .. } => format!("query/{}", name),
+                Parameter::Header { .. } => format!("header/{}", name.to_ascii_lowercase()),
+                Parameter::Cookie { .. } => format!("cookie/{}", name),
+                Parameter::Path { .. } => match path_params.iter().position(|p| p == name) {
+                    Some(index) => format!("path/{}", index),
+                    None => format!("path/{}", name),
+                },
+            };
+            Ok((location, parameter))
+        })
+        .collect()
+}
+
+fn parameter_schema(parameter: &Parameter) -> Result<Option<Value>, ValidationError> {
+    match &parameter.parameter_data_ref().format {
+        ParameterSchemaOrContent::Schema(schema) => schema_value(schema).map(Some),
+        ParameterSchemaOrContent::Content(content) => json_schema(content),
+    }
+}
+
+/// Schema of the `application/json` media type, if any
+fn json_schema(content: &openapiv3::Content) -> Result<Option<Value>, ValidationError> {
+    content
+        .get("application/json")
+        .and_then(|media_type| media_type.schema.as_ref())
+        .map(schema_value)
+        .transpose()
+}
+
+fn schema_value(schema: &impl Serialize) -> Result<Value, ValidationError> {
+    serde_json::to_value(schema).map_err(|e| {
+        ValidationError::SchemaCompilationError(format!("Failed to convert schema to JSON: {}", e))
+    })
+}
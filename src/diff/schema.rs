@@ -0,0 +1,323 @@
+use crate::drift_types::{DriftType, Severity, ValidationContext};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashSet};
+
+/// Nesting depth after which schemas are no longer compared
+const MAX_DEPTH: usize = 32;
+
+/// A difference found inside a schema
+pub(super) struct Finding {
+    pub severity: Severity,
+    pub drift_type: Option<DriftType>,
+    pub location: String,
+    pub message: String,
+}
+
+/// Compares two schemas, resolving `$ref`s against their spec documents
+///
+/// Whether narrowing or widening breaks clients depends on direction: for
+/// parameters and request bodies the new schema must still accept everything
+/// the old one did; for response bodies it must not produce anything the old
+/// one didn't promise.
+pub(super) fn diff(
+    old_root: &Value,
+    new_root: &Value,
+    old: &Value,
+    new: &Value,
+    context: ValidationContext,
+    location: &str,
+) -> Vec<Finding> {
+    let mut walker = Walker {
+        old_root,
+        new_root,
+        context,
+        visited: HashSet::new(),
+        findings: Vec::new(),
+    };
+    walker.compare(old, new, location, 0);
+    walker.findings
+}
+
+/// The assertion a change affects, used to pick the drift type
+#[derive(Clone, Copy)]
+enum Check {
+    Type,
+    Required,
+    Enum,
+    Format,
+    OneOf,
+    AnyOf,
+}
+
+struct Walker<'a> {
+    old_root: &'a Value,
+    new_root: &'a Value,
+    context: ValidationContext,
+    /// `$ref` pairs already compared, so recursive schemas terminate
+    visited: HashSet<(String, String)>,
+    findings: Vec<Finding>,
+}
+
+impl Walker<'_> {
+    fn compare(&mut self, old: &Value, new: &Value, location: &str, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let old_ref = ref_target(old);
+        let new_ref = ref_target(new);
+        if old_ref.is_some() || new_ref.is_some() {
+            let pair = (old_ref.unwrap_or_default().to_string(), new_ref.unwrap_or_default().to_string());
+            if !self.visited.insert(pair) {
+                return;
+            }
+        }
+        let (Some(old), Some(new)) = (resolve(self.old_root, old), resolve(self.new_root, new)) else {
+            return;
+        };
+
+        self.types(old, new, location);
+        self.enums(old, new, location);
+        self.format(old, new, location);
+        self.required(old, new, location);
+        self.properties(old, new, location, depth);
+        self.composition(old, new, "oneOf", Check::OneOf, location);
+        self.composition(old, new, "anyOf", Check::AnyOf, location);
+
+        if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+            self.compare(old_items, new_items, &format!("{}/items", location), depth + 1);
+        }
+    }
+
+    fn types(&mut self, old: &Value, new: &Value, location: &str) {
+        let (Some(old_types), Some(new_types)) = (types(old), types(new)) else {
+            return;
+        };
+        if old_types == new_types {
+            return;
+        }
+        // An integer is still a number
+        let accepts = |types: &BTreeSet<&str>, t: &str| {
+            types.contains(t) || (t == "integer" && types.contains("number"))
+        };
+        let narrowed = old_types.iter().any(|t| !accepts(&new_types, t));
+        let widened = new_types.iter().any(|t| !accepts(&old_types, t));
+        self.report(
+            Check::Type,
+            narrowed,
+            widened,
+            location,
+            format!("Type changed from {} to {}", join(&old_types), join(&new_types)),
+        );
+    }
+
+    fn enums(&mut self, old: &Value, new: &Value, location: &str) {
+        let (Some(Value::Array(old_values)), Some(Value::Array(new_values))) =
+            (old.get("enum"), new.get("enum"))
+        else {
+            return;
+        };
+        let removed: Vec<&Value> = old_values.iter().filter(|v| !new_values.contains(v)).collect();
+        let added: Vec<&Value> = new_values.iter().filter(|v| !old_values.contains(v)).collect();
+
+        if !removed.is_empty() {
+            self.report(
+                Check::Enum,
+                true,
+                false,
+                location,
+                format!("Enum values removed: {}", join_values(&removed)),
+            );
+        }
+        if !added.is_empty() {
+            self.report(
+                Check::Enum,
+                false,
+                true,
+                location,
+                format!("Enum values added: {}", join_values(&added)),
+            );
+        }
+    }
+
+    fn format(&mut self, old: &Value, new: &Value, location: &str) {
+        let old_format = old.get("format").and_then(Value::as_str);
+        let new_format = new.get("format").and_then(Value::as_str);
+        if old_format == new_format {
+            return;
+        }
+        let message = match (old_format, new_format) {
+            (Some(old_format), Some(new_format)) => {
+                format!("Format changed from {} to {}", old_format, new_format)
+            }
+            (None, Some(new_format)) => format!("Format {} was added", new_format),
+            (Some(old_format), None) => format!("Format {} was removed", old_format),
+            (None, None) => return,
+        };
+        self.report(Check::Format, new_format.is_some(), old_format.is_some(), location, message);
+    }
+
+    fn required(&mut self, old: &Value, new: &Value, location: &str) {
+        let old_required = required(old);
+        let new_required = required(new);
+
+        for name in new_required.difference(&old_required) {
+            self.report(
+                Check::Required,
+                true,
+                false,
+                &format!("{}/{}", location, name),
+                format!("Property '{}' became required", name),
+            );
+        }
+        for name in old_required.difference(&new_required) {
+            self.report(
+                Check::Required,
+                false,
+                true,
+                &format!("{}/{}", location, name),
+                format!("Property '{}' is no longer required", name),
+            );
+        }
+    }
+
+    fn properties(&mut self, old: &Value, new: &Value, location: &str, depth: usize) {
+        let empty = serde_json::Map::new();
+        let old_properties = old.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+        let new_properties = new.get("properties").and_then(Value::as_object).unwrap_or(&empty);
+
+        for (name, old_property) in old_properties {
+            let property_location = format!("{}/{}", location, name);
+            match new_properties.get(name) {
+                Some(new_property) => self.compare(old_property, new_property, &property_location, depth + 1),
+                // Removing a required property is already reported by `required`
+                None if !required(new).contains(name.as_str()) && !required(old).contains(name.as_str()) => {
+                    self.findings.push(Finding {
+                        severity: Severity::Warning,
+                        drift_type: None,
+                        location: property_location,
+                        message: format!("Property '{}' was removed", name),
+                    })
+                }
+                None => {}
+            }
+        }
+        for name in new_properties.keys().filter(|name| !old_properties.contains_key(*name)) {
+            self.findings.push(Finding {
+                severity: Severity::Info,
+                drift_type: None,
+                location: format!("{}/{}", location, name),
+                message: format!("Property '{}' was added", name),
+            });
+        }
+    }
+
+    fn composition(&mut self, old: &Value, new: &Value, keyword: &str, check: Check, location: &str) {
+        let old_branches = old.get(keyword).and_then(Value::as_array).map_or(0, Vec::len);
+        let new_branches = new.get(keyword).and_then(Value::as_array).map_or(0, Vec::len);
+        if old_branches == new_branches {
+            return;
+        }
+        self.report(
+            check,
+            new_branches < old_branches,
+            new_branches > old_branches,
+            location,
+            format!("{} branches changed from {} to {}", keyword, old_branches, new_branches),
+        );
+    }
+
+    /// Records a change given whether it narrows and/or widens what the schema allows
+    fn report(&mut self, check: Check, narrowed: bool, widened: bool, location: &str, message: String) {
+        let breaking = match self.context {
+            ValidationContext::Parameter | ValidationContext::RequestBody => narrowed,
+            ValidationContext::ResponseBody | ValidationContext::MessagePayload => widened,
+        };
+        let (severity, drift_type) = if breaking {
+            (Severity::Breaking, Some(drift_type(check, self.context)))
+        } else {
+            (Severity::Info, None)
+        };
+        self.findings.push(Finding {
+            severity,
+            drift_type,
+            location: location.to_string(),
+            message,
+        });
+    }
+}
+
+fn drift_type(check: Check, context: ValidationContext) -> DriftType {
+    use ValidationContext::*;
+
+    match (check, context) {
+        (Check::Type, Parameter) => DriftType::ParameterTypeMismatch,
+        (Check::Type, RequestBody) => DriftType::RequestBodyTypeMismatch,
+        (Check::Type, ResponseBody) => DriftType::ResponseBodyTypeMismatch,
+        (Check::Type, MessagePayload) => DriftType::MessagePayloadTypeMismatch,
+        (Check::Required, Parameter) => DriftType::ParameterMissingRequired,
+        (Check::Required, RequestBody) => DriftType::RequestBodyMissingRequired,
+        (Check::Required, ResponseBody) => DriftType::ResponseBodyMissingRequired,
+        (Check::Required, MessagePayload) => DriftType::MessagePayloadMissingRequired,
+        (Check::Enum, Parameter) => DriftType::ParameterEnumViolation,
+        (Check::Enum, RequestBody) => DriftType::RequestBodyEnumViolation,
+        (Check::Enum, ResponseBody) => DriftType::ResponseBodyEnumViolation,
+        (Check::Enum, MessagePayload) => DriftType::MessagePayloadEnumViolation,
+        (Check::Format, Parameter) => DriftType::ParameterFormatViolation,
+        (Check::Format, RequestBody) => DriftType::RequestBodyFormatViolation,
+        (Check::Format, ResponseBody) => DriftType::ResponseBodyFormatViolation,
+        (Check::Format, MessagePayload) => DriftType::MessagePayloadFormatViolation,
+        (Check::OneOf, Parameter) => DriftType::ParameterOneOfNoMatch,
+        (Check::OneOf, RequestBody) => DriftType::RequestBodyOneOfNoMatch,
+        (Check::OneOf, ResponseBody) => DriftType::ResponseBodyOneOfNoMatch,
+        (Check::OneOf, MessagePayload) => DriftType::MessagePayloadOneOfNoMatch,
+        (Check::AnyOf, Parameter) => DriftType::ParameterAnyOfNoMatch,
+        (Check::AnyOf, RequestBody) => DriftType::RequestBodyAnyOfNoMatch,
+        (Check::AnyOf, ResponseBody) => DriftType::ResponseBodyAnyOfNoMatch,
+        (Check::AnyOf, MessagePayload) => DriftType::MessagePayloadAnyOfNoMatch,
+    }
+}
+
+fn ref_target(schema: &Value) -> Option<&str> {
+    schema.get("$ref").and_then(Value::as_str)
+}
+
+/// Follows local `$ref`s until reaching a schema
+fn resolve<'v>(root: &'v Value, schema: &'v Value) -> Option<&'v Value> {
+    let mut current = schema;
+    for _ in 0..MAX_DEPTH {
+        match ref_target(current) {
+            Some(reference) => current = root.pointer(reference.strip_prefix('#')?)?,
+            None => return Some(current),
+        }
+    }
+    None
+}
+
+/// Declared types, including `null` for OpenAPI 3.0 `nullable` schemas
+fn types(schema: &Value) -> Option<BTreeSet<&str>> {
+    let mut types: BTreeSet<&str> = match schema.get("type")? {
+        Value::String(t) => BTreeSet::from([t.as_str()]),
+        Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => return None,
+    };
+    if schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+        types.insert("null");
+    }
+    Some(types)
+}
+
+fn required(schema: &Value) -> BTreeSet<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn join(types: &BTreeSet<&str>) -> String {
+    types.iter().copied().collect::<Vec<_>>().join("|")
+}
+
+fn join_values(values: &[&Value]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,286 @@
+use crate::api_validator::HttpMethod;
+use crate::array_sampling::ArraySample;
+use crate::drift_types::{DriftType, Severity};
+use crate::error::ValidationError;
+use crate::exchange::ResponseOrigin;
+use crate::validation_helpers::format_drift_error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(not(feature = "wasm"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+// `std` has no clock on `wasm32-unknown-unknown`
+#[cfg(feature = "wasm")]
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// Version of the serialized event format, written as `schema_version`
+///
+/// Raised when a field changes meaning or is removed; added fields keep
+/// the version, so consumers should ignore fields they don't know.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of serialized events at [`EVENT_SCHEMA_VERSION`]
+pub const DRIFT_EVENT_SCHEMA: &str = include_str!("../schemas/drift-event.v1.json");
+
+/// Opaque caller-supplied labels (tenant ID, feature flags, deploy version, ...)
+/// attached verbatim to every event from one validation call
+pub type EventContext = BTreeMap<String, String>;
+
+/// A single drift finding produced while validating observed traffic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftEvent {
+    /// Version of the format the event was serialized in; events written
+    /// before the format was versioned read as version 1
+    #[serde(default = "first_schema_version")]
+    pub schema_version: u32,
+    pub drift_type: DriftType,
+    pub severity: Severity,
+    /// Where in the exchange the drift was found (e.g. `body/users/0/email`, `limit`)
+    pub location: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    /// Concrete request path as observed in traffic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Spec path template the request was routed to (e.g. `/users/{userId}`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_template: Option<String>,
+    /// The spec's `operationId` for the routed operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    /// The spec's `tags` for the routed operation, used to attribute drift to owning teams
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The spec's `summary` of the routed operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_summary: Option<String>,
+    /// Team owning the drift, resolved from the monitor's ownership mapping
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Which contract the drift was found against, when an exchange is checked against several
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// The offending value, truncated to a bounded size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub observed: Option<Value>,
+    /// Likely intended field names for rename-style drift (e.g. "`customer_id`: did you mean `customerId`?")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+    /// Set when the body's large arrays were sampled rather than validated in full
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub array_sample: Option<ArraySample>,
+    /// Set when the body was cut off by the capture source and only its prefix was validated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_truncated_capture: bool,
+    /// Set when the body was over the size limit and only checked down to this nesting depth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validated_depth: Option<usize>,
+    /// Who produced the response, when known; gateway-origin findings are infrastructure noise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_origin: Option<ResponseOrigin>,
+    /// Caller-supplied context for the validation call that produced the event
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: EventContext,
+    /// Milliseconds since the Unix epoch when the drift was observed
+    pub timestamp_ms: u64,
+}
+
+impl DriftEvent {
+    /// Creates an event without operation details, timestamped now
+    pub fn new(drift_type: DriftType, location: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            schema_version: EVENT_SCHEMA_VERSION,
+            drift_type,
+            severity: drift_type.severity(),
+            location: location.into(),
+            message: message.into(),
+            method: None,
+            path: None,
+            path_template: None,
+            operation_id: None,
+            tags: Vec::new(),
+            operation_summary: None,
+            owner: None,
+            contract: None,
+            status_code: None,
+            observed: None,
+            suggestions: Vec::new(),
+            array_sample: None,
+            from_truncated_capture: false,
+            validated_depth: None,
+            response_origin: None,
+            context: EventContext::new(),
+            timestamp_ms: now_ms(),
+        }
+    }
+
+    /// Serializes the event as compact JSON, in the current format version
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string(self).map_err(|e| ValidationError::EventFormat(e.to_string()))
+    }
+
+    /// Parses an event serialized in this format version or an earlier one
+    pub fn from_json(json: &str) -> Result<Self, ValidationError> {
+        let event: Self = serde_json::from_str(json).map_err(|e| ValidationError::EventFormat(e.to_string()))?;
+        if event.schema_version > EVENT_SCHEMA_VERSION {
+            return Err(ValidationError::EventFormat(format!(
+                "schema version {} is newer than the supported {}",
+                event.schema_version, EVENT_SCHEMA_VERSION
+            )));
+        }
+        Ok(event)
+    }
+
+    /// Attaches the operation the drift was observed on
+    pub fn with_operation(mut self, method: HttpMethod, path: &str, path_template: &str) -> Self {
+        self.method = Some(method);
+        self.path = Some(path.to_string());
+        self.path_template = Some(path_template.to_string());
+        self
+    }
+
+    /// Attaches the message channel the drift was observed on, in place of an
+    /// HTTP operation; messages carry no method
+    pub fn with_channel(mut self, channel: &str, channel_template: &str) -> Self {
+        self.path = Some(channel.to_string());
+        self.path_template = Some(channel_template.to_string());
+        self
+    }
+
+    /// Attaches the spec's `operationId` for the routed operation
+    pub fn with_operation_id(mut self, operation_id: Option<&str>) -> Self {
+        self.operation_id = operation_id.map(str::to_string);
+        self
+    }
+
+    /// Attaches the spec's `tags` for the routed operation
+    pub fn with_tags(mut self, tags: &[String]) -> Self {
+        self.tags = tags.to_vec();
+        self
+    }
+
+    /// Attaches the spec's `summary` of the routed operation
+    pub fn with_operation_summary(mut self, summary: Option<&str>) -> Self {
+        self.operation_summary = summary.map(str::to_string);
+        self
+    }
+
+    /// Records which contract the drift was found against
+    pub fn with_contract(mut self, contract: impl Into<String>) -> Self {
+        self.contract = Some(contract.into());
+        self
+    }
+
+    /// Attaches the offending value, truncated to a bounded size
+    pub fn with_observed(mut self, value: &Value) -> Self {
+        self.observed = Some(truncate_sample(value, SAMPLE_MAX_DEPTH));
+        self
+    }
+
+    /// Attaches near-miss field name suggestions, appending them to the message
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        if !suggestions.is_empty() {
+            self.message = format!("{}; {}", self.message, suggestions.join("; "));
+            self.suggestions = suggestions;
+        }
+        self
+    }
+
+    /// Records that the drift was found in a sample of the body's arrays
+    pub fn with_array_sample(mut self, sample: ArraySample) -> Self {
+        self.array_sample = Some(sample);
+        self
+    }
+
+    /// Records who produced the response the drift was observed on
+    pub fn with_response_origin(mut self, origin: ResponseOrigin) -> Self {
+        self.response_origin = Some(origin);
+        self
+    }
+
+    /// Attaches caller-supplied context
+    ///
+    /// Entries already present are kept, except those under a key the
+    /// caller also supplies, whose value the caller's replaces.
+    pub fn with_context(mut self, context: &EventContext) -> Self {
+        self.context
+            .extend(context.iter().map(|(key, value)| (key.clone(), value.clone())));
+        self
+    }
+
+    /// Attaches the response status code the drift was observed on
+    pub fn with_status(mut self, status_code: u16) -> Self {
+        self.status_code = Some(status_code);
+        self
+    }
+}
+
+impl fmt::Display for DriftEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_drift_error(self.drift_type, &self.location, &self.message))
+    }
+}
+
+/// Nesting depth kept when capturing offending values
+const SAMPLE_MAX_DEPTH: usize = 4;
+/// Array items / object members kept per level when capturing offending values
+const SAMPLE_MAX_ENTRIES: usize = 10;
+/// Characters kept per string when capturing offending values
+const SAMPLE_MAX_STRING: usize = 200;
+
+/// Copies a value, cutting off deep nesting, long collections, and long strings
+pub(crate) fn truncate_sample(value: &Value, depth: usize) -> Value {
+    match value {
+        Value::String(s) if s.chars().count() > SAMPLE_MAX_STRING => {
+            Value::String(format!("{}…", s.chars().take(SAMPLE_MAX_STRING).collect::<String>()))
+        }
+        Value::Array(_) | Value::Object(_) if depth == 0 => Value::String("…".to_string()),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .take(SAMPLE_MAX_ENTRIES)
+                .map(|item| truncate_sample(item, depth - 1))
+                .collect(),
+        ),
+        Value::Object(members) => Value::Object(
+            members
+                .iter()
+                .take(SAMPLE_MAX_ENTRIES)
+                .map(|(key, member)| (key.clone(), truncate_sample(member, depth - 1)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn first_schema_version() -> u32 {
+    1
+}
+
+/// Current time in milliseconds since the Unix epoch
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD` UTC date of a millisecond Unix timestamp
+#[cfg(feature = "monitor")]
+pub(crate) fn utc_date(timestamp_ms: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = (timestamp_ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
@@ -1,6 +1,9 @@
 use jsonschema::error::ValidationErrorKind;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum DriftType {
     ParameterTypeMismatch,
     RequestBodyTypeMismatch,
@@ -17,9 +20,188 @@ pub enum DriftType {
     ParameterAnyOfNoMatch,
     RequestBodyAnyOfNoMatch,
     ResponseBodyAnyOfNoMatch,
+    ParameterFormatViolation,
+    RequestBodyFormatViolation,
+    ResponseBodyFormatViolation,
+    ParameterRangeViolation,
+    RequestBodyRangeViolation,
+    ResponseBodyRangeViolation,
+    ParameterLengthViolation,
+    RequestBodyLengthViolation,
+    ResponseBodyLengthViolation,
+    ParameterPatternViolation,
+    RequestBodyPatternViolation,
+    ResponseBodyPatternViolation,
+    ParameterArrayConstraintViolation,
+    RequestBodyArrayConstraintViolation,
+    ResponseBodyArrayConstraintViolation,
+    ParameterObjectConstraintViolation,
+    RequestBodyObjectConstraintViolation,
+    ResponseBodyObjectConstraintViolation,
+    ParameterOneOfMultipleMatch,
+    RequestBodyOneOfMultipleMatch,
+    ResponseBodyOneOfMultipleMatch,
+    ParameterConstraintViolation,
+    RequestBodyConstraintViolation,
+    ResponseBodyConstraintViolation,
+    RequestBodyUndocumentedField,
+    ResponseBodyUndocumentedField,
+    RequestBodyMalformedJson,
+    ResponseBodyMalformedJson,
+    /// A body whose `Content-Encoding` couldn't be undone, so it went unvalidated
+    RequestBodyUndecodable,
+    ResponseBodyUndecodable,
+    RateLimitHeaderMissing,
+    RateLimitHeaderInvalid,
+    RateLimitHeaderInconsistent,
+    IdempotencyKeyMissing,
+    IdempotencyReplayMismatch,
+    SecurityRequirementDrift,
+    GatewayRequestTransformDrift,
+    GatewayResponseTransformDrift,
+    ResponseContentTypeDrift,
+    UndocumentedParameter,
+    /// Traffic arrived under a path prefix no server or configured base path declares
+    UndeclaredBasePath,
+    /// A request used an operation, parameter or field the spec marks deprecated
+    DeprecatedUsage,
+    /// A gRPC call to a method no loaded service declares
+    GrpcUnknownMethod,
+    /// A gRPC request message that doesn't decode
+    GrpcRequestMalformed,
+    GrpcResponseMalformed,
+    /// A gRPC message field the message type doesn't declare
+    GrpcRequestUnknownField,
+    GrpcResponseUnknownField,
+    /// A gRPC message field whose wire or JSON type differs from its declaration
+    GrpcRequestTypeMismatch,
+    GrpcResponseTypeMismatch,
+    /// A gRPC enum field holding a value the enum doesn't declare
+    GrpcRequestEnumViolation,
+    GrpcResponseEnumViolation,
+    /// A GraphQL request whose query doesn't parse or names no operation to run
+    GraphQlInvalidQuery,
+    /// A GraphQL query selecting a field its type doesn't declare
+    GraphQlUndeclaredField,
+    GraphQlUnknownArgument,
+    GraphQlUnknownType,
+    /// A GraphQL response holding a field the query didn't select or the schema doesn't declare
+    GraphQlResponseUndeclaredField,
+    GraphQlResponseTypeMismatch,
+    /// A message payload, validated against its AsyncAPI schema, that doesn't match it
+    MessagePayloadTypeMismatch,
+    MessagePayloadMissingRequired,
+    MessagePayloadEnumViolation,
+    MessagePayloadOneOfNoMatch,
+    MessagePayloadAnyOfNoMatch,
+    MessagePayloadFormatViolation,
+    MessagePayloadRangeViolation,
+    MessagePayloadLengthViolation,
+    MessagePayloadPatternViolation,
+    MessagePayloadArrayConstraintViolation,
+    MessagePayloadObjectConstraintViolation,
+    MessagePayloadOneOfMultipleMatch,
+    MessagePayloadConstraintViolation,
+    MessagePayloadUndocumentedField,
+    /// A message on a channel no AsyncAPI document declares
+    MessageUnknownChannel,
+    /// A message of a type its channel doesn't declare
+    MessageUnknownType,
 }
 
 impl DriftType {
+    /// Every drift type, in declaration order
+    pub const ALL: &'static [DriftType] = &[
+        Self::ParameterTypeMismatch,
+        Self::RequestBodyTypeMismatch,
+        Self::ResponseBodyTypeMismatch,
+        Self::ParameterMissingRequired,
+        Self::RequestBodyMissingRequired,
+        Self::ResponseBodyMissingRequired,
+        Self::ParameterEnumViolation,
+        Self::RequestBodyEnumViolation,
+        Self::ResponseBodyEnumViolation,
+        Self::ParameterOneOfNoMatch,
+        Self::RequestBodyOneOfNoMatch,
+        Self::ResponseBodyOneOfNoMatch,
+        Self::ParameterAnyOfNoMatch,
+        Self::RequestBodyAnyOfNoMatch,
+        Self::ResponseBodyAnyOfNoMatch,
+        Self::ParameterFormatViolation,
+        Self::RequestBodyFormatViolation,
+        Self::ResponseBodyFormatViolation,
+        Self::ParameterRangeViolation,
+        Self::RequestBodyRangeViolation,
+        Self::ResponseBodyRangeViolation,
+        Self::ParameterLengthViolation,
+        Self::RequestBodyLengthViolation,
+        Self::ResponseBodyLengthViolation,
+        Self::ParameterPatternViolation,
+        Self::RequestBodyPatternViolation,
+        Self::ResponseBodyPatternViolation,
+        Self::ParameterArrayConstraintViolation,
+        Self::RequestBodyArrayConstraintViolation,
+        Self::ResponseBodyArrayConstraintViolation,
+        Self::ParameterObjectConstraintViolation,
+        Self::RequestBodyObjectConstraintViolation,
+        Self::ResponseBodyObjectConstraintViolation,
+        Self::ParameterOneOfMultipleMatch,
+        Self::RequestBodyOneOfMultipleMatch,
+        Self::ResponseBodyOneOfMultipleMatch,
+        Self::ParameterConstraintViolation,
+        Self::RequestBodyConstraintViolation,
+        Self::ResponseBodyConstraintViolation,
+        Self::RequestBodyUndocumentedField,
+        Self::ResponseBodyUndocumentedField,
+        Self::RequestBodyMalformedJson,
+        Self::ResponseBodyMalformedJson,
+        Self::RequestBodyUndecodable,
+        Self::ResponseBodyUndecodable,
+        Self::RateLimitHeaderMissing,
+        Self::RateLimitHeaderInvalid,
+        Self::RateLimitHeaderInconsistent,
+        Self::IdempotencyKeyMissing,
+        Self::IdempotencyReplayMismatch,
+        Self::SecurityRequirementDrift,
+        Self::GatewayRequestTransformDrift,
+        Self::GatewayResponseTransformDrift,
+        Self::ResponseContentTypeDrift,
+        Self::UndocumentedParameter,
+        Self::UndeclaredBasePath,
+        Self::DeprecatedUsage,
+        Self::GrpcUnknownMethod,
+        Self::GrpcRequestMalformed,
+        Self::GrpcResponseMalformed,
+        Self::GrpcRequestUnknownField,
+        Self::GrpcResponseUnknownField,
+        Self::GrpcRequestTypeMismatch,
+        Self::GrpcResponseTypeMismatch,
+        Self::GrpcRequestEnumViolation,
+        Self::GrpcResponseEnumViolation,
+        Self::GraphQlInvalidQuery,
+        Self::GraphQlUndeclaredField,
+        Self::GraphQlUnknownArgument,
+        Self::GraphQlUnknownType,
+        Self::GraphQlResponseUndeclaredField,
+        Self::GraphQlResponseTypeMismatch,
+        Self::MessagePayloadTypeMismatch,
+        Self::MessagePayloadMissingRequired,
+        Self::MessagePayloadEnumViolation,
+        Self::MessagePayloadOneOfNoMatch,
+        Self::MessagePayloadAnyOfNoMatch,
+        Self::MessagePayloadFormatViolation,
+        Self::MessagePayloadRangeViolation,
+        Self::MessagePayloadLengthViolation,
+        Self::MessagePayloadPatternViolation,
+        Self::MessagePayloadArrayConstraintViolation,
+        Self::MessagePayloadObjectConstraintViolation,
+        Self::MessagePayloadOneOfMultipleMatch,
+        Self::MessagePayloadConstraintViolation,
+        Self::MessagePayloadUndocumentedField,
+        Self::MessageUnknownChannel,
+        Self::MessageUnknownType,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::ParameterTypeMismatch => "PARAMETER_TYPE_MISMATCH",
@@ -37,6 +219,198 @@ impl DriftType {
             Self::ParameterAnyOfNoMatch => "PARAMETER_ANYOF_NO_MATCH",
             Self::RequestBodyAnyOfNoMatch => "REQUEST_BODY_ANYOF_NO_MATCH",
             Self::ResponseBodyAnyOfNoMatch => "RESPONSE_BODY_ANYOF_NO_MATCH",
+            Self::ParameterFormatViolation => "PARAMETER_FORMAT_VIOLATION",
+            Self::RequestBodyFormatViolation => "REQUEST_BODY_FORMAT_VIOLATION",
+            Self::ResponseBodyFormatViolation => "RESPONSE_BODY_FORMAT_VIOLATION",
+            Self::ParameterRangeViolation => "PARAMETER_RANGE_VIOLATION",
+            Self::RequestBodyRangeViolation => "REQUEST_BODY_RANGE_VIOLATION",
+            Self::ResponseBodyRangeViolation => "RESPONSE_BODY_RANGE_VIOLATION",
+            Self::ParameterLengthViolation => "PARAMETER_LENGTH_VIOLATION",
+            Self::RequestBodyLengthViolation => "REQUEST_BODY_LENGTH_VIOLATION",
+            Self::ResponseBodyLengthViolation => "RESPONSE_BODY_LENGTH_VIOLATION",
+            Self::ParameterPatternViolation => "PARAMETER_PATTERN_VIOLATION",
+            Self::RequestBodyPatternViolation => "REQUEST_BODY_PATTERN_VIOLATION",
+            Self::ResponseBodyPatternViolation => "RESPONSE_BODY_PATTERN_VIOLATION",
+            Self::ParameterArrayConstraintViolation => "PARAMETER_ARRAY_CONSTRAINT_VIOLATION",
+            Self::RequestBodyArrayConstraintViolation => "REQUEST_BODY_ARRAY_CONSTRAINT_VIOLATION",
+            Self::ResponseBodyArrayConstraintViolation => "RESPONSE_BODY_ARRAY_CONSTRAINT_VIOLATION",
+            Self::ParameterObjectConstraintViolation => "PARAMETER_OBJECT_CONSTRAINT_VIOLATION",
+            Self::RequestBodyObjectConstraintViolation => "REQUEST_BODY_OBJECT_CONSTRAINT_VIOLATION",
+            Self::ResponseBodyObjectConstraintViolation => "RESPONSE_BODY_OBJECT_CONSTRAINT_VIOLATION",
+            Self::ParameterOneOfMultipleMatch => "PARAMETER_ONEOF_MULTIPLE_MATCH",
+            Self::RequestBodyOneOfMultipleMatch => "REQUEST_BODY_ONEOF_MULTIPLE_MATCH",
+            Self::ResponseBodyOneOfMultipleMatch => "RESPONSE_BODY_ONEOF_MULTIPLE_MATCH",
+            Self::ParameterConstraintViolation => "PARAMETER_CONSTRAINT_VIOLATION",
+            Self::RequestBodyConstraintViolation => "REQUEST_BODY_CONSTRAINT_VIOLATION",
+            Self::ResponseBodyConstraintViolation => "RESPONSE_BODY_CONSTRAINT_VIOLATION",
+            Self::RequestBodyUndocumentedField => "REQUEST_BODY_UNDOCUMENTED_FIELD",
+            Self::ResponseBodyUndocumentedField => "RESPONSE_BODY_UNDOCUMENTED_FIELD",
+            Self::RequestBodyMalformedJson => "REQUEST_BODY_MALFORMED_JSON",
+            Self::ResponseBodyMalformedJson => "RESPONSE_BODY_MALFORMED_JSON",
+            Self::RequestBodyUndecodable => "REQUEST_BODY_UNDECODABLE",
+            Self::ResponseBodyUndecodable => "RESPONSE_BODY_UNDECODABLE",
+            Self::RateLimitHeaderMissing => "RATE_LIMIT_HEADER_MISSING",
+            Self::RateLimitHeaderInvalid => "RATE_LIMIT_HEADER_INVALID",
+            Self::RateLimitHeaderInconsistent => "RATE_LIMIT_HEADER_INCONSISTENT",
+            Self::IdempotencyKeyMissing => "IDEMPOTENCY_KEY_MISSING",
+            Self::IdempotencyReplayMismatch => "IDEMPOTENCY_REPLAY_MISMATCH",
+            Self::SecurityRequirementDrift => "SECURITY_REQUIREMENT_DRIFT",
+            Self::GatewayRequestTransformDrift => "GATEWAY_REQUEST_TRANSFORM_DRIFT",
+            Self::GatewayResponseTransformDrift => "GATEWAY_RESPONSE_TRANSFORM_DRIFT",
+            Self::ResponseContentTypeDrift => "RESPONSE_CONTENT_TYPE_DRIFT",
+            Self::UndocumentedParameter => "UNDOCUMENTED_PARAMETER",
+            Self::UndeclaredBasePath => "UNDECLARED_BASE_PATH",
+            Self::DeprecatedUsage => "DEPRECATED_USAGE",
+            Self::GrpcUnknownMethod => "GRPC_UNKNOWN_METHOD",
+            Self::GrpcRequestMalformed => "GRPC_REQUEST_MALFORMED",
+            Self::GrpcResponseMalformed => "GRPC_RESPONSE_MALFORMED",
+            Self::GrpcRequestUnknownField => "GRPC_REQUEST_UNKNOWN_FIELD",
+            Self::GrpcResponseUnknownField => "GRPC_RESPONSE_UNKNOWN_FIELD",
+            Self::GrpcRequestTypeMismatch => "GRPC_REQUEST_TYPE_MISMATCH",
+            Self::GrpcResponseTypeMismatch => "GRPC_RESPONSE_TYPE_MISMATCH",
+            Self::GrpcRequestEnumViolation => "GRPC_REQUEST_ENUM_VIOLATION",
+            Self::GrpcResponseEnumViolation => "GRPC_RESPONSE_ENUM_VIOLATION",
+            Self::GraphQlInvalidQuery => "GRAPHQL_INVALID_QUERY",
+            Self::GraphQlUndeclaredField => "GRAPHQL_UNDECLARED_FIELD",
+            Self::GraphQlUnknownArgument => "GRAPHQL_UNKNOWN_ARGUMENT",
+            Self::GraphQlUnknownType => "GRAPHQL_UNKNOWN_TYPE",
+            Self::GraphQlResponseUndeclaredField => "GRAPHQL_RESPONSE_UNDECLARED_FIELD",
+            Self::GraphQlResponseTypeMismatch => "GRAPHQL_RESPONSE_TYPE_MISMATCH",
+            Self::MessagePayloadTypeMismatch => "MESSAGE_PAYLOAD_TYPE_MISMATCH",
+            Self::MessagePayloadMissingRequired => "MESSAGE_PAYLOAD_MISSING_REQUIRED",
+            Self::MessagePayloadEnumViolation => "MESSAGE_PAYLOAD_ENUM_VIOLATION",
+            Self::MessagePayloadOneOfNoMatch => "MESSAGE_PAYLOAD_ONEOF_NO_MATCH",
+            Self::MessagePayloadAnyOfNoMatch => "MESSAGE_PAYLOAD_ANYOF_NO_MATCH",
+            Self::MessagePayloadFormatViolation => "MESSAGE_PAYLOAD_FORMAT_VIOLATION",
+            Self::MessagePayloadRangeViolation => "MESSAGE_PAYLOAD_RANGE_VIOLATION",
+            Self::MessagePayloadLengthViolation => "MESSAGE_PAYLOAD_LENGTH_VIOLATION",
+            Self::MessagePayloadPatternViolation => "MESSAGE_PAYLOAD_PATTERN_VIOLATION",
+            Self::MessagePayloadArrayConstraintViolation => "MESSAGE_PAYLOAD_ARRAY_CONSTRAINT_VIOLATION",
+            Self::MessagePayloadObjectConstraintViolation => "MESSAGE_PAYLOAD_OBJECT_CONSTRAINT_VIOLATION",
+            Self::MessagePayloadOneOfMultipleMatch => "MESSAGE_PAYLOAD_ONEOF_MULTIPLE_MATCH",
+            Self::MessagePayloadConstraintViolation => "MESSAGE_PAYLOAD_CONSTRAINT_VIOLATION",
+            Self::MessagePayloadUndocumentedField => "MESSAGE_PAYLOAD_UNDOCUMENTED_FIELD",
+            Self::MessageUnknownChannel => "MESSAGE_UNKNOWN_CHANNEL",
+            Self::MessageUnknownType => "MESSAGE_UNKNOWN_TYPE",
+        }
+    }
+
+    /// Whether the drift was found in the response rather than the request
+    pub fn concerns_response(&self) -> bool {
+        self.as_str().starts_with("RESPONSE_")
+            || self.as_str().starts_with("GRPC_RESPONSE_")
+            || self.as_str().starts_with("GRAPHQL_RESPONSE_")
+            || matches!(
+                self,
+                Self::RateLimitHeaderMissing
+                    | Self::RateLimitHeaderInvalid
+                    | Self::RateLimitHeaderInconsistent
+                    | Self::IdempotencyReplayMismatch
+                    | Self::GatewayResponseTransformDrift
+            )
+    }
+}
+
+impl DriftType {
+    /// Default severity of this drift type
+    ///
+    /// Response drift breaks consumers relying on the contract; request drift
+    /// means clients deviate from it, which servers usually tolerate.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::ResponseBodyTypeMismatch
+            | Self::ResponseBodyMissingRequired
+            | Self::ResponseBodyEnumViolation
+            | Self::ResponseBodyOneOfNoMatch
+            | Self::ResponseBodyAnyOfNoMatch
+            | Self::ResponseBodyFormatViolation
+            | Self::ResponseBodyRangeViolation
+            | Self::ResponseBodyLengthViolation
+            | Self::ResponseBodyPatternViolation
+            | Self::ResponseBodyArrayConstraintViolation
+            | Self::ResponseBodyObjectConstraintViolation
+            | Self::ResponseBodyOneOfMultipleMatch
+            | Self::ResponseBodyConstraintViolation
+            | Self::ResponseBodyMalformedJson
+            | Self::IdempotencyReplayMismatch
+            | Self::GatewayResponseTransformDrift
+            | Self::ResponseContentTypeDrift
+            | Self::GrpcResponseMalformed
+            | Self::GrpcResponseTypeMismatch
+            | Self::GrpcResponseEnumViolation
+            | Self::GraphQlResponseTypeMismatch
+            | Self::MessagePayloadTypeMismatch
+            | Self::MessagePayloadMissingRequired
+            | Self::MessagePayloadEnumViolation
+            | Self::MessagePayloadOneOfNoMatch
+            | Self::MessagePayloadAnyOfNoMatch
+            | Self::MessagePayloadFormatViolation
+            | Self::MessagePayloadRangeViolation
+            | Self::MessagePayloadLengthViolation
+            | Self::MessagePayloadPatternViolation
+            | Self::MessagePayloadArrayConstraintViolation
+            | Self::MessagePayloadObjectConstraintViolation
+            | Self::MessagePayloadOneOfMultipleMatch
+            | Self::MessagePayloadConstraintViolation => Severity::Breaking,
+            // Usage the spec still allows, tracked ahead of sunsetting it
+            Self::DeprecatedUsage => Severity::Info,
+            // A gap in monitoring rather than a deviation from the contract
+            Self::RequestBodyUndecodable | Self::ResponseBodyUndecodable => Severity::Info,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl Serialize for DriftType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl FromStr for DriftType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.iter().copied().find(|t| t.as_str() == s).ok_or(())
+    }
+}
+
+impl<'de> Deserialize<'de> for DriftType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| de::Error::custom(format!("unknown drift type: {}", s)))
+    }
+}
+
+/// How serious a drift finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Breaking,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Breaking => "breaking",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "warning" => Ok(Self::Warning),
+            "breaking" => Ok(Self::Breaking),
+            _ => Err(()),
         }
     }
 }
@@ -46,6 +420,7 @@ pub enum ValidationContext {
     Parameter,
     RequestBody,
     ResponseBody,
+    MessagePayload,
 }
 
 /// Maps ValidationErrorKind to DriftType based on context
@@ -57,28 +432,114 @@ pub fn map_to_drift_type(kind: &ValidationErrorKind, context: ValidationContext)
             Parameter => DriftType::ParameterTypeMismatch,
             RequestBody => DriftType::RequestBodyTypeMismatch,
             ResponseBody => DriftType::ResponseBodyTypeMismatch,
+            MessagePayload => DriftType::MessagePayloadTypeMismatch,
         }),
         ValidationErrorKind::Required { .. } => Some(match context {
             Parameter => DriftType::ParameterMissingRequired,
             RequestBody => DriftType::RequestBodyMissingRequired,
             ResponseBody => DriftType::ResponseBodyMissingRequired,
+            MessagePayload => DriftType::MessagePayloadMissingRequired,
         }),
         ValidationErrorKind::Enum { .. } => Some(match context {
             Parameter => DriftType::ParameterEnumViolation,
             RequestBody => DriftType::RequestBodyEnumViolation,
             ResponseBody => DriftType::ResponseBodyEnumViolation,
+            MessagePayload => DriftType::MessagePayloadEnumViolation,
         }),
         ValidationErrorKind::OneOfNotValid { .. } => Some(match context {
             Parameter => DriftType::ParameterOneOfNoMatch,
             RequestBody => DriftType::RequestBodyOneOfNoMatch,
             ResponseBody => DriftType::ResponseBodyOneOfNoMatch,
+            MessagePayload => DriftType::MessagePayloadOneOfNoMatch,
         }),
         ValidationErrorKind::AnyOf { .. } => Some(match context {
             Parameter => DriftType::ParameterAnyOfNoMatch,
             RequestBody => DriftType::RequestBodyAnyOfNoMatch,
             ResponseBody => DriftType::ResponseBodyAnyOfNoMatch,
+            MessagePayload => DriftType::MessagePayloadAnyOfNoMatch,
+        }),
+        ValidationErrorKind::Format { .. } => Some(match context {
+            Parameter => DriftType::ParameterFormatViolation,
+            RequestBody => DriftType::RequestBodyFormatViolation,
+            ResponseBody => DriftType::ResponseBodyFormatViolation,
+            MessagePayload => DriftType::MessagePayloadFormatViolation,
+        }),
+        ValidationErrorKind::Constant { .. } => Some(match context {
+            Parameter => DriftType::ParameterEnumViolation,
+            RequestBody => DriftType::RequestBodyEnumViolation,
+            ResponseBody => DriftType::ResponseBodyEnumViolation,
+            MessagePayload => DriftType::MessagePayloadEnumViolation,
+        }),
+        ValidationErrorKind::OneOfMultipleValid { .. } => Some(match context {
+            Parameter => DriftType::ParameterOneOfMultipleMatch,
+            RequestBody => DriftType::RequestBodyOneOfMultipleMatch,
+            ResponseBody => DriftType::ResponseBodyOneOfMultipleMatch,
+            MessagePayload => DriftType::MessagePayloadOneOfMultipleMatch,
+        }),
+        ValidationErrorKind::Minimum { .. }
+        | ValidationErrorKind::Maximum { .. }
+        | ValidationErrorKind::ExclusiveMinimum { .. }
+        | ValidationErrorKind::ExclusiveMaximum { .. }
+        | ValidationErrorKind::MultipleOf { .. } => Some(match context {
+            Parameter => DriftType::ParameterRangeViolation,
+            RequestBody => DriftType::RequestBodyRangeViolation,
+            ResponseBody => DriftType::ResponseBodyRangeViolation,
+            MessagePayload => DriftType::MessagePayloadRangeViolation,
+        }),
+        ValidationErrorKind::MinLength { .. } | ValidationErrorKind::MaxLength { .. } => Some(match context {
+            Parameter => DriftType::ParameterLengthViolation,
+            RequestBody => DriftType::RequestBodyLengthViolation,
+            ResponseBody => DriftType::ResponseBodyLengthViolation,
+            MessagePayload => DriftType::MessagePayloadLengthViolation,
         }),
-        _ => None,
+        ValidationErrorKind::Pattern { .. } => Some(match context {
+            Parameter => DriftType::ParameterPatternViolation,
+            RequestBody => DriftType::RequestBodyPatternViolation,
+            ResponseBody => DriftType::ResponseBodyPatternViolation,
+            MessagePayload => DriftType::MessagePayloadPatternViolation,
+        }),
+        ValidationErrorKind::MinItems { .. }
+        | ValidationErrorKind::MaxItems { .. }
+        | ValidationErrorKind::UniqueItems
+        | ValidationErrorKind::Contains
+        | ValidationErrorKind::AdditionalItems { .. }
+        | ValidationErrorKind::UnevaluatedItems { .. } => Some(match context {
+            Parameter => DriftType::ParameterArrayConstraintViolation,
+            RequestBody => DriftType::RequestBodyArrayConstraintViolation,
+            ResponseBody => DriftType::ResponseBodyArrayConstraintViolation,
+            MessagePayload => DriftType::MessagePayloadArrayConstraintViolation,
+        }),
+        ValidationErrorKind::MinProperties { .. }
+        | ValidationErrorKind::MaxProperties { .. }
+        | ValidationErrorKind::PropertyNames { .. } => Some(match context {
+            Parameter => DriftType::ParameterObjectConstraintViolation,
+            RequestBody => DriftType::RequestBodyObjectConstraintViolation,
+            ResponseBody => DriftType::ResponseBodyObjectConstraintViolation,
+            MessagePayload => DriftType::MessagePayloadObjectConstraintViolation,
+        }),
+        ValidationErrorKind::Not { .. }
+        | ValidationErrorKind::FalseSchema
+        | ValidationErrorKind::ContentEncoding { .. }
+        | ValidationErrorKind::ContentMediaType { .. }
+        | ValidationErrorKind::Custom { .. } => Some(match context {
+            Parameter => DriftType::ParameterConstraintViolation,
+            RequestBody => DriftType::RequestBodyConstraintViolation,
+            ResponseBody => DriftType::ResponseBodyConstraintViolation,
+            MessagePayload => DriftType::MessagePayloadConstraintViolation,
+        }),
+        // Only reported where a schema closes objects with `additionalProperties: false`
+        ValidationErrorKind::AdditionalProperties { .. } | ValidationErrorKind::UnevaluatedProperties { .. } => {
+            match context {
+                Parameter => None,
+                RequestBody => Some(DriftType::RequestBodyUndocumentedField),
+                ResponseBody => Some(DriftType::ResponseBodyUndocumentedField),
+                MessagePayload => Some(DriftType::MessagePayloadUndocumentedField),
+            }
+        }
+        // Failures of the validator itself rather than of the instance
+        ValidationErrorKind::Referencing(_)
+        | ValidationErrorKind::BacktrackLimitExceeded { .. }
+        | ValidationErrorKind::FromUtf8 { .. } => None,
     }
 }
 
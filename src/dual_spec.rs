@@ -0,0 +1,109 @@
+//! Validation of gateway-transformed traffic against two contracts
+//!
+//! A gateway publishing an external contract often maps requests and
+//! responses onto a different internal one. Checking the client side of an
+//! exchange against the external spec and the upstream side against the
+//! internal spec shows whether drift was introduced by the client, the
+//! service, or the gateway's mapping policies in between.
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::spec::{build_api_validator_with_options, BuildOptions};
+use openapiv3::OpenAPI;
+
+/// [`DriftEvent::contract`] of events found against the published contract
+pub const EXTERNAL_CONTRACT: &str = "external";
+/// [`DriftEvent::contract`] of events found against the upstream contract
+pub const INTERNAL_CONTRACT: &str = "internal";
+
+/// Validates both sides of a gateway against their own contracts
+pub struct DualSpecValidator {
+    external: ApiValidator,
+    internal: ApiValidator,
+}
+
+impl DualSpecValidator {
+    /// Pairs the validator of the published contract with that of the upstream contract
+    pub fn new(external: ApiValidator, internal: ApiValidator) -> Self {
+        Self { external, internal }
+    }
+
+    /// Builds both validators with the same options
+    pub fn from_specs(external: &OpenAPI, internal: &OpenAPI, options: &BuildOptions) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            build_api_validator_with_options(external, options)?,
+            build_api_validator_with_options(internal, options)?,
+        ))
+    }
+
+    /// Validates an exchange as seen by the client and as forwarded to the upstream
+    ///
+    /// Events are tagged with the contract they were found against. When one
+    /// side of the gateway conforms and the other doesn't, the gateway's
+    /// mapping introduced the drift, which is reported as an additional
+    /// `GATEWAY_REQUEST_TRANSFORM_DRIFT` (client request fine, forwarded
+    /// request not) or `GATEWAY_RESPONSE_TRANSFORM_DRIFT` (upstream response
+    /// fine, response returned to the client not).
+    pub fn validate(&self, client: &Exchange, upstream: &Exchange) -> Result<Vec<DriftEvent>, ValidationError> {
+        let external: Vec<DriftEvent> = self
+            .external
+            .validate_exchange(client)?
+            .into_iter()
+            .map(|event| event.with_contract(EXTERNAL_CONTRACT))
+            .collect();
+        let internal: Vec<DriftEvent> = self
+            .internal
+            .validate_exchange(upstream)?
+            .into_iter()
+            .map(|event| event.with_contract(INTERNAL_CONTRACT))
+            .collect();
+
+        let mut transform = Vec::new();
+        let (external_request, external_response): (Vec<&DriftEvent>, Vec<&DriftEvent>) =
+            external.iter().partition(|event| !event.drift_type.concerns_response());
+        let (internal_request, internal_response): (Vec<&DriftEvent>, Vec<&DriftEvent>) =
+            internal.iter().partition(|event| !event.drift_type.concerns_response());
+
+        if external_request.is_empty() && !internal_request.is_empty() {
+            transform.push(transform_event(
+                DriftType::GatewayRequestTransformDrift,
+                "gateway/request",
+                "Client request conforms to the external contract but the request forwarded upstream violates the internal contract",
+                &internal_request,
+            ));
+        }
+        if internal_response.is_empty() && !external_response.is_empty() {
+            transform.push(transform_event(
+                DriftType::GatewayResponseTransformDrift,
+                "gateway/response",
+                "Upstream response conforms to the internal contract but the response returned to the client violates the external contract",
+                &external_response,
+            ));
+        }
+
+        Ok(external.into_iter().chain(internal).chain(transform).collect())
+    }
+}
+
+/// Summarizes the findings on the non-conforming side of the gateway
+fn transform_event(drift_type: DriftType, location: &str, summary: &str, causes: &[&DriftEvent]) -> DriftEvent {
+    let first = causes[0];
+    let listed: Vec<String> = causes
+        .iter()
+        .map(|cause| format!("{} at {}", cause.drift_type.as_str(), cause.location))
+        .collect();
+    let mut event = DriftEvent::new(drift_type, location, format!("{}: {}", summary, listed.join(", ")));
+    event.method = first.method;
+    event.path = first.path.clone();
+    event.path_template = first.path_template.clone();
+    event.operation_id = first.operation_id.clone();
+    event.tags = first.tags.clone();
+    event.operation_summary = first.operation_summary.clone();
+    event.owner = first.owner.clone();
+    event.status_code = first.status_code;
+    event.context = first.context.clone();
+    event
+}
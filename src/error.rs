@@ -1,5 +1,12 @@
+#[cfg(feature = "monitor")]
+use crate::spec::source_map::SpecSourceMap;
+use crate::spec::build_report::OverlappingTemplate;
 use thiserror::Error;
 
+/// Errors raised while loading specs, building validators and recording drift
+///
+/// Every variant has a stable [`code`](ValidationError::code); match on the
+/// variant (or log the code) rather than on the message, which may change.
 #[derive(Error, Debug)]
 pub enum ValidationError {
     #[error("Validation failed: {0}")]
@@ -13,4 +20,107 @@ pub enum ValidationError {
 
     #[error("Failed to compile JSON schema: {0}")]
     SchemaCompilationError(String),
+
+    /// The spec couldn't be read from `location`, a file path or URL
+    #[error("Failed to read spec from {location}: {reason}")]
+    SpecIo { location: String, reason: String },
+
+    #[error("Failed to parse OpenAPI spec: {reason}")]
+    SpecParse { reason: String },
+
+    /// A `$ref` (or a security requirement's scheme name) points at nothing in the spec
+    #[error("Unresolved reference: {reference}")]
+    UnresolvedReference { reference: String },
+
+    #[error("Unsupported spec feature: {feature}")]
+    UnsupportedFeature { feature: String },
+
+    /// An operation's schemas exceed a configured [`crate::SchemaLimits`] limit
+    #[error("Schema too complex at {location}: {reason}")]
+    SchemaTooComplex { location: String, reason: String },
+
+    /// A path template can't be routed alongside those already registered
+    #[error("Conflicting route '{path}': {reason}")]
+    RouteConflict { path: String, reason: String },
+
+    /// Two path templates match some of the same paths and neither is more concrete
+    #[error("Ambiguous routes {} and {}: some paths match both and neither is more concrete", .templates[0], .templates[1])]
+    AmbiguousRoutes { templates: Box<[OverlappingTemplate; 2]> },
+
+    #[error("Failed to record drift event: {0}")]
+    SinkError(String),
+
+    #[error("Failed to render drift report: {0}")]
+    ReportError(String),
+
+    #[error("Drift baseline error: {0}")]
+    BaselineError(String),
+
+    #[error("Failed to watch spec file: {0}")]
+    WatchError(String),
+
+    #[error("Invalid monitor configuration: {0}")]
+    ConfigError(String),
+
+    #[error("Report signature error: {0}")]
+    SignatureError(String),
+
+    #[error("Event transform script error: {0}")]
+    ScriptError(String),
+
+    #[error("Failed to read traffic: {0}")]
+    TrafficError(String),
+
+    #[error("WASM plugin error: {0}")]
+    PluginError(String),
+
+    /// A serialized drift event is malformed or in an unsupported format version
+    #[error("Invalid drift event: {0}")]
+    EventFormat(String),
+
+    /// A caller of the C ABI passed a null pointer or text that isn't UTF-8
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    /// The library panicked while serving a call through the C ABI
+    #[error("Internal panic: {0}")]
+    Panic(String),
+}
+
+impl ValidationError {
+    /// Stable identifier of the failure cause, e.g. `UNRESOLVED_REFERENCE`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ValidationFailed(_) => "VALIDATION_FAILED",
+            Self::RequestBodyMissing => "REQUEST_BODY_MISSING",
+            Self::NoSchemaForStatusCode(_) => "NO_SCHEMA_FOR_STATUS_CODE",
+            Self::SchemaCompilationError(_) => "SCHEMA_COMPILATION",
+            Self::SpecIo { .. } => "SPEC_IO",
+            Self::SpecParse { .. } => "SPEC_PARSE",
+            Self::UnresolvedReference { .. } => "UNRESOLVED_REFERENCE",
+            Self::UnsupportedFeature { .. } => "UNSUPPORTED_FEATURE",
+            Self::SchemaTooComplex { .. } => "SCHEMA_TOO_COMPLEX",
+            Self::RouteConflict { .. } | Self::AmbiguousRoutes { .. } => "ROUTE_CONFLICT",
+            Self::SinkError(_) => "SINK",
+            Self::ReportError(_) => "REPORT",
+            Self::BaselineError(_) => "BASELINE",
+            Self::WatchError(_) => "WATCH",
+            Self::ConfigError(_) => "CONFIG",
+            Self::SignatureError(_) => "SIGNATURE",
+            Self::ScriptError(_) => "SCRIPT",
+            Self::TrafficError(_) => "TRAFFIC",
+            Self::PluginError(_) => "PLUGIN",
+            Self::EventFormat(_) => "EVENT_FORMAT",
+            Self::InvalidArgument(_) => "INVALID_ARGUMENT",
+            Self::Panic(_) => "PANIC",
+        }
+    }
+
+    /// Fills in the spec lines of the templates an ambiguous routes error names
+    #[cfg(feature = "monitor")]
+    pub fn locate(&mut self, source_map: &SpecSourceMap) {
+        if let Self::AmbiguousRoutes { templates } = self {
+            templates.iter_mut().for_each(|template| template.locate(source_map));
+        }
+    }
 }
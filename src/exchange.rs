@@ -0,0 +1,192 @@
+use crate::api_validator::HttpMethod;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An HTTP request as observed in traffic
+#[derive(Debug, Clone)]
+pub struct ObservedRequest {
+    pub method: HttpMethod,
+    /// Request path without the query string
+    pub path: String,
+    /// Raw query string without the leading `?`
+    pub query: Option<String>,
+    /// Headers keyed by lowercase name
+    pub headers: HashMap<String, String>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl ObservedRequest {
+    /// Creates a request, splitting any query string off the given path
+    pub fn new(method: HttpMethod, path: &str) -> Self {
+        let (path, query) = match path.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (path.to_string(), None),
+        };
+        Self {
+            method,
+            path,
+            query,
+            headers: HashMap::new(),
+            body: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_ascii_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Looks up a header by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Decoded query parameters in the order they appear
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query.as_deref().map(parse_query_string).unwrap_or_default()
+    }
+}
+
+/// Who produced a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseOrigin {
+    /// The API implementation behind the gateway
+    Upstream,
+    /// The gateway or load balancer itself (rate limiting 429s, 502/504 error pages, ...)
+    Gateway,
+}
+
+impl ResponseOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upstream => "upstream",
+            Self::Gateway => "gateway",
+        }
+    }
+}
+
+/// An HTTP response as observed in traffic
+#[derive(Debug, Clone)]
+pub struct ObservedResponse {
+    pub status: u16,
+    /// Headers keyed by lowercase name
+    pub headers: HashMap<String, String>,
+    pub body: Option<Vec<u8>>,
+    /// Origin reported by the capture source, if it knows
+    pub origin: Option<ResponseOrigin>,
+}
+
+impl ObservedResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: HashMap::new(),
+            body: None,
+            origin: None,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_ascii_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Records who produced the response, as known to the capture source
+    pub fn with_origin(mut self, origin: ResponseOrigin) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Looks up a header by case-insensitive name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+}
+
+/// A request/response pair to validate together
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub request: ObservedRequest,
+    pub response: ObservedResponse,
+}
+
+impl Exchange {
+    pub fn new(request: ObservedRequest, response: ObservedResponse) -> Self {
+        Self { request, response }
+    }
+}
+
+/// Splits a raw query string into percent-decoded key/value pairs
+pub fn parse_query_string(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes and `+` as space, leaving malformed escapes untouched
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Decodes the `%XX` escapes of a path segment
+///
+/// Unlike in query strings `+` is literal, and an escaped `/` stays escaped
+/// so it can't split the segment. Escapes that don't decode to UTF-8 leave
+/// the segment untouched.
+pub(crate) fn percent_decode_path(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1).copied().and_then(hex_value), bytes.get(i + 2).copied().and_then(hex_value)) {
+            (b'%', Some(high), Some(low)) if high << 4 | low != b'/' => {
+                decoded.push(high << 4 | low);
+                i += 3;
+            }
+            (byte, _, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| segment.to_string())
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
@@ -0,0 +1,212 @@
+//! Cheap pre-filters deciding which exchanges get validated at all
+//!
+//! Upstream error storms (502s from the load balancer, 503s while a
+//! deployment rolls) and CORS preflights carry bodies no spec documents.
+//! Skipping them before routing saves the validation work and the noise.
+//!
+//! Gateways also answer on the API's behalf (rate limiting 429s, 504 error
+//! pages). A [`GatewayMarker`] tells those apart from upstream responses so
+//! they can be skipped, or kept and bucketed separately.
+
+use crate::api_validator::HttpMethod;
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse, ResponseOrigin};
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Which exchanges are validated, by method, response status and response origin
+///
+/// The default filter lets everything through.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExchangeFilter {
+    /// Methods never validated (e.g. `OPTIONS`)
+    pub skip_methods: Vec<HttpMethod>,
+    /// Statuses never validated (e.g. `5xx`)
+    pub skip_statuses: Vec<StatusRange>,
+    /// When non-empty, only these statuses are validated (e.g. `2xx`, `4xx`)
+    pub only_statuses: Vec<StatusRange>,
+    /// How gateway-generated responses are recognised when the capture source doesn't say
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_marker: Option<GatewayMarker>,
+    /// Never validates gateway-generated responses
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skip_gateway_responses: bool,
+}
+
+impl ExchangeFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never validates requests with this method
+    pub fn skip_method(mut self, method: HttpMethod) -> Self {
+        self.skip_methods.push(method);
+        self
+    }
+
+    /// Never validates exchanges whose status falls in `range`
+    pub fn skip_status(mut self, range: StatusRange) -> Self {
+        self.skip_statuses.push(range);
+        self
+    }
+
+    /// Validates only exchanges whose status falls in `range` (or another allowed range)
+    pub fn only_status(mut self, range: StatusRange) -> Self {
+        self.only_statuses.push(range);
+        self
+    }
+
+    /// Recognises gateway-generated responses by `marker`
+    pub fn gateway_marker(mut self, marker: GatewayMarker) -> Self {
+        self.gateway_marker = Some(marker);
+        self
+    }
+
+    /// Never validates gateway-generated responses, instead of bucketing them separately
+    pub fn skip_gateway_responses(mut self) -> Self {
+        self.skip_gateway_responses = true;
+        self
+    }
+
+    /// Who produced a response
+    ///
+    /// An origin recorded by the capture source wins; otherwise the marker
+    /// decides. `None` when neither is available.
+    pub fn response_origin(&self, response: &ObservedResponse) -> Option<ResponseOrigin> {
+        response.origin.or_else(|| {
+            let marker = self.gateway_marker.as_ref()?;
+            Some(if marker.matches(response) {
+                ResponseOrigin::Gateway
+            } else {
+                ResponseOrigin::Upstream
+            })
+        })
+    }
+
+    /// Whether a request is validated on its own, judged by its method alone
+    pub fn allows_request(&self, request: &ObservedRequest) -> bool {
+        !self.skip_methods.contains(&request.method)
+    }
+
+    /// Whether an exchange is validated
+    pub fn allows(&self, exchange: &Exchange) -> bool {
+        let status = exchange.response.status;
+        self.allows_request(&exchange.request)
+            && !self.skip_statuses.iter().any(|range| range.contains(status))
+            && (self.only_statuses.is_empty() || self.only_statuses.iter().any(|range| range.contains(status)))
+            && !(self.skip_gateway_responses
+                && self.response_origin(&exchange.response) == Some(ResponseOrigin::Gateway))
+    }
+}
+
+/// How a gateway marks the responses it generates itself
+///
+/// Most gateways either add a header of their own to generated responses
+/// (`x-kong-response-latency` without `x-kong-upstream-latency`, Envoy's
+/// `x-envoy-ratelimited`) or stamp a header on proxied ones only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum GatewayMarker {
+    /// Gateway-generated responses carry this header
+    HeaderPresent { header: String },
+    /// Proxied responses carry this header; responses without it are gateway-generated
+    HeaderAbsent { header: String },
+    /// Gateway-generated responses carry this header with this value (case-insensitive)
+    HeaderEquals { header: String, value: String },
+}
+
+impl GatewayMarker {
+    /// Whether `response` was generated by the gateway
+    pub fn matches(&self, response: &ObservedResponse) -> bool {
+        match self {
+            Self::HeaderPresent { header } => response.header(header).is_some(),
+            Self::HeaderAbsent { header } => response.header(header).is_none(),
+            Self::HeaderEquals { header, value } => response
+                .header(header)
+                .is_some_and(|observed| observed.trim().eq_ignore_ascii_case(value)),
+        }
+    }
+}
+
+/// An inclusive range of status codes, written `404`, `5xx` or `500-503`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusRange {
+    pub first: u16,
+    pub last: u16,
+}
+
+impl StatusRange {
+    /// A single status code
+    pub fn exact(status: u16) -> Self {
+        Self { first: status, last: status }
+    }
+
+    /// Every status of a class, e.g. `class(5)` for `5xx`
+    pub fn class(class: u16) -> Self {
+        Self {
+            first: class * 100,
+            last: class * 100 + 99,
+        }
+    }
+
+    pub fn contains(&self, status: u16) -> bool {
+        (self.first..=self.last).contains(&status)
+    }
+}
+
+impl fmt::Display for StatusRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.first == self.last {
+            write!(f, "{}", self.first)
+        } else if self.first.is_multiple_of(100) && self.last == self.first + 99 {
+            write!(f, "{}xx", self.first / 100)
+        } else {
+            write!(f, "{}-{}", self.first, self.last)
+        }
+    }
+}
+
+impl FromStr for StatusRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || format!("invalid status range '{}' (expected e.g. 404, 5xx or 500-503)", s);
+        let parse = |code: &str| code.trim().parse::<u16>().ok().filter(|code| (100..=599).contains(code));
+
+        if let Some(class) = s.strip_suffix("xx").or_else(|| s.strip_suffix("XX")) {
+            return class
+                .parse::<u16>()
+                .ok()
+                .filter(|class| (1..=5).contains(class))
+                .map(Self::class)
+                .ok_or_else(invalid);
+        }
+        match s.split_once('-') {
+            Some((first, last)) => match (parse(first), parse(last)) {
+                (Some(first), Some(last)) if first <= last => Ok(Self { first, last }),
+                _ => Err(invalid()),
+            },
+            None => parse(s).map(Self::exact).ok_or_else(invalid),
+        }
+    }
+}
+
+impl Serialize for StatusRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Bare numbers (`404`) are as valid as strings in YAML and JSON configs
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(s) => s.parse().map_err(de::Error::custom),
+            serde_json::Value::Number(n) => n.to_string().parse().map_err(de::Error::custom),
+            other => Err(de::Error::custom(format!("invalid status range: {}", other))),
+        }
+    }
+}
@@ -0,0 +1,301 @@
+//! C ABI for embedding the validator in sidecars written in other languages
+//!
+//! The library builds as a `cdylib`; `include/drift_monitor.h` declares
+//! these functions for C and for the FFI layers of Python, Node and Go.
+//! A spec is loaded once into an opaque [`DriftValidator`], then each
+//! exchange is passed as plain strings and its drift events come back as
+//! a JSON array, in the form the JSONL sink writes them.
+//!
+//! Functions that fail return `NULL` and leave a message for
+//! [`drift_last_error`]. A panic inside the library is caught at the
+//! boundary and reported the same way rather than unwinding into the host.
+//! Strings returned by the library are owned by the caller, who releases
+//! them with [`drift_string_free`]. A validator may be shared between
+//! threads.
+
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::config::MonitorConfig;
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse};
+use crate::spec::builder::{build_api_validator_from_file, build_api_validator_with_options, BuildOptions};
+use crate::spec::loader::parse_openapi_spec;
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+/// Version of the ABI, raised whenever a function changes incompatibly
+pub const DRIFT_ABI_VERSION: u32 = 1;
+
+thread_local! {
+    /// Message of the last failure on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A validator built from one spec, opaque to C
+pub struct DriftValidator {
+    validator: ApiValidator,
+}
+
+/// The ABI version the library implements, to check against the header's
+#[no_mangle]
+pub extern "C" fn drift_abi_version() -> u32 {
+    DRIFT_ABI_VERSION
+}
+
+/// Message of the last failure on the calling thread, `CODE: description`, or `NULL`
+///
+/// The string stays valid until the next call into the library on the
+/// same thread, and must not be freed.
+#[no_mangle]
+pub extern "C" fn drift_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Loads the spec (YAML or JSON) at `spec_path` and builds a validator for it
+///
+/// `config_path` names a monitor configuration whose build options apply,
+/// or is `NULL` for the defaults. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `spec_path` must be a NUL-terminated string and `config_path` one or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn drift_validator_load(
+    spec_path: *const c_char,
+    config_path: *const c_char,
+) -> *mut DriftValidator {
+    outcome(|| {
+        let spec_path = text(spec_path, "spec_path")?;
+        let options = build_options(config_path)?;
+        let validator = build_api_validator_from_file(Path::new(spec_path), &options)?;
+        Ok(Box::into_raw(Box::new(DriftValidator { validator })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Builds a validator for a spec (YAML or JSON) held in memory
+///
+/// `config_path` is as for [`drift_validator_load`]. Returns `NULL` on failure.
+///
+/// # Safety
+///
+/// `spec` must be a NUL-terminated string and `config_path` one or `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn drift_validator_parse(spec: *const c_char, config_path: *const c_char) -> *mut DriftValidator {
+    outcome(|| {
+        let spec = parse_openapi_spec(text(spec, "spec")?)?;
+        let options = build_options(config_path)?;
+        let validator = build_api_validator_with_options(&spec, &options)?;
+        Ok(Box::into_raw(Box::new(DriftValidator { validator })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Releases a validator; `NULL` is ignored
+///
+/// # Safety
+///
+/// `validator` must come from [`drift_validator_load`] or
+/// [`drift_validator_parse`], not be in use on another thread, and not be
+/// used again.
+#[no_mangle]
+pub unsafe extern "C" fn drift_validator_free(validator: *mut DriftValidator) {
+    if !validator.is_null() {
+        drop(Box::from_raw(validator));
+    }
+}
+
+/// Validates a request on its own, returning its drift events as a JSON array
+///
+/// `headers` holds one `Name: value` line per header, separated by
+/// `\n` or `\r\n`, or is `NULL`. The body is `body_len` bytes at `body`,
+/// or absent when `body` is `NULL`. `path` may carry a query string.
+/// Returns `NULL` on failure, including for a path the spec has no
+/// operation for.
+///
+/// # Safety
+///
+/// `validator` must be a live validator, `method` and `path` NUL-terminated
+/// strings, `headers` one or `NULL`, and `body` point to `body_len`
+/// readable bytes unless `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn drift_validate_request(
+    validator: *const DriftValidator,
+    method: *const c_char,
+    path: *const c_char,
+    headers: *const c_char,
+    body: *const u8,
+    body_len: usize,
+) -> *mut c_char {
+    outcome(|| {
+        let validator = live(validator)?;
+        let request = request(method, path, headers, body, body_len)?;
+        events_json(&validator.validator.validate_request(&request)?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Validates a request and the response it got, returning their drift events as a JSON array
+///
+/// Request arguments are as for [`drift_validate_request`]; the response
+/// has the given `status`, headers and body in the same form.
+///
+/// # Safety
+///
+/// As for [`drift_validate_request`], for both the request and the
+/// response arguments.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn drift_validate_exchange(
+    validator: *const DriftValidator,
+    method: *const c_char,
+    path: *const c_char,
+    request_headers: *const c_char,
+    request_body: *const u8,
+    request_body_len: usize,
+    status: u16,
+    response_headers: *const c_char,
+    response_body: *const u8,
+    response_body_len: usize,
+) -> *mut c_char {
+    outcome(|| {
+        let validator = live(validator)?;
+        let request = request(method, path, request_headers, request_body, request_body_len)?;
+        let mut response = ObservedResponse::new(status);
+        for (name, value) in header_lines(optional_text(response_headers, "response_headers")?) {
+            response = response.with_header(name, value);
+        }
+        if let Some(body) = bytes(response_body, response_body_len) {
+            response = response.with_body(body);
+        }
+        events_json(&validator.validator.validate_exchange(&Exchange::new(request, response))?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Releases a string returned by the library; `NULL` is ignored
+///
+/// # Safety
+///
+/// `string` must come from this library and not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn drift_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Runs `call`, recording its error, or the message it panicked with, for [`drift_last_error`]
+fn outcome<T>(call: impl FnOnce() -> Result<T, ValidationError>) -> Option<T> {
+    let result = panic::catch_unwind(AssertUnwindSafe(call))
+        .unwrap_or_else(|payload| Err(ValidationError::Panic(panic_message(payload.as_ref()))));
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = result.as_ref().err().map(|e| {
+            // Interior NULs would cut the message short in C
+            CString::new(format!("{}: {}", e.code(), e).replace('\0', " ")).unwrap_or_default()
+        });
+    });
+    result.ok()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown panic".to_string(),
+    }
+}
+
+unsafe fn live<'a>(validator: *const DriftValidator) -> Result<&'a DriftValidator, ValidationError> {
+    validator
+        .as_ref()
+        .ok_or_else(|| ValidationError::InvalidArgument("`validator` is NULL".to_string()))
+}
+
+unsafe fn text<'a>(value: *const c_char, name: &str) -> Result<&'a str, ValidationError> {
+    optional_text(value, name)?.ok_or_else(|| ValidationError::InvalidArgument(format!("`{}` is NULL", name)))
+}
+
+unsafe fn optional_text<'a>(value: *const c_char, name: &str) -> Result<Option<&'a str>, ValidationError> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map(Some)
+        .map_err(|_| ValidationError::InvalidArgument(format!("`{}` is not valid UTF-8", name)))
+}
+
+unsafe fn bytes(data: *const u8, len: usize) -> Option<Vec<u8>> {
+    (!data.is_null()).then(|| slice::from_raw_parts(data, len).to_vec())
+}
+
+unsafe fn build_options(config_path: *const c_char) -> Result<BuildOptions, ValidationError> {
+    match optional_text(config_path, "config_path")? {
+        Some(config_path) => Ok(MonitorConfig::load(Path::new(config_path))?.build_options()),
+        None => Ok(BuildOptions::default()),
+    }
+}
+
+unsafe fn request(
+    method: *const c_char,
+    path: *const c_char,
+    headers: *const c_char,
+    body: *const u8,
+    body_len: usize,
+) -> Result<ObservedRequest, ValidationError> {
+    let method_name = text(method, "method")?;
+    let method: HttpMethod = method_name
+        .parse()
+        .map_err(|()| ValidationError::InvalidArgument(format!("unknown method `{}`", method_name)))?;
+
+    let mut request = ObservedRequest::new(method, text(path, "path")?);
+    for (name, value) in header_lines(optional_text(headers, "headers")?) {
+        request = request.with_header(name, value);
+    }
+    if let Some(body) = bytes(body, body_len) {
+        request = request.with_body(body);
+    }
+    Ok(request)
+}
+
+/// `Name: value` pairs of a header block, skipping lines without a colon
+fn header_lines(headers: Option<&str>) -> impl Iterator<Item = (&str, &str)> {
+    headers
+        .into_iter()
+        .flat_map(str::lines)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+}
+
+fn events_json(events: &[DriftEvent]) -> Result<*mut c_char, ValidationError> {
+    let json = serde_json::to_string(events).map_err(|e| ValidationError::SinkError(e.to_string()))?;
+    // JSON escapes control characters, NUL included
+    Ok(CString::new(json).unwrap_or_default().into_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let message = drift_last_error();
+        (!message.is_null()).then(|| unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn panics_are_reported_instead_of_unwinding() {
+        let result = outcome(|| -> Result<(), ValidationError> { panic!("validator exploded") });
+        assert!(result.is_none());
+        assert_eq!(last_error().as_deref(), Some("PANIC: Internal panic: validator exploded"));
+
+        // The next call on the thread clears it
+        assert_eq!(outcome(|| Ok(1)), Some(1));
+        assert_eq!(last_error(), None);
+    }
+}
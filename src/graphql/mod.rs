@@ -0,0 +1,217 @@
+//! Drift monitoring for GraphQL APIs
+//!
+//! A [`GraphQlSchemaValidator`] checks observed operations against an SDL schema:
+//! queries selecting fields or passing arguments the schema doesn't
+//! declare, and responses holding fields that weren't selected or values
+//! of the wrong type. Drift is reported as [`DriftEvent`]s on the endpoint's
+//! path, with the operation (`query GetUser`) as the template, so reports,
+//! sinks and baselines group it per operation like HTTP drift.
+//!
+//! Where [`crate::validators::GraphQlValidator`] only checks the HTTP
+//! envelope of a GraphQL route, this checks the operations themselves.
+//! Variables aren't checked against their declared input types.
+
+pub mod query;
+pub mod schema;
+mod request;
+mod response;
+mod syntax;
+
+pub use query::{FieldSelection, Fragment, Operation, OperationKind, QueryDocument, Selection};
+pub use schema::{FieldDefinition, GraphQlSchema, TypeDefinition, TypeKind, TypeRef};
+
+use crate::api_validator::HttpMethod;
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use crate::traffic::{IngestSummary, MAX_KEPT_ERRORS};
+use crate::validation_helpers::gather_drift_events;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Path GraphQL requests are served on, unless configured otherwise
+pub const DEFAULT_ENDPOINT: &str = "/graphql";
+
+/// One observed GraphQL request and, if captured, its response
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQlExchange {
+    pub query: String,
+    #[serde(default)]
+    pub operation_name: Option<String>,
+    /// The response body, with `data` and `errors`
+    #[serde(default)]
+    pub response: Option<Value>,
+}
+
+impl GraphQlExchange {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            operation_name: None,
+            response: None,
+        }
+    }
+
+    pub fn with_operation_name(mut self, name: impl Into<String>) -> Self {
+        self.operation_name = Some(name.into());
+        self
+    }
+
+    pub fn with_response(mut self, response: Value) -> Self {
+        self.response = Some(response);
+        self
+    }
+
+    /// How the exchange's operation is named in reports, e.g. `query GetUser`
+    pub fn operation_template(&self) -> String {
+        let document = QueryDocument::parse(&self.query).ok();
+        let operation = document
+            .as_ref()
+            .and_then(|document| document.operation(self.operation_name.as_deref()));
+        operation_template(operation, self.operation_name.as_deref())
+    }
+
+    /// Reads an exchange logged as one JSON line: the request body's
+    /// `query` and `operationName`, and the response body as `response`
+    pub fn from_json_line(line: &str) -> Result<Self, ValidationError> {
+        serde_json::from_str(line)
+            .map_err(|e| ValidationError::TrafficError(format!("Invalid GraphQL traffic record: {}", e)))
+    }
+}
+
+/// Checks GraphQL operations and their responses against a schema
+pub struct GraphQlSchemaValidator {
+    schema: GraphQlSchema,
+    endpoint: String,
+}
+
+impl GraphQlSchemaValidator {
+    pub fn new(schema: GraphQlSchema) -> Self {
+        Self {
+            schema,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+        }
+    }
+
+    /// Loads the schema from SDL files
+    pub fn from_files(paths: &[PathBuf]) -> Result<Self, ValidationError> {
+        Ok(Self::new(GraphQlSchema::load(paths)?))
+    }
+
+    /// Reports drift on `path` rather than `/graphql`
+    pub fn with_endpoint(mut self, path: impl Into<String>) -> Self {
+        self.endpoint = path.into();
+        self
+    }
+
+    pub fn schema(&self) -> &GraphQlSchema {
+        &self.schema
+    }
+
+    /// Validates an operation and its response
+    pub fn validate_exchange(&self, exchange: &GraphQlExchange) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.validate_exchange_with(exchange, &EventContext::new(), emit))
+    }
+
+    /// Like [`Self::validate_exchange`], attaching `context` to every event
+    /// and passing each to `emit` as it is found
+    pub fn validate_exchange_with(
+        &self,
+        exchange: &GraphQlExchange,
+        context: &EventContext,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        let document = QueryDocument::parse(&exchange.query);
+        let operation = document
+            .as_ref()
+            .ok()
+            .and_then(|document| document.operation(exchange.operation_name.as_deref()));
+        let template = operation_template(operation, exchange.operation_name.as_deref());
+        let mut emit = |event: DriftEvent| {
+            emit(event.with_operation(HttpMethod::POST, &self.endpoint, &template).with_context(context))
+        };
+
+        let (document, operation) = match (&document, operation) {
+            (Ok(document), Some(operation)) => (document, operation),
+            (Err(e), _) => {
+                let message = match e {
+                    ValidationError::ValidationFailed(message) => message.clone(),
+                    other => other.to_string(),
+                };
+                emit(DriftEvent::new(DriftType::GraphQlInvalidQuery, "query", message));
+                return;
+            }
+            (Ok(_), None) => {
+                let message = match &exchange.operation_name {
+                    Some(name) => format!("The query defines no operation named '{}'", name),
+                    None => "The query defines several operations but names none to run".to_string(),
+                };
+                emit(DriftEvent::new(DriftType::GraphQlInvalidQuery, "query", message));
+                return;
+            }
+        };
+        let root_type = match operation.kind {
+            OperationKind::Query => self.schema.query_type(),
+            OperationKind::Mutation => self.schema.mutation_type(),
+            OperationKind::Subscription => self.schema.subscription_type(),
+        };
+        if self.schema.type_definition(root_type).is_none() {
+            emit(DriftEvent::new(
+                DriftType::GraphQlUnknownType,
+                "query",
+                format!("Ran a {}, which the schema doesn't support", operation.kind.as_str()),
+            ));
+            return;
+        }
+        request::check(&self.schema, document, root_type, &operation.selections, &mut emit);
+        if let Some(body) = &exchange.response {
+            response::check(&self.schema, document, root_type, &operation.selections, body, &mut emit);
+        }
+    }
+}
+
+/// How an operation is named in reports, e.g. `query GetUser`
+fn operation_template(operation: Option<&Operation>, requested_name: Option<&str>) -> String {
+    match operation {
+        Some(operation) => match &operation.name {
+            Some(name) => format!("{} {}", operation.kind.as_str(), name),
+            None => operation.kind.as_str().to_string(),
+        },
+        None => requested_name.unwrap_or("(invalid)").to_string(),
+    }
+}
+
+/// Validates every exchange logged in `reader`, one JSON line each as read
+/// by [`GraphQlExchange::from_json_line`], sending drift to `sink` and
+/// aggregating it per operation
+///
+/// Malformed lines are skipped and counted; read and sink failures are returned.
+pub fn ingest(validator: &GraphQlSchemaValidator, reader: impl BufRead, sink: &dyn DriftSink) -> Result<IngestSummary, ValidationError> {
+    let mut summary = IngestSummary::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| ValidationError::TrafficError(format!("line {}: {}", index + 1, e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.records += 1;
+        let exchange = match GraphQlExchange::from_json_line(&line) {
+            Ok(exchange) => exchange,
+            Err(e) => {
+                summary.malformed += 1;
+                if summary.errors.len() < MAX_KEPT_ERRORS {
+                    summary.errors.push(format!("line {}: {}", index + 1, e));
+                }
+                continue;
+            }
+        };
+        let events = validator.validate_exchange(&exchange);
+        sink.record_all(events.clone())?;
+        summary.report.record_exchange(HttpMethod::POST, &exchange.operation_template(), events);
+    }
+    sink.flush()?;
+    Ok(summary)
+}
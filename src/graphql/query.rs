@@ -0,0 +1,181 @@
+//! Operations read from GraphQL query documents
+
+use super::syntax::Parser;
+use crate::error::ValidationError;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Query => "query",
+            Self::Mutation => "mutation",
+            Self::Subscription => "subscription",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSelection {
+    pub alias: Option<String>,
+    pub name: String,
+    /// Names of the arguments passed
+    pub arguments: Vec<String>,
+    pub selections: Vec<Selection>,
+}
+
+impl FieldSelection {
+    /// The key the field's value has in the response
+    pub fn response_key(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    Field(FieldSelection),
+    FragmentSpread(String),
+    InlineFragment {
+        type_condition: Option<String>,
+        selections: Vec<Selection>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub name: Option<String>,
+    pub selections: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub type_condition: String,
+    pub selections: Vec<Selection>,
+}
+
+/// The operations and fragments of a query document
+///
+/// Variable definitions, argument values and directives are skipped;
+/// fields under `@skip` or `@include` are treated as selected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryDocument {
+    pub operations: Vec<Operation>,
+    pub fragments: HashMap<String, Fragment>,
+}
+
+impl QueryDocument {
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let mut parser = Parser::new(source)?;
+        let mut document = Self::default();
+        while !parser.at_end() {
+            if parser.at("{") {
+                document.operations.push(Operation {
+                    kind: OperationKind::Query,
+                    name: None,
+                    selections: selection_set(&mut parser)?,
+                });
+                continue;
+            }
+            let keyword = parser.expect_name()?;
+            let kind = match keyword.as_str() {
+                "fragment" => {
+                    let name = parser.expect_name()?;
+                    if !parser.eat_name("on") {
+                        return Err(parser.error("expected 'on'"));
+                    }
+                    let type_condition = parser.expect_name()?;
+                    parser.skip_directives()?;
+                    let selections = selection_set(&mut parser)?;
+                    document.fragments.insert(name, Fragment { type_condition, selections });
+                    continue;
+                }
+                "query" => OperationKind::Query,
+                "mutation" => OperationKind::Mutation,
+                "subscription" => OperationKind::Subscription,
+                other => return Err(parser.error(format!("unexpected '{}'", other))),
+            };
+            let name = match parser.at("(") || parser.at("@") || parser.at("{") {
+                true => None,
+                false => Some(parser.expect_name()?),
+            };
+            parser.skip_parenthesized()?;
+            parser.skip_directives()?;
+            document.operations.push(Operation {
+                kind,
+                name,
+                selections: selection_set(&mut parser)?,
+            });
+        }
+        Ok(document)
+    }
+
+    /// The operation a request runs: the one named, or the only one
+    pub fn operation(&self, name: Option<&str>) -> Option<&Operation> {
+        match name {
+            Some(name) => self.operations.iter().find(|operation| operation.name.as_deref() == Some(name)),
+            None if self.operations.len() == 1 => self.operations.first(),
+            None => None,
+        }
+    }
+}
+
+fn selection_set(parser: &mut Parser) -> Result<Vec<Selection>, ValidationError> {
+    parser.expect("{")?;
+    let mut selections = Vec::new();
+    while !parser.eat("}") {
+        if parser.eat("...") {
+            if parser.eat_name("on") {
+                let type_condition = Some(parser.expect_name()?);
+                parser.skip_directives()?;
+                selections.push(Selection::InlineFragment {
+                    type_condition,
+                    selections: selection_set(parser)?,
+                });
+            } else if parser.at("@") || parser.at("{") {
+                parser.skip_directives()?;
+                selections.push(Selection::InlineFragment {
+                    type_condition: None,
+                    selections: selection_set(parser)?,
+                });
+            } else {
+                selections.push(Selection::FragmentSpread(parser.expect_name()?));
+                parser.skip_directives()?;
+            }
+            continue;
+        }
+
+        let mut name = parser.expect_name()?;
+        let mut alias = None;
+        if parser.eat(":") {
+            alias = Some(name);
+            name = parser.expect_name()?;
+        }
+        let mut arguments = Vec::new();
+        if parser.eat("(") {
+            while !parser.eat(")") {
+                arguments.push(parser.expect_name()?);
+                parser.expect(":")?;
+                parser.skip_value()?;
+            }
+        }
+        parser.skip_directives()?;
+        let selections_below = match parser.at("{") {
+            true => selection_set(parser)?,
+            false => Vec::new(),
+        };
+        selections.push(Selection::Field(FieldSelection {
+            alias,
+            name,
+            arguments,
+            selections: selections_below,
+        }));
+    }
+    Ok(selections)
+}
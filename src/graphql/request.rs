@@ -0,0 +1,114 @@
+//! Checks a query's selections against the schema
+
+use super::query::{QueryDocument, Selection};
+use super::schema::GraphQlSchema;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+
+pub(super) fn check(
+    schema: &GraphQlSchema,
+    document: &QueryDocument,
+    root_type: &str,
+    selections: &[Selection],
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    let mut checker = Checker {
+        schema,
+        document,
+        emit,
+        path: Vec::new(),
+        fragments: Vec::new(),
+        root_type,
+    };
+    checker.selections(root_type, selections);
+}
+
+struct Checker<'a, 'e> {
+    schema: &'a GraphQlSchema,
+    document: &'a QueryDocument,
+    emit: &'e mut dyn FnMut(DriftEvent),
+    /// Response keys down to the selection being checked
+    path: Vec<String>,
+    /// Fragments being expanded, to stop at cycles
+    fragments: Vec<&'a str>,
+    root_type: &'a str,
+}
+
+impl<'a> Checker<'a, '_> {
+    fn selections(&mut self, type_name: &str, selections: &'a [Selection]) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    // Introspection is answered by the server, not the schema
+                    if field.name == "__typename" || (type_name == self.root_type && field.name.starts_with("__")) {
+                        continue;
+                    }
+                    self.path.push(field.response_key().to_string());
+                    match self.schema.field(type_name, &field.name) {
+                        None => self.drift(
+                            DriftType::GraphQlUndeclaredField,
+                            format!("Selected {}.{}, which the schema doesn't declare", type_name, field.name),
+                        ),
+                        Some(definition) => {
+                            if definition.deprecated {
+                                self.drift(
+                                    DriftType::DeprecatedUsage,
+                                    format!("Selected {}.{}, which the schema marks deprecated", type_name, field.name),
+                                );
+                            }
+                            for argument in &field.arguments {
+                                if !definition.arguments.contains(argument) {
+                                    self.drift(
+                                        DriftType::GraphQlUnknownArgument,
+                                        format!("Passed argument '{}' to {}.{}, which doesn't declare it", argument, type_name, field.name),
+                                    );
+                                }
+                            }
+                            let field_type = definition.field_type.named();
+                            if !field.selections.is_empty() {
+                                self.selections(field_type, &field.selections);
+                            }
+                        }
+                    }
+                    self.path.pop();
+                }
+                Selection::FragmentSpread(name) => {
+                    let Some((name, fragment)) = self.document.fragments.get_key_value(name) else {
+                        self.drift(DriftType::GraphQlInvalidQuery, format!("Spread fragment '{}', which the query doesn't define", name));
+                        continue;
+                    };
+                    if self.fragments.contains(&name.as_str()) {
+                        continue;
+                    }
+                    if self.known_type(&fragment.type_condition) {
+                        self.fragments.push(name);
+                        self.selections(&fragment.type_condition, &fragment.selections);
+                        self.fragments.pop();
+                    }
+                }
+                Selection::InlineFragment { type_condition, selections } => match type_condition {
+                    Some(condition) if !self.known_type(condition) => {}
+                    Some(condition) => self.selections(condition, selections),
+                    None => self.selections(type_name, selections),
+                },
+            }
+        }
+    }
+
+    /// Whether a fragment's type condition names a type, reporting it if not
+    fn known_type(&mut self, name: &str) -> bool {
+        let known = self.schema.type_definition(name).is_some();
+        if !known {
+            self.drift(DriftType::GraphQlUnknownType, format!("Fragment on type {}, which the schema doesn't declare", name));
+        }
+        known
+    }
+
+    fn drift(&mut self, drift_type: DriftType, message: String) {
+        let location = match self.path.is_empty() {
+            true => "query".to_string(),
+            false => format!("query/{}", self.path.join("/")),
+        };
+        (self.emit)(DriftEvent::new(drift_type, location, message));
+    }
+}
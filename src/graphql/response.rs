@@ -0,0 +1,190 @@
+//! Checks a response's data against the query's selections and the schema
+
+use super::query::{FieldSelection, QueryDocument, Selection};
+use super::schema::{GraphQlSchema, TypeKind, TypeRef};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use serde_json::{Map, Value};
+
+pub(super) fn check(
+    schema: &GraphQlSchema,
+    document: &QueryDocument,
+    root_type: &str,
+    selections: &[Selection],
+    response: &Value,
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    // Field errors null out fields, non-null ones included
+    let has_errors = response
+        .get("errors")
+        .and_then(Value::as_array)
+        .is_some_and(|errors| !errors.is_empty());
+    let mut checker = Checker {
+        schema,
+        document,
+        emit,
+        path: vec!["data".to_string()],
+        has_errors,
+    };
+    match response.get("data") {
+        Some(Value::Object(data)) => checker.object(root_type, selections, data),
+        Some(Value::Null) | None => {}
+        Some(other) => checker.drift(
+            DriftType::GraphQlResponseTypeMismatch,
+            format!("Response data should be an object, got {}", kind(other)),
+        ),
+    }
+}
+
+struct Checker<'a, 'e> {
+    schema: &'a GraphQlSchema,
+    document: &'a QueryDocument,
+    emit: &'e mut dyn FnMut(DriftEvent),
+    path: Vec<String>,
+    has_errors: bool,
+}
+
+impl<'a> Checker<'a, '_> {
+    fn object(&mut self, type_name: &str, selections: &'a [Selection], object: &Map<String, Value>) {
+        // Abstract types are checked as the object type the server names
+        let concrete = object
+            .get("__typename")
+            .and_then(Value::as_str)
+            .filter(|concrete| self.schema.is_possible_type(type_name, concrete))
+            .unwrap_or(type_name)
+            .to_string();
+        for (key, value) in object {
+            if key == "__typename" {
+                continue;
+            }
+            self.path.push(key.clone());
+            match self.find_field(&concrete, selections, key, &mut Vec::new()) {
+                None if self.schema.field(&concrete, key).is_some() => self.drift(
+                    DriftType::GraphQlResponseUndeclaredField,
+                    format!("Returned {}.{}, which the query didn't select", concrete, key),
+                ),
+                None => self.drift(
+                    DriftType::GraphQlResponseUndeclaredField,
+                    format!("Returned field '{}', which {} doesn't declare", key, concrete),
+                ),
+                Some((field, parent)) => {
+                    let definition = self
+                        .schema
+                        .field(&parent, &field.name)
+                        .or_else(|| self.schema.field(&concrete, &field.name));
+                    // Undeclared selections are reported with the query
+                    if let Some(definition) = definition.filter(|_| !field.name.starts_with("__")) {
+                        self.value(&definition.field_type, field, value);
+                    }
+                }
+            }
+            self.path.pop();
+        }
+    }
+
+    /// The selection answering response key `key`, with the type it was selected on
+    fn find_field(
+        &self,
+        type_name: &str,
+        selections: &'a [Selection],
+        key: &str,
+        fragments: &mut Vec<&'a str>,
+    ) -> Option<(&'a FieldSelection, String)> {
+        selections.iter().find_map(|selection| match selection {
+            Selection::Field(field) => (field.response_key() == key).then(|| (field, type_name.to_string())),
+            Selection::FragmentSpread(name) => {
+                let (name, fragment) = self.document.fragments.get_key_value(name)?;
+                if fragments.contains(&name.as_str()) {
+                    return None;
+                }
+                fragments.push(name);
+                let found = self.find_field(&fragment.type_condition, &fragment.selections, key, fragments);
+                fragments.pop();
+                found
+            }
+            Selection::InlineFragment { type_condition, selections } => {
+                self.find_field(type_condition.as_deref().unwrap_or(type_name), selections, key, fragments)
+            }
+        })
+    }
+
+    fn value(&mut self, field_type: &TypeRef, field: &'a FieldSelection, value: &Value) {
+        match (field_type, value) {
+            (TypeRef::NonNull(_), Value::Null) if !self.has_errors => self.drift(
+                DriftType::GraphQlResponseTypeMismatch,
+                format!("Field '{}' is declared {} but returned null", field.name, field_type),
+            ),
+            (_, Value::Null) => {}
+            (TypeRef::NonNull(inner), _) => self.value(inner, field, value),
+            (TypeRef::List(inner), Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.path.push(index.to_string());
+                    self.value(inner, field, item);
+                    self.path.pop();
+                }
+            }
+            (TypeRef::List(_), _) => self.mismatch(field, field_type, value),
+            (TypeRef::Named(name), _) => {
+                let Some(definition) = self.schema.type_definition(name) else {
+                    return;
+                };
+                let matches = match definition.kind {
+                    TypeKind::Scalar => scalar_matches(name, value),
+                    TypeKind::Enum => value.as_str().is_some_and(|text| definition.enum_values.iter().any(|declared| declared == text)),
+                    TypeKind::Object | TypeKind::Interface | TypeKind::Union => match value {
+                        Value::Object(object) => {
+                            self.object(name, &field.selections, object);
+                            true
+                        }
+                        _ => false,
+                    },
+                    TypeKind::InputObject => true,
+                };
+                if !matches {
+                    self.mismatch(field, field_type, value);
+                }
+            }
+        }
+    }
+
+    fn mismatch(&mut self, field: &FieldSelection, field_type: &TypeRef, value: &Value) {
+        let got = match value {
+            Value::String(text) => format!("\"{}\"", text),
+            other => kind(other).to_string(),
+        };
+        self.drift(
+            DriftType::GraphQlResponseTypeMismatch,
+            format!("Field '{}' is declared {} but returned {}", field.name, field_type, got),
+        );
+    }
+
+    fn drift(&mut self, drift_type: DriftType, message: String) {
+        (self.emit)(DriftEvent::new(drift_type, format!("body/{}", self.path.join("/")), message));
+    }
+}
+
+/// Whether `value` is a valid result for scalar `name`; custom scalars take anything
+fn scalar_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "Int" => value
+            .as_i64()
+            .is_some_and(|int| i32::try_from(int).is_ok()),
+        "Float" => value.is_number(),
+        "String" => value.is_string(),
+        "Boolean" => value.is_boolean(),
+        // IDs serialize as strings but may be returned as integers
+        "ID" => value.is_string() || value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -0,0 +1,305 @@
+//! Types read from GraphQL SDL
+
+use super::syntax::Parser;
+use crate::error::ValidationError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Scalars every schema has
+const BUILT_IN_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeKind {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+}
+
+/// A reference to a type, e.g. `[User!]!`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeRef {
+    Named(String),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>),
+}
+
+impl TypeRef {
+    /// The named type at the reference's core
+    pub fn named(&self) -> &str {
+        match self {
+            Self::Named(name) => name,
+            Self::List(inner) | Self::NonNull(inner) => inner.named(),
+        }
+    }
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Named(name) => f.write_str(name),
+            Self::List(inner) => write!(f, "[{}]", inner),
+            Self::NonNull(inner) => write!(f, "{}!", inner),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDefinition {
+    pub name: String,
+    pub arguments: Vec<String>,
+    pub field_type: TypeRef,
+    pub deprecated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeDefinition {
+    pub name: String,
+    pub kind: TypeKind,
+    /// Fields of objects, interfaces and input objects
+    pub fields: Vec<FieldDefinition>,
+    pub enum_values: Vec<String>,
+    /// Interfaces an object or interface implements
+    pub interfaces: Vec<String>,
+    /// Members of a union
+    pub members: Vec<String>,
+}
+
+impl TypeDefinition {
+    fn new(name: String, kind: TypeKind) -> Self {
+        Self {
+            name,
+            kind,
+            fields: Vec::new(),
+            enum_values: Vec::new(),
+            interfaces: Vec::new(),
+            members: Vec::new(),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldDefinition> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Whether values of the type are objects with selectable fields
+    pub fn is_composite(&self) -> bool {
+        matches!(self.kind, TypeKind::Object | TypeKind::Interface | TypeKind::Union)
+    }
+}
+
+/// The types of a GraphQL schema
+#[derive(Debug, Clone)]
+pub struct GraphQlSchema {
+    types: BTreeMap<String, TypeDefinition>,
+    query_type: String,
+    mutation_type: String,
+    subscription_type: String,
+}
+
+impl GraphQlSchema {
+    /// Parses SDL, type extensions included
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let mut schema = Self {
+            types: BUILT_IN_SCALARS
+                .iter()
+                .map(|name| (name.to_string(), TypeDefinition::new(name.to_string(), TypeKind::Scalar)))
+                .collect(),
+            query_type: "Query".to_string(),
+            mutation_type: "Mutation".to_string(),
+            subscription_type: "Subscription".to_string(),
+        };
+        let mut parser = Parser::new(source)?;
+        while !parser.at_end() {
+            schema.definition(&mut parser)?;
+        }
+        Ok(schema)
+    }
+
+    /// Loads a schema split across SDL files
+    pub fn load(paths: &[PathBuf]) -> Result<Self, ValidationError> {
+        let mut source = String::new();
+        for path in paths {
+            let content = fs::read_to_string(path).map_err(|e| ValidationError::SpecParse {
+                reason: format!("{}: {}", path.display(), e),
+            })?;
+            source.push_str(&content);
+            source.push('\n');
+        }
+        Self::parse(&source).map_err(|e| ValidationError::SpecParse { reason: e.to_string() })
+    }
+
+    pub fn type_definition(&self, name: &str) -> Option<&TypeDefinition> {
+        self.types.get(name)
+    }
+
+    /// The field `name` of type `type_name`
+    pub fn field(&self, type_name: &str, name: &str) -> Option<&FieldDefinition> {
+        self.types.get(type_name)?.field(name)
+    }
+
+    pub fn query_type(&self) -> &str {
+        &self.query_type
+    }
+
+    pub fn mutation_type(&self) -> &str {
+        &self.mutation_type
+    }
+
+    pub fn subscription_type(&self) -> &str {
+        &self.subscription_type
+    }
+
+    /// Whether a value of object type `concrete` may stand for `abstract_type`
+    pub fn is_possible_type(&self, abstract_type: &str, concrete: &str) -> bool {
+        if abstract_type == concrete {
+            return true;
+        }
+        match self.types.get(abstract_type) {
+            Some(definition) if definition.kind == TypeKind::Union => definition.members.iter().any(|member| member == concrete),
+            Some(definition) if definition.kind == TypeKind::Interface => self
+                .types
+                .get(concrete)
+                .is_some_and(|concrete| concrete.interfaces.iter().any(|interface| interface == abstract_type)),
+            _ => false,
+        }
+    }
+
+    fn definition(&mut self, parser: &mut Parser) -> Result<(), ValidationError> {
+        parser.skip_description();
+        // Extensions add to the type they extend
+        parser.eat_name("extend");
+        let keyword = parser.expect_name()?;
+        let kind = match keyword.as_str() {
+            "schema" => return self.schema_definition(parser),
+            "directive" => return skip_directive_definition(parser),
+            "scalar" => TypeKind::Scalar,
+            "type" => TypeKind::Object,
+            "interface" => TypeKind::Interface,
+            "union" => TypeKind::Union,
+            "enum" => TypeKind::Enum,
+            "input" => TypeKind::InputObject,
+            other => return Err(parser.error(format!("unexpected '{}'", other))),
+        };
+        let name = parser.expect_name()?;
+        let definition = self
+            .types
+            .entry(name.clone())
+            .or_insert_with(|| TypeDefinition::new(name, kind));
+        if parser.eat_name("implements") {
+            parser.eat("&");
+            definition.interfaces.push(parser.expect_name()?);
+            while parser.eat("&") {
+                definition.interfaces.push(parser.expect_name()?);
+            }
+        }
+        parser.skip_directives()?;
+        match kind {
+            TypeKind::Object | TypeKind::Interface | TypeKind::InputObject if parser.eat("{") => {
+                while !parser.eat("}") {
+                    definition.fields.push(field_definition(parser)?);
+                }
+            }
+            TypeKind::Enum if parser.eat("{") => {
+                while !parser.eat("}") {
+                    parser.skip_description();
+                    definition.enum_values.push(parser.expect_name()?);
+                    parser.skip_directives()?;
+                }
+            }
+            TypeKind::Union if parser.eat("=") => {
+                parser.eat("|");
+                definition.members.push(parser.expect_name()?);
+                while parser.eat("|") {
+                    definition.members.push(parser.expect_name()?);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Reads `schema { query: Q mutation: M }`, renaming the root types
+    fn schema_definition(&mut self, parser: &mut Parser) -> Result<(), ValidationError> {
+        parser.skip_directives()?;
+        if !parser.eat("{") {
+            return Ok(());
+        }
+        while !parser.eat("}") {
+            let operation = parser.expect_name()?;
+            parser.expect(":")?;
+            let root = parser.expect_name()?;
+            match operation.as_str() {
+                "query" => self.query_type = root,
+                "mutation" => self.mutation_type = root,
+                "subscription" => self.subscription_type = root,
+                other => return Err(parser.error(format!("unknown operation type '{}'", other))),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads `name(arguments): Type @directives`, or an input field `name: Type = default`
+fn field_definition(parser: &mut Parser) -> Result<FieldDefinition, ValidationError> {
+    parser.skip_description();
+    let name = parser.expect_name()?;
+    let mut arguments = Vec::new();
+    if parser.eat("(") {
+        while !parser.eat(")") {
+            parser.skip_description();
+            arguments.push(parser.expect_name()?);
+            parser.expect(":")?;
+            type_ref(parser)?;
+            if parser.eat("=") {
+                parser.skip_value()?;
+            }
+            parser.skip_directives()?;
+        }
+    }
+    parser.expect(":")?;
+    let field_type = type_ref(parser)?;
+    if parser.eat("=") {
+        parser.skip_value()?;
+    }
+    let deprecated = parser.skip_directives()?;
+    Ok(FieldDefinition {
+        name,
+        arguments,
+        field_type,
+        deprecated,
+    })
+}
+
+fn type_ref(parser: &mut Parser) -> Result<TypeRef, ValidationError> {
+    let inner = if parser.eat("[") {
+        let item = type_ref(parser)?;
+        parser.expect("]")?;
+        TypeRef::List(Box::new(item))
+    } else {
+        TypeRef::Named(parser.expect_name()?)
+    };
+    Ok(match parser.eat("!") {
+        true => TypeRef::NonNull(Box::new(inner)),
+        false => inner,
+    })
+}
+
+/// Skips `directive @name(arguments) repeatable on LOCATION | LOCATION`
+fn skip_directive_definition(parser: &mut Parser) -> Result<(), ValidationError> {
+    parser.expect("@")?;
+    parser.expect_name()?;
+    parser.skip_parenthesized()?;
+    parser.eat_name("repeatable");
+    if !parser.eat_name("on") {
+        return Err(parser.error("expected 'on'"));
+    }
+    parser.eat("|");
+    parser.expect_name()?;
+    while parser.eat("|") {
+        parser.expect_name()?;
+    }
+    Ok(())
+}
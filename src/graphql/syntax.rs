@@ -0,0 +1,255 @@
+//! Tokens shared by the SDL and query parsers
+
+use crate::error::ValidationError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    Name(String),
+    /// One of `! $ & ( ) : = @ [ ] { | }`, or `...`
+    Punctuator(&'static str),
+    String(String),
+    Number(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Self::Name(name) => format!("'{}'", name),
+            Self::Punctuator(punctuator) => format!("'{}'", punctuator),
+            Self::String(_) => "a string".to_string(),
+            Self::Number(number) => format!("'{}'", number),
+        }
+    }
+}
+
+/// Splits GraphQL source into tokens with the line each starts on; commas
+/// and comments are dropped, as the grammar ignores them
+pub(super) fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ValidationError> {
+    const PUNCTUATORS: [&str; 13] = ["!", "$", "&", "(", ")", ":", "=", "@", "[", "]", "{", "|", "}"];
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            ',' => {}
+            c if c.is_whitespace() || c == '\u{feff}' => {}
+            '#' => {
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            }
+            '.' => {
+                if chars.next() != Some('.') || chars.next() != Some('.') {
+                    return Err(syntax_error(line, "expected '...'"));
+                }
+                tokens.push((Token::Punctuator("..."), line));
+            }
+            '"' => {
+                let start = line;
+                let block = chars.peek() == Some(&'"') && {
+                    chars.next();
+                    if chars.peek() == Some(&'"') {
+                        chars.next();
+                        true
+                    } else {
+                        // An empty string
+                        tokens.push((Token::String(String::new()), line));
+                        continue;
+                    }
+                };
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') if !block => break,
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            if chars.peek() == Some(&'"') {
+                                chars.next();
+                                break;
+                            }
+                            text.push_str("\"\"");
+                        }
+                        Some('\\') if !block => text.extend(chars.next()),
+                        Some('\n') if !block => return Err(syntax_error(line, "unterminated string")),
+                        Some(next) => {
+                            if next == '\n' {
+                                line += 1;
+                            }
+                            text.push(next);
+                        }
+                        None => return Err(syntax_error(start, "unterminated string")),
+                    }
+                }
+                tokens.push((Token::String(text), start));
+            }
+            c if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::from(c);
+                while let Some(next) = chars.next_if(|next| next.is_ascii_alphanumeric() || matches!(next, '.' | '+' | '-')) {
+                    number.push(next);
+                }
+                tokens.push((Token::Number(number), line));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::from(c);
+                while let Some(next) = chars.next_if(|next| next.is_ascii_alphanumeric() || *next == '_') {
+                    name.push(next);
+                }
+                tokens.push((Token::Name(name), line));
+            }
+            c => match PUNCTUATORS.iter().find(|punctuator| punctuator.starts_with(c)) {
+                Some(punctuator) => tokens.push((Token::Punctuator(punctuator), line)),
+                None => return Err(syntax_error(line, &format!("unexpected character '{}'", c))),
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+fn syntax_error(line: usize, message: &str) -> ValidationError {
+    ValidationError::ValidationFailed(format!("GraphQL syntax error on line {}: {}", line, message))
+}
+
+/// A cursor over tokens, with the helpers both grammars need
+pub(super) struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    pub(super) fn new(source: &str) -> Result<Self, ValidationError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            position: 0,
+        })
+    }
+
+    pub(super) fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    pub(super) fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position)?.0.clone();
+        self.position += 1;
+        Some(token)
+    }
+
+    pub(super) fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    pub(super) fn error(&self, message: impl AsRef<str>) -> ValidationError {
+        let line = self
+            .tokens
+            .get(self.position.min(self.tokens.len().saturating_sub(1)))
+            .map_or(0, |(_, line)| *line);
+        syntax_error(line, message.as_ref())
+    }
+
+    /// Whether the next token is `punctuator`
+    pub(super) fn at(&self, punctuator: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punctuator(next)) if *next == punctuator)
+    }
+
+    /// Consumes `punctuator` if it is next
+    pub(super) fn eat(&mut self, punctuator: &str) -> bool {
+        let at = self.at(punctuator);
+        if at {
+            self.position += 1;
+        }
+        at
+    }
+
+    pub(super) fn expect(&mut self, punctuator: &str) -> Result<(), ValidationError> {
+        if self.eat(punctuator) {
+            return Ok(());
+        }
+        let found = self.peek().map_or("end of input".to_string(), Token::describe);
+        Err(self.error(format!("expected '{}', found {}", punctuator, found)))
+    }
+
+    /// Whether the next token is the name `keyword`
+    pub(super) fn at_name(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(name)) if name == keyword)
+    }
+
+    /// Consumes the name `keyword` if it is next
+    pub(super) fn eat_name(&mut self, keyword: &str) -> bool {
+        let at = self.at_name(keyword);
+        if at {
+            self.position += 1;
+        }
+        at
+    }
+
+    pub(super) fn expect_name(&mut self) -> Result<String, ValidationError> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let name = name.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            found => {
+                let found = found.map_or("end of input".to_string(), Token::describe);
+                Err(self.error(format!("expected a name, found {}", found)))
+            }
+        }
+    }
+
+    /// Skips a description string if one is next
+    pub(super) fn skip_description(&mut self) {
+        if matches!(self.peek(), Some(Token::String(_))) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips a value: a scalar, a variable, or a list or object, nested
+    pub(super) fn skip_value(&mut self) -> Result<(), ValidationError> {
+        if self.eat("$") {
+            return self.expect_name().map(|_| ());
+        }
+        let close = match self.next() {
+            Some(Token::Punctuator("[")) => "]",
+            Some(Token::Punctuator("{")) => "}",
+            Some(Token::Punctuator(punctuator)) => return Err(self.error(format!("unexpected '{}' in value", punctuator))),
+            Some(_) => return Ok(()),
+            None => return Err(self.error("expected a value")),
+        };
+        while !self.eat(close) {
+            if self.at_end() {
+                return Err(self.error(format!("expected '{}'", close)));
+            }
+            if close == "}" {
+                self.expect_name()?;
+                self.expect(":")?;
+            }
+            self.skip_value()?;
+        }
+        Ok(())
+    }
+
+    /// Skips a parenthesized list, e.g. arguments or variable definitions
+    pub(super) fn skip_parenthesized(&mut self) -> Result<(), ValidationError> {
+        if !self.eat("(") {
+            return Ok(());
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next() {
+                Some(Token::Punctuator("(")) => depth += 1,
+                Some(Token::Punctuator(")")) => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.error("expected ')'")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips directives, returning whether `@deprecated` was among them
+    pub(super) fn skip_directives(&mut self) -> Result<bool, ValidationError> {
+        let mut deprecated = false;
+        while self.eat("@") {
+            deprecated |= self.expect_name()? == "deprecated";
+            self.skip_parenthesized()?;
+        }
+        Ok(deprecated)
+    }
+}
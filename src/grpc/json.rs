@@ -0,0 +1,196 @@
+//! Checks messages in the proto3 JSON mapping
+
+use super::proto::{FieldDescriptor, FieldType, MessageDescriptor, ProtoDescriptors, ScalarType};
+use super::wire::type_name;
+use super::{location, Direction, MAX_MESSAGE_DEPTH};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use serde_json::Value;
+
+pub(super) fn validate(
+    descriptors: &ProtoDescriptors,
+    message: &MessageDescriptor,
+    value: &Value,
+    direction: Direction,
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    let mut checker = Checker {
+        descriptors,
+        direction,
+        emit,
+        path: Vec::new(),
+    };
+    checker.message(message, value, 0);
+}
+
+struct Checker<'a, 'e> {
+    descriptors: &'a ProtoDescriptors,
+    direction: Direction,
+    emit: &'e mut dyn FnMut(DriftEvent),
+    path: Vec<String>,
+}
+
+impl Checker<'_, '_> {
+    fn message(&mut self, message: &MessageDescriptor, value: &Value, depth: usize) {
+        if depth > MAX_MESSAGE_DEPTH {
+            return;
+        }
+        let Value::Object(members) = value else {
+            self.drift(
+                self.direction.malformed(),
+                format!("{} message should be a JSON object, got {}", message.full_name, kind(value)),
+            );
+            return;
+        };
+        for (name, member) in members {
+            self.path.push(name.clone());
+            match message.field_by_json_name(name) {
+                Some(field) => self.field(field, member, depth),
+                None => self.drift(
+                    self.direction.unknown_field(),
+                    format!("{} has no field '{}'", message.full_name, name),
+                ),
+            }
+            self.path.pop();
+        }
+    }
+
+    fn field(&mut self, field: &FieldDescriptor, value: &Value, depth: usize) {
+        // null stands for the field's default
+        if value.is_null() {
+            return;
+        }
+        match (&field.field_type, value) {
+            (FieldType::Map(_, value_type), Value::Object(entries)) => {
+                for (key, entry) in entries {
+                    self.path.push(key.clone());
+                    self.value(field, value_type, entry, depth);
+                    self.path.pop();
+                }
+            }
+            (FieldType::Map(..), _) => self.mismatch(field, &field.field_type, value),
+            (field_type, Value::Array(items)) if field.repeated => {
+                for (index, item) in items.iter().enumerate() {
+                    self.path.push(index.to_string());
+                    self.value(field, field_type, item, depth);
+                    self.path.pop();
+                }
+            }
+            (field_type, _) if field.repeated => {
+                self.drift(
+                    self.direction.type_mismatch(),
+                    format!("Field '{}' is repeated {} but got {}", field.name, type_name(field_type), kind(value)),
+                );
+            }
+            (field_type, _) => self.value(field, field_type, value, depth),
+        }
+    }
+
+    fn value(&mut self, field: &FieldDescriptor, field_type: &FieldType, value: &Value, depth: usize) {
+        let matches = match field_type {
+            FieldType::Scalar(scalar) => scalar_matches(*scalar, value),
+            FieldType::Enum(name) => {
+                let declared = self.descriptors.enumeration(name).is_some_and(|enumeration| {
+                    enumeration.values.iter().any(|(declared_name, number)| match value {
+                        Value::String(text) => text == declared_name,
+                        Value::Number(_) => value.as_i64() == Some(i64::from(*number)),
+                        _ => false,
+                    })
+                });
+                if !declared && (value.is_string() || value.is_number()) {
+                    self.drift(
+                        self.direction.enum_violation(),
+                        format!("Field '{}' holds {}, which enum {} doesn't declare", field.name, value, name),
+                    );
+                    return;
+                }
+                declared
+            }
+            FieldType::Message(name) => {
+                match self.descriptors.message(name) {
+                    Some(message) if value.is_object() => self.message(message, value, depth + 1),
+                    Some(_) => self.mismatch(field, field_type, value),
+                    None => {}
+                }
+                return;
+            }
+            FieldType::WellKnown(name) => well_known_matches(name, value),
+            // Maps only hold scalars, enums and messages
+            FieldType::Map(..) => true,
+        };
+        if !matches {
+            self.mismatch(field, field_type, value);
+        }
+    }
+
+    fn mismatch(&mut self, field: &FieldDescriptor, field_type: &FieldType, value: &Value) {
+        self.drift(
+            self.direction.type_mismatch(),
+            format!("Field '{}' is declared {} but got {}", field.name, type_name(field_type), kind(value)),
+        );
+    }
+
+    fn drift(&mut self, drift_type: DriftType, message: String) {
+        (self.emit)(DriftEvent::new(drift_type, location(&self.path), message));
+    }
+}
+
+/// Whether `value` is a valid JSON encoding of `scalar`
+///
+/// Integers may be numbers or decimal strings, 64-bit ones usually being
+/// strings; floats may also be `"NaN"` and `"Infinity"`.
+fn scalar_matches(scalar: ScalarType, value: &Value) -> bool {
+    let (min, max): (i128, i128) = match scalar {
+        ScalarType::Bool => return value.is_boolean(),
+        ScalarType::String | ScalarType::Bytes => return value.is_string(),
+        ScalarType::Double | ScalarType::Float => {
+            return match value {
+                Value::Number(_) => true,
+                Value::String(text) => {
+                    matches!(text.as_str(), "NaN" | "Infinity" | "-Infinity") || text.parse::<f64>().is_ok()
+                }
+                _ => false,
+            }
+        }
+        ScalarType::Int32 | ScalarType::Sint32 | ScalarType::Sfixed32 => (i32::MIN.into(), i32::MAX.into()),
+        ScalarType::Uint32 | ScalarType::Fixed32 => (0, u32::MAX.into()),
+        ScalarType::Int64 | ScalarType::Sint64 | ScalarType::Sfixed64 => (i64::MIN.into(), i64::MAX.into()),
+        ScalarType::Uint64 | ScalarType::Fixed64 => (0, u64::MAX.into()),
+    };
+    let integer = match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| number.as_u64().map(i128::from))
+            .or_else(|| number.as_f64().filter(|float| float.fract() == 0.0).map(|float| float as i128)),
+        Value::String(text) => text.parse::<i128>().ok(),
+        _ => None,
+    };
+    integer.is_some_and(|integer| (min..=max).contains(&integer))
+}
+
+/// Loose checks for well-known types, whose JSON forms are special-cased
+fn well_known_matches(name: &str, value: &Value) -> bool {
+    match name.trim_start_matches("google.protobuf.") {
+        "Timestamp" | "Duration" | "FieldMask" => value.is_string(),
+        "Struct" | "Any" | "Empty" => value.is_object(),
+        "ListValue" => value.is_array(),
+        "BoolValue" => value.is_boolean(),
+        "StringValue" | "BytesValue" => value.is_string(),
+        "DoubleValue" | "FloatValue" | "Int32Value" | "Int64Value" | "UInt32Value" | "UInt64Value" => {
+            value.is_number() || value.is_string()
+        }
+        _ => true,
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
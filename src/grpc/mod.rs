@@ -0,0 +1,315 @@
+//! Drift monitoring for gRPC services
+//!
+//! A [`GrpcValidator`] checks messages observed by interceptors or logged
+//! by proxies against the message types `.proto` files declare, in binary
+//! wire format or the proto3 JSON mapping. It reports fields the type
+//! doesn't declare, values of the wrong type, undeclared enum values and
+//! calls to undeclared methods as [`DriftEvent`]s, tagged with the method's
+//! path (`/shop.v1.Orders/Create`) as both path and template, so reports,
+//! sinks and baselines handle them like HTTP drift.
+//!
+//! Descriptors come from `.proto` sources only; server reflection is not
+//! supported.
+
+pub mod proto;
+mod json;
+mod wire;
+
+pub use proto::{
+    EnumDescriptor, FieldDescriptor, FieldType, MessageDescriptor, MethodDescriptor, ProtoDescriptors, ScalarType,
+    ServiceDescriptor,
+};
+
+use crate::api_validator::HttpMethod;
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use crate::traffic::{IngestSummary, MAX_KEPT_ERRORS};
+use crate::validation_helpers::gather_drift_events;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+/// Nesting depth past which message fields aren't followed
+const MAX_MESSAGE_DEPTH: usize = 64;
+
+/// Which side of a call a message was sent by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+impl Direction {
+    fn malformed(self) -> DriftType {
+        match self {
+            Self::Request => DriftType::GrpcRequestMalformed,
+            Self::Response => DriftType::GrpcResponseMalformed,
+        }
+    }
+
+    fn unknown_field(self) -> DriftType {
+        match self {
+            Self::Request => DriftType::GrpcRequestUnknownField,
+            Self::Response => DriftType::GrpcResponseUnknownField,
+        }
+    }
+
+    fn type_mismatch(self) -> DriftType {
+        match self {
+            Self::Request => DriftType::GrpcRequestTypeMismatch,
+            Self::Response => DriftType::GrpcResponseTypeMismatch,
+        }
+    }
+
+    fn enum_violation(self) -> DriftType {
+        match self {
+            Self::Request => DriftType::GrpcRequestEnumViolation,
+            Self::Response => DriftType::GrpcResponseEnumViolation,
+        }
+    }
+}
+
+/// One message as observed
+#[derive(Debug, Clone, PartialEq)]
+pub enum GrpcMessage {
+    /// Protobuf wire format, without gRPC's length prefix
+    Binary(Vec<u8>),
+    /// The proto3 JSON mapping
+    Json(Value),
+}
+
+impl GrpcMessage {
+    /// Splits a gRPC body into its length-prefixed messages
+    ///
+    /// Compressed messages can't be checked and are refused.
+    pub fn unframe(body: &[u8]) -> Result<Vec<GrpcMessage>, ValidationError> {
+        let mut messages = Vec::new();
+        let mut rest = body;
+        while !rest.is_empty() {
+            let Some((header, after)) = rest.split_first_chunk::<5>() else {
+                return Err(ValidationError::TrafficError("Truncated gRPC message prefix".to_string()));
+            };
+            if header[0] != 0 {
+                return Err(ValidationError::TrafficError("Compressed gRPC messages aren't supported".to_string()));
+            }
+            let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            if after.len() < length {
+                return Err(ValidationError::TrafficError(format!(
+                    "gRPC message declares {} bytes but {} remain",
+                    length,
+                    after.len()
+                )));
+            }
+            messages.push(GrpcMessage::Binary(after[..length].to_vec()));
+            rest = &after[length..];
+        }
+        Ok(messages)
+    }
+}
+
+/// One observed call: its method and the messages each side sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrpcExchange {
+    /// The method's path, e.g. `/shop.v1.Orders/Create`
+    pub method: String,
+    /// Request messages, one for unary calls or several for client streams
+    pub requests: Vec<GrpcMessage>,
+    pub responses: Vec<GrpcMessage>,
+}
+
+impl GrpcExchange {
+    pub fn new(method: impl Into<String>) -> Self {
+        Self {
+            method: method.into(),
+            requests: Vec::new(),
+            responses: Vec::new(),
+        }
+    }
+
+    pub fn with_request(mut self, message: GrpcMessage) -> Self {
+        self.requests.push(message);
+        self
+    }
+
+    pub fn with_response(mut self, message: GrpcMessage) -> Self {
+        self.responses.push(message);
+        self
+    }
+
+    /// Reads a call logged as one JSONL line
+    ///
+    /// `request` and `response` hold a message in the JSON mapping, or an
+    /// array of them for streams; `request_base64` and `response_base64`
+    /// hold wire-format messages the same way, length-prefixed as on the
+    /// wire when `framed` is set.
+    pub fn from_json_line(line: &str) -> Result<Self, ValidationError> {
+        let record: GrpcRecord = serde_json::from_str(line)
+            .map_err(|e| ValidationError::TrafficError(format!("Invalid gRPC traffic record: {}", e)))?;
+        let mut exchange = Self::new(record.method);
+        exchange.requests = record_messages(record.request, record.request_base64, record.framed)?;
+        exchange.responses = record_messages(record.response, record.response_base64, record.framed)?;
+        Ok(exchange)
+    }
+}
+
+#[derive(Deserialize)]
+struct GrpcRecord {
+    method: String,
+    #[serde(default)]
+    request: Option<Value>,
+    #[serde(default)]
+    response: Option<Value>,
+    #[serde(default)]
+    request_base64: Option<Value>,
+    #[serde(default)]
+    response_base64: Option<Value>,
+    #[serde(default)]
+    framed: bool,
+}
+
+fn record_messages(json: Option<Value>, base64: Option<Value>, framed: bool) -> Result<Vec<GrpcMessage>, ValidationError> {
+    let mut messages: Vec<GrpcMessage> = match json {
+        Some(Value::Array(items)) => items.into_iter().map(GrpcMessage::Json).collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(message) => vec![GrpcMessage::Json(message)],
+    };
+    let encoded = match base64 {
+        Some(Value::Array(items)) => items,
+        Some(Value::Null) | None => Vec::new(),
+        Some(message) => vec![message],
+    };
+    for item in encoded {
+        let bytes = item
+            .as_str()
+            .and_then(|text| STANDARD.decode(text).ok())
+            .ok_or_else(|| ValidationError::TrafficError(format!("Invalid base64 message: {}", item)))?;
+        match framed {
+            true => messages.extend(GrpcMessage::unframe(&bytes)?),
+            false => messages.push(GrpcMessage::Binary(bytes)),
+        }
+    }
+    Ok(messages)
+}
+
+/// Checks gRPC calls against the services and messages `.proto` files declare
+pub struct GrpcValidator {
+    descriptors: ProtoDescriptors,
+}
+
+impl GrpcValidator {
+    pub fn new(descriptors: ProtoDescriptors) -> Self {
+        Self { descriptors }
+    }
+
+    /// Loads `files` and their imports, looked up in `include_paths`
+    pub fn from_files(files: &[PathBuf], include_paths: &[PathBuf]) -> Result<Self, ValidationError> {
+        Ok(Self::new(ProtoDescriptors::load(files, include_paths)?))
+    }
+
+    pub fn descriptors(&self) -> &ProtoDescriptors {
+        &self.descriptors
+    }
+
+    /// Validates every message of a call against the method's message types
+    pub fn validate_exchange(&self, exchange: &GrpcExchange) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.validate_exchange_with(exchange, &EventContext::new(), emit))
+    }
+
+    /// Like [`Self::validate_exchange`], attaching `context` to every event
+    /// and passing each to `emit` as it is found
+    pub fn validate_exchange_with(&self, exchange: &GrpcExchange, context: &EventContext, emit: &mut dyn FnMut(DriftEvent)) {
+        let method = exchange.method.as_str();
+        let mut emit = |event: DriftEvent| emit(event.with_operation(HttpMethod::POST, method, method).with_context(context));
+        let Some(descriptor) = self.descriptors.method(method) else {
+            emit(DriftEvent::new(
+                DriftType::GrpcUnknownMethod,
+                "method",
+                format!("Called {}, which no loaded service declares", method),
+            ));
+            return;
+        };
+        let sides = [
+            (Direction::Request, &descriptor.input, &exchange.requests),
+            (Direction::Response, &descriptor.output, &exchange.responses),
+        ];
+        for (direction, message_type, messages) in sides {
+            // Well-known types without a loaded definition aren't checked
+            let Some(message_type) = self.descriptors.message(message_type) else {
+                continue;
+            };
+            for message in messages {
+                self.validate_with(message_type, message, direction, &mut emit);
+            }
+        }
+    }
+
+    /// Validates one message as the type named `message_type`, e.g. `shop.v1.Order`
+    pub fn validate_message(
+        &self,
+        message_type: &str,
+        message: &GrpcMessage,
+        direction: Direction,
+    ) -> Result<Vec<DriftEvent>, ValidationError> {
+        let descriptor = self.descriptors.message(message_type).ok_or_else(|| {
+            ValidationError::ValidationFailed(format!("Unknown message type {}", message_type))
+        })?;
+        Ok(gather_drift_events(|emit| self.validate_with(descriptor, message, direction, emit)))
+    }
+
+    fn validate_with(
+        &self,
+        descriptor: &MessageDescriptor,
+        message: &GrpcMessage,
+        direction: Direction,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        match message {
+            GrpcMessage::Binary(bytes) => wire::validate(&self.descriptors, descriptor, bytes, direction, emit),
+            GrpcMessage::Json(value) => json::validate(&self.descriptors, descriptor, value, direction, emit),
+        }
+    }
+}
+
+/// Validates every call logged in `reader`, one JSON line each as read by
+/// [`GrpcExchange::from_json_line`], sending drift to `sink` and
+/// aggregating it per method
+///
+/// Malformed lines are skipped and counted; read and sink failures are returned.
+pub fn ingest(validator: &GrpcValidator, reader: impl BufRead, sink: &dyn DriftSink) -> Result<IngestSummary, ValidationError> {
+    let mut summary = IngestSummary::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| ValidationError::TrafficError(format!("line {}: {}", index + 1, e)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.records += 1;
+        let exchange = match GrpcExchange::from_json_line(&line) {
+            Ok(exchange) => exchange,
+            Err(e) => {
+                summary.malformed += 1;
+                if summary.errors.len() < MAX_KEPT_ERRORS {
+                    summary.errors.push(format!("line {}: {}", index + 1, e));
+                }
+                continue;
+            }
+        };
+        let events = validator.validate_exchange(&exchange);
+        sink.record_all(events.clone())?;
+        summary.report.record_exchange(HttpMethod::POST, &exchange.method, events);
+    }
+    sink.flush()?;
+    Ok(summary)
+}
+
+/// The event location of a field path, e.g. `body/items/0/sku`
+fn location(path: &[String]) -> String {
+    match path.is_empty() {
+        true => "body".to_string(),
+        false => format!("body/{}", path.join("/")),
+    }
+}
@@ -0,0 +1,726 @@
+//! Message and service definitions read from `.proto` files
+//!
+//! Covers what validating messages needs: packages, messages (nested, with
+//! `oneof` and `map` fields), enums and services. Options, reservations and
+//! extensions are skipped. Imports are followed through the include paths;
+//! `google/protobuf/*` imports that can't be found are assumed to be the
+//! well-known types, which are validated loosely.
+
+use crate::error::ValidationError;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Scalar field types, as written in `.proto` files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarType {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "double" => Self::Double,
+            "float" => Self::Float,
+            "int32" => Self::Int32,
+            "int64" => Self::Int64,
+            "uint32" => Self::Uint32,
+            "uint64" => Self::Uint64,
+            "sint32" => Self::Sint32,
+            "sint64" => Self::Sint64,
+            "fixed32" => Self::Fixed32,
+            "fixed64" => Self::Fixed64,
+            "sfixed32" => Self::Sfixed32,
+            "sfixed64" => Self::Sfixed64,
+            "bool" => Self::Bool,
+            "string" => Self::String,
+            "bytes" => Self::Bytes,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int32 => "int32",
+            Self::Int64 => "int64",
+            Self::Uint32 => "uint32",
+            Self::Uint64 => "uint64",
+            Self::Sint32 => "sint32",
+            Self::Sint64 => "sint64",
+            Self::Fixed32 => "fixed32",
+            Self::Fixed64 => "fixed64",
+            Self::Sfixed32 => "sfixed32",
+            Self::Sfixed64 => "sfixed64",
+            Self::Bool => "bool",
+            Self::String => "string",
+            Self::Bytes => "bytes",
+        }
+    }
+}
+
+/// The type of a field's values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Scalar(ScalarType),
+    /// A message, by fully qualified name without the leading dot
+    Message(String),
+    Enum(String),
+    /// A `google.protobuf.*` type whose definition wasn't loaded
+    WellKnown(String),
+    Map(ScalarType, Box<FieldType>),
+}
+
+/// A field of a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    pub name: String,
+    /// Name in the JSON mapping: `json_name`, or the name in lowerCamelCase
+    pub json_name: String,
+    pub number: u32,
+    pub field_type: FieldType,
+    pub repeated: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageDescriptor {
+    pub full_name: String,
+    pub fields: Vec<FieldDescriptor>,
+}
+
+impl MessageDescriptor {
+    pub fn field_by_number(&self, number: u32) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.number == number)
+    }
+
+    /// The field a JSON member names, by its JSON name or its declared name
+    pub fn field_by_json_name(&self, name: &str) -> Option<&FieldDescriptor> {
+        self.fields.iter().find(|field| field.json_name == name || field.name == name)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnumDescriptor {
+    pub full_name: String,
+    pub values: Vec<(String, i32)>,
+}
+
+/// A method of a service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodDescriptor {
+    pub name: String,
+    /// Fully qualified request message type
+    pub input: String,
+    /// Fully qualified response message type
+    pub output: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceDescriptor {
+    pub full_name: String,
+    pub methods: Vec<MethodDescriptor>,
+}
+
+/// Every message, enum and service of a set of `.proto` files
+#[derive(Debug, Clone, Default)]
+pub struct ProtoDescriptors {
+    messages: BTreeMap<String, MessageDescriptor>,
+    enums: BTreeMap<String, EnumDescriptor>,
+    services: BTreeMap<String, ServiceDescriptor>,
+}
+
+impl ProtoDescriptors {
+    /// Parses a single `.proto` source, ignoring its imports
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let mut parsed = Vec::new();
+        parse_file(source, &mut parsed)?;
+        Self::resolve(parsed)
+    }
+
+    /// Loads `files` and the files they import, looked up next to the
+    /// importing file and then in `include_paths`
+    pub fn load(files: &[PathBuf], include_paths: &[PathBuf]) -> Result<Self, ValidationError> {
+        let mut queue: VecDeque<PathBuf> = files.iter().cloned().collect();
+        let mut loaded: Vec<PathBuf> = Vec::new();
+        let mut parsed = Vec::new();
+        while let Some(path) = queue.pop_front() {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if loaded.contains(&canonical) {
+                continue;
+            }
+            let source = fs::read_to_string(&path).map_err(|e| proto_error(&path, e))?;
+            let imports = parse_file(&source, &mut parsed).map_err(|e| proto_error(&path, e))?;
+            for import in imports {
+                let directories = path.parent().map(Path::to_path_buf).into_iter().chain(include_paths.iter().cloned());
+                match directories.map(|directory| directory.join(&import)).find(|candidate| candidate.is_file()) {
+                    Some(found) => queue.push_back(found),
+                    None if import.starts_with("google/protobuf/") => {}
+                    None => {
+                        return Err(ValidationError::SpecParse {
+                            reason: format!("{}: import \"{}\" not found", path.display(), import),
+                        })
+                    }
+                }
+            }
+            loaded.push(canonical);
+        }
+        Self::resolve(parsed)
+    }
+
+    pub fn message(&self, full_name: &str) -> Option<&MessageDescriptor> {
+        self.messages.get(full_name.trim_start_matches('.'))
+    }
+
+    pub fn enumeration(&self, full_name: &str) -> Option<&EnumDescriptor> {
+        self.enums.get(full_name.trim_start_matches('.'))
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &ServiceDescriptor> {
+        self.services.values()
+    }
+
+    /// The method a gRPC path such as `/shop.v1.Orders/Create` calls
+    pub fn method(&self, path: &str) -> Option<&MethodDescriptor> {
+        let (service, method) = path.trim_start_matches('/').split_once('/')?;
+        self.services.get(service)?.methods.iter().find(|candidate| candidate.name == method)
+    }
+
+    /// Resolves every type reference against the parsed definitions
+    fn resolve(parsed: Vec<Definition>) -> Result<Self, ValidationError> {
+        let mut descriptors = Self::default();
+        let mut kinds: BTreeMap<String, bool> = BTreeMap::new();
+        for definition in &parsed {
+            match definition {
+                Definition::Message { full_name, .. } => kinds.insert(full_name.clone(), true),
+                Definition::Enum(enumeration) => kinds.insert(enumeration.full_name.clone(), false),
+                Definition::Service { .. } => None,
+            };
+        }
+        let resolve = |scope: &str, name: &str| -> Result<FieldType, ValidationError> {
+            if let Some(scalar) = ScalarType::parse(name) {
+                return Ok(FieldType::Scalar(scalar));
+            }
+            let full_name = lookup(&kinds, scope, name).ok_or_else(|| ValidationError::SpecParse {
+                reason: format!("unknown type {} referenced in {}", name, scope),
+            });
+            match full_name {
+                Ok(full_name) if kinds[&full_name] => Ok(FieldType::Message(full_name)),
+                Ok(full_name) => Ok(FieldType::Enum(full_name)),
+                Err(_) if name.trim_start_matches('.').starts_with("google.protobuf.") => {
+                    Ok(FieldType::WellKnown(name.trim_start_matches('.').to_string()))
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        for definition in parsed {
+            match definition {
+                Definition::Message { full_name, fields } => {
+                    let fields = fields
+                        .into_iter()
+                        .map(|field| {
+                            let field_type = match &field.map_key {
+                                Some(key) => {
+                                    let key = ScalarType::parse(key).ok_or_else(|| ValidationError::SpecParse {
+                                        reason: format!("invalid map key type {} in {}", key, full_name),
+                                    })?;
+                                    FieldType::Map(key, Box::new(resolve(&full_name, &field.type_name)?))
+                                }
+                                None => resolve(&full_name, &field.type_name)?,
+                            };
+                            Ok(FieldDescriptor {
+                                json_name: field.json_name.unwrap_or_else(|| lower_camel_case(&field.name)),
+                                name: field.name,
+                                number: field.number,
+                                field_type,
+                                repeated: field.repeated,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, ValidationError>>()?;
+                    descriptors.messages.insert(full_name.clone(), MessageDescriptor { full_name, fields });
+                }
+                Definition::Enum(enumeration) => {
+                    descriptors.enums.insert(enumeration.full_name.clone(), enumeration);
+                }
+                Definition::Service { full_name, methods } => {
+                    let methods = methods
+                        .into_iter()
+                        .map(|method| {
+                            let message_name = |name: &str| match resolve(&full_name, name)? {
+                                FieldType::Message(name) | FieldType::WellKnown(name) => Ok(name),
+                                _ => Err(ValidationError::SpecParse {
+                                    reason: format!("{}.{} takes {}, which isn't a message", full_name, method.name, name),
+                                }),
+                            };
+                            Ok(MethodDescriptor {
+                                input: message_name(&method.input)?,
+                                output: message_name(&method.output)?,
+                                name: method.name,
+                                client_streaming: method.client_streaming,
+                                server_streaming: method.server_streaming,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, ValidationError>>()?;
+                    descriptors.services.insert(full_name.clone(), ServiceDescriptor { full_name, methods });
+                }
+            }
+        }
+        Ok(descriptors)
+    }
+}
+
+fn proto_error(path: &Path, error: impl std::fmt::Display) -> ValidationError {
+    ValidationError::SpecParse {
+        reason: format!("{}: {}", path.display(), error),
+    }
+}
+
+/// Finds `name` as protobuf scoping does: in `scope`, then each enclosing scope
+fn lookup(kinds: &BTreeMap<String, bool>, scope: &str, name: &str) -> Option<String> {
+    if let Some(absolute) = name.strip_prefix('.') {
+        return kinds.contains_key(absolute).then(|| absolute.to_string());
+    }
+    let mut scope = scope;
+    loop {
+        let candidate = if scope.is_empty() { name.to_string() } else { format!("{}.{}", scope, name) };
+        if kinds.contains_key(&candidate) {
+            return Some(candidate);
+        }
+        if scope.is_empty() {
+            return None;
+        }
+        scope = scope.rfind('.').map_or("", |end| &scope[..end]);
+    }
+}
+
+/// `snake_case` as `snakeCase`, the JSON mapping's default field name
+fn lower_camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// A definition as parsed, its type references not yet resolved
+enum Definition {
+    Message { full_name: String, fields: Vec<ParsedField> },
+    Enum(EnumDescriptor),
+    Service { full_name: String, methods: Vec<ParsedMethod> },
+}
+
+struct ParsedField {
+    name: String,
+    json_name: Option<String>,
+    number: u32,
+    type_name: String,
+    map_key: Option<String>,
+    repeated: bool,
+}
+
+struct ParsedMethod {
+    name: String,
+    input: String,
+    output: String,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+/// Parses one file's definitions into `parsed`, returning its imports
+fn parse_file(source: &str, parsed: &mut Vec<Definition>) -> Result<Vec<String>, ValidationError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    let mut package = String::new();
+    let mut imports = Vec::new();
+    while let Some(token) = parser.next() {
+        match token.as_str() {
+            "syntax" | "edition" | "option" => parser.skip_statement(),
+            "package" => {
+                package = parser.expect_word()?;
+                parser.expect(";")?;
+            }
+            "import" => {
+                if matches!(parser.peek(), Some("public" | "weak")) {
+                    parser.next();
+                }
+                imports.push(parser.expect_string()?);
+                parser.expect(";")?;
+            }
+            "message" => parser.message(&package, parsed)?,
+            "enum" => parsed.push(Definition::Enum(parser.enumeration(&package)?)),
+            "service" => parser.service(&package, parsed)?,
+            "extend" => parser.skip_block()?,
+            ";" => {}
+            other => return Err(parser.error(format!("unexpected '{}'", other))),
+        }
+    }
+    Ok(imports)
+}
+
+struct Parser {
+    /// Tokens with the line they start on
+    tokens: Vec<(String, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.position)?.0.clone();
+        self.position += 1;
+        Some(token)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(|(token, _)| token.as_str())
+    }
+
+    fn error(&self, message: String) -> ValidationError {
+        let line = self
+            .tokens
+            .get(self.position.saturating_sub(1))
+            .map_or(0, |(_, line)| *line);
+        ValidationError::SpecParse {
+            reason: format!("line {}: {}", line, message),
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), ValidationError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(self.error(format!("expected '{}', found '{}'", expected, token))),
+            None => Err(self.error(format!("expected '{}' before end of file", expected))),
+        }
+    }
+
+    fn expect_word(&mut self) -> Result<String, ValidationError> {
+        match self.next() {
+            Some(token) if token.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.') => Ok(token),
+            Some(token) => Err(self.error(format!("expected a name, found '{}'", token))),
+            None => Err(self.error("expected a name before end of file".to_string())),
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, ValidationError> {
+        match self.next() {
+            Some(token) if token.starts_with('"') => Ok(token.trim_matches('"').to_string()),
+            Some(token) => Err(self.error(format!("expected a string, found '{}'", token))),
+            None => Err(self.error("expected a string before end of file".to_string())),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<i64, ValidationError> {
+        let token = self.next().unwrap_or_default();
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits.to_string()),
+            None => (false, token.clone()),
+        };
+        let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        }
+        .map_err(|_| self.error(format!("expected a number, found '{}'", token)))?;
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Skips to the end of a statement, including any `{ ... }` aggregate in it
+    fn skip_statement(&mut self) {
+        let mut depth = 0usize;
+        while let Some(token) = self.next() {
+            match token.as_str() {
+                "{" => depth += 1,
+                "}" => depth = depth.saturating_sub(1),
+                ";" if depth == 0 => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Skips a name and its `{ ... }` body
+    fn skip_block(&mut self) -> Result<(), ValidationError> {
+        while self.peek().is_some_and(|token| token != "{") {
+            self.next();
+        }
+        self.expect("{")?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next().as_deref() {
+                Some("{") => depth += 1,
+                Some("}") => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.error("unterminated block".to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a `[ ... ]` option list, returning its `json_name` if set
+    fn field_options(&mut self) -> Result<Option<String>, ValidationError> {
+        if self.peek() != Some("[") {
+            return Ok(None);
+        }
+        self.next();
+        let mut json_name = None;
+        loop {
+            match self.next().as_deref() {
+                Some("]") => return Ok(json_name),
+                Some("json_name") => {
+                    self.expect("=")?;
+                    json_name = Some(self.expect_string()?);
+                }
+                Some(_) => {}
+                None => return Err(self.error("unterminated field options".to_string())),
+            }
+        }
+    }
+
+    fn message(&mut self, scope: &str, parsed: &mut Vec<Definition>) -> Result<(), ValidationError> {
+        let full_name = qualify(scope, &self.expect_word()?);
+        self.expect("{")?;
+        let mut fields = Vec::new();
+        loop {
+            let Some(token) = self.next() else {
+                return Err(self.error(format!("message {} is not closed", full_name)));
+            };
+            match token.as_str() {
+                "}" => break,
+                ";" => {}
+                "message" => self.message(&full_name, parsed)?,
+                "enum" => parsed.push(Definition::Enum(self.enumeration(&full_name)?)),
+                "option" | "reserved" | "extensions" => self.skip_statement(),
+                "extend" => self.skip_block()?,
+                "oneof" => {
+                    self.expect_word()?;
+                    self.expect("{")?;
+                    while self.peek() != Some("}") {
+                        match self.peek() {
+                            Some("option") => self.skip_statement(),
+                            Some(";") => {
+                                self.next();
+                            }
+                            Some(_) => {
+                                let type_name = self.expect_word()?;
+                                fields.push(self.field(type_name, None, false)?);
+                            }
+                            None => return Err(self.error("oneof is not closed".to_string())),
+                        }
+                    }
+                    self.next();
+                }
+                "map" => {
+                    self.expect("<")?;
+                    let key = self.expect_word()?;
+                    self.expect(",")?;
+                    let value = self.expect_word()?;
+                    self.expect(">")?;
+                    fields.push(self.field(value, Some(key), false)?);
+                }
+                "repeated" => {
+                    let type_name = self.expect_word()?;
+                    fields.push(self.field(type_name, None, true)?);
+                }
+                "optional" | "required" => {
+                    let type_name = self.expect_word()?;
+                    fields.push(self.field(type_name, None, false)?);
+                }
+                _ => fields.push(self.field(token, None, false)?),
+            }
+        }
+        parsed.push(Definition::Message { full_name, fields });
+        Ok(())
+    }
+
+    /// Reads `name = number [options];` after a field's type
+    fn field(&mut self, type_name: String, map_key: Option<String>, repeated: bool) -> Result<ParsedField, ValidationError> {
+        let name = self.expect_word()?;
+        self.expect("=")?;
+        let number = self.expect_number()?;
+        let number = u32::try_from(number).map_err(|_| self.error(format!("invalid field number {}", number)))?;
+        let json_name = self.field_options()?;
+        self.expect(";")?;
+        Ok(ParsedField {
+            name,
+            json_name,
+            number,
+            type_name,
+            map_key,
+            repeated,
+        })
+    }
+
+    fn enumeration(&mut self, scope: &str) -> Result<EnumDescriptor, ValidationError> {
+        let full_name = qualify(scope, &self.expect_word()?);
+        self.expect("{")?;
+        let mut values = Vec::new();
+        loop {
+            let Some(token) = self.next() else {
+                return Err(self.error(format!("enum {} is not closed", full_name)));
+            };
+            match token.as_str() {
+                "}" => break,
+                ";" => {}
+                "option" | "reserved" => self.skip_statement(),
+                _ => {
+                    self.expect("=")?;
+                    let number = self.expect_number()?;
+                    let number = i32::try_from(number).map_err(|_| self.error(format!("invalid enum value {}", number)))?;
+                    self.field_options()?;
+                    self.expect(";")?;
+                    values.push((token, number));
+                }
+            }
+        }
+        Ok(EnumDescriptor { full_name, values })
+    }
+
+    fn service(&mut self, scope: &str, parsed: &mut Vec<Definition>) -> Result<(), ValidationError> {
+        let full_name = qualify(scope, &self.expect_word()?);
+        self.expect("{")?;
+        let mut methods = Vec::new();
+        loop {
+            let Some(token) = self.next() else {
+                return Err(self.error(format!("service {} is not closed", full_name)));
+            };
+            match token.as_str() {
+                "}" => break,
+                ";" => {}
+                "option" => self.skip_statement(),
+                "rpc" => {
+                    let name = self.expect_word()?;
+                    let (input, client_streaming) = self.rpc_message()?;
+                    self.expect("returns")?;
+                    let (output, server_streaming) = self.rpc_message()?;
+                    match self.peek() {
+                        Some("{") => self.skip_block()?,
+                        _ => self.expect(";")?,
+                    }
+                    methods.push(ParsedMethod {
+                        name,
+                        input,
+                        output,
+                        client_streaming,
+                        server_streaming,
+                    });
+                }
+                other => return Err(self.error(format!("unexpected '{}' in service {}", other, full_name))),
+            }
+        }
+        parsed.push(Definition::Service { full_name, methods });
+        Ok(())
+    }
+
+    /// Reads `( [stream] Type )`
+    fn rpc_message(&mut self) -> Result<(String, bool), ValidationError> {
+        self.expect("(")?;
+        let mut name = self.expect_word()?;
+        let streaming = name == "stream" && self.peek() != Some(")");
+        if streaming {
+            name = self.expect_word()?;
+        }
+        self.expect(")")?;
+        Ok((name, streaming))
+    }
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+/// Splits a `.proto` source into words, numbers, strings and symbols, dropping comments
+fn tokenize(source: &str) -> Result<Vec<(String, usize)>, ValidationError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' => {
+                let mut string = String::from('"');
+                let mut escaped = false;
+                loop {
+                    match chars.next() {
+                        Some(next) if escaped => {
+                            string.push(next);
+                            escaped = false;
+                        }
+                        Some('\\') => escaped = true,
+                        Some(next) if next == c => break,
+                        Some(next) => string.push(next),
+                        None => {
+                            return Err(ValidationError::SpecParse {
+                                reason: format!("line {}: unterminated string", line),
+                            })
+                        }
+                    }
+                }
+                string.push('"');
+                tokens.push((string, line));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || c == '+' => {
+                let mut word = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_' || next == '.') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                tokens.push((word, line));
+            }
+            c => tokens.push((c.to_string(), line)),
+        }
+    }
+    Ok(tokens)
+}
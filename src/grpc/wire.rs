@@ -0,0 +1,282 @@
+//! Checks messages in protobuf's binary wire format
+
+use super::proto::{FieldDescriptor, FieldType, MessageDescriptor, ProtoDescriptors, ScalarType};
+use super::{location, Direction, MAX_MESSAGE_DEPTH};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use std::collections::HashMap;
+
+const VARINT: u8 = 0;
+const FIXED64: u8 = 1;
+const LENGTH_DELIMITED: u8 = 2;
+const FIXED32: u8 = 5;
+
+pub(super) fn validate(
+    descriptors: &ProtoDescriptors,
+    message: &MessageDescriptor,
+    bytes: &[u8],
+    direction: Direction,
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    let mut checker = Checker {
+        descriptors,
+        direction,
+        emit,
+        path: Vec::new(),
+    };
+    if let Err(reason) = checker.message(message, bytes, 0) {
+        let event = DriftEvent::new(
+            direction.malformed(),
+            location(&checker.path),
+            format!("{} message doesn't decode: {}", message.full_name, reason),
+        );
+        (checker.emit)(event);
+    }
+}
+
+/// A field's value as read off the wire, fixed-width values unread
+enum Wire<'b> {
+    Varint(u64),
+    Fixed64,
+    Fixed32,
+    LengthDelimited(&'b [u8]),
+}
+
+impl Wire<'_> {
+    fn wire_type(&self) -> u8 {
+        match self {
+            Self::Varint(_) => VARINT,
+            Self::Fixed64 => FIXED64,
+            Self::Fixed32 => FIXED32,
+            Self::LengthDelimited(_) => LENGTH_DELIMITED,
+        }
+    }
+}
+
+fn wire_type_name(wire_type: u8) -> &'static str {
+    match wire_type {
+        VARINT => "varint",
+        FIXED64 => "64-bit",
+        FIXED32 => "32-bit",
+        _ => "length-delimited",
+    }
+}
+
+/// The wire type a scalar is encoded with, unpacked
+fn scalar_wire_type(scalar: ScalarType) -> u8 {
+    match scalar {
+        ScalarType::Double | ScalarType::Fixed64 | ScalarType::Sfixed64 => FIXED64,
+        ScalarType::Float | ScalarType::Fixed32 | ScalarType::Sfixed32 => FIXED32,
+        ScalarType::String | ScalarType::Bytes => LENGTH_DELIMITED,
+        _ => VARINT,
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+}
+
+impl<'b> Reader<'b> {
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for (index, byte) in self.bytes.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * index);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[index + 1..];
+                return Ok(value);
+            }
+        }
+        Err("truncated or overlong varint".to_string())
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'b [u8], String> {
+        if self.bytes.len() < length {
+            return Err(format!("{} bytes expected but {} remain", length, self.bytes.len()));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn value(&mut self, wire_type: u8) -> Result<Wire<'b>, String> {
+        match wire_type {
+            VARINT => self.varint().map(Wire::Varint),
+            FIXED64 => self.take(8).map(|_| Wire::Fixed64),
+            FIXED32 => self.take(4).map(|_| Wire::Fixed32),
+            LENGTH_DELIMITED => {
+                let length = usize::try_from(self.varint()?).map_err(|_| "length out of range".to_string())?;
+                self.take(length).map(Wire::LengthDelimited)
+            }
+            3 | 4 => Err("groups aren't supported".to_string()),
+            other => Err(format!("invalid wire type {}", other)),
+        }
+    }
+}
+
+struct Checker<'a, 'e> {
+    descriptors: &'a ProtoDescriptors,
+    direction: Direction,
+    emit: &'e mut dyn FnMut(DriftEvent),
+    /// Path of the field being checked, in JSON names
+    path: Vec<String>,
+}
+
+impl Checker<'_, '_> {
+    /// Checks a message's fields, failing on bytes that don't decode
+    fn message(&mut self, message: &MessageDescriptor, bytes: &[u8], depth: usize) -> Result<(), String> {
+        if depth > MAX_MESSAGE_DEPTH {
+            return Ok(());
+        }
+        let mut reader = Reader { bytes };
+        let mut occurrences: HashMap<u32, usize> = HashMap::new();
+        while !reader.is_empty() {
+            let key = reader.varint()?;
+            let number = u32::try_from(key >> 3).map_err(|_| "field number out of range".to_string())?;
+            if number == 0 {
+                return Err("field number 0".to_string());
+            }
+            let value = reader.value((key & 7) as u8)?;
+            let Some(field) = message.field_by_number(number) else {
+                self.path.push(format!("#{}", number));
+                self.drift(
+                    self.direction.unknown_field(),
+                    format!("{} has no field number {}", message.full_name, number),
+                );
+                self.path.pop();
+                continue;
+            };
+            self.path.push(field.json_name.clone());
+            if field.repeated || matches!(field.field_type, FieldType::Map(..)) {
+                let index = occurrences.entry(number).or_default();
+                // Packed values are indexed within their record
+                if !matches!(value, Wire::LengthDelimited(_)) || !is_packable(&field.field_type) {
+                    self.path.push(index.to_string());
+                    *index += 1;
+                    self.field(field, &field.field_type, value, depth)?;
+                    self.path.pop();
+                } else {
+                    self.packed(field, value, depth)?;
+                }
+            } else {
+                self.field(field, &field.field_type, value, depth)?;
+            }
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    fn packed(&mut self, field: &FieldDescriptor, value: Wire, depth: usize) -> Result<(), String> {
+        let Wire::LengthDelimited(bytes) = value else {
+            return Ok(());
+        };
+        let wire_type = match &field.field_type {
+            FieldType::Scalar(scalar) => scalar_wire_type(*scalar),
+            _ => VARINT,
+        };
+        let mut reader = Reader { bytes };
+        let mut index = 0;
+        while !reader.is_empty() {
+            let element = reader.value(wire_type)?;
+            self.path.push(index.to_string());
+            self.field(field, &field.field_type, element, depth)?;
+            self.path.pop();
+            index += 1;
+        }
+        Ok(())
+    }
+
+    fn field(&mut self, field: &FieldDescriptor, field_type: &FieldType, value: Wire, depth: usize) -> Result<(), String> {
+        let expected = match field_type {
+            FieldType::Scalar(scalar) => scalar_wire_type(*scalar),
+            FieldType::Enum(_) => VARINT,
+            FieldType::Message(_) | FieldType::WellKnown(_) | FieldType::Map(..) => LENGTH_DELIMITED,
+        };
+        if value.wire_type() != expected {
+            self.drift(
+                self.direction.type_mismatch(),
+                format!(
+                    "Field '{}' is declared {} ({}) but arrived {}",
+                    field.name,
+                    type_name(field_type),
+                    wire_type_name(expected),
+                    wire_type_name(value.wire_type())
+                ),
+            );
+            return Ok(());
+        }
+        match (field_type, value) {
+            (FieldType::Scalar(ScalarType::String), Wire::LengthDelimited(bytes)) if std::str::from_utf8(bytes).is_err() => {
+                self.drift(
+                    self.direction.type_mismatch(),
+                    format!("Field '{}' is declared string but holds invalid UTF-8", field.name),
+                );
+            }
+            (FieldType::Enum(name), Wire::Varint(raw)) => {
+                // Enum values are int32, sign-extended on the wire
+                let number = raw as i64 as i32;
+                let declared = self
+                    .descriptors
+                    .enumeration(name)
+                    .is_some_and(|enumeration| enumeration.values.iter().any(|(_, value)| *value == number));
+                if !declared {
+                    self.drift(
+                        self.direction.enum_violation(),
+                        format!("Field '{}' holds {}, which enum {} doesn't declare", field.name, number, name),
+                    );
+                }
+            }
+            (FieldType::Message(name), Wire::LengthDelimited(bytes)) => {
+                if let Some(message) = self.descriptors.message(name) {
+                    self.message(message, bytes, depth + 1)?;
+                }
+            }
+            (FieldType::Map(key, value_type), Wire::LengthDelimited(bytes)) => {
+                let entry = MessageDescriptor {
+                    full_name: format!("{} entry", field.name),
+                    fields: vec![
+                        entry_field("key", 1, FieldType::Scalar(*key)),
+                        entry_field("value", 2, (**value_type).clone()),
+                    ],
+                };
+                self.message(&entry, bytes, depth + 1)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn drift(&mut self, drift_type: DriftType, message: String) {
+        (self.emit)(DriftEvent::new(drift_type, location(&self.path), message));
+    }
+}
+
+/// Whether repeated values of a type may be packed into one record
+fn is_packable(field_type: &FieldType) -> bool {
+    match field_type {
+        FieldType::Scalar(scalar) => scalar_wire_type(*scalar) != LENGTH_DELIMITED,
+        FieldType::Enum(_) => true,
+        _ => false,
+    }
+}
+
+fn entry_field(name: &str, number: u32, field_type: FieldType) -> FieldDescriptor {
+    FieldDescriptor {
+        name: name.to_string(),
+        json_name: name.to_string(),
+        number,
+        field_type,
+        repeated: false,
+    }
+}
+
+pub(super) fn type_name(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Scalar(scalar) => scalar.as_str().to_string(),
+        FieldType::Message(name) | FieldType::Enum(name) | FieldType::WellKnown(name) => name.clone(),
+        FieldType::Map(key, value) => format!("map<{}, {}>", key.as_str(), type_name(value)),
+    }
+}
@@ -0,0 +1,110 @@
+//! Helpers for the `tracing` spans around building and validation
+//!
+//! Spans are declared with `#[cfg_attr(feature = "tracing", tracing::instrument(...))]`
+//! where they cover a whole function; the helpers here fill in their
+//! fields and wrap individual validators. Without the `tracing` feature
+//! they do nothing and compile away.
+
+use crate::drift_event::DriftEvent;
+use std::fmt::Display;
+#[cfg(all(feature = "tracing", not(feature = "wasm")))]
+use std::time::Instant;
+#[cfg(all(feature = "tracing", feature = "wasm"))]
+use web_time::Instant;
+
+/// Records how long the current span took in its `duration_us` field when dropped
+pub(crate) struct SpanTimer {
+    #[cfg(feature = "tracing")]
+    started: Instant,
+}
+
+impl SpanTimer {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            started: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for SpanTimer {
+    fn drop(&mut self) {
+        let elapsed = u64::try_from(self.started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        tracing::Span::current().record("duration_us", elapsed);
+    }
+}
+
+/// Sets a field the current span declared empty
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record(field: &'static str, value: impl Display) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record(field, tracing::field::display(value));
+}
+
+/// Sets a numeric field the current span declared empty
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn record_count(field: &'static str, count: u64) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record(field, count);
+}
+
+/// The span current where work is handed to another thread, to enter there
+#[derive(Clone)]
+pub(crate) struct ParentSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl ParentSpan {
+    pub(crate) fn current() -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        }
+    }
+
+    /// Runs `f` inside the captured span
+    #[cfg(feature = "tracing")]
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        self.span.in_scope(f)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+}
+
+/// Runs one validator's check inside a `validator` span recording the drift it found
+///
+/// The span is at trace level; when no subscriber wants it the check runs
+/// as is, without counting.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn check(
+    validator: &'static str,
+    emit: &mut dyn FnMut(DriftEvent),
+    check: impl FnOnce(&mut dyn FnMut(DriftEvent)),
+) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::trace_span!(
+            "validator",
+            validator,
+            drift_count = tracing::field::Empty,
+            duration_us = tracing::field::Empty
+        );
+        if !span.is_disabled() {
+            let _entered = span.enter();
+            let _timer = SpanTimer::start();
+            let mut drift_count = 0u64;
+            check(&mut |event| {
+                drift_count += 1;
+                emit(event)
+            });
+            span.record("drift_count", drift_count);
+            return;
+        }
+    }
+    check(emit)
+}
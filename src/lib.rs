@@ -1,13 +1,227 @@
+//! Detects drift between an OpenAPI spec and the traffic an API serves
+//!
+//! The default `monitor` feature builds the whole monitor: traffic readers,
+//! sinks, reports, baselines and the CLI. Without it only the validation
+//! core is built: compiling a parsed spec into an [`ApiValidator`] and
+//! checking [`Exchange`]s against it, with no file or network IO, no
+//! threads of its own and a small dependency set, for embedding in
+//! constrained agents. The core still needs `std`, as the JSON Schema
+//! validator does. Its specs come parsed, e.g. deserialized from JSON with
+//! `serde_json`, and compile sequentially unless the `parallel` feature is on.
+//! The `wasm` feature adds a wasm-bindgen API over the core, in the `wasm` module,
+//! for building it to `wasm32-unknown-unknown`. The `tracing` feature
+//! instruments building and validation with `tracing` spans.
+
+#[cfg(feature = "monitor")]
+pub mod aggregation;
 pub mod api_validator;
+pub mod array_sampling;
+#[cfg(feature = "monitor")]
+pub mod asyncapi;
+#[cfg(feature = "monitor")]
+pub mod audit;
+#[cfg(feature = "monitor")]
+pub mod baseline;
+#[cfg(feature = "monitor")]
+pub mod changelog;
+#[cfg(feature = "monitor")]
+pub mod check;
+#[cfg(feature = "monitor")]
+pub mod compliance;
+#[cfg(feature = "monitor")]
+pub mod config;
+mod content_encoding;
+#[cfg(feature = "monitor")]
+pub mod contract_tests;
+pub mod correlation;
+#[cfg(feature = "monitor")]
+pub mod coverage;
+#[cfg(feature = "monitor")]
+pub mod diff;
+pub mod drift_event;
 pub mod drift_types;
+#[cfg(feature = "monitor")]
+pub mod dual_spec;
 pub mod error;
+pub mod exchange;
+pub mod exchange_filter;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod instrumentation;
+#[cfg(feature = "monitor")]
+pub mod lint;
+#[cfg(feature = "monitor")]
+pub mod metrics;
+#[cfg(feature = "monitor")]
+pub mod mock;
+#[cfg(feature = "monitor")]
+pub mod notify;
+#[cfg(feature = "opentelemetry")]
+pub mod otel;
+pub mod ownership;
+pub mod payloads;
+#[cfg(feature = "async")]
+pub mod pipeline;
+#[cfg(feature = "wasm-plugins")]
+pub mod plugin;
+pub mod redaction;
+#[cfg(feature = "monitor")]
+pub mod rehydrate;
+#[cfg(feature = "monitor")]
+pub mod report;
+#[cfg(feature = "monitor")]
+pub mod reproducer;
+pub mod routing;
+pub mod sampling;
+#[cfg(feature = "monitor")]
+pub mod schema_coverage;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "monitor")]
+pub mod session;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "monitor")]
+pub mod sinks;
 pub mod spec;
+#[cfg(feature = "monitor")]
+pub mod spec_registry;
+#[cfg(feature = "sqlite")]
+pub mod store;
+#[cfg(feature = "kafka")]
+pub mod stream;
+mod shallow;
+mod suggestions;
+pub mod suppression;
+#[cfg(feature = "monitor")]
+pub mod traffic;
+mod truncation;
 pub mod validation_helpers;
+#[cfg(feature = "monitor")]
+pub mod upgrade;
+pub mod validator_config;
 pub mod validators;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "monitor")]
+pub mod what_if;
 
+#[cfg(feature = "monitor")]
+pub use aggregation::{DriftRate, DriftWindows};
 pub use api_validator::{ApiValidator, HttpMethod, OperationValidator};
-pub use drift_types::{map_to_drift_type, DriftType, ValidationContext};
+pub use array_sampling::{ArraySample, ArraySampling};
+#[cfg(feature = "monitor")]
+pub use asyncapi::{AsyncApiValidator, ObservedMessage};
+#[cfg(feature = "monitor")]
+pub use audit::{AuditReport, AuditRule, AuditTracker};
+#[cfg(feature = "monitor")]
+pub use baseline::{BaselineFilter, DefaultFingerprint, DriftBaseline, Fingerprint, FingerprintDimensions};
+#[cfg(feature = "monitor")]
+pub use changelog::{Changelog, ChangelogEntry};
+#[cfg(feature = "monitor")]
+pub use check::{CheckResult, CheckThresholds};
+#[cfg(feature = "monitor")]
+pub use compliance::{ComplianceIndex, ComplianceReport};
+#[cfg(feature = "monitor")]
+pub use config::{MonitorConfig, PolicyVerdict, Preset};
+#[cfg(feature = "monitor")]
+pub use contract_tests::{ContractTest, ContractTests};
+pub use correlation::{ExchangeHalf, TrafficCorrelator, UnpairedHalf};
+#[cfg(feature = "monitor")]
+pub use coverage::{CoverageReport, CoverageTracker};
+#[cfg(feature = "monitor")]
+pub use diff::{diff_specs, ChangeKind, SpecChange, SpecDiff};
+pub use drift_event::{DriftEvent, EventContext, DRIFT_EVENT_SCHEMA, EVENT_SCHEMA_VERSION};
+pub use drift_types::{map_to_drift_type, DriftType, Severity, ValidationContext};
+#[cfg(feature = "monitor")]
+pub use dual_spec::DualSpecValidator;
 pub use error::ValidationError;
-pub use spec::{build_api_validator, load_openapi_spec, ResolveReference};
-pub use validation_helpers::{build_validator, format_drift_error, format_instance_location};
+pub use exchange::{Exchange, ObservedRequest, ObservedResponse, ResponseOrigin};
+pub use exchange_filter::{ExchangeFilter, GatewayMarker, StatusRange};
+#[cfg(feature = "graphql")]
+pub use graphql::{GraphQlExchange, GraphQlSchema, GraphQlSchemaValidator};
+#[cfg(feature = "grpc")]
+pub use grpc::{GrpcExchange, GrpcMessage, GrpcValidator, ProtoDescriptors};
+#[cfg(feature = "monitor")]
+pub use lint::{lint_spec, LintIssue, LintReport};
+#[cfg(feature = "monitor")]
+pub use metrics::{spawn_metrics_server, DriftMetrics};
+#[cfg(feature = "monitor")]
+pub use mock::{spawn_mock_server, MockServer};
+#[cfg(feature = "monitor")]
+pub use notify::{Alert, PayloadFormat, WebhookConfig};
+pub use ownership::Ownership;
+pub use payloads::{generate_example, Mutation, MutationKind, PayloadGenerator};
+pub use redaction::{FieldPattern, Redaction};
+#[cfg(feature = "monitor")]
+pub use rehydrate::{rehydrate, Rehydration};
+#[cfg(feature = "monitor")]
+pub use report::heatmap::DriftHeatmap;
+#[cfg(feature = "monitor")]
+pub use report::json::{DRIFT_REPORT_SCHEMA, REPORT_SCHEMA_VERSION};
+#[cfg(feature = "monitor")]
+pub use report::patch::{PatchOp, PatchSuggestion, PatchThresholds, SpecPatch};
+#[cfg(feature = "monitor")]
+pub use report::{DriftReport, ReportFormat, ReportProfile};
+#[cfg(feature = "monitor")]
+pub use reproducer::{Reproducer, ReproducerCapture};
+pub use routing::{overlapping_templates, BasePath, Overlap, PathNormalization, PathTemplate, RoutingConfig, TemplatePattern};
+pub use sampling::{ExchangeSampler, Sampling, Stratum};
+#[cfg(feature = "monitor")]
+pub use schema_coverage::{BranchUsage, SchemaCoverageReport, SchemaCoverageTracker};
+#[cfg(feature = "monitor")]
+pub use session::{Session, SessionOutcome};
+#[cfg(feature = "monitor")]
+pub use sinks::{DriftSink, MemorySink, RotatingFileSink, StdoutJsonlSink};
+pub use spec::{
+    build_api_validator, build_api_validator_with_options, build_api_validator_with_report, BuildOptions,
+    BuildProgress, BuildReport, ResolveReference, SchemaLimits,
+};
+#[cfg(feature = "monitor")]
+pub use spec::{
+    build_api_validator_from_file, load_openapi_spec, load_openapi_spec_cached, load_spec_document, parse_openapi_spec,
+    SpecLocation, SpecLocator, SpecSourceMap,
+};
+#[cfg(feature = "monitor")]
+pub use spec_registry::{ApiVersion, SpecRegistry};
+pub use suppression::{SuppressionRule, Suppressions};
+#[cfg(feature = "monitor")]
+pub use traffic::{ingest, ingest_sampled, EnvoyFormat, IngestSummary, JsonlFormat, LogReader, NginxFormat, TrafficRecord};
+#[cfg(feature = "monitor")]
+pub use upgrade::{upgrade_check, SpecVersion, UpgradeReport};
+pub use validator_config::{DriftPolicy, SeverityPolicy, ValidatorConfig, DEFAULT_MAX_DECOMPRESSED_BYTES};
+pub use validation_helpers::{
+    build_validator, format_drift_error, format_instance_location, CompileSchema, LazyRegistry,
+    SchemaValidator,
+};
+#[cfg(feature = "async")]
+pub use pipeline::{ExchangeSource, Pipeline, PipelineBuilder};
+#[cfg(feature = "wasm-plugins")]
+pub use plugin::{WasmRule, WasmSink};
+#[cfg(feature = "scripting")]
+pub use script::{ScriptTransform, ScriptedSink};
+#[cfg(feature = "signing")]
+pub use signing::{ReportPublicKey, ReportSignature, ReportSigner};
+#[cfg(feature = "async")]
+pub use sinks::{AsyncDriftSink, AsyncJsonlSink, BlockingSink};
+#[cfg(feature = "async")]
+pub use spec::{fetch_openapi_spec, load_openapi_spec_async};
+#[cfg(feature = "sqlite")]
+pub use store::{DriftQuery, DriftStore, StoredFinding};
+#[cfg(feature = "kafka")]
+pub use stream::kafka::{KafkaSink, KafkaSource};
+#[cfg(feature = "pcap")]
+pub use traffic::PcapReader;
 pub use validators::{ParameterValidator, ParametersValidator, RequestBodyValidator, ResponseValidator};
+#[cfg(feature = "watch")]
+pub use watch::SpecWatcher;
+#[cfg(feature = "monitor")]
+pub use what_if::{DriftDelta, WhatIfReplay, WhatIfReport};
+#[cfg(feature = "webhooks")]
+pub use notify::WebhookNotifier;
@@ -0,0 +1,507 @@
+//! Preflight checks of a spec before validators are built from it
+//!
+//! A spec can be valid OpenAPI and still leave the monitor blind: responses
+//! without schemas validate nothing, undeclared path parameters go
+//! unchecked, and duplicate `operationId`s make findings ambiguous. Linting
+//! surfaces these up front rather than as silence in the drift stream.
+//! Embedded examples are checked against their schemas as well.
+
+use crate::api_validator::HttpMethod;
+use crate::error::ValidationError;
+use crate::spec::builder::{components_document, registry_from_document, schema_to_json};
+use crate::spec::transform::Direction;
+use crate::spec::{BuildOptions, ResolveReference};
+use crate::validation_helpers::build_validator;
+use jsonschema::Registry;
+use openapiv3::{OpenAPI, ParameterSchemaOrContent, ReferenceOr};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// What a lint issue is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// No response of the operation declares a body schema
+    MissingResponseSchema,
+    /// A parameter declares `content` without a schema, or `content` at all (not validated)
+    MissingParameterSchema,
+    /// A component nothing in the paths refers to, directly or through other components
+    UnreachableComponent,
+    /// Several operations share an `operationId`
+    DuplicateOperationId,
+    /// A `{param}` in a path template no path parameter declares
+    UndeclaredPathParameter,
+    /// An `example` or `examples` entry doesn't match its own schema
+    InvalidExample,
+}
+
+impl LintRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MissingResponseSchema => "missing-response-schema",
+            Self::MissingParameterSchema => "missing-parameter-schema",
+            Self::UnreachableComponent => "unreachable-component",
+            Self::DuplicateOperationId => "duplicate-operation-id",
+            Self::UndeclaredPathParameter => "undeclared-path-parameter",
+            Self::InvalidExample => "invalid-example",
+        }
+    }
+
+    /// Errors make findings wrong or ambiguous; warnings leave parts of the API unchecked
+    pub fn level(&self) -> LintLevel {
+        match self {
+            Self::DuplicateOperationId | Self::UndeclaredPathParameter => LintLevel::Error,
+            Self::MissingResponseSchema
+            | Self::MissingParameterSchema
+            | Self::UnreachableComponent
+            | Self::InvalidExample => LintLevel::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintLevel {
+    Warning,
+    Error,
+}
+
+impl LintLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A single problem found in a spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub level: LintLevel,
+    /// The operation (`GET /users/{userId}`) or component (`#/components/schemas/User`) concerned
+    pub location: String,
+    pub message: String,
+}
+
+impl LintIssue {
+    fn new(rule: LintRule, location: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            level: rule.level(),
+            location: location.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Issues found in a spec, errors first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|issue| issue.level == LintLevel::Error)
+    }
+
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ValidationError::ReportError(format!("Failed to serialize lint report: {}", e)))
+    }
+
+    /// One line per issue, as a compiler would print them
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        for issue in &self.issues {
+            let _ = writeln!(
+                out,
+                "{}[{}]: {}: {}",
+                issue.level.as_str(),
+                issue.rule.as_str(),
+                issue.location,
+                issue.message
+            );
+        }
+        let errors = self.issues.iter().filter(|issue| issue.level == LintLevel::Error).count();
+        let _ = writeln!(out, "{} errors, {} warnings", errors, self.issues.len() - errors);
+        out
+    }
+}
+
+/// Checks a spec for problems that would limit or confuse drift monitoring
+pub fn lint_spec(spec: &OpenAPI) -> LintReport {
+    let mut issues = Vec::new();
+    let mut operation_ids: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+    for (path, path_item) in spec.paths.paths.iter().filter_map(|(path, item)| Some((path, item.as_item()?))) {
+        let templated = template_parameters(path);
+        for (method_str, operation) in path_item.iter() {
+            let Ok(method) = HttpMethod::from_str(method_str) else {
+                continue;
+            };
+            let label = format!("{} {}", method.as_str(), path);
+            if let Some(operation_id) = &operation.operation_id {
+                operation_ids.entry(operation_id).or_default().push(label.clone());
+            }
+
+            let mut declared_path_parameters = BTreeSet::new();
+            for parameter_ref in path_item.parameters.iter().chain(&operation.parameters) {
+                let Ok(parameter) = parameter_ref.resolve(spec) else {
+                    continue;
+                };
+                let parameter_data = parameter.parameter_data_ref();
+                if matches!(parameter, openapiv3::Parameter::Path { .. }) {
+                    declared_path_parameters.insert(parameter_data.name.as_str());
+                }
+                if let ParameterSchemaOrContent::Content(content) = &parameter_data.format {
+                    let reason = if content.values().any(|media_type| media_type.schema.is_some()) {
+                        "declares its schema under `content`, which is not validated"
+                    } else {
+                        "declares `content` without a schema"
+                    };
+                    issues.push(LintIssue::new(
+                        LintRule::MissingParameterSchema,
+                        &label,
+                        format!("Parameter '{}' {}", parameter_data.name, reason),
+                    ));
+                }
+            }
+            for name in templated.iter().filter(|name| !declared_path_parameters.contains(*name)) {
+                issues.push(LintIssue::new(
+                    LintRule::UndeclaredPathParameter,
+                    &label,
+                    format!("Path parameter '{}' is not declared", name),
+                ));
+            }
+
+            if method != HttpMethod::HEAD && !has_response_schema(spec, operation) {
+                issues.push(LintIssue::new(
+                    LintRule::MissingResponseSchema,
+                    &label,
+                    "No response declares a body schema, so responses are not validated",
+                ));
+            }
+        }
+    }
+
+    for (operation_id, operations) in operation_ids.into_iter().filter(|(_, operations)| operations.len() > 1) {
+        for operation in &operations {
+            issues.push(LintIssue::new(
+                LintRule::DuplicateOperationId,
+                operation,
+                format!("operationId '{}' is shared by {}", operation_id, operations.join(", ")),
+            ));
+        }
+    }
+
+    for component in unreachable_components(spec) {
+        issues.push(LintIssue::new(
+            LintRule::UnreachableComponent,
+            component,
+            "Not referenced from any operation",
+        ));
+    }
+
+    issues.extend(example_issues(spec));
+
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.level));
+    LintReport { issues }
+}
+
+/// Names of the `{param}` segments of a path template
+fn template_parameters(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Whether any response (or media type) of the operation has a schema, or
+/// the operation only ever answers without a body
+fn has_response_schema(spec: &OpenAPI, operation: &openapiv3::Operation) -> bool {
+    let mut bodiless = true;
+    let responses = operation
+        .responses
+        .responses
+        .iter()
+        .map(|(status, response)| (Some(status.to_string()), response))
+        .chain(operation.responses.default.iter().map(|response| (None, response)));
+    for (status, response_ref) in responses {
+        let Ok(response) = response_ref.resolve(spec) else {
+            continue;
+        };
+        if response.content.values().any(|media_type| media_type.schema.is_some()) {
+            return true;
+        }
+        if !matches!(status.as_deref(), Some("204") | Some("304")) {
+            bodiless = false;
+        }
+    }
+    bodiless
+}
+
+/// Components not reachable through `$ref`s from the paths
+///
+/// Security schemes are referenced by name rather than `$ref` and are left out.
+fn unreachable_components(spec: &OpenAPI) -> Vec<String> {
+    let Ok(document) = serde_json::to_value(spec) else {
+        return Vec::new();
+    };
+    let mut reachable = BTreeSet::new();
+    let mut pending = Vec::new();
+    collect_refs(&document["paths"], &mut pending);
+    while let Some(reference) = pending.pop() {
+        if !reachable.insert(reference.clone()) {
+            continue;
+        }
+        if let Some(target) = reference.strip_prefix('#').and_then(|pointer| document.pointer(pointer)) {
+            collect_refs(target, &mut pending);
+        }
+    }
+
+    let mut unreachable = Vec::new();
+    let Some(components) = &spec.components else {
+        return unreachable;
+    };
+    let sections: [(&str, Vec<&String>); 6] = [
+        ("schemas", components.schemas.keys().collect()),
+        ("responses", components.responses.keys().collect()),
+        ("parameters", components.parameters.keys().collect()),
+        ("requestBodies", components.request_bodies.keys().collect()),
+        ("headers", components.headers.keys().collect()),
+        ("examples", components.examples.keys().collect()),
+    ];
+    for (section, names) in sections {
+        for name in names {
+            let reference = format!("#/components/{}/{}", section, name.replace('~', "~0").replace('/', "~1"));
+            if !reachable.contains(&reference) {
+                unreachable.push(reference);
+            }
+        }
+    }
+    unreachable
+}
+
+fn collect_refs(value: &Value, refs: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                refs.push(reference.clone());
+            }
+            map.values().for_each(|value| collect_refs(value, refs));
+        }
+        Value::Array(items) => items.iter().for_each(|value| collect_refs(value, refs)),
+        _ => {}
+    }
+}
+
+/// Registries `$ref`s in examples' schemas resolve against, one per side of the exchange
+struct ExampleRegistries {
+    request: Registry,
+    response: Registry,
+    /// Response-side components, whose schemas' own examples are checked
+    components: Value,
+}
+
+/// Checks the `example`/`examples` of media types, parameters and schemas against their schemas
+///
+/// Examples are often written once and never looked at again, so one that
+/// no longer matches its schema is an early sign the spec has drifted from
+/// what the API really does. Schemas that fail to compile are left to the
+/// build report.
+fn example_issues(spec: &OpenAPI) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let options = BuildOptions::default();
+    let document = |direction| {
+        components_document(spec, Some(direction), &options)
+            .unwrap_or_else(|_| serde_json::json!({ "components": {} }))
+    };
+    let (request, response) = (document(Direction::Request), document(Direction::Response));
+    let (Ok(request_registry), Ok(response_registry)) =
+        (registry_from_document(request), registry_from_document(response.clone()))
+    else {
+        return issues;
+    };
+    let registries = ExampleRegistries {
+        request: request_registry,
+        response: response_registry,
+        components: response,
+    };
+
+    for (path, path_item) in spec.paths.paths.iter().filter_map(|(path, item)| Some((path, item.as_item()?))) {
+        for (method_str, operation) in path_item.iter() {
+            let Ok(method) = HttpMethod::from_str(method_str) else {
+                continue;
+            };
+            let label = format!("{} {}", method.as_str(), path);
+
+            for parameter_ref in path_item.parameters.iter().chain(&operation.parameters) {
+                let Ok(parameter) = parameter_ref.resolve(spec) else {
+                    continue;
+                };
+                let parameter_data = parameter.parameter_data_ref();
+                let ParameterSchemaOrContent::Schema(schema_ref) = &parameter_data.format else {
+                    continue;
+                };
+                let examples = named_examples(spec, parameter_data.example.as_ref(), &parameter_data.examples);
+                let context = format!("parameter '{}'", parameter_data.name);
+                check_examples(spec, schema_ref, Direction::Request, &examples, &registries, &label, &context, &mut issues);
+            }
+
+            if let Some(Ok(request_body)) = operation.request_body.as_ref().map(|body| body.resolve(spec)) {
+                for (media_type_name, media_type) in &request_body.content {
+                    let Some(schema_ref) = &media_type.schema else {
+                        continue;
+                    };
+                    let examples = named_examples(spec, media_type.example.as_ref(), &media_type.examples);
+                    let context = format!("request body {}", media_type_name);
+                    check_examples(spec, schema_ref, Direction::Request, &examples, &registries, &label, &context, &mut issues);
+                }
+            }
+
+            let responses = operation
+                .responses
+                .responses
+                .iter()
+                .map(|(status, response)| (status.to_string(), response))
+                .chain(operation.responses.default.iter().map(|response| ("default".to_string(), response)));
+            for (status, response_ref) in responses {
+                let Ok(response) = response_ref.resolve(spec) else {
+                    continue;
+                };
+                for (media_type_name, media_type) in &response.content {
+                    let Some(schema_ref) = &media_type.schema else {
+                        continue;
+                    };
+                    let examples = named_examples(spec, media_type.example.as_ref(), &media_type.examples);
+                    let context = format!("response {} {}", status, media_type_name);
+                    check_examples(spec, schema_ref, Direction::Response, &examples, &registries, &label, &context, &mut issues);
+                }
+            }
+        }
+    }
+
+    if let Some(Value::Object(schemas)) = registries.components.pointer("/components/schemas") {
+        for (name, schema) in schemas {
+            let location = format!("#/components/schemas/{}", name);
+            check_schema_examples(schema, "", &registries, &location, &mut issues);
+        }
+    }
+    issues
+}
+
+/// A media type's or parameter's `example` and inline or referenced `examples`, by name
+fn named_examples<'a>(
+    spec: &'a OpenAPI,
+    example: Option<&'a Value>,
+    examples: &'a indexmap::IndexMap<String, ReferenceOr<openapiv3::Example>>,
+) -> Vec<(String, &'a Value)> {
+    let mut named: Vec<(String, &Value)> = example.map(|value| ("example".to_string(), value)).into_iter().collect();
+    for (name, example_ref) in examples {
+        // External examples (`externalValue`) aren't fetched
+        if let Some(value) = example_ref.resolve(spec).ok().and_then(|example| example.value.as_ref()) {
+            named.push((format!("examples/{}", name), value));
+        }
+    }
+    named
+}
+
+#[allow(clippy::too_many_arguments)]
+fn check_examples(
+    spec: &OpenAPI,
+    schema_ref: &ReferenceOr<openapiv3::Schema>,
+    direction: Direction,
+    examples: &[(String, &Value)],
+    registries: &ExampleRegistries,
+    label: &str,
+    context: &str,
+    issues: &mut Vec<LintIssue>,
+) {
+    let Ok(schema) = schema_to_json(schema_ref, context, spec, Some(direction), &BuildOptions::default()) else {
+        return;
+    };
+    // Examples nested in inline schemas; those of `$ref`d components are checked once, with the components
+    check_schema_examples(&schema, "", registries, &format!("{} {}", label, context), issues);
+    if examples.is_empty() {
+        return;
+    }
+
+    let registry = match direction {
+        Direction::Request => &registries.request,
+        Direction::Response => &registries.response,
+    };
+    let Ok(validator) = build_validator(&schema, registry, context) else {
+        return;
+    };
+    for (name, example) in examples {
+        if let Some(error) = validator.iter_errors(example).next() {
+            issues.push(LintIssue::new(
+                LintRule::InvalidExample,
+                label,
+                format!("{} {} does not match its schema: {}", context, name, describe_error(&error)),
+            ));
+        }
+    }
+}
+
+/// Checks the `example` of a schema and of every schema nested in it, without following `$ref`s
+///
+/// Component schemas are shared by requests and responses, so their
+/// examples only have to be valid on one side (a `readOnly` ID may be
+/// present or not).
+fn check_schema_examples(
+    schema: &Value,
+    pointer: &str,
+    registries: &ExampleRegistries,
+    location: &str,
+    issues: &mut Vec<LintIssue>,
+) {
+    if let Some(example) = schema.get("example") {
+        let errors = [&registries.response, &registries.request].map(|registry| {
+            build_validator(schema, registry, location)
+                .ok()
+                .and_then(|validator| validator.iter_errors(example).next().map(|error| describe_error(&error)))
+        });
+        if let [Some(error), Some(_)] = errors {
+            let at = if pointer.is_empty() { String::new() } else { format!(" at {}", pointer) };
+            issues.push(LintIssue::new(
+                LintRule::InvalidExample,
+                location,
+                format!("Schema example{} does not match its schema: {}", at, error),
+            ));
+        }
+    }
+
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, subschema) in properties {
+            let property_pointer = format!("{}/properties/{}", pointer, name);
+            check_schema_examples(subschema, &property_pointer, registries, location, issues);
+        }
+    }
+    for keyword in ["allOf", "oneOf", "anyOf"] {
+        for (index, branch) in schema.get(keyword).and_then(Value::as_array).into_iter().flatten().enumerate() {
+            let branch_pointer = format!("{}/{}/{}", pointer, keyword, index);
+            check_schema_examples(branch, &branch_pointer, registries, location, issues);
+        }
+    }
+    for keyword in ["items", "additionalProperties"] {
+        if let Some(subschema) = schema.get(keyword).filter(|s| s.is_object()) {
+            check_schema_examples(subschema, &format!("{}/{}", pointer, keyword), registries, location, issues);
+        }
+    }
+}
+
+fn describe_error(error: &jsonschema::ValidationError<'_>) -> String {
+    let path = error.instance_path.to_string();
+    if path.is_empty() {
+        error.to_string()
+    } else {
+        format!("{} at {}", error, path)
+    }
+}
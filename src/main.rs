@@ -1,12 +1,132 @@
-use api_spec_drift_monitor_poc::{build_api_validator, load_openapi_spec};
-use std::path::Path;
+use api_spec_drift_monitor_poc::baseline::DEFAULT_BASELINE_PATH;
+use api_spec_drift_monitor_poc::compliance::DEFAULT_CONSUMER_KEY;
+use api_spec_drift_monitor_poc::mock::DEFAULT_MOCK_ADDR;
+use api_spec_drift_monitor_poc::report::heatmap::DEFAULT_BUCKET_WIDTH;
+use api_spec_drift_monitor_poc::session::{parse_deadline, parse_duration};
+use api_spec_drift_monitor_poc::sinks::read_events;
+use api_spec_drift_monitor_poc::suppression::DEFAULT_SUPPRESSIONS_PATH;
+#[cfg(feature = "signing")]
+use api_spec_drift_monitor_poc::signing::signature_path;
+#[cfg(feature = "signing")]
+use api_spec_drift_monitor_poc::{ReportPublicKey, ReportSigner};
+#[cfg(feature = "graphql")]
+use api_spec_drift_monitor_poc::GraphQlSchemaValidator;
+#[cfg(feature = "grpc")]
+use api_spec_drift_monitor_poc::GrpcValidator;
+#[cfg(feature = "pcap")]
+use api_spec_drift_monitor_poc::PcapReader;
+#[cfg(feature = "scripting")]
+use api_spec_drift_monitor_poc::ScriptTransform;
+#[cfg(feature = "wasm-plugins")]
+use api_spec_drift_monitor_poc::WasmSink;
+use api_spec_drift_monitor_poc::{
+    build_api_validator_with_options, build_api_validator_with_report, diff_specs, lint_spec, load_openapi_spec, spawn_mock_server,
+    ingest_sampled, rehydrate, load_spec_document, upgrade_check, EnvoyFormat, JsonlFormat, LogReader, NginxFormat, StdoutJsonlSink,
+    ApiValidator, AsyncApiValidator, BuildOptions, BuildProgress, BuildReport, Changelog, CheckThresholds, ComplianceIndex, ComplianceReport, ContractTests, DriftBaseline, DriftEvent, DriftHeatmap, DriftReport, DriftSink, ExchangeSampler, FingerprintDimensions, IngestSummary, MockServer, MonitorConfig, PatchThresholds, Preset, ReportFormat, Reproducer,
+    ReportProfile, Session, Severity, SeverityPolicy, SpecPatch, SpecSourceMap, TrafficRecord, ValidationError, WhatIfReplay,
+    DRIFT_EVENT_SCHEMA, DRIFT_REPORT_SCHEMA,
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-fn main() {
+const USAGE: &str = "\
+Usage:
+  api-spec-drift-monitor-poc
+  api-spec-drift-monitor-poc baseline <events.jsonl> [--output drift-baseline.json]
+  api-spec-drift-monitor-poc filter <events.jsonl> [--baseline drift-baseline.json]
+  api-spec-drift-monitor-poc transform <events.jsonl> --script <policy.rhai>
+  api-spec-drift-monitor-poc forward <events.jsonl> --plugin <sink.wasm>
+  api-spec-drift-monitor-poc ingest <spec.yaml> <traffic.log> [--format jsonl|envoy|nginx|pcap] [--log-format '<nginx log_format>']
+  api-spec-drift-monitor-poc check <spec.yaml> <traffic.log> [--format ...] [--fail-on breaking|warning|info]
+                             [--max-breaking <n>] [--max-warnings <n>] [--max-info <n>] [--baseline drift-baseline.json]
+                             [--preset <name> | --config <config.yaml>]
+  api-spec-drift-monitor-poc what-if <current.yaml> <proposed.yaml> <traffic.log> [--format ...] [--output-format text|json]
+                             [--preset <name> | --config <config.yaml>]
+  api-spec-drift-monitor-poc suggest-patch <spec.yaml> <traffic.log> [--format ...] [--patch-format json-patch|overlay]
+                             [--min-share 1.0] [--min-exchanges <n>] [--output <file>] [--preset <name> | --config <config.yaml>]
+  api-spec-drift-monitor-poc graphql-ingest <traffic.jsonl> <schema.graphql>... [--endpoint /graphql]
+  api-spec-drift-monitor-poc grpc-ingest <traffic.jsonl> <service.proto>... [--proto-path <dir>]...
+  api-spec-drift-monitor-poc asyncapi-ingest <asyncapi.yaml> <messages.jsonl>
+  api-spec-drift-monitor-poc mock <spec.yaml> [--listen 127.0.0.1:4010] [--preset <name> | --config <config.yaml>]
+  api-spec-drift-monitor-poc monitor [--preset <name> | --config <config.yaml>] [--duration 2h | --until <timestamp>]
+  api-spec-drift-monitor-poc lint <spec.yaml> [--format text|json]
+  api-spec-drift-monitor-poc upgrade-check --from 3.0 --to 3.1 --spec <spec.yaml> [--format text|json]
+  api-spec-drift-monitor-poc build-report <spec.yaml> [--preset <name> | --config <config.yaml>] [--lenient]
+  api-spec-drift-monitor-poc report <events.jsonl> [--format json|junit|sarif|html|markdown] [--profile full|redacted]
+                             [--output <path> [--sign <secret.key>]]
+  api-spec-drift-monitor-poc rehydrate <archive>... [--severities <policy.yaml>] [--ignore method,path,status]
+                             [--context-key consumer] [--format json|junit|sarif|html|markdown] [--profile full|redacted]
+                             [--output <path>] [--baseline-output drift-baseline.json]
+  api-spec-drift-monitor-poc heatmap <events.jsonl> [--bucket 1h]
+  api-spec-drift-monitor-poc compliance <spec.yaml> <events.jsonl> [--format json|markdown] [--consumer-key consumer]
+  api-spec-drift-monitor-poc config [--preset <name> | --config <config.yaml>]
+  api-spec-drift-monitor-poc schema event|report
+  api-spec-drift-monitor-poc contract-tests <report.json> <spec.yaml> [--format rust|json] [--output <file>]
+  api-spec-drift-monitor-poc changelog <old.yaml> <new.yaml> [--events events.jsonl] [--date YYYY-MM-DD]
+  api-spec-drift-monitor-poc keygen <secret.key> <public.key>
+  api-spec-drift-monitor-poc sign <artifact> --key <secret.key>
+  api-spec-drift-monitor-poc verify <artifact> --key <public.key> [--signature <artifact>.minisig]
+
+Presets: ci-replay, k8s-sidecar, gateway-kafka";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None => {
+            run_demo();
+            ExitCode::SUCCESS
+        }
+        Some("baseline") => save_baseline(&args[1..]),
+        Some("filter") => filter_against_baseline(&args[1..]),
+        #[cfg(feature = "scripting")]
+        Some("transform") => transform_events(&args[1..]),
+        #[cfg(feature = "wasm-plugins")]
+        Some("forward") => forward_events(&args[1..]),
+        Some("ingest") => ingest_traffic(&args[1..]),
+        Some("check") => check_traffic(&args[1..]),
+        Some("what-if") => compare_revisions(&args[1..]),
+        Some("suggest-patch") => suggest_spec_patch(&args[1..]),
+        #[cfg(feature = "graphql")]
+        Some("graphql-ingest") => ingest_graphql_traffic(&args[1..]),
+        #[cfg(feature = "grpc")]
+        Some("grpc-ingest") => ingest_grpc_traffic(&args[1..]),
+        Some("asyncapi-ingest") => ingest_messages(&args[1..]),
+        Some("mock") => serve_mock(&args[1..]),
+        Some("monitor") => run_session(&args[1..]),
+        Some("lint") => print_lint_report(&args[1..]),
+        Some("upgrade-check") => print_upgrade_check(&args[1..]),
+        Some("build-report") => print_build_report(&args[1..]),
+        Some("changelog") => print_changelog(&args[1..]),
+        Some("contract-tests") => generate_contract_tests(&args[1..]),
+        Some("config") => print_config(&args[1..]),
+        Some("schema") => print_schema(&args[1..]),
+        Some("report") => print_report(&args[1..]),
+        Some("rehydrate") => rehydrate_archive(&args[1..]),
+        Some("heatmap") => print_heatmap(&args[1..]),
+        Some("compliance") => print_compliance_report(&args[1..]),
+        #[cfg(feature = "signing")]
+        Some("keygen") => generate_signing_key(&args[1..]),
+        #[cfg(feature = "signing")]
+        Some("sign") => sign_artifact(&args[1..]),
+        #[cfg(feature = "signing")]
+        Some("verify") => verify_artifact(&args[1..]),
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run_demo() {
     println!("=== API Spec Drift Monitor ===\n");
 
     // Load OpenAPI specification
     let spec_path = Path::new("test-api-spec.yaml");
-    
+
     let spec = match load_openapi_spec(spec_path) {
         Ok(spec) => {
             println!("✓ Loaded spec: {} v{}", spec.info.title, spec.info.version);
@@ -19,7 +139,11 @@ fn main() {
     };
 
     // Build API validator from the spec
-    let _api_validator = match build_api_validator(&spec) {
+    let options = BuildOptions {
+        progress: Some(Arc::new(TerminalProgress)),
+        ..BuildOptions::default()
+    };
+    let _api_validator = match build_api_validator_with_options(&spec, &options) {
         Ok(validator) => {
             println!("✓ API Validator built successfully\n");
             validator
@@ -32,3 +156,1264 @@ fn main() {
 
     println!("Ready to validate API traffic.");
 }
+
+/// Aggregates recorded drift events into a baseline file
+fn save_baseline(args: &[String]) -> ExitCode {
+    let Some(events_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let output = flag_value(args, "--output").unwrap_or(DEFAULT_BASELINE_PATH);
+
+    let result = read_events(Path::new(events_path)).and_then(|events| {
+        let baseline = DriftBaseline::from_events(&events);
+        baseline.save(Path::new(output)).map(|()| baseline.len())
+    });
+    match result {
+        Ok(entries) => {
+            eprintln!("✓ Wrote {} known drift entries to {}", entries, output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to create baseline: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints recorded drift events not present in the baseline, failing if there are any
+fn filter_against_baseline(args: &[String]) -> ExitCode {
+    let Some(events_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let baseline_path = flag_value(args, "--baseline").unwrap_or(DEFAULT_BASELINE_PATH);
+
+    let result = DriftBaseline::load(Path::new(baseline_path)).and_then(|baseline| {
+        read_events(Path::new(events_path)).map(|events| baseline.new_drift(events))
+    });
+    match result {
+        Ok(new_drift) => {
+            for event in &new_drift {
+                if let Ok(line) = serde_json::to_string(event) {
+                    println!("{}", line);
+                }
+            }
+            eprintln!("{} new drift events not in {}", new_drift.len(), baseline_path);
+            if new_drift.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to filter against baseline: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints recorded drift events as rewritten by a transform script, e.g. to try out a policy
+#[cfg(feature = "scripting")]
+fn transform_events(args: &[String]) -> ExitCode {
+    let (Some(events_path), Some(script_path)) = (args.first(), flag_value(args, "--script")) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = ScriptTransform::load(Path::new(script_path)).and_then(|transform| {
+        let events = read_events(Path::new(events_path))?;
+        let total = events.len();
+        let mut kept = 0;
+        for event in events {
+            if let Some(event) = transform.apply(event)? {
+                kept += 1;
+                if let Ok(line) = serde_json::to_string(&event) {
+                    println!("{}", line);
+                }
+            }
+        }
+        Ok((kept, total))
+    });
+    match result {
+        Ok((kept, total)) => {
+            eprintln!("{} of {} events kept", kept, total);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to transform events: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Replays recorded events into a WASM sink plugin
+#[cfg(feature = "wasm-plugins")]
+fn forward_events(args: &[String]) -> ExitCode {
+    let (Some(events_path), Some(plugin_path)) = (args.first(), flag_value(args, "--plugin")) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = WasmSink::load(Path::new(plugin_path)).and_then(|sink| {
+        let events = read_events(Path::new(events_path))?;
+        let total = events.len();
+        sink.record_all(events)?;
+        sink.flush()?;
+        Ok(total)
+    });
+    match result {
+        Ok(total) => {
+            eprintln!("{} events forwarded to {}", total, plugin_path);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to forward events: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Validates a traffic log, printing drift events as JSON lines and failing if there are any
+fn ingest_traffic(args: &[String]) -> ExitCode {
+    let (Some(spec_path), Some(traffic_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let format = flag_value(args, "--format").unwrap_or("jsonl");
+    if !["jsonl", "envoy", "nginx", "pcap"].contains(&format) {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let (options, sampler) = match monitor_config(args) {
+        Ok(config) => (
+            BuildOptions {
+                progress: Some(Arc::new(TerminalProgress)),
+                ..config.as_ref().map(|config| config.build_options()).unwrap_or_default()
+            },
+            ExchangeSampler::new(config.map(|config| config.sampling).unwrap_or_default()),
+        ),
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = load_openapi_spec(Path::new(spec_path))
+        .and_then(|spec| build_api_validator_with_options(&spec, &options))
+        .and_then(|validator| {
+            read_traffic(&validator, Path::new(traffic_path), format, args, &StdoutJsonlSink::new(), &sampler)
+        });
+    match result {
+        Ok(summary) => {
+            for error in &summary.errors {
+                eprintln!("✗ Skipped record: {}", error);
+            }
+            eprintln!(
+                "{} records, {} malformed, {} drift events across {} operations",
+                summary.records,
+                summary.malformed,
+                summary.report.summary.total,
+                summary.report.operations.len()
+            );
+            let sampled_out: u64 = summary.report.operations.values().map(|stats| stats.sampled_out).sum();
+            if sampled_out > 0 {
+                eprintln!("{} exchanges sampled out; drift rates account for them", sampled_out);
+            }
+            if summary.report.summary.suppressed > 0 {
+                eprintln!("{} drift events suppressed by ignore rules", summary.report.summary.suppressed);
+            }
+            if summary.report.summary.total == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to ingest traffic [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Validates the traffic log at `path`, read in `format`, into `sink`
+fn read_traffic(
+    validator: &ApiValidator,
+    path: &Path,
+    format: &str,
+    args: &[String],
+    sink: &dyn DriftSink,
+    sampler: &ExchangeSampler,
+) -> Result<IngestSummary, ValidationError> {
+    ingest_sampled(validator, traffic_records(path, format, args)?, sink, sampler)
+}
+
+/// Replays a traffic corpus against the current and a proposed revision of
+/// a spec, printing the drift the revision introduces and fixes
+fn compare_revisions(args: &[String]) -> ExitCode {
+    let (Some(current_path), Some(proposed_path), Some(traffic_path)) = (args.first(), args.get(1), args.get(2)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let format = flag_value(args, "--format").unwrap_or("jsonl");
+    let output_format = flag_value(args, "--output-format").unwrap_or("text");
+    if !["jsonl", "envoy", "nginx", "pcap"].contains(&format) || !["text", "json"].contains(&output_format) {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let options = match monitor_config(args) {
+        Ok(config) => config.map(|config| config.build_options()).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = load_openapi_spec(Path::new(current_path))
+        .and_then(|current| Ok((current, load_openapi_spec(Path::new(proposed_path))?)))
+        .and_then(|(current, proposed)| WhatIfReplay::from_specs(&current, &proposed, &options))
+        .and_then(|replay| Ok(replay.replay_records(traffic_records(Path::new(traffic_path), format, args)?)))
+        .and_then(|report| match output_format {
+            "json" => report.to_json(),
+            _ => Ok(report.render_table()),
+        });
+    match result {
+        Ok(output) => {
+            println!("{}", output.trim_end());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Validates a traffic corpus and writes the spec edits that would resolve
+/// the drift it shows consistently, as a JSON Patch or an OpenAPI Overlay
+fn suggest_spec_patch(args: &[String]) -> ExitCode {
+    let (Some(spec_path), Some(traffic_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let format = flag_value(args, "--format").unwrap_or("jsonl");
+    let patch_format = flag_value(args, "--patch-format").unwrap_or("json-patch");
+    if !["jsonl", "envoy", "nginx", "pcap"].contains(&format) || !["json-patch", "overlay"].contains(&patch_format) {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let mut thresholds = PatchThresholds::default();
+    if let Some(value) = flag_value(args, "--min-share") {
+        match value.parse::<f64>() {
+            Ok(share) if (0.0..=1.0).contains(&share) => thresholds.min_share = share,
+            _ => {
+                eprintln!("✗ --min-share expects a share between 0 and 1, got '{}'", value);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    if let Some(value) = flag_value(args, "--min-exchanges") {
+        match value.parse() {
+            Ok(min) => thresholds.min_exchanges = min,
+            Err(_) => {
+                eprintln!("✗ --min-exchanges expects a count, got '{}'", value);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let config = match monitor_config(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    let options = config.as_ref().map(|config| config.build_options()).unwrap_or_default();
+    // Every exchange counts towards the shares, so nothing is sampled out
+    let sampler = ExchangeSampler::default();
+
+    let result = load_openapi_spec(Path::new(spec_path))
+        .and_then(|spec| build_api_validator_with_options(&spec, &options))
+        .and_then(|validator| {
+            let sink: Vec<StdoutJsonlSink> = Vec::new();
+            read_traffic(&validator, Path::new(traffic_path), format, args, &sink, &sampler)
+        })
+        .and_then(|summary| {
+            let document = load_spec_document(Path::new(spec_path))?;
+            let patch = SpecPatch::suggest(&summary.report, &document, thresholds);
+            let rendered = match patch_format {
+                "overlay" => patch.to_overlay(&format!("Drift observed against {}", spec_path))?,
+                _ => patch.to_json_patch()?,
+            };
+            Ok((patch, rendered))
+        });
+    let (patch, rendered) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("✗ Failed to suggest a spec patch [{}]: {}", e.code(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if patch.is_empty() {
+        eprintln!("No drift reached the thresholds; the spec needs no changes");
+    }
+    for suggestion in &patch.suggestions {
+        eprintln!("  {} {}: {}", suggestion.op.as_str(), suggestion.path, suggestion.reason);
+    }
+    match flag_value(args, "--output") {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, format!("{}\n", rendered)) {
+                eprintln!("✗ Failed to write {}: {}", output, e);
+                return ExitCode::FAILURE;
+            }
+            eprintln!("✓ Wrote {} suggested edits to {}", patch.suggestions.len(), output);
+        }
+        None => println!("{}", rendered),
+    }
+    ExitCode::SUCCESS
+}
+
+/// Reads the traffic at `path` in `format`, one of jsonl, envoy, nginx or pcap
+fn traffic_records(
+    path: &Path,
+    format: &str,
+    args: &[String],
+) -> Result<Box<dyn Iterator<Item = Result<TrafficRecord, ValidationError>>>, ValidationError> {
+    Ok(match format {
+        "envoy" => Box::new(LogReader::open(path, EnvoyFormat::default())?),
+        "nginx" => {
+            let nginx = match flag_value(args, "--log-format") {
+                Some(log_format) => NginxFormat::parse(log_format)?,
+                None => NginxFormat::default(),
+            };
+            Box::new(LogReader::open(path, nginx)?)
+        }
+        #[cfg(feature = "pcap")]
+        "pcap" => Box::new(PcapReader::open(path)?),
+        #[cfg(not(feature = "pcap"))]
+        "pcap" => {
+            return Err(ValidationError::UnsupportedFeature {
+                feature: "packet captures (build with the `pcap` feature)".to_string(),
+            })
+        }
+        _ => Box::new(LogReader::open(path, JsonlFormat)?),
+    })
+}
+
+/// Validates a traffic corpus and prints drift per severity against the
+/// thresholds, failing when any is exceeded
+///
+/// Without `--fail-on`, the configuration's policy decides, and failing on
+/// breaking drift is the default; likewise for `--baseline`.
+fn check_traffic(args: &[String]) -> ExitCode {
+    let (Some(spec_path), Some(traffic_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let format = flag_value(args, "--format").unwrap_or("jsonl");
+    if !["jsonl", "envoy", "nginx", "pcap"].contains(&format) {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let config = match monitor_config(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let fail_on = match flag_value(args, "--fail-on") {
+        Some(severity) => match severity.parse::<Severity>() {
+            Ok(severity) => Some(severity),
+            Err(()) => {
+                eprintln!("✗ Unknown severity '{}' (expected breaking, warning or info)", severity);
+                return ExitCode::from(2);
+            }
+        },
+        None => config.as_ref().map_or(Some(Severity::Breaking), |config| config.policy.fail_on),
+    };
+    let mut thresholds = CheckThresholds { fail_on, ..CheckThresholds::new() };
+    for (flag, severity) in [
+        ("--max-breaking", Severity::Breaking),
+        ("--max-warnings", Severity::Warning),
+        ("--max-info", Severity::Info),
+    ] {
+        let Some(value) = flag_value(args, flag) else {
+            continue;
+        };
+        match value.parse() {
+            Ok(max) => thresholds = thresholds.max(severity, max),
+            Err(_) => {
+                eprintln!("✗ {} expects a count, got '{}'", flag, value);
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let baseline = flag_value(args, "--baseline")
+        .map(PathBuf::from)
+        .or_else(|| config.as_ref().and_then(|config| config.policy.baseline.clone()));
+
+    let options = BuildOptions {
+        progress: Some(Arc::new(TerminalProgress)),
+        ..config.as_ref().map(|config| config.build_options()).unwrap_or_default()
+    };
+    let sampler = ExchangeSampler::new(config.map(|config| config.sampling).unwrap_or_default());
+    let result = load_openapi_spec(Path::new(spec_path))
+        .and_then(|spec| build_api_validator_with_options(&spec, &options))
+        .and_then(|validator| {
+            // Only the counts matter, so events aren't recorded anywhere
+            let sink: Vec<StdoutJsonlSink> = Vec::new();
+            read_traffic(&validator, Path::new(traffic_path), format, args, &sink, &sampler)
+        })
+        .and_then(|summary| match &baseline {
+            Some(path) => Ok((
+                DriftReport::from_events(DriftBaseline::load(path)?.new_drift(summary.report.events.clone())),
+                summary,
+            )),
+            None => Ok((summary.report.clone(), summary)),
+        });
+    match result {
+        Ok((report, summary)) => {
+            let check = thresholds.check(&report);
+            print!("{}", check.render_table());
+            eprintln!(
+                "\n{} records, {} malformed, {} drift events{}",
+                summary.records,
+                summary.malformed,
+                summary.report.summary.total,
+                if baseline.is_some() {
+                    format!(" ({} not in the baseline)", report.summary.total)
+                } else {
+                    String::new()
+                }
+            );
+            if check.passed() {
+                eprintln!("✓ Drift is within thresholds");
+                ExitCode::SUCCESS
+            } else {
+                eprintln!("✗ Drift exceeds thresholds");
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to check traffic [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Validates a log of broker messages against an AsyncAPI document,
+/// printing drift events as JSON lines and failing if there are any
+fn ingest_messages(args: &[String]) -> ExitCode {
+    let (Some(spec_path), Some(messages_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = AsyncApiValidator::load(Path::new(spec_path)).and_then(|validator| {
+        let file = std::fs::File::open(messages_path)
+            .map_err(|e| ValidationError::TrafficError(format!("{}: {}", messages_path, e)))?;
+        api_spec_drift_monitor_poc::asyncapi::ingest(&validator, std::io::BufReader::new(file), &StdoutJsonlSink::new())
+    });
+    match result {
+        Ok(summary) => {
+            for error in &summary.errors {
+                eprintln!("✗ Skipped record: {}", error);
+            }
+            eprintln!(
+                "{} messages, {} malformed, {} drift events across {} channels",
+                summary.records,
+                summary.malformed,
+                summary.report.summary.total,
+                summary.report.operations.len()
+            );
+            if summary.report.summary.total == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to ingest messages [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Validates a log of GraphQL requests against an SDL schema, printing
+/// drift events as JSON lines and failing if there are any
+#[cfg(feature = "graphql")]
+fn ingest_graphql_traffic(args: &[String]) -> ExitCode {
+    let endpoint = flag_value(args, "--endpoint");
+    let positional: Vec<&String> = args
+        .iter()
+        .enumerate()
+        .filter(|(index, arg)| !arg.starts_with("--") && (*index == 0 || args[index - 1] != "--endpoint"))
+        .map(|(_, arg)| arg)
+        .collect();
+    let Some((traffic_path, schema_files)) = positional.split_first().filter(|(_, files)| !files.is_empty()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let schema_files: Vec<PathBuf> = schema_files.iter().map(PathBuf::from).collect();
+
+    let result = GraphQlSchemaValidator::from_files(&schema_files).and_then(|validator| {
+        let validator = match endpoint {
+            Some(endpoint) => validator.with_endpoint(endpoint),
+            None => validator,
+        };
+        let file = std::fs::File::open(traffic_path.as_str())
+            .map_err(|e| ValidationError::TrafficError(format!("{}: {}", traffic_path, e)))?;
+        api_spec_drift_monitor_poc::graphql::ingest(&validator, std::io::BufReader::new(file), &StdoutJsonlSink::new())
+    });
+    match result {
+        Ok(summary) => {
+            for error in &summary.errors {
+                eprintln!("✗ Skipped record: {}", error);
+            }
+            eprintln!(
+                "{} requests, {} malformed, {} drift events across {} operations",
+                summary.records,
+                summary.malformed,
+                summary.report.summary.total,
+                summary.report.operations.len()
+            );
+            if summary.report.summary.total == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to ingest GraphQL traffic [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Validates a log of gRPC calls against `.proto` files, printing drift
+/// events as JSON lines and failing if there are any
+#[cfg(feature = "grpc")]
+fn ingest_grpc_traffic(args: &[String]) -> ExitCode {
+    let mut include_paths = Vec::new();
+    let mut positional = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--proto-path" => include_paths.extend(rest.next().map(PathBuf::from)),
+            _ => positional.push(arg),
+        }
+    }
+    let Some((traffic_path, proto_files)) = positional.split_first().filter(|(_, files)| !files.is_empty()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let proto_files: Vec<PathBuf> = proto_files.iter().map(PathBuf::from).collect();
+
+    let result = GrpcValidator::from_files(&proto_files, &include_paths).and_then(|validator| {
+        let file = std::fs::File::open(traffic_path.as_str())
+            .map_err(|e| ValidationError::TrafficError(format!("{}: {}", traffic_path, e)))?;
+        api_spec_drift_monitor_poc::grpc::ingest(&validator, std::io::BufReader::new(file), &StdoutJsonlSink::new())
+    });
+    match result {
+        Ok(summary) => {
+            for error in &summary.errors {
+                eprintln!("✗ Skipped record: {}", error);
+            }
+            eprintln!(
+                "{} calls, {} malformed, {} drift events across {} methods",
+                summary.records,
+                summary.malformed,
+                summary.report.summary.total,
+                summary.report.operations.len()
+            );
+            if summary.report.summary.total == 0 {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to ingest gRPC traffic [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Serves the spec's documented responses until interrupted, printing
+/// drift in the requests clients send as JSON lines
+fn serve_mock(args: &[String]) -> ExitCode {
+    let Some(spec_path) = args.first().filter(|arg| !arg.starts_with("--")) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let listen = flag_value(args, "--listen").unwrap_or(DEFAULT_MOCK_ADDR);
+    let options = match monitor_config(args) {
+        Ok(config) => config.map(|config| config.build_options()).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let mock = load_openapi_spec(Path::new(spec_path))
+        .and_then(|spec| MockServer::new(&spec, build_api_validator_with_options(&spec, &options)?));
+    let mock = match mock {
+        Ok(mock) => mock,
+        Err(e) => {
+            eprintln!("✗ Failed to build mock [{}]: {}", e.code(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let server = match spawn_mock_server(listen, Arc::new(mock), Arc::new(StdoutJsonlSink::new())) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("✗ Failed to listen on {}: {}", listen, e);
+            return ExitCode::from(2);
+        }
+    };
+    eprintln!("Serving mock responses on http://{}", listen);
+    let _ = server.join();
+    ExitCode::SUCCESS
+}
+
+/// Monitors the configured source until the deadline, then writes the policy's reports
+///
+/// Exits with the policy's verdict, so a canary pipeline can gate on it.
+fn run_session(args: &[String]) -> ExitCode {
+    let deadline = match (flag_value(args, "--duration"), flag_value(args, "--until")) {
+        (Some(_), Some(_)) => Err("--duration and --until are mutually exclusive".to_string()),
+        (Some(duration), None) => parse_duration(duration).map(|duration| Some(SystemTime::now() + duration)),
+        (None, Some(until)) => parse_deadline(until).map(Some),
+        (None, None) => Ok(None),
+    };
+    let session = match (deadline, monitor_config(args)) {
+        (Ok(deadline), Ok(config)) => {
+            let session = Session::new(config.unwrap_or_default()).with_progress(Arc::new(TerminalProgress));
+            match deadline {
+                Some(deadline) => session.until(deadline),
+                None => session,
+            }
+        }
+        (Err(e), _) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+        (_, Err(e)) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match session.run() {
+        Ok(outcome) => {
+            for error in &outcome.summary.errors {
+                eprintln!("✗ Skipped record: {}", error);
+            }
+            for path in &outcome.reports {
+                eprintln!("✓ Wrote {}", path.display());
+            }
+            eprintln!(
+                "{} records, {} drift events, {} new, {} failing the policy",
+                outcome.summary.records,
+                outcome.summary.report.summary.total,
+                outcome.verdict.new_drift,
+                outcome.verdict.failing
+            );
+            if outcome.verdict.passed() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Monitoring session failed [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints problems in a spec that would limit drift monitoring, failing on errors
+fn print_lint_report(args: &[String]) -> ExitCode {
+    let Some(spec_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let json = match flag_value(args, "--format") {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = match load_openapi_spec(Path::new(spec_path)) {
+        Ok(spec) => lint_spec(&spec),
+        Err(e) => {
+            eprintln!("✗ Failed to load spec [{}]: {}", e.code(), e);
+            return ExitCode::from(2);
+        }
+    };
+    let rendered = if json { report.to_json() } else { Ok(report.render_text()) };
+    match rendered {
+        Ok(rendered) => {
+            print!("{}", rendered);
+            if report.has_errors() {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the spec constructs that would validate differently under a newer OpenAPI version
+///
+/// Fails if there are any, so the check can gate a spec's version bump.
+fn print_upgrade_check(args: &[String]) -> ExitCode {
+    let (Some(from), Some(to), Some(spec_path)) =
+        (flag_value(args, "--from"), flag_value(args, "--to"), flag_value(args, "--spec"))
+    else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let json = match flag_value(args, "--format") {
+        None | Some("text") => false,
+        Some("json") => true,
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = from.parse().and_then(|from| {
+        let to = to.parse()?;
+        upgrade_check(&load_spec_document(Path::new(spec_path))?, from, to)
+    });
+    let rendered = report.and_then(|report| {
+        let rendered = if json { report.to_json()? } else { report.render_text() };
+        Ok((rendered, report.is_empty()))
+    });
+    match rendered {
+        Ok((rendered, unchanged)) => {
+            print!("{}", rendered);
+            if unchanged {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to check upgrade [{}]: {}", e.code(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the build report for a spec as JSON, failing unless every operation compiled
+fn print_build_report(args: &[String]) -> ExitCode {
+    let Some(spec_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let mut options = match monitor_config(args) {
+        Ok(config) => BuildOptions {
+            progress: Some(Arc::new(TerminalProgress)),
+            ..config.map(|config| config.build_options()).unwrap_or_default()
+        },
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+    options.lenient |= args.iter().any(|arg| arg == "--lenient");
+
+    let result = load_openapi_spec(Path::new(spec_path))
+        .and_then(|spec| build_api_validator_with_report(&spec, &options))
+        .and_then(|(_, mut report)| {
+            // Lines are a nicety; a report without them is still worth printing
+            if let Ok(source_map) = SpecSourceMap::load(Path::new(spec_path)) {
+                report.locate(&source_map);
+            }
+            report.to_json().map(|json| (report, json))
+        });
+    match result {
+        Ok((report, json)) => {
+            println!("{}", json);
+            if report.is_complete() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(mut e) => {
+            if let Ok(source_map) = SpecSourceMap::load(Path::new(spec_path)) {
+                e.locate(&source_map);
+            }
+            eprintln!("✗ Failed to build validator [{}]: {}", e.code(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints a Markdown changelog between two spec versions, dated by recorded drift history
+fn print_changelog(args: &[String]) -> ExitCode {
+    let (Some(old_path), Some(new_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = (|| -> Result<Changelog, ValidationError> {
+        let old = load_openapi_spec(Path::new(old_path))?;
+        let new = load_openapi_spec(Path::new(new_path))?;
+        let history = match flag_value(args, "--events") {
+            Some(events_path) => read_events(Path::new(events_path))?,
+            None => Vec::new(),
+        };
+        Ok(Changelog::generate(&diff_specs(&old, &new)?, &history, flag_value(args, "--date")))
+    })();
+    match result {
+        Ok(changelog) => {
+            print!("{}", changelog.render_markdown());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to generate changelog: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Turns the reproducers of a JSON report into contract tests, as a Rust
+/// test file or JSON fixtures
+fn generate_contract_tests(args: &[String]) -> ExitCode {
+    let (Some(report_path), Some(spec_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let rust = match flag_value(args, "--format") {
+        None | Some("rust") => true,
+        Some("json") => false,
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = (|| -> Result<ContractTests, ValidationError> {
+        let spec = load_openapi_spec(Path::new(spec_path))?;
+        let report: serde_json::Value = std::fs::read_to_string(report_path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+            .map_err(|e| ValidationError::ReportError(format!("{}: {}", report_path, e)))?;
+        let field = |name: &str| report.get(name).cloned().unwrap_or_else(|| serde_json::json!([]));
+        let reproducers: Vec<Reproducer> = serde_json::from_value(field("reproducers"))
+            .map_err(|e| ValidationError::ReportError(format!("{}: invalid reproducers: {}", report_path, e)))?;
+        let events: Vec<DriftEvent> = serde_json::from_value(field("events"))
+            .map_err(|e| ValidationError::ReportError(format!("{}: invalid events: {}", report_path, e)))?;
+        Ok(ContractTests::generate(&spec, &reproducers, &events))
+    })();
+    let tests = match result {
+        Ok(tests) => tests,
+        Err(e) => {
+            eprintln!("✗ Failed to generate contract tests: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if tests.is_empty() {
+        eprintln!("✗ {} holds no reproducers; record them with a session or ingest run", report_path);
+        return ExitCode::FAILURE;
+    }
+    let rendered = match rust {
+        true => tests.render_rust(spec_path),
+        false => tests.render_json(),
+    };
+    match flag_value(args, "--output") {
+        Some(output) => {
+            if let Err(e) = std::fs::write(output, rendered) {
+                eprintln!("✗ Failed to write {}: {}", output, e);
+                return ExitCode::FAILURE;
+            }
+            eprintln!("✓ Wrote {} contract tests to {}", tests.tests.len(), output);
+        }
+        None => print!("{}", rendered),
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a report of recorded drift events, showing only what the profile allows
+fn print_report(args: &[String]) -> ExitCode {
+    let Some(events_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let format = flag_value(args, "--format").map_or(Ok(ReportFormat::Json), str::parse);
+    let profile = flag_value(args, "--profile").map_or(Ok(ReportProfile::Full), str::parse);
+    let (Ok(format), Ok(profile)) = (format, profile) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let signing_key = flag_value(args, "--sign");
+    let output = match (flag_value(args, "--output"), signing_key) {
+        (None, Some(_)) => {
+            eprintln!("✗ --sign requires --output");
+            return ExitCode::from(2);
+        }
+        (output, _) => output.map(Path::new),
+    };
+
+    let result = read_events(Path::new(events_path))
+        .and_then(|events| DriftReport::from_events(events).for_profile(profile).render(format));
+    let rendered = match result {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            eprintln!("✗ Failed to render report: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let Some(output) = output else {
+        println!("{}", rendered);
+        return ExitCode::SUCCESS;
+    };
+
+    if let Err(e) = std::fs::write(output, rendered) {
+        eprintln!("✗ Failed to write {}: {}", output.display(), e);
+        return ExitCode::FAILURE;
+    }
+    eprintln!("✓ Wrote {}", output.display());
+    match signing_key {
+        Some(key) => sign_file(output, Path::new(key)),
+        None => ExitCode::SUCCESS,
+    }
+}
+
+/// Re-derives a report, and optionally a baseline, from archived events
+/// under a new severity policy and fingerprint
+fn rehydrate_archive(args: &[String]) -> ExitCode {
+    let archives: Vec<PathBuf> = args
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .map(PathBuf::from)
+        .collect();
+    let format = flag_value(args, "--format").map_or(Ok(ReportFormat::Json), str::parse);
+    let profile = flag_value(args, "--profile").map_or(Ok(ReportProfile::Full), str::parse);
+    let fingerprint = fingerprint_dimensions(args);
+    let (false, Ok(format), Ok(profile), Some(fingerprint)) = (archives.is_empty(), format, profile, fingerprint) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let severities = match flag_value(args, "--severities") {
+        Some(path) => SeverityPolicy::load(Path::new(path)),
+        None => Ok(SeverityPolicy::new()),
+    };
+    let result = severities.and_then(|severities| rehydrate(&archives, &severities, fingerprint));
+    let rehydration = match result {
+        Ok(rehydration) => rehydration,
+        Err(e) => {
+            eprintln!("✗ Failed to rehydrate archive: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!(
+        "{} events from {} files, {} reclassified, {} distinct findings",
+        rehydration.report.summary.total,
+        rehydration.files.len(),
+        rehydration.reclassified,
+        rehydration.baseline.len()
+    );
+
+    if let Some(output) = flag_value(args, "--baseline-output") {
+        if let Err(e) = rehydration.baseline.save(Path::new(output)) {
+            eprintln!("✗ Failed to write baseline: {}", e);
+            return ExitCode::FAILURE;
+        }
+        eprintln!("✓ Wrote {} known drift entries to {}", rehydration.baseline.len(), output);
+    }
+    let rendered = match rehydration.report.for_profile(profile).render(format) {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            eprintln!("✗ Failed to render report: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    match flag_value(args, "--output") {
+        Some(output) => match std::fs::write(output, rendered) {
+            Ok(()) => {
+                eprintln!("✓ Wrote {}", output);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("✗ Failed to write {}: {}", output, e);
+                ExitCode::FAILURE
+            }
+        },
+        None => {
+            println!("{}", rendered);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// The fingerprint selected by `--ignore` and `--context-key`, `None` for an unknown dimension
+fn fingerprint_dimensions(args: &[String]) -> Option<FingerprintDimensions> {
+    let mut dimensions = FingerprintDimensions {
+        context_keys: flag_value(args, "--context-key").map(str::to_string).into_iter().collect(),
+        ..FingerprintDimensions::default()
+    };
+    for ignored in flag_value(args, "--ignore").into_iter().flat_map(|list| list.split(',')) {
+        match ignored.trim() {
+            "method" => dimensions.method = false,
+            "path" => dimensions.path_template = false,
+            "status" => dimensions.status_code = false,
+            _ => return None,
+        }
+    }
+    Some(dimensions)
+}
+
+#[cfg(feature = "signing")]
+fn sign_file(path: &Path, key: &Path) -> ExitCode {
+    match ReportSigner::load(key).and_then(|signer| signer.sign_file(path)) {
+        Ok(signature_path) => {
+            eprintln!("✓ Wrote signature {}", signature_path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to sign {}: {}", path.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "signing"))]
+fn sign_file(_path: &Path, _key: &Path) -> ExitCode {
+    eprintln!("✗ Signing requires building with the `signing` feature");
+    ExitCode::from(2)
+}
+
+/// Creates a key pair for signing report artifacts
+#[cfg(feature = "signing")]
+fn generate_signing_key(args: &[String]) -> ExitCode {
+    let (Some(secret_path), Some(public_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let result = ReportSigner::generate().and_then(|signer| {
+        signer.save(Path::new(secret_path))?;
+        signer.public_key().save(Path::new(public_path))
+    });
+    match result {
+        Ok(()) => {
+            eprintln!("✓ Wrote secret key {} and public key {}", secret_path, public_path);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to generate key pair: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Writes a detached signature next to an artifact
+#[cfg(feature = "signing")]
+fn sign_artifact(args: &[String]) -> ExitCode {
+    let (Some(path), Some(key)) = (args.first(), flag_value(args, "--key")) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    sign_file(Path::new(path), Path::new(key))
+}
+
+/// Checks an artifact against its detached signature, failing if either was tampered with
+#[cfg(feature = "signing")]
+fn verify_artifact(args: &[String]) -> ExitCode {
+    let (Some(path), Some(key)) = (args.first(), flag_value(args, "--key")) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let path = Path::new(path);
+    let signature_path = flag_value(args, "--signature")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| signature_path(path));
+
+    match ReportPublicKey::load(Path::new(key)).and_then(|key| key.verify_file(path, &signature_path)) {
+        Ok(trusted_comment) => {
+            println!("✓ Signature valid ({})", trusted_comment);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ {}: {}", path.display(), e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints drift counts per operation, drift type and time bucket as compact JSON
+fn print_heatmap(args: &[String]) -> ExitCode {
+    let Some(events_path) = args.first() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let bucket_width = match flag_value(args, "--bucket").map(parse_duration) {
+        None => DEFAULT_BUCKET_WIDTH,
+        Some(Ok(width)) => width,
+        Some(Err(e)) => {
+            eprintln!("✗ {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = read_events(Path::new(events_path))
+        .and_then(|events| DriftHeatmap::from_events(&events, bucket_width).to_json());
+    match result {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to build heatmap: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Prints drift affecting fields classified with `x-data-classification`, failing if there is any
+fn print_compliance_report(args: &[String]) -> ExitCode {
+    let (Some(spec_path), Some(events_path)) = (args.first(), args.get(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    let markdown = match flag_value(args, "--format") {
+        None | Some("json") => false,
+        Some("markdown") => true,
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let consumer_key = flag_value(args, "--consumer-key").unwrap_or(DEFAULT_CONSUMER_KEY);
+
+    let result = (|| -> Result<ComplianceReport, ValidationError> {
+        let spec = load_openapi_spec(Path::new(spec_path))?;
+        let index = ComplianceIndex::from_spec(&spec, &BuildOptions::default())?;
+        let events = read_events(Path::new(events_path))?;
+        Ok(ComplianceReport::from_events(&index, &events, consumer_key))
+    })();
+    let rendered = result.and_then(|report| {
+        let rendered = if markdown { Ok(report.render_markdown()) } else { report.to_json() };
+        rendered.map(|rendered| (report.is_empty(), rendered))
+    });
+    match rendered {
+        Ok((clean, rendered)) => {
+            println!("{}", rendered);
+            if clean {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("✗ Failed to build compliance report: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the resolved configuration as YAML, e.g. to start a config file from a preset
+fn print_config(args: &[String]) -> ExitCode {
+    let result = monitor_config(args)
+        .and_then(|config| config.unwrap_or_default().to_yaml());
+    match result {
+        Ok(yaml) => {
+            print!("{}", yaml);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the JSON Schema of serialized drift events or of JSON reports
+fn print_schema(args: &[String]) -> ExitCode {
+    let schema = match args.first().map(String::as_str) {
+        Some("event") => DRIFT_EVENT_SCHEMA,
+        Some("report") => DRIFT_REPORT_SCHEMA,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    print!("{}", schema);
+    ExitCode::SUCCESS
+}
+
+/// Configuration selected by `--preset` or `--config`, if either was given,
+/// or else one holding just the rules of a `.driftignore` in the working directory
+fn monitor_config(args: &[String]) -> Result<Option<MonitorConfig>, ValidationError> {
+    match (flag_value(args, "--preset"), flag_value(args, "--config")) {
+        (Some(_), Some(_)) => Err(ValidationError::ConfigError(
+            "--preset and --config are mutually exclusive; use `preset:` in the config file".to_string(),
+        )),
+        // Presets, and runs without a configuration, pick up a `.driftignore` in the working directory
+        (Some(name), None) => name
+            .parse::<Preset>()
+            .map_err(ValidationError::ConfigError)
+            .and_then(|preset| preset.config().with_suppressions_in(Path::new("")))
+            .map(Some),
+        (None, Some(path)) => MonitorConfig::load(Path::new(path)).map(Some),
+        (None, None) if Path::new(DEFAULT_SUPPRESSIONS_PATH).is_file() => {
+            MonitorConfig::default().with_suppressions_in(Path::new("")).map(Some)
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Draws build progress on stderr, keeping stdout for command output
+struct TerminalProgress;
+
+impl BuildProgress for TerminalProgress {
+    fn operation_compiled(&self, completed: usize, total: usize) {
+        let percentage = (completed as f64 / total as f64) * 100.0;
+        // Holding the lock keeps concurrent updates from interleaving mid-line
+        let mut out = std::io::stderr().lock();
+        let _ = write!(
+            out,
+            "\r--- 🛠️ Building API Validator: {:.0}% complete ({}/{}) ---",
+            percentage, completed, total
+        );
+        let _ = out.flush();
+    }
+
+    fn finished(&self, report: &BuildReport) {
+        if report.operations_total == 0 {
+            eprintln!("--- ✅ No operations found to build. ---");
+        } else {
+            eprintln!();
+            eprintln!("--- ✅ Build Complete in {:.0} ms ---", report.total_duration_ms);
+        }
+    }
+
+    fn warning(&self, message: &str) {
+        eprintln!("WARNING: {}", message);
+    }
+}
+
+/// Value following `flag` in the argument list
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
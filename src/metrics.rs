@@ -0,0 +1,271 @@
+use crate::aggregation::{DriftWindows, DEFAULT_WINDOW};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{DriftType, Severity};
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long a scrape may take to send its request or receive the response;
+/// connections are served one at a time, so an idle client can't hold up
+/// the next scrape for longer than this
+const SCRAPE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Bytes of request line and headers read per scrape
+const MAX_REQUEST_BYTES: u64 = 8192;
+
+/// Operation label pair: (method, path template)
+type OperationKey = (String, String);
+
+/// Drift counters exposed in the Prometheus text exposition format
+///
+/// Register it as a [`DriftSink`] and either call [`DriftMetrics::render`]
+/// from an existing HTTP server or start the built-in endpoint with
+/// [`spawn_metrics_server`].
+///
+/// Fed exchanges through [`DriftSink::observe_exchange`], it also exposes
+/// traffic per operation and the share of it showing each drift in the
+/// current time window.
+#[derive(Debug, Default)]
+pub struct DriftMetrics {
+    counters: Mutex<Counters>,
+}
+
+#[derive(Debug)]
+struct Counters {
+    by_drift_type: BTreeMap<&'static str, u64>,
+    by_operation: BTreeMap<OperationKey, u64>,
+    by_severity: BTreeMap<&'static str, u64>,
+    by_origin: BTreeMap<&'static str, u64>,
+    exchanges: BTreeMap<OperationKey, u64>,
+    windows: DriftWindows,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            by_drift_type: BTreeMap::new(),
+            by_operation: BTreeMap::new(),
+            by_severity: BTreeMap::new(),
+            by_origin: BTreeMap::new(),
+            exchanges: BTreeMap::new(),
+            windows: rate_windows(DEFAULT_WINDOW),
+        }
+    }
+}
+
+/// Only the current window's rates are exposed, so older ones needn't be kept
+fn rate_windows(width: Duration) -> DriftWindows {
+    DriftWindows::new(width).with_retention(1)
+}
+
+impl DriftMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Computes drift rates over windows of `width` instead of [`DEFAULT_WINDOW`]
+    pub fn with_rate_window(self, width: Duration) -> Self {
+        self.lock().windows = rate_windows(width);
+        self
+    }
+
+    /// Counts an exchange validated against `operation` and its drift towards the rates
+    pub fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        let mut counters = self.lock();
+        *counters.exchanges.entry(split_operation(operation)).or_default() += 1;
+        counters.windows.record_exchange(operation, timestamp_ms, events);
+    }
+
+    /// Counts a drift event
+    pub fn observe(&self, event: &DriftEvent) {
+        let mut counters = self.lock();
+        *counters.by_drift_type.entry(event.drift_type.as_str()).or_default() += 1;
+        *counters.by_severity.entry(event.severity.as_str()).or_default() += 1;
+
+        let operation = (
+            event.method.map(|m| m.as_str()).unwrap_or("UNKNOWN").to_string(),
+            event.path_template.clone().unwrap_or_else(|| "unmatched".to_string()),
+        );
+        *counters.by_operation.entry(operation).or_default() += 1;
+
+        if let Some(origin) = event.response_origin {
+            *counters.by_origin.entry(origin.as_str()).or_default() += 1;
+        }
+    }
+
+    /// Current count for a drift type
+    pub fn drift_type_count(&self, drift_type: DriftType) -> u64 {
+        self.lock().by_drift_type.get(drift_type.as_str()).copied().unwrap_or(0)
+    }
+
+    /// Current count for a severity
+    pub fn severity_count(&self, severity: Severity) -> u64 {
+        self.lock().by_severity.get(severity.as_str()).copied().unwrap_or(0)
+    }
+
+    /// Renders all counters in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let counters = self.lock();
+        let mut out = String::new();
+
+        write_counter_header(&mut out, "drift_events_total", "Drift events observed, by drift type");
+        for (drift_type, count) in &counters.by_drift_type {
+            let _ = writeln!(out, "drift_events_total{{drift_type=\"{}\"}} {}", drift_type, count);
+        }
+
+        write_counter_header(
+            &mut out,
+            "drift_events_by_operation_total",
+            "Drift events observed, by operation",
+        );
+        for ((method, path), count) in &counters.by_operation {
+            let _ = writeln!(
+                out,
+                "drift_events_by_operation_total{{method=\"{}\",path=\"{}\"}} {}",
+                escape_label(method),
+                escape_label(path),
+                count
+            );
+        }
+
+        write_counter_header(
+            &mut out,
+            "drift_events_by_severity_total",
+            "Drift events observed, by severity",
+        );
+        for (severity, count) in &counters.by_severity {
+            let _ = writeln!(out, "drift_events_by_severity_total{{severity=\"{}\"}} {}", severity, count);
+        }
+
+        if !counters.exchanges.is_empty() {
+            write_counter_header(
+                &mut out,
+                "drift_exchanges_total",
+                "Exchanges validated, by operation",
+            );
+            for ((method, path), count) in &counters.exchanges {
+                let _ = writeln!(
+                    out,
+                    "drift_exchanges_total{{method=\"{}\",path=\"{}\"}} {}",
+                    escape_label(method),
+                    escape_label(path),
+                    count
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "# HELP drift_rate Share of the operation's exchanges showing the drift in the current {}s window",
+                counters.windows.bucket_width().as_secs()
+            );
+            let _ = writeln!(out, "# TYPE drift_rate gauge");
+            for rate in counters.windows.latest_rates() {
+                let (method, path) = split_operation(&rate.operation);
+                let _ = writeln!(
+                    out,
+                    "drift_rate{{method=\"{}\",path=\"{}\",drift_type=\"{}\",location=\"{}\"}} {}",
+                    escape_label(&method),
+                    escape_label(&path),
+                    rate.drift_type.as_str(),
+                    escape_label(&rate.location),
+                    rate.rate
+                );
+            }
+        }
+
+        if !counters.by_origin.is_empty() {
+            write_counter_header(
+                &mut out,
+                "drift_events_by_origin_total",
+                "Drift events observed, by response origin (gateway or upstream)",
+            );
+            for (origin, count) in &counters.by_origin {
+                let _ = writeln!(out, "drift_events_by_origin_total{{origin=\"{}\"}} {}", origin, count);
+            }
+        }
+        out
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Counters> {
+        self.counters.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl DriftSink for DriftMetrics {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        self.observe(&event);
+        Ok(())
+    }
+
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        DriftMetrics::observe_exchange(self, operation, timestamp_ms, events);
+    }
+}
+
+/// Splits an operation label (`GET /users`) into method and path
+fn split_operation(operation: &str) -> OperationKey {
+    let (method, path) = operation.split_once(' ').unwrap_or(("UNKNOWN", operation));
+    (method.to_string(), path.to_string())
+}
+
+/// Serves `GET /metrics` for the given metrics on a background thread
+///
+/// The server is intentionally minimal (one connection at a time, no
+/// keep-alive, requests cut off after 5 seconds or 8 KiB); embedders with an existing HTTP stack should expose
+/// [`DriftMetrics::render`] from it instead.
+pub fn spawn_metrics_server(
+    addr: impl ToSocketAddrs,
+    metrics: Arc<DriftMetrics>,
+) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A failed scrape only affects that client
+            let _ = handle_scrape(stream, &metrics);
+        }
+    }))
+}
+
+fn handle_scrape(mut stream: TcpStream, metrics: &DriftMetrics) -> io::Result<()> {
+    stream.set_read_timeout(Some(SCRAPE_TIMEOUT))?;
+    stream.set_write_timeout(Some(SCRAPE_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Drain the remaining request headers so closing doesn't reset the connection
+    let mut header_line = String::new();
+    while reader.read_line(&mut header_line)? > 2 {
+        header_line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", metrics.render()),
+        _ => ("404 Not Found", "Not Found\n".to_string()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn write_counter_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
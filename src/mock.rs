@@ -0,0 +1,351 @@
+//! Mock server answering every operation of a spec from its documented responses
+//!
+//! Clients pointed at a [`MockServer`] get the spec's examples, or bodies
+//! generated from the response schemas where there are none, and every
+//! request they send is validated against the spec. Drift found this way is
+//! the client's: assumptions about parameters, bodies or headers that the
+//! contract doesn't back, which server-side monitoring never sees because
+//! the real server tolerates or rejects them.
+//!
+//! A `Prefer` header picks among the documented responses, as with other
+//! OpenAPI mock servers: `Prefer: code=404` serves the 404 response and
+//! `Prefer: example=empty` the named example.
+
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::error::ValidationError;
+use crate::exchange::{ObservedRequest, ObservedResponse};
+use crate::payloads::PayloadGenerator;
+use crate::sinks::DriftSink;
+use crate::spec::builder::{components_document, schema_to_json};
+use crate::spec::transform::Direction;
+use crate::spec::{BuildOptions, ResolveReference};
+use openapiv3::{OpenAPI, ParameterSchemaOrContent, ReferenceOr, StatusCode};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// Address `mock` listens on unless told otherwise
+pub const DEFAULT_MOCK_ADDR: &str = "127.0.0.1:4010";
+
+/// Request bodies larger than this are refused rather than read
+const MAX_REQUEST_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// One documented response of an operation, ready to serve
+#[derive(Debug, Clone)]
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    content_type: Option<String>,
+    /// Bodies by example name; the first is served unless another is asked for
+    bodies: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct MockOperation {
+    /// Explicitly documented responses, the one served by default first
+    responses: Vec<MockResponse>,
+    /// The `default` response, served for statuses not documented explicitly
+    fallback: Option<MockResponse>,
+}
+
+/// Serves the documented responses of a spec's operations
+pub struct MockServer {
+    validator: ApiValidator,
+    operations: BTreeMap<(HttpMethod, String), MockOperation>,
+}
+
+impl MockServer {
+    /// Prepares a response for every operation in `spec`, routing and
+    /// validating requests with `validator`, built from the same spec
+    pub fn new(spec: &OpenAPI, validator: ApiValidator) -> Result<Self, ValidationError> {
+        let options = BuildOptions::default();
+        let document = components_document(spec, Some(Direction::Response), &options)
+            .unwrap_or_else(|_| json!({ "components": {} }));
+        let generator = PayloadGenerator::new(&document).direction(Direction::Response);
+
+        let mut operations = BTreeMap::new();
+        for (path, path_item) in spec.paths.paths.iter().filter_map(|(path, item)| Some((path, item.as_item()?))) {
+            for (method_str, operation) in path_item.iter() {
+                let Ok(method) = HttpMethod::from_str(method_str) else {
+                    continue;
+                };
+                let context = format!("{} {}", method.as_str(), path);
+                let mut mock = MockOperation::default();
+                for (status, response_ref) in &operation.responses.responses {
+                    let status = match status {
+                        StatusCode::Code(code) => *code,
+                        StatusCode::Range(range) => range * 100,
+                    };
+                    mock.responses.push(mock_response(spec, status, response_ref, &generator, &context)?);
+                }
+                if let Some(response_ref) = &operation.responses.default {
+                    mock.fallback = Some(mock_response(spec, 200, response_ref, &generator, &context)?);
+                }
+                // Success first, then the lowest documented status
+                mock.responses.sort_by_key(|response| (!(200..300).contains(&response.status), response.status));
+                operations.insert((method, path.clone()), mock);
+            }
+        }
+        Ok(Self { validator, operations })
+    }
+
+    /// The validator requests are routed and checked with
+    pub fn validator(&self) -> &ApiValidator {
+        &self.validator
+    }
+
+    /// The response the mock serves for `request`
+    ///
+    /// Requests that don't route get a 404, or a 405 when only the method
+    /// is undeclared; operations without any documented response a 501.
+    pub fn respond(&self, request: &ObservedRequest) -> ObservedResponse {
+        let Some(template) = self.validator.path_template(&request.path) else {
+            return error_response(404, format!("No route found for path: {}", request.path));
+        };
+        let Some(operation) = self.operations.get(&(request.method, template.to_string())) else {
+            return error_response(405, format!("Method {} not allowed for path: {}", request.method.as_str(), request.path));
+        };
+
+        let prefer = request.header("prefer").map(parse_prefer).unwrap_or_default();
+        let response = match prefer.code {
+            Some(code) => operation
+                .responses
+                .iter()
+                .find(|response| response.status == code)
+                .or_else(|| operation.responses.iter().find(|response| response.status == code / 100 * 100))
+                .or(operation.fallback.as_ref())
+                .map(|response| (code, response)),
+            None => operation
+                .responses
+                .first()
+                .or(operation.fallback.as_ref())
+                .map(|response| (response.status, response)),
+        };
+        let Some((status, response)) = response else {
+            return error_response(501, format!("{} {} documents no response", request.method.as_str(), template));
+        };
+
+        let mut served = ObservedResponse::new(status);
+        for (name, value) in &response.headers {
+            served = served.with_header(name, value);
+        }
+        let body = prefer
+            .example
+            .and_then(|name| response.bodies.iter().find(|(example, _)| *example == name))
+            .or_else(|| response.bodies.first());
+        if let (Some(content_type), Some((_, body))) = (&response.content_type, body) {
+            let bytes = match (body, is_json(content_type)) {
+                (Value::String(text), false) => text.clone().into_bytes(),
+                (body, _) => body.to_string().into_bytes(),
+            };
+            served = served.with_header("content-type", content_type).with_body(bytes);
+        }
+        served
+    }
+}
+
+/// What a request's `Prefer` header asks of the mock
+#[derive(Debug, Default)]
+struct Preference<'a> {
+    code: Option<u16>,
+    example: Option<&'a str>,
+}
+
+fn parse_prefer(header: &str) -> Preference<'_> {
+    let mut preference = Preference::default();
+    for (key, value) in header
+        .split([',', ';'])
+        .filter_map(|part| part.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().trim_matches('"')))
+    {
+        match key {
+            "code" => preference.code = value.parse().ok(),
+            "example" => preference.example = Some(value),
+            _ => {}
+        }
+    }
+    preference
+}
+
+fn mock_response(
+    spec: &OpenAPI,
+    status: u16,
+    response_ref: &ReferenceOr<openapiv3::Response>,
+    generator: &PayloadGenerator,
+    context: &str,
+) -> Result<MockResponse, ValidationError> {
+    let response = response_ref.resolve(spec)?;
+    let mut headers = Vec::new();
+    for (name, header_ref) in &response.headers {
+        let Ok(header) = header_ref.resolve(spec) else {
+            continue;
+        };
+        let value = match (&header.example, &header.format) {
+            (Some(example), _) => Some(example.clone()),
+            (None, ParameterSchemaOrContent::Schema(schema_ref)) => {
+                let context = format!("{} response {} header {}", context, status, name);
+                let schema = schema_to_json(schema_ref, &context, spec, Some(Direction::Response), &BuildOptions::default())?;
+                Some(generator.generate(&schema))
+            }
+            (None, ParameterSchemaOrContent::Content(_)) => None,
+        };
+        // The server writes its own framing headers
+        if name.eq_ignore_ascii_case("content-length") {
+            continue;
+        }
+        if let Some(value) = value.filter(|value| !value.is_null()) {
+            let value = match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            headers.push((name.to_ascii_lowercase(), value));
+        }
+    }
+
+    // JSON when documented, otherwise the first media type
+    let media = response
+        .content
+        .iter()
+        .find(|(content_type, _)| is_json(content_type))
+        .or_else(|| response.content.iter().next());
+    let Some((content_type, media_type)) = media else {
+        return Ok(MockResponse { status, headers, content_type: None, bodies: Vec::new() });
+    };
+    let mut bodies: Vec<(String, Value)> = media_type.example.iter().map(|example| ("example".to_string(), example.clone())).collect();
+    for (name, example_ref) in &media_type.examples {
+        // External examples (`externalValue`) aren't fetched
+        if let Some(value) = example_ref.resolve(spec).ok().and_then(|example| example.value.clone()) {
+            bodies.push((name.clone(), value));
+        }
+    }
+    if bodies.is_empty() {
+        if let Some(schema_ref) = &media_type.schema {
+            let context = format!("{} response {} {}", context, status, content_type);
+            let schema = schema_to_json(schema_ref, &context, spec, Some(Direction::Response), &BuildOptions::default())?;
+            bodies.push(("generated".to_string(), generator.generate(&schema)));
+        }
+    }
+    Ok(MockResponse {
+        status,
+        headers,
+        content_type: Some(content_type.clone()),
+        bodies,
+    })
+}
+
+fn is_json(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+fn error_response(status: u16, message: String) -> ObservedResponse {
+    ObservedResponse::new(status)
+        .with_header("content-type", "application/json")
+        .with_body(json!({ "error": message }).to_string())
+}
+
+/// Serves `mock` on a background thread, recording drift in the requests it receives to `sink`
+///
+/// Like the metrics endpoint, the server handles one connection at a time
+/// without keep-alive; it is meant for test clients, not load.
+pub fn spawn_mock_server(
+    addr: impl ToSocketAddrs,
+    mock: Arc<MockServer>,
+    sink: Arc<dyn DriftSink>,
+) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A failed connection only affects that client
+            let _ = handle_request(stream, &mock, sink.as_ref());
+        }
+    }))
+}
+
+fn handle_request(mut stream: TcpStream, mock: &MockServer, sink: &dyn DriftSink) -> io::Result<()> {
+    let response = match read_request(&stream)? {
+        Ok(request) => {
+            let response = mock.respond(&request);
+            // Unrouted requests are answered with 404/405 rather than reported
+            if let Ok(events) = mock.validator.validate_request(&request) {
+                let _ = sink.record_all(events);
+            }
+            response
+        }
+        Err(status) => ObservedResponse::new(status),
+    };
+    write_response(&mut stream, &response)?;
+    stream.flush()
+}
+
+/// Reads one HTTP/1.1 request, or the error status to answer it with
+fn read_request(stream: &TcpStream) -> io::Result<Result<ObservedRequest, u16>> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(Err(400));
+    };
+    let Ok(method) = HttpMethod::from_str(method) else {
+        return Ok(Err(501));
+    };
+    let mut request = ObservedRequest::new(method, target);
+
+    let mut header_line = String::new();
+    while reader.read_line(&mut header_line)? > 2 {
+        if let Some((name, value)) = header_line.split_once(':') {
+            request = request.with_header(name.trim(), value.trim());
+        }
+        header_line.clear();
+    }
+
+    let length = match request.header("content-length").map(str::parse::<usize>) {
+        None => 0,
+        Some(Ok(length)) if length <= MAX_REQUEST_BODY_BYTES => length,
+        Some(Ok(_)) => return Ok(Err(413)),
+        Some(Err(_)) => return Ok(Err(400)),
+    };
+    if length > 0 {
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        request = request.with_body(body);
+    }
+    Ok(Ok(request))
+}
+
+fn write_response(stream: &mut TcpStream, response: &ObservedResponse) -> io::Result<()> {
+    let body = response.body.as_deref().unwrap_or_default();
+    write!(stream, "HTTP/1.1 {} {}\r\n", response.status, reason_phrase(response.status))?;
+    for (name, value) in &response.headers {
+        write!(stream, "{}: {}\r\n", name, value)?;
+    }
+    write!(stream, "Content-Length: {}\r\nConnection: close\r\n\r\n", body.len())?;
+    stream.write_all(body)
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Content Too Large",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
@@ -0,0 +1,538 @@
+//! Alerts on new drift, delivered to webhooks
+//!
+//! A [`WebhookNotifier`] watches the drift a monitor records and posts an
+//! alert when a drift signature is seen for the first time, or when a
+//! finding's share of an operation's exchanges crosses a threshold. Alerts
+//! are rendered as Slack blocks, generic JSON or a custom template, repeated
+//! alerts for the same finding are held back for a debounce period, and
+//! failed deliveries are retried with exponential backoff. Alerts name the
+//! team owning the drift, and a webhook can be limited to some owners'
+//! drift, so each team's channel gets what it owns.
+//!
+//! Only sending needs the `webhooks` feature; the configuration and payloads
+//! are always available.
+
+use crate::aggregation::DriftRate;
+use crate::drift_event::DriftEvent;
+use crate::report::operation_label;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// How alert payloads are shaped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    /// Slack incoming-webhook message with `text` and `blocks`
+    Slack,
+    /// `{"kind": ..., "summary": ..., ...}` with the event or rate attached
+    #[default]
+    Json,
+}
+
+/// A webhook target and when it's alerted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub format: PayloadFormat,
+    /// Body sent instead of `format`, with `{{placeholders}}` filled in
+    ///
+    /// Placeholders are `kind`, `summary`, `operation`, `drift_type`,
+    /// `severity`, `location`, `message`, `owner` and `rate` (a percentage). Values
+    /// are JSON-escaped without quotes, so they belong inside string
+    /// literals of a JSON template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Only alert on drift owned by one of these teams; empty alerts on all drift
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// Alert when a drift signature is first observed
+    #[serde(default = "default_true")]
+    pub on_new_drift: bool,
+    /// Alert when a finding affects at least this share (0 to 1) of an
+    /// operation's exchanges within a rate window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_threshold: Option<f64>,
+    /// Validated exchanges a window needs before its rates can alert
+    #[serde(default = "default_min_exchanges")]
+    pub min_exchanges: u64,
+    /// Seconds before the same finding can alert again
+    #[serde(default = "default_debounce_secs")]
+    pub debounce_secs: u64,
+    /// Extra attempts after a failed delivery
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+impl WebhookConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            format: PayloadFormat::default(),
+            template: None,
+            owners: Vec::new(),
+            on_new_drift: true,
+            rate_threshold: None,
+            min_exchanges: default_min_exchanges(),
+            debounce_secs: default_debounce_secs(),
+            retries: default_retries(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_exchanges() -> u64 {
+    20
+}
+
+fn default_debounce_secs() -> u64 {
+    600
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+/// Something worth telling someone about
+#[derive(Debug, Clone)]
+pub enum Alert {
+    /// A drift signature seen for the first time
+    NewDrift(Box<DriftEvent>),
+    /// A finding at or above the configured rate
+    RateExceeded {
+        rate: DriftRate,
+        threshold: f64,
+        /// Owner of the latest event behind the rate
+        owner: Option<String>,
+    },
+}
+
+impl Alert {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NewDrift(_) => "new_drift",
+            Self::RateExceeded { .. } => "drift_rate",
+        }
+    }
+
+    /// Team owning the drift alerted on, if known
+    pub fn owner(&self) -> Option<&str> {
+        match self {
+            Self::NewDrift(event) => event.owner.as_deref(),
+            Self::RateExceeded { owner, .. } => owner.as_deref(),
+        }
+    }
+
+    /// One-line description, used as the message text
+    pub fn summary(&self) -> String {
+        let summary = match self {
+            Self::NewDrift(event) => format!(
+                "New {} drift on {}: {} at {}",
+                event.severity.as_str(),
+                operation_label(event),
+                event.drift_type.as_str(),
+                event.location
+            ),
+            Self::RateExceeded { rate, threshold, .. } => {
+                format!("Drift rate above {:.1}%: {}", threshold * 100.0, rate.describe())
+            }
+        };
+        match self.owner() {
+            Some(owner) => format!("[{}] {}", owner, summary),
+            None => summary,
+        }
+    }
+
+    /// The alert in `format`
+    pub fn payload(&self, format: PayloadFormat) -> Value {
+        match format {
+            PayloadFormat::Json => match self {
+                Self::NewDrift(event) => json!({
+                    "kind": self.kind(),
+                    "summary": self.summary(),
+                    "owner": event.owner,
+                    "event": event,
+                }),
+                Self::RateExceeded { rate, threshold, owner } => json!({
+                    "kind": self.kind(),
+                    "summary": self.summary(),
+                    "owner": owner,
+                    "rate": rate,
+                    "threshold": threshold,
+                }),
+            },
+            PayloadFormat::Slack => {
+                let (title, details) = match self {
+                    Self::NewDrift(event) => (
+                        "New API drift",
+                        format!(
+                            "*{}* `{}`\n{} at `{}`\n{}",
+                            event.severity.as_str(),
+                            operation_label(event),
+                            event.drift_type.as_str(),
+                            event.location,
+                            event.message
+                        ),
+                    ),
+                    Self::RateExceeded { rate, threshold, .. } => (
+                        "API drift rate above threshold",
+                        format!(
+                            "`{}`\n{} at `{}`\n*{:.1}%* of {} exchanges (threshold {:.1}%)",
+                            rate.operation,
+                            rate.drift_type.as_str(),
+                            rate.location,
+                            rate.rate * 100.0,
+                            rate.exchanges,
+                            threshold * 100.0
+                        ),
+                    ),
+                };
+                let details = match self.owner() {
+                    Some(owner) => format!("{}\nOwner: *{}*", details, owner),
+                    None => details,
+                };
+                json!({
+                    "text": self.summary(),
+                    "blocks": [
+                        { "type": "header", "text": { "type": "plain_text", "text": title } },
+                        { "type": "section", "text": { "type": "mrkdwn", "text": details } },
+                    ],
+                })
+            }
+        }
+    }
+
+    /// `template` with its placeholders filled in
+    pub fn render(&self, template: &str) -> String {
+        let (operation, drift_type, severity, location, message, rate) = match self {
+            Self::NewDrift(event) => (
+                operation_label(event),
+                event.drift_type.as_str(),
+                event.severity.as_str(),
+                event.location.clone(),
+                event.message.clone(),
+                String::new(),
+            ),
+            Self::RateExceeded { rate, .. } => (
+                rate.operation.clone(),
+                rate.drift_type.as_str(),
+                "",
+                rate.location.clone(),
+                rate.describe(),
+                format!("{:.1}", rate.rate * 100.0),
+            ),
+        };
+        let fields = [
+            ("kind", self.kind().to_string()),
+            ("summary", self.summary()),
+            ("operation", operation),
+            ("drift_type", drift_type.to_string()),
+            ("severity", severity.to_string()),
+            ("location", location),
+            ("message", message),
+            ("owner", self.owner().unwrap_or_default().to_string()),
+            ("rate", rate),
+        ];
+        fields.iter().fold(template.to_string(), |body, (name, value)| {
+            body.replace(&format!("{{{{{}}}}}", name), &json_escape(value))
+        })
+    }
+}
+
+/// `value` as the inside of a JSON string literal
+fn json_escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(feature = "webhooks")]
+pub use delivery::WebhookNotifier;
+
+#[cfg(feature = "webhooks")]
+mod delivery {
+    use super::{Alert, WebhookConfig};
+    use crate::aggregation::DriftWindows;
+    use crate::baseline::{DriftBaseline, Fingerprint};
+    use crate::drift_event::DriftEvent;
+    use crate::drift_types::DriftType;
+    use crate::error::ValidationError;
+    use crate::report::operation_label;
+    use crate::sinks::DriftSink;
+    use std::collections::{BTreeSet, HashMap};
+    use std::fmt;
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+    use std::thread::{self, JoinHandle};
+    use std::time::{Duration, Instant};
+
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+    const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+    /// Posts alerts to a webhook as drift is recorded
+    ///
+    /// Deliveries happen on a background thread so a slow endpoint never
+    /// holds up validation; [`DriftSink::flush`] waits for queued alerts and
+    /// fails if any couldn't be delivered.
+    pub struct WebhookNotifier {
+        config: WebhookConfig,
+        state: Mutex<NotifyState>,
+        queue: Mutex<Option<Sender<Alert>>>,
+        worker: Mutex<Option<JoinHandle<()>>>,
+        progress: Arc<Progress>,
+    }
+
+    struct NotifyState {
+        /// Drift signatures already seen, starting from any baseline given
+        seen: DriftBaseline,
+        windows: DriftWindows,
+        /// Findings that have already alerted in the current rate window
+        alerted: (u64, BTreeSet<String>),
+        last_sent: HashMap<String, Instant>,
+        /// Owner of the latest event per operation and drift type, for rate alerts
+        owners: HashMap<(String, DriftType), String>,
+    }
+
+    /// Alerts queued but not yet delivered, and the first failure since the last flush
+    #[derive(Default)]
+    struct Progress {
+        state: Mutex<(usize, Option<String>)>,
+        idle: Condvar,
+    }
+
+    impl fmt::Debug for WebhookNotifier {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("WebhookNotifier").field("config", &self.config).finish_non_exhaustive()
+        }
+    }
+
+    impl WebhookNotifier {
+        pub fn new(config: WebhookConfig) -> Result<Self, ValidationError> {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(|e| ValidationError::SinkError(format!("Webhook client: {}", e)))?;
+            let progress = Arc::new(Progress::default());
+            let (sender, receiver) = mpsc::channel::<Alert>();
+            let worker = {
+                let config = config.clone();
+                let progress = Arc::clone(&progress);
+                thread::spawn(move || {
+                    for alert in receiver {
+                        let result = deliver(&client, &config, &alert);
+                        let mut state = progress.lock();
+                        if let Err(e) = result {
+                            state.1.get_or_insert(e);
+                        }
+                        state.0 -= 1;
+                        progress.idle.notify_all();
+                    }
+                })
+            };
+            Ok(Self {
+                state: Mutex::new(NotifyState {
+                    seen: DriftBaseline::new(),
+                    windows: DriftWindows::default().with_retention(1),
+                    alerted: (0, BTreeSet::new()),
+                    last_sent: HashMap::new(),
+                    owners: HashMap::new(),
+                }),
+                config,
+                queue: Mutex::new(Some(sender)),
+                worker: Mutex::new(Some(worker)),
+                progress,
+            })
+        }
+
+        /// Treats drift in `baseline` as already known, so only drift beyond it alerts
+        ///
+        /// Signatures are compared under the baseline's fingerprint.
+        pub fn with_baseline(self, baseline: DriftBaseline) -> Self {
+            self.lock().seen = baseline;
+            self
+        }
+
+        /// Tells drift signatures apart by `fingerprint` instead of
+        /// [`crate::baseline::DefaultFingerprint`]
+        pub fn with_fingerprint(self, fingerprint: impl Fingerprint + 'static) -> Self {
+            {
+                let mut state = self.lock();
+                let seen = std::mem::take(&mut state.seen);
+                state.seen = seen.with_fingerprint(fingerprint);
+            }
+            self
+        }
+
+        /// Measures drift rates over windows of `width` instead of
+        /// [`crate::aggregation::DEFAULT_WINDOW`]
+        pub fn with_rate_window(self, width: Duration) -> Self {
+            self.lock().windows = DriftWindows::new(width).with_retention(1);
+            self
+        }
+
+        /// Queues `alert` unless it is for another team's drift or the same
+        /// finding alerted within the debounce period
+        fn send(&self, state: &mut NotifyState, alert: Alert) {
+            if !self.config.owners.is_empty()
+                && !alert.owner().is_some_and(|owner| self.config.owners.iter().any(|o| o == owner))
+            {
+                return;
+            }
+            let key = debounce_key(&alert);
+            let now = Instant::now();
+            let debounce = Duration::from_secs(self.config.debounce_secs);
+            if state
+                .last_sent
+                .get(&key)
+                .is_some_and(|sent| now.duration_since(*sent) < debounce)
+            {
+                return;
+            }
+            state.last_sent.retain(|_, sent| now.duration_since(*sent) < debounce);
+            state.last_sent.insert(key, now);
+
+            let queue = self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Some(sender) = queue.as_ref() {
+                self.progress.lock().0 += 1;
+                if sender.send(alert).is_err() {
+                    self.progress.lock().0 -= 1;
+                }
+            }
+        }
+
+        fn lock(&self) -> MutexGuard<'_, NotifyState> {
+            self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+
+    impl DriftSink for WebhookNotifier {
+        fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+            let mut state = self.lock();
+            if state.seen.contains(&event) {
+                return Ok(());
+            }
+            state.seen.add(&event);
+            if self.config.on_new_drift {
+                self.send(&mut state, Alert::NewDrift(Box::new(event)));
+            }
+            Ok(())
+        }
+
+        fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+            let Some(threshold) = self.config.rate_threshold else {
+                return;
+            };
+            let mut state = self.lock();
+            state.windows.record_exchange(operation, timestamp_ms, events);
+            for event in events {
+                if let Some(owner) = &event.owner {
+                    state.owners.insert((operation.to_string(), event.drift_type), owner.clone());
+                }
+            }
+            let crossed: Vec<_> = state
+                .windows
+                .latest_rates()
+                .into_iter()
+                .filter(|rate| {
+                    rate.operation == operation
+                        && rate.validated >= self.config.min_exchanges
+                        && rate.rate >= threshold
+                })
+                .collect();
+            for rate in crossed {
+                if state.alerted.0 != rate.window_start_ms {
+                    state.alerted = (rate.window_start_ms, BTreeSet::new());
+                }
+                let finding = format!("{} {} {}", rate.operation, rate.drift_type.as_str(), rate.location);
+                if state.alerted.1.insert(finding) {
+                    let owner = state.owners.get(&(rate.operation.clone(), rate.drift_type)).cloned();
+                    self.send(&mut state, Alert::RateExceeded { rate, threshold, owner });
+                }
+            }
+        }
+
+        fn flush(&self) -> Result<(), ValidationError> {
+            let mut state = self.progress.lock();
+            while state.0 > 0 {
+                state = self.progress.idle.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            match state.1.take() {
+                Some(failure) => Err(ValidationError::SinkError(format!(
+                    "Webhook {}: {}",
+                    self.config.url, failure
+                ))),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl Drop for WebhookNotifier {
+        /// Delivers whatever is still queued before going away
+        fn drop(&mut self) {
+            self.queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+            let worker = self.worker.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+            if let Some(worker) = worker {
+                let _ = worker.join();
+            }
+        }
+    }
+
+    impl Progress {
+        fn lock(&self) -> MutexGuard<'_, (usize, Option<String>)> {
+            self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+        }
+    }
+
+    /// What debouncing keys on: the same finding alerts at most once per period
+    fn debounce_key(alert: &Alert) -> String {
+        match alert {
+            Alert::NewDrift(event) => format!(
+                "new {} {} {}",
+                operation_label(event),
+                event.drift_type.as_str(),
+                event.location
+            ),
+            Alert::RateExceeded { rate, .. } => {
+                format!("rate {} {} {}", rate.operation, rate.drift_type.as_str(), rate.location)
+            }
+        }
+    }
+
+    /// Posts the alert, retrying transport errors, throttling and server errors
+    fn deliver(client: &reqwest::blocking::Client, config: &WebhookConfig, alert: &Alert) -> Result<(), String> {
+        let body = match &config.template {
+            Some(template) => alert.render(template),
+            None => alert.payload(config.format).to_string(),
+        };
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let failure = match client
+                .post(&config.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .send()
+            {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let failure = format!("{} for {} alert", status, alert.kind());
+                    if !(status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS) {
+                        return Err(failure);
+                    }
+                    failure
+                }
+                Err(e) => e.to_string(),
+            };
+            if attempt >= config.retries {
+                return Err(format!("{} (after {} attempts)", failure, attempt + 1));
+            }
+            attempt += 1;
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::api_validator::ApiValidator;
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::sinks::DriftSink;
+use opentelemetry::trace::{Span, Status, TraceContextExt, Tracer};
+use opentelemetry::{Array, Context, KeyValue, StringValue, Value};
+
+/// Name of the span event emitted for each drift finding
+pub const DRIFT_EVENT_NAME: &str = "drift";
+
+/// Records drift events as span events on the caller's current span
+///
+/// Useful in middleware where a request span is already active; events
+/// recorded outside any span are dropped by the OpenTelemetry no-op span.
+#[derive(Debug, Default)]
+pub struct OtelSink;
+
+impl OtelSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl DriftSink for OtelSink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        Context::current()
+            .span()
+            .add_event(DRIFT_EVENT_NAME, drift_attributes(&event));
+        Ok(())
+    }
+}
+
+/// Validates an exchange inside a `drift.validate_exchange` span
+///
+/// The span carries the route attributes, one span event per drift finding,
+/// and the drift count; its duration is the validation latency. Routing
+/// failures set the span status to error.
+pub fn validate_exchange_traced<T: Tracer>(
+    validator: &ApiValidator,
+    exchange: &Exchange,
+    tracer: &T,
+) -> Result<Vec<DriftEvent>, ValidationError> {
+    let request = &exchange.request;
+    let mut span = tracer.start("drift.validate_exchange");
+    span.set_attribute(KeyValue::new("http.request.method", request.method.as_str()));
+    span.set_attribute(KeyValue::new("url.path", request.path.clone()));
+    span.set_attribute(KeyValue::new(
+        "http.response.status_code",
+        i64::from(exchange.response.status),
+    ));
+    if let Some(template) = validator.path_template(&request.path) {
+        span.set_attribute(KeyValue::new("http.route", template.to_string()));
+    }
+    if let Ok((operation, _)) = validator.find_operation(&request.path, request.method) {
+        if let Some(operation_id) = &operation.operation_id {
+            span.set_attribute(KeyValue::new("drift.operation_id", operation_id.clone()));
+        }
+        if !operation.tags.is_empty() {
+            span.set_attribute(tags_attribute(&operation.tags));
+        }
+    }
+
+    let result = validator.validate_exchange(exchange);
+    match &result {
+        Ok(events) => {
+            for event in events {
+                span.add_event(DRIFT_EVENT_NAME, drift_attributes(event));
+            }
+            span.set_attribute(KeyValue::new("drift.count", events.len() as i64));
+        }
+        Err(e) => span.set_status(Status::error(e.to_string())),
+    }
+    span.end();
+    result
+}
+
+/// Attributes describing a drift event
+fn drift_attributes(event: &DriftEvent) -> Vec<KeyValue> {
+    let mut attributes = vec![
+        KeyValue::new("drift.type", event.drift_type.as_str()),
+        KeyValue::new("drift.severity", event.severity.as_str()),
+        KeyValue::new("drift.location", event.location.clone()),
+        KeyValue::new("drift.message", event.message.clone()),
+    ];
+    if let Some(template) = &event.path_template {
+        attributes.push(KeyValue::new("http.route", template.clone()));
+    }
+    if let Some(operation_id) = &event.operation_id {
+        attributes.push(KeyValue::new("drift.operation_id", operation_id.clone()));
+    }
+    if !event.tags.is_empty() {
+        attributes.push(tags_attribute(&event.tags));
+    }
+    if let Some(status_code) = event.status_code {
+        attributes.push(KeyValue::new("http.response.status_code", i64::from(status_code)));
+    }
+    attributes
+}
+
+/// The operation's spec tags, as a string array attribute
+fn tags_attribute(tags: &[String]) -> KeyValue {
+    let tags: Vec<StringValue> = tags.iter().cloned().map(StringValue::from).collect();
+    KeyValue::new("drift.tags", Value::Array(Array::String(tags)))
+}
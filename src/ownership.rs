@@ -0,0 +1,111 @@
+//! Routing drift to the team that owns it
+//!
+//! A finding is only useful once it reaches whoever can fix it. An
+//! [`Ownership`] mapping names owners by drift type, by the spec's
+//! operation tags and by path prefix; the validator resolves each event's
+//! owner as it is emitted, and webhook alerts carry it, so a team's
+//! webhook can be limited to the drift it owns.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Who owns which drift
+///
+/// ```yaml
+/// ownership:
+///   drift_types:
+///     SECURITY_REQUIREMENT_DRIFT: security
+///   tags:
+///     billing: payments
+///   paths:
+///     /admin: platform
+///   default: api-guild
+/// ```
+///
+/// The first mapping that applies wins, in the order above: the drift
+/// type, then the first of the operation's tags with an owner, then the
+/// longest path prefix matching the operation's template segment by
+/// segment, so `/admin` owns `/admin/users` but not `/administrators`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ownership {
+    /// Owner per drift type, whichever operation it is found on
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub drift_types: BTreeMap<DriftType, String>,
+    /// Owner per OpenAPI tag
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Owner per path prefix
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub paths: BTreeMap<String, String>,
+    /// Owner of drift no other mapping applies to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl Ownership {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn drift_type(mut self, drift_type: DriftType, owner: impl Into<String>) -> Self {
+        self.drift_types.insert(drift_type, owner.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>, owner: impl Into<String>) -> Self {
+        self.tags.insert(tag.into(), owner.into());
+        self
+    }
+
+    pub fn path(mut self, prefix: impl Into<String>, owner: impl Into<String>) -> Self {
+        self.paths.insert(prefix.into(), owner.into());
+        self
+    }
+
+    pub fn with_default(mut self, owner: impl Into<String>) -> Self {
+        self.default = Some(owner.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drift_types.is_empty() && self.tags.is_empty() && self.paths.is_empty() && self.default.is_none()
+    }
+
+    /// The owner of `event`, if any mapping applies
+    pub fn owner(&self, event: &DriftEvent) -> Option<&str> {
+        if let Some(owner) = self.drift_types.get(&event.drift_type) {
+            return Some(owner);
+        }
+        if let Some(owner) = event.tags.iter().find_map(|tag| self.tags.get(tag)) {
+            return Some(owner);
+        }
+        let path = event.path_template.as_deref().or(event.path.as_deref());
+        let by_path = path.and_then(|path| {
+            self.paths
+                .iter()
+                .filter(|(prefix, _)| has_prefix(path, prefix))
+                .max_by_key(|(prefix, _)| prefix.trim_end_matches('/').len())
+                .map(|(_, owner)| owner)
+        });
+        by_path.or(self.default.as_ref()).map(String::as_str)
+    }
+
+    /// Sets the owner of `event`, keeping one it already has
+    pub fn assign(&self, event: &mut DriftEvent) {
+        if event.owner.is_none() {
+            event.owner = self.owner(event).map(str::to_string);
+        }
+    }
+}
+
+/// Whether `prefix` is `path` or a run of its leading segments
+fn has_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
@@ -0,0 +1,563 @@
+//! Payloads generated from schemas, conforming or deliberately broken
+//!
+//! [`generate_example`] builds one value matching a schema, as the mock
+//! server does for responses that document no example. From any valid
+//! payload a [`PayloadGenerator`] derives [`Mutation`]s, each breaking one
+//! constraint and naming the drift that should be reported for it, so the
+//! validators can be checked against payloads of every shape a schema
+//! allows (see `tests/payload_properties.rs`) rather than a handful of fixtures.
+
+use crate::drift_types::{DriftType, ValidationContext};
+use crate::spec::transform::Direction;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+
+/// Nesting depth past which generated payloads stop descending, so recursive schemas terminate
+const MAX_GENERATED_DEPTH: usize = 8;
+
+/// A value matching `schema`, resolving `$ref`s within the schema itself
+///
+/// The value is always the same for a schema: the schema's own `example`,
+/// `const` or `default` where it has one, otherwise the first `enum`
+/// value, the lower bound of numbers and every documented property.
+pub fn generate_example(schema: &Value) -> Value {
+    PayloadGenerator::new(schema).generate(schema)
+}
+
+/// Builds payloads matching schemas of one document
+///
+/// `$ref`s resolve against the document, typically the whole spec or
+/// its components. `oneOf` always takes its first branch, which another
+/// branch may happen to match as well when the branches aren't disjoint.
+pub struct PayloadGenerator<'a> {
+    /// The document local `$ref`s resolve against
+    document: &'a Value,
+    /// Properties left out: `readOnly` ones in requests, `writeOnly` ones in responses
+    excluded: Option<&'static str>,
+    /// References being generated, outermost first
+    expanding: RefCell<Vec<String>>,
+}
+
+impl<'a> PayloadGenerator<'a> {
+    pub fn new(document: &'a Value) -> Self {
+        Self {
+            document,
+            excluded: None,
+            expanding: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Generates the payloads of one side of an exchange, leaving out
+    /// properties that don't travel in `direction`
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.excluded = Some(match direction {
+            Direction::Request => "readOnly",
+            Direction::Response => "writeOnly",
+        });
+        self
+    }
+
+    /// A payload matching `schema`
+    pub fn generate(&self, schema: &Value) -> Value {
+        self.generate_at(schema, 0)
+    }
+
+    fn generate_at(&self, schema: &Value, depth: usize) -> Value {
+        let reference = schema.get("$ref").and_then(Value::as_str);
+        if let Some(reference) = reference {
+            self.expanding.borrow_mut().push(reference.to_string());
+        }
+        let value = self.generate_resolved(self.resolve(schema), depth);
+        if reference.is_some() {
+            self.expanding.borrow_mut().pop();
+        }
+        value
+    }
+
+    fn generate_resolved(&self, schema: &Value, depth: usize) -> Value {
+        for keyword in ["example", "const", "default"] {
+            if let Some(value) = schema.get(keyword) {
+                return value.clone();
+            }
+        }
+        if let Some(first) = schema.get("enum").and_then(Value::as_array).and_then(|values| values.first()) {
+            return first.clone();
+        }
+        if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Map::new();
+            for branch in branches {
+                match self.generate_at(branch, depth) {
+                    Value::Object(object) => merged.extend(object),
+                    other if merged.is_empty() => return other,
+                    _ => {}
+                }
+            }
+            return Value::Object(merged);
+        }
+        let first_branch = |keyword| schema.get(keyword).and_then(Value::as_array).and_then(|branches| branches.first());
+        if let Some(first) = first_branch("oneOf").or_else(|| first_branch("anyOf")) {
+            return self.generate_at(first, depth);
+        }
+
+        match declared_type(schema) {
+            Some("object") => self.object(schema, depth),
+            Some("array") => self.array(schema, depth),
+            Some("string") => Value::String(self.string(schema)),
+            Some("integer") => json!(self.integer(schema)),
+            Some("number") => json!(self.number(schema)),
+            Some("boolean") => Value::Bool(true),
+            _ => Value::Null,
+        }
+    }
+
+    fn object(&self, schema: &Value, depth: usize) -> Value {
+        let mut object = Map::new();
+        if depth >= MAX_GENERATED_DEPTH {
+            return Value::Object(object);
+        }
+        let required = schema.get("required").and_then(Value::as_array);
+        for (name, property) in schema.get("properties").and_then(Value::as_object).into_iter().flatten() {
+            if self.excluded.is_some_and(|keyword| self.resolve(property).get(keyword) == Some(&Value::Bool(true))) {
+                continue;
+            }
+            let is_required = required.is_some_and(|required| required.iter().any(|field| field == name.as_str()));
+            if !is_required {
+                // Optional properties recursing into the schema being built are left out
+                if self.recurses(property) {
+                    continue;
+                }
+            }
+            object.insert(name.clone(), self.generate_at(property, depth + 1));
+        }
+        Value::Object(object)
+    }
+
+    fn array(&self, schema: &Value, depth: usize) -> Value {
+        let count = schema.get("minItems").and_then(Value::as_u64).unwrap_or(1).max(1);
+        let Some(items) = schema.get("items").filter(|_| depth < MAX_GENERATED_DEPTH) else {
+            return Value::Array(Vec::new());
+        };
+        let mut values: Vec<Value> = (0..count).map(|_| self.generate_at(items, depth + 1)).collect();
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let mut unique = Vec::with_capacity(values.len());
+            for value in values {
+                if !unique.contains(&value) {
+                    unique.push(value);
+                }
+            }
+            values = unique;
+        }
+        Value::Array(values)
+    }
+
+    /// A string satisfying a schema's `format`, `pattern` and length bounds
+    fn string(&self, schema: &Value) -> String {
+        let format = schema.get("format").and_then(Value::as_str);
+        let sample = match format {
+            Some("date-time") => Some("2024-01-01T00:00:00Z"),
+            Some("date") => Some("2024-01-01"),
+            Some("time") => Some("00:00:00Z"),
+            Some("email") => Some("user@example.com"),
+            Some("uuid") => Some("00000000-0000-0000-0000-000000000000"),
+            Some("uri" | "url") => Some("https://example.com"),
+            Some("hostname") => Some("example.com"),
+            Some("ipv4") => Some("192.0.2.1"),
+            Some("ipv6") => Some("2001:db8::1"),
+            Some("byte") => Some("c3RyaW5n"),
+            _ => None,
+        };
+        let min_length = schema.get("minLength").and_then(Value::as_u64);
+        let max_length = schema.get("maxLength").and_then(Value::as_u64);
+        let pattern = schema.get("pattern").and_then(Value::as_str);
+        if let Some(matching) = pattern.and_then(|pattern| self.matching(pattern)) {
+            return matching;
+        }
+        let mut sample = sample.unwrap_or("string").to_string();
+        if let Some(min_length) = min_length {
+            while (sample.chars().count() as u64) < min_length {
+                sample.push('x');
+            }
+        }
+        if let Some(max_length) = max_length {
+            sample = sample.chars().take(max_length as usize).collect();
+        }
+        sample
+    }
+
+    fn integer(&self, schema: &Value) -> i64 {
+        let (low, high) = integer_bounds(schema);
+        let value = match (low, high) {
+            (Some(low), _) => low,
+            (None, high) => high.map_or(0, |high| high.min(0)),
+        };
+        match schema.get("multipleOf").and_then(Value::as_i64).filter(|step| *step > 0) {
+            Some(step) => {
+                let above = value.div_euclid(step).saturating_add(i64::from(value.rem_euclid(step) != 0)).saturating_mul(step);
+                match high {
+                    Some(high) if above > high => value.div_euclid(step).saturating_mul(step),
+                    _ => above,
+                }
+            }
+            None => value,
+        }
+    }
+
+    fn number(&self, schema: &Value) -> f64 {
+        let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+        let exclusive = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+        let low = bound("minimum").map(|low| (low, exclusive("exclusiveMinimum"))).or(bound("exclusiveMinimum").map(|low| (low, true)));
+        let high = bound("maximum").map(|high| (high, exclusive("exclusiveMaximum"))).or(bound("exclusiveMaximum").map(|high| (high, true)));
+        // Multiples are generated as whole numbers, of whole steps only
+        if schema.get("multipleOf").is_some() {
+            return self.integer(schema) as f64;
+        }
+        match (low, high) {
+            (Some((low, false)), _) => low,
+            (Some((low, true)), Some((high, _))) => (low + high) / 2.0,
+            (Some((low, true)), None) => low + 1.0,
+            (None, Some((high, _))) => high.min(0.0) - f64::from(u8::from(high <= 0.0)),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// A string matching `pattern`, or `None` if the pattern isn't understood
+    fn matching(&self, pattern: &str) -> Option<String> {
+        let hir = regex_syntax::parse(pattern).ok()?;
+        let mut matching = String::new();
+        self.push_matching(&hir, &mut matching);
+        Some(matching)
+    }
+
+    fn push_matching(&self, hir: &Hir, matching: &mut String) {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => {}
+            HirKind::Literal(literal) => matching.push_str(&String::from_utf8_lossy(&literal.0)),
+            HirKind::Class(Class::Unicode(class)) => {
+                let ranges: Vec<(char, char)> = class.ranges().iter().map(|range| (range.start(), range.end())).collect();
+                if let Some(c) = self.class_member(&ranges) {
+                    matching.push(c);
+                }
+            }
+            HirKind::Class(Class::Bytes(class)) => {
+                let ranges: Vec<(char, char)> = class
+                    .ranges()
+                    .iter()
+                    .filter(|range| range.start().is_ascii())
+                    .map(|range| (char::from(range.start()), char::from(range.end().min(0x7f))))
+                    .collect();
+                if let Some(c) = self.class_member(&ranges) {
+                    matching.push(c);
+                }
+            }
+            HirKind::Repetition(repetition) => {
+                for _ in 0..repetition.min {
+                    self.push_matching(&repetition.sub, matching);
+                }
+            }
+            HirKind::Capture(capture) => self.push_matching(&capture.sub, matching),
+            HirKind::Concat(parts) => {
+                for part in parts {
+                    self.push_matching(part, matching);
+                }
+            }
+            HirKind::Alternation(branches) => {
+                if let Some(first) = branches.first() {
+                    self.push_matching(first, matching);
+                }
+            }
+        }
+    }
+
+    /// A character in a class, preferring letters and digits to punctuation and control characters
+    fn class_member(&self, ranges: &[(char, char)]) -> Option<char> {
+        let contains = |c: char| ranges.iter().any(|(start, end)| (*start..=*end).contains(&c));
+        ('a'..='z')
+            .chain('0'..='9')
+            .chain('A'..='Z')
+            .find(|c| contains(*c))
+            .or_else(|| ranges.first().map(|(start, _)| *start))
+    }
+
+    /// Whether a property, or the items of an array property, refers to a schema being generated
+    fn recurses(&self, property: &Value) -> bool {
+        let expanding = self.expanding.borrow();
+        [property.get("$ref"), property.pointer("/items/$ref")]
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .any(|reference| expanding.iter().any(|outer| outer == reference))
+    }
+
+    /// Follows local `$ref`s
+    fn resolve<'s>(&'s self, schema: &'s Value) -> &'s Value {
+        let mut current = schema;
+        // Bounded, in case references form a cycle
+        for _ in 0..MAX_GENERATED_DEPTH {
+            let Some(target) = current
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| self.document.pointer(pointer))
+            else {
+                break;
+            };
+            current = target;
+        }
+        current
+    }
+
+    /// Every single-constraint violation of `schema` that can be made in `payload`
+    ///
+    /// Each mutation changes one value of `payload`, which is expected to
+    /// match `schema`: a value of the wrong type, a required property
+    /// removed, a value outside its enum, range or length bounds, an array
+    /// of the wrong size or a property added to a closed object. Values
+    /// under `oneOf`, `anyOf` or `not`, and nullable compositions, aren't
+    /// mutated, as their violations are reported as the composition failing.
+    pub fn mutations(&self, schema: &Value, payload: &Value) -> Vec<Mutation> {
+        let mut mutations = Vec::new();
+        self.mutate(schema, payload, "", payload, 0, &mut mutations);
+        mutations
+    }
+
+    fn mutate(&self, schema: &Value, value: &Value, pointer: &str, payload: &Value, depth: usize, mutations: &mut Vec<Mutation>) {
+        if depth >= MAX_GENERATED_DEPTH {
+            return;
+        }
+        let composed = ["$ref", "allOf", "anyOf", "oneOf"].iter().any(|keyword| schema.get(*keyword).is_some());
+        if composed && schema.get("nullable") == Some(&Value::Bool(true)) {
+            return;
+        }
+        let schema = self.resolve(schema);
+        for branch in schema.get("allOf").and_then(Value::as_array).into_iter().flatten() {
+            self.mutate(branch, value, pointer, payload, depth + 1, mutations);
+        }
+        let mut replace = |kind, replacement: Value| {
+            let mut mutated = payload.clone();
+            if let Some(target) = mutated.pointer_mut(pointer) {
+                *target = replacement;
+                mutations.push(Mutation {
+                    kind,
+                    pointer: pointer.to_string(),
+                    payload: mutated,
+                });
+            }
+        };
+
+        if let Some(replacement) = wrong_type(schema) {
+            replace(MutationKind::WrongType, replacement);
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if values.iter().all(Value::is_string) {
+                let outside = (0..)
+                    .map(|n| format!("not-in-enum-{}", n))
+                    .find(|candidate| !values.iter().any(|value| value == candidate.as_str()))
+                    .unwrap_or_default();
+                replace(MutationKind::EnumViolation, Value::String(outside));
+            }
+        }
+        if matches!(declared_type(schema), Some("integer" | "number")) && schema.get("enum").is_none() {
+            let (low, high) = integer_bounds(schema);
+            if let Some(low) = low {
+                replace(MutationKind::RangeViolation, json!(low.saturating_sub(1)));
+            }
+            if let Some(high) = high {
+                replace(MutationKind::RangeViolation, json!(high.saturating_add(1)));
+            }
+        }
+        if declared_type(schema) == Some("string") && schema.get("enum").is_none() {
+            if let Some(max_length) = schema.get("maxLength").and_then(Value::as_u64) {
+                replace(MutationKind::LengthViolation, Value::String("x".repeat(max_length as usize + 1)));
+            }
+            if schema.get("minLength").and_then(Value::as_u64).is_some_and(|min_length| min_length > 0) {
+                replace(MutationKind::LengthViolation, Value::String(String::new()));
+            }
+        }
+
+        match value {
+            Value::Object(object) => {
+                let required = schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str);
+                for name in required.filter(|name| object.contains_key(*name)) {
+                    let mut without = object.clone();
+                    without.remove(name);
+                    replace(MutationKind::MissingRequired, Value::Object(without));
+                }
+                let properties = schema.get("properties").and_then(Value::as_object);
+                if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+                    let name = (0..)
+                        .map(|n| format!("undocumented_{}", n))
+                        .find(|candidate| !object.contains_key(candidate) && !properties.is_some_and(|properties| properties.contains_key(candidate)))
+                        .unwrap_or_default();
+                    let mut extended = object.clone();
+                    extended.insert(name, Value::Bool(true));
+                    replace(MutationKind::UndocumentedField, Value::Object(extended));
+                }
+                for (name, property) in properties.into_iter().flatten() {
+                    if let Some(child) = object.get(name) {
+                        self.mutate(property, child, &format!("{}/{}", pointer, escape(name)), payload, depth + 1, mutations);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+                    let filler = items.first().cloned().or_else(|| schema.get("items").map(|items| self.generate(items)));
+                    if let Some(filler) = filler {
+                        let mut longer = items.clone();
+                        while (longer.len() as u64) <= max_items {
+                            longer.push(filler.clone());
+                        }
+                        replace(MutationKind::ArrayConstraintViolation, Value::Array(longer));
+                    }
+                }
+                if schema.get("minItems").and_then(Value::as_u64).is_some_and(|min_items| min_items > 0) {
+                    replace(MutationKind::ArrayConstraintViolation, Value::Array(Vec::new()));
+                }
+                if let (Some(item_schema), Some(first)) = (schema.get("items"), items.first()) {
+                    self.mutate(item_schema, first, &format!("{}/0", pointer), payload, depth + 1, mutations);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One constraint of a schema broken in an otherwise valid payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    pub kind: MutationKind,
+    /// JSON pointer to the value changed; for a removed property, its object
+    pub pointer: String,
+    /// The whole payload with the change made
+    pub payload: Value,
+}
+
+impl Mutation {
+    /// The drift a validator should report for the mutated payload
+    pub fn expected_drift(&self, context: ValidationContext) -> Option<DriftType> {
+        self.kind.drift_type(context)
+    }
+}
+
+/// Which kind of constraint a [`Mutation`] breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MutationKind {
+    /// A value of a type the schema doesn't allow
+    WrongType,
+    /// A required property removed
+    MissingRequired,
+    /// A string outside the schema's enum
+    EnumViolation,
+    /// A number below `minimum` or above `maximum`
+    RangeViolation,
+    /// A string shorter than `minLength` or longer than `maxLength`
+    LengthViolation,
+    /// An array with fewer than `minItems` or more than `maxItems` items
+    ArrayConstraintViolation,
+    /// A property added to an object closed with `additionalProperties: false`
+    UndocumentedField,
+}
+
+impl MutationKind {
+    /// The drift reported for this kind of violation where it's found,
+    /// if it's reported there at all
+    pub fn drift_type(self, context: ValidationContext) -> Option<DriftType> {
+        use DriftType::*;
+        use MutationKind::*;
+        use ValidationContext::*;
+
+        Some(match (context, self) {
+            (Parameter, WrongType) => ParameterTypeMismatch,
+            (Parameter, MissingRequired) => ParameterMissingRequired,
+            (Parameter, EnumViolation) => ParameterEnumViolation,
+            (Parameter, RangeViolation) => ParameterRangeViolation,
+            (Parameter, LengthViolation) => ParameterLengthViolation,
+            (Parameter, ArrayConstraintViolation) => ParameterArrayConstraintViolation,
+            (Parameter, UndocumentedField) => return None,
+            (RequestBody, WrongType) => RequestBodyTypeMismatch,
+            (RequestBody, MissingRequired) => RequestBodyMissingRequired,
+            (RequestBody, EnumViolation) => RequestBodyEnumViolation,
+            (RequestBody, RangeViolation) => RequestBodyRangeViolation,
+            (RequestBody, LengthViolation) => RequestBodyLengthViolation,
+            (RequestBody, ArrayConstraintViolation) => RequestBodyArrayConstraintViolation,
+            (RequestBody, UndocumentedField) => RequestBodyUndocumentedField,
+            (ResponseBody, WrongType) => ResponseBodyTypeMismatch,
+            (ResponseBody, MissingRequired) => ResponseBodyMissingRequired,
+            (ResponseBody, EnumViolation) => ResponseBodyEnumViolation,
+            (ResponseBody, RangeViolation) => ResponseBodyRangeViolation,
+            (ResponseBody, LengthViolation) => ResponseBodyLengthViolation,
+            (ResponseBody, ArrayConstraintViolation) => ResponseBodyArrayConstraintViolation,
+            (ResponseBody, UndocumentedField) => ResponseBodyUndocumentedField,
+            (MessagePayload, WrongType) => MessagePayloadTypeMismatch,
+            (MessagePayload, MissingRequired) => MessagePayloadMissingRequired,
+            (MessagePayload, EnumViolation) => MessagePayloadEnumViolation,
+            (MessagePayload, RangeViolation) => MessagePayloadRangeViolation,
+            (MessagePayload, LengthViolation) => MessagePayloadLengthViolation,
+            (MessagePayload, ArrayConstraintViolation) => MessagePayloadArrayConstraintViolation,
+            (MessagePayload, UndocumentedField) => MessagePayloadUndocumentedField,
+        })
+    }
+}
+
+/// The schema's `type`, or the first non-null one of a 3.1 type list,
+/// inferred from `properties` or `items` when not declared
+fn declared_type(schema: &Value) -> Option<&str> {
+    let declared = match schema.get("type") {
+        Some(Value::String(name)) => Some(name.as_str()),
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).find(|name| *name != "null"),
+        _ => None,
+    };
+    declared.or_else(|| {
+        if schema.get("properties").is_some() {
+            Some("object")
+        } else if schema.get("items").is_some() {
+            Some("array")
+        } else {
+            None
+        }
+    })
+}
+
+/// The smallest and largest whole numbers a schema's bounds allow
+fn integer_bounds(schema: &Value) -> (Option<i64>, Option<i64>) {
+    let exclusive = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+    let low = match (schema.get("minimum").and_then(Value::as_f64), schema.get("exclusiveMinimum").and_then(Value::as_f64)) {
+        // 3.1's numeric `exclusiveMinimum`
+        (_, Some(bound)) => Some(bound.floor() as i64 + 1),
+        (Some(minimum), None) if exclusive("exclusiveMinimum") => Some(minimum.floor() as i64 + 1),
+        (Some(minimum), None) => Some(minimum.ceil() as i64),
+        (None, None) => None,
+    };
+    let high = match (schema.get("maximum").and_then(Value::as_f64), schema.get("exclusiveMaximum").and_then(Value::as_f64)) {
+        (_, Some(bound)) => Some(bound.ceil() as i64 - 1),
+        (Some(maximum), None) if exclusive("exclusiveMaximum") => Some(maximum.ceil() as i64 - 1),
+        (Some(maximum), None) => Some(maximum.floor() as i64),
+        (None, None) => None,
+    };
+    (low, high)
+}
+
+/// A value of a type `schema` doesn't allow, if it restricts the type
+fn wrong_type(schema: &Value) -> Option<Value> {
+    let allowed: Vec<&str> = match schema.get("type")? {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => return None,
+    };
+    let allows = |name: &str| allowed.contains(&name) || (name == "integer" && allowed.contains(&"number"));
+    [
+        ("string", json!("mutated")),
+        ("integer", json!(7)),
+        ("boolean", json!(true)),
+        ("object", json!({})),
+        ("array", json!([])),
+    ]
+    .into_iter()
+    .find(|(name, _)| !allows(name))
+    .map(|(_, value)| value)
+}
+
+/// Escapes a property name for use in a JSON pointer
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
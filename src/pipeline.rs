@@ -0,0 +1,244 @@
+//! Embeddable monitoring pipeline: sources → filters → validator → aggregator → sinks
+//!
+//! The CLI and config files are one way to wire the monitor; binaries that
+//! embed it assemble the same stages in code:
+//!
+//! ```no_run
+//! # async fn run(validator: std::sync::Arc<api_spec_drift_monitor_poc::ApiValidator>,
+//! #              exchanges: Vec<api_spec_drift_monitor_poc::Exchange>)
+//! #     -> Result<(), api_spec_drift_monitor_poc::ValidationError> {
+//! use api_spec_drift_monitor_poc::pipeline::{self, Pipeline};
+//! use api_spec_drift_monitor_poc::{BlockingSink, StdoutJsonlSink};
+//!
+//! let report = Pipeline::builder()
+//!     .source(pipeline::iter(exchanges))
+//!     .validator(validator)
+//!     .sink(BlockingSink::new(StdoutJsonlSink::new()))
+//!     .build()?
+//!     .run()
+//!     .await?;
+//! println!("{} drift events", report.summary.total);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::{now_ms, DriftEvent, EventContext};
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::exchange_filter::ExchangeFilter;
+use crate::report::DriftReport;
+use crate::reproducer::ReproducerCapture;
+use crate::sampling::{ExchangeSampler, Sampling, Stratum};
+use crate::sinks::AsyncDriftSink;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Future returned by [`ExchangeSource::next_exchange`]
+pub type SourceFuture<'a> = Pin<Box<dyn Future<Output = Result<Option<Exchange>, ValidationError>> + Send + 'a>>;
+
+/// Where a pipeline's observed traffic comes from
+///
+/// `Ok(None)` ends the run. To merge several live sources, forward them
+/// into one [`mpsc::Receiver`].
+pub trait ExchangeSource: Send {
+    /// The next observed exchange, or `None` once the source is exhausted
+    fn next_exchange(&mut self) -> SourceFuture<'_>;
+}
+
+impl<S: ExchangeSource + ?Sized> ExchangeSource for Box<S> {
+    fn next_exchange(&mut self) -> SourceFuture<'_> {
+        (**self).next_exchange()
+    }
+}
+
+/// Exchanges sent by other tasks, until every sender is dropped
+impl ExchangeSource for mpsc::Receiver<Exchange> {
+    fn next_exchange(&mut self) -> SourceFuture<'_> {
+        Box::pin(async move { Ok(self.recv().await) })
+    }
+}
+
+/// A source yielding already collected exchanges
+pub struct IterSource<I> {
+    exchanges: I,
+}
+
+/// Wraps already collected exchanges (a replayed capture, a test fixture) as a source
+pub fn iter<I>(exchanges: I) -> IterSource<I::IntoIter>
+where
+    I: IntoIterator<Item = Exchange>,
+{
+    IterSource {
+        exchanges: exchanges.into_iter(),
+    }
+}
+
+impl<I: Iterator<Item = Exchange> + Send> ExchangeSource for IterSource<I> {
+    fn next_exchange(&mut self) -> SourceFuture<'_> {
+        let next = self.exchanges.next();
+        Box::pin(async move { Ok(next) })
+    }
+}
+
+type ExchangePredicate = Box<dyn Fn(&Exchange) -> bool + Send + Sync>;
+
+/// Assembles a [`Pipeline`]; a source and a validator are required
+#[derive(Default)]
+pub struct PipelineBuilder {
+    source: Option<Box<dyn ExchangeSource>>,
+    filter: ExchangeFilter,
+    predicates: Vec<ExchangePredicate>,
+    validator: Option<Arc<ApiValidator>>,
+    context: EventContext,
+    sampling: Sampling,
+    sinks: Vec<Box<dyn AsyncDriftSink>>,
+}
+
+impl PipelineBuilder {
+    /// Sets where traffic comes from
+    pub fn source(mut self, source: impl ExchangeSource + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// Skips exchanges the filter rejects, in addition to the validator's own filter
+    pub fn filter(mut self, filter: ExchangeFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Skips exchanges for which `predicate` returns false
+    pub fn filter_fn(mut self, predicate: impl Fn(&Exchange) -> bool + Send + Sync + 'static) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Sets the validator exchanges are checked against
+    pub fn validator(mut self, validator: Arc<ApiValidator>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Attaches caller-supplied context to every event
+    pub fn context(mut self, context: EventContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Validates only the exchanges `sampling` admits; the report's drift rates account for the rest
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Adds a destination for drift events; sync sinks can be wrapped in [`crate::sinks::BlockingSink`]
+    pub fn sink(mut self, sink: impl AsyncDriftSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn build(self) -> Result<Pipeline, ValidationError> {
+        let source = self
+            .source
+            .ok_or_else(|| ValidationError::ConfigError("pipeline has no source".to_string()))?;
+        let validator = self
+            .validator
+            .ok_or_else(|| ValidationError::ConfigError("pipeline has no validator".to_string()))?;
+        Ok(Pipeline {
+            source,
+            filter: self.filter,
+            predicates: self.predicates,
+            validator,
+            context: Arc::new(self.context),
+            sampler: ExchangeSampler::new(self.sampling),
+            sinks: self.sinks,
+        })
+    }
+}
+
+/// A wired monitoring pipeline, run with [`Pipeline::run`]
+pub struct Pipeline {
+    source: Box<dyn ExchangeSource>,
+    filter: ExchangeFilter,
+    predicates: Vec<ExchangePredicate>,
+    validator: Arc<ApiValidator>,
+    context: Arc<EventContext>,
+    sampler: ExchangeSampler,
+    sinks: Vec<Box<dyn AsyncDriftSink>>,
+}
+
+impl Pipeline {
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    /// Validates every exchange until the source is exhausted, returning the aggregated report
+    ///
+    /// Validation runs on tokio's blocking pool so large bodies don't stall
+    /// the runtime. Exchanges that route to no operation are skipped; a
+    /// failing source or sink stops the run.
+    pub async fn run(mut self) -> Result<DriftReport, ValidationError> {
+        let mut report = DriftReport::new();
+        let reproducers = Arc::new(ReproducerCapture::new().with_redaction(self.validator.redaction().clone()));
+        let suppressed = self.validator.suppressed_events();
+        while let Some(exchange) = self.source.next_exchange().await? {
+            if !self.filter.allows(&exchange)
+                || !self.validator.exchange_filter().allows(&exchange)
+                || !self.predicates.iter().all(|predicate| predicate(&exchange))
+            {
+                continue;
+            }
+            let Some(template) = self.validator.path_template(&exchange.request.path) else {
+                continue;
+            };
+            let method = exchange.request.method;
+            let stratum = Stratum::of(exchange.response.status);
+            let timestamp_ms = now_ms();
+            if !self.sampler.admit(&format!("{} {}", method.as_str(), template), exchange.response.status, timestamp_ms) {
+                report.record_sampled_at(method, template, timestamp_ms, stratum, None);
+                continue;
+            }
+
+            let validator = Arc::clone(&self.validator);
+            let context = Arc::clone(&self.context);
+            let capture = Arc::clone(&reproducers);
+            let (exchange, result) = tokio::task::spawn_blocking(move || {
+                let result = validator.validate_exchange_with_context(&exchange, &context);
+                if let Ok(events) = &result {
+                    capture.capture(&exchange, events);
+                }
+                (exchange, result)
+            })
+            .await
+            .map_err(|e| ValidationError::ValidationFailed(format!("Validation task failed: {}", e)))?;
+            let Ok(events) = result else {
+                continue;
+            };
+            let Some(template) = self.validator.path_template(&exchange.request.path) else {
+                continue;
+            };
+
+            self.publish(&events).await?;
+            report.record_sampled_at(method, template, timestamp_ms, stratum, Some(events));
+        }
+
+        for sink in &self.sinks {
+            sink.flush().await?;
+        }
+        report.summary.suppressed = self.validator.suppressed_events().saturating_sub(suppressed);
+        Ok(report.with_reproducers(reproducers.reproducers()))
+    }
+
+    async fn publish(&self, events: &[DriftEvent]) -> Result<(), ValidationError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        for sink in &self.sinks {
+            sink.record_all(events.to_vec()).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! Custom sinks and rules loaded at runtime as WebAssembly components
+//!
+//! Proprietary integrations (an internal ticketing system, a house rule about
+//! header naming) can be shipped to users of the monitor without forking it:
+//! they are built as components against `wit/drift-plugin.wit` and loaded
+//! from a `.wasm` file. A sink component implements the `sink` world and
+//! receives every event as a JSON string; a rule component implements the
+//! `rule` world, sees each exchange and returns drift events as JSON.
+//!
+//! Components get no imports, so a plugin can't touch the filesystem or
+//! network, and each call runs with a fuel budget so a runaway loop fails
+//! the call rather than stalling the monitor.
+
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::sinks::{to_json_line, DriftSink};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config, Engine, Store};
+
+mod sink_world {
+    wasmtime::component::bindgen!({ path: "wit/drift-plugin.wit", world: "sink" });
+}
+
+mod rule_world {
+    wasmtime::component::bindgen!({ path: "wit/drift-plugin.wit", world: "rule" });
+}
+
+/// Fuel a plugin may burn per call before it is aborted
+const MAX_FUEL: u64 = 100_000_000;
+
+/// A component instance with the store it lives in
+struct Instance<B> {
+    store: Store<()>,
+    bindings: B,
+}
+
+impl<B> Instance<B> {
+    fn load(
+        path: &Path,
+        instantiate: impl FnOnce(&mut Store<()>, &Component, &Linker<()>) -> wasmtime::Result<B>,
+    ) -> Result<Self, ValidationError> {
+        let engine = engine()?;
+        let component = Component::from_file(engine, path)
+            .map_err(|e| ValidationError::PluginError(format!("Failed to load {}: {:#}", path.display(), e)))?;
+        let mut store = Store::new(engine, ());
+        let bindings = instantiate(&mut store, &component, &Linker::new(engine)).map_err(|e| {
+            ValidationError::PluginError(format!("Failed to instantiate {}: {:#}", path.display(), e))
+        })?;
+        Ok(Self { store, bindings })
+    }
+
+    /// Runs one export with a fresh fuel budget
+    fn call<R>(
+        &mut self,
+        plugin: &str,
+        call: impl FnOnce(&B, &mut Store<()>) -> wasmtime::Result<Result<R, String>>,
+    ) -> Result<R, ValidationError> {
+        self.store
+            .set_fuel(MAX_FUEL)
+            .map_err(|e| ValidationError::PluginError(format!("{}: {:#}", plugin, e)))?;
+        match call(&self.bindings, &mut self.store) {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(message)) => Err(ValidationError::PluginError(format!("{}: {}", plugin, message))),
+            Err(trap) => Err(ValidationError::PluginError(format!("{} trapped: {:#}", plugin, trap))),
+        }
+    }
+}
+
+/// Engine shared by every plugin in the process
+fn engine() -> Result<&'static Engine, ValidationError> {
+    static ENGINE: OnceLock<Result<Engine, String>> = OnceLock::new();
+    ENGINE
+        .get_or_init(|| {
+            let mut config = Config::new();
+            config.wasm_component_model(true).consume_fuel(true);
+            Engine::new(&config).map_err(|e| format!("{:#}", e))
+        })
+        .as_ref()
+        .map_err(|e| ValidationError::PluginError(format!("Failed to start the WASM engine: {}", e)))
+}
+
+/// Name a plugin is referred to by in errors: its file name
+fn plugin_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// A drift sink implemented by a WASM component
+pub struct WasmSink {
+    name: String,
+    instance: Mutex<Instance<sink_world::Sink>>,
+}
+
+impl WasmSink {
+    /// Loads a component implementing the `sink` world
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let instance = Instance::load(path, |store, component, linker| {
+            sink_world::Sink::instantiate(store, component, linker)
+        })?;
+        Ok(Self {
+            name: plugin_name(path),
+            instance: Mutex::new(instance),
+        })
+    }
+}
+
+impl DriftSink for WasmSink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        let line = to_json_line(&event)?;
+        let mut instance = self.instance.lock().unwrap_or_else(|p| p.into_inner());
+        instance.call(&self.name, |sink, store| sink.call_record_event(store, &line))
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        let mut instance = self.instance.lock().unwrap_or_else(|p| p.into_inner());
+        instance.call(&self.name, |sink, store| sink.call_flush(store))
+    }
+}
+
+/// A custom rule implemented by a WASM component
+///
+/// Rules run next to the validators rather than inside them: check each
+/// exchange with both and send the combined events to the sinks.
+pub struct WasmRule {
+    name: String,
+    instance: Mutex<Instance<rule_world::Rule>>,
+}
+
+impl WasmRule {
+    /// Loads a component implementing the `rule` world
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let mut instance = Instance::load(path, |store, component, linker| {
+            rule_world::Rule::instantiate(store, component, linker)
+        })?;
+        let name = instance.call(&plugin_name(path), |rule, store| rule.call_name(store).map(Ok))?;
+        Ok(Self {
+            name,
+            instance: Mutex::new(instance),
+        })
+    }
+
+    /// The name the rule reports for itself
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Drift the rule finds in an exchange
+    ///
+    /// Fails if the rule errors or returns something that isn't a drift
+    /// event, so a broken rule doesn't silently stop reporting.
+    pub fn check(&self, exchange: &Exchange) -> Result<Vec<DriftEvent>, ValidationError> {
+        let input = to_wit_exchange(exchange);
+        let mut instance = self.instance.lock().unwrap_or_else(|p| p.into_inner());
+        let events = instance.call(&self.name, |rule, store| rule.call_check(store, &input))?;
+        events
+            .iter()
+            .map(|event| {
+                serde_json::from_str(event).map_err(|e| {
+                    ValidationError::PluginError(format!("{} returned an invalid event: {}", self.name, e))
+                })
+            })
+            .collect()
+    }
+}
+
+fn to_wit_exchange(exchange: &Exchange) -> rule_world::Exchange {
+    let headers = |headers: &std::collections::HashMap<String, String>| {
+        let mut headers: Vec<_> = headers.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+        headers.sort();
+        headers
+    };
+    let request = &exchange.request;
+    let response = &exchange.response;
+    rule_world::Exchange {
+        request: rule_world::Request {
+            method: request.method.as_str().to_string(),
+            path: request.path.clone(),
+            query: request.query.clone(),
+            headers: headers(&request.headers),
+            body: request.body.clone(),
+        },
+        response: rule_world::Response {
+            status: response.status,
+            headers: headers(&response.headers),
+            body: response.body.clone(),
+        },
+    }
+}
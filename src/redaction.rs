@@ -0,0 +1,272 @@
+//! Masking personal data in drift before it leaves the validator
+//!
+//! Drift events quote what they found: offending values, concrete paths,
+//! messages naming the value. A [`Redaction`] names the fields and headers
+//! that may hold personal data, and the validator masks them on every event
+//! it emits, so no sink, report or store ever sees the original values.
+
+use crate::drift_event::DriftEvent;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// Stands in for a masked value
+pub const REDACTED: &str = "<redacted>";
+
+/// Which values are masked in drift events
+///
+/// ```yaml
+/// redaction:
+///   fields: ["*.email", "*.ssn", "/customer/address"]
+///   headers: [Authorization, X-Api-Key]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Redaction {
+    /// Body fields, parameters and path parameters whose values are masked
+    pub fields: Vec<FieldPattern>,
+    /// Headers whose values are masked, matched case-insensitively
+    pub headers: Vec<String>,
+}
+
+impl Redaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Masks values at fields matching `pattern`, e.g. `*.email`
+    pub fn field(mut self, pattern: FieldPattern) -> Self {
+        self.fields.push(pattern);
+        self
+    }
+
+    /// Masks the values of header `name`
+    pub fn header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.headers.is_empty()
+    }
+
+    /// Whether the value at `path`, or anything enclosing it, is masked
+    pub fn covers(&self, path: &[&str]) -> bool {
+        (1..=path.len()).any(|len| self.fields.iter().any(|pattern| pattern.matches(&path[..len])))
+    }
+
+    /// Whether header `name` is masked
+    pub fn covers_header(&self, name: &str) -> bool {
+        self.headers.iter().any(|header| header.eq_ignore_ascii_case(name))
+    }
+
+    /// `value`, found at `path`, with masked fields replaced by [`REDACTED`]
+    ///
+    /// Returns the masked scalars too, so text quoting them can be scrubbed.
+    pub fn redact_value(&self, path: &[&str], value: &Value) -> (Value, Vec<String>) {
+        let mut masked = Vec::new();
+        let mut path: Vec<String> = path.iter().map(|segment| segment.to_string()).collect();
+        let value = self.redact_at(&mut path, value, &mut masked);
+        (value, masked)
+    }
+
+    fn redact_at(&self, path: &mut Vec<String>, value: &Value, masked: &mut Vec<String>) -> Value {
+        let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+        if !segments.is_empty() && self.fields.iter().any(|pattern| pattern.matches(&segments)) {
+            collect_scalars(value, masked);
+            return Value::String(REDACTED.to_string());
+        }
+        match value {
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        path.push(index.to_string());
+                        let item = self.redact_at(path, item, masked);
+                        path.pop();
+                        item
+                    })
+                    .collect(),
+            ),
+            Value::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(key, member)| {
+                        path.push(key.clone());
+                        let member = self.redact_at(path, member, masked);
+                        path.pop();
+                        (key.clone(), member)
+                    })
+                    .collect(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    /// `path` with the segments bound to masked path parameters of `template` replaced
+    ///
+    /// Segments are matched from the end, so a base path the template
+    /// doesn't include is left as is.
+    pub fn redact_path(&self, path: &str, template: &str) -> String {
+        let template: Vec<&str> = template.split('/').collect();
+        let mut segments: Vec<&str> = path.split('/').collect();
+        let Some(offset) = segments.len().checked_sub(template.len()) else {
+            return path.to_string();
+        };
+        for (segment, template_segment) in segments[offset..].iter_mut().zip(&template) {
+            let masked = template_segment
+                .strip_prefix('{')
+                .and_then(|name| name.strip_suffix('}'))
+                .is_some_and(|name| self.covers(&[name]));
+            if masked {
+                *segment = REDACTED;
+            }
+        }
+        segments.join("/")
+    }
+
+    /// Masks what `event` quotes of covered fields and headers
+    ///
+    /// Drift at a covered location loses its observed value and gets a
+    /// message that doesn't quote it; drift enclosing covered fields has them
+    /// masked in the observed value and scrubbed from the message.
+    pub fn redact_event(&self, event: &mut DriftEvent) {
+        if self.is_empty() {
+            return;
+        }
+        if let (Some(path), Some(template)) = (&event.path, &event.path_template) {
+            event.path = Some(self.redact_path(path, template));
+        }
+
+        let location = event.location.clone();
+        let (covered, base): (bool, Vec<&str>) = match location.split_once('/') {
+            Some(("header", name)) => (self.covers_header(name), Vec::new()),
+            _ if location == "body" => (false, Vec::new()),
+            Some(("body", path)) => {
+                let base: Vec<&str> = path.split('/').collect();
+                (self.covers(&base), base)
+            }
+            // Parameters are located by name alone
+            _ => (self.covers_header(&location) || self.covers(&[&location]), Vec::new()),
+        };
+        if covered {
+            if event.observed.is_some() {
+                event.observed = Some(Value::String(REDACTED.to_string()));
+            }
+            event.message = format!("{} at {} (value redacted)", event.drift_type.as_str(), event.location);
+            return;
+        }
+        if let Some(observed) = &event.observed {
+            let (observed, masked) = self.redact_value(&base, observed);
+            event.observed = Some(observed);
+            for value in masked {
+                event.message = event.message.replace(&value, REDACTED);
+            }
+        }
+    }
+}
+
+/// Scalars worth scrubbing from text quoting them; tiny numbers and
+/// booleans would match all over a message
+fn collect_scalars(value: &Value, masked: &mut Vec<String>) {
+    match value {
+        Value::String(s) if !s.is_empty() => masked.push(s.clone()),
+        Value::Number(n) if n.to_string().len() >= 4 => masked.push(n.to_string()),
+        Value::Array(items) => items.iter().for_each(|item| collect_scalars(item, masked)),
+        Value::Object(members) => members.values().for_each(|member| collect_scalars(member, masked)),
+        _ => {}
+    }
+}
+
+/// Fields a redaction applies to
+///
+/// Written as dot- or slash-separated segments where `*` matches any one
+/// segment (an array index included) and `**` any number of them. Patterns
+/// match at any depth, so `email`, `*.email` and `**.email` all cover every
+/// `email` field; a pattern starting with `/` is a JSON Pointer from the
+/// body's root, e.g. `/customer/ssn` or `/items/*/card`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPattern {
+    pattern: String,
+    anchored: bool,
+    segments: Vec<String>,
+}
+
+impl FieldPattern {
+    /// Whether the field at `path`, from the body's root, matches
+    pub fn matches(&self, path: &[&str]) -> bool {
+        let segments: Vec<&str> = self.segments.iter().map(String::as_str).collect();
+        if self.anchored {
+            matches_segments(&segments, path)
+        } else {
+            (0..path.len()).any(|start| matches_segments(&segments, &path[start..]))
+        }
+    }
+}
+
+/// Whether `path` matches `pattern`, where `*` matches any one segment and `**` any number
+pub(crate) fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| matches_segments(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path)) => (*first == "*" || first == segment) && matches_segments(rest, path),
+            None => false,
+        },
+    }
+}
+
+impl fmt::Display for FieldPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.pattern)
+    }
+}
+
+impl FromStr for FieldPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s.trim();
+        let (anchored, body) = match pattern.strip_prefix('/') {
+            Some(pointer) => (true, pointer),
+            None => (false, pattern),
+        };
+        let mut segments: Vec<String> = if anchored {
+            body.split('/').map(|segment| segment.replace("~1", "/").replace("~0", "~")).collect()
+        } else {
+            body.split(['.', '/']).map(str::to_string).collect()
+        };
+        if !anchored {
+            // Unanchored patterns already match at any depth
+            while segments.len() > 1 && matches!(segments[0].as_str(), "*" | "**") {
+                segments.remove(0);
+            }
+        }
+        if segments.iter().any(String::is_empty) {
+            return Err(format!(
+                "invalid field pattern '{}' (expected e.g. *.email or /customer/ssn)",
+                s
+            ));
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            anchored,
+            segments,
+        })
+    }
+}
+
+impl Serialize for FieldPattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
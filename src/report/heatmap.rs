@@ -0,0 +1,78 @@
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::report::operation_label;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+/// Bucket width used when none is given
+pub const DEFAULT_BUCKET_WIDTH: Duration = Duration::from_secs(3600);
+
+/// Drift counts per operation, drift type and time bucket, laid out for heatmap rendering
+///
+/// Operations and drift types are listed once and cells refer to them by
+/// index; only non-empty cells are stored, so a large, mostly healthy API
+/// surface stays small on the wire.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriftHeatmap {
+    /// Width of each time bucket
+    pub bucket_ms: u64,
+    /// Start of the first bucket, in milliseconds since the Unix epoch
+    pub start_ms: u64,
+    /// Number of buckets, including empty ones between the first and last
+    pub buckets: u64,
+    /// Operation labels (e.g. `GET /users/{userId}`), sorted
+    pub operations: Vec<String>,
+    /// Drift type names, sorted
+    pub drift_types: Vec<String>,
+    /// Non-empty cells as `[operation index, drift type index, bucket index, count]`
+    pub cells: Vec<[u64; 4]>,
+}
+
+impl DriftHeatmap {
+    /// Buckets events by the time they were observed
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a DriftEvent>, bucket_width: Duration) -> Self {
+        let bucket_ms = (bucket_width.as_millis() as u64).max(1);
+        let mut counts: BTreeMap<(String, &'static str, u64), u64> = BTreeMap::new();
+        for event in events {
+            let bucket = event.timestamp_ms - event.timestamp_ms % bucket_ms;
+            *counts
+                .entry((operation_label(event), event.drift_type.as_str(), bucket))
+                .or_default() += 1;
+        }
+
+        let operations: BTreeSet<&str> = counts.keys().map(|(operation, _, _)| operation.as_str()).collect();
+        let drift_types: BTreeSet<&str> = counts.keys().map(|(_, drift_type, _)| *drift_type).collect();
+        let operation_index: BTreeMap<&str, u64> = operations.iter().zip(0..).map(|(name, i)| (*name, i)).collect();
+        let drift_type_index: BTreeMap<&str, u64> = drift_types.iter().zip(0..).map(|(name, i)| (*name, i)).collect();
+        let start_ms = counts.keys().map(|(_, _, bucket)| *bucket).min().unwrap_or(0);
+        let end_ms = counts.keys().map(|(_, _, bucket)| *bucket).max().unwrap_or(0);
+
+        let cells = counts
+            .iter()
+            .map(|((operation, drift_type, bucket), count)| {
+                [
+                    operation_index[operation.as_str()],
+                    drift_type_index[drift_type],
+                    (bucket - start_ms) / bucket_ms,
+                    *count,
+                ]
+            })
+            .collect();
+
+        Self {
+            bucket_ms,
+            start_ms,
+            buckets: if counts.is_empty() { 0 } else { (end_ms - start_ms) / bucket_ms + 1 },
+            operations: operations.into_iter().map(str::to_string).collect(),
+            drift_types: drift_types.into_iter().map(str::to_string).collect(),
+            cells,
+        }
+    }
+
+    /// Serializes the heatmap as compact JSON
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string(self)
+            .map_err(|e| ValidationError::ReportError(format!("Failed to serialize heatmap: {}", e)))
+    }
+}
@@ -0,0 +1,166 @@
+use crate::report::junit::escape_xml;
+use crate::report::{described_event, redact_sample, representative_sample, value_free_message, DriftReport};
+use crate::spec::{SpecLocation, SpecLocator};
+use std::fmt::Write as _;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2rem;color:#222}\
+table{border-collapse:collapse;margin:1rem 0}\
+td,th{border:1px solid #ccc;padding:.3rem .6rem;text-align:left}\
+.breaking{color:#b00020}.warning{color:#a15c00}.info{color:#555}\
+.up{color:#b00020}.down{color:#1b7f3a}pre{background:#f6f6f6;padding:.6rem}";
+
+/// Renders the report as a self-contained HTML page
+///
+/// Findings are described without the observed values their messages
+/// quote, and sample values are redacted, so the page can be shared.
+pub fn render(report: &DriftReport) -> String {
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>API Spec Drift Report</title><style>{}</style></head><body>\n",
+        STYLE
+    );
+    out.push_str("<h1>API Spec Drift Report</h1>\n");
+    if let Some(spec_path) = &report.spec_path {
+        let _ = writeln!(out, "<p>Spec: <code>{}</code></p>", escape_xml(spec_path));
+    }
+
+    out.push_str("<h2>Summary</h2>\n");
+    let _ = writeln!(out, "<p>Total drift events: <strong>{}</strong></p>", report.summary.total);
+    if report.summary.suppressed > 0 {
+        let _ = writeln!(out, "<p>Suppressed by ignore rules: {}</p>", report.summary.suppressed);
+    }
+    out.push_str("<ul>\n");
+    for (severity, count) in &report.summary.by_severity {
+        let _ = writeln!(out, "<li class=\"{}\">{}: {}</li>", severity, severity, count);
+    }
+    out.push_str("</ul>\n");
+
+    let trend = report.trend();
+    out.push_str("<table><tr><th>Drift type</th><th>Count</th>");
+    if trend.is_some() {
+        out.push_str("<th>&Delta; vs previous</th>");
+    }
+    out.push_str("</tr>\n");
+    let mut drift_types: Vec<&String> = report.summary.by_drift_type.keys().collect();
+    if let Some(deltas) = &trend {
+        drift_types.extend(deltas.keys().filter(|k| !report.summary.by_drift_type.contains_key(*k)));
+    }
+    for drift_type in drift_types {
+        let count = report.summary.by_drift_type.get(drift_type).copied().unwrap_or(0);
+        let _ = write!(out, "<tr><td>{}</td><td>{}</td>", escape_xml(drift_type), count);
+        if let Some(deltas) = &trend {
+            let delta = deltas.get(drift_type).copied().unwrap_or(0);
+            let class = if delta > 0 { "up" } else if delta < 0 { "down" } else { "" };
+            let _ = write!(out, "<td class=\"{}\">{:+}</td>", class, delta);
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+
+    if !report.operations.is_empty() {
+        out.push_str("<h2>Coverage</h2>\n<table><tr><th>Operation</th><th>Exchanges</th><th>With drift</th><th>Clean</th></tr>\n");
+        for (operation, stats) in &report.operations {
+            let clean = stats.exchanges - stats.exchanges_with_drift;
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>",
+                escape_xml(operation),
+                stats.exchanges,
+                stats.exchanges_with_drift,
+                clean as f64 * 100.0 / stats.exchanges.max(1) as f64
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    if let Some(coverage) = &report.coverage {
+        let _ = writeln!(
+            out,
+            "<h2>Spec coverage</h2>\n<p>{} of {} operations received traffic ({:.1}%); {} requests matched no operation.</p>",
+            coverage.operations_observed,
+            coverage.operations_total,
+            coverage.ratio() * 100.0,
+            coverage.unmatched_requests
+        );
+        out.push_str("<table><tr><th>Operation</th><th>Exchanges</th><th>Unobserved responses</th><th>Undocumented statuses</th></tr>\n");
+        for op in &coverage.operations {
+            let undocumented: Vec<String> = op.undocumented_statuses.iter().map(u16::to_string).collect();
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{} {}</code></td><td>{}</td><td>{}</td><td class=\"{}\">{}</td></tr>",
+                op.method.as_str(),
+                escape_xml(&op.path_template),
+                op.exchanges,
+                escape_xml(&op.unobserved_statuses.join(", ")),
+                if undocumented.is_empty() { "" } else { "warning" },
+                undocumented.join(", ")
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Drift by endpoint</h2>\n");
+    for (operation, events) in report.events_by_operation() {
+        let _ = writeln!(out, "<h3><code>{}</code></h3>", escape_xml(&operation));
+        if let Some(described) = described_event(&events) {
+            let mut details = Vec::new();
+            if let Some(summary) = &described.operation_summary {
+                details.push(escape_xml(summary));
+            }
+            if let Some(operation_id) = &described.operation_id {
+                details.push(format!("operationId <code>{}</code>", escape_xml(operation_id)));
+            }
+            if !described.tags.is_empty() {
+                details.push(format!("tags: {}", escape_xml(&described.tags.join(", "))));
+            }
+            let _ = writeln!(out, "<p>{}</p>", details.join(" · "));
+        }
+        let locator = report.spec_locator.as_deref();
+        out.push_str("<table><tr><th>Drift type</th><th>Severity</th><th>Location</th><th>Message</th>");
+        if locator.is_some() {
+            out.push_str("<th>Spec</th>");
+        }
+        out.push_str("</tr>\n");
+        for event in &events {
+            let _ = write!(
+                out,
+                "<tr><td>{}</td><td class=\"{}\">{}</td><td><code>{}</code></td><td>{}</td>",
+                event.drift_type.as_str(),
+                event.severity.as_str(),
+                event.severity.as_str(),
+                escape_xml(&event.location),
+                escape_xml(&value_free_message(event))
+            );
+            if let Some(locator) = locator {
+                out.push_str(&spec_cell(locator, locator.locate(event)));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+
+        if let Some(sample) = representative_sample(&events) {
+            let redacted = serde_json::to_string_pretty(&redact_sample(sample)).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "<details><summary>Sample offending value (redacted)</summary><pre>{}</pre></details>",
+                escape_xml(&redacted)
+            );
+        }
+    }
+
+    out.push_str("</body></html>\n");
+    out
+}
+
+/// `openapi.yaml:42`, linked to the line when the locator has a link base
+fn spec_cell(locator: &SpecLocator, location: Option<SpecLocation>) -> String {
+    let Some(location) = location else {
+        return "<td></td>".to_string();
+    };
+    let label = format!("{}:{}", escape_xml(locator.path()), location.line);
+    match locator.link(&location) {
+        Some(link) => format!("<td><a href=\"{}\"><code>{}</code></a></td>", escape_xml(&link), label),
+        None => format!("<td><code>{}</code></td>", label),
+    }
+}
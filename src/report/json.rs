@@ -0,0 +1,29 @@
+use crate::error::ValidationError;
+use crate::report::DriftReport;
+use serde::Serialize;
+
+/// Version of the JSON report format, written as `schema_version`
+///
+/// Raised on the same terms as [`crate::drift_event::EVENT_SCHEMA_VERSION`].
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// JSON Schema of JSON reports at [`REPORT_SCHEMA_VERSION`]; its events
+/// refer to [`crate::drift_event::DRIFT_EVENT_SCHEMA`] by `$id`
+pub const DRIFT_REPORT_SCHEMA: &str = include_str!("../../schemas/drift-report.v1.json");
+
+#[derive(Serialize)]
+struct VersionedReport<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    report: &'a DriftReport,
+}
+
+/// Renders the report as pretty-printed JSON
+pub fn render(report: &DriftReport) -> Result<String, ValidationError> {
+    let report = VersionedReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        report,
+    };
+    serde_json::to_string_pretty(&report)
+        .map_err(|e| ValidationError::ReportError(e.to_string()))
+}
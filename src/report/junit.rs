@@ -0,0 +1,113 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::Severity;
+use crate::report::{described_event, DriftReport};
+use std::fmt::Write as _;
+
+/// Renders the report as JUnit XML
+///
+/// Each validated operation becomes a test case, so CI tools show passes
+/// as well as failures per operation. Operations are those the report
+/// recorded exchanges or coverage for, plus any with drift events. A case
+/// fails with every finding of its operation listed; info-level findings are
+/// listed in `system-out` without failing the case.
+pub fn render(report: &DriftReport) -> String {
+    let mut by_operation = report.events_by_operation();
+    let recorded = report.operations.iter().filter(|(_, stats)| stats.exchanges > 0).map(|(label, _)| label.clone());
+    let covered = report
+        .coverage
+        .iter()
+        .flat_map(|coverage| &coverage.operations)
+        .filter(|operation| operation.exchanges > 0)
+        .map(|operation| format!("{} {}", operation.method.as_str(), operation.path_template));
+    for label in recorded.chain(covered) {
+        by_operation.entry(label).or_default();
+    }
+    let failures = by_operation
+        .values()
+        .filter(|events| events.iter().any(|e| e.severity > Severity::Info))
+        .count();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"api-spec-drift\" tests=\"{}\" failures=\"{}\">",
+        by_operation.len(),
+        failures
+    );
+    let _ = writeln!(
+        out,
+        "  <testsuite name=\"api-spec-drift\" tests=\"{}\" failures=\"{}\">",
+        by_operation.len(),
+        failures
+    );
+
+    for (operation, events) in &by_operation {
+        let _ = writeln!(out, "    <testcase classname=\"drift\" name=\"{}\">", escape_xml(operation));
+        // Passing operations have no event to describe them, only what their stats kept
+        let description = match described_event(events) {
+            Some(described) => Some((&described.operation_id, &described.tags, &described.operation_summary)),
+            None => report
+                .operations
+                .get(operation)
+                .filter(|stats| stats.operation_id.is_some() || !stats.tags.is_empty() || stats.summary.is_some())
+                .map(|stats| (&stats.operation_id, &stats.tags, &stats.summary)),
+        };
+        if let Some((operation_id, tags, summary)) = description {
+            out.push_str("      <properties>\n");
+            let properties = [
+                ("operationId", operation_id.clone()),
+                ("tags", (!tags.is_empty()).then(|| tags.join(","))),
+                ("summary", summary.clone()),
+            ];
+            for (name, value) in properties {
+                if let Some(value) = value {
+                    let _ = writeln!(out, "        <property name=\"{}\" value=\"{}\"/>", name, escape_xml(&value));
+                }
+            }
+            out.push_str("      </properties>\n");
+        }
+
+        let (failing, informational): (Vec<&DriftEvent>, Vec<&DriftEvent>) =
+            events.iter().partition(|e| e.severity > Severity::Info);
+        if !failing.is_empty() {
+            let _ = writeln!(
+                out,
+                "      <failure message=\"{} drift finding(s)\" type=\"{}\">",
+                failing.len(),
+                failing[0].drift_type.as_str()
+            );
+            for event in &failing {
+                let _ = writeln!(out, "{}", escape_xml(&event.to_string()));
+            }
+            out.push_str("      </failure>\n");
+        }
+        if !informational.is_empty() {
+            out.push_str("      <system-out>\n");
+            for event in &informational {
+                let _ = writeln!(out, "{}", escape_xml(&event.to_string()));
+            }
+            out.push_str("      </system-out>\n");
+        }
+        out.push_str("    </testcase>\n");
+    }
+
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+/// Escapes text for use in XML content and attribute values
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if (c as u32) < 0x20 && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
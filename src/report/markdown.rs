@@ -0,0 +1,172 @@
+use crate::report::{described_event, redact_sample, representative_sample, value_free_message, DriftReport};
+use std::fmt::Write as _;
+
+/// Highest drift rates listed; the JSON report has them all
+const MAX_RATE_ROWS: usize = 20;
+
+/// Renders the report as GitHub-flavored Markdown
+///
+/// Findings are described without the observed values their messages
+/// quote, and sample values are redacted, so the page can be shared.
+pub fn render(report: &DriftReport) -> String {
+    let mut out = String::from("# API Spec Drift Report\n\n");
+    if let Some(spec_path) = &report.spec_path {
+        let _ = writeln!(out, "Spec: {}\n", code_span(spec_path));
+    }
+
+    out.push_str("## Summary\n\n");
+    let _ = writeln!(out, "Total drift events: **{}**\n", report.summary.total);
+    if report.summary.suppressed > 0 {
+        let _ = writeln!(out, "Suppressed by ignore rules: {}\n", report.summary.suppressed);
+    }
+    for (severity, count) in &report.summary.by_severity {
+        let _ = writeln!(out, "- {}: {}", severity, count);
+    }
+    out.push('\n');
+
+    let trend = report.trend();
+    match &trend {
+        Some(_) => out.push_str("| Drift type | Count | Δ vs previous |\n|---|---:|---:|\n"),
+        None => out.push_str("| Drift type | Count |\n|---|---:|\n"),
+    }
+    for (drift_type, count) in &report.summary.by_drift_type {
+        match &trend {
+            Some(deltas) => {
+                let delta = deltas.get(drift_type).copied().unwrap_or(0);
+                let _ = writeln!(out, "| {} | {} | {:+} |", escape_cell(drift_type), count, delta);
+            }
+            None => {
+                let _ = writeln!(out, "| {} | {} |", escape_cell(drift_type), count);
+            }
+        }
+    }
+    if let Some(deltas) = &trend {
+        for (drift_type, delta) in deltas {
+            if !report.summary.by_drift_type.contains_key(drift_type) {
+                let _ = writeln!(out, "| {} | 0 | {:+} |", escape_cell(drift_type), delta);
+            }
+        }
+    }
+    out.push('\n');
+
+    if !report.operations.is_empty() {
+        out.push_str("## Coverage\n\n| Operation | Exchanges | With drift | Clean |\n|---|---:|---:|---:|\n");
+        for (operation, stats) in &report.operations {
+            let clean = stats.exchanges - stats.exchanges_with_drift;
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} | {:.1}% |",
+                operation,
+                stats.exchanges,
+                stats.exchanges_with_drift,
+                clean as f64 * 100.0 / stats.exchanges.max(1) as f64
+            );
+        }
+        out.push('\n');
+    }
+
+    let rates = report.rates();
+    if !rates.is_empty() {
+        out.push_str("## Drift rates\n\n| Operation | Drift type | Location | Exchanges affected | Rate |\n|---|---|---|---:|---:|\n");
+        for rate in rates.iter().take(MAX_RATE_ROWS) {
+            let affected = if rate.validated < rate.exchanges {
+                format!("{} of {} validated ({} seen)", rate.occurrences, rate.validated, rate.exchanges)
+            } else {
+                format!("{} of {}", rate.occurrences, rate.exchanges)
+            };
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | `{}` | {} | {:.1}% |",
+                rate.operation,
+                rate.drift_type.as_str(),
+                escape_cell(&rate.location),
+                affected,
+                rate.rate * 100.0
+            );
+        }
+        if rates.len() > MAX_RATE_ROWS {
+            let _ = writeln!(out, "\n{} lower rates omitted.", rates.len() - MAX_RATE_ROWS);
+        }
+        out.push('\n');
+    }
+
+    if let Some(coverage) = &report.coverage {
+        let _ = writeln!(
+            out,
+            "## Spec coverage\n\n{} of {} operations received traffic ({:.1}%); {} requests matched no operation.\n",
+            coverage.operations_observed,
+            coverage.operations_total,
+            coverage.ratio() * 100.0,
+            coverage.unmatched_requests
+        );
+        let never_hit: Vec<_> = coverage.never_hit().collect();
+        if !never_hit.is_empty() {
+            out.push_str("Never hit:\n\n");
+            for op in never_hit {
+                let _ = writeln!(out, "- `{} {}`", op.method.as_str(), op.path_template);
+            }
+            out.push('\n');
+        }
+        for op in coverage.operations.iter().filter(|op| !op.undocumented_statuses.is_empty()) {
+            let statuses: Vec<String> = op.undocumented_statuses.iter().map(u16::to_string).collect();
+            let _ = writeln!(
+                out,
+                "- `{} {}` returned undocumented status {}",
+                op.method.as_str(),
+                op.path_template,
+                statuses.join(", ")
+            );
+        }
+        out.push('\n');
+    }
+
+    out.push_str("## Drift by endpoint\n");
+    for (operation, events) in report.events_by_operation() {
+        let _ = writeln!(out, "\n### `{}`\n", operation);
+        if let Some(described) = described_event(&events) {
+            if let Some(summary) = &described.operation_summary {
+                let _ = writeln!(out, "{}\n", escape_cell(summary));
+            }
+            let mut details = Vec::new();
+            if let Some(operation_id) = &described.operation_id {
+                details.push(format!("operationId: `{}`", operation_id));
+            }
+            if !described.tags.is_empty() {
+                details.push(format!("tags: {}", described.tags.join(", ")));
+            }
+            if !details.is_empty() {
+                let _ = writeln!(out, "{}\n", details.join(" · "));
+            }
+        }
+        out.push_str("| Drift type | Severity | Location | Message |\n|---|---|---|---|\n");
+        for event in &events {
+            let _ = writeln!(
+                out,
+                "| {} | {} | `{}` | {} |",
+                event.drift_type.as_str(),
+                event.severity.as_str(),
+                escape_cell(&event.location),
+                escape_cell(&value_free_message(event))
+            );
+        }
+
+        if let Some(sample) = representative_sample(&events) {
+            let redacted = serde_json::to_string_pretty(&redact_sample(sample)).unwrap_or_default();
+            let _ = writeln!(out, "\nSample offending value (redacted):\n\n```json\n{}\n```", redacted);
+        }
+    }
+    out
+}
+
+/// Keeps text inside its table cell and out of the HTML Markdown passes through
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('<', "&lt;").replace('>', "&gt;").replace('\n', " ")
+}
+
+/// Inline code showing `text` literally, fenced by more backticks than it contains in a row
+fn code_span(text: &str) -> String {
+    let text = text.replace('\n', " ");
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    format!("{} {} {}", fence, text, fence)
+}
@@ -0,0 +1,439 @@
+pub mod heatmap;
+pub mod html;
+pub mod json;
+pub mod junit;
+pub mod markdown;
+pub mod patch;
+pub mod sarif;
+
+use crate::aggregation::{DriftRate, DriftWindows};
+use crate::api_validator::HttpMethod;
+use crate::coverage::CoverageReport;
+use crate::drift_event::{now_ms, DriftEvent};
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::reproducer::Reproducer;
+use crate::sampling::Stratum;
+use crate::spec::{SpecLocation, SpecLocator};
+use heatmap::DriftHeatmap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Machine-readable output formats for a drift report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    JUnit,
+    Sarif,
+    Html,
+    Markdown,
+}
+
+impl FromStr for ReportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "junit" | "xml" => Ok(Self::JUnit),
+            "sarif" => Ok(Self::Sarif),
+            "html" => Ok(Self::Html),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Who a report is written for, deciding how much of the observed traffic it reveals
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportProfile {
+    /// Everything, for the team owning the API
+    #[default]
+    Full,
+    /// No payload excerpts, concrete paths or caller context, for org-wide dashboards
+    ///
+    /// Offending values keep their shape with scalars masked, and messages
+    /// (which quote offending values) become a description of the drift type.
+    Redacted,
+}
+
+impl ReportProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Redacted => "redacted",
+        }
+    }
+
+    /// Strips what the profile doesn't show from an event
+    pub fn apply(&self, event: &mut DriftEvent) {
+        if *self == Self::Full {
+            return;
+        }
+        event.observed = event.observed.as_ref().map(redact_sample);
+        event.message = value_free_message(event);
+        event.path = None;
+        event.context.clear();
+    }
+}
+
+impl FromStr for ReportProfile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "redacted" => Ok(Self::Redacted),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What an event found and where, without the observed values its message quotes
+pub(crate) fn value_free_message(event: &DriftEvent) -> String {
+    format!("{} at {}", describe_drift_type(event.drift_type), event.location)
+}
+
+/// Sentence-case description of a drift type, e.g. `Response body type mismatch`
+fn describe_drift_type(drift_type: DriftType) -> String {
+    let words = drift_type.as_str().to_lowercase().replace('_', " ");
+    let mut chars = words.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Counts of drift events by drift type, severity and response origin
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportSummary {
+    pub total: usize,
+    pub by_drift_type: BTreeMap<String, usize>,
+    pub by_severity: BTreeMap<String, usize>,
+    /// Only events whose response origin is known are counted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub by_origin: BTreeMap<String, usize>,
+    /// Events dropped by suppression rules, not counted in `total`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub suppressed: u64,
+}
+
+impl ReportSummary {
+    /// Reads the summary of a previously written JSON report
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        #[derive(Deserialize)]
+        struct PreviousReport {
+            summary: ReportSummary,
+        }
+
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ValidationError::ReportError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        serde_json::from_str::<PreviousReport>(&contents)
+            .map(|previous| previous.summary)
+            .map_err(|e| {
+                ValidationError::ReportError(format!("Failed to parse {}: {}", path.display(), e))
+            })
+    }
+}
+
+/// Traffic seen for one operation during a run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationStats {
+    /// Exchanges validated
+    pub exchanges: u64,
+    pub exchanges_with_drift: u64,
+    /// Exchanges sampling skipped, on top of those validated
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub sampled_out: u64,
+    /// The spec's `operationId`, `tags` and `summary`, taken from the operation's drift events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+impl OperationStats {
+    /// Takes the operation's spec metadata from one of its events, unless already known
+    fn describe(&mut self, event: &DriftEvent) {
+        if self.operation_id.is_none() {
+            self.operation_id = event.operation_id.clone();
+        }
+        if self.tags.is_empty() {
+            self.tags = event.tags.clone();
+        }
+        if self.summary.is_none() {
+            self.summary = event.operation_summary.clone();
+        }
+    }
+}
+
+fn is_zero(count: &u64) -> bool {
+    *count == 0
+}
+
+/// Aggregated drift events for a monitoring run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DriftReport {
+    /// Spec file the traffic was validated against, used as the SARIF artifact
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spec_path: Option<String>,
+    pub summary: ReportSummary,
+    /// Validated exchanges per operation label, for coverage
+    pub operations: BTreeMap<String, OperationStats>,
+    /// Share of each operation's exchanges showing each drift, per time window
+    #[serde(rename = "rates", skip_serializing_if = "DriftWindows::is_empty")]
+    pub windows: DriftWindows,
+    pub events: Vec<DriftEvent>,
+    /// The first exchange seen for each drift fingerprint
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reproducers: Vec<Reproducer>,
+    /// Spec operations and status codes that did or didn't see traffic
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageReport>,
+    /// Summary of an earlier run, used for trend deltas in human-readable output
+    #[serde(skip)]
+    pub previous: Option<ReportSummary>,
+    /// Maps events to spec lines for SARIF regions and HTML links
+    #[serde(skip)]
+    pub spec_locator: Option<Arc<SpecLocator>>,
+}
+
+impl DriftReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a report from already collected events
+    pub fn from_events(events: impl IntoIterator<Item = DriftEvent>) -> Self {
+        let mut report = Self::new();
+        report.extend(events);
+        report
+    }
+
+    /// Records the spec file the events refer to
+    pub fn with_spec_path(mut self, spec_path: impl Into<String>) -> Self {
+        self.spec_path = Some(spec_path.into());
+        self
+    }
+
+    /// Locates events in the spec file, which becomes the report's spec path
+    pub fn with_spec_locator(mut self, locator: Arc<SpecLocator>) -> Self {
+        self.spec_path = Some(locator.path().to_string());
+        self.spec_locator = Some(locator);
+        self
+    }
+
+    /// Where in the spec file the contract an event violates is written
+    pub fn spec_location(&self, event: &DriftEvent) -> Option<SpecLocation> {
+        self.spec_locator.as_ref()?.locate(event)
+    }
+
+    /// Sets the earlier run to compare against
+    pub fn with_previous(mut self, previous: ReportSummary) -> Self {
+        self.previous = Some(previous);
+        self
+    }
+
+    /// Attaches spec coverage collected by a [`crate::coverage::CoverageTracker`]
+    pub fn with_coverage(mut self, coverage: CoverageReport) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Attaches reproducers collected by a [`crate::reproducer::ReproducerCapture`]
+    pub fn with_reproducers(mut self, reproducers: Vec<Reproducer>) -> Self {
+        self.reproducers = reproducers;
+        self
+    }
+
+    /// Buckets drift rates by `width` instead of [`crate::aggregation::DEFAULT_WINDOW`]
+    ///
+    /// Set before recording exchanges; exchanges already recorded are dropped from the rates.
+    pub fn with_rate_window(mut self, width: Duration) -> Self {
+        self.windows = DriftWindows::new(width);
+        self
+    }
+
+    /// Records the outcome of validating one exchange against an operation just now
+    pub fn record_exchange(&mut self, method: HttpMethod, path_template: &str, events: Vec<DriftEvent>) {
+        self.record_exchange_at(method, path_template, now_ms(), events);
+    }
+
+    /// Records the outcome of validating one exchange that happened at `timestamp_ms`
+    pub fn record_exchange_at(
+        &mut self,
+        method: HttpMethod,
+        path_template: &str,
+        timestamp_ms: u64,
+        events: Vec<DriftEvent>,
+    ) {
+        self.record_sampled_at(method, path_template, timestamp_ms, Stratum::Success, Some(events));
+    }
+
+    /// Records one exchange that happened at `timestamp_ms` and its drift,
+    /// or `None` when sampling skipped it, so rates account for the skipped
+    pub fn record_sampled_at(
+        &mut self,
+        method: HttpMethod,
+        path_template: &str,
+        timestamp_ms: u64,
+        stratum: Stratum,
+        events: Option<Vec<DriftEvent>>,
+    ) {
+        let operation = format!("{} {}", method.as_str(), path_template);
+        self.windows.record_sampled(&operation, timestamp_ms, stratum, events.as_deref());
+        let stats = self.operations.entry(operation).or_default();
+        let Some(events) = events else {
+            stats.sampled_out += 1;
+            return;
+        };
+        stats.exchanges += 1;
+        if !events.is_empty() {
+            stats.exchanges_with_drift += 1;
+        }
+        self.extend(events);
+    }
+
+    /// Share of each operation's exchanges showing each drift over the whole run, highest first
+    pub fn rates(&self) -> Vec<DriftRate> {
+        self.windows.overall_rates()
+    }
+
+    /// Drift counts per operation, drift type and time bucket, for heatmap rendering
+    pub fn heatmap(&self, bucket_width: Duration) -> DriftHeatmap {
+        DriftHeatmap::from_events(&self.events, bucket_width)
+    }
+
+    /// Change in count per drift type versus the previous run, including types that disappeared
+    pub fn trend(&self) -> Option<BTreeMap<String, i64>> {
+        let previous = self.previous.as_ref()?;
+        let mut deltas: BTreeMap<String, i64> = BTreeMap::new();
+        for (drift_type, count) in &self.summary.by_drift_type {
+            *deltas.entry(drift_type.clone()).or_default() += *count as i64;
+        }
+        for (drift_type, count) in &previous.by_drift_type {
+            *deltas.entry(drift_type.clone()).or_default() -= *count as i64;
+        }
+        Some(deltas)
+    }
+
+    /// Adds an event, updating the summary
+    pub fn add(&mut self, event: DriftEvent) {
+        self.summary.total += 1;
+        *self.summary.by_drift_type.entry(event.drift_type.as_str().to_string()).or_default() += 1;
+        *self.summary.by_severity.entry(event.severity.as_str().to_string()).or_default() += 1;
+        if let Some(origin) = event.response_origin {
+            *self.summary.by_origin.entry(origin.as_str().to_string()).or_default() += 1;
+        }
+        if event.operation_id.is_some() || !event.tags.is_empty() || event.operation_summary.is_some() {
+            if let Some(stats) = self.operations.get_mut(&operation_label(&event)) {
+                stats.describe(&event);
+            }
+        }
+        self.events.push(event);
+    }
+
+    pub fn extend(&mut self, events: impl IntoIterator<Item = DriftEvent>) {
+        events.into_iter().for_each(|event| self.add(event));
+    }
+
+    /// Groups events by operation label (e.g. `GET /users/{userId}`), sorted by label
+    pub fn events_by_operation(&self) -> BTreeMap<String, Vec<&DriftEvent>> {
+        let mut grouped: BTreeMap<String, Vec<&DriftEvent>> = BTreeMap::new();
+        for event in &self.events {
+            grouped.entry(operation_label(event)).or_default().push(event);
+        }
+        grouped
+    }
+
+    /// Requests still using what the spec marks deprecated, counted by
+    /// operation label and then by what they used (`operation`, a parameter
+    /// or a `body/...` field)
+    pub fn deprecated_usage(&self) -> BTreeMap<String, BTreeMap<String, usize>> {
+        let mut usage: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+        for event in self.events.iter().filter(|event| event.drift_type == DriftType::DeprecatedUsage) {
+            *usage.entry(operation_label(event)).or_default().entry(event.location.clone()).or_default() += 1;
+        }
+        usage
+    }
+
+    /// A copy of the report showing only what `profile` allows
+    pub fn for_profile(&self, profile: ReportProfile) -> DriftReport {
+        let mut report = self.clone();
+        report.events.iter_mut().for_each(|event| profile.apply(event));
+        if profile == ReportProfile::Redacted {
+            report.reproducers = report.reproducers.iter().map(Reproducer::redacted).collect();
+        }
+        report
+    }
+
+    /// Serializes the report in the given format
+    pub fn render(&self, format: ReportFormat) -> Result<String, ValidationError> {
+        match format {
+            ReportFormat::Json => json::render(self),
+            ReportFormat::JUnit => Ok(junit::render(self)),
+            ReportFormat::Sarif => sarif::render(self),
+            ReportFormat::Html => Ok(html::render(self)),
+            ReportFormat::Markdown => Ok(markdown::render(self)),
+        }
+    }
+}
+
+/// Picks the most informative offending value among events, preferring objects and arrays
+pub fn representative_sample<'a>(events: &[&'a DriftEvent]) -> Option<&'a Value> {
+    let mut samples = events.iter().filter_map(|e| e.observed.as_ref());
+    let first = samples.clone().next();
+    samples.find(|v| v.is_object() || v.is_array()).or(first)
+}
+
+/// The first of an operation's events carrying the spec's `operationId`, `tags` or `summary`
+pub fn described_event<'a>(events: &[&'a DriftEvent]) -> Option<&'a DriftEvent> {
+    events
+        .iter()
+        .copied()
+        .find(|e| e.operation_id.is_some() || !e.tags.is_empty() || e.operation_summary.is_some())
+}
+
+/// Masks every scalar in a sample with its JSON type, keeping only the shape
+///
+/// Human-readable reports get shared widely, so offending payloads are shown
+/// as structure without values.
+pub fn redact_sample(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::Bool(_) => Value::String("<boolean>".to_string()),
+        Value::Number(n) if n.is_f64() => Value::String("<number>".to_string()),
+        Value::Number(_) => Value::String("<integer>".to_string()),
+        Value::String(s) if s == "…" => value.clone(),
+        Value::String(_) => Value::String("<string>".to_string()),
+        Value::Array(items) => Value::Array(items.iter().map(redact_sample).collect()),
+        Value::Object(members) => Value::Object(
+            members
+                .iter()
+                .map(|(key, member)| (key.clone(), redact_sample(member)))
+                .collect(),
+        ),
+    }
+}
+
+/// Human-readable operation label for an event
+pub fn operation_label(event: &DriftEvent) -> String {
+    let method = event.method.map(|m| m.as_str()).unwrap_or("ANY");
+    let path = event
+        .path_template
+        .as_deref()
+        .or(event.path.as_deref())
+        .unwrap_or("unmatched");
+    format!("{} {}", method, path)
+}
@@ -0,0 +1,429 @@
+//! Spec changes that would make confirmed drift conform
+//!
+//! Drift seen in every exchange of an operation is rarely a bug in the
+//! traffic: the spec is out of date. For the drift a report confirms often
+//! enough, [`SpecPatch`] proposes the edits that would bring the spec in
+//! line, as an RFC 6902 JSON Patch or an OpenAPI Overlay, for maintainers
+//! to review and apply. Undocumented fields are added with a schema
+//! inferred from the observed values, mismatched types are replaced or
+//! made nullable, and required fields traffic never carries are made
+//! optional. Edits land where the schema is defined, following `$ref`s,
+//! so a shared component is patched once.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::report::{operation_label, DriftReport};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// How often drift has to occur before a spec change is suggested for it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatchThresholds {
+    /// Share of the operation's validated exchanges showing the drift, between 0 and 1
+    pub min_share: f64,
+    /// Exchanges showing the drift
+    pub min_exchanges: u64,
+}
+
+impl Default for PatchThresholds {
+    fn default() -> Self {
+        Self {
+            min_share: 1.0,
+            min_exchanges: 1,
+        }
+    }
+}
+
+/// Suggested edits to a spec, in the order they apply
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpecPatch {
+    pub suggestions: Vec<PatchSuggestion>,
+}
+
+/// One edit and the drift behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchSuggestion {
+    pub op: PatchOp,
+    /// JSON Pointer into the spec document
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    /// The drift the edit resolves, e.g. "`tag` undocumented in 100% of GET /pets exchanges (12 of 12)"
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchOp {
+    Add,
+    Replace,
+    Remove,
+}
+
+impl PatchOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Replace => "replace",
+            Self::Remove => "remove",
+        }
+    }
+}
+
+/// What a group of events says about one schema
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Finding {
+    /// An object schema lacks a property traffic carries
+    Field { schema: String, name: String },
+    /// A schema's `type` differs from the values traffic carries
+    Type { schema: String, observed: &'static str },
+    /// A required property traffic leaves out
+    Required { schema: String, name: String },
+}
+
+/// Events supporting a finding
+struct Support<'a> {
+    operation: String,
+    exchanges: u64,
+    /// A value seen for the finding, to infer a schema from
+    sample: Option<&'a Value>,
+}
+
+impl SpecPatch {
+    /// Suggests edits to `spec`, the document the report's traffic was validated against
+    ///
+    /// Shares are taken against the validated exchanges the report recorded
+    /// per operation, so a report built from events alone, without its
+    /// traffic, yields no suggestions.
+    pub fn suggest(report: &DriftReport, spec: &Value, thresholds: PatchThresholds) -> Self {
+        let mut findings: BTreeMap<Finding, Support> = BTreeMap::new();
+        for event in &report.events {
+            for (finding, sample) in findings_of(spec, event) {
+                let support = findings.entry(finding).or_insert_with(|| Support {
+                    operation: operation_label(event),
+                    exchanges: 0,
+                    sample: None,
+                });
+                support.exchanges += 1;
+                support.sample = support.sample.or(sample);
+            }
+        }
+
+        let nullable_as_type = spec
+            .get("openapi")
+            .and_then(Value::as_str)
+            .is_some_and(|version| version.starts_with("3.1"));
+        let mut suggestions = Vec::new();
+        let mut added_properties: BTreeMap<String, Map<String, Value>> = BTreeMap::new();
+        let mut removed_required: BTreeMap<String, Vec<(usize, String)>> = BTreeMap::new();
+        for (finding, support) in findings {
+            let Some(validated) = report.operations.get(&support.operation).map(|stats| stats.exchanges) else {
+                continue;
+            };
+            let share = (support.exchanges as f64 / validated.max(1) as f64).min(1.0);
+            if support.exchanges < thresholds.min_exchanges || share < thresholds.min_share {
+                continue;
+            }
+            let extent = format!(
+                "in {:.0}% of {} exchanges ({} of {})",
+                share * 100.0,
+                support.operation,
+                support.exchanges.min(validated),
+                validated
+            );
+            match finding {
+                Finding::Field { schema, name } => {
+                    let inferred = support.sample.map(infer_schema).unwrap_or_else(|| json!({}));
+                    match spec.pointer(&format!("{}/properties", schema)) {
+                        Some(_) => suggestions.push(PatchSuggestion {
+                            op: PatchOp::Add,
+                            path: format!("{}/properties/{}", schema, escape(&name)),
+                            value: Some(inferred),
+                            reason: format!("`{}` undocumented {}", name, extent),
+                        }),
+                        // Fields of a schema without `properties` are added as one object
+                        None => {
+                            added_properties.entry(schema).or_default().insert(name, inferred);
+                        }
+                    }
+                }
+                Finding::Type { schema, observed } => {
+                    let Some(declared) = spec.pointer(&format!("{}/type", schema)).and_then(Value::as_str) else {
+                        continue;
+                    };
+                    let reason = format!("`{}` values where the spec declares `{}` {}", observed, declared, extent);
+                    suggestions.push(match (observed, nullable_as_type) {
+                        ("null", false) => PatchSuggestion {
+                            op: PatchOp::Add,
+                            path: format!("{}/nullable", schema),
+                            value: Some(Value::Bool(true)),
+                            reason,
+                        },
+                        ("null", true) => PatchSuggestion {
+                            op: PatchOp::Replace,
+                            path: format!("{}/type", schema),
+                            value: Some(json!([declared, "null"])),
+                            reason,
+                        },
+                        _ => PatchSuggestion {
+                            op: PatchOp::Replace,
+                            path: format!("{}/type", schema),
+                            value: Some(Value::String(observed.to_string())),
+                            reason,
+                        },
+                    });
+                }
+                Finding::Required { schema, name } => {
+                    let index = spec
+                        .pointer(&format!("{}/required", schema))
+                        .and_then(Value::as_array)
+                        .and_then(|required| required.iter().position(|field| field.as_str() == Some(name.as_str())));
+                    if let Some(index) = index {
+                        let reason = format!("required `{}` missing {}", name, extent);
+                        removed_required.entry(schema).or_default().push((index, reason));
+                    }
+                }
+            }
+        }
+
+        for (schema, properties) in added_properties {
+            let names: Vec<&str> = properties.keys().map(String::as_str).collect();
+            suggestions.push(PatchSuggestion {
+                op: PatchOp::Add,
+                path: format!("{}/properties", schema),
+                reason: format!("`{}` undocumented", names.join("`, `")),
+                value: Some(Value::Object(properties)),
+            });
+        }
+        for (schema, mut removals) in removed_required {
+            // Later entries first, so earlier indices stay valid
+            removals.sort_by_key(|(index, _)| Reverse(*index));
+            suggestions.extend(removals.into_iter().map(|(index, reason)| PatchSuggestion {
+                op: PatchOp::Remove,
+                path: format!("{}/required/{}", schema, index),
+                value: None,
+                reason,
+            }));
+        }
+        Self { suggestions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.suggestions.is_empty()
+    }
+
+    /// Renders the edits as an RFC 6902 JSON Patch document
+    pub fn to_json_patch(&self) -> Result<String, ValidationError> {
+        let operations: Vec<Value> = self
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                let mut operation = json!({ "op": suggestion.op, "path": suggestion.path });
+                if let Some(value) = &suggestion.value {
+                    operation["value"] = value.clone();
+                }
+                operation
+            })
+            .collect();
+        serde_json::to_string_pretty(&operations).map_err(|e| ValidationError::ReportError(e.to_string()))
+    }
+
+    /// Renders the edits as an OpenAPI Overlay 1.0 document (in JSON, which is also YAML)
+    pub fn to_overlay(&self, title: &str) -> Result<String, ValidationError> {
+        let actions: Vec<Value> = self
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                let (parent, key) = suggestion.path.rsplit_once('/').unwrap_or(("", suggestion.path.as_str()));
+                match (&suggestion.op, &suggestion.value) {
+                    (PatchOp::Remove, _) => json!({
+                        "target": json_path(&suggestion.path, true),
+                        "description": suggestion.reason,
+                        "remove": true,
+                    }),
+                    (_, value) => json!({
+                        "target": json_path(parent, false),
+                        "description": suggestion.reason,
+                        "update": { unescape(key): value.clone().unwrap_or(Value::Null) },
+                    }),
+                }
+            })
+            .collect();
+        let overlay = json!({
+            "overlay": "1.0.0",
+            "info": { "title": title, "version": "1.0.0" },
+            "actions": actions,
+        });
+        serde_json::to_string_pretty(&overlay).map_err(|e| ValidationError::ReportError(e.to_string()))
+    }
+}
+
+/// What an event says about the spec, with a value observed for it
+fn findings_of<'a>(spec: &Value, event: &'a DriftEvent) -> Vec<(Finding, Option<&'a Value>)> {
+    use DriftType::*;
+
+    let request = match event.drift_type {
+        RequestBodyUndocumentedField | RequestBodyTypeMismatch | RequestBodyMissingRequired => true,
+        ResponseBodyUndocumentedField | ResponseBodyTypeMismatch | ResponseBodyMissingRequired => false,
+        _ => return Vec::new(),
+    };
+    let Some(schema) = body_schema(spec, event, request) else {
+        return Vec::new();
+    };
+    let observed = event.observed.as_ref();
+    match event.drift_type {
+        RequestBodyUndocumentedField | ResponseBodyUndocumentedField => {
+            let Some(Value::Object(fields)) = observed else {
+                return Vec::new();
+            };
+            let declared = spec.pointer(&format!("{}/properties", schema)).and_then(Value::as_object);
+            fields
+                .iter()
+                .filter(|(name, _)| !declared.is_some_and(|declared| declared.contains_key(*name)))
+                .map(|(name, value)| {
+                    let finding = Finding::Field {
+                        schema: schema.clone(),
+                        name: name.clone(),
+                    };
+                    (finding, Some(value))
+                })
+                .collect()
+        }
+        RequestBodyTypeMismatch | ResponseBodyTypeMismatch => match observed {
+            Some(value) => vec![(
+                Finding::Type {
+                    schema,
+                    observed: json_type(value),
+                },
+                Some(value),
+            )],
+            None => Vec::new(),
+        },
+        _ => {
+            let Some(Value::Object(fields)) = observed else {
+                return Vec::new();
+            };
+            let required = spec.pointer(&format!("{}/required", schema)).and_then(Value::as_array);
+            required
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .filter(|name| !fields.contains_key(*name))
+                .map(|name| {
+                    let finding = Finding::Required {
+                        schema: schema.clone(),
+                        name: name.to_string(),
+                    };
+                    (finding, None)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Pointer to the schema at the event's body location, with `$ref`s followed
+fn body_schema(spec: &Value, event: &DriftEvent, request: bool) -> Option<String> {
+    let operation = format!(
+        "/paths/{}/{}",
+        escape(event.path_template.as_deref()?),
+        event.method?.as_str().to_ascii_lowercase()
+    );
+    let container = if request {
+        resolve(spec, format!("{}/requestBody", operation))?
+    } else {
+        let responses = spec.pointer(&format!("{}/responses", operation))?.as_object()?;
+        let status = event.status_code?.to_string();
+        let range = format!("{}XX", &status[..1]);
+        let key = responses
+            .keys()
+            .find(|key| **key == status)
+            .or_else(|| responses.keys().find(|key| key.eq_ignore_ascii_case(&range)))
+            .or_else(|| responses.keys().find(|key| *key == "default"))?;
+        resolve(spec, format!("{}/responses/{}", operation, escape(key)))?
+    };
+    let content = spec.pointer(&format!("{}/content", container))?.as_object()?;
+    let media_type = content.keys().find(|media_type| media_type.contains("json"))?;
+    let mut schema = resolve(spec, format!("{}/content/{}/schema", container, escape(media_type)))?;
+
+    let mut segments = event.location.split('/');
+    if segments.next() != Some("body") {
+        return None;
+    }
+    for segment in segments.filter(|segment| !segment.is_empty()) {
+        let child = if segment == "*" || segment.bytes().all(|b| b.is_ascii_digit()) {
+            format!("{}/items", schema)
+        } else {
+            format!("{}/properties/{}", schema, escape(segment))
+        };
+        schema = resolve(spec, child)?;
+    }
+    Some(schema)
+}
+
+/// `pointer`, or what its chain of local `$ref`s leads to, if it exists
+fn resolve(spec: &Value, mut pointer: String) -> Option<String> {
+    // Bounded, as references may be circular
+    for _ in 0..32 {
+        let value = spec.pointer(&pointer)?;
+        match value.get("$ref").and_then(Value::as_str) {
+            Some(reference) => pointer = reference.strip_prefix('#')?.to_string(),
+            None => return Some(pointer),
+        }
+    }
+    None
+}
+
+/// A schema describing `value`
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullable": true }),
+        Value::Array(items) => match items.first() {
+            Some(item) => json!({ "type": "array", "items": infer_schema(item) }),
+            None => json!({ "type": "array" }),
+        },
+        other => json!({ "type": json_type(other) }),
+    }
+}
+
+/// JSON Schema type name of `value`
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escapes a JSON Pointer segment
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+/// JSONPath of the node at `pointer`, whose last segment is an array index when `indexed`
+fn json_path(pointer: &str, indexed: bool) -> String {
+    let segments: Vec<String> = pointer.split('/').skip(1).map(unescape).collect();
+    let mut path = "$".to_string();
+    for (position, segment) in segments.iter().enumerate() {
+        let identifier = segment.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if indexed && position + 1 == segments.len() {
+            path = format!("{}[{}]", path, segment);
+        } else if identifier {
+            path = format!("{}.{}", path, segment);
+        } else {
+            path = format!("{}['{}']", path, segment.replace('\\', "\\\\").replace('\'', "\\'"));
+        }
+    }
+    path
+}
@@ -0,0 +1,92 @@
+use crate::drift_types::Severity;
+use crate::error::ValidationError;
+use crate::report::{operation_label, DriftReport};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Renders the report as SARIF 2.1.0 for code scanning tools
+///
+/// Each drift type becomes a rule; results point at the spec file when the
+/// report knows it, at the violated line when it has a spec locator, plus a
+/// logical location naming the operation.
+pub fn render(report: &DriftReport) -> Result<String, ValidationError> {
+    let rule_ids: BTreeSet<&str> = report.events.iter().map(|e| e.drift_type.as_str()).collect();
+    let rules: Vec<Value> = rule_ids
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "name": id,
+                "shortDescription": { "text": format!("API spec drift: {}", id) }
+            })
+        })
+        .collect();
+
+    let results: Vec<Value> = report
+        .events
+        .iter()
+        .map(|event| {
+            let mut location = json!({
+                "logicalLocations": [{
+                    "fullyQualifiedName": operation_label(event),
+                    "kind": "function"
+                }]
+            });
+            if let Some(spec_path) = &report.spec_path {
+                let region = match report.spec_location(event) {
+                    Some(spec_location) => json!({
+                        "startLine": spec_location.line,
+                        "startColumn": spec_location.column
+                    }),
+                    None => json!({ "startLine": 1 }),
+                };
+                location["physicalLocation"] = json!({
+                    "artifactLocation": { "uri": spec_path },
+                    "region": region
+                });
+            }
+            let mut result = json!({
+                "ruleId": event.drift_type.as_str(),
+                "level": level(event.severity),
+                "message": { "text": format!("{} at {}: {}", operation_label(event), event.location, event.message) },
+                "locations": [location]
+            });
+            // SARIF viewers filter results by `properties.tags`
+            if !event.tags.is_empty() {
+                result["properties"]["tags"] = json!(event.tags);
+            }
+            if let Some(operation_id) = &event.operation_id {
+                result["properties"]["operationId"] = json!(operation_id);
+            }
+            result
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    });
+
+    serde_json::to_string_pretty(&sarif)
+        .map_err(|e| ValidationError::ReportError(format!("SARIF serialization failed: {}", e)))
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Breaking => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
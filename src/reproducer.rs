@@ -0,0 +1,272 @@
+use crate::api_validator::HttpMethod;
+use crate::baseline::{default_fingerprint, BaselineKey, Fingerprint};
+use crate::drift_event::{truncate_sample, DriftEvent};
+use crate::exchange::Exchange;
+use crate::redaction::{Redaction, REDACTED};
+use crate::report::redact_sample;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Nesting depth kept of a reproducer's bodies, deeper than event samples so the drift stays in view
+const BODY_MAX_DEPTH: usize = 8;
+
+/// Headers whose values are never stored
+const CREDENTIAL_HEADERS: [&str; 5] = ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-api-key"];
+
+/// Query parameter name fragments whose values are never stored
+const CREDENTIAL_PARAMETERS: [&str; 4] = ["token", "key", "secret", "password"];
+
+/// The exchange a drift fingerprint was first seen on
+///
+/// Triage wants one concrete exchange to reproduce drift with, not the
+/// thousands of occurrences the aggregate counts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reproducer {
+    /// The drift fingerprint, as used by baselines
+    #[serde(flatten)]
+    pub key: BaselineKey,
+    /// Milliseconds since the Unix epoch of the first occurrence
+    pub first_seen_ms: u64,
+    pub exchange: ReproducerExchange,
+}
+
+/// A captured exchange with credentials removed and bodies bounded in size
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReproducerExchange {
+    pub method: HttpMethod,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub request_headers: BTreeMap<String, String>,
+    /// The JSON document, or the raw text of a body that isn't JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<Value>,
+}
+
+impl ReproducerExchange {
+    pub fn capture(exchange: &Exchange) -> Self {
+        let request = &exchange.request;
+        let response = &exchange.response;
+        Self {
+            method: request.method,
+            path: request.path.clone(),
+            query: request.query.as_deref().map(redact_query),
+            request_headers: redact_headers(&request.headers),
+            request_body: request.body.as_deref().map(capture_body),
+            status: response.status,
+            response_headers: redact_headers(&response.headers),
+            response_body: response.body.as_deref().map(capture_body),
+        }
+    }
+
+    /// Masks what `redaction` covers: headers, query parameters, body fields
+    /// and the path segments of parameters of `path_template`
+    pub fn redact(&mut self, redaction: &Redaction, path_template: Option<&str>) {
+        if redaction.is_empty() {
+            return;
+        }
+        if let Some(template) = path_template {
+            self.path = redaction.redact_path(&self.path, template);
+        }
+        if let Some(query) = &self.query {
+            let query = query
+                .split('&')
+                .map(|pair| match pair.split_once('=') {
+                    Some((name, _)) if redaction.covers(&[name]) => format!("{}={}", name, REDACTED),
+                    _ => pair.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("&");
+            self.query = Some(query);
+        }
+        for headers in [&mut self.request_headers, &mut self.response_headers] {
+            for (name, value) in headers.iter_mut() {
+                if redaction.covers_header(name) {
+                    *value = REDACTED.to_string();
+                }
+            }
+        }
+        for body in [&mut self.request_body, &mut self.response_body].into_iter().flatten() {
+            *body = redaction.redact_value(&[], body).0;
+        }
+    }
+}
+
+impl Reproducer {
+    /// A copy showing only the exchange's shape: the routed template for the
+    /// path, no query, header names without values and bodies with scalars masked
+    pub fn redacted(&self) -> Self {
+        let exchange = &self.exchange;
+        let mask = |headers: &BTreeMap<String, String>| {
+            headers
+                .keys()
+                .map(|name| (name.clone(), REDACTED.to_string()))
+                .collect()
+        };
+        Self {
+            key: self.key.clone(),
+            first_seen_ms: self.first_seen_ms,
+            exchange: ReproducerExchange {
+                method: exchange.method,
+                path: self.key.path_template.clone().unwrap_or_default(),
+                query: None,
+                request_headers: mask(&exchange.request_headers),
+                request_body: exchange.request_body.as_ref().map(redact_sample),
+                status: exchange.status,
+                response_headers: mask(&exchange.response_headers),
+                response_body: exchange.response_body.as_ref().map(redact_sample),
+            },
+        }
+    }
+}
+
+/// Captures one [`Reproducer`] per drift fingerprint
+///
+/// Shared across worker threads like a sink. Whichever thread first
+/// records an exchange for a fingerprint stores it; every later occurrence,
+/// including ones validated concurrently, leaves it untouched.
+#[derive(Debug)]
+pub struct ReproducerCapture {
+    captured: Mutex<BTreeMap<BaselineKey, Reproducer>>,
+    fingerprint: Arc<dyn Fingerprint>,
+    redaction: Redaction,
+}
+
+impl Default for ReproducerCapture {
+    fn default() -> Self {
+        Self {
+            captured: Mutex::default(),
+            fingerprint: default_fingerprint(),
+            redaction: Redaction::default(),
+        }
+    }
+}
+
+impl ReproducerCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps one reproducer per `fingerprint` key instead of per [`crate::baseline::DefaultFingerprint`] key
+    pub fn with_fingerprint(mut self, fingerprint: impl Fingerprint + 'static) -> Self {
+        self.fingerprint = Arc::new(fingerprint);
+        self
+    }
+
+    /// Masks what `redaction` covers in every captured exchange, as the validator does in events
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Resumes capturing after the given reproducers, e.g. those of an earlier report
+    pub fn from_reproducers(reproducers: impl IntoIterator<Item = Reproducer>) -> Self {
+        let captured = reproducers
+            .into_iter()
+            .map(|reproducer| (reproducer.key.clone(), reproducer))
+            .collect();
+        Self {
+            captured: Mutex::new(captured),
+            ..Self::default()
+        }
+    }
+
+    /// Stores `exchange` for each of its events' fingerprints not yet seen,
+    /// returning how many were new
+    pub fn capture(&self, exchange: &Exchange, events: &[DriftEvent]) -> usize {
+        let keys: Vec<(BaselineKey, u64)> = {
+            let captured = self.lock();
+            events
+                .iter()
+                .map(|event| (self.fingerprint.fingerprint(event), event.timestamp_ms))
+                .filter(|(key, _)| !captured.contains_key(key))
+                .collect()
+        };
+        if keys.is_empty() {
+            return 0;
+        }
+
+        // Built outside the lock; a fingerprint another thread stored in the
+        // meantime keeps that thread's exchange
+        let mut sample = ReproducerExchange::capture(exchange);
+        sample.redact(&self.redaction, keys[0].0.path_template.as_deref());
+        let mut captured = self.lock();
+        let mut new = 0;
+        for (key, first_seen_ms) in keys {
+            if captured.contains_key(&key) {
+                continue;
+            }
+            captured.insert(
+                key.clone(),
+                Reproducer {
+                    key,
+                    first_seen_ms,
+                    exchange: sample.clone(),
+                },
+            );
+            new += 1;
+        }
+        new
+    }
+
+    /// Reproducers captured so far, ordered by fingerprint
+    pub fn reproducers(&self) -> Vec<Reproducer> {
+        self.lock().values().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<BaselineKey, Reproducer>> {
+        self.captured.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn redact_headers(headers: &HashMap<String, String>) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if CREDENTIAL_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                value.clone()
+            };
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_credential_parameter(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn is_credential_parameter(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    CREDENTIAL_PARAMETERS.iter().any(|fragment| name.contains(fragment))
+}
+
+fn capture_body(body: &[u8]) -> Value {
+    let document = serde_json::from_slice(body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+    truncate_sample(&document, BODY_MAX_DEPTH)
+}
@@ -0,0 +1,641 @@
+//! Mapping request paths onto the spec's path templates
+//!
+//! Specs template paths relative to their servers: with
+//! `servers: [{url: https://api.example.com/v2}]`, traffic for `/users/{id}`
+//! arrives as `/v2/users/42`. The validator strips such [`BasePath`]s before
+//! routing, and can report traffic arriving under a base path nobody
+//! declared. Paths are first cleaned up per [`PathNormalization`], so
+//! `/users//42/` routes like `/users/42`.
+//!
+//! Templates are translated into the radix router's syntax by
+//! [`PathTemplate`]; those it can't represent, such as two parameters in one
+//! segment (`/{name}.{format}`) or a parameter capturing slashes in the
+//! middle of a path (`/files/{+path}/raw`), are matched by a
+//! [`TemplatePattern`] instead.
+//!
+//! Templates that match some of the same paths [`Overlap`]: concrete
+//! segments take precedence over templated ones, as OpenAPI prescribes, and
+//! templates neither of which is more concrete are ambiguous.
+
+use crate::exchange::percent_decode_path;
+use openapiv3::{OpenAPI, Server};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How request paths are matched to the spec's path templates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutingConfig {
+    /// Strip the paths of the spec's `servers` URLs, e.g. `/v2` of `https://api.example.com/v2`
+    pub server_base_paths: bool,
+    /// Further prefixes traffic may arrive under; `{name}` matches any one segment
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub base_paths: Vec<String>,
+    /// Report traffic that only routes once leading segments no base path declares are dropped
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub report_undeclared_base_paths: bool,
+    /// How paths are cleaned up before routing
+    pub normalization: PathNormalization,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            server_base_paths: true,
+            base_paths: Vec::new(),
+            report_undeclared_base_paths: false,
+            normalization: PathNormalization::default(),
+        }
+    }
+}
+
+impl RoutingConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also strips `base_path`, e.g. `/api/{version}`, before routing
+    pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+        self.base_paths.push(base_path.into());
+        self
+    }
+
+    /// Ignores the spec's `servers`
+    pub fn without_server_base_paths(mut self) -> Self {
+        self.server_base_paths = false;
+        self
+    }
+
+    /// Reports traffic under base paths neither the spec nor the config declares
+    pub fn report_undeclared_base_paths(mut self) -> Self {
+        self.report_undeclared_base_paths = true;
+        self
+    }
+
+    pub fn with_normalization(mut self, normalization: PathNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+
+    /// The base paths stripped for `spec`: its top-level servers' (when
+    /// enabled) and the configured ones, longest first
+    pub fn base_paths_for(&self, spec: &OpenAPI) -> Vec<BasePath> {
+        let servers = spec.servers.iter().filter(|_| self.server_base_paths).map(BasePath::from_server);
+        let configured = self.base_paths.iter().map(|base_path| BasePath::parse(base_path));
+        let mut base_paths: Vec<BasePath> = servers.chain(configured).filter(|base_path| !base_path.is_root()).collect();
+        base_paths.sort_by_key(|base_path| std::cmp::Reverse(base_path.depth()));
+        base_paths.dedup();
+        base_paths
+    }
+}
+
+/// Rewrites applied to request paths before routing
+///
+/// All but case folding are on by default: none of them changes which
+/// operation a well-formed path routes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathNormalization {
+    /// Route `/users/42/` like `/users/42` when the spec doesn't declare the former
+    pub trailing_slash: bool,
+    /// Route `/users//42` like `/users/42`
+    pub collapse_slashes: bool,
+    /// Decode `%XX` escapes, except an encoded `/`, so path parameters are
+    /// validated as sent rather than as escaped
+    pub percent_decode: bool,
+    /// Match the spec's literal segments whatever their ASCII case; path
+    /// parameters keep theirs
+    pub case_insensitive: bool,
+}
+
+impl Default for PathNormalization {
+    fn default() -> Self {
+        Self {
+            trailing_slash: true,
+            collapse_slashes: true,
+            percent_decode: true,
+            case_insensitive: false,
+        }
+    }
+}
+
+impl PathNormalization {
+    /// Paths routed exactly as they arrive
+    pub fn none() -> Self {
+        Self {
+            trailing_slash: false,
+            collapse_slashes: false,
+            percent_decode: false,
+            case_insensitive: false,
+        }
+    }
+
+    /// Matches literal segments whatever their case
+    pub fn case_insensitive(mut self) -> Self {
+        self.case_insensitive = true;
+        self
+    }
+
+    /// `path` with duplicate slashes collapsed and escapes decoded, as enabled
+    ///
+    /// Trailing slashes and case are left alone: the router tries those
+    /// variants only when the path doesn't route as is.
+    pub fn normalize<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        let mut path = Cow::Borrowed(path);
+        if self.collapse_slashes && path.contains("//") {
+            let mut collapsed = String::with_capacity(path.len());
+            for c in path.chars() {
+                if !(c == '/' && collapsed.ends_with('/')) {
+                    collapsed.push(c);
+                }
+            }
+            path = Cow::Owned(collapsed);
+        }
+        if self.percent_decode && path.contains('%') {
+            path = Cow::Owned(path.split('/').map(percent_decode_path).collect::<Vec<_>>().join("/"));
+        }
+        path
+    }
+}
+
+/// `template` with its literal parts lowercased, for case-insensitive routing
+pub(crate) fn fold_template(template: &str) -> String {
+    let mut in_parameter = false;
+    template
+        .chars()
+        .map(|c| {
+            match c {
+                '{' => in_parameter = true,
+                '}' => in_parameter = false,
+                _ => {}
+            }
+            if in_parameter {
+                c
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// A spec path template, parsed for routing
+///
+/// Parameters are written `{name}`; `{+name}` (RFC 6570 reserved
+/// expansion) and `{*name}` capture the rest of the path, slashes included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    template: String,
+    segments: Vec<Vec<Piece>>,
+}
+
+/// A piece of a path template segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Parameter { name: String, greedy: bool },
+}
+
+impl PathTemplate {
+    pub fn parse(template: &str) -> Self {
+        let segments = template
+            .strip_prefix('/')
+            .unwrap_or(template)
+            .split('/')
+            .map(|segment| {
+                let mut pieces = Vec::new();
+                let mut rest = segment;
+                while let Some(start) = rest.find('{') {
+                    let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                        break;
+                    };
+                    let name = &rest[start + 1..end];
+                    let (name, greedy) = match name.strip_prefix('+').or_else(|| name.strip_prefix('*')) {
+                        Some(name) => (name, true),
+                        None => (name, false),
+                    };
+                    if name.is_empty() {
+                        push_literal(&mut pieces, &rest[..end + 1]);
+                    } else {
+                        push_literal(&mut pieces, &rest[..start]);
+                        pieces.push(Piece::Parameter {
+                            name: name.to_string(),
+                            greedy,
+                        });
+                    }
+                    rest = &rest[end + 1..];
+                }
+                push_literal(&mut pieces, rest);
+                pieces
+            })
+            .collect();
+        Self {
+            template: template.to_string(),
+            segments,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// How this template and `other` overlap, if some path matches both
+    ///
+    /// The first segment where one is more concrete than the other decides:
+    /// a literal beats a segment mixing literals and parameters (the one with
+    /// more literal text beating the other), which beats a lone parameter,
+    /// which beats one capturing slashes. Overlap past a parameter capturing
+    /// slashes is assumed, so the analysis errs towards reporting.
+    pub fn overlap(&self, other: &PathTemplate) -> Option<Overlap> {
+        let mut decided = None;
+        for position in 0..self.segments.len().max(other.segments.len()) {
+            let (ours, theirs) = (self.segments.get(position)?, other.segments.get(position)?);
+            let (our_greedy, their_greedy) = (is_greedy(ours), is_greedy(theirs));
+            if our_greedy || their_greedy {
+                // The segments after a greedy one need as many to match
+                let (greedy, rest) = if our_greedy { (self, other) } else { (other, self) };
+                if !(our_greedy && their_greedy) && greedy.segments.len() > rest.segments.len() {
+                    return None;
+                }
+                if decided.is_none() && our_greedy != their_greedy {
+                    decided = Some(if our_greedy { Overlap::ShadowedBy } else { Overlap::Shadows });
+                }
+                break;
+            }
+            if !segments_overlap(ours, theirs) {
+                return None;
+            }
+            if decided.is_none() {
+                decided = match concreteness(ours).cmp(&concreteness(theirs)) {
+                    std::cmp::Ordering::Greater => Some(Overlap::Shadows),
+                    std::cmp::Ordering::Less => Some(Overlap::ShadowedBy),
+                    std::cmp::Ordering::Equal => None,
+                };
+            }
+        }
+        Some(decided.unwrap_or(Overlap::Ambiguous))
+    }
+
+    /// Names of the template's parameters, in order
+    pub fn parameters(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().flatten().filter_map(|piece| match piece {
+            Piece::Parameter { name, .. } => Some(name.as_str()),
+            Piece::Literal(_) => None,
+        })
+    }
+
+    /// The template in the radix router's syntax, or why it has none
+    ///
+    /// The router allows one parameter per segment and captures slashes
+    /// only in a whole final segment; literal braces are escaped.
+    pub fn router_syntax(&self) -> Result<String, String> {
+        let last = self.segments.len() - 1;
+        let mut route = String::with_capacity(self.template.len());
+        for (position, pieces) in self.segments.iter().enumerate() {
+            route.push('/');
+            let parameters = pieces.iter().filter(|piece| matches!(piece, Piece::Parameter { .. })).count();
+            if parameters > 1 {
+                return Err(format!("segment '{}' has {} parameters", display_segment(pieces), parameters));
+            }
+            for piece in pieces {
+                match piece {
+                    Piece::Literal(literal) => route.push_str(&literal.replace('{', "{{").replace('}', "}}")),
+                    Piece::Parameter { name, greedy: false } => {
+                        route.push('{');
+                        route.push_str(name);
+                        route.push('}');
+                    }
+                    Piece::Parameter { name, greedy: true } if position == last && pieces.len() == 1 => {
+                        route.push_str("{*");
+                        route.push_str(name);
+                        route.push('}');
+                    }
+                    Piece::Parameter { name, greedy: true } => {
+                        return Err(format!("parameter '{}' captures slashes but isn't the whole last segment", name));
+                    }
+                }
+            }
+        }
+        Ok(route)
+    }
+
+    /// A regex matching the paths the template describes
+    ///
+    /// Fails only for templates too long to compile within the regex size limit.
+    pub fn pattern(&self) -> Result<TemplatePattern, String> {
+        let mut source = String::from("^");
+        let mut names = Vec::new();
+        for pieces in &self.segments {
+            source.push('/');
+            for piece in pieces {
+                match piece {
+                    Piece::Literal(literal) => source.push_str(&regex::escape(literal)),
+                    // Earlier parameters take as much as they can, so
+                    // `{name}.{format}` splits `a.b.csv` at the last dot
+                    Piece::Parameter { name, greedy } => {
+                        names.push(name.clone());
+                        source.push_str(if *greedy { "(.+)" } else { "([^/]+)" });
+                    }
+                }
+            }
+        }
+        source.push('$');
+        TemplatePattern::new(&self.template, &source, names)
+    }
+}
+
+/// How two path templates matching some of the same paths are told apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overlap {
+    /// This template is more concrete, so paths matching both route to it
+    Shadows,
+    /// The other template is more concrete
+    ShadowedBy,
+    /// Neither is more concrete, so paths matching both can't be routed
+    Ambiguous,
+}
+
+/// Every pair of `templates` that overlaps, as (earlier, later, how the earlier overlaps the later)
+pub fn overlapping_templates<'a>(templates: &[&'a str]) -> Vec<(&'a str, &'a str, Overlap)> {
+    let parsed: Vec<PathTemplate> = templates.iter().map(|template| PathTemplate::parse(template)).collect();
+    let mut overlaps = Vec::new();
+    for (index, earlier) in parsed.iter().enumerate() {
+        for (offset, later) in parsed[index + 1..].iter().enumerate() {
+            if let Some(overlap) = earlier.overlap(later) {
+                overlaps.push((templates[index], templates[index + 1 + offset], overlap));
+            }
+        }
+    }
+    overlaps
+}
+
+fn is_greedy(pieces: &[Piece]) -> bool {
+    pieces.iter().any(|piece| matches!(piece, Piece::Parameter { greedy: true, .. }))
+}
+
+/// Orders non-greedy segments by how concrete they are: literals first,
+/// then mixed segments by their literal text, then lone parameters
+fn concreteness(pieces: &[Piece]) -> (u8, usize) {
+    let literal_len: usize = pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Literal(literal) => literal.len(),
+            Piece::Parameter { .. } => 0,
+        })
+        .sum();
+    match pieces {
+        _ if pieces.iter().all(|piece| matches!(piece, Piece::Literal(_))) => (2, literal_len),
+        [Piece::Parameter { .. }] => (0, 0),
+        _ => (1, literal_len),
+    }
+}
+
+/// Whether some segment matches both, comparing the literal text around parameters
+fn segments_overlap(ours: &[Piece], theirs: &[Piece]) -> bool {
+    match (literal_text(ours), literal_text(theirs)) {
+        (Some(ours), Some(theirs)) => ours == theirs,
+        (Some(text), None) => matches_pieces(theirs, &text),
+        (None, Some(text)) => matches_pieces(ours, &text),
+        (None, None) => {
+            let (our_prefix, our_suffix) = affixes(ours);
+            let (their_prefix, their_suffix) = affixes(theirs);
+            (our_prefix.starts_with(their_prefix) || their_prefix.starts_with(our_prefix))
+                && (our_suffix.ends_with(their_suffix) || their_suffix.ends_with(our_suffix))
+        }
+    }
+}
+
+/// The segment's text, if it has no parameters
+fn literal_text(pieces: &[Piece]) -> Option<String> {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Literal(literal) => Some(literal.as_str()),
+            Piece::Parameter { .. } => None,
+        })
+        .collect()
+}
+
+/// The literals before the first and after the last parameter
+fn affixes(pieces: &[Piece]) -> (&str, &str) {
+    fn literal(piece: Option<&Piece>) -> &str {
+        match piece {
+            Some(Piece::Literal(literal)) => literal,
+            _ => "",
+        }
+    }
+    (literal(pieces.first()), literal(pieces.last()))
+}
+
+fn matches_pieces(pieces: &[Piece], segment: &str) -> bool {
+    match pieces.split_first() {
+        None => segment.is_empty(),
+        Some((Piece::Literal(literal), rest)) => segment
+            .strip_prefix(literal.as_str())
+            .is_some_and(|segment| matches_pieces(rest, segment)),
+        Some((Piece::Parameter { .. }, rest)) => {
+            (1..=segment.len()).any(|len| segment.is_char_boundary(len) && matches_pieces(rest, &segment[len..]))
+        }
+    }
+}
+
+fn push_literal(pieces: &mut Vec<Piece>, literal: &str) {
+    if literal.is_empty() {
+        return;
+    }
+    match pieces.last_mut() {
+        Some(Piece::Literal(previous)) => previous.push_str(literal),
+        _ => pieces.push(Piece::Literal(literal.to_string())),
+    }
+}
+
+fn display_segment(pieces: &[Piece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Literal(literal) => literal.clone(),
+            Piece::Parameter { name, .. } => format!("{{{}}}", name),
+        })
+        .collect()
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// Matches paths for a template the radix router can't represent
+#[derive(Debug, Clone)]
+pub struct TemplatePattern {
+    template: String,
+    regex: Regex,
+    /// `regex` matching literal parts whatever their ASCII case
+    folded: Regex,
+    /// Parameter names, one per capture group
+    names: Vec<String>,
+}
+
+impl TemplatePattern {
+    fn new(template: &str, source: &str, names: Vec<String>) -> Result<Self, String> {
+        // Built only from escaped literals and fixed groups, so the syntax is
+        // valid; a hostile spec can still make it too big to compile
+        let build = |case_insensitive| {
+            RegexBuilder::new(source)
+                .case_insensitive(case_insensitive)
+                .build()
+                .map_err(|e| format!("template can't be matched by regex: {}", e))
+        };
+        Ok(Self {
+            template: template.to_string(),
+            regex: build(false)?,
+            folded: build(true)?,
+            names,
+        })
+    }
+
+    /// The template's parameters bound by `path`, if it matches
+    pub fn captures(&self, path: &str, case_insensitive: bool) -> Option<HashMap<String, String>> {
+        let regex = if case_insensitive { &self.folded } else { &self.regex };
+        let captures = regex.captures(path)?;
+        Some(
+            self.names
+                .iter()
+                .zip(captures.iter().skip(1))
+                .filter_map(|(name, value)| Some((name.clone(), value?.as_str().to_string())))
+                .collect(),
+        )
+    }
+
+    /// The regex paths are matched with
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+}
+
+impl fmt::Display for TemplatePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// A path prefix traffic arrives under, possibly templated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasePath {
+    template: String,
+    segments: Vec<Vec<Part>>,
+}
+
+/// A piece of a base path segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// A server variable: one of its `enum` values, or anything non-empty without one
+    Variable(Vec<String>),
+}
+
+impl BasePath {
+    /// Parses a path prefix such as `/v2` or `/api/{version}`
+    pub fn parse(template: &str) -> Self {
+        Self::with_variables(template, |_| Vec::new())
+    }
+
+    /// The path of a server URL, its variables matching their `enum` values
+    ///
+    /// Scheme and host are dropped, so `https://{region}.example.com/v2` and
+    /// the relative `/v2` both give `/v2`.
+    pub fn from_server(server: &Server) -> Self {
+        let url = server.url.as_str();
+        let path = match url.split_once("://") {
+            Some((_, rest)) => rest.find('/').map_or("", |start| &rest[start..]),
+            None => url,
+        };
+        Self::with_variables(path, |name| {
+            server
+                .variables
+                .as_ref()
+                .and_then(|variables| variables.get(name))
+                .map(|variable| variable.enumeration.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    fn with_variables(template: &str, values: impl Fn(&str) -> Vec<String>) -> Self {
+        let segments = template
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                let mut parts = Vec::new();
+                let mut rest = segment;
+                while let Some(start) = rest.find('{') {
+                    let Some(end) = rest[start..].find('}') else {
+                        break;
+                    };
+                    if start > 0 {
+                        parts.push(Part::Literal(rest[..start].to_string()));
+                    }
+                    parts.push(Part::Variable(values(&rest[start + 1..start + end])));
+                    rest = &rest[start + end + 1..];
+                }
+                if !rest.is_empty() {
+                    parts.push(Part::Literal(rest.to_string()));
+                }
+                parts
+            })
+            .collect();
+        Self {
+            template: template.trim_end_matches('/').to_string(),
+            segments,
+        }
+    }
+
+    /// Whether this is the root, which needs no stripping
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Number of path segments the prefix spans
+    pub fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// `path` without this prefix, if it starts with it; always starts with `/`
+    pub fn strip<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let mut rest = path;
+        for parts in &self.segments {
+            let segment_path = rest.strip_prefix('/')?;
+            let end = segment_path.find('/').unwrap_or(segment_path.len());
+            if !matches_parts(parts, &segment_path[..end]) {
+                return None;
+            }
+            rest = &segment_path[end..];
+        }
+        Some(if rest.is_empty() { "/" } else { rest })
+    }
+}
+
+fn matches_parts(parts: &[Part], segment: &str) -> bool {
+    match parts.split_first() {
+        None => segment.is_empty(),
+        Some((Part::Literal(literal), rest)) => segment
+            .strip_prefix(literal.as_str())
+            .is_some_and(|segment| matches_parts(rest, segment)),
+        Some((Part::Variable(values), rest)) if values.is_empty() => {
+            (1..=segment.len()).any(|len| segment.is_char_boundary(len) && matches_parts(rest, &segment[len..]))
+        }
+        Some((Part::Variable(values), rest)) => values.iter().any(|value| {
+            segment
+                .strip_prefix(value.as_str())
+                .is_some_and(|segment| matches_parts(rest, segment))
+        }),
+    }
+}
+
+impl fmt::Display for BasePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
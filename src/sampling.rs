@@ -0,0 +1,172 @@
+//! Validating a share of traffic instead of all of it
+//!
+//! A busy service can't afford to validate every exchange. [`Sampling`]
+//! validates a percentage of them, caps how many each operation gets per
+//! second, and can keep validating every error response while successes
+//! are sampled. Drift rates stay honest: sampled-out exchanges are still
+//! counted, per [`Stratum`], and each stratum's drift is scaled by how much
+//! of it was validated.
+
+use crate::array_sampling::SplitMix64;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Which exchanges get validated
+///
+/// The default validates everything.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sampling {
+    /// Share of exchanges validated, from 0 to 1
+    #[serde(deserialize_with = "deserialize_rate")]
+    pub rate: f64,
+    /// Most exchanges validated per operation per second; unlimited when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_per_second: Option<u32>,
+    /// Validates every 4xx and 5xx response, sampling only the rest
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub always_errors: bool,
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            max_per_second: None,
+            always_errors: false,
+        }
+    }
+}
+
+impl Sampling {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validates `rate` (0 to 1) of the exchanges
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Validates at most `limit` exchanges per operation per second
+    pub fn max_per_second(mut self, limit: u32) -> Self {
+        self.max_per_second = Some(limit);
+        self
+    }
+
+    /// Validates every error response, whatever the rate and limit
+    pub fn always_errors(mut self) -> Self {
+        self.always_errors = true;
+        self
+    }
+
+    /// Whether every exchange is validated
+    pub fn is_all(&self) -> bool {
+        self.rate >= 1.0 && self.max_per_second.is_none()
+    }
+}
+
+/// Reads a sampling rate, rejecting one outside 0 to 1 rather than clamping
+/// it, as `10` is more likely meant as 10% than as everything
+fn deserialize_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let rate = f64::deserialize(deserializer)?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(serde::de::Error::custom(format!(
+            "sampling rate must be between 0 and 1, got {}",
+            rate
+        )));
+    }
+    Ok(rate)
+}
+
+/// Exchanges sampled alike, whose drift rates are estimated together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stratum {
+    Success,
+    /// 4xx and 5xx responses
+    Error,
+}
+
+impl Stratum {
+    pub fn of(status: u16) -> Self {
+        if status >= 400 {
+            Self::Error
+        } else {
+            Self::Success
+        }
+    }
+}
+
+/// Makes [`Sampling`] decisions for a stream of exchanges
+///
+/// Shared by reference across threads; per-operation limits are tracked
+/// against the exchanges' own timestamps, so replayed traffic is limited
+/// as it originally arrived.
+#[derive(Debug)]
+pub struct ExchangeSampler {
+    sampling: Sampling,
+    state: Mutex<SamplerState>,
+}
+
+#[derive(Debug)]
+struct SamplerState {
+    rng: SplitMix64,
+    /// Second and exchanges validated in it, per operation
+    admitted: HashMap<String, (u64, u32)>,
+}
+
+impl Default for ExchangeSampler {
+    fn default() -> Self {
+        Self::new(Sampling::default())
+    }
+}
+
+impl ExchangeSampler {
+    pub fn new(sampling: Sampling) -> Self {
+        Self {
+            sampling,
+            state: Mutex::new(SamplerState {
+                rng: SplitMix64::seeded(),
+                admitted: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn sampling(&self) -> &Sampling {
+        &self.sampling
+    }
+
+    /// Whether to validate an exchange on `operation` that got `status` at `timestamp_ms`
+    pub fn admit(&self, operation: &str, status: u16, timestamp_ms: u64) -> bool {
+        if self.sampling.is_all() || (self.sampling.always_errors && Stratum::of(status) == Stratum::Error) {
+            return true;
+        }
+        let mut state = self.lock();
+        if self.sampling.rate < 1.0 {
+            // Top 53 bits as a uniform draw from [0, 1)
+            let draw = (state.rng.next() >> 11) as f64 / (1u64 << 53) as f64;
+            if draw >= self.sampling.rate {
+                return false;
+            }
+        }
+        let Some(limit) = self.sampling.max_per_second else {
+            return true;
+        };
+        let second = timestamp_ms / 1000;
+        let admitted = state.admitted.entry(operation.to_string()).or_insert((second, 0));
+        if admitted.0 != second {
+            *admitted = (second, 0);
+        }
+        if admitted.1 >= limit {
+            return false;
+        }
+        admitted.1 += 1;
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SamplerState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,592 @@
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::drift_event::now_ms;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::spec::builder::{components_document, extract_json_schema, registry_from_document};
+use crate::spec::{BuildOptions, ResolveReference};
+use crate::validation_helpers::build_validator;
+use jsonschema::{Registry, Validator};
+use openapiv3::OpenAPI;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Body key: (method, path template, body such as `request` or `response 200`)
+type BodyKey = (HttpMethod, String, String);
+
+/// Compiled `oneOf`/`anyOf` branch per body and pointer; `None` if it didn't compile
+type BranchCache = HashMap<(BodyKey, String), Option<Arc<Validator>>>;
+
+/// Width of the time buckets branch usage is counted in by default
+const DEFAULT_BUCKET_WIDTH: Duration = Duration::from_secs(3600);
+
+/// Tracks which schema nodes are populated by observed valid bodies
+///
+/// Nodes are the properties and `oneOf`/`anyOf` branches of every request
+/// and response body schema, keyed by JSON pointer into the body schema
+/// (`/properties/pet/$ref/oneOf/1/properties/name`). Documented fields that
+/// no traffic ever populates are the mirror image of undocumented-field
+/// drift. Recursive schemas are expanded once.
+///
+/// For polymorphic schemas it also counts which branch every payload
+/// matched, per time bucket, so a documented variant that stops appearing
+/// or an unknown variant that starts appearing shows up in
+/// [`SchemaCoverageTracker::branch_usage`].
+pub struct SchemaCoverageTracker {
+    registry: Registry,
+    /// Target of the spec's local `$ref`s
+    components: Value,
+    bodies: BTreeMap<BodyKey, BodySchema>,
+    /// `oneOf`/`anyOf` nodes by body and pointer (`/properties/pet/oneOf`)
+    polymorphic: BTreeMap<(BodyKey, String), Polymorphic>,
+    bucket_ms: u64,
+    observed: Mutex<Observed>,
+    compiled_branches: Mutex<BranchCache>,
+}
+
+struct BodySchema {
+    schema: Value,
+    validator: Validator,
+}
+
+struct Polymorphic {
+    branch_count: usize,
+    discriminator: Option<String>,
+    mapped_values: Vec<String>,
+}
+
+#[derive(Default)]
+struct Observed {
+    nodes: BTreeMap<BodyKey, BTreeMap<String, u64>>,
+    /// Branch counts per polymorphic node, keyed by bucket start
+    branches: BTreeMap<(BodyKey, String), BTreeMap<u64, BranchCounts>>,
+}
+
+/// What one payload did at a `oneOf`/`anyOf` node
+struct BranchObservation {
+    pointer: String,
+    matched: Vec<usize>,
+    discriminator_value: Option<String>,
+}
+
+/// Nodes and branches found while walking one payload
+#[derive(Default)]
+struct Walk {
+    hits: Vec<String>,
+    branches: Vec<BranchObservation>,
+}
+
+impl SchemaCoverageTracker {
+    /// Creates a tracker for every JSON request and response body in a spec
+    pub fn new(spec: &OpenAPI, options: &BuildOptions) -> Result<Self, ValidationError> {
+        let components = components_document(spec, None, options)?;
+        let registry = registry_from_document(components.clone())?;
+        let mut bodies = BTreeMap::new();
+        let mut polymorphic = BTreeMap::new();
+        let mut observed = Observed::default();
+
+        for (path, path_item) in spec.paths.paths.iter().filter_map(|(path, item)| Some((path, item.as_item()?))) {
+            for (method_str, operation) in path_item.iter() {
+                let Ok(method) = HttpMethod::from_str(method_str) else {
+                    continue;
+                };
+
+                let mut schemas = Vec::new();
+                if let Some(request_body_ref) = &operation.request_body {
+                    let request_body = request_body_ref.resolve(spec)?;
+                    if let Ok(schema) = extract_json_schema(&request_body.content, "request body", spec, None, options) {
+                        schemas.push(("request".to_string(), schema));
+                    }
+                }
+                for (status_code, response_ref) in &operation.responses.responses {
+                    let openapiv3::StatusCode::Code(code) = status_code else {
+                        continue;
+                    };
+                    let response = response_ref.resolve(spec)?;
+                    if let Ok(schema) = extract_json_schema(&response.content, "response", spec, None, options) {
+                        schemas.push((format!("response {}", code), schema));
+                    }
+                }
+                if let Some(default_ref) = &operation.responses.default {
+                    let response = default_ref.resolve(spec)?;
+                    if let Ok(schema) = extract_json_schema(&response.content, "default response", spec, None, options) {
+                        schemas.push(("response default".to_string(), schema));
+                    }
+                }
+
+                for (body, schema) in schemas {
+                    let key = (method, path.clone(), body);
+                    let validator = build_validator(&schema, &registry, &key.2)?;
+                    let mut nodes = Vec::new();
+                    let mut polymorphic_nodes = Vec::new();
+                    declare_nodes(&schema, "", &components, &mut Vec::new(), &mut nodes, &mut polymorphic_nodes);
+                    observed.nodes.insert(key.clone(), nodes.into_iter().map(|node| (node, 0)).collect());
+                    for (pointer, node) in polymorphic_nodes {
+                        polymorphic.insert((key.clone(), pointer), node);
+                    }
+                    bodies.insert(key, BodySchema { schema, validator });
+                }
+            }
+        }
+
+        Ok(Self {
+            registry,
+            components,
+            bodies,
+            polymorphic,
+            bucket_ms: DEFAULT_BUCKET_WIDTH.as_millis() as u64,
+            observed: Mutex::new(observed),
+            compiled_branches: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Sets the width of the time buckets branch usage is counted in (hourly by default)
+    pub fn with_bucket_width(mut self, width: Duration) -> Self {
+        self.bucket_ms = (width.as_millis() as u64).max(1);
+        self
+    }
+
+    /// Records the bodies of an exchange, routing it with `validator`
+    ///
+    /// Bodies that are missing or not JSON are ignored. Bodies invalid against
+    /// their schema count toward branch usage, where unknown variants show
+    /// up, but not toward node coverage.
+    pub fn record_exchange(&self, validator: &ApiValidator, exchange: &Exchange) {
+        self.record_exchange_at(validator, exchange, now_ms());
+    }
+
+    /// Like [`SchemaCoverageTracker::record_exchange`] for an exchange observed
+    /// at `timestamp_ms` (milliseconds since the Unix epoch), e.g. when replaying captures
+    pub fn record_exchange_at(&self, validator: &ApiValidator, exchange: &Exchange, timestamp_ms: u64) {
+        let request = &exchange.request;
+        let Some(template) = validator.path_template(&request.path) else {
+            return;
+        };
+
+        let status = exchange.response.status;
+        let response_key = [format!("response {}", status), "response default".to_string()]
+            .into_iter()
+            .map(|body| (request.method, template.to_string(), body))
+            .find(|key| self.bodies.contains_key(key));
+
+        self.record_body(
+            &(request.method, template.to_string(), "request".to_string()),
+            request.body.as_deref(),
+            timestamp_ms,
+        );
+        if let Some(key) = response_key {
+            self.record_body(&key, exchange.response.body.as_deref(), timestamp_ms);
+        }
+    }
+
+    fn record_body(&self, key: &BodyKey, body: Option<&[u8]>, timestamp_ms: u64) {
+        let Some(schema) = self.bodies.get(key) else {
+            return;
+        };
+        let Some(value) = body.and_then(|bytes| serde_json::from_slice::<Value>(bytes).ok()) else {
+            return;
+        };
+        let valid = schema.validator.is_valid(&value);
+        let mut walk = Walk::default();
+        self.exercise(key, &schema.schema, String::new(), &value, &mut Vec::new(), &mut walk);
+
+        let mut observed = self.lock();
+        // Nodes past the first expansion of a recursive schema aren't declared
+        if let Some(nodes) = observed.nodes.get_mut(key).filter(|_| valid) {
+            for hit in walk.hits {
+                if let Some(count) = nodes.get_mut(&hit) {
+                    *count += 1;
+                }
+            }
+        }
+        let bucket = timestamp_ms - timestamp_ms % self.bucket_ms;
+        for observation in walk.branches {
+            let node = (key.clone(), observation.pointer);
+            if self.polymorphic.contains_key(&node) {
+                let counts = observed.branches.entry(node).or_default().entry(bucket).or_default();
+                counts.payloads += 1;
+                if observation.matched.is_empty() {
+                    counts.unmatched += 1;
+                }
+                for index in observation.matched {
+                    *counts.matched.entry(index).or_default() += 1;
+                }
+                if let Some(value) = observation.discriminator_value {
+                    *counts.discriminator_values.entry(value).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    /// Walks an instance alongside its schema, noting the nodes it populates
+    /// and the branches it matches
+    fn exercise<'a>(
+        &'a self,
+        key: &BodyKey,
+        schema: &'a Value,
+        pointer: String,
+        instance: &Value,
+        refs: &mut Vec<&'a str>,
+        walk: &mut Walk,
+    ) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            // A `$ref` cycle without descending into the instance would never end
+            if refs.contains(&reference) {
+                return;
+            }
+            if let Some(resolved) = resolve_ref(&self.components, reference) {
+                refs.push(reference);
+                self.exercise(key, resolved, format!("{}/$ref", pointer), instance, refs, walk);
+                refs.pop();
+            }
+            return;
+        }
+
+        if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+            for (index, branch) in branches.iter().enumerate() {
+                let branch_pointer = format!("{}/allOf/{}", pointer, index);
+                self.exercise(key, branch, branch_pointer, instance, refs, walk);
+            }
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            let Some(branches) = schema.get(keyword).and_then(Value::as_array) else {
+                continue;
+            };
+            let mut matched = Vec::new();
+            for (index, branch) in branches.iter().enumerate() {
+                let branch_pointer = format!("{}/{}/{}", pointer, keyword, index);
+                if self.branch_matches(key, &branch_pointer, branch, instance) {
+                    matched.push(index);
+                    walk.hits.push(branch_pointer.clone());
+                    self.exercise(key, branch, branch_pointer, instance, refs, walk);
+                }
+            }
+            walk.branches.push(BranchObservation {
+                pointer: format!("{}/{}", pointer, keyword),
+                matched,
+                discriminator_value: discriminator(schema)
+                    .and_then(|property| instance.get(property))
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            });
+        }
+
+        match instance {
+            Value::Object(fields) => {
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let additional = schema.get("additionalProperties").filter(|s| s.is_object());
+                for (name, value) in fields {
+                    let (subschema, child_pointer) = match properties.and_then(|p| p.get(name)) {
+                        Some(subschema) => {
+                            let child_pointer = format!("{}/properties/{}", pointer, escape_pointer(name));
+                            walk.hits.push(child_pointer.clone());
+                            (subschema, child_pointer)
+                        }
+                        None => match additional {
+                            Some(subschema) => (subschema, format!("{}/additionalProperties", pointer)),
+                            None => continue,
+                        },
+                    };
+                    self.exercise(key, subschema, child_pointer, value, &mut Vec::new(), walk);
+                }
+            }
+            Value::Array(items) => {
+                if let Some(subschema) = schema.get("items").filter(|s| s.is_object()) {
+                    for item in items {
+                        let child_pointer = format!("{}/items", pointer);
+                        self.exercise(key, subschema, child_pointer, item, &mut Vec::new(), walk);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether an instance is valid against a single `oneOf`/`anyOf` branch
+    fn branch_matches(&self, key: &BodyKey, pointer: &str, branch: &Value, instance: &Value) -> bool {
+        let mut branches = self.compiled_branches.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let compiled = branches
+            .entry((key.clone(), pointer.to_string()))
+            .or_insert_with(|| build_validator(branch, &self.registry, pointer).ok().map(Arc::new))
+            .clone();
+        drop(branches);
+        compiled.map(|validator| validator.is_valid(instance)).unwrap_or(false)
+    }
+
+    /// Snapshot of schema coverage so far
+    pub fn report(&self) -> SchemaCoverageReport {
+        let observed = self.lock();
+        let bodies: Vec<BodySchemaCoverage> = observed
+            .nodes
+            .iter()
+            .map(|((method, path_template, body), nodes)| BodySchemaCoverage {
+                method: *method,
+                path_template: path_template.clone(),
+                body: body.clone(),
+                nodes: nodes.clone(),
+            })
+            .collect();
+
+        SchemaCoverageReport {
+            nodes_total: bodies.iter().map(|body| body.nodes.len()).sum(),
+            nodes_exercised: bodies
+                .iter()
+                .map(|body| body.nodes.values().filter(|count| **count > 0).count())
+                .sum(),
+            bodies,
+        }
+    }
+
+    /// Branch usage of every `oneOf`/`anyOf` node so far, oldest bucket first
+    pub fn branch_usage(&self) -> Vec<BranchUsage> {
+        let observed = self.lock();
+        self.polymorphic
+            .iter()
+            .map(|(node, declared)| {
+                let ((method, path_template, body), pointer) = node;
+                let buckets = observed
+                    .branches
+                    .get(node)
+                    .map(|buckets| {
+                        buckets
+                            .iter()
+                            .map(|(start_ms, counts)| BranchBucket {
+                                start_ms: *start_ms,
+                                counts: counts.clone(),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                BranchUsage {
+                    method: *method,
+                    path_template: path_template.clone(),
+                    body: body.clone(),
+                    pointer: pointer.clone(),
+                    branch_count: declared.branch_count,
+                    discriminator: declared.discriminator.clone(),
+                    mapped_values: declared.mapped_values.clone(),
+                    buckets,
+                }
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Observed> {
+        self.observed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Looks up a local `$ref` such as `#/components/schemas/Pet`
+fn resolve_ref<'a>(components: &'a Value, reference: &str) -> Option<&'a Value> {
+    components.pointer(reference.strip_prefix('#')?)
+}
+
+/// Property name of a schema's `discriminator`
+fn discriminator(schema: &Value) -> Option<&str> {
+    schema.get("discriminator")?.get("propertyName")?.as_str()
+}
+
+/// Collects the pointers of every property and `oneOf`/`anyOf` branch in a
+/// schema, along with the `oneOf`/`anyOf` nodes themselves
+fn declare_nodes<'a>(
+    schema: &'a Value,
+    pointer: &str,
+    components: &'a Value,
+    refs: &mut Vec<&'a str>,
+    nodes: &mut Vec<String>,
+    polymorphic: &mut Vec<(String, Polymorphic)>,
+) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if refs.contains(&reference) {
+            return;
+        }
+        if let Some(resolved) = resolve_ref(components, reference) {
+            refs.push(reference);
+            declare_nodes(resolved, &format!("{}/$ref", pointer), components, refs, nodes, polymorphic);
+            refs.pop();
+        }
+        return;
+    }
+
+    if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+        for (index, branch) in branches.iter().enumerate() {
+            declare_nodes(branch, &format!("{}/allOf/{}", pointer, index), components, refs, nodes, polymorphic);
+        }
+    }
+    for keyword in ["oneOf", "anyOf"] {
+        if let Some(branches) = schema.get(keyword).and_then(Value::as_array) {
+            let mapped_values = schema
+                .get("discriminator")
+                .and_then(|d| d.get("mapping"))
+                .and_then(Value::as_object)
+                .map(|mapping| mapping.keys().cloned().collect())
+                .unwrap_or_default();
+            polymorphic.push((
+                format!("{}/{}", pointer, keyword),
+                Polymorphic {
+                    branch_count: branches.len(),
+                    discriminator: discriminator(schema).map(str::to_string),
+                    mapped_values,
+                },
+            ));
+            for (index, branch) in branches.iter().enumerate() {
+                let branch_pointer = format!("{}/{}/{}", pointer, keyword, index);
+                nodes.push(branch_pointer.clone());
+                declare_nodes(branch, &branch_pointer, components, refs, nodes, polymorphic);
+            }
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (name, subschema) in properties {
+            let property_pointer = format!("{}/properties/{}", pointer, escape_pointer(name));
+            nodes.push(property_pointer.clone());
+            declare_nodes(subschema, &property_pointer, components, refs, nodes, polymorphic);
+        }
+    }
+    if let Some(subschema) = schema.get("additionalProperties").filter(|s| s.is_object()) {
+        declare_nodes(subschema, &format!("{}/additionalProperties", pointer), components, refs, nodes, polymorphic);
+    }
+    if let Some(subschema) = schema.get("items").filter(|s| s.is_object()) {
+        declare_nodes(subschema, &format!("{}/items", pointer), components, refs, nodes, polymorphic);
+    }
+}
+
+/// Escapes a property name for use as a JSON pointer segment
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Schema nodes of one request or response body, with how often valid traffic populated each
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodySchemaCoverage {
+    pub method: HttpMethod,
+    pub path_template: String,
+    /// `request`, `response 200` or `response default`
+    pub body: String,
+    /// Populated count per JSON pointer into the body schema
+    pub nodes: BTreeMap<String, u64>,
+}
+
+/// Coverage of the spec's body schemas by observed valid traffic
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaCoverageReport {
+    pub nodes_total: usize,
+    pub nodes_exercised: usize,
+    pub bodies: Vec<BodySchemaCoverage>,
+}
+
+impl SchemaCoverageReport {
+    /// Fraction of schema nodes populated by traffic, between 0 and 1
+    pub fn ratio(&self) -> f64 {
+        if self.nodes_total == 0 {
+            return 1.0;
+        }
+        self.nodes_exercised as f64 / self.nodes_total as f64
+    }
+
+    /// Documented schema nodes no valid traffic has populated
+    pub fn never_exercised(&self) -> impl Iterator<Item = (&BodySchemaCoverage, &str)> {
+        self.bodies.iter().flat_map(|body| {
+            body.nodes
+                .iter()
+                .filter(|(_, count)| **count == 0)
+                .map(move |(pointer, _)| (body, pointer.as_str()))
+        })
+    }
+}
+
+/// Payloads seen at a `oneOf`/`anyOf` node, by the branch they matched
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BranchCounts {
+    pub payloads: u64,
+    /// Payloads per matching branch index (`anyOf` payloads can match several)
+    pub matched: BTreeMap<usize, u64>,
+    /// Payloads matching no branch: an undocumented variant
+    pub unmatched: u64,
+    /// Payloads per value of the discriminator property
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub discriminator_values: BTreeMap<String, u64>,
+}
+
+impl BranchCounts {
+    fn merge(&mut self, other: &BranchCounts) {
+        self.payloads += other.payloads;
+        self.unmatched += other.unmatched;
+        for (index, count) in &other.matched {
+            *self.matched.entry(*index).or_default() += count;
+        }
+        for (value, count) in &other.discriminator_values {
+            *self.discriminator_values.entry(value.clone()).or_default() += count;
+        }
+    }
+}
+
+/// Branch counts for one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchBucket {
+    /// Bucket start in milliseconds since the Unix epoch
+    pub start_ms: u64,
+    #[serde(flatten)]
+    pub counts: BranchCounts,
+}
+
+/// Which branches of a polymorphic schema traffic used, over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchUsage {
+    pub method: HttpMethod,
+    pub path_template: String,
+    /// `request`, `response 200` or `response default`
+    pub body: String,
+    /// JSON pointer of the `oneOf`/`anyOf` keyword in the body schema
+    pub pointer: String,
+    pub branch_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<String>,
+    /// Discriminator values listed in the schema's `mapping`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mapped_values: Vec<String>,
+    pub buckets: Vec<BranchBucket>,
+}
+
+impl BranchUsage {
+    /// Counts summed over all buckets
+    pub fn totals(&self) -> BranchCounts {
+        let mut totals = BranchCounts::default();
+        for bucket in &self.buckets {
+            totals.merge(&bucket.counts);
+        }
+        totals
+    }
+
+    /// Start of the latest bucket in which a branch matched
+    pub fn last_matched_ms(&self, branch: usize) -> Option<u64> {
+        self.buckets
+            .iter()
+            .rev()
+            .find(|bucket| bucket.counts.matched.contains_key(&branch))
+            .map(|bucket| bucket.start_ms)
+    }
+
+    /// Documented branches no payload has matched
+    pub fn unused_branches(&self) -> Vec<usize> {
+        let totals = self.totals();
+        (0..self.branch_count).filter(|index| !totals.matched.contains_key(index)).collect()
+    }
+
+    /// Discriminator values seen that the schema's `mapping` doesn't list
+    ///
+    /// Empty when the schema has no mapping to compare against.
+    pub fn unknown_discriminator_values(&self) -> Vec<String> {
+        if self.mapped_values.is_empty() {
+            return Vec::new();
+        }
+        self.totals()
+            .discriminator_values
+            .into_keys()
+            .filter(|value| !self.mapped_values.contains(value))
+            .collect()
+    }
+}
@@ -0,0 +1,125 @@
+//! Operator-supplied Rhai scripts run over drift events before they reach sinks
+//!
+//! One-off policies (drop a known-noisy finding, downgrade drift on a
+//! deprecated operation, tag events with the owning team) are easier to
+//! express as a few lines of script than as a Rust plugin. The script
+//! defines a `transform` function taking the event as an object map (the
+//! same shape as its JSON form) and returning the event to keep, or `()` to
+//! drop it:
+//!
+//! ```rhai
+//! const OWNERS = #{ "/users/{userId}": "identity", "/orders": "checkout" };
+//!
+//! fn transform(event) {
+//!     if event.drift_type == "RESPONSE_BODY_UNDOCUMENTED_FIELD" && event.path_template == "/legacy" {
+//!         return ();
+//!     }
+//!     if event.location.starts_with("body/debug") {
+//!         event.severity = "info";
+//!     }
+//!     let owner = global::OWNERS.get(event.path_template ?? "");
+//!     if owner != () {
+//!         event.context.team = owner;
+//!     }
+//!     event
+//! }
+//! ```
+
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use rhai::{Dynamic, Engine, Scope, AST};
+use std::fs;
+use std::path::Path;
+
+/// Name of the function a script must define
+pub const TRANSFORM_FN: &str = "transform";
+
+/// Operations a script may run per event before it is aborted, so a runaway
+/// loop can't stall the monitor
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A compiled event transform script
+pub struct ScriptTransform {
+    engine: Engine,
+    ast: AST,
+}
+
+impl ScriptTransform {
+    /// Compiles a script file
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let source = fs::read_to_string(path).map_err(|e| {
+            ValidationError::ScriptError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        Self::compile(&source).map_err(|e| match e {
+            ValidationError::ScriptError(message) => {
+                ValidationError::ScriptError(format!("{}: {}", path.display(), message))
+            }
+            other => other,
+        })
+    }
+
+    /// Compiles a script, which must define `transform(event)`
+    pub fn compile(source: &str) -> Result<Self, ValidationError> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| ValidationError::ScriptError(e.to_string()))?;
+        if !ast.iter_functions().any(|f| f.name == TRANSFORM_FN && f.params.len() == 1) {
+            return Err(ValidationError::ScriptError(format!(
+                "script does not define `fn {}(event)`",
+                TRANSFORM_FN
+            )));
+        }
+        Ok(Self { engine, ast })
+    }
+
+    /// Runs the script over an event; `None` means the script dropped it
+    ///
+    /// Fails if the script errors or returns something that isn't a drift
+    /// event, so a broken policy doesn't silently discard findings.
+    pub fn apply(&self, event: DriftEvent) -> Result<Option<DriftEvent>, ValidationError> {
+        let script_error = |e: &dyn std::fmt::Display| ValidationError::ScriptError(e.to_string());
+        let mut input = rhai::serde::to_dynamic(&event).map_err(|e| script_error(&e))?;
+        // An empty context is left out of the serialized event; scripts enriching it expect a map
+        if let Some(mut map) = input.write_lock::<rhai::Map>() {
+            map.entry("context".into()).or_insert_with(|| rhai::Map::new().into());
+        }
+        let output: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, TRANSFORM_FN, (input,))
+            .map_err(|e| script_error(&e))?;
+        if output.is_unit() {
+            return Ok(None);
+        }
+        rhai::serde::from_dynamic(&output)
+            .map(Some)
+            .map_err(|e| ValidationError::ScriptError(format!("`{}` returned an invalid event: {}", TRANSFORM_FN, e)))
+    }
+}
+
+/// Sink wrapper running every event through a script before forwarding it
+pub struct ScriptedSink<S> {
+    transform: ScriptTransform,
+    inner: S,
+}
+
+impl<S: DriftSink> ScriptedSink<S> {
+    pub fn new(transform: ScriptTransform, inner: S) -> Self {
+        Self { transform, inner }
+    }
+}
+
+impl<S: DriftSink> DriftSink for ScriptedSink<S> {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        match self.transform.apply(event)? {
+            Some(event) => self.inner.record(event),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,143 @@
+//! Time-boxed monitoring runs driven by a [`MonitorConfig`]
+//!
+//! Canary pipelines want "monitor for two hours after the deploy, then gate
+//! on the result" without something external to stop the monitor. A session
+//! reads the configured source until its deadline, sends drift to the
+//! configured sinks, then writes the policy's reports and returns its verdict.
+
+use crate::config::{MonitorConfig, PolicyVerdict, SourceConfig};
+use crate::error::ValidationError;
+use crate::sampling::ExchangeSampler;
+use crate::sinks::DriftSink;
+use crate::spec::{
+    build_api_validator_with_options, load_openapi_spec, BuildOptions, BuildProgress, SpecLocator,
+};
+#[cfg(feature = "kafka")]
+use crate::stream::KafkaSource;
+use crate::traffic::{ingest_sampled, parse_rfc3339, IngestSummary, JsonlFormat, LogReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A monitoring run over a configuration's source, sinks and policy
+#[derive(Debug, Clone)]
+pub struct Session {
+    config: MonitorConfig,
+    deadline: Option<SystemTime>,
+    progress: Option<Arc<dyn BuildProgress>>,
+}
+
+/// What a session saw and decided
+#[derive(Debug, Clone)]
+pub struct SessionOutcome {
+    pub summary: IngestSummary,
+    pub verdict: PolicyVerdict,
+    /// Report files written for the policy
+    pub reports: Vec<PathBuf>,
+}
+
+impl Session {
+    pub fn new(config: MonitorConfig) -> Self {
+        Self {
+            config,
+            deadline: None,
+            progress: None,
+        }
+    }
+
+    /// Stops intake at `deadline`
+    ///
+    /// Without a deadline a replayed log is read to its end and a Kafka topic
+    /// is consumed indefinitely; with one, lines appended to the log keep
+    /// being read until the deadline passes.
+    pub fn until(mut self, deadline: SystemTime) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops intake once `duration` has passed from now
+    pub fn for_duration(self, duration: Duration) -> Self {
+        self.until(SystemTime::now() + duration)
+    }
+
+    /// Reports validator build progress to `progress`
+    pub fn with_progress(mut self, progress: Arc<dyn BuildProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Runs to the deadline (or the end of the source), then finalizes
+    pub fn run(&self) -> Result<SessionOutcome, ValidationError> {
+        let config = &self.config;
+        let spec_path = config
+            .spec
+            .as_ref()
+            .ok_or_else(|| ValidationError::ConfigError("no `spec` to validate against".to_string()))?;
+        let options = BuildOptions {
+            progress: self.progress.clone(),
+            ..config.build_options()
+        };
+        let validator = build_api_validator_with_options(&load_openapi_spec(spec_path)?, &options)?;
+
+        let sinks = config.sinks.iter().map(|sink| sink.open()).collect::<Result<Vec<_>, _>>()?;
+        let mut summary = match &config.source {
+            Some(SourceConfig::Replay { path }) => {
+                let mut reader = LogReader::open(path, JsonlFormat)?;
+                if let Some(deadline) = self.deadline {
+                    reader = reader.follow().until(deadline);
+                }
+                ingest_sampled(&validator, reader, &sinks as &dyn DriftSink, &ExchangeSampler::new(config.sampling.clone()))?
+            }
+            Some(SourceConfig::Sidecar { .. }) => return Err(unsupported_source("sidecar")),
+            #[cfg(feature = "kafka")]
+            Some(SourceConfig::Kafka {
+                brokers,
+                topic,
+                group_id,
+            }) => KafkaSource::connect(brokers, topic, group_id)?
+                .with_sampling(config.sampling.clone())
+                .run(&validator, &sinks, self.deadline)?,
+            #[cfg(not(feature = "kafka"))]
+            Some(SourceConfig::Kafka { .. }) => return Err(unsupported_source("kafka")),
+            None => return Err(ValidationError::ConfigError("no `source` to read traffic from".to_string())),
+        };
+
+        let locator = Arc::new(SpecLocator::load(spec_path)?);
+        summary.report = std::mem::take(&mut summary.report).with_spec_locator(locator);
+        let verdict = config.policy.evaluate(&summary.report.events)?;
+        let reports = config.policy.write_reports(&summary.report)?;
+        Ok(SessionOutcome {
+            summary,
+            verdict,
+            reports,
+        })
+    }
+}
+
+fn unsupported_source(kind: &str) -> ValidationError {
+    ValidationError::UnsupportedFeature {
+        feature: format!("`{}` sources in monitoring sessions", kind),
+    }
+}
+
+/// Parses a duration such as `90s`, `15m`, `2h` or `7d`
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}' (expected e.g. 90s, 15m, 2h or 7d)", value);
+    let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: u64 = value[..split].parse().map_err(|_| invalid())?;
+    let unit_secs = match &value[split..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+    Ok(Duration::from_secs(amount * unit_secs))
+}
+
+/// Parses an RFC 3339 timestamp such as `2025-06-01T14:00:00Z`
+pub fn parse_deadline(value: &str) -> Result<SystemTime, String> {
+    parse_rfc3339(value)
+        .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+        .ok_or_else(|| format!("invalid timestamp '{}' (expected RFC 3339, e.g. 2025-06-01T14:00:00Z)", value))
+}
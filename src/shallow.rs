@@ -0,0 +1,155 @@
+//! Depth-limited validation of oversized bodies
+//!
+//! Materializing a multi-megabyte body as a `serde_json::Value` costs many
+//! times its size in memory. Bodies over the size limit can instead be
+//! streamed into a value that keeps only their outer levels: containers
+//! nested deeper than the configured depth are skipped while parsing and
+//! stand in as empty ones, so their type is still checked but not their
+//! contents. Findings are marked with the depth the body was checked to.
+
+use crate::drift_event::DriftEvent;
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserializer;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// An oversized body with its deeper containers emptied
+pub(crate) struct ShallowBody {
+    pub(crate) value: Value,
+    depth: usize,
+    /// JSON pointers of the containers emptied
+    pruned: Vec<String>,
+}
+
+impl ShallowBody {
+    /// Streams `bytes` into a value keeping `depth` levels of nesting
+    ///
+    /// With a depth of 1 only the top-level container's own members are
+    /// kept, each nested container emptied.
+    pub(crate) fn parse(bytes: &[u8], depth: usize) -> Result<Self, serde_json::Error> {
+        let mut pruned = Vec::new();
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let value = Pruning {
+            pointer: String::new(),
+            remaining: depth,
+            pruned: &mut pruned,
+        }
+        .deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(Self { value, depth, pruned })
+    }
+
+    /// Marks an event found in the kept levels, or drops it if the emptied containers may have caused it
+    ///
+    /// Emptied containers keep their type and nothing else, so only type
+    /// mismatches on them hold; uniqueness of an array whose items were
+    /// emptied doesn't either.
+    pub(crate) fn annotate(&self, mut event: DriftEvent) -> Option<DriftEvent> {
+        let pointer = event.location.find('/').map_or("", |start| &event.location[start..]);
+        let drift_type = event.drift_type.as_str();
+        if self.pruned.iter().any(|pruned| pruned == pointer) && !drift_type.ends_with("_TYPE_MISMATCH") {
+            return None;
+        }
+        if drift_type.ends_with("_ARRAY_CONSTRAINT_VIOLATION")
+            && self.pruned.iter().any(|pruned| parent(pruned) == pointer)
+        {
+            return None;
+        }
+        event.validated_depth = Some(self.depth);
+        Some(event)
+    }
+}
+
+fn parent(pointer: &str) -> &str {
+    pointer.rfind('/').map_or("", |end| &pointer[..end])
+}
+
+/// Deserializes one value, emptying containers once `remaining` levels are used up
+struct Pruning<'p> {
+    pointer: String,
+    remaining: usize,
+    pruned: &'p mut Vec<String>,
+}
+
+impl<'de> DeserializeSeed<'de> for Pruning<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Pruning<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(value.into())
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        if self.remaining == 0 {
+            while seq.next_element::<IgnoredAny>()?.is_some() {}
+            self.pruned.push(self.pointer);
+            return Ok(Value::Array(Vec::new()));
+        }
+        let mut items = Vec::new();
+        loop {
+            let item = Pruning {
+                pointer: format!("{}/{}", self.pointer, items.len()),
+                remaining: self.remaining - 1,
+                pruned: &mut *self.pruned,
+            };
+            match seq.next_element_seed(item)? {
+                Some(item) => items.push(item),
+                None => break,
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        if self.remaining == 0 {
+            while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+            self.pruned.push(self.pointer);
+            return Ok(Value::Object(Map::new()));
+        }
+        let mut object = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(Pruning {
+                pointer: format!("{}/{}", self.pointer, key.replace('~', "~0").replace('/', "~1")),
+                remaining: self.remaining - 1,
+                pruned: &mut *self.pruned,
+            })?;
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
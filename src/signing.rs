@@ -0,0 +1,312 @@
+//! Tamper-evident report artifacts
+//!
+//! Reports used as audit evidence are signed with Ed25519 in the minisign
+//! format: public keys and `.minisig` signatures written here can be checked
+//! with `minisign -V`, and signatures made by minisign verify here. The
+//! trusted comment (signing time and file name) is covered by the signature
+//! too, so it can't be swapped either.
+//!
+//! Secret keys are stored unencrypted, in the same layout as a minisign
+//! secret key's decrypted payload, readable only by their owner on Unix;
+//! keep them in a secret store rather than next to the reports they sign.
+
+use crate::error::ValidationError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name suffix of detached signatures
+pub const SIGNATURE_EXTENSION: &str = "minisig";
+
+/// Algorithm tag of keys, and of signatures over the raw artifact
+const ALG_ED25519: [u8; 2] = *b"Ed";
+/// Algorithm tag of signatures over the artifact's BLAKE2b-512 hash
+const ALG_ED25519_PREHASHED: [u8; 2] = *b"ED";
+
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+/// Signs report artifacts
+pub struct ReportSigner {
+    key_id: [u8; 8],
+    key: SigningKey,
+}
+
+impl ReportSigner {
+    /// Creates a new key pair from the operating system's random source
+    pub fn generate() -> Result<Self, ValidationError> {
+        let mut key_id = [0; 8];
+        let mut seed = [0; 32];
+        getrandom::getrandom(&mut key_id)
+            .and_then(|()| getrandom::getrandom(&mut seed))
+            .map_err(|e| ValidationError::SignatureError(format!("Failed to generate key: {}", e)))?;
+        Ok(Self {
+            key_id,
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Reads a secret key written by [`ReportSigner::save`]
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let payload = decode_file(path, 2 + 8 + 32)?;
+        if payload[..2] != ALG_ED25519 {
+            return Err(ValidationError::SignatureError(format!(
+                "{} is not an Ed25519 secret key",
+                path.display()
+            )));
+        }
+        let mut seed = [0; 32];
+        seed.copy_from_slice(&payload[10..]);
+        Ok(Self {
+            key_id: key_id(&payload[2..10]),
+            key: SigningKey::from_bytes(&seed),
+        })
+    }
+
+    /// Writes the secret key to a new file, readable only by its owner on Unix
+    ///
+    /// Fails rather than overwrite an existing file, which may be another key.
+    pub fn save(&self, path: &Path) -> Result<(), ValidationError> {
+        let payload = [&ALG_ED25519[..], &self.key_id, self.key.as_bytes()].concat();
+        let contents = format!(
+            "{}api-spec-drift-monitor secret key {}\n{}\n",
+            UNTRUSTED_COMMENT,
+            key_id_hex(&self.key_id),
+            STANDARD.encode(payload)
+        );
+        write_secret_file(path, &contents)
+    }
+
+    /// The public key verifying this signer's signatures
+    pub fn public_key(&self) -> ReportPublicKey {
+        ReportPublicKey {
+            key_id: self.key_id,
+            key: self.key.verifying_key(),
+        }
+    }
+
+    /// Signs an artifact, covering `trusted_comment` as well
+    pub fn sign(&self, artifact: &[u8], trusted_comment: &str) -> ReportSignature {
+        let signature = self.key.sign(&Blake2b512::digest(artifact));
+        let global_signature = self.key.sign(&[&signature.to_bytes()[..], trusted_comment.as_bytes()].concat());
+        ReportSignature {
+            algorithm: ALG_ED25519_PREHASHED,
+            key_id: self.key_id,
+            signature,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature,
+        }
+    }
+
+    /// Signs the file at `path`, writing the signature next to it; returns the signature's path
+    ///
+    /// The trusted comment records when and which file was signed.
+    pub fn sign_file(&self, path: &Path) -> Result<PathBuf, ValidationError> {
+        let artifact = read_file(path)?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let file = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let signature = self.sign(&artifact, &format!("timestamp:{}\tfile:{}", timestamp, file));
+        let signature_path = signature_path(path);
+        write_file(&signature_path, &signature.to_string())?;
+        Ok(signature_path)
+    }
+}
+
+/// Verifies signed report artifacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportPublicKey {
+    key_id: [u8; 8],
+    key: VerifyingKey,
+}
+
+impl ReportPublicKey {
+    /// Reads a public key file, as written by [`ReportPublicKey::save`] or `minisign -G`
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let payload = decode_file(path, 2 + 8 + 32)?;
+        if payload[..2] != ALG_ED25519 {
+            return Err(ValidationError::SignatureError(format!(
+                "{} is not an Ed25519 public key",
+                path.display()
+            )));
+        }
+        let mut key = [0; 32];
+        key.copy_from_slice(&payload[10..]);
+        Ok(Self {
+            key_id: key_id(&payload[2..10]),
+            key: VerifyingKey::from_bytes(&key).map_err(|e| {
+                ValidationError::SignatureError(format!("Invalid public key in {}: {}", path.display(), e))
+            })?,
+        })
+    }
+
+    /// Writes the public key in minisign's format
+    pub fn save(&self, path: &Path) -> Result<(), ValidationError> {
+        write_file(path, &self.to_string())
+    }
+
+    /// Checks a signature over an artifact and its trusted comment
+    pub fn verify(&self, artifact: &[u8], signature: &ReportSignature) -> Result<(), ValidationError> {
+        if signature.key_id != self.key_id {
+            return Err(ValidationError::SignatureError(format!(
+                "Signed with key {}, not {}",
+                key_id_hex(&signature.key_id),
+                key_id_hex(&self.key_id)
+            )));
+        }
+        let tampered = |what: &str| ValidationError::SignatureError(format!("Signature does not match the {}", what));
+        let signed = match signature.algorithm {
+            ALG_ED25519_PREHASHED => self.key.verify(&Blake2b512::digest(artifact), &signature.signature),
+            _ => self.key.verify(artifact, &signature.signature),
+        };
+        signed.map_err(|_| tampered("artifact"))?;
+        let comment = [&signature.signature.to_bytes()[..], signature.trusted_comment.as_bytes()].concat();
+        self.key
+            .verify(&comment, &signature.global_signature)
+            .map_err(|_| tampered("trusted comment"))
+    }
+
+    /// Checks the signature at `signature_path` over the file at `path`, returning its trusted comment
+    pub fn verify_file(&self, path: &Path, signature_path: &Path) -> Result<String, ValidationError> {
+        let signature = ReportSignature::load(signature_path)?;
+        self.verify(&read_file(path)?, &signature)?;
+        Ok(signature.trusted_comment)
+    }
+}
+
+impl fmt::Display for ReportPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let payload = [&ALG_ED25519[..], &self.key_id, self.key.as_bytes()].concat();
+        writeln!(f, "{}minisign public key {}", UNTRUSTED_COMMENT, key_id_hex(&self.key_id))?;
+        writeln!(f, "{}", STANDARD.encode(payload))
+    }
+}
+
+/// A detached signature over an artifact and a trusted comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportSignature {
+    algorithm: [u8; 2],
+    key_id: [u8; 8],
+    signature: Signature,
+    /// Signed metadata, e.g. `timestamp:1760659200\tfile:drift-report.json`
+    pub trusted_comment: String,
+    global_signature: Signature,
+}
+
+impl ReportSignature {
+    /// Reads a `.minisig` signature file
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let contents = String::from_utf8(read_file(path)?).unwrap_or_default();
+        contents
+            .parse()
+            .map_err(|e| ValidationError::SignatureError(format!("{}: {}", path.display(), e)))
+    }
+}
+
+impl std::str::FromStr for ReportSignature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let (Some(_), Some(payload), Some(comment), Some(global)) =
+            (lines.next(), lines.next(), lines.next(), lines.next())
+        else {
+            return Err("expected 4 lines".to_string());
+        };
+        let payload = decode(payload, 2 + 8 + 64)?;
+        let algorithm = [payload[0], payload[1]];
+        if algorithm != ALG_ED25519 && algorithm != ALG_ED25519_PREHASHED {
+            return Err("unsupported signature algorithm".to_string());
+        }
+        let trusted_comment = comment
+            .strip_prefix(TRUSTED_COMMENT)
+            .ok_or_else(|| "missing trusted comment".to_string())?;
+        Ok(Self {
+            algorithm,
+            key_id: key_id(&payload[2..10]),
+            signature: Signature::from_slice(&payload[10..]).map_err(|e| e.to_string())?,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature: Signature::from_slice(&decode(global, 64)?).map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+impl fmt::Display for ReportSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let payload = [&self.algorithm[..], &self.key_id, &self.signature.to_bytes()].concat();
+        writeln!(f, "{}signature from api-spec-drift-monitor key {}", UNTRUSTED_COMMENT, key_id_hex(&self.key_id))?;
+        writeln!(f, "{}", STANDARD.encode(payload))?;
+        writeln!(f, "{}{}", TRUSTED_COMMENT, self.trusted_comment)?;
+        writeln!(f, "{}", STANDARD.encode(self.global_signature.to_bytes()))
+    }
+}
+
+/// Where the signature of the artifact at `path` is written
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(".");
+    signature_path.push(SIGNATURE_EXTENSION);
+    PathBuf::from(signature_path)
+}
+
+fn key_id(bytes: &[u8]) -> [u8; 8] {
+    let mut key_id = [0; 8];
+    key_id.copy_from_slice(bytes);
+    key_id
+}
+
+/// Key IDs are shown as minisign shows them: a little-endian integer in hex
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+fn decode(line: &str, len: usize) -> Result<Vec<u8>, String> {
+    let bytes = STANDARD.decode(line.trim()).map_err(|e| e.to_string())?;
+    if bytes.len() != len {
+        return Err(format!("expected {} bytes, found {}", len, bytes.len()));
+    }
+    Ok(bytes)
+}
+
+/// Decodes the base64 line following a key file's untrusted comment
+fn decode_file(path: &Path, len: usize) -> Result<Vec<u8>, ValidationError> {
+    let contents = String::from_utf8(read_file(path)?).unwrap_or_default();
+    let line = contents
+        .lines()
+        .find(|line| !line.starts_with(UNTRUSTED_COMMENT) && !line.trim().is_empty())
+        .unwrap_or_default();
+    decode(line, len).map_err(|e| ValidationError::SignatureError(format!("{}: {}", path.display(), e)))
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, ValidationError> {
+    fs::read(path).map_err(|e| ValidationError::SignatureError(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), ValidationError> {
+    fs::write(path, contents)
+        .map_err(|e| ValidationError::SignatureError(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Creates `path` with permissions `0600` and writes `contents`, refusing to replace an existing file
+fn write_secret_file(path: &Path, contents: &str) -> Result<(), ValidationError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => {
+                ValidationError::SignatureError(format!("{} already exists; not overwriting it", path.display()))
+            }
+            _ => ValidationError::SignatureError(format!("Failed to write {}: {}", path.display(), e)),
+        })
+}
@@ -0,0 +1,143 @@
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::sinks::{to_json_line, DriftSink};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+/// Future returned by [`AsyncDriftSink`] methods
+pub type SinkFuture<'a> = Pin<Box<dyn Future<Output = Result<(), ValidationError>> + Send + 'a>>;
+
+/// Async counterpart of [`DriftSink`] for sinks embedded in async services
+///
+/// Methods return boxed futures so sinks can be used as trait objects
+/// (`Box<dyn AsyncDriftSink>`), like their sync counterparts.
+pub trait AsyncDriftSink: Send + Sync {
+    /// Records a single drift event
+    fn record(&self, event: DriftEvent) -> SinkFuture<'_>;
+
+    /// Flushes any buffered events
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Records every event in order, stopping at the first failure
+    fn record_all(&self, events: Vec<DriftEvent>) -> SinkFuture<'_> {
+        Box::pin(async move {
+            for event in events {
+                self.record(event).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl<S: AsyncDriftSink + ?Sized> AsyncDriftSink for Box<S> {
+    fn record(&self, event: DriftEvent) -> SinkFuture<'_> {
+        (**self).record(event)
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        (**self).flush()
+    }
+}
+
+impl<S: AsyncDriftSink + ?Sized> AsyncDriftSink for Arc<S> {
+    fn record(&self, event: DriftEvent) -> SinkFuture<'_> {
+        (**self).record(event)
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        (**self).flush()
+    }
+}
+
+/// Runs a sync [`DriftSink`] on tokio's blocking pool
+///
+/// Lets the file and stdout sinks be used from async code without their
+/// I/O stalling the runtime's worker threads.
+#[derive(Debug)]
+pub struct BlockingSink<S> {
+    inner: Arc<S>,
+}
+
+impl<S: DriftSink + 'static> BlockingSink<S> {
+    pub fn new(sink: S) -> Self {
+        Self { inner: Arc::new(sink) }
+    }
+
+    /// The wrapped sink
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn run<F>(&self, op: F) -> SinkFuture<'_>
+    where
+        F: FnOnce(&S) -> Result<(), ValidationError> + Send + 'static,
+    {
+        let sink = Arc::clone(&self.inner);
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || op(&sink))
+                .await
+                .map_err(|e| ValidationError::SinkError(format!("Sink task failed: {}", e)))?
+        })
+    }
+}
+
+impl<S: DriftSink + 'static> AsyncDriftSink for BlockingSink<S> {
+    fn record(&self, event: DriftEvent) -> SinkFuture<'_> {
+        self.run(move |sink| sink.record(event))
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        self.run(|sink| sink.flush())
+    }
+
+    fn record_all(&self, events: Vec<DriftEvent>) -> SinkFuture<'_> {
+        // One hop to the blocking pool for the whole batch
+        self.run(move |sink| sink.record_all(events))
+    }
+}
+
+/// Writes each drift event as one JSON line to any tokio writer
+/// (a `tokio::fs::File`, a socket, ...)
+#[derive(Debug)]
+pub struct AsyncJsonlSink<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncJsonlSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send> AsyncDriftSink for AsyncJsonlSink<W> {
+    fn record(&self, event: DriftEvent) -> SinkFuture<'_> {
+        Box::pin(async move {
+            let mut line = to_json_line(&event)?;
+            line.push('\n');
+            self.writer
+                .lock()
+                .await
+                .write_all(line.as_bytes())
+                .await
+                .map_err(|e| ValidationError::SinkError(format!("Failed to write drift event: {}", e)))
+        })
+    }
+
+    fn flush(&self) -> SinkFuture<'_> {
+        Box::pin(async move {
+            self.writer
+                .lock()
+                .await
+                .flush()
+                .await
+                .map_err(|e| ValidationError::SinkError(format!("Failed to flush drift events: {}", e)))
+        })
+    }
+}
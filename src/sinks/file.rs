@@ -0,0 +1,143 @@
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::sinks::{to_json_line, DriftSink};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Appends drift events as JSON lines to a file, rotating it once it grows past a size limit
+///
+/// Rotated files are renamed `<path>.1`, `<path>.2`, ... with `.1` the most
+/// recent; files beyond `max_files` are deleted.
+#[derive(Debug)]
+pub struct RotatingFileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    state: Mutex<FileState>,
+}
+
+#[derive(Debug)]
+struct FileState {
+    writer: BufWriter<File>,
+    written: u64,
+}
+
+impl RotatingFileSink {
+    /// Opens (or creates) the active log file at `path`
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Result<Self, ValidationError> {
+        let path = path.into();
+        let (file, written) = open_append(&path)?;
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            state: Mutex::new(FileState {
+                writer: BufWriter::new(file),
+                written,
+            }),
+        })
+    }
+
+    /// Path of the file currently being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self, state: &mut FileState) -> Result<(), ValidationError> {
+        state.writer.flush().map_err(io_error)?;
+
+        if self.max_files == 0 {
+            fs::remove_file(&self.path).map_err(io_error)?;
+        } else {
+            let oldest = self.rotated_path(self.max_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest).map_err(io_error)?;
+            }
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1)).map_err(io_error)?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1)).map_err(io_error)?;
+        }
+
+        let (file, written) = open_append(&self.path)?;
+        state.writer = BufWriter::new(file);
+        state.written = written;
+        Ok(())
+    }
+}
+
+impl DriftSink for RotatingFileSink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        let line = to_json_line(&event)?;
+        let line_len = line.len() as u64 + 1;
+
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if state.written > 0 && state.written + line_len > self.max_bytes {
+            self.rotate(&mut state)?;
+        }
+        writeln!(state.writer, "{}", line).map_err(io_error)?;
+        state.written += line_len;
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.writer.flush().map_err(io_error)
+    }
+}
+
+impl Drop for RotatingFileSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Opens a file for appending and reports its current size
+fn open_append(path: &Path) -> Result<(File, u64), ValidationError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| ValidationError::SinkError(format!("Failed to open {}: {}", path.display(), e)))?;
+    let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok((file, written))
+}
+
+fn io_error(e: std::io::Error) -> ValidationError {
+    ValidationError::SinkError(format!("Drift log I/O failed: {}", e))
+}
+
+/// Reads drift events back from a JSON lines file written by a sink
+///
+/// Blank lines are skipped; a line that fails to parse is an error.
+pub fn read_events(path: &Path) -> Result<Vec<DriftEvent>, ValidationError> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        ValidationError::SinkError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            DriftEvent::from_json(line).map_err(|e| {
+                ValidationError::SinkError(format!(
+                    "Failed to parse {} line {}: {}",
+                    path.display(),
+                    index + 1,
+                    e
+                ))
+            })
+        })
+        .collect()
+}
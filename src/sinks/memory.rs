@@ -0,0 +1,46 @@
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::sinks::DriftSink;
+use std::sync::{Mutex, MutexGuard};
+
+/// Collects drift events in memory, mainly for embedders and tests
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<DriftEvent>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of all events recorded so far
+    pub fn events(&self) -> Vec<DriftEvent> {
+        self.lock().clone()
+    }
+
+    /// Removes and returns all events recorded so far
+    pub fn take(&self) -> Vec<DriftEvent> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// A poisoned lock only means another thread panicked mid-push; the events are still usable
+    fn lock(&self) -> MutexGuard<'_, Vec<DriftEvent>> {
+        self.events.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl DriftSink for MemorySink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        self.lock().push(event);
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(feature = "async")]
+pub mod async_sink;
+pub mod file;
+pub mod memory;
+pub mod stdout;
+
+#[cfg(feature = "async")]
+pub use async_sink::{AsyncDriftSink, AsyncJsonlSink, BlockingSink, SinkFuture};
+pub use file::{read_events, RotatingFileSink};
+pub use memory::MemorySink;
+pub use stdout::StdoutJsonlSink;
+
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use std::sync::Arc;
+
+/// Destination for drift events produced by validation
+///
+/// Sinks take `&self` so a single instance can be shared across worker
+/// threads; implementations are expected to synchronize internally.
+pub trait DriftSink: Send + Sync {
+    /// Records a single drift event
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError>;
+
+    /// Flushes any buffered events
+    fn flush(&self) -> Result<(), ValidationError> {
+        Ok(())
+    }
+
+    /// Records every event in order, stopping at the first failure
+    fn record_all(&self, events: Vec<DriftEvent>) -> Result<(), ValidationError> {
+        events.into_iter().try_for_each(|event| self.record(event))
+    }
+
+    /// Notes one exchange validated against `operation` (e.g. `GET /users`)
+    /// and the drift found in it, possibly none
+    ///
+    /// Lets sinks relate drift to traffic volume; the events themselves
+    /// still arrive through [`DriftSink::record`]. Does nothing by default.
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        let _ = (operation, timestamp_ms, events);
+    }
+}
+
+impl<S: DriftSink + ?Sized> DriftSink for Box<S> {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        (**self).record(event)
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        (**self).flush()
+    }
+
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        (**self).observe_exchange(operation, timestamp_ms, events)
+    }
+}
+
+impl<S: DriftSink + ?Sized> DriftSink for Arc<S> {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        (**self).record(event)
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        (**self).flush()
+    }
+
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        (**self).observe_exchange(operation, timestamp_ms, events)
+    }
+}
+
+/// Sends every event to each sink in turn, stopping at the first failure
+impl<S: DriftSink> DriftSink for Vec<S> {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        self.iter().try_for_each(|sink| sink.record(event.clone()))
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        self.iter().try_for_each(|sink| sink.flush())
+    }
+
+    fn observe_exchange(&self, operation: &str, timestamp_ms: u64, events: &[DriftEvent]) {
+        self.iter()
+            .for_each(|sink| sink.observe_exchange(operation, timestamp_ms, events));
+    }
+}
+
+/// Serializes an event as a single JSON line (without the trailing newline)
+pub(crate) fn to_json_line(event: &DriftEvent) -> Result<String, ValidationError> {
+    serde_json::to_string(event)
+        .map_err(|e| ValidationError::SinkError(format!("Failed to serialize drift event: {}", e)))
+}
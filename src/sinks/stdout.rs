@@ -0,0 +1,28 @@
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::sinks::{to_json_line, DriftSink};
+use std::io::{stdout, Write};
+
+/// Writes each drift event to stdout as one JSON object per line
+#[derive(Debug, Default)]
+pub struct StdoutJsonlSink;
+
+impl StdoutJsonlSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl DriftSink for StdoutJsonlSink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        let line = to_json_line(&event)?;
+        writeln!(stdout().lock(), "{}", line)
+            .map_err(|e| ValidationError::SinkError(format!("Failed to write to stdout: {}", e)))
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        stdout()
+            .flush()
+            .map_err(|e| ValidationError::SinkError(format!("Failed to flush stdout: {}", e)))
+    }
+}
@@ -0,0 +1,178 @@
+use crate::api_validator::HttpMethod;
+use crate::error::ValidationError;
+#[cfg(feature = "monitor")]
+use crate::spec::source_map::SpecSourceMap;
+use serde::Serialize;
+use std::fmt;
+
+/// What happened while building validators from a spec
+///
+/// Serialize it to JSON in CI to gate on the spec itself, e.g. requiring
+/// that every operation compiled.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BuildReport {
+    /// Operations declared in the spec, including skipped ones
+    pub operations_total: usize,
+    pub compiled: Vec<CompiledOperation>,
+    pub skipped: Vec<SkippedOperation>,
+    /// Operations that failed to build and were left out, with lenient builds
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedOperation>,
+    /// Spec features that were accepted but are not validated
+    pub unsupported: Vec<UnsupportedFeature>,
+    /// Path templates matched by a regex because the router can't represent them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback_routes: Vec<FallbackRoute>,
+    /// Path templates matching some of the same paths
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub route_overlaps: Vec<RouteOverlap>,
+    pub total_duration_ms: f64,
+}
+
+/// An operation that was compiled into a validator
+#[derive(Debug, Clone, Serialize)]
+pub struct CompiledOperation {
+    pub method: HttpMethod,
+    pub path_template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    pub duration_ms: f64,
+}
+
+/// An operation, or a whole path, that no validator was built for
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedOperation {
+    /// Unset when a whole path item was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<HttpMethod>,
+    pub path_template: String,
+    pub reason: String,
+}
+
+/// An operation whose validator failed to build, left out of a lenient build
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedOperation {
+    pub method: HttpMethod,
+    pub path_template: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    /// The error's [`code`](ValidationError::code), e.g. `UNRESOLVED_REFERENCE`
+    pub code: &'static str,
+    pub error: String,
+}
+
+impl FailedOperation {
+    pub(crate) fn new(
+        method: HttpMethod,
+        path_template: impl Into<String>,
+        operation_id: Option<String>,
+        error: &ValidationError,
+    ) -> Self {
+        Self {
+            method,
+            path_template: path_template.into(),
+            operation_id,
+            code: error.code(),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// A path template routed by regex, e.g. `/report/{name}.{format}`
+#[derive(Debug, Clone, Serialize)]
+pub struct FallbackRoute {
+    pub path_template: String,
+    /// Why the router can't represent it
+    pub reason: String,
+}
+
+/// Two path templates some paths match both of, e.g. `/users/me` and `/users/{id}`
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteOverlap {
+    /// The two templates, in spec order
+    pub templates: [OverlappingTemplate; 2],
+    /// The more concrete template, which paths matching both route to;
+    /// unset when ambiguous, in which case a lenient build skips the later one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routes_to: Option<String>,
+}
+
+/// Where an overlapping template is declared
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlappingTemplate {
+    pub path_template: String,
+    /// JSON pointer to the path item, e.g. `/paths/~1users~1{id}`
+    pub pointer: String,
+    /// Line of the path item in the spec file, when the report was located against it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl OverlappingTemplate {
+    pub(crate) fn new(path_template: &str) -> Self {
+        Self {
+            path_template: path_template.to_string(),
+            pointer: format!("/paths/{}", path_template.replace('~', "~0").replace('/', "~1")),
+            line: None,
+        }
+    }
+
+    /// Fills in the line of the path item from the file's source map
+    #[cfg(feature = "monitor")]
+    pub(crate) fn locate(&mut self, source_map: &SpecSourceMap) {
+        self.line = source_map.position(&self.pointer).map(|position| position.line);
+    }
+}
+
+impl fmt::Display for OverlappingTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "'{}' (line {})", self.path_template, line),
+            None => write!(f, "'{}' (at {})", self.path_template, self.pointer),
+        }
+    }
+}
+
+/// A spec construct the validators ignore
+#[derive(Debug, Clone, Serialize)]
+pub struct UnsupportedFeature {
+    /// Where it occurs (e.g. `GET /users/{id} response/2XX`)
+    pub location: String,
+    pub feature: String,
+}
+
+impl BuildReport {
+    /// Percentage of declared operations that compiled
+    pub fn compiled_percentage(&self) -> f64 {
+        if self.operations_total == 0 {
+            return 100.0;
+        }
+        self.compiled.len() as f64 * 100.0 / self.operations_total as f64
+    }
+
+    /// Whether every declared operation compiled
+    pub fn is_complete(&self) -> bool {
+        self.skipped.is_empty() && self.failed.is_empty() && self.compiled.len() == self.operations_total
+    }
+
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            ValidationError::ReportError(format!("Failed to serialize build report: {}", e))
+        })
+    }
+
+    /// Fills in the spec lines of overlapping templates from the file's source map
+    #[cfg(feature = "monitor")]
+    pub fn locate(&mut self, source_map: &SpecSourceMap) {
+        for overlap in &mut self.route_overlaps {
+            overlap.templates.iter_mut().for_each(|template| template.locate(source_map));
+        }
+    }
+
+    pub(crate) fn unsupported(&mut self, location: impl Into<String>, feature: impl Into<String>) {
+        self.unsupported.push(UnsupportedFeature {
+            location: location.into(),
+            feature: feature.into(),
+        });
+    }
+}
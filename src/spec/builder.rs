@@ -1,203 +1,1027 @@
 use crate::api_validator::{ApiValidator, HttpMethod, OperationValidator};
+use crate::array_sampling::ArraySampling;
 use crate::error::ValidationError;
+use crate::exchange_filter::ExchangeFilter;
+use crate::instrumentation::{self, ParentSpan, SpanTimer};
+use crate::ownership::Ownership;
+use crate::redaction::Redaction;
+use crate::routing::{overlapping_templates, Overlap, RoutingConfig};
+#[cfg(feature = "monitor")]
+use crate::spec::cache::load_openapi_spec_cached_with;
+#[cfg(feature = "monitor")]
+use crate::spec::loader::load_openapi_spec;
+#[cfg(feature = "monitor")]
+use crate::spec::source_map::SpecSourceMap;
+use crate::spec::build_report::{
+    BuildReport, CompiledOperation, FailedOperation, FallbackRoute, OverlappingTemplate, RouteOverlap, SkippedOperation,
+};
+use crate::spec::limits::SchemaLimits;
+use crate::spec::progress::BuildProgress;
+use crate::suppression::Suppressions;
 use crate::spec::reference_resolver::ResolveReference;
+use crate::spec::transform::{self, Direction};
+use crate::validation_helpers::{CompileSchema, LazyRegistry, SPEC_BASE_URI};
+use crate::validator_config::ValidatorConfig;
+use crate::validators::ParameterStyle;
 use jsonschema::{Registry, Resource};
 use openapiv3::OpenAPI;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde_json::{self, Value};
 use std::collections::HashMap;
-use std::io::{stdout, Write};
+#[cfg(feature = "monitor")]
+use std::path::Path;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
 
-/// Converts a schema reference to JSON Value
-fn schema_to_json(schema_ref: &impl serde::Serialize, context: &str) -> Result<Value, ValidationError> {
-    serde_json::to_value(schema_ref).map_err(|e| {
+/// Converts a schema reference to JSON Value, applying the enabled schema rewrites
+///
+/// With a `direction`, `readOnly`/`writeOnly` properties are only required
+/// on the side of the exchange they travel in.
+pub(crate) fn schema_to_json(
+    schema_ref: &impl serde::Serialize,
+    context: &str,
+    spec: &OpenAPI,
+    direction: Option<Direction>,
+    options: &BuildOptions,
+) -> Result<Value, ValidationError> {
+    let mut schema = serde_json::to_value(schema_ref).map_err(|e| {
         ValidationError::SchemaCompilationError(format!(
             "Failed to convert {} schema to JSON: {}",
             context, e
         ))
-    })
+    })?;
+    if options.validation.merge_all_of {
+        transform::merge_all_of(&mut schema, spec);
+    }
+    if let Some(direction) = direction {
+        transform::read_write_only(&mut schema, direction, spec);
+    }
+    transform::apply(&mut schema, options);
+    if options.validation.reports_unknown_fields() {
+        transform::close_objects(&mut schema, true);
+    }
+    Ok(schema)
 }
 
 /// Extracts JSON schema from application/json content
-fn extract_json_schema(
+pub(crate) fn extract_json_schema(
     content: &openapiv3::Content,
-    context: &str
+    context: &str,
+    spec: &OpenAPI,
+    direction: Option<Direction>,
+    options: &BuildOptions,
 ) -> Result<Value, ValidationError> {
     let media_type = content.get("application/json")
-        .ok_or_else(|| ValidationError::SchemaCompilationError(
-            format!("{} must have application/json content", context)
-        ))?;
+        .ok_or_else(|| ValidationError::UnsupportedFeature {
+            feature: format!("{} without application/json content", context),
+        })?;
     
     let schema_ref = media_type.schema.as_ref()
-        .ok_or_else(|| ValidationError::SchemaCompilationError(
-            format!("{} schema is missing", context)
-        ))?;
+        .ok_or_else(|| ValidationError::UnsupportedFeature {
+            feature: format!("{} without a schema", context),
+        })?;
     
-    schema_to_json(schema_ref, context)
+    schema_to_json(schema_ref, context, spec, direction, options)
 }
 
 /// Builds JSON Schema registry from OpenAPI components section
-fn build_registry(spec: &OpenAPI) -> Result<Registry, ValidationError> {
+pub(crate) fn build_registry(
+    spec: &OpenAPI,
+    direction: Option<Direction>,
+    options: &BuildOptions,
+) -> Result<Registry, ValidationError> {
+    registry_from_document(components_document(spec, direction, options)?)
+}
+
+/// Builds the registry `$ref`s resolve against from a [`components_document`]
+pub(crate) fn registry_from_document(document: Value) -> Result<Registry, ValidationError> {
+    let components_resource = Resource::from_contents(document)
+        .map_err(|e| ValidationError::SchemaCompilationError(format!("Failed to create resource: {}", e)))?;
+    
+    Registry::try_new(SPEC_BASE_URI, components_resource)
+        .map_err(|e| ValidationError::SchemaCompilationError(format!("Failed to create registry: {}", e)))
+}
+
+/// The spec's components wrapped so that `#/components/...` references resolve against it
+pub(crate) fn components_document(
+    spec: &OpenAPI,
+    direction: Option<Direction>,
+    options: &BuildOptions,
+) -> Result<Value, ValidationError> {
     let spec_json_val = serde_json::to_value(spec).map_err(|e| {
         ValidationError::SchemaCompilationError(format!("Failed to serialize spec to JSON: {}", e))
     })?;
     
-    let components_json = spec_json_val.get("components")
-        .ok_or_else(|| ValidationError::SchemaCompilationError("No components section in spec".to_string()))?
+    let mut components_json = spec_json_val.get("components")
+        .ok_or_else(|| ValidationError::SpecParse {
+            reason: "no components section in spec".to_string(),
+        })?
         .clone();
+
+    // Rewrites must also reach the schemas that `$ref`s resolve to
+    if let Some(Value::Object(schemas)) = components_json.get_mut("schemas") {
+        for schema in schemas.values_mut() {
+            if options.validation.merge_all_of {
+                transform::merge_all_of(schema, spec);
+            }
+            if let Some(direction) = direction {
+                transform::read_write_only(schema, direction, spec);
+            }
+            transform::apply(schema, options);
+            if options.validation.reports_unknown_fields() {
+                transform::close_objects(schema, false);
+            }
+        }
+    }
     
-    let wrapped_components = serde_json::json!({
+    Ok(serde_json::json!({
         "components": components_json
-    });
-    
-    let components_resource = Resource::from_contents(wrapped_components)
-        .map_err(|e| ValidationError::SchemaCompilationError(format!("Failed to create resource: {}", e)))?;
-    
-    Registry::try_new("urn:oas:spec", components_resource)
-        .map_err(|e| ValidationError::SchemaCompilationError(format!("Failed to create registry: {}", e)))
+    }))
+}
+
+/// Options controlling how validators are built from a spec
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Path templates treated as GraphQL endpoints, in addition to those marked `x-graphql: true`
+    pub graphql_paths: Vec<String>,
+    /// Check the `{data, errors}` envelope of GraphQL bodies instead of skipping them entirely
+    pub validate_graphql_envelope: bool,
+    /// Accept the protobuf JSON mapping produced by gRPC-JSON transcoding gateways
+    /// (int64 as strings, numeric enums, omitted default-valued fields)
+    pub protobuf_json_compat: bool,
+    /// Distinct raw query strings per operation whose coerced parameters are memoized (0 disables)
+    pub query_cache_capacity: usize,
+    /// Compile each schema on first use rather than at build time
+    ///
+    /// Startup becomes near-instant for huge specs, at the cost of schema
+    /// compilation errors going unreported until the schema is first used.
+    pub lazy_compilation: bool,
+    /// Directory caching parsed specs for [`build_api_validator_from_file`]
+    ///
+    /// Only parsing is skipped; schemas are still resolved and compiled on
+    /// every build (see [`load_openapi_spec_cached`](crate::spec::load_openapi_spec_cached)).
+    pub cache_dir: Option<PathBuf>,
+    /// Which items of large arrays in JSON bodies get validated
+    pub array_sampling: ArraySampling,
+    /// Exchanges skipped before validation, by method or response status
+    pub exchange_filter: ExchangeFilter,
+    /// Fields and headers masked in every drift event
+    pub redaction: Redaction,
+    /// How strictly traffic is held to the spec
+    pub validation: ValidatorConfig,
+    /// How request paths are matched to the spec's path templates
+    pub routing: RoutingConfig,
+    /// Validate the parseable prefix of bodies truncated by the capture source
+    /// (log pipelines often cut bodies at a few KB) rather than reporting them as malformed
+    pub truncated_captures: bool,
+    /// Report query parameters an operation doesn't declare, except those
+    /// matching these name patterns (e.g. [`crate::validators::TRACKING_QUERY_PARAMETERS`]);
+    /// `None` disables the check
+    pub undocumented_query_parameters: Option<Vec<String>>,
+    /// Accepted drift never reported, e.g. read from a `.driftignore` file
+    pub suppressions: Suppressions,
+    /// Who owns drift, attached to every event
+    pub ownership: Ownership,
+    /// Leave out operations that fail to build, listing them in
+    /// [`BuildReport::failed`], instead of failing the whole build
+    ///
+    /// Path templates ambiguous with an earlier one are likewise left out,
+    /// listed in [`BuildReport::skipped`]. Useful when monitoring specs you don't own, where one malformed
+    /// operation shouldn't leave the rest unmonitored.
+    pub lenient: bool,
+    /// Bounds on schema complexity, past which an operation fails the build or is skipped
+    pub limits: SchemaLimits,
+    /// Receives build progress and warnings; nothing is reported when unset
+    pub progress: Option<Arc<dyn BuildProgress>>,
+}
+
+impl BuildOptions {
+    fn warn(&self, message: &str) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("{}", message);
+        if let Some(progress) = &self.progress {
+            progress.warning(message);
+        }
+    }
 }
 
 /// Build an ApiValidator from a parsed OpenAPI specification
 pub fn build_api_validator(spec: &OpenAPI) -> Result<ApiValidator, ValidationError> {
-    let mut api_validator = ApiValidator::new();
-    let registry = build_registry(spec)?;
+    build_api_validator_with_options(spec, &BuildOptions::default())
+}
+
+/// Loads the spec at `path` and builds a validator for it
+///
+/// The parsed spec is reused from [`BuildOptions::cache_dir`] when set and
+/// the file hasn't changed; the validator itself is always built afresh.
+#[cfg(feature = "monitor")]
+pub fn build_api_validator_from_file(path: &Path, options: &BuildOptions) -> Result<ApiValidator, ValidationError> {
+    let spec = match &options.cache_dir {
+        Some(cache_dir) => load_openapi_spec_cached_with(path, cache_dir, options.progress.as_deref())?,
+        None => load_openapi_spec(path)?,
+    };
+    build_api_validator_with_options(&spec, options).map_err(|mut e| {
+        if let (ValidationError::AmbiguousRoutes { .. }, Ok(source_map)) = (&e, SpecSourceMap::load(path)) {
+            e.locate(&source_map);
+        }
+        e
+    })
+}
+
+/// Build an ApiValidator from a parsed OpenAPI specification with custom options
+pub fn build_api_validator_with_options(
+    spec: &OpenAPI,
+    options: &BuildOptions,
+) -> Result<ApiValidator, ValidationError> {
+    build_api_validator_with_report(spec, options).map(|(api_validator, _)| api_validator)
+}
+
+/// Build an ApiValidator and a report of what was compiled, skipped, and left unvalidated
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "build_api_validator",
+        skip_all,
+        fields(
+            operations = tracing::field::Empty,
+            compiled = tracing::field::Empty,
+            failed = tracing::field::Empty,
+            duration_us = tracing::field::Empty,
+        )
+    )
+)]
+pub fn build_api_validator_with_report(
+    spec: &OpenAPI,
+    options: &BuildOptions,
+) -> Result<(ApiValidator, BuildReport), ValidationError> {
+    let started = Instant::now();
+    let _timer = SpanTimer::start();
+    let mut report = BuildReport::default();
+    let mut api_validator = ApiValidator::new()
+        .with_exchange_filter(options.exchange_filter.clone())
+        .with_redaction(options.redaction.clone())
+        .with_drift_policy(options.validation.policy.clone())
+        .with_suppressions(options.suppressions.clone())
+        .with_ownership(options.ownership.clone())
+        .with_base_paths(options.routing.base_paths_for(spec))
+        .with_undeclared_base_paths_reported(options.routing.report_undeclared_base_paths)
+        .with_path_normalization(options.routing.normalization);
+    let registries = Registries {
+        request: directional_registry(spec, Direction::Request, options)?,
+        response: directional_registry(spec, Direction::Response, options)?,
+    };
 
     let total_operations: usize = spec.paths.paths.values()
         .filter_map(|path_item_ref| path_item_ref.as_item())
         .map(|path_item| path_item.iter().count())
         .sum();
 
+    report.operations_total = total_operations;
+    instrumentation::record_count("operations", total_operations as u64);
+
     if total_operations == 0 {
-        println!("--- ✅ No operations found to build. ---");
-        return Ok(api_validator);
+        report.total_duration_ms = elapsed_ms(started);
+        if let Some(progress) = &options.progress {
+            progress.finished(&report);
+        }
+        return Ok((api_validator, report));
     }
 
-    let mut completed_operations = 0;
+    let ambiguous = analyze_route_overlaps(spec, &mut report);
+    if !options.lenient {
+        if let Some(overlap) = report.route_overlaps.iter().find(|overlap| overlap.routes_to.is_none()) {
+            return Err(ValidationError::AmbiguousRoutes {
+                templates: Box::new(overlap.templates.clone()),
+            });
+        }
+    }
 
+    // Gather the operations up front so they can be compiled independently
+    let mut paths = Vec::new();
+    let mut jobs = Vec::new();
     for (path, path_item_ref) in &spec.paths.paths {
+        if let Some(earlier) = ambiguous.get(path.as_str()) {
+            let reason = format!("Ambiguous with {}: some paths match both and neither is more concrete", earlier);
+            options.warn(&format!("Skipping path {}. {}", path, reason));
+            report.skipped.push(SkippedOperation {
+                method: None,
+                path_template: path.clone(),
+                reason,
+            });
+            continue;
+        }
         let path_item = match path_item_ref {
             openapiv3::ReferenceOr::Item(item) => item,
             openapiv3::ReferenceOr::Reference { reference } => {
-                eprintln!("\nWARNING: Skipping path. Path references ($ref) are not yet supported: {}", reference);
+                options.warn(&format!("Skipping path. Path references ($ref) are not yet supported: {}", reference));
+                report.skipped.push(SkippedOperation {
+                    method: None,
+                    path_template: path.clone(),
+                    reason: format!("Path references ($ref) are not supported: {}", reference),
+                });
                 continue; 
             }
         };
 
-        // Collect all operations for this path into a HashMap
-        let mut operations_map = HashMap::new();
-        
+        let path_is_graphql = options.graphql_paths.iter().any(|p| p == path)
+            || is_graphql_extension(&path_item.extensions);
+
         for (method_str, operation) in path_item.iter() {
             let method = HttpMethod::from_str(method_str).map_err(|_| {
-                ValidationError::SchemaCompilationError(format!(
-                    "Unknown HTTP method: {}",
-                    method_str
-                ))
+                ValidationError::UnsupportedFeature {
+                    feature: format!("HTTP method {}", method_str),
+                }
             })?;
+            jobs.push(OperationJob {
+                path_index: paths.len(),
+                method,
+                operation,
+                graphql: path_is_graphql || is_graphql_extension(&operation.extensions),
+            });
+        }
+        paths.push(path);
+    }
 
-            let validator = build_operation_validator(spec, &registry, operation)?;
-            operations_map.insert(method, validator);
-            
-            completed_operations += 1;
-            let percentage = (completed_operations as f64 / total_operations as f64) * 100.0;
-            print!(
-                "\r--- 🛠️ Building API Validator: {:.0}% complete ({}/{}) ---",
-                percentage, completed_operations, total_operations
-            );
-            stdout().flush().unwrap_or(()); 
+    // Schemas are measured in their serialized form, `$ref`s resolving against the whole spec
+    let document = if options.limits.checks_schemas() {
+        Some(serde_json::to_value(spec).map_err(|e| {
+            ValidationError::SchemaCompilationError(format!("Failed to serialize spec to JSON: {}", e))
+        })?)
+    } else {
+        None
+    };
+
+    let completed = AtomicUsize::new(0);
+    // Operations compile on the thread pool, outside the build's span
+    let parent = ParentSpan::current();
+    #[cfg(feature = "parallel")]
+    let jobs_iter = jobs.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let jobs_iter = jobs.iter();
+    let compiled = jobs_iter
+        .map(|job| {
+            let path = paths[job.path_index];
+            let operation_started = Instant::now();
+            let label = format!("{} {}", job.method.as_str(), path);
+            let outcome = parent.in_scope(|| compile_operation(spec, &registries, job, document.as_ref(), options, &label));
+            let completed = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(progress) = &options.progress {
+                progress.operation_compiled(completed, total_operations);
+            }
+            match outcome {
+                Ok((validator, notes)) => {
+                    let compiled = CompiledOperation {
+                        method: job.method,
+                        path_template: path.clone(),
+                        operation_id: job.operation.operation_id.clone(),
+                        duration_ms: elapsed_ms(operation_started),
+                    };
+                    Ok(OperationOutcome::Compiled(Box::new(validator), compiled, notes))
+                }
+                Err(ValidationError::SchemaTooComplex { reason, .. }) if options.limits.skip_too_complex => {
+                    options.warn(&format!("Skipping {}. Schema too complex: {}", label, reason));
+                    Ok(OperationOutcome::Skipped(SkippedOperation {
+                        method: Some(job.method),
+                        path_template: path.clone(),
+                        reason: format!("Schema too complex: {}", reason),
+                    }))
+                }
+                Err(e) if options.lenient => {
+                    options.warn(&format!("Skipping {}. {}", label, e));
+                    let failed = FailedOperation::new(job.method, path, job.operation.operation_id.clone(), &e);
+                    Ok(OperationOutcome::Failed(failed))
+                }
+                Err(e) => Err(e),
+            }
+        })
+        .collect::<Result<Vec<_>, ValidationError>>()?;
+
+    let mut operations_by_path: Vec<HashMap<HttpMethod, OperationValidator>> =
+        paths.iter().map(|_| HashMap::new()).collect();
+    for (job, outcome) in jobs.iter().zip(compiled) {
+        match outcome {
+            OperationOutcome::Compiled(validator, compiled, notes) => {
+                operations_by_path[job.path_index].insert(job.method, *validator);
+                report.compiled.push(compiled);
+                report.unsupported.extend(notes.unsupported);
+            }
+            OperationOutcome::Skipped(skipped) => report.skipped.push(skipped),
+            OperationOutcome::Failed(failed) => report.failed.push(failed),
         }
-        
-        // Insert all operations for this path at once
-        api_validator.add_path_operations(path, operations_map)?;
     }
 
-    println!();
-    println!("--- ✅ Build Complete ---");
-    Ok(api_validator)
+    // Insert all operations for each path at once
+    for (path, operations_map) in paths.into_iter().zip(operations_by_path) {
+        let added = match api_validator.add_path_operations(path, operations_map) {
+            Err(e) if options.lenient => {
+                options.warn(&format!("Skipping path {}. {}", path, e));
+                // The path's operations compiled but can't be routed to
+                let (unroutable, compiled) = std::mem::take(&mut report.compiled)
+                    .into_iter()
+                    .partition(|compiled| &compiled.path_template == path);
+                report.compiled = compiled;
+                report.failed.extend(unroutable.into_iter().map(|compiled: CompiledOperation| {
+                    FailedOperation::new(compiled.method, path.as_str(), compiled.operation_id, &e)
+                }));
+                continue;
+            }
+            added => added?,
+        };
+        if let Some(reason) = added {
+            options.warn(&format!("{} is matched by regex: {}", path, reason));
+            report.fallback_routes.push(FallbackRoute {
+                path_template: path.to_string(),
+                reason,
+            });
+        }
+    }
+
+    report.total_duration_ms = elapsed_ms(started);
+    instrumentation::record_count("compiled", report.compiled.len() as u64);
+    instrumentation::record_count("failed", report.failed.len() as u64);
+    if let Some(progress) = &options.progress {
+        progress.finished(&report);
+    }
+    Ok((api_validator, report))
+}
+
+/// Records the spec's overlapping path templates, returning the ambiguous
+/// ones a lenient build skips, each mapped to the earlier template it is
+/// ambiguous with
+///
+/// Overlaps one template is more concrete in route to it, so `/users/me`
+/// and `/users/{id}` are both kept.
+fn analyze_route_overlaps<'a>(spec: &'a OpenAPI, report: &mut BuildReport) -> HashMap<&'a str, &'a str> {
+    let templates: Vec<&str> = spec
+        .paths
+        .paths
+        .iter()
+        .filter(|(_, path_item)| path_item.as_item().is_some())
+        .map(|(path, _)| path.as_str())
+        .collect();
+    let overlaps = overlapping_templates(&templates);
+    // Overlaps are listed earlier template first, so whether the earlier
+    // one is itself skipped is known by the time it is compared
+    let mut ambiguous = HashMap::new();
+    for (earlier, later, overlap) in &overlaps {
+        if *overlap == Overlap::Ambiguous && !ambiguous.contains_key(earlier) {
+            ambiguous.entry(*later).or_insert(*earlier);
+        }
+    }
+    for (earlier, later, overlap) in overlaps {
+        let routes_to = match overlap {
+            Overlap::Shadows => earlier,
+            Overlap::ShadowedBy => later,
+            Overlap::Ambiguous if ambiguous.get(later) == Some(&earlier) => {
+                report.route_overlaps.push(RouteOverlap {
+                    templates: [OverlappingTemplate::new(earlier), OverlappingTemplate::new(later)],
+                    routes_to: None,
+                });
+                continue;
+            }
+            Overlap::Ambiguous => continue,
+        };
+        // Overlaps with a skipped template are moot
+        if ambiguous.contains_key(earlier) || ambiguous.contains_key(later) {
+            continue;
+        }
+        report.route_overlaps.push(RouteOverlap {
+            templates: [OverlappingTemplate::new(earlier), OverlappingTemplate::new(later)],
+            routes_to: Some(routes_to.to_string()),
+        });
+    }
+    ambiguous
+}
+
+/// What became of an operation once its job ran
+enum OperationOutcome {
+    Compiled(Box<OperationValidator>, CompiledOperation, BuildReport),
+    /// Over the schema limits, with [`SchemaLimits::skip_too_complex`]
+    Skipped(SkippedOperation),
+    /// Failed to build, with [`BuildOptions::lenient`]
+    Failed(FailedOperation),
+}
+
+/// An operation to compile, referring back to its path by index
+struct OperationJob<'a> {
+    path_index: usize,
+    method: HttpMethod,
+    operation: &'a openapiv3::Operation,
+    graphql: bool,
+}
+
+/// Builds the validator for one operation within the schema limits, along
+/// with the unsupported features it noted
+///
+/// Each operation notes unsupported features in its own report, merged in
+/// spec order by the caller.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(operation = %label, duration_us = tracing::field::Empty)
+    )
+)]
+fn compile_operation(
+    spec: &OpenAPI,
+    registries: &Registries,
+    job: &OperationJob,
+    document: Option<&Value>,
+    options: &BuildOptions,
+    label: &str,
+) -> Result<(OperationValidator, BuildReport), ValidationError> {
+    let started = Instant::now();
+    let _timer = SpanTimer::start();
+    if let Some(document) = document {
+        let operation = serde_json::to_value(job.operation).map_err(|e| {
+            ValidationError::SchemaCompilationError(format!("Failed to serialize {} to JSON: {}", label, e))
+        })?;
+        options.limits.check_operation(document, &operation, label)?;
+    }
+    let graphql = if job.graphql {
+        Some(build_graphql_validator(options)?)
+    } else {
+        None
+    };
+    let mut notes = BuildReport::default();
+    let validator = build_operation_validator(spec, registries, job.operation, graphql, options, &mut notes, label)?;
+    options.limits.check_compile_time(started.elapsed(), label)?;
+    Ok((validator, notes))
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Whether an `x-graphql: true` extension marks the path or operation as GraphQL
+fn is_graphql_extension(extensions: &indexmap::IndexMap<String, Value>) -> bool {
+    extensions.get("x-graphql").and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// Build a GraphQlValidator according to the envelope option
+fn build_graphql_validator(
+    options: &BuildOptions,
+) -> Result<crate::validators::GraphQlValidator, ValidationError> {
+    if options.validate_graphql_envelope {
+        crate::validators::GraphQlValidator::with_envelope()
+    } else {
+        Ok(crate::validators::GraphQlValidator::passthrough())
+    }
+}
+
+/// Registries `$ref`s resolve against, one per side of the exchange
+///
+/// Component schemas differ between the two only in which `readOnly` and
+/// `writeOnly` properties are required.
+struct Registries {
+    request: Box<dyn CompileSchema>,
+    response: Box<dyn CompileSchema>,
+}
+
+fn directional_registry(
+    spec: &OpenAPI,
+    direction: Direction,
+    options: &BuildOptions,
+) -> Result<Box<dyn CompileSchema>, ValidationError> {
+    let registry = build_registry(spec, Some(direction), options)?;
+    Ok(if options.lazy_compilation {
+        let lazy = LazyRegistry::new(registry);
+        Box::new(match &options.progress {
+            Some(progress) => lazy.with_progress(Arc::clone(progress)),
+            None => lazy,
+        })
+    } else {
+        Box::new(registry)
+    })
 }
 
 /// Build an OperationValidator from an OpenAPI operation
+///
+/// GraphQL operations skip body schema compilation: their bodies are
+/// checked by `graphql` instead, while every other check applies as usual.
 fn build_operation_validator(
     spec: &OpenAPI,
-    registry: &Registry,
+    registries: &Registries,
     operation: &openapiv3::Operation,
+    graphql: Option<crate::validators::GraphQlValidator>,
+    options: &BuildOptions,
+    report: &mut BuildReport,
+    label: &str,
 ) -> Result<OperationValidator, ValidationError> {
     let parameters_validator =
-        build_parameters_validator(spec, registry, &operation.parameters)?;
+        build_parameters_validator(spec, registries.request.as_ref(), &operation.parameters, options, report, label)?;
+
+    let security_validator = build_security_validator(spec, operation)?;
 
-    let request_body_validator = if let Some(request_body) = &operation.request_body {
-        Some(build_request_body_validator(
+    let compile_bodies = graphql.is_none();
+    let request_body_validator = match &operation.request_body {
+        Some(request_body) if compile_bodies => Some(build_request_body_validator(
             spec,
-            registry,
+            registries.request.as_ref(),
             request_body,
-        )?)
-    } else {
-        None
+            options,
+        )?),
+        _ => None,
     };
 
-    let response_validator =
-        build_response_validator(spec, registry, &operation.responses)?;
+    let response_validator = build_response_validator(
+        spec,
+        registries.response.as_ref(),
+        &operation.responses,
+        compile_bodies,
+        options,
+        report,
+        label,
+    )?;
 
-    Ok(OperationValidator::new(
+    let operation_validator = OperationValidator::new(
         request_body_validator,
         response_validator,
         parameters_validator,
-    ))
+    )
+    .with_operation_id(operation.operation_id.clone())
+    .with_metadata(operation.tags.clone(), operation.summary.clone())
+    .with_array_sampling(options.array_sampling.clone())
+    .with_truncated_captures(options.truncated_captures)
+    .with_max_body_bytes(options.validation.max_body_bytes)
+    .with_oversized_body_depth(options.validation.oversized_body_depth)
+    .with_max_decompressed_bytes(options.validation.max_decompressed_bytes)
+    .with_content_types(options.validation.content_types.clone());
+
+    let operation_validator = match graphql {
+        Some(graphql) => operation_validator.with_graphql(graphql),
+        None => operation_validator,
+    };
+
+    let rate_limit_validator = build_rate_limit_validator(spec, &operation.responses)?;
+    let operation_validator = if rate_limit_validator.is_empty() {
+        operation_validator
+    } else {
+        operation_validator.with_rate_limits(rate_limit_validator)
+    };
+
+    let operation_validator = match security_validator {
+        Some(security_validator) => operation_validator.with_security(security_validator),
+        None => operation_validator,
+    };
+
+    let deprecation_validator = build_deprecation_validator(spec, operation)?;
+    let operation_validator = if deprecation_validator.is_empty() {
+        operation_validator
+    } else {
+        operation_validator.with_deprecation(deprecation_validator)
+    };
+
+    match build_idempotency_validator(spec, &operation.parameters)? {
+        Some(idempotency_validator) => Ok(operation_validator.with_idempotency(idempotency_validator)),
+        None => Ok(operation_validator),
+    }
+}
+
+/// Build a SecurityValidator from the operation's `security`, falling back to the spec-wide one
+///
+/// Returns `None` when the operation doesn't require credentials.
+fn build_security_validator(
+    spec: &OpenAPI,
+    operation: &openapiv3::Operation,
+) -> Result<Option<crate::validators::SecurityValidator>, ValidationError> {
+    use crate::validators::Credential;
+
+    let Some(requirements) = operation.security.as_ref().or(spec.security.as_ref()) else {
+        return Ok(None);
+    };
+    let schemes = spec.components.as_ref().map(|c| &c.security_schemes);
+
+    let mut alternatives = Vec::with_capacity(requirements.len());
+    for requirement in requirements {
+        let mut credentials = Vec::with_capacity(requirement.len());
+        for (scheme_name, scopes) in requirement {
+            let scheme = schemes
+                .and_then(|schemes| schemes.get(scheme_name))
+                .ok_or_else(|| {
+                    ValidationError::UnresolvedReference {
+                        reference: format!("#/components/securitySchemes/{}", scheme_name),
+                    }
+                })?
+                .resolve(spec)?;
+            credentials.push(match scheme {
+                openapiv3::SecurityScheme::APIKey { location, name, .. } => match location {
+                    openapiv3::APIKeyLocation::Header => Credential::HeaderApiKey(name.clone()),
+                    openapiv3::APIKeyLocation::Query => Credential::QueryApiKey(name.clone()),
+                    openapiv3::APIKeyLocation::Cookie => Credential::CookieApiKey(name.clone()),
+                },
+                openapiv3::SecurityScheme::HTTP { scheme, .. } => Credential::Authorization {
+                    scheme: scheme.clone(),
+                    scopes: Vec::new(),
+                },
+                openapiv3::SecurityScheme::OAuth2 { .. } | openapiv3::SecurityScheme::OpenIDConnect { .. } => {
+                    Credential::Authorization {
+                        scheme: "Bearer".to_string(),
+                        scopes: scopes.clone(),
+                    }
+                }
+            });
+        }
+        alternatives.push(credentials);
+    }
+
+    let security_validator = crate::validators::SecurityValidator::new(alternatives);
+    Ok((!security_validator.is_empty()).then_some(security_validator))
+}
+
+/// Build a DeprecationValidator from the operation's `deprecated` flags and
+/// those of its parameters and request body fields
+fn build_deprecation_validator(
+    spec: &OpenAPI,
+    operation: &openapiv3::Operation,
+) -> Result<crate::validators::DeprecationValidator, ValidationError> {
+    use crate::validators::DeprecatedParameterLocation;
+
+    let mut deprecation = crate::validators::DeprecationValidator::new().with_deprecated_operation(operation.deprecated);
+    for parameter_ref in &operation.parameters {
+        let (location, parameter_data) = match parameter_ref.resolve(spec)? {
+            openapiv3::Parameter::Path { parameter_data, .. } => (DeprecatedParameterLocation::Path, parameter_data),
+            openapiv3::Parameter::Query { parameter_data, .. } => (DeprecatedParameterLocation::Query, parameter_data),
+            openapiv3::Parameter::Header { parameter_data, .. } => (DeprecatedParameterLocation::Header, parameter_data),
+            openapiv3::Parameter::Cookie { parameter_data, .. } => (DeprecatedParameterLocation::Cookie, parameter_data),
+        };
+        if parameter_data.deprecated == Some(true) {
+            deprecation.add_parameter(location, parameter_data.name.clone());
+        }
+    }
+
+    if let Some(request_body_ref) = &operation.request_body {
+        for media_type in request_body_ref.resolve(spec)?.content.values() {
+            if let Some(schema) = media_type.schema.as_ref().and_then(|schema| schema_of(spec, schema)) {
+                collect_deprecated_fields(spec, schema, &mut Vec::new(), 0, &mut deprecation);
+            }
+        }
+    }
+    Ok(deprecation)
+}
+
+/// Schemas followed through properties, items and compositions before giving up, bounding recursive schemas
+const DEPRECATED_FIELD_DEPTH: usize = 32;
+
+/// Adds every field of `schema` marked deprecated, found at `path` below the body's root
+fn collect_deprecated_fields(
+    spec: &OpenAPI,
+    schema: &openapiv3::Schema,
+    path: &mut Vec<String>,
+    depth: usize,
+    deprecation: &mut crate::validators::DeprecationValidator,
+) {
+    use openapiv3::{SchemaKind, Type};
+
+    if depth > DEPRECATED_FIELD_DEPTH {
+        return;
+    }
+    if schema.schema_data.deprecated && !path.is_empty() {
+        deprecation.add_field(path.clone());
+    }
+
+    let mut visit_child = |segment: &str, child: Option<&openapiv3::Schema>, path: &mut Vec<String>| {
+        if let Some(child) = child {
+            path.push(segment.to_string());
+            collect_deprecated_fields(spec, child, path, depth + 1, deprecation);
+            path.pop();
+        }
+    };
+    let (properties, items, compositions) = match &schema.schema_kind {
+        SchemaKind::Type(Type::Object(object)) => (Some(&object.properties), None, &[][..]),
+        SchemaKind::Type(Type::Array(array)) => (None, array.items.as_ref(), &[][..]),
+        SchemaKind::OneOf { one_of: members }
+        | SchemaKind::AllOf { all_of: members }
+        | SchemaKind::AnyOf { any_of: members } => (None, None, members.as_slice()),
+        SchemaKind::Any(any) => (Some(&any.properties), any.items.as_ref(), &[][..]),
+        _ => (None, None, &[][..]),
+    };
+    for (name, property) in properties.into_iter().flatten() {
+        visit_child(name, schema_of(spec, property), path);
+    }
+    if let Some(items) = items {
+        visit_child("*", schema_of(spec, items), path);
+    }
+    // Composition members describe the same value
+    for member in compositions {
+        if let Some(member) = schema_of(spec, member) {
+            collect_deprecated_fields(spec, member, path, depth + 1, deprecation);
+        }
+    }
+    if let SchemaKind::Any(any) = &schema.schema_kind {
+        for member in any.all_of.iter().chain(&any.one_of).chain(&any.any_of) {
+            if let Some(member) = schema_of(spec, member) {
+                collect_deprecated_fields(spec, member, path, depth + 1, deprecation);
+            }
+        }
+    }
+}
+
+/// A schema, or the component schema it refers to
+fn schema_of<'a, T: std::borrow::Borrow<openapiv3::Schema>>(
+    spec: &'a OpenAPI,
+    schema: &'a openapiv3::ReferenceOr<T>,
+) -> Option<&'a openapiv3::Schema> {
+    match schema {
+        openapiv3::ReferenceOr::Item(schema) => Some(schema.borrow()),
+        openapiv3::ReferenceOr::Reference { reference } => {
+            let name = reference.strip_prefix("#/components/schemas/")?;
+            spec.components.as_ref()?.schemas.get(name)?.as_item()
+        }
+    }
+}
+
+/// Build an IdempotencyValidator if the operation documents an `Idempotency-Key` header
+fn build_idempotency_validator(
+    spec: &OpenAPI,
+    parameters: &[openapiv3::ReferenceOr<openapiv3::Parameter>],
+) -> Result<Option<crate::validators::IdempotencyValidator>, ValidationError> {
+    for parameter_ref in parameters {
+        if let openapiv3::Parameter::Header { parameter_data, .. } = parameter_ref.resolve(spec)? {
+            if parameter_data.name.eq_ignore_ascii_case("idempotency-key") {
+                return Ok(Some(crate::validators::IdempotencyValidator::new(
+                    parameter_data.name.clone(),
+                    parameter_data.required,
+                )));
+            }
+        }
+    }
+    Ok(None)
 }
 
 /// Build a RequestBodyValidator from an OpenAPI RequestBody
 fn build_request_body_validator(
     spec: &OpenAPI,
-    registry: &Registry,
+    registry: &dyn CompileSchema,
     request_body_ref: &openapiv3::ReferenceOr<openapiv3::RequestBody>,
+    options: &BuildOptions,
 ) -> Result<crate::validators::RequestBodyValidator, ValidationError> {
     let request_body = request_body_ref.resolve(spec)?;
-    let schema_json = extract_json_schema(&request_body.content, "request body")?;
+    let schema_json = extract_json_schema(&request_body.content, "request body", spec, Some(Direction::Request), options)?;
     let required = request_body.required;
 
     crate::validators::RequestBodyValidator::new(&schema_json, required, registry)
 }
 
 /// Build a ResponseValidator from OpenAPI Responses
+///
+/// Without `compile_bodies`, only the documented statuses and media types
+/// are recorded, for operations whose bodies are checked another way.
 fn build_response_validator(
     spec: &OpenAPI,
-    registry: &Registry,
+    registry: &dyn CompileSchema,
     responses: &openapiv3::Responses,
+    compile_bodies: bool,
+    options: &BuildOptions,
+    report: &mut BuildReport,
+    label: &str,
 ) -> Result<crate::validators::ResponseValidator, ValidationError> {
     let mut response_validator = crate::validators::ResponseValidator::new();
 
     for (status_code_str, response_ref) in &responses.responses {
         let status_code = match status_code_str {
             openapiv3::StatusCode::Code(code) => *code,
-            openapiv3::StatusCode::Range(_) => continue,
+            openapiv3::StatusCode::Range(class) => {
+                response_validator.document_range(*class);
+                let media_types = response_ref.resolve(spec)?.content.keys().cloned().collect();
+                response_validator.declare_range_media_types(*class, media_types);
+                report.unsupported(
+                    format!("{} response/{}XX", label, class),
+                    "Bodies of status code range responses are not validated",
+                );
+                continue;
+            }
         };
+        response_validator.document_status(status_code);
 
         let response = response_ref.resolve(spec)?;
+        response_validator.declare_media_types(status_code, response.content.keys().cloned().collect());
+        if !compile_bodies {
+            continue;
+        }
+        note_unvalidated_media_types(report, &format!("{} response/{}", label, status_code), &response.content);
 
         if !response.content.is_empty() {
-            if let Ok(schema_json) = extract_json_schema(&response.content, "response") {
+            if let Ok(schema_json) = extract_json_schema(&response.content, "response", spec, Some(Direction::Response), options) {
                 response_validator.add_response(status_code, &schema_json, registry)?;
             }
         }
+
+        if let Some(stream) = build_event_stream_validator(spec, registry, &response.content, "event stream", options)? {
+            response_validator.add_event_stream(status_code, stream);
+        }
     }
 
     if let Some(default_response_ref) = &responses.default {
+        response_validator.document_default();
         let default_response = default_response_ref.resolve(spec)?;
+        response_validator.set_default_media_types(default_response.content.keys().cloned().collect());
+        if !compile_bodies {
+            return Ok(response_validator);
+        }
+        note_unvalidated_media_types(report, &format!("{} response/default", label), &default_response.content);
 
         if !default_response.content.is_empty() {
-            if let Ok(schema_json) = extract_json_schema(&default_response.content, "default response") {
+            if let Ok(schema_json) = extract_json_schema(&default_response.content, "default response", spec, Some(Direction::Response), options) {
                 response_validator.set_default(&schema_json, registry)?;
             }
         }
+
+        if let Some(stream) =
+            build_event_stream_validator(spec, registry, &default_response.content, "default event stream", options)?
+        {
+            response_validator.set_default_event_stream(stream);
+        }
     }
 
     Ok(response_validator)
 }
 
+/// Records response media types that have no validator
+fn note_unvalidated_media_types(report: &mut BuildReport, location: &str, content: &openapiv3::Content) {
+    let unvalidated: Vec<&str> = content
+        .keys()
+        .map(String::as_str)
+        .filter(|media_type| *media_type != "application/json" && *media_type != "text/event-stream")
+        .collect();
+    if !unvalidated.is_empty() {
+        report.unsupported(
+            location,
+            format!("Media types are not validated: {}", unvalidated.join(", ")),
+        );
+    }
+}
+
+/// Build an EventStreamValidator if the content documents `text/event-stream`
+///
+/// The media type's schema, when present, describes a single event's data.
+fn build_event_stream_validator(
+    spec: &OpenAPI,
+    registry: &dyn CompileSchema,
+    content: &openapiv3::Content,
+    context: &str,
+    options: &BuildOptions,
+) -> Result<Option<crate::validators::EventStreamValidator>, ValidationError> {
+    let Some(media_type) = content.get("text/event-stream") else {
+        return Ok(None);
+    };
+
+    match &media_type.schema {
+        Some(schema_ref) => {
+            let schema_json = schema_to_json(schema_ref, context, spec, Some(Direction::Response), options)?;
+            crate::validators::EventStreamValidator::with_item_schema(&schema_json, registry, context)
+                .map(Some)
+        }
+        None => Ok(Some(crate::validators::EventStreamValidator::exempt())),
+    }
+}
+
+/// Build a RateLimitValidator from the response headers documented on an operation
+fn build_rate_limit_validator(
+    spec: &OpenAPI,
+    responses: &openapiv3::Responses,
+) -> Result<crate::validators::RateLimitValidator, ValidationError> {
+    let mut rate_limit_validator = crate::validators::RateLimitValidator::new();
+
+    for (status_code_str, response_ref) in &responses.responses {
+        let status_code = match status_code_str {
+            openapiv3::StatusCode::Code(code) => *code,
+            openapiv3::StatusCode::Range(_) => continue,
+        };
+
+        for (header_name, header_ref) in &response_ref.resolve(spec)?.headers {
+            // Surface dangling header references at build time
+            header_ref.resolve(spec)?;
+            rate_limit_validator.add_header(status_code, header_name);
+        }
+    }
+
+    if let Some(default_response_ref) = &responses.default {
+        for (header_name, header_ref) in &default_response_ref.resolve(spec)?.headers {
+            header_ref.resolve(spec)?;
+            rate_limit_validator.add_default_header(header_name);
+        }
+    }
+
+    Ok(rate_limit_validator)
+}
+
 /// Build a ParametersValidator from OpenAPI Parameters
 fn build_parameters_validator(
     spec: &OpenAPI,
-    registry: &Registry,
+    registry: &dyn CompileSchema,
     parameters: &[openapiv3::ReferenceOr<openapiv3::Parameter>],
+    options: &BuildOptions,
+    report: &mut BuildReport,
+    label: &str,
 ) -> Result<crate::validators::ParametersValidator, ValidationError> {
     let mut params_validator = crate::validators::ParametersValidator::new();
 
@@ -207,20 +1031,33 @@ fn build_parameters_validator(
         let parameter_data = match parameter {
             openapiv3::Parameter::Query { parameter_data, .. } 
             | openapiv3::Parameter::Path { parameter_data, .. } => parameter_data,
-            openapiv3::Parameter::Header { .. } | openapiv3::Parameter::Cookie { .. } => continue,
+            openapiv3::Parameter::Header { parameter_data, .. } => {
+                report.unsupported(
+                    format!("{} header/{}", label, parameter_data.name.to_ascii_lowercase()),
+                    "Header parameter schemas are not validated",
+                );
+                continue;
+            }
+            openapiv3::Parameter::Cookie { parameter_data, .. } => {
+                report.unsupported(
+                    format!("{} cookie/{}", label, parameter_data.name),
+                    "Cookie parameters are not validated",
+                );
+                continue;
+            }
         };
 
         let schema_ref = match &parameter_data.format {
             openapiv3::ParameterSchemaOrContent::Schema(s) => s,
-            _ => return Err(ValidationError::SchemaCompilationError(
-                "Content-based parameters not supported".to_string()
-            )),
+            _ => return Err(ValidationError::UnsupportedFeature {
+                feature: format!("content-based parameter '{}'", parameter_data.name),
+            }),
         };
 
         let name = parameter_data.name.clone();
         let required = parameter_data.required;
 
-        let schema_json = schema_to_json(schema_ref, "parameter")?;
+        let schema_json = schema_to_json(schema_ref, "parameter", spec, Some(Direction::Request), options)?;
 
         let param_validator = crate::validators::ParameterValidator::new(
             name,
@@ -230,12 +1067,31 @@ fn build_parameters_validator(
         )?;
 
         match parameter {
-            openapiv3::Parameter::Query { .. } => params_validator.add_query_parameter(param_validator),
+            openapiv3::Parameter::Query { style, .. } => {
+                let style = match style {
+                    openapiv3::QueryStyle::Form => ParameterStyle::Form,
+                    openapiv3::QueryStyle::SpaceDelimited => ParameterStyle::SpaceDelimited,
+                    openapiv3::QueryStyle::PipeDelimited => ParameterStyle::PipeDelimited,
+                    openapiv3::QueryStyle::DeepObject => ParameterStyle::DeepObject,
+                };
+                // Only `form` explodes by default
+                let explode = parameter_data.explode.unwrap_or(style == ParameterStyle::Form);
+                params_validator.add_query_parameter(param_validator.with_style(style, explode))
+            }
             openapiv3::Parameter::Header { .. } => params_validator.add_header_parameter(param_validator),
             openapiv3::Parameter::Path { .. } => params_validator.add_path_parameter(param_validator),
             openapiv3::Parameter::Cookie { .. } => {}
         }
     }
 
+    if let Some(capacity) = std::num::NonZeroUsize::new(options.query_cache_capacity) {
+        params_validator.enable_query_cache(capacity);
+    }
+    match &options.undocumented_query_parameters {
+        Some(allowlist) => params_validator.report_undocumented_query(allowlist.clone()),
+        None if options.validation.strict => params_validator.report_undocumented_query(Vec::new()),
+        None => {}
+    }
+
     Ok(params_validator)
 }
\ No newline at end of file
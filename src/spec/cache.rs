@@ -0,0 +1,110 @@
+use crate::error::ValidationError;
+use crate::spec::loader::{io_error, load_openapi_spec};
+use crate::spec::progress::BuildProgress;
+use openapiv3::OpenAPI;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the cached representation changes shape
+const CACHE_VERSION: u32 = 1;
+
+/// A parsed spec as stored in the cache directory
+#[derive(Serialize, Deserialize)]
+struct CachedSpec {
+    version: u32,
+    /// Hash of the spec file's bytes and the crate version that parsed it
+    spec_hash: String,
+    spec: OpenAPI,
+}
+
+/// Loads a spec, reusing its cached parsed form when the file hasn't changed
+///
+/// The parsed spec is stored as JSON in `cache_dir`, keyed by a hash of the
+/// file's contents, so restarts skip parsing the spec file, and only that.
+/// Neither the resolved schemas nor the route table are cached: every build
+/// still resolves `$ref`s, routes the path templates and compiles each
+/// schema, the bulk of the work for a large spec, as compiled schemas have
+/// no serialized form. Pair the cache with
+/// [`BuildOptions::lazy_compilation`](crate::spec::BuildOptions::lazy_compilation)
+/// to defer compilation instead. A missing, stale or unreadable cache entry
+/// falls back to parsing the spec and rewriting the entry.
+pub fn load_openapi_spec_cached(path: &Path, cache_dir: &Path) -> Result<OpenAPI, ValidationError> {
+    load_openapi_spec_cached_with(path, cache_dir, None)
+}
+
+/// [`load_openapi_spec_cached`], passing a failure to write the cache entry to `progress`
+pub(crate) fn load_openapi_spec_cached_with(
+    path: &Path,
+    cache_dir: &Path,
+    progress: Option<&dyn BuildProgress>,
+) -> Result<OpenAPI, ValidationError> {
+    let bytes = fs::read(path).map_err(|e| io_error(path, e))?;
+    let spec_hash = format!("{:016x}", spec_hash(&bytes));
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("spec");
+    let entry_path = cache_dir.join(format!("{}-{}.json", stem, spec_hash));
+
+    if let Some(spec) = read_entry(&entry_path, &spec_hash) {
+        return Ok(spec);
+    }
+
+    let spec = load_openapi_spec(path)?;
+    let entry = CachedSpec {
+        version: CACHE_VERSION,
+        spec_hash,
+        spec,
+    };
+    if let (Err(e), Some(progress)) = (write_entry(cache_dir, stem, &entry_path, &entry), progress) {
+        progress.warning(&format!("Failed to write spec cache {}: {}", entry_path.display(), e));
+    }
+    Ok(entry.spec)
+}
+
+fn read_entry(entry_path: &Path, spec_hash: &str) -> Option<OpenAPI> {
+    let contents = fs::read(entry_path).ok()?;
+    // Through a document first, as a damaged entry typed straight from its
+    // bytes can panic in `openapiv3`
+    let entry: CachedSpec = serde_json::from_slice(&contents).and_then(serde_json::from_value).ok()?;
+    (entry.version == CACHE_VERSION && entry.spec_hash == spec_hash).then_some(entry.spec)
+}
+
+/// Writes an entry, replacing the entries of earlier versions of the same spec
+fn write_entry(cache_dir: &Path, stem: &str, entry_path: &Path, entry: &CachedSpec) -> std::io::Result<()> {
+    fs::create_dir_all(cache_dir)?;
+    for stale in stale_entries(cache_dir, stem, entry_path)? {
+        let _ = fs::remove_file(stale);
+    }
+
+    // Write then rename so a concurrent reader never sees a partial entry
+    let partial = entry_path.with_extension("json.tmp");
+    fs::write(&partial, serde_json::to_vec(entry)?)?;
+    fs::rename(&partial, entry_path)
+}
+
+fn stale_entries(cache_dir: &Path, stem: &str, entry_path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let prefix = format!("{}-", stem);
+    Ok(fs::read_dir(cache_dir)?
+        .filter_map(Result::ok)
+        .map(|dir_entry| dir_entry.path())
+        .filter(|candidate| candidate != entry_path)
+        .filter(|candidate| {
+            candidate.extension().is_some_and(|ext| ext == "json")
+                && candidate
+                    .file_stem()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix(&prefix))
+                    .is_some_and(|hash| hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        })
+        .collect())
+}
+
+/// FNV-1a over the spec and crate version, stable across builds and platforms
+fn spec_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes
+        .iter()
+        .chain(env!("CARGO_PKG_VERSION").as_bytes())
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(PRIME))
+}
@@ -0,0 +1,210 @@
+//! Guardrails against schemas too complex to compile in reasonable time
+//!
+//! Generated specs sometimes contain pathological schemas, such as
+//! `allOf` chains hundreds of levels deep or enums of thousands of values,
+//! on which compilation can effectively hang. [`SchemaLimits`] bounds them
+//! per operation. An operation over a limit fails the build with
+//! [`ValidationError::SchemaTooComplex`], or is skipped with a warning.
+
+use crate::error::ValidationError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Limits on the schemas of each operation, none enforced by default
+///
+/// ```yaml
+/// limits:
+///   max_schema_depth: 64
+///   max_enum_values: 1000
+///   max_compile_ms: 5000
+///   skip_too_complex: true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchemaLimits {
+    /// Nesting levels of subschemas (properties, items, compositions) with
+    /// `$ref`s followed; recursive references don't count as nesting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_schema_depth: Option<usize>,
+    /// Values of any one `enum`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_enum_values: Option<usize>,
+    /// Milliseconds to compile an operation's validators
+    ///
+    /// Compilation isn't interrupted: an operation that takes longer is
+    /// rejected once it finishes. With lazy compilation schemas compile on
+    /// first use instead, outside this limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_compile_ms: Option<u64>,
+    /// Skip operations over a limit, with a warning, instead of failing the build
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skip_too_complex: bool,
+}
+
+impl SchemaLimits {
+    pub fn max_schema_depth(mut self, depth: usize) -> Self {
+        self.max_schema_depth = Some(depth);
+        self
+    }
+
+    pub fn max_enum_values(mut self, values: usize) -> Self {
+        self.max_enum_values = Some(values);
+        self
+    }
+
+    pub fn max_compile_time(mut self, limit: Duration) -> Self {
+        self.max_compile_ms = Some(limit.as_millis().try_into().unwrap_or(u64::MAX));
+        self
+    }
+
+    pub fn skip_too_complex(mut self, skip: bool) -> Self {
+        self.skip_too_complex = skip;
+        self
+    }
+
+    /// Whether schemas have to be walked before compiling them
+    pub(crate) fn checks_schemas(&self) -> bool {
+        self.max_schema_depth.is_some() || self.max_enum_values.is_some()
+    }
+
+    /// Checks the schemas of `operation`, serialized, whose `$ref`s resolve against `document`, the whole spec
+    pub(crate) fn check_operation(&self, document: &Value, operation: &Value, label: &str) -> Result<(), ValidationError> {
+        let mut walk = SchemaWalk {
+            limits: self,
+            document,
+            heights: HashMap::new(),
+            references: Vec::new(),
+        };
+        walk.operation_part(operation).map_err(|reason| ValidationError::SchemaTooComplex {
+            location: label.to_string(),
+            reason,
+        })
+    }
+
+    /// Checks how long compiling the operation at `label` took
+    pub(crate) fn check_compile_time(&self, elapsed: Duration, label: &str) -> Result<(), ValidationError> {
+        match self.max_compile_ms {
+            Some(limit) if elapsed > Duration::from_millis(limit) => Err(ValidationError::SchemaTooComplex {
+                location: label.to_string(),
+                reason: format!("compiling took {} ms, over the limit of {} ms", elapsed.as_millis(), limit),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keywords whose value is a subschema
+const SCHEMA_KEYWORDS: [&str; 4] = ["items", "additionalProperties", "not", "additionalItems"];
+/// Keywords whose value is an array of subschemas
+const SCHEMA_LIST_KEYWORDS: [&str; 4] = ["allOf", "anyOf", "oneOf", "prefixItems"];
+/// Keywords whose value maps names to subschemas
+const SCHEMA_MAP_KEYWORDS: [&str; 3] = ["properties", "patternProperties", "$defs"];
+
+/// Walks the schemas reachable from an operation, measuring them against the limits
+struct SchemaWalk<'a> {
+    limits: &'a SchemaLimits,
+    document: &'a Value,
+    /// Heights of the referenced schemas already walked, so shared ones are walked once
+    heights: HashMap<&'a str, usize>,
+    /// References being walked, to cut recursion
+    references: Vec<&'a str>,
+}
+
+impl<'a> SchemaWalk<'a> {
+    /// Finds the schemas in a part of the operation (parameters, request body, responses)
+    fn operation_part(&mut self, value: &'a Value) -> Result<(), String> {
+        match value {
+            Value::Object(object) => {
+                if let Some(target) = object.get("$ref").and_then(Value::as_str).and_then(|r| self.resolve(r)) {
+                    // Components such as parameters and responses aren't recursive
+                    return self.operation_part(target);
+                }
+                for (key, value) in object {
+                    if key == "schema" {
+                        self.schema(value, 1)?;
+                    } else if key != "example" && key != "examples" {
+                        self.operation_part(value)?;
+                    }
+                }
+                Ok(())
+            }
+            Value::Array(items) => items.iter().try_for_each(|item| self.operation_part(item)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of levels from `schema` down to its deepest subschema, itself included
+    ///
+    /// `level` is the schema's own nesting level, checked as the walk
+    /// descends so a pathological chain is abandoned at the limit.
+    fn schema(&mut self, schema: &'a Value, level: usize) -> Result<usize, String> {
+        if let Some(max) = self.limits.max_schema_depth {
+            if level > max {
+                return Err(format!("schemas are nested more than {} levels deep", max));
+            }
+        }
+        let Value::Object(object) = schema else {
+            return Ok(0);
+        };
+
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            let height = if self.references.contains(&reference) {
+                0
+            } else if let Some(height) = self.heights.get(reference) {
+                *height
+            } else {
+                // Unresolved references are reported when the schema is compiled
+                let Some(target) = self.resolve(reference) else {
+                    return Ok(0);
+                };
+                self.references.push(reference);
+                // The referenced schema takes the place of the reference, at the same level
+                let height = self.schema(target, level)?;
+                self.references.pop();
+                self.heights.insert(reference, height);
+                height
+            };
+            if let Some(max) = self.limits.max_schema_depth {
+                if level + height > max + 1 {
+                    return Err(format!("schemas are nested more than {} levels deep", max));
+                }
+            }
+            return Ok(height);
+        }
+
+        if let (Some(max), Some(Value::Array(values))) = (self.limits.max_enum_values, object.get("enum")) {
+            if values.len() > max {
+                return Err(format!("an enum has {} values, over the limit of {}", values.len(), max));
+            }
+        }
+
+        let mut deepest = 0;
+        for keyword in SCHEMA_KEYWORDS {
+            if let Some(subschema) = object.get(keyword) {
+                deepest = deepest.max(self.schema(subschema, level + 1)?);
+            }
+        }
+        for keyword in SCHEMA_LIST_KEYWORDS {
+            if let Some(Value::Array(subschemas)) = object.get(keyword) {
+                for subschema in subschemas {
+                    deepest = deepest.max(self.schema(subschema, level + 1)?);
+                }
+            }
+        }
+        for keyword in SCHEMA_MAP_KEYWORDS {
+            if let Some(Value::Object(subschemas)) = object.get(keyword) {
+                for subschema in subschemas.values() {
+                    deepest = deepest.max(self.schema(subschema, level + 1)?);
+                }
+            }
+        }
+        Ok(deepest + 1)
+    }
+
+    /// The value a local `#/...` reference points to
+    fn resolve(&self, reference: &str) -> Option<&'a Value> {
+        self.document.pointer(reference.strip_prefix('#')?)
+    }
+}
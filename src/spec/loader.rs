@@ -5,13 +5,72 @@ use std::path::Path;
 
 /// Loads an OpenAPI specification from a YAML file
 pub fn load_openapi_spec(path: &Path) -> Result<OpenAPI, ValidationError> {
-    let file = File::open(path).map_err(|e| {
-        ValidationError::SchemaCompilationError(format!("Failed to open spec file: {}", e))
-    })?;
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
 
-    let spec: OpenAPI = serde_yaml::from_reader(file).map_err(|e| {
-        ValidationError::SchemaCompilationError(format!("Failed to parse OpenAPI spec: {}", e))
-    })?;
+    to_spec(serde_yaml::from_reader(file).map_err(parse_error)?)
+}
+
+/// Parses an OpenAPI specification held in memory, in YAML or JSON
+pub fn parse_openapi_spec(contents: &str) -> Result<OpenAPI, ValidationError> {
+    to_spec(serde_yaml::from_str(contents).map_err(parse_error)?)
+}
+
+/// Loads a spec file (YAML or JSON) as a plain document, keeping everything typed parsing drops
+pub fn load_spec_document(path: &Path) -> Result<serde_json::Value, ValidationError> {
+    let file = File::open(path).map_err(|e| io_error(path, e))?;
+
+    serde_yaml::from_reader(file).map_err(parse_error)
+}
+
+/// Loads an OpenAPI specification from a YAML file without blocking the async runtime
+///
+/// The file is read through tokio; parsing happens on a blocking worker so
+/// large specs don't stall other tasks.
+#[cfg(feature = "async")]
+pub async fn load_openapi_spec_async(path: &Path) -> Result<OpenAPI, ValidationError> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| io_error(path, e))?;
+    parse_off_runtime(bytes).await
+}
+
+/// Fetches an OpenAPI specification (YAML or JSON) over HTTP(S)
+#[cfg(feature = "async")]
+pub async fn fetch_openapi_spec(url: &str) -> Result<OpenAPI, ValidationError> {
+    let fetch_error = |e: reqwest::Error| ValidationError::SpecIo {
+        location: url.to_string(),
+        reason: e.to_string(),
+    };
+
+    let response = reqwest::get(url).await.map_err(fetch_error)?;
+    let bytes = response.error_for_status().map_err(fetch_error)?.bytes().await.map_err(fetch_error)?;
+    parse_off_runtime(bytes.to_vec()).await
+}
+
+#[cfg(feature = "async")]
+async fn parse_off_runtime(bytes: Vec<u8>) -> Result<OpenAPI, ValidationError> {
+    tokio::task::spawn_blocking(move || to_spec(serde_yaml::from_slice(&bytes).map_err(parse_error)?))
+        .await
+        .map_err(|e| ValidationError::SpecParse {
+            reason: format!("parsing task failed: {}", e),
+        })?
+}
+
+/// Types a parsed document as a spec
+///
+/// Specs are never typed straight from their text: `openapiv3` panics on
+/// some documents with a key given twice (two `application/json` entries
+/// under one `content`, say), where parsing to a document first reports
+/// the duplicate as an error.
+fn to_spec(document: serde_yaml::Value) -> Result<OpenAPI, ValidationError> {
+    serde_yaml::from_value(document).map_err(parse_error)
+}
+
+pub(crate) fn io_error(path: &Path, e: std::io::Error) -> ValidationError {
+    ValidationError::SpecIo {
+        location: path.display().to_string(),
+        reason: e.to_string(),
+    }
+}
 
-    Ok(spec)
+fn parse_error(e: serde_yaml::Error) -> ValidationError {
+    ValidationError::SpecParse { reason: e.to_string() }
 }
@@ -1,7 +1,30 @@
+pub mod build_report;
 pub mod builder;
+pub mod limits;
+#[cfg(feature = "monitor")]
+pub mod cache;
+#[cfg(feature = "monitor")]
 pub mod loader;
+pub mod progress;
 pub mod reference_resolver;
+#[cfg(feature = "monitor")]
+pub mod source_map;
+pub mod transform;
 
-pub use builder::build_api_validator;
-pub use loader::load_openapi_spec;
+pub use build_report::BuildReport;
+pub use builder::{
+    build_api_validator, build_api_validator_with_options, build_api_validator_with_report, BuildOptions,
+};
+#[cfg(feature = "monitor")]
+pub use builder::build_api_validator_from_file;
+#[cfg(feature = "monitor")]
+pub use cache::load_openapi_spec_cached;
+#[cfg(feature = "monitor")]
+pub use loader::{load_openapi_spec, load_spec_document, parse_openapi_spec};
+pub use limits::SchemaLimits;
+pub use progress::BuildProgress;
+#[cfg(feature = "async")]
+pub use loader::{fetch_openapi_spec, load_openapi_spec_async};
 pub use reference_resolver::ResolveReference;
+#[cfg(feature = "monitor")]
+pub use source_map::{SourcePosition, SpecLocation, SpecLocator, SpecSourceMap};
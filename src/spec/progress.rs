@@ -0,0 +1,33 @@
+use crate::spec::build_report::BuildReport;
+use std::fmt;
+
+/// Receives progress and diagnostics while validators are built
+///
+/// The library never writes to stdout or stderr itself; embedders route
+/// these to their logger, and the CLI draws a progress line. Calls may come
+/// from several threads at once. Every method defaults to doing nothing.
+pub trait BuildProgress: Send + Sync {
+    /// `completed` of `total` operations have been compiled
+    fn operation_compiled(&self, completed: usize, total: usize) {
+        let _ = (completed, total);
+    }
+
+    /// The build finished; the report lists what was compiled and skipped
+    fn finished(&self, report: &BuildReport) {
+        let _ = report;
+    }
+
+    /// Something was skipped or failed without failing the build
+    ///
+    /// Also called after the build, when a lazily compiled schema fails to
+    /// compile on first use.
+    fn warning(&self, message: &str) {
+        let _ = message;
+    }
+}
+
+impl fmt::Debug for dyn BuildProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BuildProgress")
+    }
+}
@@ -43,10 +43,9 @@ where
         ReferenceOr::Item(item) => Ok(item),
         ReferenceOr::Reference { reference } => {
             if !reference.starts_with(prefix) {
-                return Err(ValidationError::SchemaCompilationError(format!(
-                    "Invalid reference: {}. Expected prefix: {}",
-                    reference, prefix
-                )));
+                return Err(ValidationError::UnresolvedReference {
+                    reference: format!("{} (expected prefix {})", reference, prefix),
+                });
             }
             let name = &reference[prefix.len()..];
 
@@ -56,10 +55,9 @@ where
                 .and_then(|map| map.get(name))
                 .and_then(|r| r.as_item())
                 .ok_or_else(|| {
-                    ValidationError::SchemaCompilationError(format!(
-                        "Reference not found: {}",
-                        reference
-                    ))
+                    ValidationError::UnresolvedReference {
+                        reference: reference.clone(),
+                    }
                 })
         }
     }
@@ -95,3 +93,35 @@ impl ResolveReference<openapiv3::Response> for ReferenceOr<openapiv3::Response>
     }
 }
 
+impl ResolveReference<openapiv3::Header> for ReferenceOr<openapiv3::Header> {
+    fn resolve<'a>(
+        &'a self,
+        spec: &'a OpenAPI,
+    ) -> Result<&'a openapiv3::Header, ValidationError> {
+        resolve_logic(self, spec, "#/components/headers/", |c| {
+            Some(&c.headers)
+        })
+    }
+}
+
+impl ResolveReference<openapiv3::Example> for ReferenceOr<openapiv3::Example> {
+    fn resolve<'a>(
+        &'a self,
+        spec: &'a OpenAPI,
+    ) -> Result<&'a openapiv3::Example, ValidationError> {
+        resolve_logic(self, spec, "#/components/examples/", |c| {
+            Some(&c.examples)
+        })
+    }
+}
+
+impl ResolveReference<openapiv3::SecurityScheme> for ReferenceOr<openapiv3::SecurityScheme> {
+    fn resolve<'a>(
+        &'a self,
+        spec: &'a OpenAPI,
+    ) -> Result<&'a openapiv3::SecurityScheme, ValidationError> {
+        resolve_logic(self, spec, "#/components/securitySchemes/", |c| {
+            Some(&c.security_schemes)
+        })
+    }
+}
@@ -0,0 +1,371 @@
+//! Where in the spec file a drift finding's contract is written
+//!
+//! Spec documents are parsed into plain values, which forget where each
+//! node came from. A [`SpecSourceMap`] re-reads the file for the line and
+//! column of every node by JSON pointer, and a [`SpecLocator`] works out
+//! which node a drift event violated, so reports can link to the exact line
+//! of the contract.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::spec::loader::{io_error, load_spec_document};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+/// `$ref` hops followed when resolving a node, enough for any sane spec and a stop for cyclic ones
+const MAX_REF_HOPS: usize = 16;
+
+/// A 1-based line and column in a spec file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Line and column of every node of a YAML (or JSON) spec, by JSON pointer
+///
+/// Mapping members are positioned at their key, so a link lands on the
+/// `name:` line rather than wherever the value starts.
+#[derive(Debug, Clone, Default)]
+pub struct SpecSourceMap {
+    positions: HashMap<String, SourcePosition>,
+}
+
+impl SpecSourceMap {
+    pub fn parse(source: &str) -> Result<Self, ValidationError> {
+        let mut builder = SourceMapBuilder::default();
+        Parser::new_from_str(source)
+            .load(&mut builder, false)
+            .map_err(|e| ValidationError::SpecParse { reason: e.to_string() })?;
+        Ok(Self {
+            positions: builder.positions,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        Self::parse(&fs::read_to_string(path).map_err(|e| io_error(path, e))?)
+    }
+
+    /// Position of the node at `pointer`, or of its nearest ancestor that has one
+    pub fn position(&self, pointer: &str) -> Option<SourcePosition> {
+        let mut pointer = pointer;
+        loop {
+            if let Some(position) = self.positions.get(pointer) {
+                return Some(*position);
+            }
+            pointer = &pointer[..pointer.rfind('/')?];
+        }
+    }
+}
+
+/// One open mapping or sequence while walking parser events
+enum Frame {
+    /// `pointer` is `None` inside complex keys, which have no pointer
+    Mapping {
+        pointer: Option<String>,
+        key: Option<String>,
+    },
+    Sequence {
+        pointer: Option<String>,
+        index: usize,
+    },
+}
+
+#[derive(Default)]
+struct SourceMapBuilder {
+    stack: Vec<Frame>,
+    positions: HashMap<String, SourcePosition>,
+}
+
+impl SourceMapBuilder {
+    /// Pointer of the node starting now, recording its position; `None` if it
+    /// is a mapping key (recorded once the key's text is known) or has no pointer
+    fn enter_node(&mut self, mark: Marker) -> Option<String> {
+        let pointer = match self.stack.last_mut() {
+            None => Some(String::new()),
+            Some(Frame::Mapping { pointer, key }) => {
+                // A value: its member was positioned at the key
+                return match key.take() {
+                    Some(key) => pointer.as_ref().map(|pointer| format!("{}/{}", pointer, escape(&key))),
+                    None => None,
+                };
+            }
+            Some(Frame::Sequence { pointer, index }) => {
+                *index += 1;
+                pointer.as_ref().map(|pointer| format!("{}/{}", pointer, *index - 1))
+            }
+        };
+        if let Some(pointer) = &pointer {
+            self.positions.insert(pointer.clone(), position(mark));
+        }
+        pointer
+    }
+
+    fn expecting_key(&self) -> bool {
+        matches!(self.stack.last(), Some(Frame::Mapping { key: None, .. }))
+    }
+}
+
+impl MarkedEventReceiver for SourceMapBuilder {
+    fn on_event(&mut self, event: Event, mark: Marker) {
+        match event {
+            Event::Scalar(value, ..) if self.expecting_key() => {
+                if let Some(Frame::Mapping { pointer, key }) = self.stack.last_mut() {
+                    if let Some(pointer) = pointer {
+                        self.positions.insert(format!("{}/{}", pointer, escape(&value)), position(mark));
+                    }
+                    *key = Some(value);
+                }
+            }
+            Event::MappingStart(..) | Event::SequenceStart(..) if self.expecting_key() => {
+                // A complex key: neither it nor its value can be named by a pointer
+                let frame = match event {
+                    Event::MappingStart(..) => Frame::Mapping { pointer: None, key: None },
+                    _ => Frame::Sequence { pointer: None, index: 0 },
+                };
+                if let Some(Frame::Mapping { key, .. }) = self.stack.last_mut() {
+                    *key = Some(String::new());
+                }
+                self.stack.push(frame);
+            }
+            Event::Scalar(..) | Event::Alias(_) => {
+                self.enter_node(mark);
+            }
+            Event::MappingStart(..) => {
+                let pointer = self.enter_node(mark);
+                self.stack.push(Frame::Mapping { pointer, key: None });
+            }
+            Event::SequenceStart(..) => {
+                let pointer = self.enter_node(mark);
+                self.stack.push(Frame::Sequence { pointer, index: 0 });
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn position(mark: Marker) -> SourcePosition {
+    SourcePosition {
+        line: mark.line(),
+        column: mark.col() + 1,
+    }
+}
+
+/// Escapes a JSON pointer reference token
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Where a drift event's contract is written in the spec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpecLocation {
+    /// JSON pointer to the violated node, e.g. `/components/schemas/User/properties/email`
+    pub pointer: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Maps drift events to the spec nodes they violate and those nodes' lines
+///
+/// The node is the schema of the deepest part of the body the drift was
+/// found in, following local `$ref`s, or the parameter, response or
+/// operation involved. Findings outside any operation have no location.
+#[derive(Debug, Clone)]
+pub struct SpecLocator {
+    path: String,
+    document: Value,
+    source_map: SpecSourceMap,
+    link_base: Option<String>,
+}
+
+impl SpecLocator {
+    /// Reads the spec at `path`, naming it as given in locations
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            path.display().to_string(),
+            load_spec_document(path)?,
+            SpecSourceMap::load(path)?,
+        ))
+    }
+
+    pub fn new(path: impl Into<String>, document: Value, source_map: SpecSourceMap) -> Self {
+        Self {
+            path: path.into(),
+            document,
+            source_map,
+            link_base: None,
+        }
+    }
+
+    /// Links lines as `<link_base>#L<line>`, e.g. with the spec's URL on a code host
+    pub fn with_link_base(mut self, link_base: impl Into<String>) -> Self {
+        self.link_base = Some(link_base.into());
+        self
+    }
+
+    /// The spec file as named in locations
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn locate(&self, event: &DriftEvent) -> Option<SpecLocation> {
+        let pointer = self.pointer_for(event)?;
+        let position = self.source_map.position(&pointer)?;
+        Some(SpecLocation {
+            pointer,
+            line: position.line,
+            column: position.column,
+        })
+    }
+
+    /// Link to a location's line, if a link base is set
+    pub fn link(&self, location: &SpecLocation) -> Option<String> {
+        self.link_base
+            .as_ref()
+            .map(|base| format!("{}#L{}", base, location.line))
+    }
+
+    /// JSON pointer to the node `event` violates
+    pub fn pointer_for(&self, event: &DriftEvent) -> Option<String> {
+        let path_item = format!("/paths/{}", escape(event.path_template.as_deref()?));
+        let operation = format!("{}/{}", path_item, event.method?.as_str().to_ascii_lowercase());
+        self.document.pointer(&operation)?;
+
+        let drift_type = event.drift_type.as_str();
+        let pointer = if drift_type.starts_with("RESPONSE_BODY_") {
+            let response = self.response(&operation, event.status_code)?;
+            self.body_schema(&format!("{}/content", response), &event.location)
+                .unwrap_or(response)
+        } else if drift_type.starts_with("REQUEST_BODY_") {
+            let request_body = self.resolve(&format!("{}/requestBody", operation))?;
+            self.body_schema(&format!("{}/content", request_body), &event.location)
+                .unwrap_or(request_body)
+        } else if drift_type.starts_with("PARAMETER_") {
+            let name = event.location.split('[').next().unwrap_or_default();
+            self.parameter(&operation, name)
+                .or_else(|| self.parameter(&path_item, name))
+                .unwrap_or(operation)
+        } else {
+            match event.drift_type {
+                DriftType::ResponseContentTypeDrift
+                | DriftType::RateLimitHeaderMissing
+                | DriftType::RateLimitHeaderInvalid
+                | DriftType::RateLimitHeaderInconsistent
+                | DriftType::GatewayResponseTransformDrift => {
+                    self.response(&operation, event.status_code).unwrap_or(operation)
+                }
+                DriftType::UndocumentedParameter => format!("{}/parameters", operation),
+                DriftType::SecurityRequirementDrift => format!("{}/security", operation),
+                DriftType::DeprecatedUsage => match event.location.split_once('/') {
+                    Some(("body", _)) => self
+                        .resolve(&format!("{}/requestBody", operation))
+                        .and_then(|request_body| {
+                            self.body_schema(&format!("{}/content", request_body), &event.location)
+                        })
+                        .unwrap_or(operation),
+                    _ if event.location == "operation" => operation,
+                    // Headers and cookies are located as `header/<name>`
+                    located => {
+                        let name = located.map_or(event.location.as_str(), |(_, name)| name);
+                        self.parameter(&operation, name)
+                            .or_else(|| self.parameter(&path_item, name))
+                            .unwrap_or(operation)
+                    }
+                },
+                _ => operation,
+            }
+        };
+        Some(pointer)
+    }
+
+    /// The response documented for `status`: exact, then its class (`2XX`), then `default`
+    fn response(&self, operation: &str, status: Option<u16>) -> Option<String> {
+        let responses = format!("{}/responses", operation);
+        let mut keys: Vec<String> = status
+            .map(|status| vec![status.to_string(), format!("{}XX", status / 100)])
+            .unwrap_or_default();
+        keys.push("default".to_string());
+        keys.iter().find_map(|key| self.resolve(&format!("{}/{}", responses, key)))
+    }
+
+    /// A parameter of an operation or path item by name, following `$ref`s
+    fn parameter(&self, owner: &str, name: &str) -> Option<String> {
+        let parameters = format!("{}/parameters", owner);
+        let count = self.document.pointer(&parameters)?.as_array()?.len();
+        (0..count)
+            .filter_map(|index| self.resolve(&format!("{}/{}", parameters, index)))
+            .find(|parameter| {
+                self.document
+                    .pointer(&format!("{}/name", parameter))
+                    .and_then(Value::as_str)
+                    == Some(name)
+            })
+    }
+
+    /// The schema of the JSON media type in `content`, walked down to the
+    /// deepest node covering a body location such as `body/users/0/email`
+    fn body_schema(&self, content: &str, location: &str) -> Option<String> {
+        let media_types = self.document.pointer(content)?.as_object()?;
+        let media_type = ["application/json"]
+            .into_iter()
+            .find(|media_type| media_types.contains_key(*media_type))
+            .or_else(|| media_types.keys().map(String::as_str).find(|key| key.contains("json")))
+            .or_else(|| media_types.keys().next().map(String::as_str))?;
+        let mut schema = self.resolve(&format!("{}/{}/schema", content, escape(media_type)))?;
+
+        let segments = location.strip_prefix("body").unwrap_or_default();
+        for segment in segments.split('/').filter(|segment| !segment.is_empty()) {
+            match self.child_schema(&schema, segment) {
+                Some(child) => schema = child,
+                None => break,
+            }
+        }
+        Some(schema)
+    }
+
+    /// The subschema of `schema` covering an instance member or item
+    fn child_schema(&self, schema: &str, segment: &str) -> Option<String> {
+        let node = self.document.pointer(schema)?;
+        let property = format!("{}/properties/{}", schema, escape(segment));
+        if node.pointer(&format!("/properties/{}", escape(segment))).is_some() {
+            return self.resolve(&property);
+        }
+        if (segment == "*" || segment.parse::<usize>().is_ok()) && node.get("items").is_some() {
+            return self.resolve(&format!("{}/items", schema));
+        }
+        for combinator in ["allOf", "oneOf", "anyOf"] {
+            let count = node.get(combinator).and_then(Value::as_array).map_or(0, Vec::len);
+            let found = (0..count)
+                .filter_map(|index| self.resolve(&format!("{}/{}/{}", schema, combinator, index)))
+                .find_map(|branch| self.child_schema(&branch, segment));
+            if found.is_some() {
+                return found;
+            }
+        }
+        node.get("additionalProperties")
+            .filter(|additional| additional.is_object())
+            .and_then(|_| self.resolve(&format!("{}/additionalProperties", schema)))
+    }
+
+    /// Follows local `$ref`s from the node at `pointer`, returning the pointer of the node reached
+    fn resolve(&self, pointer: &str) -> Option<String> {
+        let mut pointer = pointer.to_string();
+        for _ in 0..MAX_REF_HOPS {
+            let node = self.document.pointer(&pointer)?;
+            match node.get("$ref").and_then(Value::as_str).and_then(|target| target.strip_prefix('#')) {
+                Some(target) => pointer = target.to_string(),
+                None => return Some(pointer),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,353 @@
+use crate::spec::builder::BuildOptions;
+use openapiv3::{OpenAPI, ReferenceOr};
+use serde_json::{json, Map, Value};
+
+/// Applies OpenAPI-to-JSON-Schema rewrites, then those enabled in the build options
+pub fn apply(schema: &mut Value, options: &BuildOptions) {
+    if options.protobuf_json_compat {
+        walk_schema_mut(schema, &mut protobuf_json_compat);
+    }
+    // After the protobuf rewrite, which matches on single `type` names
+    walk_schema_mut(schema, &mut nullable_to_json_schema);
+    // Last, as the protobuf rewrite reads `format`
+    if !options.validation.format_checks {
+        walk_schema_mut(schema, &mut |map| {
+            map.remove("format");
+        });
+    }
+}
+
+/// Closes object schemas left open, so fields they don't declare are reported
+///
+/// Every schema that declares properties, composes others or refers to
+/// one gets `unevaluatedProperties: false`, unless it already says what
+/// other properties may hold. `unevaluatedProperties` sees through
+/// `allOf`, `anyOf`, `oneOf` and `$ref`, so only the outermost schema of a
+/// composition is closed; its members, and component schemas
+/// (`close_root: false`), which may be such members, are checked through
+/// the schemas using them.
+pub fn close_objects(schema: &mut Value, close_root: bool) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+
+    for keyword in ["items", "additionalProperties"] {
+        if let Some(subschema) = map.get_mut(keyword) {
+            close_objects(subschema, true);
+        }
+    }
+    if let Some(Value::Array(subschemas)) = map.get_mut("prefixItems") {
+        subschemas.iter_mut().for_each(|s| close_objects(s, true));
+    }
+    for keyword in ["properties", "patternProperties"] {
+        if let Some(Value::Object(subschemas)) = map.get_mut(keyword) {
+            subschemas.values_mut().for_each(|s| close_objects(s, true));
+        }
+    }
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(subschemas)) = map.get_mut(keyword) {
+            subschemas.iter_mut().for_each(|s| close_objects(s, false));
+        }
+    }
+
+    let open = ["properties", "allOf", "anyOf", "oneOf", "$ref"].iter().any(|keyword| map.contains_key(*keyword))
+        && !map.contains_key("additionalProperties")
+        && !map.contains_key("unevaluatedProperties");
+    if close_root && open {
+        map.insert("unevaluatedProperties".to_string(), Value::Bool(false));
+    }
+}
+
+/// Merges `allOf` members into the schema composing them, so drift points
+/// at the effective schema rather than at whichever member a check fell in
+///
+/// Members that `$ref` a component schema are inlined. Properties are
+/// combined, `required` lists joined, and a property declared by several
+/// members merged in turn. Where merging would change what the schema
+/// accepts the `allOf` is left as it is: members disagreeing on a keyword,
+/// `additionalProperties` or `unevaluatedProperties`, whose scope is the
+/// schema declaring them, and members only some of which are nullable.
+pub fn merge_all_of(schema: &mut Value, spec: &OpenAPI) {
+    merge_all_of_with(schema, spec, &mut Vec::new());
+}
+
+/// [`merge_all_of`], with the component schemas being inlined on `stack`
+fn merge_all_of_with(schema: &mut Value, spec: &OpenAPI, stack: &mut Vec<String>) {
+    walk_schema_mut(schema, &mut |map| {
+        if !map.contains_key("allOf") {
+            return;
+        }
+        if let Some(merged) = merged_all_of(map, spec, stack) {
+            *map = merged;
+        }
+    });
+}
+
+/// Keywords describing a schema without constraining it, the first one given kept
+const ANNOTATIONS: [&str; 7] = ["title", "description", "example", "examples", "deprecated", "externalDocs", "xml"];
+
+fn merged_all_of(schema: &Map<String, Value>, spec: &OpenAPI, stack: &mut Vec<String>) -> Option<Map<String, Value>> {
+    let Some(Value::Array(members)) = schema.get("allOf") else {
+        return None;
+    };
+    let scoped = |map: &Map<String, Value>| map.contains_key("additionalProperties") || map.contains_key("unevaluatedProperties");
+    if scoped(schema) {
+        return None;
+    }
+    let members = members
+        .iter()
+        .map(|member| inlined_member(member, spec, stack))
+        .collect::<Option<Vec<_>>>()?;
+    if members.iter().any(scoped) {
+        return None;
+    }
+    // A member's `nullable` holds for the merged schema only if every typed member has it
+    let nullable = |map: &Map<String, Value>| map.get("nullable") == Some(&Value::Bool(true));
+    let members_nullable = members.iter().any(nullable);
+    if members_nullable && members.iter().any(|member| member.contains_key("type") && !nullable(member)) {
+        return None;
+    }
+
+    let mut merged = schema.clone();
+    merged.remove("allOf");
+    for member in members {
+        for (keyword, value) in member {
+            match keyword.as_str() {
+                "nullable" => {}
+                "properties" => {
+                    let Value::Object(properties) = value else {
+                        return None;
+                    };
+                    let Value::Object(merged_properties) =
+                        merged.entry("properties").or_insert_with(|| Value::Object(Map::new()))
+                    else {
+                        return None;
+                    };
+                    for (name, property) in properties {
+                        match merged_properties.get_mut(&name) {
+                            Some(existing) if *existing == property => {}
+                            Some(existing) => {
+                                let mut combined = json!({ "allOf": [existing.take(), property] });
+                                merge_all_of_with(&mut combined, spec, stack);
+                                *existing = combined;
+                            }
+                            None => {
+                                merged_properties.insert(name, property);
+                            }
+                        }
+                    }
+                }
+                "required" => {
+                    let Value::Array(names) = value else {
+                        return None;
+                    };
+                    let Value::Array(required) = merged.entry("required").or_insert_with(|| json!([])) else {
+                        return None;
+                    };
+                    for name in names {
+                        if !required.contains(&name) {
+                            required.push(name);
+                        }
+                    }
+                }
+                keyword if ANNOTATIONS.contains(&keyword) || keyword.starts_with("x-") => {
+                    merged.entry(keyword).or_insert(value);
+                }
+                _ => match merged.get(&keyword) {
+                    None => {
+                        merged.insert(keyword, value);
+                    }
+                    Some(existing) if *existing == value => {}
+                    Some(_) => return None,
+                },
+            }
+        }
+    }
+    if members_nullable {
+        merged.insert("nullable".to_string(), Value::Bool(true));
+    }
+    Some(merged)
+}
+
+/// An `allOf` member as an object, with a `$ref` to a component schema inlined
+///
+/// Returns `None` for references that can't be inlined: to anything but a
+/// component schema, or back to a component already being inlined.
+fn inlined_member(member: &Value, spec: &OpenAPI, stack: &mut Vec<String>) -> Option<Map<String, Value>> {
+    let Value::Object(map) = member else {
+        return None;
+    };
+    let Some(reference) = map.get("$ref") else {
+        return Some(map.clone());
+    };
+    let name = reference.as_str()?.strip_prefix("#/components/schemas/")?;
+    if stack.iter().any(|inlining| inlining == name) {
+        return None;
+    }
+    let component = spec.components.as_ref()?.schemas.get(name)?.as_item()?;
+    let mut component = serde_json::to_value(component).ok()?;
+    stack.push(name.to_string());
+    merge_all_of_with(&mut component, spec, stack);
+    stack.pop();
+    match component {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+/// Which side of an exchange a schema validates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+/// Drops properties that don't travel in `direction` from `required`
+///
+/// `readOnly` properties are only sent in responses and `writeOnly` ones
+/// only in requests, and OpenAPI has `required` apply to them in that
+/// direction alone. A property counts as read-only (write-only) when its
+/// own schema says so or it's a `$ref` to a component schema that does.
+pub fn read_write_only(schema: &mut Value, direction: Direction, spec: &OpenAPI) {
+    let keyword = match direction {
+        Direction::Request => "readOnly",
+        Direction::Response => "writeOnly",
+    };
+    let excluded = |property: &Value| {
+        if property.get(keyword) == Some(&Value::Bool(true)) {
+            return true;
+        }
+        let Some(name) = property
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|reference| reference.strip_prefix("#/components/schemas/"))
+        else {
+            return false;
+        };
+        let component = spec.components.as_ref().and_then(|components| components.schemas.get(name));
+        match (component, direction) {
+            (Some(ReferenceOr::Item(schema)), Direction::Request) => schema.schema_data.read_only,
+            (Some(ReferenceOr::Item(schema)), Direction::Response) => schema.schema_data.write_only,
+            _ => false,
+        }
+    };
+
+    walk_schema_mut(schema, &mut |map| {
+        let Some(Value::Object(properties)) = map.get("properties") else {
+            return;
+        };
+        let excluded: Vec<String> = properties
+            .iter()
+            .filter(|(_, property)| excluded(property))
+            .map(|(name, _)| name.clone())
+            .collect();
+        if excluded.is_empty() {
+            return;
+        }
+        if let Some(Value::Array(required)) = map.get_mut("required") {
+            required.retain(|name| !name.as_str().is_some_and(|name| excluded.iter().any(|e| e == name)));
+            if required.is_empty() {
+                map.remove("required");
+            }
+        }
+    });
+}
+
+/// Visits a schema and every subschema nested under schema keywords
+///
+/// Only keywords whose values are schemas are followed, so property names
+/// such as `required` or `enum` inside `properties` are never mistaken for
+/// keywords. Subschemas are visited before their parent.
+pub fn walk_schema_mut(schema: &mut Value, visit: &mut impl FnMut(&mut Map<String, Value>)) {
+    let Value::Object(map) = schema else {
+        return;
+    };
+
+    for keyword in ["items", "additionalProperties", "not", "contains", "propertyNames"] {
+        if let Some(subschema) = map.get_mut(keyword) {
+            walk_schema_mut(subschema, visit);
+        }
+    }
+    for keyword in ["allOf", "anyOf", "oneOf", "prefixItems"] {
+        if let Some(Value::Array(subschemas)) = map.get_mut(keyword) {
+            subschemas.iter_mut().for_each(|s| walk_schema_mut(s, visit));
+        }
+    }
+    for keyword in ["properties", "patternProperties"] {
+        if let Some(Value::Object(subschemas)) = map.get_mut(keyword) {
+            subschemas.values_mut().for_each(|s| walk_schema_mut(s, visit));
+        }
+    }
+
+    visit(map);
+}
+
+/// Relaxes a schema to accept the protobuf JSON mapping used by gRPC transcoding
+///
+/// - 64-bit integers (`format: int64/uint64`) may arrive as decimal strings
+/// - floating point numbers may arrive as `"NaN"`, `"Infinity"`, `"-Infinity"`
+/// - string enums may arrive as their numeric values
+/// - proto3 omits fields holding default values, so `required` is dropped
+fn protobuf_json_compat(schema: &mut Map<String, Value>) {
+    schema.remove("required");
+
+    let schema_type = schema.get("type").and_then(Value::as_str).map(str::to_string);
+    let format = schema.get("format").and_then(Value::as_str).map(str::to_string);
+
+    match (schema_type.as_deref(), format.as_deref()) {
+        (Some("integer"), Some("int64" | "uint64" | "fixed64" | "sfixed64" | "sint64")) => {
+            schema.insert("type".to_string(), json!(["integer", "string"]));
+            schema.insert("pattern".to_string(), json!("^-?[0-9]+$"));
+        }
+        (Some("number"), _) => {
+            let original = Value::Object(std::mem::take(schema));
+            schema.insert(
+                "anyOf".to_string(),
+                json!([original, { "enum": ["NaN", "Infinity", "-Infinity"] }]),
+            );
+        }
+        (Some("string"), _) if schema.contains_key("enum") => {
+            let original = Value::Object(std::mem::take(schema));
+            schema.insert("anyOf".to_string(), json!([original, { "type": "integer" }]));
+        }
+        _ => {}
+    }
+}
+
+/// Rewrites OpenAPI 3.0's `nullable: true`, which JSON Schema ignores, into an explicit `null` type
+///
+/// `type: T` becomes `type: [T, "null"]` and `null` joins any `enum`.
+/// Schemas without a `type` of their own (`$ref` and composition wrappers
+/// like `allOf: [{$ref}]`) can't take the extra type without making their
+/// branches reject null, so they become `anyOf: [<schema>, {type: null}]`.
+pub(crate) fn nullable_to_json_schema(schema: &mut Map<String, Value>) {
+    let Some(nullable) = schema.remove("nullable") else {
+        return;
+    };
+    if nullable != Value::Bool(true) {
+        return;
+    }
+
+    let composed = ["$ref", "allOf", "anyOf", "oneOf"].iter().any(|keyword| schema.contains_key(*keyword));
+    match schema.get_mut("type") {
+        Some(Value::String(schema_type)) if !composed => {
+            let schema_type = std::mem::take(schema_type);
+            schema.insert("type".to_string(), json!([schema_type, "null"]));
+        }
+        Some(Value::Array(types)) if !composed => {
+            if !types.iter().any(|t| t == "null") {
+                types.push(json!("null"));
+            }
+        }
+        _ => {
+            let original = Value::Object(std::mem::take(schema));
+            schema.insert("anyOf".to_string(), json!([original, { "type": "null" }]));
+            return;
+        }
+    }
+    if let Some(Value::Array(values)) = schema.get_mut("enum") {
+        if !values.contains(&Value::Null) {
+            values.push(Value::Null);
+        }
+    }
+}
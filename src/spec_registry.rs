@@ -0,0 +1,200 @@
+//! Validation against whichever version of an API a request targets
+//!
+//! APIs evolve while old clients keep calling: `/v1` and `/v2` served side
+//! by side, or one path answering per a date-based `Accept-Version`. A
+//! [`SpecRegistry`] holds a validator per version, picks the one each
+//! exchange targets and tags the drift it finds with that version, so a v1
+//! client isn't reported against the v2 contract.
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::{DriftEvent, EventContext};
+use crate::error::ValidationError;
+use crate::exchange::{Exchange, ObservedRequest};
+use crate::routing::BasePath;
+use crate::spec::{build_api_validator_with_options, BuildOptions};
+use openapiv3::OpenAPI;
+use std::sync::Arc;
+
+/// Header naming the version a request targets, unless configured otherwise
+pub const DEFAULT_VERSION_HEADER: &str = "Accept-Version";
+
+/// Picks a version name for a request, or `None` to fall through to the other rules
+pub type VersionSelector = Arc<dyn Fn(&ObservedRequest) -> Option<String> + Send + Sync>;
+
+/// One version of an API and the validator for its contract
+pub struct ApiVersion {
+    name: String,
+    validator: ApiValidator,
+    path_prefix: Option<BasePath>,
+}
+
+impl ApiVersion {
+    /// A version named e.g. `v2` or `2024-06-01`, validated by `validator`
+    pub fn new(name: impl Into<String>, validator: ApiValidator) -> Self {
+        Self {
+            name: name.into(),
+            validator,
+            path_prefix: None,
+        }
+    }
+
+    /// Builds the version's validator from its spec
+    pub fn from_spec(name: impl Into<String>, spec: &OpenAPI, options: &BuildOptions) -> Result<Self, ValidationError> {
+        Ok(Self::new(name, build_api_validator_with_options(spec, options)?))
+    }
+
+    /// Selects this version for paths under `prefix`, e.g. `/v2`, which is
+    /// stripped before routing
+    pub fn with_path_prefix(mut self, prefix: &str) -> Self {
+        let prefix = BasePath::parse(prefix);
+        self.validator.add_base_path(prefix.clone());
+        self.path_prefix = Some(prefix);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn validator(&self) -> &ApiValidator {
+        &self.validator
+    }
+}
+
+/// Validators for several versions of one API, chosen per request
+///
+/// A request's version is taken, in order, from the selector function, the
+/// version header, the longest matching path prefix, and finally the
+/// default version. Version header values match names case-insensitively
+/// with or without a leading `v` (`2` selects `v2`); a date such as
+/// `2024-07-15` selects the latest date-named version not after it.
+pub struct SpecRegistry {
+    versions: Vec<ApiVersion>,
+    version_header: Option<String>,
+    selector: Option<VersionSelector>,
+    default_version: Option<String>,
+}
+
+impl Default for SpecRegistry {
+    fn default() -> Self {
+        Self {
+            versions: Vec::new(),
+            version_header: Some(DEFAULT_VERSION_HEADER.to_string()),
+            selector: None,
+            default_version: None,
+        }
+    }
+}
+
+impl SpecRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_version(mut self, version: ApiVersion) -> Self {
+        self.versions.push(version);
+        self
+    }
+
+    /// Reads the requested version from header `name` instead of `Accept-Version`
+    pub fn with_version_header(mut self, name: impl Into<String>) -> Self {
+        self.version_header = Some(name.into());
+        self
+    }
+
+    /// Ignores version headers, selecting by selector and path prefix alone
+    pub fn without_version_header(mut self) -> Self {
+        self.version_header = None;
+        self
+    }
+
+    /// Asks `selector` first which version a request targets
+    pub fn with_selector(mut self, selector: impl Fn(&ObservedRequest) -> Option<String> + Send + Sync + 'static) -> Self {
+        self.selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Validates requests nothing else selects a version for against `name`
+    pub fn with_default_version(mut self, name: impl Into<String>) -> Self {
+        self.default_version = Some(name.into());
+        self
+    }
+
+    pub fn versions(&self) -> impl Iterator<Item = &ApiVersion> {
+        self.versions.iter()
+    }
+
+    pub fn version(&self, name: &str) -> Option<&ApiVersion> {
+        self.versions.iter().find(|version| version.name == name)
+    }
+
+    /// The version `request` targets, if any
+    pub fn select(&self, request: &ObservedRequest) -> Option<&ApiVersion> {
+        if let Some(name) = self.selector.as_ref().and_then(|selector| selector(request)) {
+            return self.version(&name);
+        }
+        let requested = self.version_header.as_deref().and_then(|header| request.header(header));
+        if let Some(version) = requested.and_then(|requested| self.requested_version(requested.trim())) {
+            return Some(version);
+        }
+        let by_prefix = self
+            .versions
+            .iter()
+            .filter_map(|version| {
+                let prefix = version.path_prefix.as_ref()?;
+                prefix.strip(&request.path).map(|_| (prefix.depth(), version))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, version)| version);
+        by_prefix.or_else(|| self.version(self.default_version.as_deref()?))
+    }
+
+    fn requested_version(&self, requested: &str) -> Option<&ApiVersion> {
+        let bare = |name: &str| name.strip_prefix(['v', 'V']).unwrap_or(name).to_ascii_lowercase();
+        let exact = self.versions.iter().find(|version| bare(&version.name) == bare(requested));
+        if exact.is_some() || !is_date(requested) {
+            return exact;
+        }
+        // ISO dates order lexically
+        self.versions
+            .iter()
+            .filter(|version| is_date(&version.name) && version.name.as_str() <= requested)
+            .max_by(|a, b| a.name.cmp(&b.name))
+    }
+
+    /// Validates an exchange against the version it targets, tagging events with the version's name
+    pub fn validate_exchange(&self, exchange: &Exchange) -> Result<Vec<DriftEvent>, ValidationError> {
+        self.validate_exchange_with_context(exchange, &EventContext::new())
+    }
+
+    /// Like [`Self::validate_exchange`], attaching `context` to every event
+    pub fn validate_exchange_with_context(
+        &self,
+        exchange: &Exchange,
+        context: &EventContext,
+    ) -> Result<Vec<DriftEvent>, ValidationError> {
+        let version = self.select(&exchange.request).ok_or_else(|| {
+            ValidationError::ValidationFailed(format!(
+                "No API version selected for {} {}",
+                exchange.request.method.as_str(),
+                exchange.request.path
+            ))
+        })?;
+        Ok(version
+            .validator
+            .validate_exchange_with_context(exchange, context)?
+            .into_iter()
+            .map(|event| event.with_contract(version.name.as_str()))
+            .collect())
+    }
+}
+
+/// Whether `name` is an ISO date, e.g. `2024-06-01`
+fn is_date(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(index, byte)| match index {
+            4 | 7 => *byte == b'-',
+            _ => byte.is_ascii_digit(),
+        })
+}
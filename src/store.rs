@@ -0,0 +1,306 @@
+//! Durable drift history in SQLite
+//!
+//! A [`DriftStore`] keeps every distinct finding a monitor has seen, so a
+//! long-running deployment can answer "what drifted on this operation last
+//! week" after restarts. Identical findings, the same drift fingerprint with
+//! the same observed value, are stored once and counted; a new value for a
+//! known fingerprint is a finding of its own.
+
+use crate::baseline::{default_fingerprint, Fingerprint};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::error::ValidationError;
+use crate::report::operation_label;
+use crate::sinks::DriftSink;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS drift_findings (
+    id INTEGER PRIMARY KEY,
+    fingerprint TEXT NOT NULL,
+    sample_hash TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    operation_id TEXT,
+    drift_type TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    first_seen_ms INTEGER NOT NULL,
+    last_seen_ms INTEGER NOT NULL,
+    occurrences INTEGER NOT NULL,
+    event TEXT NOT NULL,
+    UNIQUE (fingerprint, sample_hash)
+);
+CREATE INDEX IF NOT EXISTS drift_findings_operation ON drift_findings (operation, last_seen_ms);
+CREATE INDEX IF NOT EXISTS drift_findings_operation_id ON drift_findings (operation_id, last_seen_ms);
+CREATE INDEX IF NOT EXISTS drift_findings_last_seen ON drift_findings (last_seen_ms);
+";
+
+/// Keeps the first occurrence's time, the latest event and a running count
+const UPSERT: &str = "
+INSERT INTO drift_findings
+    (fingerprint, sample_hash, operation, operation_id, drift_type, severity,
+     first_seen_ms, last_seen_ms, occurrences, event)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, 1, ?8)
+ON CONFLICT (fingerprint, sample_hash) DO UPDATE SET
+    occurrences = occurrences + 1,
+    first_seen_ms = min(first_seen_ms, excluded.first_seen_ms),
+    event = CASE WHEN excluded.last_seen_ms >= last_seen_ms THEN excluded.event ELSE event END,
+    last_seen_ms = max(last_seen_ms, excluded.last_seen_ms)
+RETURNING occurrences
+";
+
+/// A distinct finding and its history
+#[derive(Debug, Clone)]
+pub struct StoredFinding {
+    /// The most recent occurrence
+    pub event: DriftEvent,
+    /// FNV-1a of the observed value, as 16 hex digits
+    pub sample_hash: String,
+    /// Milliseconds since the Unix epoch
+    pub first_seen_ms: u64,
+    pub last_seen_ms: u64,
+    pub occurrences: u64,
+}
+
+/// Which stored findings to return
+///
+/// Every filter set must match. Findings come back most recently seen first.
+#[derive(Debug, Clone, Default)]
+pub struct DriftQuery {
+    operation: Option<String>,
+    drift_type: Option<DriftType>,
+    since_ms: Option<u64>,
+    until_ms: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl DriftQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Findings on one operation, named by `operationId` or as `GET /users/{userId}`
+    pub fn operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    pub fn drift_type(mut self, drift_type: DriftType) -> Self {
+        self.drift_type = Some(drift_type);
+        self
+    }
+
+    /// Findings last seen at or after `since`
+    pub fn since(mut self, since: SystemTime) -> Self {
+        self.since_ms = Some(millis(since));
+        self
+    }
+
+    /// Findings last seen before `until`
+    pub fn until(mut self, until: SystemTime) -> Self {
+        self.until_ms = Some(millis(until));
+        self
+    }
+
+    /// Findings seen within `window` of now, e.g. the last seven days
+    pub fn within(self, window: Duration) -> Self {
+        self.since(SystemTime::now().checked_sub(window).unwrap_or(UNIX_EPOCH))
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// Drift findings persisted in a SQLite database
+///
+/// Also a [`DriftSink`], so it can sit alongside the other sinks of a
+/// monitor. Each event is committed as it's recorded.
+#[derive(Debug)]
+pub struct DriftStore {
+    connection: Mutex<Connection>,
+    fingerprint: Arc<dyn Fingerprint>,
+}
+
+impl DriftStore {
+    /// Opens the database at `path`, creating it and its tables as needed
+    pub fn open(path: &Path) -> Result<Self, ValidationError> {
+        let connection = Connection::open(path)
+            .map_err(|e| ValidationError::SinkError(format!("Failed to open {}: {}", path.display(), e)))?;
+        // Lets readers query history while a monitor is writing to it
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .map_err(store_error)?;
+        Self::with_connection(connection)
+    }
+
+    /// A store living only as long as the process
+    pub fn in_memory() -> Result<Self, ValidationError> {
+        Self::with_connection(Connection::open_in_memory().map_err(store_error)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, ValidationError> {
+        connection.execute_batch(SCHEMA).map_err(store_error)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            fingerprint: default_fingerprint(),
+        })
+    }
+
+    /// Dedupes by `fingerprint` instead of [`crate::baseline::DefaultFingerprint`]
+    ///
+    /// Findings already stored keep the keys they were stored under, so a
+    /// database should stick with one fingerprint.
+    pub fn with_fingerprint(mut self, fingerprint: impl Fingerprint + 'static) -> Self {
+        self.fingerprint = Arc::new(fingerprint);
+        self
+    }
+
+    /// Stores an occurrence of `event`, returning whether it's a new finding
+    pub fn insert(&self, event: &DriftEvent) -> Result<bool, ValidationError> {
+        let fingerprint = self.fingerprint_of(event)?;
+        let json = serde_json::to_string(event)
+            .map_err(|e| ValidationError::SinkError(format!("Failed to serialize drift event: {}", e)))?;
+        let occurrences: i64 = self
+            .lock()
+            .query_row(
+                UPSERT,
+                params![
+                    fingerprint,
+                    sample_hash(event),
+                    operation_label(event),
+                    event.operation_id,
+                    event.drift_type.as_str(),
+                    event.severity.as_str(),
+                    event.timestamp_ms as i64,
+                    json,
+                ],
+                |row| row.get(0),
+            )
+            .map_err(store_error)?;
+        Ok(occurrences == 1)
+    }
+
+    /// Stored findings matching `query`
+    pub fn query(&self, query: &DriftQuery) -> Result<Vec<StoredFinding>, ValidationError> {
+        let mut sql = String::from(
+            "SELECT event, sample_hash, first_seen_ms, last_seen_ms, occurrences FROM drift_findings WHERE 1 = 1",
+        );
+        let mut values: Vec<SqlValue> = Vec::new();
+        if let Some(operation) = &query.operation {
+            values.push(SqlValue::Text(operation.clone()));
+            sql.push_str(&format!(" AND (operation = ?{0} OR operation_id = ?{0})", values.len()));
+        }
+        if let Some(drift_type) = query.drift_type {
+            values.push(SqlValue::Text(drift_type.as_str().to_string()));
+            sql.push_str(&format!(" AND drift_type = ?{}", values.len()));
+        }
+        if let Some(since_ms) = query.since_ms {
+            values.push(SqlValue::Integer(since_ms as i64));
+            sql.push_str(&format!(" AND last_seen_ms >= ?{}", values.len()));
+        }
+        if let Some(until_ms) = query.until_ms {
+            values.push(SqlValue::Integer(until_ms as i64));
+            sql.push_str(&format!(" AND last_seen_ms < ?{}", values.len()));
+        }
+        sql.push_str(" ORDER BY last_seen_ms DESC, id DESC");
+        if let Some(limit) = query.limit {
+            values.push(SqlValue::Integer(limit as i64));
+            sql.push_str(&format!(" LIMIT ?{}", values.len()));
+        }
+
+        let connection = self.lock();
+        let mut statement = connection.prepare(&sql).map_err(store_error)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, i64>(4)?,
+                ))
+            })
+            .map_err(store_error)?;
+        rows.map(|row| {
+            let (event, sample_hash, first_seen_ms, last_seen_ms, occurrences) = row.map_err(store_error)?;
+            Ok(StoredFinding {
+                event: serde_json::from_str(&event)
+                    .map_err(|e| ValidationError::SinkError(format!("Corrupt stored drift event: {}", e)))?,
+                sample_hash,
+                first_seen_ms: first_seen_ms as u64,
+                last_seen_ms: last_seen_ms as u64,
+                occurrences: occurrences as u64,
+            })
+        })
+        .collect()
+    }
+
+    /// How many occurrences of a finding have been stored, `0` if none
+    pub fn occurrences(&self, event: &DriftEvent) -> Result<u64, ValidationError> {
+        let fingerprint = self.fingerprint_of(event)?;
+        let occurrences: Option<i64> = self
+            .lock()
+            .query_row(
+                "SELECT occurrences FROM drift_findings WHERE fingerprint = ?1 AND sample_hash = ?2",
+                params![fingerprint, sample_hash(event)],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(store_error)?;
+        Ok(occurrences.unwrap_or(0) as u64)
+    }
+
+    /// Deletes findings not seen since `before`, returning how many went
+    pub fn prune(&self, before: SystemTime) -> Result<usize, ValidationError> {
+        self.lock()
+            .execute(
+                "DELETE FROM drift_findings WHERE last_seen_ms < ?1",
+                params![millis(before) as i64],
+            )
+            .map_err(store_error)
+    }
+
+    fn fingerprint_of(&self, event: &DriftEvent) -> Result<String, ValidationError> {
+        serde_json::to_string(&self.fingerprint.fingerprint(event))
+            .map_err(|e| ValidationError::SinkError(format!("Failed to serialize drift fingerprint: {}", e)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl DriftSink for DriftStore {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        self.insert(&event).map(|_| ())
+    }
+}
+
+/// FNV-1a over the observed value's JSON, stable across builds and platforms
+fn sample_hash(event: &DriftEvent) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let sample = event
+        .observed
+        .as_ref()
+        .map(|observed| observed.to_string())
+        .unwrap_or_default();
+    let hash = sample
+        .bytes()
+        .fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME));
+    format!("{:016x}", hash)
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+fn store_error(e: rusqlite::Error) -> ValidationError {
+    ValidationError::SinkError(format!("Drift store: {}", e))
+}
@@ -0,0 +1,251 @@
+//! Kafka consumer mode
+//!
+//! A [`KafkaSource`] joins a consumer group and validates the
+//! [`TrafficRecord`]s published to a topic, one JSON record per message as
+//! in the JSONL format. Drift goes to any [`DriftSink`], including a
+//! [`KafkaSink`] publishing it to a results topic.
+//!
+//! Records are handled in batches. The group's offsets are committed only
+//! once a batch's drift has been flushed to the sink, so a monitor that dies
+//! mid-batch leaves the batch to be consumed again by the next one: every
+//! record is validated at least once, and drift from a batch in flight at a
+//! crash may be published twice.
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::DriftEvent;
+use crate::error::ValidationError;
+use crate::report::operation_label;
+use crate::sampling::{ExchangeSampler, Sampling};
+use crate::sinks::{to_json_line, DriftSink};
+use crate::traffic::{ingest_into, IngestSummary, JsonlFormat, LogFormat, TrafficRecord};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+use rdkafka::message::{BorrowedMessage, Message};
+use rdkafka::producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer};
+use rdkafka::ClientContext;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Longest a single poll blocks, so deadlines are noticed promptly
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Records validated before offsets are committed, unless the window closes first
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Longest a batch stays open waiting for records
+const DEFAULT_BATCH_WINDOW: Duration = Duration::from_secs(5);
+
+/// How long flushing a [`KafkaSink`] waits for the brokers to acknowledge
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Wait before retrying a send rejected because the producer's queue is full
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Validates traffic records consumed from a Kafka topic
+pub struct KafkaSource {
+    consumer: BaseConsumer,
+    batch_size: usize,
+    batch_window: Duration,
+    sampler: ExchangeSampler,
+}
+
+impl KafkaSource {
+    /// Joins `group_id` on `brokers` and subscribes to `topic`
+    pub fn connect(brokers: &[String], topic: &str, group_id: &str) -> Result<Self, ValidationError> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers.join(",")).set("group.id", group_id);
+        Self::from_config(config, topic)
+    }
+
+    /// Subscribes to `topic` with a client configured by the caller, e.g. for TLS or SASL
+    ///
+    /// `config` must name the consumer group. Automatic offset commits are
+    /// turned off whatever it says, as they would break at-least-once delivery.
+    pub fn from_config(mut config: ClientConfig, topic: &str) -> Result<Self, ValidationError> {
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer = config.create().map_err(consumer_error)?;
+        consumer.subscribe(&[topic]).map_err(consumer_error)?;
+        Ok(Self {
+            consumer,
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_window: DEFAULT_BATCH_WINDOW,
+            sampler: ExchangeSampler::default(),
+        })
+    }
+
+    /// Commits after `size` records or once `window` has passed, whichever comes first
+    ///
+    /// Smaller batches replay less after a crash at the cost of more commits.
+    pub fn with_batch(mut self, size: usize, window: Duration) -> Self {
+        self.batch_size = size.max(1);
+        self.batch_window = window;
+        self
+    }
+
+    /// Validates only the records `sampling` admits; the rest still count towards drift rates
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampler = ExchangeSampler::new(sampling);
+        self
+    }
+
+    /// Validates records until `deadline`, or indefinitely without one
+    ///
+    /// Malformed records are skipped and counted as in [`crate::ingest`];
+    /// sink and consumer failures end the run, leaving the current batch
+    /// uncommitted.
+    pub fn run(
+        &self,
+        validator: &ApiValidator,
+        sink: &dyn DriftSink,
+        deadline: Option<SystemTime>,
+    ) -> Result<IngestSummary, ValidationError> {
+        let mut summary = IngestSummary::default();
+        while !past(deadline) {
+            let batch = self.next_batch(deadline)?;
+            if batch.is_empty() {
+                continue;
+            }
+            ingest_into(&mut summary, validator, batch, sink, &self.sampler)?;
+            self.consumer
+                .commit_consumer_state(CommitMode::Sync)
+                .map_err(consumer_error)?;
+        }
+        Ok(summary)
+    }
+
+    fn next_batch(
+        &self,
+        deadline: Option<SystemTime>,
+    ) -> Result<Vec<Result<TrafficRecord, ValidationError>>, ValidationError> {
+        let closes = Instant::now() + self.batch_window;
+        let mut batch = Vec::new();
+        while batch.len() < self.batch_size {
+            let remaining = closes.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || past(deadline) {
+                break;
+            }
+            match self.consumer.poll(remaining.min(POLL_INTERVAL)) {
+                None => {}
+                Some(Ok(message)) => batch.push(parse_message(&message)),
+                // librdkafka reconnects by itself; these only report that it's trying
+                Some(Err(KafkaError::MessageConsumption(
+                    RDKafkaErrorCode::BrokerTransportFailure | RDKafkaErrorCode::AllBrokersDown,
+                ))) => {}
+                Some(Err(e)) => return Err(consumer_error(e)),
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// Reads a message's payload as a traffic record, naming its position on failure
+fn parse_message(message: &BorrowedMessage<'_>) -> Result<TrafficRecord, ValidationError> {
+    let position = format!("{}/{}@{}", message.topic(), message.partition(), message.offset());
+    let payload = match message.payload_view::<str>() {
+        None => return Err(ValidationError::TrafficError(format!("{}: message has no payload", position))),
+        Some(Err(e)) => return Err(ValidationError::TrafficError(format!("{}: {}", position, e))),
+        Some(Ok(payload)) => payload,
+    };
+    JsonlFormat.parse_line(payload.trim()).map_err(|e| match e {
+        ValidationError::TrafficError(message) => ValidationError::TrafficError(format!("{}: {}", position, message)),
+        other => other,
+    })
+}
+
+fn past(deadline: Option<SystemTime>) -> bool {
+    deadline.is_some_and(|deadline| SystemTime::now() >= deadline)
+}
+
+fn consumer_error(e: KafkaError) -> ValidationError {
+    ValidationError::TrafficError(format!("Kafka consumer: {}", e))
+}
+
+/// Publishes drift events as JSON to a Kafka topic
+///
+/// Events are keyed by operation, so each operation's events stay in order
+/// on one partition. Sends are asynchronous; [`DriftSink::flush`] waits for
+/// the brokers to acknowledge them and fails if any were lost.
+pub struct KafkaSink {
+    producer: ThreadedProducer<DeliveryTracker>,
+    topic: String,
+}
+
+impl KafkaSink {
+    /// Publishes to `topic` on `brokers`
+    pub fn connect(brokers: &[String], topic: &str) -> Result<Self, ValidationError> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", brokers.join(","));
+        Self::from_config(config, topic)
+    }
+
+    /// Publishes to `topic` with a client configured by the caller
+    pub fn from_config(config: ClientConfig, topic: &str) -> Result<Self, ValidationError> {
+        let producer = config
+            .create_with_context(DeliveryTracker::default())
+            .map_err(producer_error)?;
+        Ok(Self {
+            producer,
+            topic: topic.to_string(),
+        })
+    }
+}
+
+impl DriftSink for KafkaSink {
+    fn record(&self, event: DriftEvent) -> Result<(), ValidationError> {
+        let line = to_json_line(&event)?;
+        let key = operation_label(&event);
+        let mut record = BaseRecord::to(&self.topic).key(&key).payload(&line);
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => return Ok(()),
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), unsent)) => {
+                    record = unsent;
+                    thread::sleep(QUEUE_FULL_BACKOFF);
+                }
+                Err((e, _)) => return Err(producer_error(e)),
+            }
+        }
+    }
+
+    fn flush(&self) -> Result<(), ValidationError> {
+        self.producer.flush(FLUSH_TIMEOUT).map_err(producer_error)?;
+        match self.producer.context().failure().take() {
+            Some(failure) => Err(ValidationError::SinkError(format!(
+                "Failed to deliver drift events to {}: {}",
+                self.topic, failure
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Remembers the first failed delivery since the last flush
+#[derive(Default)]
+struct DeliveryTracker {
+    failure: Mutex<Option<String>>,
+}
+
+impl DeliveryTracker {
+    /// A poisoned lock only means a delivery callback panicked; the failure it holds is still usable
+    fn failure(&self) -> MutexGuard<'_, Option<String>> {
+        self.failure.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ClientContext for DeliveryTracker {}
+
+impl ProducerContext for DeliveryTracker {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((e, _)) = result {
+            self.failure().get_or_insert_with(|| e.to_string());
+        }
+    }
+}
+
+fn producer_error(e: KafkaError) -> ValidationError {
+    ValidationError::SinkError(format!("Kafka producer: {}", e))
+}
@@ -0,0 +1,10 @@
+//! Continuous validation of traffic streamed through a message broker
+//!
+//! Where [`crate::traffic`] reads logs that have already been written, a
+//! stream source runs for as long as the monitor does, validating records as
+//! the broker delivers them and tracking its position so that a restarted
+//! monitor resumes where the last one stopped.
+
+pub mod kafka;
+
+pub use kafka::{KafkaSink, KafkaSource};
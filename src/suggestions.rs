@@ -0,0 +1,126 @@
+//! Near-miss field name suggestions for rename-style drift
+//!
+//! A field renamed on one side of the contract (`customerId` → `customer_id`)
+//! shows up as a missing required field and/or an undocumented one. Matching
+//! the two by edit distance turns that pair of findings into an obvious
+//! "did you mean" hint.
+
+use crate::validation_helpers::SPEC_BASE_URI;
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::Registry;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Keywords whose values are instances rather than subschemas
+const INSTANCE_KEYWORDS: &[&str] = &["example", "examples", "default", "enum", "const"];
+
+/// Every property name declared anywhere in a schema, following `$ref`s
+pub(crate) fn property_names(schema: &Value, registry: &Registry) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    let mut refs = Vec::new();
+    collect_property_names(schema, registry, &mut refs, &mut names);
+    names.into_iter().collect()
+}
+
+fn collect_property_names(schema: &Value, registry: &Registry, refs: &mut Vec<String>, names: &mut BTreeSet<String>) {
+    match schema {
+        Value::Object(members) => {
+            if let Some(reference) = members.get("$ref").and_then(Value::as_str) {
+                if !refs.iter().any(|seen| seen == reference) {
+                    refs.push(reference.to_string());
+                    if let Some(target) = registry
+                        .try_resolver(SPEC_BASE_URI)
+                        .ok()
+                        .and_then(|resolver| resolver.lookup(reference).ok())
+                    {
+                        collect_property_names(target.contents(), registry, refs, names);
+                    }
+                }
+            }
+            if let Some(Value::Object(properties)) = members.get("properties") {
+                names.extend(properties.keys().cloned());
+            }
+            for (keyword, value) in members {
+                if !INSTANCE_KEYWORDS.contains(&keyword.as_str()) {
+                    collect_property_names(value, registry, refs, names);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_property_names(item, registry, refs, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Suggestions for a schema error, given the object it was raised on and
+/// the property names the schema documents
+///
+/// An undocumented field is matched against documented names the object
+/// doesn't already use; a missing required field against the object's
+/// undocumented fields.
+pub(crate) fn suggest(kind: &ValidationErrorKind, instance: &Value, documented: &[String]) -> Vec<String> {
+    let Value::Object(observed) = instance else {
+        return Vec::new();
+    };
+    let is_documented = |name: &str| documented.binary_search_by(|d| d.as_str().cmp(name)).is_ok();
+
+    match kind {
+        ValidationErrorKind::AdditionalProperties { unexpected } => unexpected
+            .iter()
+            .filter_map(|field| {
+                let unused = documented.iter().map(String::as_str).filter(|name| !observed.contains_key(*name));
+                closest(field, unused).map(|name| format!("`{}`: did you mean `{}`?", field, name))
+            })
+            .collect(),
+        ValidationErrorKind::Required { property } => {
+            let Some(missing) = property.as_str() else {
+                return Vec::new();
+            };
+            let undocumented = observed.keys().map(String::as_str).filter(|name| !is_documented(name));
+            closest(missing, undocumented)
+                .map(|name| vec![format!("`{}`: was it sent as `{}`?", missing, name)])
+                .unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The candidate closest to `name`, if any is close enough to be a likely rename
+///
+/// Case and `_`/`-` separators are ignored, so `customer_id`, `customerId`
+/// and `Customer-ID` are all exact matches.
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let target = fold(name);
+    let max_distance = (target.len() / 3).max(1);
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(&target, &fold(candidate)), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn fold(name: &str) -> Vec<char> {
+    name.chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
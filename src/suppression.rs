@@ -0,0 +1,145 @@
+//! Suppressing known, accepted drift before it leaves the validator
+//!
+//! Some drift is understood and tolerated: a legacy field the spec will
+//! never document, a header a gateway adds. A `.driftignore` file lists
+//! [`SuppressionRule`]s matching such drift by operation, drift type and
+//! location; the validator drops matching events before any sink sees them
+//! and counts them, so reports can say how much was suppressed.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+#[cfg(feature = "monitor")]
+use crate::error::ValidationError;
+use crate::redaction::{matches_segments, FieldPattern};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "monitor")]
+use std::fs;
+#[cfg(feature = "monitor")]
+use std::path::Path;
+
+/// File suppression rules are read from, next to the monitor configuration
+pub const DEFAULT_SUPPRESSIONS_PATH: &str = ".driftignore";
+
+/// Drift that is never reported
+///
+/// Written as a list of rules, each matching events on every criterion it
+/// sets:
+///
+/// ```yaml
+/// - drift_type: RESPONSE_BODY_UNDOCUMENTED_FIELD
+///   operation: GET /users/*
+///   pointer: /users/*/metadata/*
+///   reason: Legacy metadata, documented in v3
+/// - drift_type: UNDOCUMENTED_PARAMETER
+///   pointer: /debug
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Suppressions {
+    rules: Vec<SuppressionRule>,
+}
+
+/// One suppression; criteria left unset match any event
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SuppressionRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift_type: Option<DriftType>,
+    /// Operation label such as `GET /users/{id}`, where the method may be
+    /// `*` or left out and path segments may be `*` (any one) or `**` (any number)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// JSON Pointer into the part of the exchange the drift is in, e.g.
+    /// `/users/*/metadata/*` in a body, `/X-Request-Id` for a header or
+    /// `/limit` for a parameter; `*` matches any one segment and `**` any number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<FieldPattern>,
+    /// Why the drift is accepted, for whoever reads the file next
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SuppressionRule {
+    /// Whether `event` matches every criterion the rule sets
+    pub fn matches(&self, event: &DriftEvent) -> bool {
+        if self.drift_type.is_some_and(|drift_type| drift_type != event.drift_type) {
+            return false;
+        }
+        if let Some(operation) = &self.operation {
+            if !operation_matches(operation, event) {
+                return false;
+            }
+        }
+        match &self.pointer {
+            Some(pointer) => {
+                // Parameters are located by name alone
+                let segments: Vec<&str> = match event.location.split_once('/') {
+                    Some((_, rest)) => rest.split('/').collect(),
+                    None => vec![event.location.as_str()],
+                };
+                pointer.matches(&segments)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Whether the event's operation matches `pattern`, e.g. `GET /users/*`
+fn operation_matches(pattern: &str, event: &DriftEvent) -> bool {
+    let (method, template) = match pattern.trim().split_once(' ') {
+        Some((method, template)) => (method, template.trim()),
+        None => ("*", pattern.trim()),
+    };
+    if method != "*" && !event.method.is_some_and(|m| m.as_str().eq_ignore_ascii_case(method)) {
+        return false;
+    }
+    let Some(path_template) = &event.path_template else {
+        return false;
+    };
+    let pattern: Vec<&str> = template.trim_start_matches('/').split('/').collect();
+    let path: Vec<&str> = path_template.trim_start_matches('/').split('/').collect();
+    matches_segments(&pattern, &path)
+}
+
+impl Suppressions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule(mut self, rule: SuppressionRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Adds the rules of `other` after these
+    pub fn extend(&mut self, other: Suppressions) {
+        self.rules.extend(other.rules);
+    }
+
+    pub fn rules(&self) -> &[SuppressionRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule matches `event`
+    pub fn suppresses(&self, event: &DriftEvent) -> bool {
+        self.rules.iter().any(|rule| rule.matches(event))
+    }
+
+    /// Reads a YAML (or JSON) list of rules
+    #[cfg(feature = "monitor")]
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ValidationError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        // An empty file suppresses nothing
+        if contents.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_yaml::from_str(&contents)
+            .map_err(|e| ValidationError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+}
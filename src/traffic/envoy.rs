@@ -0,0 +1,96 @@
+//! Envoy JSON access logs
+//!
+//! Envoy writes whatever keys its `json_format` names, so the keys read here
+//! are configurable. The defaults match this access log configuration, with
+//! bodies put into dynamic metadata by a Lua or tap filter:
+//!
+//! ```yaml
+//! json_format:
+//!   start_time: "%START_TIME%"
+//!   method: "%REQ(:METHOD)%"
+//!   path: "%REQ(X-ENVOY-ORIGINAL-PATH?:PATH)%"
+//!   response_code: "%RESPONSE_CODE%"
+//!   request_content_type: "%REQ(CONTENT-TYPE)%"
+//!   response_content_type: "%RESP(CONTENT-TYPE)%"
+//!   request_body: "%DYNAMIC_METADATA(envoy.lua:request_body)%"
+//!   response_body: "%DYNAMIC_METADATA(envoy.lua:response_body)%"
+//! ```
+//!
+//! Envoy logs missing values as `null` or `"-"`; both count as absent.
+
+use crate::error::ValidationError;
+use crate::traffic::jsonl::{Timestamp, TrafficRecord};
+use crate::traffic::LogFormat;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Which keys of an Envoy JSON access log entry hold which part of the exchange
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvoyFormat {
+    pub method: String,
+    /// Path including the query string
+    pub path: String,
+    pub status: String,
+    /// RFC 3339 start time
+    pub start_time: String,
+    pub request_body: String,
+    pub response_body: String,
+    /// Request header name → key holding its value
+    pub request_headers: BTreeMap<String, String>,
+    /// Response header name → key holding its value
+    pub response_headers: BTreeMap<String, String>,
+}
+
+impl Default for EnvoyFormat {
+    fn default() -> Self {
+        Self {
+            method: "method".to_string(),
+            path: "path".to_string(),
+            status: "response_code".to_string(),
+            start_time: "start_time".to_string(),
+            request_body: "request_body".to_string(),
+            response_body: "response_body".to_string(),
+            request_headers: BTreeMap::from([("content-type".to_string(), "request_content_type".to_string())]),
+            response_headers: BTreeMap::from([("content-type".to_string(), "response_content_type".to_string())]),
+        }
+    }
+}
+
+impl LogFormat for EnvoyFormat {
+    fn parse_line(&self, line: &str) -> Result<TrafficRecord, ValidationError> {
+        let entry: Map<String, Value> =
+            serde_json::from_str(line).map_err(|e| ValidationError::TrafficError(e.to_string()))?;
+        let field = |key: &str| match entry.get(key) {
+            Some(Value::String(value)) if value != "-" => Some(value.clone()),
+            Some(Value::Number(value)) => Some(value.to_string()),
+            _ => None,
+        };
+        let required = |key: &str| {
+            field(key).ok_or_else(|| ValidationError::TrafficError(format!("no `{}` in access log entry", key)))
+        };
+        let headers = |keys: &BTreeMap<String, String>| {
+            keys.iter()
+                .filter_map(|(name, key)| field(key).map(|value| (name.clone(), value)))
+                .collect()
+        };
+
+        let status = required(&self.status)?;
+        // Envoy logs 0 when no response was sent
+        let status = status
+            .parse()
+            .ok()
+            .filter(|status| *status != 0)
+            .ok_or_else(|| ValidationError::TrafficError(format!("no response (status `{}`)", status)))?;
+        Ok(TrafficRecord {
+            method: required(&self.method)?,
+            path: required(&self.path)?,
+            query: None,
+            request_headers: headers(&self.request_headers),
+            request_body: field(&self.request_body).map(Value::String),
+            status,
+            response_headers: headers(&self.response_headers),
+            response_body: field(&self.response_body).map(Value::String),
+            timestamp: field(&self.start_time).map(Timestamp::Rfc3339),
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! Traffic records as JSON lines (NDJSON)
+//!
+//! One exchange per line:
+//!
+//! ```json
+//! {"method": "GET", "path": "/users/42", "query": "expand=orders",
+//!  "request_headers": {"accept": "application/json"},
+//!  "status": 200, "response_headers": {"content-type": "application/json"},
+//!  "response_body": {"id": 42, "email": "ada@example.com"},
+//!  "timestamp": "2025-06-01T12:30:00Z"}
+//! ```
+//!
+//! Only `method`, `path` and `status` are required. Bodies are either the
+//! JSON document itself or a string holding the raw body text, as logs that
+//! capture bodies verbatim write them. `timestamp` is an RFC 3339 string or
+//! milliseconds since the Unix epoch.
+
+use crate::api_validator::HttpMethod;
+use crate::error::ValidationError;
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse};
+use crate::traffic::{parse_rfc3339, LogFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One observed exchange in the JSONL traffic format
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrafficRecord {
+    pub method: String,
+    /// Request path, optionally followed by `?` and the query string
+    pub path: String,
+    /// Raw query string without the leading `?`, if not part of `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<Value>,
+    /// When the exchange happened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<Timestamp>,
+}
+
+/// When an exchange happened, in either of the forms logs commonly use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Timestamp {
+    /// Milliseconds since the Unix epoch
+    Millis(u64),
+    /// An RFC 3339 date-time, e.g. `2025-06-01T12:30:00.250+02:00`
+    Rfc3339(String),
+}
+
+impl Timestamp {
+    /// Milliseconds since the Unix epoch, or `None` if the text isn't RFC 3339
+    pub fn as_millis(&self) -> Option<u64> {
+        match self {
+            Self::Millis(millis) => Some(*millis),
+            Self::Rfc3339(text) => parse_rfc3339(text),
+        }
+    }
+}
+
+impl TrafficRecord {
+    /// The exchange to validate
+    pub fn to_exchange(&self) -> Result<Exchange, ValidationError> {
+        let method: HttpMethod = self
+            .method
+            .parse()
+            .map_err(|()| ValidationError::TrafficError(format!("unknown method `{}`", self.method)))?;
+
+        let mut request = ObservedRequest::new(method, &self.path);
+        if let Some(query) = &self.query {
+            request.query = Some(query.clone());
+        }
+        for (name, value) in &self.request_headers {
+            request = request.with_header(name, value);
+        }
+        if let Some(body) = &self.request_body {
+            request = request.with_body(body_bytes(body));
+        }
+
+        let mut response = ObservedResponse::new(self.status);
+        for (name, value) in &self.response_headers {
+            response = response.with_header(name, value);
+        }
+        if let Some(body) = &self.response_body {
+            response = response.with_body(body_bytes(body));
+        }
+        Ok(Exchange::new(request, response))
+    }
+
+    /// When the exchange happened, if the record says and the timestamp parses
+    pub fn timestamp_ms(&self) -> Option<u64> {
+        self.timestamp.as_ref().and_then(Timestamp::as_millis)
+    }
+}
+
+/// A string body is the raw text; anything else is the JSON document itself
+fn body_bytes(body: &Value) -> Vec<u8> {
+    match body {
+        Value::String(text) => text.as_bytes().to_vec(),
+        document => document.to_string().into_bytes(),
+    }
+}
+
+/// The JSONL traffic format, one [`TrafficRecord`] per line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonlFormat;
+
+impl LogFormat for JsonlFormat {
+    fn parse_line(&self, line: &str) -> Result<TrafficRecord, ValidationError> {
+        serde_json::from_str(line).map_err(|e| ValidationError::TrafficError(e.to_string()))
+    }
+}
@@ -0,0 +1,279 @@
+//! Offline ingestion of recorded traffic
+//!
+//! A [`LogReader`] turns a log into [`TrafficRecord`]s, the monitor's own
+//! record format, one line at a time; [`ingest`] validates them and
+//! aggregates the drift found. Besides the JSONL format, access logs
+//! already collected from Envoy and Nginx can be read as they are, and with
+//! the `pcap` feature a `PcapReader` recovers exchanges from packet captures.
+
+pub mod envoy;
+pub mod jsonl;
+pub mod nginx;
+#[cfg(feature = "pcap")]
+pub mod pcap;
+
+pub use envoy::EnvoyFormat;
+pub use jsonl::{JsonlFormat, Timestamp, TrafficRecord};
+pub use nginx::NginxFormat;
+#[cfg(feature = "pcap")]
+pub use pcap::PcapReader;
+
+use crate::api_validator::ApiValidator;
+use crate::drift_event::now_ms;
+use crate::error::ValidationError;
+use crate::report::DriftReport;
+use crate::reproducer::ReproducerCapture;
+use crate::sampling::{ExchangeSampler, Stratum};
+use crate::sinks::DriftSink;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// A line-oriented log format
+pub trait LogFormat {
+    /// Parses one non-blank line into a record
+    fn parse_line(&self, line: &str) -> Result<TrafficRecord, ValidationError>;
+}
+
+/// How often a followed log is checked for new lines once its end is reached
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Streams traffic records from a log, one line at a time
+///
+/// Blank lines are skipped. A line that isn't a record yields an error
+/// naming its line number, and reading continues with the next line; a
+/// read failure yields an error and ends the stream.
+pub struct LogReader<R, F> {
+    reader: R,
+    format: F,
+    line: usize,
+    /// A line read so far, possibly still missing its end while following
+    pending: String,
+    follow: bool,
+    until: Option<SystemTime>,
+    failed: bool,
+}
+
+impl<F: LogFormat> LogReader<BufReader<File>, F> {
+    /// Opens a log file
+    pub fn open(path: &Path, format: F) -> Result<Self, ValidationError> {
+        let file = File::open(path)
+            .map_err(|e| ValidationError::TrafficError(format!("Failed to open {}: {}", path.display(), e)))?;
+        Ok(Self::new(BufReader::new(file), format))
+    }
+}
+
+impl<R: BufRead, F: LogFormat> LogReader<R, F> {
+    pub fn new(reader: R, format: F) -> Self {
+        Self {
+            reader,
+            format,
+            line: 0,
+            pending: String::new(),
+            follow: false,
+            until: None,
+            failed: false,
+        }
+    }
+
+    /// Waits for lines appended to the log instead of ending at its current end, like `tail -f`
+    ///
+    /// A followed log only ends at the deadline set with [`LogReader::until`].
+    pub fn follow(mut self) -> Self {
+        self.follow = true;
+        self
+    }
+
+    /// Ends the stream at `deadline`, even if the log has more lines
+    pub fn until(mut self, deadline: SystemTime) -> Self {
+        self.until = Some(deadline);
+        self
+    }
+
+    fn past_deadline(&self) -> bool {
+        self.until.is_some_and(|until| SystemTime::now() >= until)
+    }
+}
+
+impl<R: BufRead, F: LogFormat> Iterator for LogReader<R, F> {
+    type Item = Result<TrafficRecord, ValidationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.failed || self.past_deadline() {
+                return None;
+            }
+            match self.reader.read_line(&mut self.pending) {
+                Ok(0) if self.follow => {
+                    thread::sleep(FOLLOW_POLL_INTERVAL);
+                    continue;
+                }
+                Ok(0) if self.pending.is_empty() => return None,
+                // The writer hasn't finished the line yet
+                Ok(_) if self.follow && !self.pending.ends_with('\n') => continue,
+                Ok(_) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(ValidationError::TrafficError(format!("line {}: {}", self.line + 1, e))));
+                }
+            }
+            self.line += 1;
+            let line = std::mem::take(&mut self.pending);
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(self.format.parse_line(line.trim_end_matches(['\n', '\r'])).map_err(|e| match e {
+                ValidationError::TrafficError(message) => {
+                    ValidationError::TrafficError(format!("line {}: {}", self.line, message))
+                }
+                other => other,
+            }));
+        }
+    }
+}
+
+/// Malformed records whose errors are kept for the summary; the rest are only counted
+pub(crate) const MAX_KEPT_ERRORS: usize = 20;
+
+/// Outcome of ingesting a traffic log
+#[derive(Debug, Clone, Default)]
+pub struct IngestSummary {
+    /// Records read, malformed ones included
+    pub records: usize,
+    /// Records skipped because they couldn't be read or turned into an exchange
+    pub malformed: usize,
+    /// Why the first malformed records were skipped
+    pub errors: Vec<String>,
+    /// Drift aggregated per operation
+    pub report: DriftReport,
+}
+
+/// Validates every record, sending drift to `sink` and aggregating it into a report
+///
+/// Malformed records are skipped and counted rather than failing the run,
+/// so one bad line doesn't stop a multi-gigabyte log. Records outside the
+/// spec are skipped as the validator's routing dictates. Events and the
+/// report's drift rates take the record's timestamp when it has one, so
+/// reports bucket drift by when the traffic happened rather than when it
+/// was replayed. The report keeps the first exchange seen for each drift
+/// fingerprint as its reproducer. Sink failures are returned.
+pub fn ingest<I>(validator: &ApiValidator, records: I, sink: &dyn DriftSink) -> Result<IngestSummary, ValidationError>
+where
+    I: IntoIterator<Item = Result<TrafficRecord, ValidationError>>,
+{
+    ingest_sampled(validator, records, sink, &ExchangeSampler::default())
+}
+
+/// [`ingest`] validating only the exchanges `sampler` admits
+///
+/// Skipped exchanges still count towards the report's drift rates.
+pub fn ingest_sampled<I>(
+    validator: &ApiValidator,
+    records: I,
+    sink: &dyn DriftSink,
+    sampler: &ExchangeSampler,
+) -> Result<IngestSummary, ValidationError>
+where
+    I: IntoIterator<Item = Result<TrafficRecord, ValidationError>>,
+{
+    let mut summary = IngestSummary::default();
+    ingest_into(&mut summary, validator, records, sink, sampler)?;
+    Ok(summary)
+}
+
+/// [`ingest_sampled`] adding to an existing summary, for sources read in batches
+pub(crate) fn ingest_into<I>(
+    summary: &mut IngestSummary,
+    validator: &ApiValidator,
+    records: I,
+    sink: &dyn DriftSink,
+    sampler: &ExchangeSampler,
+) -> Result<(), ValidationError>
+where
+    I: IntoIterator<Item = Result<TrafficRecord, ValidationError>>,
+{
+    let reproducers = ReproducerCapture::from_reproducers(std::mem::take(&mut summary.report.reproducers))
+        .with_redaction(validator.redaction().clone());
+    let suppressed = validator.suppressed_events();
+    for record in records {
+        summary.records += 1;
+        let parsed = record.and_then(|record| Ok((record.to_exchange()?, record.timestamp_ms())));
+        let (exchange, timestamp_ms) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                summary.malformed += 1;
+                if summary.errors.len() < MAX_KEPT_ERRORS {
+                    summary.errors.push(e.to_string());
+                }
+                continue;
+            }
+        };
+
+        let Some(template) = validator.path_template(&exchange.request.path) else {
+            continue;
+        };
+        let method = exchange.request.method;
+        let operation = format!("{} {}", method.as_str(), template);
+        let status = exchange.response.status;
+        let observed_ms = timestamp_ms.unwrap_or_else(now_ms);
+        if !sampler.admit(&operation, status, observed_ms) {
+            summary.report.record_sampled_at(method, template, observed_ms, Stratum::of(status), None);
+            continue;
+        }
+        let Ok(mut events) = validator.validate_exchange(&exchange) else {
+            continue;
+        };
+        if timestamp_ms.is_some() {
+            events.iter_mut().for_each(|event| event.timestamp_ms = observed_ms);
+        }
+        sink.record_all(events.clone())?;
+        sink.observe_exchange(&operation, observed_ms, &events);
+        reproducers.capture(&exchange, &events);
+        summary
+            .report
+            .record_sampled_at(method, template, observed_ms, Stratum::of(status), Some(events));
+    }
+    summary.report.reproducers = reproducers.reproducers();
+    summary.report.summary.suppressed += validator.suppressed_events().saturating_sub(suppressed);
+    sink.flush()
+}
+
+/// Milliseconds since the Unix epoch for an RFC 3339 timestamp, e.g. `2025-06-01T12:30:00.250Z`
+pub(crate) fn parse_rfc3339(value: &str) -> Option<u64> {
+    let (date, time) = value.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (date_parts.next()?.ok()?, date_parts.next()?.ok()?, date_parts.next()?.ok()?);
+
+    let offset_at = time.find(['Z', 'z', '+', '-'])?;
+    let (clock, offset) = time.split_at(offset_at);
+    let offset_minutes = match offset {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = offset[1..].split_once(':')?;
+            sign * (hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?)
+        }
+    };
+    let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+    let mut clock_parts = clock.splitn(3, ':').map(str::parse::<i64>);
+    let (hour, minute, second) = (clock_parts.next()?.ok()?, clock_parts.next()?.ok()?, clock_parts.next()?.ok()?);
+    let millis = format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse::<i64>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second - offset_minutes * 60;
+    u64::try_from(seconds * 1_000 + millis).ok()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,263 @@
+//! Nginx access logs in a `log_format` of your choosing
+//!
+//! The format string from `nginx.conf` is given as is and each line is
+//! matched against it. Stock Nginx logs only the request body
+//! (`$request_body`); the response body needs a variable set by Lua or njs,
+//! `$resp_body` by default. [`NGINX_BODY_LOG_FORMAT`] is the `combined`
+//! format with both bodies appended.
+//!
+//! Recognised variables: `$request` (or `$request_method` with
+//! `$request_uri`, or `$uri` and `$args`), `$status`, `$time_iso8601`,
+//! `$time_local` or `$msec`, `$request_body`, the response body variable,
+//! `$content_type`, `$http_<name>` for request headers and
+//! `$sent_http_<name>` for response headers. Values are unescaped from both
+//! the default (`\xHH`) and `escape=json` styles; `-` counts as absent.
+
+use crate::error::ValidationError;
+use crate::traffic::jsonl::{Timestamp, TrafficRecord};
+use crate::traffic::LogFormat;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// `combined` with the request and response bodies appended
+pub const NGINX_BODY_LOG_FORMAT: &str = r#"$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent" "$request_body" "$resp_body""#;
+
+/// Response body variable used unless another is configured
+pub const DEFAULT_RESPONSE_BODY_VARIABLE: &str = "resp_body";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Variable(String),
+}
+
+/// A parsed Nginx `log_format`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NginxFormat {
+    segments: Vec<Segment>,
+    response_body_variable: String,
+}
+
+impl NginxFormat {
+    /// Parses a `log_format` string, which must log at least the request line and status
+    pub fn parse(log_format: &str) -> Result<Self, ValidationError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = log_format.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                literal.push(c);
+                continue;
+            }
+            let braced = chars.next_if_eq(&'{').is_some();
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+            if braced && chars.next_if_eq(&'}').is_none() {
+                return Err(format_error(format!("unclosed `${{{}`", name)));
+            }
+            if name.is_empty() {
+                literal.push('$');
+                continue;
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            } else if matches!(segments.last(), Some(Segment::Variable(_))) {
+                return Err(format_error(format!(
+                    "`${}` directly follows another variable, so the two can't be told apart",
+                    name
+                )));
+            }
+            segments.push(Segment::Variable(name));
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        let format = Self {
+            segments,
+            response_body_variable: DEFAULT_RESPONSE_BODY_VARIABLE.to_string(),
+        };
+        let has = |name: &str| format.segments.contains(&Segment::Variable(name.to_string()));
+        if !(has("request") || has("request_method") && (has("request_uri") || has("uri"))) {
+            return Err(format_error(
+                "log format has no `$request`, or `$request_method` with `$request_uri` or `$uri`".to_string(),
+            ));
+        }
+        if !has("status") {
+            return Err(format_error("log format has no `$status`".to_string()));
+        }
+        Ok(format)
+    }
+
+    /// Reads the response body from `$<name>` instead of `$resp_body`
+    pub fn with_response_body_variable(mut self, name: &str) -> Self {
+        self.response_body_variable = name.trim_start_matches('$').to_string();
+        self
+    }
+
+    /// Splits a line into raw variable values
+    fn match_line<'a>(&self, line: &'a str) -> Result<HashMap<&str, &'a str>, ValidationError> {
+        let mut values = HashMap::new();
+        let mut rest = line;
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(literal) => {
+                    rest = rest.strip_prefix(literal.as_str()).ok_or_else(|| {
+                        ValidationError::TrafficError(format!("expected `{}` at `{}`", literal, preview(rest)))
+                    })?;
+                }
+                Segment::Variable(name) => {
+                    let end = match self.segments.get(index + 1) {
+                        Some(Segment::Literal(next)) => rest.find(next.as_str()).ok_or_else(|| {
+                            ValidationError::TrafficError(format!("expected `{}` after `${}`", next, name))
+                        })?,
+                        _ => rest.len(),
+                    };
+                    values.insert(name.as_str(), &rest[..end]);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+impl Default for NginxFormat {
+    fn default() -> Self {
+        Self::parse(NGINX_BODY_LOG_FORMAT).expect("built-in log format is valid")
+    }
+}
+
+impl LogFormat for NginxFormat {
+    fn parse_line(&self, line: &str) -> Result<TrafficRecord, ValidationError> {
+        let raw = self.match_line(line)?;
+        let value = |name: &str| {
+            raw.get(name)
+                .filter(|value| **value != "-" && !value.is_empty())
+                .map(|value| unescape(value))
+        };
+        let missing = |what: &str| ValidationError::TrafficError(format!("no {} in log line", what));
+
+        let (method, path) = match value("request") {
+            Some(request) => {
+                let mut parts = request.split_whitespace();
+                let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+                    return Err(ValidationError::TrafficError(format!("malformed request line `{}`", request)));
+                };
+                (method.to_string(), target.to_string())
+            }
+            None => {
+                let method = value("request_method").ok_or_else(|| missing("request method"))?;
+                let path = match (value("request_uri"), value("uri"), value("args")) {
+                    (Some(uri), _, _) => uri,
+                    (None, Some(uri), Some(args)) => format!("{}?{}", uri, args),
+                    (None, Some(uri), None) => uri,
+                    (None, None, _) => return Err(missing("request path")),
+                };
+                (method, path)
+            }
+        };
+        let status = value("status").ok_or_else(|| missing("status"))?;
+
+        let mut request_headers = BTreeMap::new();
+        let mut response_headers = BTreeMap::new();
+        for name in raw.keys().copied() {
+            let Some(header_value) = value(name) else {
+                continue;
+            };
+            if let Some(header) = name.strip_prefix("sent_http_") {
+                response_headers.insert(header.replace('_', "-"), header_value);
+            } else if let Some(header) = name.strip_prefix("http_") {
+                request_headers.insert(header.replace('_', "-"), header_value);
+            } else if name == "content_type" {
+                request_headers.insert("content-type".to_string(), header_value);
+            }
+        }
+
+        let timestamp = value("time_iso8601")
+            .map(Timestamp::Rfc3339)
+            .or_else(|| value("time_local").and_then(|time| time_local_to_rfc3339(&time)).map(Timestamp::Rfc3339))
+            .or_else(|| value("msec").and_then(|msec| msec_to_millis(&msec)).map(Timestamp::Millis));
+
+        Ok(TrafficRecord {
+            method,
+            path,
+            query: None,
+            request_headers,
+            request_body: value("request_body").map(Value::String),
+            status: status
+                .parse()
+                .map_err(|_| ValidationError::TrafficError(format!("invalid status `{}`", status)))?,
+            response_headers,
+            response_body: value(&self.response_body_variable).map(Value::String),
+            timestamp,
+        })
+    }
+}
+
+fn format_error(message: String) -> ValidationError {
+    ValidationError::ConfigError(format!("Invalid Nginx log format: {}", message))
+}
+
+/// The start of the unmatched rest of a line, for error messages
+fn preview(rest: &str) -> &str {
+    rest.char_indices().nth(40).map_or(rest, |(end, _)| &rest[..end])
+}
+
+/// Undoes Nginx's `\xHH` escaping and `escape=json` escaping
+fn unescape(value: &str) -> String {
+    if !value.contains('\\') {
+        return value.to_string();
+    }
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(at) = rest.find('\\') {
+        bytes.extend_from_slice(&rest.as_bytes()[..at]);
+        let escape = &rest[at + 1..];
+        let (decoded, consumed): (Vec<u8>, usize) = match escape.chars().next() {
+            Some('x') => match escape.get(1..3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => (vec![byte], 3),
+                None => (b"\\".to_vec(), 0),
+            },
+            Some('u') => match escape
+                .get(1..5)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32)
+            {
+                Some(c) => (c.to_string().into_bytes(), 5),
+                None => (b"\\".to_vec(), 0),
+            },
+            Some('n') => (b"\n".to_vec(), 1),
+            Some('r') => (b"\r".to_vec(), 1),
+            Some('t') => (b"\t".to_vec(), 1),
+            Some('b') => (vec![0x08], 1),
+            Some('f') => (vec![0x0c], 1),
+            Some(c @ ('"' | '\\' | '/')) => (vec![c as u8], 1),
+            _ => (b"\\".to_vec(), 0),
+        };
+        bytes.extend_from_slice(&decoded);
+        rest = &escape[consumed..];
+    }
+    bytes.extend_from_slice(rest.as_bytes());
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// `01/Jun/2025:12:30:00 +0200` as `2025-06-01T12:30:00+02:00`
+fn time_local_to_rfc3339(time: &str) -> Option<String> {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let (date_time, offset) = time.split_once(' ')?;
+    let mut parts = date_time.splitn(4, [':', '/']);
+    let (day, month, year) = (parts.next()?, parts.next()?, parts.next()?);
+    let clock = parts.next()?;
+    let month = MONTHS.iter().position(|name| *name == month)? + 1;
+    let (hours, minutes) = (offset.get(..3)?, offset.get(3..)?);
+    Some(format!("{}-{:02}-{}T{}{}:{}", year, month, day, clock, hours, minutes))
+}
+
+/// `$msec` (seconds with millisecond resolution) as milliseconds
+fn msec_to_millis(msec: &str) -> Option<u64> {
+    let (seconds, millis) = msec.split_once('.').unwrap_or((msec, "0"));
+    Some(seconds.parse::<u64>().ok()? * 1_000 + format!("{:0<3}", millis).get(..3)?.parse::<u64>().ok()?)
+}
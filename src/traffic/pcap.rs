@@ -0,0 +1,841 @@
+//! HTTP/1.x exchanges reassembled from packet captures
+//!
+//! Reads classic pcap and pcapng files as written by tcpdump, Wireshark or a
+//! network tap, reassembles both directions of each TCP connection and
+//! parses the HTTP/1.x messages in them, pairing responses with requests in
+//! order as pipelining requires. Ethernet (VLAN-tagged or not), Linux
+//! cooked, BSD loopback and raw IP captures over IPv4 and IPv6 are read.
+//!
+//! Only cleartext HTTP can be read: capture behind TLS termination. Records
+//! carry bodies as raw text and the time the request started.
+
+use crate::error::ValidationError;
+use crate::traffic::jsonl::{Timestamp, TrafficRecord};
+use httparse::Status;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+
+/// Headers parsed per HTTP message
+const MAX_HEADERS: usize = 128;
+
+/// Bytes held per direction of a connection waiting for a missing segment
+/// or the rest of a message; past this the direction is given up on
+const MAX_BUFFERED: usize = 64 * 1024 * 1024;
+
+/// Largest packet read, tcpdump's maximum snapshot length; a record
+/// claiming more is taken as corruption rather than allocated for
+const MAX_PACKET_BYTES: usize = 256 * 1024;
+
+/// Largest pcapng block read: a packet of the largest size plus room for
+/// the block's fields and options
+const MAX_BLOCK_BYTES: usize = MAX_PACKET_BYTES + 64 * 1024;
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+
+/// Streams traffic records out of a packet capture
+///
+/// Exchanges are yielded as their responses complete. HTTP that can't be
+/// parsed, responses whose request wasn't captured and requests left
+/// without a response yield errors naming the connection; reading goes on.
+/// A truncated or corrupt capture yields an error and ends the stream.
+pub struct PcapReader<R> {
+    reader: R,
+    format: CaptureFormat,
+    connections: HashMap<(SocketAddr, SocketAddr), Connection>,
+    ready: VecDeque<Result<TrafficRecord, ValidationError>>,
+    done: bool,
+}
+
+enum CaptureFormat {
+    Pcap {
+        big_endian: bool,
+        nanos: bool,
+        link_type: u32,
+        /// Largest record the file header allows, capped at [`MAX_PACKET_BYTES`]
+        snaplen: usize,
+    },
+    PcapNg {
+        big_endian: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+struct Interface {
+    link_type: u32,
+    ticks_per_second: u64,
+}
+
+struct Packet {
+    link_type: u32,
+    timestamp_ms: Option<u64>,
+    data: Vec<u8>,
+}
+
+impl PcapReader<BufReader<File>> {
+    /// Opens a `.pcap` or `.pcapng` file
+    pub fn open(path: &Path) -> Result<Self, ValidationError> {
+        let file = File::open(path)
+            .map_err(|e| ValidationError::TrafficError(format!("Failed to open {}: {}", path.display(), e)))?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the capture's file header, telling pcap from pcapng by its magic number
+    pub fn new(mut reader: R) -> Result<Self, ValidationError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(capture_error)?;
+        let format = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (PCAPNG_SECTION_HEADER, _) => {
+                let mut format = CaptureFormat::PcapNg {
+                    big_endian: false,
+                    interfaces: Vec::new(),
+                };
+                read_section_header(&mut reader, &mut format)?;
+                format
+            }
+            (magic, swapped) => {
+                let (big_endian, nanos) = match (magic, swapped) {
+                    (PCAP_MAGIC_MICROS, _) => (false, false),
+                    (PCAP_MAGIC_NANOS, _) => (false, true),
+                    (_, PCAP_MAGIC_MICROS) => (true, false),
+                    (_, PCAP_MAGIC_NANOS) => (true, true),
+                    _ => {
+                        return Err(ValidationError::TrafficError(
+                            "not a pcap or pcapng capture".to_string(),
+                        ))
+                    }
+                };
+                let mut header = [0; 20];
+                reader.read_exact(&mut header).map_err(capture_error)?;
+                // Some writers leave the snapshot length zero
+                let snaplen = match read_u32(&header[12..], big_endian) as usize {
+                    0 => MAX_PACKET_BYTES,
+                    snaplen => snaplen.min(MAX_PACKET_BYTES),
+                };
+                CaptureFormat::Pcap {
+                    big_endian,
+                    nanos,
+                    link_type: read_u32(&header[16..], big_endian),
+                    snaplen,
+                }
+            }
+        };
+        Ok(Self {
+            reader,
+            format,
+            connections: HashMap::new(),
+            ready: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Option<Packet>, ValidationError> {
+        match &mut self.format {
+            CaptureFormat::Pcap {
+                big_endian,
+                nanos,
+                link_type,
+                snaplen,
+            } => {
+                let mut header = [0; 16];
+                if !read_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+                let seconds = u64::from(read_u32(&header, *big_endian));
+                let fraction = u64::from(read_u32(&header[4..], *big_endian));
+                let length = read_u32(&header[8..], *big_endian) as usize;
+                if length > *snaplen {
+                    return Err(ValidationError::TrafficError(format!(
+                        "corrupt pcap record length {} (snapshot length {})",
+                        length, snaplen
+                    )));
+                }
+                let mut data = vec![0; length];
+                self.reader.read_exact(&mut data).map_err(capture_error)?;
+                let millis = if *nanos { fraction / 1_000_000 } else { fraction / 1_000 };
+                Ok(Some(Packet {
+                    link_type: *link_type,
+                    timestamp_ms: Some(seconds * 1_000 + millis),
+                    data,
+                }))
+            }
+            CaptureFormat::PcapNg { .. } => self.next_pcapng_packet(),
+        }
+    }
+
+    fn next_pcapng_packet(&mut self) -> Result<Option<Packet>, ValidationError> {
+        loop {
+            let mut header = [0; 8];
+            if !read_or_eof(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+            if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) == PCAPNG_SECTION_HEADER {
+                // Each section states its own byte order and interfaces
+                let mut rest = &header[4..];
+                let mut format = CaptureFormat::PcapNg {
+                    big_endian: false,
+                    interfaces: Vec::new(),
+                };
+                read_section_header(&mut (&mut rest).chain(&mut self.reader), &mut format)?;
+                self.format = format;
+                continue;
+            }
+            let CaptureFormat::PcapNg { big_endian, interfaces } = &mut self.format else {
+                unreachable!("pcapng blocks are only read from pcapng captures");
+            };
+            let big_endian = *big_endian;
+            let block_type = read_u32(&header, big_endian);
+            let length = read_u32(&header[4..], big_endian) as usize;
+            if length < 12 || !length.is_multiple_of(4) || length > MAX_BLOCK_BYTES {
+                return Err(ValidationError::TrafficError(format!("corrupt pcapng block length {}", length)));
+            }
+            let mut body = vec![0; length - 12];
+            self.reader.read_exact(&mut body).map_err(capture_error)?;
+            self.reader.read_exact(&mut [0; 4]).map_err(capture_error)?;
+
+            match block_type {
+                // Interface description
+                1 if body.len() >= 8 => {
+                    let mut interface = Interface {
+                        link_type: u32::from(read_u16(&body, big_endian)),
+                        ticks_per_second: 1_000_000,
+                    };
+                    for (code, value) in pcapng_options(&body[8..], big_endian) {
+                        // if_tsresol: a power of ten, or of two with the top bit set
+                        if let (9, Some(&resolution)) = (code, value.first()) {
+                            interface.ticks_per_second = if resolution & 0x80 == 0 {
+                                10u64.saturating_pow(u32::from(resolution))
+                            } else {
+                                2u64.saturating_pow(u32::from(resolution & 0x7f))
+                            };
+                        }
+                    }
+                    interfaces.push(interface);
+                }
+                // Enhanced packet
+                6 if body.len() >= 20 => {
+                    let interface = interfaces.get(read_u32(&body, big_endian) as usize).ok_or_else(|| {
+                        ValidationError::TrafficError("pcapng packet names an undescribed interface".to_string())
+                    })?;
+                    let ticks = u64::from(read_u32(&body[4..], big_endian)) << 32
+                        | u64::from(read_u32(&body[8..], big_endian));
+                    let captured = (read_u32(&body[12..], big_endian) as usize).min(body.len() - 20);
+                    let timestamp_ms = (u128::from(ticks) * 1_000 / u128::from(interface.ticks_per_second.max(1))) as u64;
+                    return Ok(Some(Packet {
+                        link_type: interface.link_type,
+                        timestamp_ms: Some(timestamp_ms),
+                        data: body[20..20 + captured].to_vec(),
+                    }));
+                }
+                // Simple packet, always from the first interface and without a timestamp
+                3 if body.len() >= 4 => {
+                    let Some(interface) = interfaces.first() else {
+                        continue;
+                    };
+                    let captured = (read_u32(&body, big_endian) as usize).min(body.len() - 4);
+                    return Ok(Some(Packet {
+                        link_type: interface.link_type,
+                        timestamp_ms: None,
+                        data: body[4..4 + captured].to_vec(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: &Packet) {
+        let Some(segment) = decode_segment(packet.link_type, &packet.data) else {
+            return;
+        };
+        let key = if segment.source < segment.destination {
+            (segment.source, segment.destination)
+        } else {
+            (segment.destination, segment.source)
+        };
+        let connection = self.connections.entry(key).or_default();
+        let side = usize::from(segment.source != key.0);
+        connection.directions[side].accept(&segment, packet.timestamp_ms);
+        connection.advance(key, &mut self.ready);
+        if connection.directions.iter().all(|direction| direction.closed) {
+            self.close(key);
+        }
+    }
+
+    /// Completes what the end of a connection completes and reports what it leaves unanswered
+    fn close(&mut self, key: (SocketAddr, SocketAddr)) {
+        let Some(mut connection) = self.connections.remove(&key) else {
+            return;
+        };
+        connection.directions.iter_mut().for_each(|direction| direction.closed = true);
+        connection.advance(key, &mut self.ready);
+        if let Some(client) = connection.client_side() {
+            let flow = flow_label(key, client);
+            for request in &connection.requests {
+                self.ready.push_back(Err(ValidationError::TrafficError(format!(
+                    "{}: no response captured for {} {}",
+                    flow, request.method, request.path
+                ))));
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<TrafficRecord, ValidationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.ready.pop_front() {
+                return Some(record);
+            }
+            if self.done {
+                return None;
+            }
+            match self.next_packet() {
+                Ok(Some(packet)) => self.handle(&packet),
+                Ok(None) => {
+                    let open: Vec<_> = self.connections.keys().copied().collect();
+                    open.into_iter().for_each(|key| self.close(key));
+                    self.done = true;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Reads the rest of a pcapng section header block after its type, learning the byte order
+fn read_section_header(reader: &mut impl Read, format: &mut CaptureFormat) -> Result<(), ValidationError> {
+    let mut header = [0; 8];
+    reader.read_exact(&mut header).map_err(capture_error)?;
+    let big_endian = match u32::from_le_bytes([header[4], header[5], header[6], header[7]]) {
+        PCAPNG_BYTE_ORDER_MAGIC => false,
+        magic if magic.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => true,
+        _ => return Err(ValidationError::TrafficError("corrupt pcapng section header".to_string())),
+    };
+    let length = read_u32(&header, big_endian) as usize;
+    if length < 12 {
+        return Err(ValidationError::TrafficError("corrupt pcapng section header".to_string()));
+    }
+    // The block's remaining fields, options and trailing length are of no use here
+    io::copy(&mut reader.take((length - 12) as u64), &mut io::sink()).map_err(capture_error)?;
+    *format = CaptureFormat::PcapNg {
+        big_endian,
+        interfaces: Vec::new(),
+    };
+    Ok(())
+}
+
+/// Option code and value pairs of a pcapng block
+fn pcapng_options(mut options: &[u8], big_endian: bool) -> Vec<(u16, &[u8])> {
+    let mut parsed = Vec::new();
+    while options.len() >= 4 {
+        let code = read_u16(options, big_endian);
+        let length = usize::from(read_u16(&options[2..], big_endian));
+        if code == 0 || options.len() < 4 + length {
+            break;
+        }
+        parsed.push((code, &options[4..4 + length]));
+        options = &options[(4 + length.div_ceil(4) * 4).min(options.len())..];
+    }
+    parsed
+}
+
+/// Fills `buffer`, or returns `false` at a clean end of input
+fn read_or_eof(reader: &mut impl Read, buffer: &mut [u8]) -> Result<bool, ValidationError> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(ValidationError::TrafficError("capture ends mid-record".to_string())),
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(capture_error(e)),
+        }
+    }
+    Ok(true)
+}
+
+fn capture_error(e: io::Error) -> ValidationError {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => ValidationError::TrafficError("capture ends mid-record".to_string()),
+        _ => ValidationError::TrafficError(format!("Failed to read capture: {}", e)),
+    }
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+    let bytes = [bytes[0], bytes[1]];
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// A TCP segment and where it was going
+struct Segment<'a> {
+    source: SocketAddr,
+    destination: SocketAddr,
+    sequence: u32,
+    flags: u8,
+    payload: &'a [u8],
+}
+
+/// Unwraps a captured frame down to its TCP segment, if it carries one
+fn decode_segment(link_type: u32, frame: &[u8]) -> Option<Segment<'_>> {
+    let be16 = |bytes: &[u8], at: usize| Some(u16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]));
+    let (ether_type, ip) = match link_type {
+        // Ethernet, skipping 802.1Q and 802.1ad tags
+        1 => {
+            let mut offset = 12;
+            let mut ether_type = be16(frame, offset)?;
+            while ether_type == 0x8100 || ether_type == 0x88a8 {
+                offset += 4;
+                ether_type = be16(frame, offset)?;
+            }
+            (ether_type, frame.get(offset + 2..)?)
+        }
+        // BSD loopback, with the address family in host byte order
+        0 | 108 => {
+            let family = frame.get(..4)?;
+            let family = u32::from_le_bytes(family.try_into().ok()?).min(u32::from_be_bytes(family.try_into().ok()?));
+            let ether_type = match family {
+                2 => 0x0800,
+                24 | 28 | 30 => 0x86dd,
+                _ => return None,
+            };
+            (ether_type, frame.get(4..)?)
+        }
+        // Linux cooked v1 and v2
+        113 => (be16(frame, 14)?, frame.get(16..)?),
+        276 => (be16(frame, 0)?, frame.get(20..)?),
+        // Raw IP
+        12 | 14 | 101 | 228 | 229 => match frame.first()? >> 4 {
+            4 => (0x0800, frame),
+            6 => (0x86dd, frame),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let (source, destination, tcp) = match ether_type {
+        0x0800 => {
+            let header_length = usize::from(ip.first()? & 0x0f) * 4;
+            let total_length = usize::from(be16(ip, 2)?);
+            let fragment = be16(ip, 6)?;
+            // Fragments (more to come, or not the first) and non-TCP are skipped
+            if *ip.get(9)? != 6 || fragment & 0x3fff != 0 || header_length < 20 {
+                return None;
+            }
+            let address = |at: usize| -> Option<IpAddr> {
+                let octets: [u8; 4] = ip.get(at..at + 4)?.try_into().ok()?;
+                Some(IpAddr::V4(Ipv4Addr::from(octets)))
+            };
+            (address(12)?, address(16)?, ip.get(header_length..total_length.min(ip.len()))?)
+        }
+        0x86dd => {
+            // Extension headers before TCP are rare on HTTP traffic and not followed
+            if *ip.get(6)? != 6 {
+                return None;
+            }
+            let payload_length = usize::from(be16(ip, 4)?);
+            let address = |at: usize| -> Option<IpAddr> {
+                let octets: [u8; 16] = ip.get(at..at + 16)?.try_into().ok()?;
+                Some(IpAddr::V6(Ipv6Addr::from(octets)))
+            };
+            (address(8)?, address(24)?, ip.get(40..(40 + payload_length).min(ip.len()))?)
+        }
+        _ => return None,
+    };
+
+    let data_offset = usize::from(tcp.get(12)? >> 4) * 4;
+    Some(Segment {
+        source: SocketAddr::new(source, be16(tcp, 0)?),
+        destination: SocketAddr::new(destination, be16(tcp, 2)?),
+        sequence: u32::from_be_bytes(tcp.get(4..8)?.try_into().ok()?),
+        flags: *tcp.get(13)?,
+        payload: tcp.get(data_offset..)?,
+    })
+}
+
+/// Which way a direction's bytes go in HTTP terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Role {
+    /// Too little seen to tell
+    #[default]
+    Unknown,
+    Client,
+    Server,
+    /// Not HTTP, or no longer parseable
+    Ignored,
+}
+
+/// One direction of a TCP connection, reassembled in sequence order
+#[derive(Default)]
+struct Direction {
+    role: Role,
+    /// Sequence number of the next byte expected
+    next: Option<u32>,
+    /// Segments received ahead of a gap, by sequence number
+    ahead: BTreeMap<u32, Vec<u8>>,
+    buffer: Vec<u8>,
+    /// When the first byte of `buffer` was captured
+    buffer_started_ms: Option<u64>,
+    closed: bool,
+}
+
+impl Direction {
+    fn accept(&mut self, segment: &Segment<'_>, timestamp_ms: Option<u64>) {
+        let mut sequence = segment.sequence;
+        if segment.flags & TCP_SYN != 0 {
+            sequence = sequence.wrapping_add(1);
+            self.next = Some(sequence);
+        }
+        if segment.flags & (TCP_FIN | TCP_RST) != 0 {
+            self.closed = true;
+        }
+        if segment.payload.is_empty() || self.role == Role::Ignored {
+            return;
+        }
+        if self.buffer.is_empty() && self.ahead.is_empty() {
+            self.buffer_started_ms = timestamp_ms;
+        }
+        let next = *self.next.get_or_insert(sequence);
+        if (sequence.wrapping_sub(next) as i32) > 0 {
+            self.ahead.insert(sequence, segment.payload.to_vec());
+        } else {
+            self.append(sequence, segment.payload);
+        }
+        while let Some((&sequence, _)) = self
+            .ahead
+            .iter()
+            .find(|(sequence, _)| (sequence.wrapping_sub(self.next.unwrap_or(**sequence)) as i32) <= 0)
+        {
+            let payload = self.ahead.remove(&sequence).unwrap_or_default();
+            self.append(sequence, &payload);
+        }
+        let ahead: usize = self.ahead.values().map(Vec::len).sum();
+        if self.buffer.len() + ahead > MAX_BUFFERED {
+            self.give_up();
+        }
+    }
+
+    /// Appends the part of a segment past what has already been received
+    fn append(&mut self, sequence: u32, payload: &[u8]) {
+        let next = self.next.unwrap_or(sequence);
+        let already = next.wrapping_sub(sequence) as usize;
+        if already < payload.len() {
+            self.buffer.extend_from_slice(&payload[already..]);
+            self.next = Some(next.wrapping_add((payload.len() - already) as u32));
+        }
+    }
+
+    fn consume(&mut self, length: usize, timestamp_ms: Option<u64>) {
+        self.buffer.drain(..length);
+        if !self.buffer.is_empty() {
+            self.buffer_started_ms = timestamp_ms;
+        }
+    }
+
+    fn give_up(&mut self) {
+        self.role = Role::Ignored;
+        self.buffer = Vec::new();
+        self.ahead.clear();
+    }
+
+    /// Tells a client from a server by the first bytes sent
+    fn sniff_role(&mut self) {
+        if self.role != Role::Unknown {
+            return;
+        }
+        let buffer = &self.buffer;
+        self.role = if buffer.starts_with(b"HTTP/") {
+            Role::Server
+        } else if b"HTTP/".starts_with(buffer) {
+            Role::Unknown
+        } else {
+            match buffer.iter().position(|byte| *byte == b' ') {
+                Some(0) => Role::Ignored,
+                Some(end) if buffer[..end].iter().all(u8::is_ascii_uppercase) => Role::Client,
+                Some(_) => Role::Ignored,
+                None if buffer.len() > 16 || !buffer.iter().all(u8::is_ascii_uppercase) => Role::Ignored,
+                None => Role::Unknown,
+            }
+        };
+        if self.role == Role::Ignored {
+            self.give_up();
+        }
+    }
+}
+
+/// A request waiting for its response
+struct PendingRequest {
+    method: String,
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+    timestamp_ms: Option<u64>,
+}
+
+/// Both directions of a TCP connection, by which endpoint sends
+#[derive(Default)]
+struct Connection {
+    /// Sent by the lower and the higher endpoint of the connection key
+    directions: [Direction; 2],
+    requests: VecDeque<PendingRequest>,
+}
+
+impl Connection {
+    fn client_side(&self) -> Option<usize> {
+        self.directions.iter().position(|direction| direction.role == Role::Client)
+    }
+
+    /// Parses every complete message buffered on either side, emitting finished exchanges
+    fn advance(
+        &mut self,
+        key: (SocketAddr, SocketAddr),
+        ready: &mut VecDeque<Result<TrafficRecord, ValidationError>>,
+    ) {
+        for direction in &mut self.directions {
+            direction.sniff_role();
+        }
+        let Some(client) = self.client_side() else {
+            return;
+        };
+        let flow = flow_label(key, client);
+        let error = |message: String| Err(ValidationError::TrafficError(format!("{}: {}", flow, message)));
+
+        let requests = &mut self.requests;
+        let [low, high] = &mut self.directions;
+        let (client, server) = if client == 0 { (low, high) } else { (high, low) };
+
+        while client.role == Role::Client {
+            match parse_request(&client.buffer, client.closed) {
+                Ok(Some((mut request, length))) => {
+                    request.timestamp_ms = client.buffer_started_ms;
+                    requests.push_back(request);
+                    client.consume(length, client.buffer_started_ms);
+                }
+                Ok(None) => break,
+                Err(message) => {
+                    client.give_up();
+                    ready.push_back(error(message));
+                }
+            }
+        }
+
+        while server.role == Role::Server {
+            let head_request = requests.front().is_some_and(|request| request.method == "HEAD");
+            match parse_response(&server.buffer, head_request, server.closed) {
+                Ok(Some((response, length))) => {
+                    server.consume(length, server.buffer_started_ms);
+                    if (100..200).contains(&response.status) {
+                        continue;
+                    }
+                    match requests.pop_front() {
+                        Some(request) => ready.push_back(Ok(to_record(request, response))),
+                        None => ready.push_back(error(format!(
+                            "response {} without a captured request",
+                            response.status
+                        ))),
+                    }
+                }
+                Ok(None) => break,
+                Err(message) => {
+                    server.give_up();
+                    ready.push_back(error(message));
+                }
+            }
+        }
+    }
+}
+
+/// `client → server` for messages about a connection
+fn flow_label(key: (SocketAddr, SocketAddr), client_side: usize) -> String {
+    if client_side == 0 {
+        format!("{} → {}", key.0, key.1)
+    } else {
+        format!("{} → {}", key.1, key.0)
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// How a message's body ends
+enum Framing {
+    None,
+    Length(usize),
+    Chunked,
+    /// Response bodies without a length run until the server closes
+    UntilClose,
+}
+
+/// A complete request at the start of `buffer` and its length, `None` if it isn't all there yet
+fn parse_request(buffer: &[u8], closed: bool) -> Result<Option<(PendingRequest, usize)>, String> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut request = httparse::Request::new(&mut headers);
+    let head_length = match request.parse(buffer).map_err(|e| format!("malformed request: {}", e))? {
+        Status::Complete(length) => length,
+        Status::Partial => return Ok(None),
+    };
+    let headers = header_map(request.headers);
+    let framing = match framing(&headers)? {
+        Framing::UntilClose => Framing::None,
+        framing => framing,
+    };
+    let Some((body, body_length)) = read_body(&buffer[head_length..], &framing, closed)? else {
+        return Ok(None);
+    };
+    Ok(Some((
+        PendingRequest {
+            method: request.method.unwrap_or_default().to_string(),
+            path: request.path.unwrap_or_default().to_string(),
+            headers,
+            body,
+            timestamp_ms: None,
+        },
+        head_length + body_length,
+    )))
+}
+
+/// A complete response at the start of `buffer` and its length, `None` if it isn't all there yet
+fn parse_response(buffer: &[u8], head_request: bool, closed: bool) -> Result<Option<(Response, usize)>, String> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut response = httparse::Response::new(&mut headers);
+    let head_length = match response.parse(buffer).map_err(|e| format!("malformed response: {}", e))? {
+        Status::Complete(length) => length,
+        Status::Partial => return Ok(None),
+    };
+    let status = response.code.unwrap_or_default();
+    let headers = header_map(response.headers);
+    let framing = if head_request || (100..200).contains(&status) || status == 204 || status == 304 {
+        Framing::None
+    } else {
+        framing(&headers)?
+    };
+    let Some((body, body_length)) = read_body(&buffer[head_length..], &framing, closed)? else {
+        return Ok(None);
+    };
+    Ok(Some((Response { status, headers, body }, head_length + body_length)))
+}
+
+/// Headers keyed by lowercase name, repeated ones joined with commas
+fn header_map(headers: &[httparse::Header<'_>]) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for header in headers {
+        let value = String::from_utf8_lossy(header.value).into_owned();
+        map.entry(header.name.to_ascii_lowercase())
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    map
+}
+
+fn framing(headers: &BTreeMap<String, String>) -> Result<Framing, String> {
+    let chunked = headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    Ok(match (chunked, headers.get("content-length")) {
+        (true, _) => Framing::Chunked,
+        (false, Some(length)) => {
+            let length = length
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid Content-Length `{}`", length))?;
+            Framing::Length(length)
+        }
+        (false, None) => Framing::UntilClose,
+    })
+}
+
+/// The body at the start of `rest` and the bytes it took, `None` if it isn't all there yet
+fn read_body(rest: &[u8], framing: &Framing, closed: bool) -> Result<Option<(Vec<u8>, usize)>, String> {
+    Ok(match framing {
+        Framing::None => Some((Vec::new(), 0)),
+        Framing::Length(length) => rest.get(..*length).map(|body| (body.to_vec(), *length)),
+        Framing::Chunked => decode_chunked(rest)?,
+        Framing::UntilClose => closed.then(|| (rest.to_vec(), rest.len())),
+    })
+}
+
+/// Decodes a chunked body, returning it with the bytes it took once the last chunk is in
+fn decode_chunked(rest: &[u8]) -> Result<Option<(Vec<u8>, usize)>, String> {
+    let mut body = Vec::new();
+    let mut offset = 0;
+    loop {
+        let Some(line_end) = find(&rest[offset..], b"\r\n") else {
+            return Ok(None);
+        };
+        let line = String::from_utf8_lossy(&rest[offset..offset + line_end]);
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16).map_err(|_| format!("invalid chunk size `{}`", size))?;
+        offset += line_end + 2;
+        if size == 0 {
+            // Optional trailers, ended by an empty line
+            return Ok(if rest[offset..].starts_with(b"\r\n") {
+                Some((body, offset + 2))
+            } else {
+                find(&rest[offset..], b"\r\n\r\n").map(|end| (body, offset + end + 4))
+            });
+        }
+        let Some(chunk) = rest.get(offset..offset + size) else {
+            return Ok(None);
+        };
+        if rest.len() < offset + size + 2 {
+            return Ok(None);
+        }
+        body.extend_from_slice(chunk);
+        offset += size + 2;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn to_record(request: PendingRequest, response: Response) -> TrafficRecord {
+    let body = |body: Vec<u8>| (!body.is_empty()).then(|| Value::String(String::from_utf8_lossy(&body).into_owned()));
+    TrafficRecord {
+        method: request.method,
+        path: request.path,
+        query: None,
+        request_headers: request.headers,
+        request_body: body(request.body),
+        status: response.status,
+        response_headers: response.headers,
+        response_body: body(response.body),
+        timestamp: request.timestamp_ms.map(Timestamp::Millis),
+    }
+}
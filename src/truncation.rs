@@ -0,0 +1,204 @@
+//! Validation of bodies cut off by the capture source
+//!
+//! Log pipelines often store only the first few kilobytes of a body, leaving
+//! unterminated JSON. Rather than reporting those as malformed, the longest
+//! prefix ending on a complete value is closed off and validated, and the
+//! findings are marked as coming from a truncated capture.
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use serde_json::Value;
+
+/// Nesting depth beyond which a truncated body isn't repaired
+const MAX_DEPTH: usize = 128;
+
+/// The parseable prefix of a truncated JSON body
+pub(crate) struct TruncatedBody {
+    pub(crate) value: Value,
+    /// JSON pointers of the containers left open by the cut, outermost first
+    open_containers: Vec<String>,
+}
+
+impl TruncatedBody {
+    /// Closes off the longest prefix of `bytes` that ends on a complete value
+    ///
+    /// Returns `None` when no container was opened before the cut (a
+    /// truncated scalar body has no usable prefix) or the prefix isn't
+    /// valid JSON.
+    pub(crate) fn repair(bytes: &[u8]) -> Option<Self> {
+        let mut scanner = Scanner {
+            bytes,
+            pos: 0,
+            closers: Vec::new(),
+            pointers: Vec::new(),
+            cut: None,
+        };
+        if scanner.value(String::new(), 0).is_some() {
+            // Complete after all; not a truncation
+            return None;
+        }
+        let cut = scanner.cut?;
+        let mut repaired = bytes[..cut.pos].to_vec();
+        repaired.extend(cut.closers.iter().rev());
+        Some(Self {
+            value: serde_json::from_slice(&repaired).ok()?,
+            open_containers: cut.pointers,
+        })
+    }
+
+    /// Marks an event found in the prefix, or drops it if the cut may have caused it
+    ///
+    /// Containers left open lost their tail, so missing required fields,
+    /// size constraints and unmatched `oneOf`/`anyOf` branches on them say
+    /// nothing about the original body.
+    pub(crate) fn annotate(&self, mut event: DriftEvent) -> Option<DriftEvent> {
+        let pointer = event.location.find('/').map_or("", |start| &event.location[start..]);
+        if is_completeness_check(event.drift_type) && self.open_containers.iter().any(|open| open == pointer) {
+            return None;
+        }
+        event.from_truncated_capture = true;
+        Some(event)
+    }
+}
+
+fn is_completeness_check(drift_type: DriftType) -> bool {
+    matches!(
+        drift_type,
+        DriftType::RequestBodyMissingRequired
+            | DriftType::ResponseBodyMissingRequired
+            | DriftType::RequestBodyArrayConstraintViolation
+            | DriftType::ResponseBodyArrayConstraintViolation
+            | DriftType::RequestBodyObjectConstraintViolation
+            | DriftType::ResponseBodyObjectConstraintViolation
+            | DriftType::RequestBodyOneOfNoMatch
+            | DriftType::ResponseBodyOneOfNoMatch
+            | DriftType::RequestBodyAnyOfNoMatch
+            | DriftType::ResponseBodyAnyOfNoMatch
+    )
+}
+
+/// A point the body can be cut at and closed off
+struct Cut {
+    pos: usize,
+    closers: Vec<u8>,
+    pointers: Vec<String>,
+}
+
+/// Walks a JSON prefix, remembering the last position a complete value ended at
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Closing bracket of each open container, outermost first
+    closers: Vec<u8>,
+    /// JSON pointer of each open container, outermost first
+    pointers: Vec<String>,
+    cut: Option<Cut>,
+}
+
+impl Scanner<'_> {
+    /// Consumes one value; `None` if the input ends (or breaks) before it does
+    fn value(&mut self, pointer: String, depth: usize) -> Option<()> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.skip_whitespace();
+        match *self.bytes.get(self.pos)? {
+            b'{' => self.container(b'}', pointer, depth),
+            b'[' => self.container(b']', pointer, depth),
+            b'"' => self.string().map(drop),
+            b't' => self.literal(b"true"),
+            b'f' => self.literal(b"false"),
+            b'n' => self.literal(b"null"),
+            _ => self.number(),
+        }
+    }
+
+    fn container(&mut self, closer: u8, pointer: String, depth: usize) -> Option<()> {
+        self.pos += 1;
+        self.closers.push(closer);
+        self.pointers.push(pointer.clone());
+        self.mark_cut();
+
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            if *self.bytes.get(self.pos)? == closer {
+                self.pos += 1;
+                break;
+            }
+            let segment = if closer == b'}' {
+                let key = self.string()?;
+                self.skip_whitespace();
+                if *self.bytes.get(self.pos)? != b':' {
+                    return None;
+                }
+                self.pos += 1;
+                key.replace('~', "~0").replace('/', "~1")
+            } else {
+                index.to_string()
+            };
+            self.value(format!("{}/{}", pointer, segment), depth + 1)?;
+            self.mark_cut();
+            index += 1;
+
+            self.skip_whitespace();
+            match *self.bytes.get(self.pos)? {
+                b',' => self.pos += 1,
+                byte if byte == closer => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        self.closers.pop();
+        self.pointers.pop();
+        Some(())
+    }
+
+    /// Consumes a string, returning its unescaped contents
+    fn string(&mut self) -> Option<String> {
+        if *self.bytes.get(self.pos)? != b'"' {
+            return None;
+        }
+        let start = self.pos + 1;
+        let mut pos = start;
+        loop {
+            match *self.bytes.get(pos)? {
+                b'\\' => pos += 2,
+                b'"' => break,
+                _ => pos += 1,
+            }
+        }
+        self.pos = pos + 1;
+        serde_json::from_slice(&self.bytes[start - 1..self.pos]).ok()
+    }
+
+    fn literal(&mut self, word: &[u8]) -> Option<()> {
+        let end = self.pos + word.len();
+        (self.bytes.get(self.pos..end)? == word).then(|| self.pos = end)
+    }
+
+    /// Consumes a number, which is only known to be complete once something follows it
+    fn number(&mut self) -> Option<()> {
+        let start = self.pos;
+        while matches!(self.bytes.get(self.pos)?, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+            self.pos += 1;
+        }
+        (self.pos > start).then_some(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn mark_cut(&mut self) {
+        self.cut = Some(Cut {
+            pos: self.pos,
+            closers: self.closers.clone(),
+            pointers: self.pointers.clone(),
+        });
+    }
+}
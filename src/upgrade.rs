@@ -0,0 +1,268 @@
+//! Dry run of moving a spec to a newer OpenAPI version
+//!
+//! OpenAPI 3.1 adopts JSON Schema 2020-12, and a handful of 3.0 constructs
+//! mean something else, or nothing, under it: `nullable` is gone, boolean
+//! `exclusiveMinimum`/`exclusiveMaximum` became numbers, and keywords next to
+//! a `$ref` stop being ignored. Checking a spec before its `openapi` field is
+//! bumped shows exactly where validation would start accepting or rejecting
+//! different traffic.
+
+use crate::error::ValidationError;
+use crate::spec::transform::nullable_to_json_schema;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// An OpenAPI minor version, as given to `--from` / `--to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum SpecVersion {
+    #[serde(rename = "3.0")]
+    V3_0,
+    #[serde(rename = "3.1")]
+    V3_1,
+}
+
+impl SpecVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V3_0 => "3.0",
+            Self::V3_1 => "3.1",
+        }
+    }
+}
+
+impl FromStr for SpecVersion {
+    type Err = ValidationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "3.0" => Ok(Self::V3_0),
+            "3.1" => Ok(Self::V3_1),
+            other => Err(ValidationError::UnsupportedFeature {
+                feature: format!("OpenAPI version {}", other),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for SpecVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A construct whose validation behavior differs between the versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpgradeConstruct {
+    /// `nullable: true`, ignored by 3.1, so nulls accepted today are rejected
+    Nullable,
+    /// Boolean `exclusiveMinimum`/`exclusiveMaximum`, which 3.1 expects to be the bound itself
+    ExclusiveBound,
+    /// Validation keywords next to a `$ref`, ignored by 3.0 and enforced by 3.1
+    RefSiblings,
+}
+
+impl UpgradeConstruct {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Nullable => "nullable",
+            Self::ExclusiveBound => "exclusive-bound",
+            Self::RefSiblings => "ref-siblings",
+        }
+    }
+}
+
+/// A place in the spec that would validate differently after the upgrade
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UpgradeFinding {
+    pub construct: UpgradeConstruct,
+    /// JSON pointer to the schema, e.g. `#/components/schemas/User/properties/nickname`
+    pub location: String,
+    /// How validation changes if the spec is upgraded as is
+    pub message: String,
+    /// The keywords that keep today's behavior under the new version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewrite: Option<Value>,
+}
+
+/// Constructs that would change validation behavior, ordered by location
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeReport {
+    pub from: SpecVersion,
+    pub to: SpecVersion,
+    pub findings: Vec<UpgradeFinding>,
+}
+
+impl UpgradeReport {
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ValidationError::ReportError(format!("Failed to serialize upgrade report: {}", e)))
+    }
+
+    /// One line per finding, followed by its rewrite
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        for finding in &self.findings {
+            let _ = writeln!(out, "{}: {}: {}", finding.construct.as_str(), finding.location, finding.message);
+            if let Some(rewrite) = &finding.rewrite {
+                let _ = writeln!(out, "  rewrite as: {}", rewrite);
+            }
+        }
+        let _ = writeln!(
+            out,
+            "{} constructs change validation behavior from {} to {}",
+            self.findings.len(),
+            self.from,
+            self.to
+        );
+        out
+    }
+}
+
+/// Keys whose children are names (properties, components, paths) rather than keywords
+const NAME_MAPS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "schemas",
+    "paths",
+    "responses",
+    "parameters",
+    "requestBodies",
+    "headers",
+    "content",
+    "callbacks",
+    "links",
+    "securitySchemes",
+    "encoding",
+];
+
+/// Keys holding instance data or extensions, never schemas
+const DATA_KEYWORDS: &[&str] = &["example", "examples", "default", "enum", "const"];
+
+/// Keywords allowed next to a `$ref` that don't affect validation
+const ANNOTATIONS: &[&str] = &["description", "summary", "title", "example", "examples", "externalDocs", "deprecated"];
+
+/// Finds the constructs in a spec document that validate differently under `to` than under `from`
+///
+/// `document` is the spec as parsed from YAML or JSON, since some of these
+/// constructs (`$ref` siblings) don't survive parsing into typed structs.
+pub fn upgrade_check(
+    document: &Value,
+    from: SpecVersion,
+    to: SpecVersion,
+) -> Result<UpgradeReport, ValidationError> {
+    if (from, to) != (SpecVersion::V3_0, SpecVersion::V3_1) {
+        return Err(ValidationError::UnsupportedFeature {
+            feature: format!("upgrading from OpenAPI {} to {}", from, to),
+        });
+    }
+    let declared = document.get("openapi").and_then(Value::as_str).unwrap_or_default();
+    if !declared.starts_with(&format!("{}.", from)) {
+        return Err(ValidationError::SpecParse {
+            reason: format!("spec declares `openapi: {}`, not {}", declared, from),
+        });
+    }
+
+    let mut findings = Vec::new();
+    walk(document, "#", false, &mut findings);
+    Ok(UpgradeReport { from, to, findings })
+}
+
+fn walk(value: &Value, pointer: &str, names: bool, findings: &mut Vec<UpgradeFinding>) {
+    match value {
+        Value::Object(map) => {
+            if !names {
+                check_schema(map, pointer, findings);
+            }
+            for (key, child) in map {
+                if !names && (DATA_KEYWORDS.contains(&key.as_str()) || key.starts_with("x-")) {
+                    continue;
+                }
+                let child_pointer = format!("{}/{}", pointer, escape(key));
+                walk(child, &child_pointer, !names && NAME_MAPS.contains(&key.as_str()), findings);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                walk(item, &format!("{}/{}", pointer, index), false, findings);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_schema(schema: &Map<String, Value>, pointer: &str, findings: &mut Vec<UpgradeFinding>) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let siblings: Vec<&str> = schema
+            .keys()
+            .map(String::as_str)
+            .filter(|key| *key != "$ref" && *key != "nullable" && !ANNOTATIONS.contains(key) && !key.starts_with("x-"))
+            .collect();
+        if !siblings.is_empty() {
+            findings.push(UpgradeFinding {
+                construct: UpgradeConstruct::RefSiblings,
+                location: pointer.to_string(),
+                message: format!(
+                    "`{}` next to `$ref: {}` is ignored today and would be enforced",
+                    siblings.join("`, `"),
+                    reference
+                ),
+                rewrite: None,
+            });
+        }
+    }
+
+    if schema.get("nullable") == Some(&Value::Bool(true)) {
+        // The same rewrite validators apply to 3.0 schemas today, limited to the keywords it changes
+        let mut rewritten = schema.clone();
+        nullable_to_json_schema(&mut rewritten);
+        rewritten.retain(|key, value| schema.get(key) != Some(value));
+        findings.push(UpgradeFinding {
+            construct: UpgradeConstruct::Nullable,
+            location: pointer.to_string(),
+            message: "`nullable: true` is not a keyword in 3.1; null values accepted today would be rejected"
+                .to_string(),
+            rewrite: Some(Value::Object(rewritten)),
+        });
+    }
+
+    for (keyword, bound) in [("exclusiveMinimum", "minimum"), ("exclusiveMaximum", "maximum")] {
+        let Some(Value::Bool(exclusive)) = schema.get(keyword) else {
+            continue;
+        };
+        let (message, rewrite) = match (exclusive, schema.get(bound)) {
+            (true, Some(limit)) => (
+                format!(
+                    "`{}: true` must become `{}: {}`; as a boolean the schema is invalid under 3.1",
+                    keyword, keyword, limit
+                ),
+                Some(json!({ keyword: limit })),
+            ),
+            (true, None) => (
+                format!("`{}: true` has no `{}` to apply to and is invalid under 3.1", keyword, bound),
+                None,
+            ),
+            (false, _) => (
+                format!("`{}: false` is the default and is invalid under 3.1; drop it", keyword),
+                None,
+            ),
+        };
+        findings.push(UpgradeFinding {
+            construct: UpgradeConstruct::ExclusiveBound,
+            location: pointer.to_string(),
+            message,
+            rewrite,
+        });
+    }
+}
+
+/// Escapes a key for use in a JSON pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
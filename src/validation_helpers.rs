@@ -1,9 +1,19 @@
-use crate::drift_types::DriftType;
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{map_to_drift_type, DriftType, ValidationContext};
 use crate::error::ValidationError;
+use crate::spec::progress::BuildProgress;
+use crate::suggestions::{property_names, suggest};
 use jsonschema::{Registry, Validator};
 use serde_json::Value;
+use std::sync::{Arc, OnceLock};
+
+/// Base URI `$ref`s into the spec's components resolve against
+pub(crate) const SPEC_BASE_URI: &str = "urn:oas:spec";
 
 /// Builds a JSON Schema validator with registry for $ref resolution
+///
+/// `format` is asserted (`uuid`, `date-time`, `email`, `uri`, ...); formats
+/// unknown to JSON Schema, like OpenAPI's `int64` or `binary`, are ignored.
 pub fn build_validator(
     schema: &Value,
     registry: &Registry,
@@ -11,7 +21,8 @@ pub fn build_validator(
 ) -> Result<Validator, ValidationError> {
     jsonschema::options()
         .with_registry(registry.clone())
-        .with_base_uri("urn:oas:spec".to_string())
+        .with_base_uri(SPEC_BASE_URI.to_string())
+        .should_validate_formats(true)
         .build(schema)
         .map_err(|e| {
             ValidationError::SchemaCompilationError(format!(
@@ -21,6 +32,138 @@ pub fn build_validator(
         })
 }
 
+/// Turns a schema into the validator a spec validator checks values with
+///
+/// A [`Registry`] compiles schemas up front; a [`LazyRegistry`] defers each
+/// until first use.
+pub trait CompileSchema: Send + Sync {
+    fn compile_schema(&self, schema: &Value, error_context: &str) -> Result<SchemaValidator, ValidationError>;
+}
+
+impl CompileSchema for Registry {
+    fn compile_schema(&self, schema: &Value, error_context: &str) -> Result<SchemaValidator, ValidationError> {
+        let validator = build_validator(schema, self, error_context)?;
+        Ok(SchemaValidator::compiled(validator).with_property_names(property_names(schema, self)))
+    }
+}
+
+/// Registry whose schemas are compiled on first use instead of at build time
+///
+/// Startup stays fast for huge specs where traffic only reaches a fraction of
+/// the operations. Compilation errors surface on first use instead of failing
+/// the build.
+#[derive(Debug, Clone)]
+pub struct LazyRegistry {
+    registry: Arc<Registry>,
+    progress: Option<Arc<dyn BuildProgress>>,
+}
+
+impl LazyRegistry {
+    pub fn new(registry: Registry) -> Self {
+        Self {
+            registry: Arc::new(registry),
+            progress: None,
+        }
+    }
+
+    /// Reports schemas that fail to compile on first use to `progress`
+    pub fn with_progress(mut self, progress: Arc<dyn BuildProgress>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+}
+
+impl CompileSchema for LazyRegistry {
+    fn compile_schema(&self, schema: &Value, error_context: &str) -> Result<SchemaValidator, ValidationError> {
+        Ok(SchemaValidator {
+            state: SchemaState::Deferred {
+                schema: schema.clone(),
+                registry: Arc::clone(&self.registry),
+                error_context: error_context.to_string(),
+                progress: self.progress.clone(),
+                compiled: OnceLock::new(),
+            },
+            property_names: OnceLock::new(),
+        })
+    }
+}
+
+/// A JSON Schema validator, compiled either at build time or on first use
+#[derive(Debug)]
+pub struct SchemaValidator {
+    state: SchemaState,
+    /// Property names the schema documents, for near-miss suggestions
+    property_names: OnceLock<Vec<String>>,
+}
+
+#[derive(Debug)]
+enum SchemaState {
+    Compiled(Validator),
+    Deferred {
+        schema: Value,
+        registry: Arc<Registry>,
+        error_context: String,
+        progress: Option<Arc<dyn BuildProgress>>,
+        /// Unset until first use; `None` if the schema failed to compile
+        compiled: OnceLock<Option<Validator>>,
+    },
+}
+
+impl SchemaValidator {
+    pub fn compiled(validator: Validator) -> Self {
+        Self {
+            state: SchemaState::Compiled(validator),
+            property_names: OnceLock::new(),
+        }
+    }
+
+    /// Sets the documented property names undocumented or missing fields are matched against
+    pub fn with_property_names(self, names: Vec<String>) -> Self {
+        Self {
+            property_names: OnceLock::from(names),
+            ..self
+        }
+    }
+
+    /// Sorted property names the schema documents, gathered on first use for deferred schemas
+    fn property_names(&self) -> &[String] {
+        self.property_names.get_or_init(|| match &self.state {
+            SchemaState::Compiled(_) => Vec::new(),
+            SchemaState::Deferred { schema, registry, .. } => property_names(schema, registry),
+        })
+    }
+
+    /// The compiled validator, compiling a deferred schema first
+    ///
+    /// A deferred schema that fails to compile is reported once (to the
+    /// registry's [`BuildProgress`], if any) and then validates nothing.
+    pub fn get(&self) -> Option<&Validator> {
+        match &self.state {
+            SchemaState::Compiled(validator) => Some(validator),
+            SchemaState::Deferred {
+                schema,
+                registry,
+                error_context,
+                progress,
+                compiled,
+            } => compiled
+                .get_or_init(|| match build_validator(schema, registry, error_context) {
+                    Ok(validator) => Some(validator),
+                    Err(e) => {
+                        if let Some(progress) = progress {
+                            progress.warning(&format!(
+                                "Skipping validation, schema failed to compile on first use: {}",
+                                e
+                            ));
+                        }
+                        None
+                    }
+                })
+                .as_ref(),
+        }
+    }
+}
+
 /// Formats drift error message
 pub fn format_drift_error(drift_type: DriftType, location: &str, message: &str) -> String {
     format!("[{}] at {} - {}", drift_type.as_str(), location, message)
@@ -34,3 +177,58 @@ pub fn format_instance_location(instance_path: &str, prefix: &str) -> String {
         format!("{}{}", prefix, instance_path)
     }
 }
+
+/// Runs a validator and converts drift-relevant errors into events
+///
+/// `location` maps a JSON Schema instance path to the reported location.
+pub fn collect_drift_events(
+    validator: &SchemaValidator,
+    value: &Value,
+    context: ValidationContext,
+    location: impl Fn(&str) -> String,
+) -> Vec<DriftEvent> {
+    gather_drift_events(|emit| drift_events_with(validator, value, context, location, emit))
+}
+
+/// Runs a validator and passes each drift-relevant error to `emit` as it is found
+pub fn drift_events_with(
+    schema: &SchemaValidator,
+    value: &Value,
+    context: ValidationContext,
+    location: impl Fn(&str) -> String,
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    let Some(validator) = schema.get() else {
+        return;
+    };
+    if validator.is_valid(value) {
+        return;
+    }
+    for e in validator.iter_errors(value) {
+        if let Some(drift_type) = map_to_drift_type(&e.kind, context) {
+            let suggestions = suggest(&e.kind, &e.instance, schema.property_names());
+            emit(
+                DriftEvent::new(drift_type, location(&e.instance_path.to_string()), e.to_string())
+                    .with_observed(&e.instance)
+                    .with_suggestions(suggestions),
+            );
+        }
+    }
+}
+
+/// Collects the events of a callback-based check into a `Vec`
+pub fn gather_drift_events(check: impl FnOnce(&mut dyn FnMut(DriftEvent))) -> Vec<DriftEvent> {
+    let mut events = Vec::new();
+    check(&mut |event| events.push(event));
+    events
+}
+
+/// Folds drift events into the legacy `Result` shape used by `validate` methods
+pub fn drift_events_to_result(events: Vec<DriftEvent>) -> Result<(), ValidationError> {
+    if events.is_empty() {
+        Ok(()) // No drift-relevant errors
+    } else {
+        let drift_errors: Vec<String> = events.iter().map(ToString::to_string).collect();
+        Err(ValidationError::ValidationFailed(drift_errors.join("; ")))
+    }
+}
@@ -0,0 +1,253 @@
+//! Tuning how strictly traffic is held to the spec
+//!
+//! The defaults check what the spec states and nothing more. A
+//! [`ValidatorConfig`] tightens that (undeclared fields and query
+//! parameters become drift), loosens it (no `format` assertions, only some
+//! media types validated, oversized bodies skipped) and reclassifies or
+//! drops drift types through a [`DriftPolicy`].
+
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{DriftType, Severity};
+#[cfg(feature = "monitor")]
+use crate::error::ValidationError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "monitor")]
+use std::fs;
+#[cfg(feature = "monitor")]
+use std::path::Path;
+
+/// Decoded size past which a compressed body isn't validated, unless configured otherwise
+pub const DEFAULT_MAX_DECOMPRESSED_BYTES: usize = 16 * 1024 * 1024;
+
+/// How validators built from a spec check traffic
+///
+/// ```yaml
+/// validation:
+///   strict: true
+///   format_checks: false
+///   merge_all_of: true
+///   max_body_bytes: 1048576
+///   oversized_body_depth: 2
+///   max_decompressed_bytes: 16777216
+///   content_types: [application/json, application/*+json]
+///   policy:
+///     ignore: [RATE_LIMIT_HEADER_MISSING]
+///     severities:
+///       RESPONSE_BODY_UNDOCUMENTED_FIELD: warning
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ValidatorConfig {
+    /// Report everything the spec doesn't declare: undeclared body fields,
+    /// as with `unknown_fields`, and query parameters unless
+    /// [`crate::BuildOptions::undocumented_query_parameters`] sets an allowlist
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+    /// Assert `format` keywords (`uuid`, `date-time`, `email`, ...)
+    pub format_checks: bool,
+    /// Report body fields object schemas don't declare, as if every schema
+    /// left open closed itself with `additionalProperties: false`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub unknown_fields: bool,
+    /// Merge `allOf` members into the schema composing them, so drift
+    /// locations refer to the effective schema rather than to a member
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub merge_all_of: bool,
+    /// Bodies larger than this many bytes are not validated, or only partially
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_body_bytes: Option<usize>,
+    /// Validate bodies over `max_body_bytes` down to this nesting depth
+    /// instead of skipping them; they are streamed rather than materialized,
+    /// and deeper objects and arrays are only checked to be objects and arrays
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oversized_body_depth: Option<usize>,
+    /// Compressed bodies that decode to more than this many bytes are reported
+    /// undecodable instead of validated; `max_body_bytes` applies to what they decode to
+    pub max_decompressed_bytes: usize,
+    /// Media types whose bodies are validated, wildcards (`application/*+json`)
+    /// allowed; bodies labelled with any other `Content-Type` are skipped.
+    /// Empty validates every body.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub content_types: Vec<String>,
+    /// Drift types dropped or reclassified before events are emitted
+    #[serde(skip_serializing_if = "DriftPolicy::is_empty")]
+    pub policy: DriftPolicy,
+}
+
+impl Default for ValidatorConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            format_checks: true,
+            unknown_fields: false,
+            merge_all_of: false,
+            max_body_bytes: None,
+            oversized_body_depth: None,
+            max_decompressed_bytes: DEFAULT_MAX_DECOMPRESSED_BYTES,
+            content_types: Vec::new(),
+            policy: DriftPolicy::default(),
+        }
+    }
+}
+
+impl ValidatorConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports everything the spec doesn't declare
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Stops asserting `format` keywords
+    pub fn without_format_checks(mut self) -> Self {
+        self.format_checks = false;
+        self
+    }
+
+    /// Reports body fields object schemas don't declare
+    pub fn unknown_fields(mut self) -> Self {
+        self.unknown_fields = true;
+        self
+    }
+
+    /// Merges `allOf` compositions into the effective schema
+    pub fn merge_all_of(mut self) -> Self {
+        self.merge_all_of = true;
+        self
+    }
+
+    /// Skips bodies larger than `limit` bytes
+    pub fn max_body_bytes(mut self, limit: usize) -> Self {
+        self.max_body_bytes = Some(limit);
+        self
+    }
+
+    /// Validates bodies over the size limit down to `depth` levels of nesting instead of skipping them
+    pub fn oversized_body_depth(mut self, depth: usize) -> Self {
+        self.oversized_body_depth = Some(depth);
+        self
+    }
+
+    /// Reports compressed bodies decoding to more than `limit` bytes instead of validating them
+    pub fn max_decompressed_bytes(mut self, limit: usize) -> Self {
+        self.max_decompressed_bytes = limit;
+        self
+    }
+
+    /// Validates bodies of `media_type` (and of any other type already accepted)
+    pub fn content_type(mut self, media_type: impl Into<String>) -> Self {
+        self.content_types.push(media_type.into());
+        self
+    }
+
+    pub fn with_policy(mut self, policy: DriftPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether undeclared body fields are reported
+    pub fn reports_unknown_fields(&self) -> bool {
+        self.strict || self.unknown_fields
+    }
+}
+
+/// Which drift types are reported, and at what severity
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DriftPolicy {
+    /// Drift types never reported
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<DriftType>,
+    #[serde(skip_serializing_if = "SeverityPolicy::is_empty")]
+    pub severities: SeverityPolicy,
+}
+
+impl DriftPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never reports `drift_type`
+    pub fn ignore(mut self, drift_type: DriftType) -> Self {
+        self.ignore.push(drift_type);
+        self
+    }
+
+    /// Reports `drift_type` as `severity` instead of its default
+    pub fn with_override(mut self, drift_type: DriftType, severity: Severity) -> Self {
+        self.severities = self.severities.with_override(drift_type, severity);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ignore.is_empty() && self.severities.is_empty()
+    }
+
+    /// Whether `event` is reported at all
+    pub fn allows(&self, event: &DriftEvent) -> bool {
+        !self.ignore.contains(&event.drift_type)
+    }
+
+    /// `event` with its severity per the policy, or `None` if it isn't reported
+    pub fn apply(&self, mut event: DriftEvent) -> Option<DriftEvent> {
+        if !self.allows(&event) {
+            return None;
+        }
+        self.severities.apply(&mut event);
+        Some(event)
+    }
+}
+
+/// Severities assigned to drift types in place of their defaults
+///
+/// Written as a mapping from drift type to severity:
+///
+/// ```yaml
+/// RESPONSE_BODY_UNDOCUMENTED_FIELD: info
+/// UNDOCUMENTED_PARAMETER: warning
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SeverityPolicy {
+    overrides: BTreeMap<DriftType, Severity>,
+}
+
+impl SeverityPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_override(mut self, drift_type: DriftType, severity: Severity) -> Self {
+        self.overrides.insert(drift_type, severity);
+        self
+    }
+
+    /// Reads a YAML (or JSON) policy file
+    #[cfg(feature = "monitor")]
+    pub fn load(path: &Path) -> Result<Self, ValidationError> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            ValidationError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        serde_yaml::from_str(&contents)
+            .map_err(|e| ValidationError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Sets the event's severity per the policy, returning whether it changed
+    pub fn apply(&self, event: &mut DriftEvent) -> bool {
+        match self.overrides.get(&event.drift_type) {
+            Some(&severity) if severity != event.severity => {
+                event.severity = severity;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+}
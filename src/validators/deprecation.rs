@@ -0,0 +1,163 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::exchange::{parse_query_string, ObservedRequest};
+use crate::validation_helpers::gather_drift_events;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Where a deprecated parameter is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecatedParameterLocation {
+    Path,
+    Query,
+    Header,
+    Cookie,
+}
+
+/// Reports requests using what the spec marks `deprecated`
+///
+/// Calls to a deprecated operation, deprecated parameters sent and
+/// deprecated request body fields present each give a `DEPRECATED_USAGE`
+/// event, so aggregated drift shows who still relies on what is being
+/// sunset and how often.
+#[derive(Debug, Clone, Default)]
+pub struct DeprecationValidator {
+    operation: bool,
+    parameters: Vec<(DeprecatedParameterLocation, String)>,
+    /// Request body fields as paths from the body's root, `*` standing for any array item
+    fields: Vec<Vec<String>>,
+}
+
+impl DeprecationValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reports every call, the whole operation being deprecated
+    pub fn with_deprecated_operation(mut self, deprecated: bool) -> Self {
+        self.operation = deprecated;
+        self
+    }
+
+    pub fn add_parameter(&mut self, location: DeprecatedParameterLocation, name: impl Into<String>) {
+        self.parameters.push((location, name.into()));
+    }
+
+    /// Reports request bodies holding the field at `path`, e.g. `["items", "*", "fax"]`
+    pub fn add_field(&mut self, path: Vec<String>) {
+        if !self.fields.contains(&path) {
+            self.fields.push(path);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.operation && self.parameters.is_empty() && self.fields.is_empty()
+    }
+
+    /// Whether any request body fields are deprecated
+    pub fn has_fields(&self) -> bool {
+        !self.fields.is_empty()
+    }
+
+    /// Collects events for a call to a deprecated operation and deprecated parameters sent
+    pub fn request_drift_events(
+        &self,
+        request: &ObservedRequest,
+        path_params: &HashMap<String, String>,
+    ) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.request_drift_events_with(request, path_params, emit))
+    }
+
+    /// Passes an event for a call to a deprecated operation and for each deprecated parameter sent to `emit`
+    pub fn request_drift_events_with(
+        &self,
+        request: &ObservedRequest,
+        path_params: &HashMap<String, String>,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        if self.operation {
+            emit(DriftEvent::new(
+                DriftType::DeprecatedUsage,
+                "operation",
+                "Called an operation the spec marks deprecated",
+            ));
+        }
+        if self.parameters.is_empty() {
+            return;
+        }
+
+        let query = match self.parameters.iter().any(|(location, _)| *location == DeprecatedParameterLocation::Query) {
+            true => parse_query_string(request.query.as_deref().unwrap_or_default()),
+            false => Vec::new(),
+        };
+        let cookies: Vec<&str> = request
+            .header("cookie")
+            .map(|cookie| cookie.split(';').filter_map(|pair| Some(pair.split_once('=')?.0.trim())).collect())
+            .unwrap_or_default();
+        for (location, name) in &self.parameters {
+            let (sent, event_location, kind) = match location {
+                DeprecatedParameterLocation::Path => (path_params.contains_key(name), name.clone(), "path"),
+                DeprecatedParameterLocation::Query => {
+                    // deepObject parameters arrive as `name[prop]` keys
+                    let sent = query.iter().any(|(key, _)| {
+                        key == name || key.strip_prefix(name.as_str()).is_some_and(|rest| rest.starts_with('['))
+                    });
+                    (sent, name.clone(), "query")
+                }
+                DeprecatedParameterLocation::Header => (
+                    request.header(name).is_some(),
+                    format!("header/{}", name.to_ascii_lowercase()),
+                    "header",
+                ),
+                DeprecatedParameterLocation::Cookie => {
+                    (cookies.contains(&name.as_str()), format!("cookie/{}", name), "cookie")
+                }
+            };
+            if sent {
+                emit(DriftEvent::new(
+                    DriftType::DeprecatedUsage,
+                    event_location,
+                    format!("Sent {} parameter '{}', which the spec marks deprecated", kind, name),
+                ));
+            }
+        }
+    }
+
+    /// Collects events for deprecated fields present in a request body
+    pub fn body_drift_events(&self, body: Option<&Value>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.body_drift_events_with(body, emit))
+    }
+
+    /// Passes an event for each deprecated field present in a request body to `emit`,
+    /// counting its occurrences across array items
+    pub fn body_drift_events_with(&self, body: Option<&Value>, emit: &mut dyn FnMut(DriftEvent)) {
+        let Some(body) = body else {
+            return;
+        };
+        for field in &self.fields {
+            let count = occurrences(body, field);
+            if count == 0 {
+                continue;
+            }
+            let path = field.join("/");
+            let times = if count == 1 { String::new() } else { format!(" {} times", count) };
+            emit(DriftEvent::new(
+                DriftType::DeprecatedUsage,
+                format!("body/{}", path),
+                format!("Sent field '{}'{}, which the spec marks deprecated", path, times),
+            ));
+        }
+    }
+}
+
+/// How many values `value` holds at `path`, `*` matching every array item
+fn occurrences(value: &Value, path: &[String]) -> usize {
+    let Some((segment, rest)) = path.split_first() else {
+        return 1;
+    };
+    match value {
+        Value::Object(members) => members.get(segment).map_or(0, |member| occurrences(member, rest)),
+        Value::Array(items) if segment == "*" => items.iter().map(|item| occurrences(item, rest)).sum(),
+        _ => 0,
+    }
+}
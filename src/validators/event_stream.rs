@@ -0,0 +1,83 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::ValidationContext;
+use crate::error::ValidationError;
+use crate::validation_helpers::{drift_events_with, gather_drift_events, CompileSchema, SchemaValidator};
+use serde_json::Value;
+
+/// Validator for `text/event-stream` response bodies
+///
+/// Streams are exempt from whole-body validation. When the spec gives the
+/// media type a schema, the `data` payload of every event is validated
+/// against it individually: payloads that parse as JSON are validated as
+/// such, anything else as a JSON string.
+#[derive(Debug, Default)]
+pub struct EventStreamValidator {
+    item: Option<SchemaValidator>,
+}
+
+impl EventStreamValidator {
+    /// Creates a validator that exempts the stream without checking events
+    pub fn exempt() -> Self {
+        Self::default()
+    }
+
+    /// Creates a validator checking each event's data against an item schema
+    pub fn with_item_schema(
+        schema: &Value,
+        registry: &dyn CompileSchema,
+        error_context: &str,
+    ) -> Result<Self, ValidationError> {
+        let item = registry.compile_schema(schema, error_context)?;
+        Ok(Self { item: Some(item) })
+    }
+
+    /// Collects drift events for the events in a raw stream body
+    pub fn drift_events(&self, body: &[u8]) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(body, emit))
+    }
+
+    /// Passes each drift event for the events in a raw stream body to `emit`
+    pub fn drift_events_with(&self, body: &[u8], emit: &mut dyn FnMut(DriftEvent)) {
+        let Some(item) = &self.item else {
+            return;
+        };
+
+        for (index, data) in parse_event_data(&String::from_utf8_lossy(body)).into_iter().enumerate() {
+            let value = serde_json::from_str(&data).unwrap_or(Value::String(data));
+            drift_events_with(item, &value, ValidationContext::ResponseBody, |path| {
+                format!("body/events/{}{}", index, path)
+            }, emit);
+        }
+    }
+}
+
+/// Extracts the `data` payload of each event in a stream
+///
+/// Multiple `data:` lines within one event are joined with newlines, per the
+/// SSE spec. Events without data (comments, bare `id:`/`retry:`) are skipped.
+pub fn parse_event_data(stream: &str) -> Vec<String> {
+    let mut payloads = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in stream.lines() {
+        if line.is_empty() {
+            payloads.extend(current.take());
+            continue;
+        }
+        let Some(data) = line.strip_prefix("data") else {
+            continue;
+        };
+        let Some(data) = data.strip_prefix(':').map(|d| d.strip_prefix(' ').unwrap_or(d)) else {
+            continue;
+        };
+        match &mut current {
+            Some(buffer) => {
+                buffer.push('\n');
+                buffer.push_str(data);
+            }
+            None => current = Some(data.to_string()),
+        }
+    }
+    payloads.extend(current);
+    payloads
+}
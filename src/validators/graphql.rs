@@ -0,0 +1,116 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::ValidationContext;
+use crate::error::ValidationError;
+use crate::validation_helpers::{drift_events_with, format_instance_location, gather_drift_events, SchemaValidator};
+use serde_json::{json, Value};
+
+/// Validator for GraphQL-over-HTTP operations
+///
+/// A single GraphQL route serves arbitrarily shaped payloads, so its JSON
+/// Schema (usually a loose `oneOf`) says nothing useful. Bodies are exempt
+/// from schema validation; when enabled, only the transport envelope is
+/// checked: `{query, variables?, operationName?}` requests and
+/// `{data?, errors?, extensions?}` responses.
+#[derive(Debug, Default)]
+pub struct GraphQlValidator {
+    envelope: Option<Envelope>,
+}
+
+#[derive(Debug)]
+struct Envelope {
+    request: SchemaValidator,
+    response: SchemaValidator,
+}
+
+impl GraphQlValidator {
+    /// Creates a validator that exempts bodies entirely
+    pub fn passthrough() -> Self {
+        Self::default()
+    }
+
+    /// Creates a validator that checks the GraphQL request/response envelope
+    pub fn with_envelope() -> Result<Self, ValidationError> {
+        let request = compile(&json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string" },
+                "operationName": { "type": ["string", "null"] },
+                "variables": { "type": ["object", "null"] },
+                "extensions": { "type": ["object", "null"] }
+            },
+            "anyOf": [
+                { "required": ["query"] },
+                { "required": ["extensions"] }
+            ]
+        }))?;
+        let response = compile(&json!({
+            "type": "object",
+            "properties": {
+                "data": { "type": ["object", "null"] },
+                "errors": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["message"],
+                        "properties": { "message": { "type": "string" } }
+                    }
+                },
+                "extensions": { "type": "object" }
+            },
+            "anyOf": [
+                { "required": ["data"] },
+                { "required": ["errors"] }
+            ]
+        }))?;
+        Ok(Self {
+            envelope: Some(Envelope { request, response }),
+        })
+    }
+
+    /// Whether bodies need to be parsed at all
+    pub fn checks_envelope(&self) -> bool {
+        self.envelope.is_some()
+    }
+
+    /// Collects drift events for a GraphQL request body
+    pub fn request_drift_events(&self, body: Option<&Value>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.request_drift_events_with(body, emit))
+    }
+
+    /// Passes each drift event for a GraphQL request body to `emit`
+    pub fn request_drift_events_with(&self, body: Option<&Value>, emit: &mut dyn FnMut(DriftEvent)) {
+        if let (Some(envelope), Some(value)) = (&self.envelope, body) {
+            envelope_drift(&envelope.request, value, ValidationContext::RequestBody, emit);
+        }
+    }
+
+    /// Collects drift events for a GraphQL response body
+    pub fn response_drift_events(&self, body: Option<&Value>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.response_drift_events_with(body, emit))
+    }
+
+    /// Passes each drift event for a GraphQL response body to `emit`
+    pub fn response_drift_events_with(&self, body: Option<&Value>, emit: &mut dyn FnMut(DriftEvent)) {
+        if let (Some(envelope), Some(value)) = (&self.envelope, body) {
+            envelope_drift(&envelope.response, value, ValidationContext::ResponseBody, emit);
+        }
+    }
+}
+
+fn envelope_drift(
+    validator: &SchemaValidator,
+    value: &Value,
+    context: ValidationContext,
+    emit: &mut dyn FnMut(DriftEvent),
+) {
+    drift_events_with(validator, value, context, |path| format_instance_location(path, "body"), emit)
+}
+
+fn compile(schema: &Value) -> Result<SchemaValidator, ValidationError> {
+    jsonschema::validator_for(schema).map(SchemaValidator::compiled).map_err(|e| {
+        ValidationError::SchemaCompilationError(format!(
+            "Failed to compile GraphQL envelope schema: {}",
+            e
+        ))
+    })
+}
@@ -0,0 +1,124 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::exchange::{ObservedRequest, ObservedResponse};
+use crate::validation_helpers::gather_drift_events;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Default number of idempotency keys remembered per operation
+pub const DEFAULT_KEY_CAPACITY: usize = 10_000;
+
+/// Checks an operation's `Idempotency-Key` header contract
+///
+/// Requests must carry the header when the spec marks it required, and a
+/// replayed key must get the same status code as the first response seen for
+/// it. 5xx and 409 responses are never compared: servers don't store failed
+/// attempts, and 409 signals a retry racing an in-flight request.
+#[derive(Debug)]
+pub struct IdempotencyValidator {
+    header_name: String,
+    required: bool,
+    capacity: usize,
+    seen: Mutex<SeenKeys>,
+}
+
+/// First-seen status per key, evicted in insertion order
+#[derive(Debug, Default)]
+struct SeenKeys {
+    statuses: HashMap<String, u16>,
+    order: VecDeque<String>,
+}
+
+impl IdempotencyValidator {
+    pub fn new(header_name: String, required: bool) -> Self {
+        Self::with_capacity(header_name, required, DEFAULT_KEY_CAPACITY)
+    }
+
+    /// Creates a validator remembering at most `capacity` keys
+    pub fn with_capacity(header_name: String, required: bool, capacity: usize) -> Self {
+        Self {
+            header_name,
+            required,
+            capacity,
+            seen: Mutex::new(SeenKeys::default()),
+        }
+    }
+
+    /// Name of the idempotency header as declared in the spec
+    pub fn header_name(&self) -> &str {
+        &self.header_name
+    }
+
+    /// Collects drift events for a request missing a required key
+    pub fn request_drift_events(&self, request: &ObservedRequest) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.request_drift_events_with(request, emit))
+    }
+
+    /// Passes a drift event for a request missing a required key to `emit`
+    pub fn request_drift_events_with(&self, request: &ObservedRequest, emit: &mut dyn FnMut(DriftEvent)) {
+        if self.required && request.header(&self.header_name).is_none() {
+            emit(DriftEvent::new(
+                DriftType::IdempotencyKeyMissing,
+                format!("header/{}", self.header_name.to_ascii_lowercase()),
+                format!("Required idempotency header '{}' is missing", self.header_name),
+            ));
+        }
+    }
+
+    /// Records the response status for the request's key and reports inconsistent replays
+    pub fn replay_drift_events(
+        &self,
+        request: &ObservedRequest,
+        response: &ObservedResponse,
+    ) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.replay_drift_events_with(request, response, emit))
+    }
+
+    /// Records the response status for the request's key and passes an inconsistent replay to `emit`
+    pub fn replay_drift_events_with(
+        &self,
+        request: &ObservedRequest,
+        response: &ObservedResponse,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        let Some(key) = request.header(&self.header_name) else {
+            return;
+        };
+        if !is_comparable(response.status) {
+            return;
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match seen.statuses.get(key) {
+            Some(&first_status) if first_status != response.status => emit(DriftEvent::new(
+                DriftType::IdempotencyReplayMismatch,
+                format!("header/{}", self.header_name.to_ascii_lowercase()),
+                format!(
+                    "Replayed idempotency key '{}' returned {} but first returned {}",
+                    key, response.status, first_status
+                ),
+            )),
+            Some(_) => {}
+            None => seen.insert(key.to_string(), response.status, self.capacity),
+        }
+    }
+}
+
+impl SeenKeys {
+    fn insert(&mut self, key: String, status: u16, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        while self.order.len() >= capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+        self.order.push_back(key.clone());
+        self.statuses.insert(key, status);
+    }
+}
+
+fn is_comparable(status: u16) -> bool {
+    status != 409 && !(500..600).contains(&status)
+}
@@ -1,7 +1,19 @@
+pub mod deprecation;
+pub mod event_stream;
+pub mod graphql;
+pub mod idempotency;
 pub mod parameter;
+pub mod rate_limit;
 pub mod request;
 pub mod response;
+pub mod security;
 
-pub use parameter::{ParameterValidator, ParametersValidator};
+pub use deprecation::{DeprecatedParameterLocation, DeprecationValidator};
+pub use event_stream::EventStreamValidator;
+pub use graphql::GraphQlValidator;
+pub use idempotency::IdempotencyValidator;
+pub use parameter::{ParameterStyle, ParameterValidator, ParametersValidator, TRACKING_QUERY_PARAMETERS};
+pub use rate_limit::RateLimitValidator;
 pub use request::RequestBodyValidator;
 pub use response::ResponseValidator;
+pub use security::{Credential, SecurityValidator};
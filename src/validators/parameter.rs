@@ -1,16 +1,54 @@
-use crate::drift_types::{map_to_drift_type, DriftType, ValidationContext};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{DriftType, ValidationContext};
 use crate::error::ValidationError;
-use crate::validation_helpers::{build_validator, format_drift_error};
-use jsonschema::{Registry, Validator};
+use crate::validation_helpers::{
+    drift_events_to_result, drift_events_with, gather_drift_events, CompileSchema, SchemaValidator,
+};
+use crate::exchange::parse_query_string;
+use lru::LruCache;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+/// Coerced parameter values keyed by parameter name
+type ParamValues = HashMap<String, Value>;
+
+/// Coerced declared parameters and the names of undeclared ones found in a query string
+type CoercedQuery = (ParamValues, Vec<String>);
+
+/// Query parameters added by analytics and tracing tooling rather than by API clients
+pub const TRACKING_QUERY_PARAMETERS: &[&str] = &["utm_*", "trace_id"];
+
+/// How a query parameter's array or object value is serialized (OpenAPI `style`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParameterStyle {
+    /// `tags=a,b` or, exploded, `tags=a&tags=b`; exploded objects spread
+    /// their properties over the query (`status=active&size=2`)
+    #[default]
+    Form,
+    /// `tags=a%20b`
+    SpaceDelimited,
+    /// `tags=a|b`
+    PipeDelimited,
+    /// `filter[status]=active&filter[size]=2`
+    DeepObject,
+}
 
 /// Validator for a single parameter
 #[derive(Debug)]
 pub struct ParameterValidator {
     name: String,
     required: bool,
-    validator: Validator,
+    /// Declared JSON type, used to coerce raw string values from the URL
+    schema_type: Option<String>,
+    /// Declared type of array items
+    item_type: Option<String>,
+    /// Declared properties of an object value, with their types
+    properties: Vec<(String, Option<String>)>,
+    style: ParameterStyle,
+    explode: bool,
+    validator: SchemaValidator,
 }
 
 impl ParameterValidator {
@@ -19,44 +57,168 @@ impl ParameterValidator {
         name: String,
         required: bool,
         schema: &Value,
-        registry: &Registry,
+        registry: &dyn CompileSchema,
     ) -> Result<Self, ValidationError> {
-        let validator = build_validator(schema, registry, &format!("parameter '{}'", name))?;
+        let validator = registry.compile_schema(schema, &format!("parameter '{}'", name))?;
+        let type_of = |schema: &Value| schema.get("type").and_then(Value::as_str).map(str::to_string);
+        let properties = match schema.get("properties") {
+            Some(Value::Object(properties)) => properties
+                .iter()
+                .map(|(name, property)| (name.clone(), type_of(property)))
+                .collect(),
+            _ => Vec::new(),
+        };
         Ok(Self {
             name,
             required,
+            schema_type: type_of(schema),
+            item_type: schema.get("items").and_then(type_of),
+            properties,
+            style: ParameterStyle::Form,
+            explode: true,
             validator,
         })
     }
 
+    /// Sets how the value is serialized in the query string
+    ///
+    /// Defaults to OpenAPI's defaults for query parameters: `form`, exploded.
+    pub fn with_style(mut self, style: ParameterStyle, explode: bool) -> Self {
+        self.style = style;
+        self.explode = explode;
+        self
+    }
+
     /// Validate a parameter value
     pub fn validate(&self, value: &Value) -> Result<(), ValidationError> {
-        if self.validator.is_valid(value) {
-            Ok(())
-        } else {
-            let drift_errors: Vec<String> = self
-                .validator
-                .iter_errors(value)
-                .filter_map(|e| {
-                    map_to_drift_type(&e.kind, ValidationContext::Parameter).map(|drift_type| {
-                        let location = if e.instance_path.to_string().is_empty() {
-                            self.name.clone()
-                        } else {
-                            format!("{}[{}]", self.name, e.instance_path)
-                        };
-                        format_drift_error(drift_type, &location, &e.to_string())
-                    })
-                })
-                .collect();
-            
-            if drift_errors.is_empty() {
-                Ok(()) // No drift-relevant errors
+        drift_events_to_result(self.drift_events(value))
+    }
+
+    /// Collects drift events for a parameter value
+    pub fn drift_events(&self, value: &Value) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(value, emit))
+    }
+
+    /// Passes each drift event for a parameter value to `emit`
+    pub fn drift_events_with(&self, value: &Value, emit: &mut dyn FnMut(DriftEvent)) {
+        drift_events_with(&self.validator, value, ValidationContext::Parameter, |instance_path| {
+            if instance_path.is_empty() {
+                self.name.clone()
             } else {
-                Err(ValidationError::ValidationFailed(drift_errors.join("; ")))
+                format!("{}[{}]", self.name, instance_path)
+            }
+        }, emit)
+    }
+
+    /// Converts raw string occurrences from the URL into a JSON value matching the declared type
+    ///
+    /// Values that don't parse as the declared type are kept as strings so the
+    /// schema reports the type mismatch.
+    pub fn coerce(&self, raw_values: &[&str]) -> Value {
+        match self.schema_type.as_deref() {
+            Some("array") => {
+                let items = if raw_values.len() == 1 {
+                    raw_values[0].split(',').collect()
+                } else {
+                    raw_values.to_vec()
+                };
+                Value::Array(items.into_iter().map(|raw| coerce_as(raw, self.item_type.as_deref())).collect())
+            }
+            Some("integer") | Some("number") | Some("boolean") => {
+                raw_values.first().map(|raw| coerce_scalar(raw)).unwrap_or(Value::Null)
             }
+            _ => raw_values
+                .first()
+                .map(|raw| Value::String(raw.to_string()))
+                .unwrap_or(Value::Null),
         }
     }
 
+    /// Finds and coerces this parameter's value among decoded query string pairs
+    ///
+    /// Arrays and objects are read according to the parameter's style and
+    /// explode settings. Returns `None` when the parameter is absent.
+    pub fn coerce_query(&self, pairs: &[(String, String)]) -> Option<Value> {
+        let raw_values: Vec<&str> = pairs
+            .iter()
+            .filter(|(key, _)| *key == self.name)
+            .map(|(_, value)| value.as_str())
+            .collect();
+
+        match self.schema_type.as_deref() {
+            Some("array") => {
+                let separator = match (self.style, self.explode) {
+                    (ParameterStyle::Form, false) => Some(','),
+                    (ParameterStyle::SpaceDelimited, false) => Some(' '),
+                    (ParameterStyle::PipeDelimited, false) => Some('|'),
+                    _ => None,
+                };
+                let items: Vec<&str> = match separator {
+                    Some(separator) => raw_values.iter().flat_map(|raw| raw.split(separator)).collect(),
+                    None => raw_values,
+                };
+                (!items.is_empty()).then(|| {
+                    Value::Array(items.into_iter().map(|raw| coerce_as(raw, self.item_type.as_deref())).collect())
+                })
+            }
+            Some("object") => {
+                let members: Vec<(&str, &str)> = match (self.style, self.explode) {
+                    (ParameterStyle::DeepObject, _) => pairs
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            let property = key.strip_prefix(self.name.as_str())?.strip_prefix('[')?.strip_suffix(']')?;
+                            Some((property, value.as_str()))
+                        })
+                        .collect(),
+                    (ParameterStyle::Form, true) => pairs
+                        .iter()
+                        .filter(|(key, _)| self.properties.iter().any(|(name, _)| name == key))
+                        .map(|(key, value)| (key.as_str(), value.as_str()))
+                        .collect(),
+                    _ => {
+                        let raw = raw_values.first()?;
+                        let parts: Vec<&str> = raw.split(',').collect();
+                        // An odd number of parts leaves a dangling key, kept with an empty value
+                        parts.chunks(2).map(|pair| (pair[0], pair.get(1).copied().unwrap_or(""))).collect()
+                    }
+                };
+                (!members.is_empty()).then(|| {
+                    Value::Object(
+                        members
+                            .into_iter()
+                            .map(|(property, raw)| (property.to_string(), coerce_as(raw, self.property_type(property))))
+                            .collect(),
+                    )
+                })
+            }
+            _ => (!raw_values.is_empty()).then(|| self.coerce(&raw_values)),
+        }
+    }
+
+    /// Whether a query string key carries (part of) this parameter's value
+    ///
+    /// Besides the parameter's own name, this covers `name[prop]` keys of
+    /// deepObject parameters and the spread properties of exploded form objects.
+    pub fn consumes_query_key(&self, key: &str) -> bool {
+        if key == self.name {
+            return true;
+        }
+        match (self.schema_type.as_deref(), self.style, self.explode) {
+            (Some("object"), ParameterStyle::DeepObject, _) => key
+                .strip_prefix(self.name.as_str())
+                .is_some_and(|rest| rest.starts_with('[') && rest.ends_with(']')),
+            (Some("object"), ParameterStyle::Form, true) => self.properties.iter().any(|(name, _)| name == key),
+            _ => false,
+        }
+    }
+
+    fn property_type(&self, property: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(name, _)| name == property)
+            .and_then(|(_, schema_type)| schema_type.as_deref())
+    }
+
     /// Get the parameter name
     pub fn name(&self) -> &str {
         &self.name
@@ -77,6 +239,10 @@ pub struct ParametersValidator {
     query: Vec<ParameterValidator>,
     /// Header parameters
     header: Vec<ParameterValidator>,
+    /// Coerced query parameters per raw query string, when enabled
+    query_cache: Option<Mutex<LruCache<String, Arc<CoercedQuery>>>>,
+    /// Name patterns of undeclared query parameters not reported; `None` reports none at all
+    undocumented_query_allowlist: Option<Vec<String>>,
 }
 
 impl ParametersValidator {
@@ -95,6 +261,23 @@ impl ParametersValidator {
         self.query.push(validator);
     }
 
+    /// Memoizes coerced query parameters for up to `capacity` distinct raw query strings
+    ///
+    /// Worth enabling for GET-heavy proxy traffic where the same query
+    /// strings repeat; parsing and coercion are skipped on a hit.
+    pub fn enable_query_cache(&mut self, capacity: NonZeroUsize) {
+        self.query_cache = Some(Mutex::new(LruCache::new(capacity)));
+    }
+
+    /// Reports query parameters the operation doesn't declare as `UNDOCUMENTED_PARAMETER`
+    ///
+    /// Names matching an `allowlist` pattern are tolerated; a pattern ending
+    /// in `*` matches by prefix (`utm_*`), any other must match exactly. See
+    /// [`TRACKING_QUERY_PARAMETERS`] for a sensible starting point.
+    pub fn report_undocumented_query(&mut self, allowlist: Vec<String>) {
+        self.undocumented_query_allowlist = Some(allowlist);
+    }
+
     /// Add a header parameter validator
     pub fn add_header_parameter(&mut self, validator: ParameterValidator) {
         self.header.push(validator);
@@ -102,43 +285,145 @@ impl ParametersValidator {
 
     /// Validate path parameters
     pub fn validate_path(&self, params: &HashMap<String, Value>) -> Result<(), ValidationError> {
-        self.validate_parameters(&self.path, params, "path")
+        drift_events_to_result(gather_drift_events(|emit| Self::collect_drift(&self.path, params, emit)))
     }
 
     /// Validate query parameters
     pub fn validate_query(&self, params: &HashMap<String, Value>) -> Result<(), ValidationError> {
-        self.validate_parameters(&self.query, params, "query")
+        drift_events_to_result(gather_drift_events(|emit| Self::collect_drift(&self.query, params, emit)))
     }
 
     /// Validate header parameters
     pub fn validate_headers(&self, params: &HashMap<String, Value>) -> Result<(), ValidationError> {
-        self.validate_parameters(&self.header, params, "header")
+        drift_events_to_result(gather_drift_events(|emit| Self::collect_drift(&self.header, params, emit)))
+    }
+
+    /// Collects drift events for raw path parameters captured by the router
+    pub fn path_drift_events(&self, raw: &HashMap<String, String>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.path_drift_events_with(raw, emit))
+    }
+
+    /// Passes each drift event for raw path parameters to `emit`
+    pub fn path_drift_events_with(&self, raw: &HashMap<String, String>, emit: &mut dyn FnMut(DriftEvent)) {
+        let params = self.path.iter()
+            .filter_map(|v| raw.get(v.name()).map(|value| (v.name().to_string(), v.coerce(&[value]))))
+            .collect();
+        Self::collect_drift(&self.path, &params, emit)
+    }
+
+    /// Collects drift events for decoded query string pairs
+    pub fn query_drift_events(&self, pairs: &[(String, String)]) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.query_drift_events_with(pairs, emit))
+    }
+
+    /// Passes each drift event for decoded query string pairs to `emit`
+    pub fn query_drift_events_with(&self, pairs: &[(String, String)], emit: &mut dyn FnMut(DriftEvent)) {
+        let (params, undocumented) = self.coerce_query(pairs);
+        Self::collect_drift(&self.query, &params, emit);
+        Self::undocumented_drift(&undocumented, emit);
+    }
+
+    /// Passes each drift event for a raw query string (without the `?`) to `emit`
+    ///
+    /// Uses the query cache when enabled.
+    pub fn query_string_drift_events_with(&self, query: Option<&str>, emit: &mut dyn FnMut(DriftEvent)) {
+        if self.query.is_empty() && self.undocumented_query_allowlist.is_none() {
+            return;
+        }
+        let query = query.unwrap_or_default();
+        let Some(cache) = &self.query_cache else {
+            return self.query_drift_events_with(&parse_query_string(query), emit);
+        };
+
+        let cached = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(query).cloned();
+        let coerced = cached.unwrap_or_else(|| {
+            let coerced = Arc::new(self.coerce_query(&parse_query_string(query)));
+            cache
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .put(query.to_string(), Arc::clone(&coerced));
+            coerced
+        });
+        let (params, undocumented) = &*coerced;
+        Self::collect_drift(&self.query, params, emit);
+        Self::undocumented_drift(undocumented, emit);
+    }
+
+    /// Coerces the raw values of every declared query parameter present in `pairs`,
+    /// and lists the undeclared ones when they are reported
+    fn coerce_query(&self, pairs: &[(String, String)]) -> CoercedQuery {
+        let params = self.query.iter()
+            .filter_map(|v| Some((v.name().to_string(), v.coerce_query(pairs)?)))
+            .collect();
+        let mut undocumented: Vec<String> = Vec::new();
+        if let Some(allowlist) = &self.undocumented_query_allowlist {
+            for (key, _) in pairs {
+                if !self.query.iter().any(|v| v.consumes_query_key(key))
+                    && !allowlist.iter().any(|pattern| matches_name_pattern(pattern, key))
+                    && !undocumented.contains(key)
+                {
+                    undocumented.push(key.clone());
+                }
+            }
+        }
+        (params, undocumented)
     }
 
-    /// Internal helper to validate a set of parameters
-    fn validate_parameters(
-        &self,
+    fn undocumented_drift(names: &[String], emit: &mut dyn FnMut(DriftEvent)) {
+        for name in names {
+            emit(DriftEvent::new(
+                DriftType::UndocumentedParameter,
+                name,
+                format!("Query parameter '{}' is not declared by the operation", name),
+            ));
+        }
+    }
+
+    /// Internal helper to collect drift for a set of parameters
+    fn collect_drift(
         validators: &[ParameterValidator],
         params: &HashMap<String, Value>,
-        _location: &str,
-    ) -> Result<(), ValidationError> {
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
         for validator in validators {
             match params.get(validator.name()) {
-                Some(value) => {
-                    validator.validate(value)?;
-                }
+                Some(value) => validator.drift_events_with(value, emit),
                 None => {
                     if validator.is_required() {
-                        let drift_error = format_drift_error(
+                        emit(DriftEvent::new(
                             DriftType::ParameterMissingRequired,
                             validator.name(),
-                            &format!("Required parameter '{}' is missing", validator.name())
-                        );
-                        return Err(ValidationError::ValidationFailed(drift_error));
+                            format!("Required parameter '{}' is missing", validator.name()),
+                        ));
                     }
                 }
             }
         }
-        Ok(())
     }
 }
+
+/// Matches a parameter name against an exact name or a `prefix*` pattern
+fn matches_name_pattern(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// Parses a raw value as its declared type, keeping it as a string if it isn't one
+///
+/// Without a declared type, numbers and booleans are recognised.
+fn coerce_as(raw: &str, schema_type: Option<&str>) -> Value {
+    match schema_type {
+        Some("integer") | Some("number") | Some("boolean") | None => coerce_scalar(raw),
+        Some(_) => Value::String(raw.to_string()),
+    }
+}
+
+/// Parses a single raw value as a JSON scalar, falling back to a string
+fn coerce_scalar(raw: &str) -> Value {
+    serde_json::from_str::<Value>(raw)
+        .ok()
+        .filter(|v| v.is_number() || v.is_boolean())
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
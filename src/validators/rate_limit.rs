@@ -0,0 +1,186 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::exchange::ObservedResponse;
+use crate::validation_helpers::gather_drift_events;
+use std::collections::HashMap;
+
+/// Role a documented rate-limit header plays in the contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitField {
+    /// `X-RateLimit-Limit` / `RateLimit-Limit`
+    Limit,
+    /// `X-RateLimit-Remaining` / `RateLimit-Remaining`
+    Remaining,
+    /// `X-RateLimit-Reset` / `RateLimit-Reset`
+    Reset,
+    /// Structured `RateLimit` field (e.g. `limit=100, remaining=50, reset=30`)
+    Structured,
+    /// Structured `RateLimit-Policy` field (e.g. `"default";q=100;w=60`)
+    Policy,
+    /// Any other `X-RateLimit-*` header, checked for presence only
+    Other,
+}
+
+impl RateLimitField {
+    /// Classifies a header name, returning `None` for non rate-limit headers
+    pub fn classify(header_name: &str) -> Option<Self> {
+        let name = header_name.to_ascii_lowercase();
+        let suffix = name
+            .strip_prefix("x-ratelimit-")
+            .or_else(|| name.strip_prefix("x-rate-limit-"))
+            .or_else(|| name.strip_prefix("ratelimit-"));
+
+        match (name.as_str(), suffix) {
+            ("ratelimit", _) => Some(Self::Structured),
+            (_, Some("limit")) => Some(Self::Limit),
+            (_, Some("remaining")) => Some(Self::Remaining),
+            (_, Some("reset")) => Some(Self::Reset),
+            ("ratelimit-policy", _) => Some(Self::Policy),
+            (_, Some(_)) if !name.starts_with("ratelimit-") => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// A rate-limit header declared on a response
+#[derive(Debug, Clone)]
+struct DocumentedHeader {
+    name: String,
+    field: RateLimitField,
+}
+
+/// Checks the rate-limit header contract of an operation's responses
+///
+/// Every documented rate-limit header must be present, numeric headers must
+/// parse as non-negative integers, and `remaining` must not exceed `limit`.
+#[derive(Debug, Default)]
+pub struct RateLimitValidator {
+    exact: HashMap<u16, Vec<DocumentedHeader>>,
+    default: Vec<DocumentedHeader>,
+}
+
+impl RateLimitValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a documented header for a status code; non rate-limit headers are ignored
+    pub fn add_header(&mut self, status_code: u16, header_name: &str) {
+        if let Some(header) = documented_header(header_name) {
+            self.exact.entry(status_code).or_default().push(header);
+        }
+    }
+
+    /// Registers a documented header on the default response
+    pub fn add_default_header(&mut self, header_name: &str) {
+        if let Some(header) = documented_header(header_name) {
+            self.default.push(header);
+        }
+    }
+
+    /// Whether any response documents rate-limit headers
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.default.is_empty()
+    }
+
+    /// Collects drift events for the rate-limit headers of a response
+    pub fn drift_events(&self, response: &ObservedResponse) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(response, emit))
+    }
+
+    /// Passes each drift event for the rate-limit headers of a response to `emit`
+    pub fn drift_events_with(&self, response: &ObservedResponse, emit: &mut dyn FnMut(DriftEvent)) {
+        let documented = match self.exact.get(&response.status) {
+            Some(headers) => headers,
+            None => &self.default,
+        };
+
+        let mut limit = None;
+        let mut remaining = None;
+
+        for header in documented {
+            let Some(value) = response.header(&header.name) else {
+                emit(DriftEvent::new(
+                    DriftType::RateLimitHeaderMissing,
+                    header_location(&header.name),
+                    format!("Documented rate-limit header '{}' is missing", header.name),
+                ));
+                continue;
+            };
+
+            let parsed = match header.field {
+                RateLimitField::Limit | RateLimitField::Remaining | RateLimitField::Reset => {
+                    parse_count(value).map(|count| vec![(header.field, count)])
+                }
+                RateLimitField::Structured | RateLimitField::Policy => {
+                    parse_structured(header.field, value)
+                }
+                RateLimitField::Other => Some(Vec::new()),
+            };
+
+            match parsed {
+                Some(fields) => {
+                    for (field, count) in fields {
+                        match field {
+                            RateLimitField::Limit => limit = limit.or(Some(count)),
+                            RateLimitField::Remaining => remaining = remaining.or(Some(count)),
+                            _ => {}
+                        }
+                    }
+                }
+                None => emit(DriftEvent::new(
+                    DriftType::RateLimitHeaderInvalid,
+                    header_location(&header.name),
+                    format!("Rate-limit header '{}' has non-numeric value '{}'", header.name, value),
+                )),
+            }
+        }
+
+        if let (Some(limit), Some(remaining)) = (limit, remaining) {
+            if remaining > limit {
+                emit(DriftEvent::new(
+                    DriftType::RateLimitHeaderInconsistent,
+                    "header",
+                    format!("Rate-limit remaining ({}) exceeds limit ({})", remaining, limit),
+                ));
+            }
+        }
+    }
+}
+
+fn documented_header(header_name: &str) -> Option<DocumentedHeader> {
+    RateLimitField::classify(header_name).map(|field| DocumentedHeader {
+        name: header_name.to_string(),
+        field,
+    })
+}
+
+fn header_location(name: &str) -> String {
+    format!("header/{}", name.to_ascii_lowercase())
+}
+
+fn parse_count(value: &str) -> Option<u64> {
+    value.trim().parse().ok()
+}
+
+/// Extracts limit/remaining/reset from a structured field
+///
+/// Understands both `limit=100, remaining=50, reset=30` and the newer
+/// `"policy";q=100;r=50;t=30` parameter style. Returns `None` if a known
+/// parameter has a non-numeric value.
+fn parse_structured(field: RateLimitField, value: &str) -> Option<Vec<(RateLimitField, u64)>> {
+    let mut fields = Vec::new();
+    for param in value.split([',', ';']) {
+        let Some((key, raw)) = param.split_once('=') else {
+            continue;
+        };
+        let target = match (field, key.trim()) {
+            (_, "limit") | (RateLimitField::Policy, "q") => RateLimitField::Limit,
+            (_, "remaining") | (RateLimitField::Structured, "r") => RateLimitField::Remaining,
+            (_, "reset") | (RateLimitField::Structured, "t") => RateLimitField::Reset,
+            _ => continue,
+        };
+        fields.push((target, parse_count(raw)?));
+    }
+    Some(fields)
+}
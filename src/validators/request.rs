@@ -1,12 +1,15 @@
-use crate::drift_types::{map_to_drift_type, DriftType, ValidationContext};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{DriftType, ValidationContext};
 use crate::error::ValidationError;
-use crate::validation_helpers::{build_validator, format_drift_error, format_instance_location};
-use jsonschema::{Registry, Validator};
+use crate::validation_helpers::{
+    drift_events_to_result, drift_events_with, format_instance_location,
+    gather_drift_events, CompileSchema, SchemaValidator,
+};
 use serde_json::Value; 
 
 /// Validator for request body against a JSON Schema
 pub struct RequestBodyValidator {
-    schema: Validator,
+    schema: SchemaValidator,
     required: bool,
 }
 
@@ -15,48 +18,37 @@ impl RequestBodyValidator {
     pub fn new(
         schema_value: &Value, 
         required: bool,
-        registry: &Registry,
+        registry: &dyn CompileSchema,
     ) -> Result<Self, ValidationError> {
-        let schema = build_validator(schema_value, registry, "request body")?;
+        let schema = registry.compile_schema(schema_value, "request body")?;
         Ok(Self { schema, required })
     }
 
     /// Validates request body against schema
     pub fn validate(&self, body: Option<&Value>) -> Result<(), ValidationError> {
+        drift_events_to_result(self.drift_events(body))
+    }
+
+    /// Collects drift events for a request body
+    pub fn drift_events(&self, body: Option<&Value>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(body, emit))
+    }
+
+    /// Passes each drift event for a request body to `emit`
+    pub fn drift_events_with(&self, body: Option<&Value>, emit: &mut dyn FnMut(DriftEvent)) {
         match body {
             None => {
                 if self.required {
-                    let drift_error = format_drift_error(
+                    emit(DriftEvent::new(
                         DriftType::RequestBodyMissingRequired,
                         "body",
-                        "Request body is required but missing"
-                    );
-                    Err(ValidationError::ValidationFailed(drift_error))
-                } else {
-                    Ok(())
-                }
-            }
-            Some(value) => {
-                if self.schema.is_valid(value) {
-                    Ok(())
-                } else {
-                    let drift_errors: Vec<String> = self.schema
-                        .iter_errors(value)
-                        .filter_map(|e| {
-                            map_to_drift_type(&e.kind, ValidationContext::RequestBody).map(|drift_type| {
-                                let location = format_instance_location(&e.instance_path.to_string(), "body");
-                                format_drift_error(drift_type, &location, &e.to_string())
-                            })
-                        })
-                        .collect();
-                    
-                    if drift_errors.is_empty() {
-                        Ok(())
-                    } else {
-                        Err(ValidationError::ValidationFailed(drift_errors.join("; ")))
-                    }
+                        "Request body is required but missing",
+                    ));
                 }
             }
+            Some(value) => drift_events_with(&self.schema, value, ValidationContext::RequestBody, |path| {
+                format_instance_location(path, "body")
+            }, emit),
         }
     }
 }
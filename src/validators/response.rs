@@ -1,23 +1,37 @@
-use crate::drift_types::{map_to_drift_type, ValidationContext};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::{DriftType, ValidationContext};
 use crate::error::ValidationError;
-use crate::validation_helpers::{build_validator, format_drift_error, format_instance_location};
-use jsonschema::{Registry, Validator};
-use serde_json::Value;
-use std::collections::HashMap;
+use crate::validation_helpers::{
+    collect_drift_events, drift_events_to_result, drift_events_with,
+    format_instance_location, gather_drift_events, CompileSchema, SchemaValidator,
+};
+use crate::validators::EventStreamValidator;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
 
 /// Validator for response bodies against JSON Schemas based on status codes
+#[derive(Default)]
 pub struct ResponseValidator {
-    exact: HashMap<u16, Validator>,
-    default: Option<Validator>,
+    exact: HashMap<u16, SchemaValidator>,
+    default: Option<SchemaValidator>,
+    /// Status codes documented as `text/event-stream`
+    streams: HashMap<u16, EventStreamValidator>,
+    default_stream: Option<EventStreamValidator>,
+    /// Every status code documented in the spec, with or without a schema
+    documented: BTreeSet<u16>,
+    /// Documented status classes (`2` for `2XX`)
+    documented_ranges: BTreeSet<u16>,
+    documented_default: bool,
+    /// Media types declared per status code, status class and for `default`
+    media_types: HashMap<u16, Vec<String>>,
+    range_media_types: HashMap<u16, Vec<String>>,
+    default_media_types: Option<Vec<String>>,
 }
 
 impl ResponseValidator {
     /// Create a new empty ResponseValidator
     pub fn new() -> Self {
-        Self {
-            exact: HashMap::new(),
-            default: None,
-        }
+        Self::default()
     }
 
     /// Adds response schema for a specific status code
@@ -25,9 +39,9 @@ impl ResponseValidator {
         &mut self,
         status_code: u16,
         schema: &Value,
-        registry: &Registry,
+        registry: &dyn CompileSchema,
     ) -> Result<(), ValidationError> {
-        let validator = build_validator(schema, registry, &format!("response {}", status_code))?;
+        let validator = registry.compile_schema(schema, &format!("response {}", status_code))?;
         self.exact.insert(status_code, validator);
         Ok(())
     }
@@ -36,46 +50,199 @@ impl ResponseValidator {
     pub fn set_default(
         &mut self, 
         schema: &Value,
-        registry: &Registry,
+        registry: &dyn CompileSchema,
     ) -> Result<(), ValidationError> {
-        let validator = build_validator(schema, registry, "default response")?;
+        let validator = registry.compile_schema(schema, "default response")?;
         self.default = Some(validator);
         Ok(())
     }
 
+    /// Marks a status code as serving a `text/event-stream`
+    pub fn add_event_stream(&mut self, status_code: u16, stream: EventStreamValidator) {
+        self.streams.insert(status_code, stream);
+    }
+
+    /// Marks the default response as serving a `text/event-stream`
+    pub fn set_default_event_stream(&mut self, stream: EventStreamValidator) {
+        self.default_stream = Some(stream);
+    }
+
+    /// Finds the event stream validator to use for a response
+    ///
+    /// Streams apply when the status documents `text/event-stream` and either
+    /// the response declares that content type or no JSON schema exists for
+    /// the status, so operations documenting both media types still get
+    /// their JSON responses validated.
+    pub fn event_stream_for(&self, status_code: u16, content_type: Option<&str>) -> Option<&EventStreamValidator> {
+        let (stream, has_json) = match self.streams.get(&status_code) {
+            Some(stream) => (stream, self.exact.contains_key(&status_code)),
+            None if !self.exact.contains_key(&status_code) => {
+                (self.default_stream.as_ref()?, self.default.is_some())
+            }
+            None => return None,
+        };
+
+        let declares_stream = content_type
+            .map(|ct| ct.trim_start().to_ascii_lowercase().starts_with("text/event-stream"))
+            .unwrap_or(false);
+        (declares_stream || !has_json).then_some(stream)
+    }
+
     /// Validates response body against schema for the given status code
     pub fn validate(&self, status_code: u16, body: Option<&Value>) -> Result<(), ValidationError> {
         // Find the appropriate validator (exact match first, then default)
-        let validator = self.exact.get(&status_code)
-            .or(self.default.as_ref())
-            .ok_or_else(|| ValidationError::NoSchemaForStatusCode(status_code))?;
+        let validator = self.validator_for(status_code)
+            .ok_or(ValidationError::NoSchemaForStatusCode(status_code))?;
         
         match body {
-            Some(value) => {
-                if validator.is_valid(value) {
-                    Ok(())
-                } else {
-                    let drift_errors: Vec<String> = validator
-                        .iter_errors(value)
-                        .filter_map(|e| {
-                            map_to_drift_type(&e.kind, ValidationContext::ResponseBody).map(|drift_type| {
-                                let location = format_instance_location(&e.instance_path.to_string(), "body");
-                                format_drift_error(drift_type, &location, &e.to_string())
-                            })
-                        })
-                        .collect();
-                    
-                    if drift_errors.is_empty() {
-                        Ok(()) // No drift-relevant errors
-                    } else {
-                        Err(ValidationError::ValidationFailed(drift_errors.join("; ")))
-                    }
-                }
-            }
+            Some(value) => drift_events_to_result(Self::collect_drift(validator, value)),
             None => {
                 // No body provided - this is valid for responses like 204 No Content
                 Ok(())
             }
         }
     }
+
+    /// Collects drift events for a response body
+    ///
+    /// Status codes without a documented schema produce no events.
+    pub fn drift_events(&self, status_code: u16, body: Option<&Value>) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(status_code, body, emit))
+    }
+
+    /// Passes each drift event for a response body to `emit`
+    pub fn drift_events_with(&self, status_code: u16, body: Option<&Value>, emit: &mut dyn FnMut(DriftEvent)) {
+        if let (Some(validator), Some(value)) = (self.validator_for(status_code), body) {
+            drift_events_with(validator, value, ValidationContext::ResponseBody, |path| {
+                format_instance_location(path, "body")
+            }, emit);
+        }
+    }
+
+    /// Records a status code as documented, whether or not it has a body schema
+    pub fn document_status(&mut self, status_code: u16) {
+        self.documented.insert(status_code);
+    }
+
+    /// Records a status class (`2` for `2XX`) as documented
+    pub fn document_range(&mut self, class: u16) {
+        self.documented_ranges.insert(class);
+    }
+
+    /// Records that a `default` response is documented
+    pub fn document_default(&mut self) {
+        self.documented_default = true;
+    }
+
+    /// Records the media types a status code declares (none for bodiless responses)
+    pub fn declare_media_types(&mut self, status_code: u16, media_types: Vec<String>) {
+        self.media_types.insert(status_code, media_types);
+    }
+
+    /// Records the media types a status class (`2` for `2XX`) declares
+    pub fn declare_range_media_types(&mut self, class: u16, media_types: Vec<String>) {
+        self.range_media_types.insert(class, media_types);
+    }
+
+    /// Records the media types the `default` response declares
+    pub fn set_default_media_types(&mut self, media_types: Vec<String>) {
+        self.default_media_types = Some(media_types);
+    }
+
+    /// Media types declared for a status code, `None` if the status isn't documented
+    pub fn declared_media_types(&self, status_code: u16) -> Option<&[String]> {
+        self.media_types
+            .get(&status_code)
+            .or_else(|| self.range_media_types.get(&(status_code / 100)))
+            .or(self.default_media_types.as_ref())
+            .map(Vec::as_slice)
+    }
+
+    /// Passes a drift event to `emit` if a response with a body is served
+    /// with a `Content-Type` its status doesn't declare
+    ///
+    /// Media type parameters (`; charset=utf-8`) are ignored and declared
+    /// wildcards (`text/*`, `*/*`) match. Responses without a body or a
+    /// `Content-Type` header, and undocumented statuses, are not checked.
+    /// Returns whether drift was found.
+    pub fn content_type_drift_events_with(
+        &self,
+        status_code: u16,
+        content_type: Option<&str>,
+        has_body: bool,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) -> bool {
+        let (Some(declared), Some(content_type), true) =
+            (self.declared_media_types(status_code), content_type, has_body)
+        else {
+            return false;
+        };
+        let observed = media_type_essence(content_type);
+        if observed.is_empty() || declared.iter().any(|media_type| media_type_matches(media_type, &observed)) {
+            return false;
+        }
+
+        let message = if declared.is_empty() {
+            format!(
+                "Response {} was served as {} but the spec declares no content for it",
+                status_code, observed
+            )
+        } else {
+            format!(
+                "Response {} was served as {} but the spec declares {}",
+                status_code,
+                observed,
+                declared.join(", ")
+            )
+        };
+        emit(
+            DriftEvent::new(DriftType::ResponseContentTypeDrift, "header/content-type", message)
+                .with_observed(&json!(observed)),
+        );
+        true
+    }
+
+    /// Whether the spec documents a response for a status code
+    pub fn is_documented(&self, status_code: u16) -> bool {
+        self.documented_default
+            || self.documented.contains(&status_code)
+            || self.documented_ranges.contains(&(status_code / 100))
+    }
+
+    /// Documented responses as written in the spec (`200`, `4XX`, `default`)
+    pub fn documented_statuses(&self) -> Vec<String> {
+        self.documented
+            .iter()
+            .map(u16::to_string)
+            .chain(self.documented_ranges.iter().map(|class| format!("{}XX", class)))
+            .chain(self.documented_default.then(|| "default".to_string()))
+            .collect()
+    }
+
+    /// Finds the validator for a status code (exact match first, then default)
+    fn validator_for(&self, status_code: u16) -> Option<&SchemaValidator> {
+        self.exact.get(&status_code).or(self.default.as_ref())
+    }
+
+    fn collect_drift(validator: &SchemaValidator, value: &Value) -> Vec<DriftEvent> {
+        collect_drift_events(validator, value, ValidationContext::ResponseBody, |path| {
+            format_instance_location(path, "body")
+        })
+    }
+}
+
+/// The `type/subtype` of a media type, lowercased and without parameters
+pub(crate) fn media_type_essence(media_type: &str) -> String {
+    media_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+pub(crate) fn media_type_matches(declared: &str, observed: &str) -> bool {
+    let declared = media_type_essence(declared);
+    match declared.split_once('/') {
+        _ if declared == observed || declared == "*/*" => true,
+        Some((declared_type, "*")) => observed
+            .split_once('/')
+            .is_some_and(|(observed_type, _)| observed_type == declared_type),
+        _ => false,
+    }
 }
@@ -0,0 +1,198 @@
+use crate::drift_event::DriftEvent;
+use crate::drift_types::DriftType;
+use crate::exchange::{ObservedRequest, ObservedResponse};
+use crate::validation_helpers::gather_drift_events;
+use serde_json::Value;
+
+/// A credential a security scheme expects on the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// An API key in the named header
+    HeaderApiKey(String),
+    /// An API key in the named query parameter
+    QueryApiKey(String),
+    /// An API key in the named cookie
+    CookieApiKey(String),
+    /// `Authorization: <scheme> ...` (`Bearer`, `Basic`, `Digest`, ...)
+    ///
+    /// OAuth2 and OpenID Connect schemes expect `Bearer` tokens; `scopes`
+    /// lists the scopes the operation requires of them.
+    Authorization { scheme: String, scopes: Vec<String> },
+}
+
+impl Credential {
+    /// Short description for drift messages, e.g. `API key in header X-API-Key`
+    pub fn describe(&self) -> String {
+        match self {
+            Self::HeaderApiKey(name) => format!("API key in header {}", name),
+            Self::QueryApiKey(name) => format!("API key in query parameter {}", name),
+            Self::CookieApiKey(name) => format!("API key in cookie {}", name),
+            Self::Authorization { scheme, scopes } if scopes.is_empty() => {
+                format!("Authorization: {}", scheme)
+            }
+            Self::Authorization { scheme, scopes } => {
+                format!("Authorization: {} with scopes [{}]", scheme, scopes.join(", "))
+            }
+        }
+    }
+}
+
+/// Checks that requests carry the credentials an operation's `security` declares
+///
+/// The spec lists alternative requirements, each a set of schemes that must
+/// all be present; a request satisfying any alternative passes. Credentials
+/// are only checked for presence; OAuth scopes are checked when the bearer
+/// token is a JWT carrying a `scope` or `scp` claim, and ignored for opaque
+/// tokens. Requests the server rejected with 401 or 403 are skipped: the
+/// server enforced the contract, so there is no drift to report.
+#[derive(Debug, Default)]
+pub struct SecurityValidator {
+    alternatives: Vec<Vec<Credential>>,
+}
+
+impl SecurityValidator {
+    /// Creates a validator from alternative requirements
+    ///
+    /// An empty alternative (`{}` in the spec) makes authentication optional,
+    /// so nothing is checked.
+    pub fn new(alternatives: Vec<Vec<Credential>>) -> Self {
+        Self { alternatives }
+    }
+
+    /// Whether the operation requires any credentials at all
+    pub fn is_empty(&self) -> bool {
+        self.alternatives.is_empty() || self.alternatives.iter().any(Vec::is_empty)
+    }
+
+    /// Collects a drift event for a request missing its declared credentials
+    pub fn drift_events(&self, request: &ObservedRequest, response: &ObservedResponse) -> Vec<DriftEvent> {
+        gather_drift_events(|emit| self.drift_events_with(request, response, emit))
+    }
+
+    /// Passes a drift event for a request missing its declared credentials to `emit`
+    pub fn drift_events_with(
+        &self,
+        request: &ObservedRequest,
+        response: &ObservedResponse,
+        emit: &mut dyn FnMut(DriftEvent),
+    ) {
+        if self.is_empty() || matches!(response.status, 401 | 403) {
+            return;
+        }
+        let satisfied = self
+            .alternatives
+            .iter()
+            .any(|alternative| alternative.iter().all(|credential| is_present(credential, request)));
+        if satisfied {
+            return;
+        }
+
+        let expected: Vec<String> = self
+            .alternatives
+            .iter()
+            .map(|alternative| {
+                alternative.iter().map(Credential::describe).collect::<Vec<_>>().join(" + ")
+            })
+            .collect();
+        emit(DriftEvent::new(
+            DriftType::SecurityRequirementDrift,
+            "security",
+            format!(
+                "Request carried {} but the spec requires {}",
+                observed_mechanism(request),
+                expected.join(" or ")
+            ),
+        ));
+    }
+}
+
+fn is_present(credential: &Credential, request: &ObservedRequest) -> bool {
+    match credential {
+        Credential::HeaderApiKey(name) => request.header(name).is_some_and(|value| !value.is_empty()),
+        Credential::QueryApiKey(name) => request
+            .query_pairs()
+            .iter()
+            .any(|(key, value)| key == name && !value.is_empty()),
+        Credential::CookieApiKey(name) => cookie(request, name).is_some_and(|value| !value.is_empty()),
+        Credential::Authorization { scheme, scopes } => {
+            let Some((observed_scheme, token)) = authorization(request) else {
+                return false;
+            };
+            observed_scheme.eq_ignore_ascii_case(scheme)
+                && !token.is_empty()
+                && (scopes.is_empty() || has_scopes(token, scopes))
+        }
+    }
+}
+
+/// Splits the `Authorization` header into its scheme and credentials
+fn authorization(request: &ObservedRequest) -> Option<(&str, &str)> {
+    let value = request.header("authorization")?.trim();
+    Some(value.split_once(' ').map(|(scheme, token)| (scheme, token.trim())).unwrap_or((value, "")))
+}
+
+fn cookie<'a>(request: &'a ObservedRequest, name: &str) -> Option<&'a str> {
+    request
+        .header("cookie")?
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Whether a bearer token grants every required scope
+///
+/// Opaque tokens can't be inspected and are assumed to.
+fn has_scopes(token: &str, required: &[String]) -> bool {
+    let Some(claims) = jwt_claims(token) else {
+        return true;
+    };
+    let granted: Vec<&str> = match claims.get("scope").or_else(|| claims.get("scp")) {
+        Some(Value::String(scopes)) => scopes.split_whitespace().collect(),
+        Some(Value::Array(scopes)) => scopes.iter().filter_map(Value::as_str).collect(),
+        _ => return true,
+    };
+    required.iter().all(|scope| granted.contains(&scope.as_str()))
+}
+
+/// Decodes the (unverified) payload of a JWT
+fn jwt_claims(token: &str) -> Option<Value> {
+    let mut segments = token.split('.');
+    let (_header, payload, _signature) = (segments.next()?, segments.next()?, segments.next()?);
+    if segments.next().is_some() {
+        return None;
+    }
+    serde_json::from_slice(&decode_base64url(payload)?).ok()
+}
+
+fn decode_base64url(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' | b'+' => 62,
+            b'_' | b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// What authentication a request carried, for drift messages
+fn observed_mechanism(request: &ObservedRequest) -> String {
+    match authorization(request) {
+        Some((scheme, _)) => format!("Authorization: {}", scheme),
+        None => "no Authorization header".to_string(),
+    }
+}
@@ -0,0 +1,137 @@
+//! Drift checks from JavaScript, for edge workers and browser extensions
+//!
+//! Built for `wasm32-unknown-unknown` without default features, e.g. with
+//! `wasm-pack build --no-default-features --features wasm`. Specs,
+//! configuration, exchanges and events all cross the boundary as JSON
+//! strings; a YAML spec has to be converted to JSON first.
+//!
+//! ```js
+//! const checker = new DriftChecker(specJson, JSON.stringify({ strict: true }));
+//! const events = JSON.parse(checker.validateExchange(JSON.stringify({
+//!   method: "GET",
+//!   path: "/users/42",
+//!   status: 200,
+//!   response_headers: { "content-type": "application/json" },
+//!   response_body: { id: 42 },
+//! })));
+//! ```
+
+use crate::api_validator::{ApiValidator, HttpMethod};
+use crate::error::ValidationError;
+use crate::exchange::{Exchange, ObservedRequest, ObservedResponse};
+use crate::spec::builder::{build_api_validator_with_options, BuildOptions};
+use crate::validator_config::ValidatorConfig;
+use openapiv3::OpenAPI;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+/// A validator for one spec, built once and reused for every exchange
+#[wasm_bindgen]
+pub struct DriftChecker {
+    validator: ApiValidator,
+}
+
+#[wasm_bindgen]
+impl DriftChecker {
+    /// Builds the validator for an OpenAPI spec in JSON, configured by an
+    /// optional `validation` section of the monitor configuration, in JSON
+    #[wasm_bindgen(constructor)]
+    pub fn new(spec: &str, config: Option<String>) -> Result<DriftChecker, JsError> {
+        // Through a document first: typing a spec with a duplicated key straight
+        // from its text can panic in `openapiv3`
+        let spec: OpenAPI = serde_json::from_str(spec)
+            .and_then(serde_json::from_value)
+            .map_err(|e| ValidationError::SpecParse { reason: e.to_string() })?;
+        let validation: ValidatorConfig = match config {
+            Some(config) => serde_json::from_str(&config)
+                .map_err(|e| ValidationError::ConfigError(format!("validation config: {}", e)))?,
+            None => ValidatorConfig::default(),
+        };
+        let options = BuildOptions {
+            validation,
+            ..BuildOptions::default()
+        };
+        let validator = build_api_validator_with_options(&spec, &options)?;
+        Ok(Self { validator })
+    }
+
+    /// Validates an exchange, given in the JSON form of a JSONL traffic
+    /// record, returning its drift events as a JSON array
+    #[wasm_bindgen(js_name = validateExchange)]
+    pub fn validate_exchange(&self, exchange: &str) -> Result<String, JsError> {
+        let record: ExchangeRecord = serde_json::from_str(exchange)
+            .map_err(|e| ValidationError::TrafficError(format!("invalid exchange: {}", e)))?;
+        let events = self.validator.validate_exchange(&record.to_exchange()?)?;
+        Ok(serde_json::to_string(&events)?)
+    }
+
+    /// Spec path template a concrete path routes to, if any
+    #[wasm_bindgen(js_name = pathTemplate)]
+    pub fn path_template(&self, path: &str) -> Option<String> {
+        self.validator.path_template(path).map(str::to_string)
+    }
+
+    /// Events dropped by suppression rules so far
+    #[wasm_bindgen(js_name = suppressedEvents)]
+    pub fn suppressed_events(&self) -> u64 {
+        self.validator.suppressed_events()
+    }
+}
+
+/// An exchange as JavaScript hands it over, in the shape of a JSONL traffic record
+#[derive(Deserialize)]
+struct ExchangeRecord {
+    method: String,
+    /// Request path, optionally followed by `?` and the query string
+    path: String,
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    request_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    request_body: Option<Value>,
+    status: u16,
+    #[serde(default)]
+    response_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    response_body: Option<Value>,
+}
+
+impl ExchangeRecord {
+    fn to_exchange(&self) -> Result<Exchange, ValidationError> {
+        let method: HttpMethod = self
+            .method
+            .parse()
+            .map_err(|()| ValidationError::TrafficError(format!("unknown method `{}`", self.method)))?;
+
+        let mut request = ObservedRequest::new(method, &self.path);
+        if let Some(query) = &self.query {
+            request.query = Some(query.clone());
+        }
+        for (name, value) in &self.request_headers {
+            request = request.with_header(name, value);
+        }
+        if let Some(body) = &self.request_body {
+            request = request.with_body(body_bytes(body));
+        }
+
+        let mut response = ObservedResponse::new(self.status);
+        for (name, value) in &self.response_headers {
+            response = response.with_header(name, value);
+        }
+        if let Some(body) = &self.response_body {
+            response = response.with_body(body_bytes(body));
+        }
+        Ok(Exchange::new(request, response))
+    }
+}
+
+/// A string body is the raw text; anything else is the JSON document itself
+fn body_bytes(body: &Value) -> Vec<u8> {
+    match body {
+        Value::String(text) => text.as_bytes().to_vec(),
+        document => document.to_string().into_bytes(),
+    }
+}
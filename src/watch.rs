@@ -0,0 +1,119 @@
+use crate::api_validator::ApiValidator;
+use crate::error::ValidationError;
+use crate::spec::{build_api_validator_from_file, BuildOptions};
+use arc_swap::ArcSwap;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Quiet period after a change before rebuilding, so editors' multi-step saves rebuild once
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Called after every reload attempt with its outcome
+pub type ReloadListener = Box<dyn Fn(Result<(), &ValidationError>) + Send>;
+
+/// Keeps an [`ApiValidator`] in sync with a spec file on disk
+///
+/// The spec's directory is watched (editors and config management often
+/// replace files by renaming) and the validator is rebuilt when the file
+/// changes, then swapped in atomically. In-flight validations finish on the
+/// validator they started with. A spec that fails to load or build leaves
+/// the previous validator in place.
+///
+/// Per-validator state such as remembered idempotency keys starts over
+/// after each reload.
+pub struct SpecWatcher {
+    validator: Arc<ArcSwap<ApiValidator>>,
+    path: PathBuf,
+    options: BuildOptions,
+    // Dropping the watcher closes the channel and stops the reload thread
+    _watcher: RecommendedWatcher,
+}
+
+impl SpecWatcher {
+    /// Builds the validator from `path` and starts watching it for changes
+    pub fn start(path: impl Into<PathBuf>, options: BuildOptions) -> Result<Self, ValidationError> {
+        Self::start_with_listener(path, options, Box::new(|_| {}))
+    }
+
+    /// Like [`SpecWatcher::start`], reporting every reload attempt to `listener`
+    pub fn start_with_listener(
+        path: impl Into<PathBuf>,
+        options: BuildOptions,
+        listener: ReloadListener,
+    ) -> Result<Self, ValidationError> {
+        let path = path.into();
+        let validator = Arc::new(ArcSwap::from_pointee(build_api_validator_from_file(&path, &options)?));
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(watch_error)?;
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher.watch(directory, RecursiveMode::NonRecursive).map_err(watch_error)?;
+
+        let shared = Arc::clone(&validator);
+        let watched = path.clone();
+        let reload_options = options.clone();
+        thread::spawn(move || {
+            let file_name = watched.file_name().map(|name| name.to_os_string());
+            let touches_spec = |event: &notify::Event| {
+                !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+            };
+
+            while let Ok(event) = rx.recv() {
+                if !matches!(&event, Ok(event) if touches_spec(event)) {
+                    continue;
+                }
+                // Drain the burst of events a single save produces
+                loop {
+                    match rx.recv_timeout(DEBOUNCE) {
+                        Ok(_) => continue,
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+                match build_api_validator_from_file(&watched, &reload_options) {
+                    Ok(rebuilt) => {
+                        shared.store(Arc::new(rebuilt));
+                        listener(Ok(()));
+                    }
+                    Err(e) => listener(Err(&e)),
+                }
+            }
+        });
+
+        Ok(Self {
+            validator,
+            path,
+            options,
+            _watcher: watcher,
+        })
+    }
+
+    /// The validator built from the latest valid spec
+    pub fn current(&self) -> Arc<ApiValidator> {
+        self.validator.load_full()
+    }
+
+    /// Shared handle that always points at the latest validator, for handing to workers
+    pub fn shared(&self) -> Arc<ArcSwap<ApiValidator>> {
+        Arc::clone(&self.validator)
+    }
+
+    /// Rebuilds from the spec file immediately, keeping the current validator on failure
+    pub fn reload(&self) -> Result<(), ValidationError> {
+        self.validator.store(Arc::new(build_api_validator_from_file(&self.path, &self.options)?));
+        Ok(())
+    }
+}
+
+
+fn watch_error(error: notify::Error) -> ValidationError {
+    ValidationError::WatchError(error.to_string())
+}
@@ -0,0 +1,202 @@
+//! Previewing how a spec revision changes the drift real traffic shows
+//!
+//! Before merging a spec change, its author wants to know whose traffic
+//! it breaks. [`WhatIfReplay`] validates a recorded traffic corpus against
+//! the current revision and the proposed one and sets the findings side
+//! by side: drift only the proposed revision reports was introduced by
+//! the change, drift only the current one reports is fixed by it.
+
+use crate::api_validator::ApiValidator;
+use crate::baseline::{default_fingerprint, BaselineKey, Fingerprint};
+use crate::drift_event::DriftEvent;
+use crate::drift_types::Severity;
+use crate::error::ValidationError;
+use crate::exchange::Exchange;
+use crate::spec::{build_api_validator_with_options, BuildOptions};
+use crate::traffic::TrafficRecord;
+use openapiv3::OpenAPI;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+/// Validates traffic against two revisions of a spec
+pub struct WhatIfReplay {
+    current: ApiValidator,
+    proposed: ApiValidator,
+    fingerprint: Arc<dyn Fingerprint>,
+}
+
+/// How a proposed revision changes the drift of a traffic corpus
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WhatIfReport {
+    /// Exchanges replayed
+    pub exchanges: u64,
+    /// Records that couldn't be turned into an exchange
+    pub malformed: u64,
+    /// Exchanges only the proposed revision has an operation for
+    pub newly_routed: u64,
+    /// Exchanges only the current revision has an operation for
+    pub no_longer_routed: u64,
+    /// Drift reported against the proposed revision but not the current one
+    pub introduced: Vec<DriftDelta>,
+    /// Drift reported against the current revision but not the proposed one
+    pub fixed: Vec<DriftDelta>,
+    /// Occurrences of drift both revisions report
+    pub unchanged: u64,
+}
+
+/// One drift fingerprint whose occurrence the revision changes
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftDelta {
+    #[serde(flatten)]
+    pub key: BaselineKey,
+    pub severity: Severity,
+    /// Exchanges showing the drift under one revision but not the other
+    pub exchanges: u64,
+    /// The first event found, as reported against its revision
+    pub example: DriftEvent,
+}
+
+impl WhatIfReplay {
+    pub fn new(current: ApiValidator, proposed: ApiValidator) -> Self {
+        Self {
+            current,
+            proposed,
+            fingerprint: default_fingerprint(),
+        }
+    }
+
+    /// Builds the validators of both revisions with the same options
+    pub fn from_specs(current: &OpenAPI, proposed: &OpenAPI, options: &BuildOptions) -> Result<Self, ValidationError> {
+        Ok(Self::new(
+            build_api_validator_with_options(current, options)?,
+            build_api_validator_with_options(proposed, options)?,
+        ))
+    }
+
+    /// Tells drift apart by `fingerprint` instead of by [`crate::baseline::DefaultFingerprint`]
+    pub fn with_fingerprint(mut self, fingerprint: impl Fingerprint + 'static) -> Self {
+        self.fingerprint = Arc::new(fingerprint);
+        self
+    }
+
+    /// Validates an exchange against both revisions, adding the difference to `report`
+    pub fn replay(&self, exchange: &Exchange, report: &mut WhatIfReport) {
+        report.exchanges += 1;
+        // An exchange neither revision routes shows no drift either way
+        let (current, proposed) = match (self.current.validate_exchange(exchange), self.proposed.validate_exchange(exchange)) {
+            (Ok(current), Ok(proposed)) => (current, proposed),
+            (Ok(current), Err(_)) => {
+                report.no_longer_routed += 1;
+                (current, Vec::new())
+            }
+            (Err(_), Ok(proposed)) => {
+                report.newly_routed += 1;
+                (Vec::new(), proposed)
+            }
+            (Err(_), Err(_)) => return,
+        };
+
+        let current = self.by_fingerprint(current);
+        let mut proposed = self.by_fingerprint(proposed);
+        for (key, event) in current {
+            match proposed.remove(&key) {
+                Some(_) => report.unchanged += 1,
+                None => report.record(false, key, event),
+            }
+        }
+        for (key, event) in proposed {
+            report.record(true, key, event);
+        }
+    }
+
+    /// Replays every record, counting those that aren't valid exchanges
+    pub fn replay_records<I>(&self, records: I) -> WhatIfReport
+    where
+        I: IntoIterator<Item = Result<TrafficRecord, ValidationError>>,
+    {
+        let mut report = WhatIfReport::default();
+        for record in records {
+            match record.and_then(|record| record.to_exchange()) {
+                Ok(exchange) => self.replay(&exchange, &mut report),
+                Err(_) => report.malformed += 1,
+            }
+        }
+        report.sort();
+        report
+    }
+
+    /// The first event of each fingerprint in an exchange's events
+    fn by_fingerprint(&self, events: Vec<DriftEvent>) -> BTreeMap<BaselineKey, DriftEvent> {
+        let mut by_fingerprint = BTreeMap::new();
+        for event in events {
+            by_fingerprint.entry(self.fingerprint.fingerprint(&event)).or_insert(event);
+        }
+        by_fingerprint
+    }
+}
+
+impl WhatIfReport {
+    fn record(&mut self, introduced: bool, key: BaselineKey, event: DriftEvent) {
+        let deltas = if introduced { &mut self.introduced } else { &mut self.fixed };
+        match deltas.iter_mut().find(|delta| delta.key == key) {
+            Some(delta) => delta.exchanges += 1,
+            None => deltas.push(DriftDelta {
+                key,
+                severity: event.severity,
+                exchanges: 1,
+                example: event,
+            }),
+        }
+    }
+
+    /// Orders drift most severe first, then most frequent first
+    pub fn sort(&mut self) {
+        for deltas in [&mut self.introduced, &mut self.fixed] {
+            deltas.sort_by(|a, b| b.severity.cmp(&a.severity).then(b.exchanges.cmp(&a.exchanges)));
+        }
+    }
+
+    /// Renders the comparison as the plain-text tables terminals and CI logs show
+    pub fn render_table(&self) -> String {
+        let mut out = format!(
+            "{} exchanges replayed, {} newly routed, {} no longer routed, {} drift occurrences unchanged\n",
+            self.exchanges, self.newly_routed, self.no_longer_routed, self.unchanged
+        );
+        if self.malformed > 0 {
+            let _ = writeln!(out, "{} malformed records skipped", self.malformed);
+        }
+        for (title, deltas) in [("INTRODUCED", &self.introduced), ("FIXED", &self.fixed)] {
+            let _ = writeln!(out, "\n{} ({})", title, deltas.len());
+            if deltas.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "{:<10} {:>9}  DRIFT", "SEVERITY", "EXCHANGES");
+            for delta in deltas {
+                let _ = writeln!(out, "{:<10} {:>9}  {}", delta.severity.as_str(), delta.exchanges, delta_label(&delta.key));
+            }
+        }
+        out
+    }
+
+    /// Serializes the comparison as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, ValidationError> {
+        serde_json::to_string_pretty(self).map_err(|e| ValidationError::ReportError(e.to_string()))
+    }
+}
+
+/// `DRIFT_TYPE GET /users/{id} 200 body/email`, leaving out what the fingerprint doesn't set
+fn delta_label(key: &BaselineKey) -> String {
+    let mut label = key.drift_type.as_str().to_string();
+    if let Some(method) = key.method {
+        label = format!("{} {}", label, method.as_str());
+    }
+    if let Some(path_template) = &key.path_template {
+        label = format!("{} {}", label, path_template);
+    }
+    if let Some(status_code) = key.status_code {
+        label = format!("{} {}", label, status_code);
+    }
+    format!("{} {}", label, key.location)
+}
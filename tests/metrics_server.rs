@@ -0,0 +1,48 @@
+//! The built-in metrics endpoint keeps serving scrapes past misbehaving clients
+
+use api_spec_drift_monitor_poc::{spawn_metrics_server, DriftMetrics};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+fn start_server() -> u16 {
+    // Bind to an ephemeral port to learn a free one, then serve on it
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    spawn_metrics_server(("127.0.0.1", port), Arc::new(DriftMetrics::new())).unwrap();
+    port
+}
+
+fn scrape(port: u16) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+    stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn idle_connection_does_not_block_scrapes() {
+    let port = start_server();
+    let _idle = TcpStream::connect(("127.0.0.1", port)).unwrap();
+
+    let started = Instant::now();
+    let response = scrape(port);
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(started.elapsed() < Duration::from_secs(20));
+}
+
+#[test]
+fn endless_request_line_is_cut_off() {
+    let port = start_server();
+    let mut flood = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    flood.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+    // Far past the request size limit, without a newline; the server stops
+    // reading and answers, so writing may fail once it has closed
+    let _ = flood.write_all(&[b'A'; 256 * 1024]);
+    let mut response = String::new();
+    let _ = flood.read_to_string(&mut response);
+
+    assert!(scrape(port).starts_with("HTTP/1.1 200 OK"));
+}
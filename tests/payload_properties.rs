@@ -0,0 +1,475 @@
+//! Properties of the validators over payloads generated from the specs' schemas
+//!
+//! For every operation of the demo and storefront specs, proptest draws
+//! request and response bodies from strategies built from the operation's
+//! schemas: which optional properties appear, array lengths, numbers within
+//! their bounds, enum values, `null` for nullable values, strings in their
+//! `format` or matching their `pattern`. Generated payloads must pass body
+//! validation, and each single-constraint mutation of them must be reported
+//! as the drift the mutation names. Failures shrink to a minimal payload
+//! and are persisted by proptest so they're replayed first on later runs.
+
+use api_spec_drift_monitor_poc::spec::transform::Direction;
+use api_spec_drift_monitor_poc::{
+    build_api_validator, load_openapi_spec, load_spec_document, ApiValidator, DriftEvent, Exchange, HttpMethod,
+    ObservedRequest, ObservedResponse, PayloadGenerator, ValidationContext,
+};
+use proptest::prelude::*;
+use proptest::strategy::Union;
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Nesting depth past which strategies fall back to the schema's fixed example
+const MAX_DEPTH: usize = 6;
+
+/// How many items past `minItems` a generated array may have when `maxItems` doesn't say
+const ARRAY_SPREAD: usize = 4;
+
+/// Strings for `format`s the validators assert, as regular expressions
+const FORMATS: &[(&str, &str)] = &[
+    ("uuid", "[0-9a-f]{8}-[0-9a-f]{4}-[1-5][0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}"),
+    ("date", "20[0-9]{2}-(0[1-9]|1[0-2])-(0[1-9]|1[0-9]|2[0-8])"),
+    (
+        "date-time",
+        "20[0-9]{2}-(0[1-9]|1[0-2])-(0[1-9]|1[0-9]|2[0-8])T([01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9](\\.[0-9]{1,6})?(Z|[+-](0[0-9]|1[0-3]):[0-5][0-9])",
+    ),
+    ("email", "[a-z0-9]{1,12}(\\.[a-z0-9]{1,8})?@[a-z0-9]{1,12}\\.[a-z]{2,6}"),
+    ("uri", "https://[a-z0-9]{1,12}\\.example\\.com(/[a-zA-Z0-9._~-]{1,12}){0,3}"),
+];
+
+struct Spec {
+    path: &'static str,
+    document: Value,
+    validator: ApiValidator,
+    operations: Vec<Operation>,
+}
+
+struct Operation {
+    method: HttpMethod,
+    path: String,
+    request_schema: Option<Value>,
+    status: u16,
+    response_schema: Value,
+}
+
+impl Operation {
+    fn label(&self, spec: &Spec) -> String {
+        format!("{} {} in {}", self.method.as_str(), self.path, spec.path)
+    }
+}
+
+fn specs() -> &'static [Spec] {
+    static SPECS: OnceLock<Vec<Spec>> = OnceLock::new();
+    SPECS.get_or_init(|| {
+        ["test-api-spec.yaml", "benches/fixtures/storefront.yaml"]
+            .into_iter()
+            .map(|path| {
+                let full_path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+                let spec = load_openapi_spec(&full_path).expect("spec loads");
+                let document = load_spec_document(&full_path).expect("spec loads");
+                let operations = operations(&document);
+                assert!(!operations.is_empty(), "{} has operations with JSON responses", path);
+                Spec {
+                    path,
+                    validator: build_api_validator(&spec).expect("spec builds"),
+                    document,
+                    operations,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Operations with a JSON success response, and their JSON request body if any
+fn operations(document: &Value) -> Vec<Operation> {
+    let json_schema = |content: Option<&Value>| content?.pointer("/application~1json/schema").cloned();
+    let mut operations = Vec::new();
+    for (path, item) in document["paths"].as_object().into_iter().flatten() {
+        for (method, operation) in item.as_object().into_iter().flatten() {
+            let Ok(method) = HttpMethod::from_str(&method.to_uppercase()) else {
+                continue;
+            };
+            let success = operation["responses"].as_object().into_iter().flatten().find_map(|(status, response)| {
+                let status = status.parse::<u16>().ok().filter(|status| (200..300).contains(status))?;
+                Some((status, json_schema(response.get("content"))?))
+            });
+            let Some((status, response_schema)) = success else {
+                continue;
+            };
+            operations.push(Operation {
+                method,
+                // Path parameters only need to route here
+                path: path.replace('{', "p").replace('}', ""),
+                request_schema: json_schema(operation.pointer("/requestBody/content")),
+                status,
+                response_schema,
+            });
+        }
+    }
+    operations
+}
+
+/// Strategies for the values the schemas of one document allow
+struct Schemas<'a> {
+    document: &'a Value,
+    direction: Direction,
+}
+
+impl Schemas<'_> {
+    fn strategy(&self, schema: &Value, depth: usize) -> BoxedStrategy<Value> {
+        if depth >= MAX_DEPTH {
+            return Just(self.example(schema)).boxed();
+        }
+        let resolved = self.resolve(schema);
+        let strategy = self.resolved_strategy(resolved, depth);
+        let nullable = [schema, resolved].iter().any(|schema| schema.get("nullable") == Some(&Value::Bool(true)));
+        if nullable {
+            prop_oneof![1 => Just(Value::Null), 3 => strategy].boxed()
+        } else {
+            strategy
+        }
+    }
+
+    fn resolved_strategy(&self, schema: &Value, depth: usize) -> BoxedStrategy<Value> {
+        if let Some(value) = schema.get("const") {
+            return Just(value.clone()).boxed();
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array).filter(|values| !values.is_empty()) {
+            return proptest::sample::select(values.clone()).boxed();
+        }
+        if let Some(branches) = schema.get("allOf").and_then(Value::as_array) {
+            let branches: Vec<_> = branches.iter().map(|branch| self.strategy(branch, depth)).collect();
+            return branches.prop_map(merge_objects).boxed();
+        }
+        // Only the first `oneOf` branch, as another may match what the others generate
+        if let Some(first) = schema.get("oneOf").and_then(Value::as_array).and_then(|branches| branches.first()) {
+            return self.strategy(first, depth);
+        }
+        if let Some(branches) = schema.get("anyOf").and_then(Value::as_array).filter(|branches| !branches.is_empty()) {
+            return Union::new(branches.iter().map(|branch| self.strategy(branch, depth))).boxed();
+        }
+
+        match declared_type(schema) {
+            Some("object") => self.object(schema, depth),
+            Some("array") => self.array(schema, depth),
+            Some("string") => self.string(schema),
+            Some("integer") => integer(schema),
+            Some("number") => number(schema),
+            Some("boolean") => any::<bool>().prop_map(Value::Bool).boxed(),
+            _ => Just(self.example(schema)).boxed(),
+        }
+    }
+
+    fn object(&self, schema: &Value, depth: usize) -> BoxedStrategy<Value> {
+        let excluded = match self.direction {
+            Direction::Request => "readOnly",
+            Direction::Response => "writeOnly",
+        };
+        let properties: Vec<(&String, &Value)> = schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+            .filter(|(_, property)| self.resolve(property).get(excluded) != Some(&Value::Bool(true)))
+            .collect();
+        let required: Vec<&str> =
+            schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str).collect();
+        // Every property is generated when the optional ones might not reach `minProperties`
+        let min_properties = schema.get("minProperties").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let all_required = min_properties > required.len();
+
+        let fields: Vec<BoxedStrategy<Option<(String, Value)>>> = properties
+            .into_iter()
+            .map(|(name, property)| {
+                let name = name.clone();
+                let is_required = all_required || required.contains(&name.as_str());
+                let field = self.strategy(property, depth + 1).prop_map(move |value| (name.clone(), value));
+                if is_required {
+                    field.prop_map(Some).boxed()
+                } else {
+                    proptest::option::of(field).boxed()
+                }
+            })
+            .collect();
+        let additional = match schema.get("additionalProperties") {
+            Some(additional @ Value::Object(_)) => {
+                proptest::collection::btree_map("extra_[a-z]{1,6}", self.strategy(additional, depth + 1), 0..3)
+                    .prop_map(|entries| entries.into_iter().collect::<Vec<_>>())
+                    .boxed()
+            }
+            _ => Just(Vec::new()).boxed(),
+        };
+
+        (fields, additional)
+            .prop_map(|(fields, additional)| {
+                let mut object: Map<String, Value> = additional.into_iter().collect();
+                object.extend(fields.into_iter().flatten());
+                Value::Object(object)
+            })
+            .boxed()
+    }
+
+    fn array(&self, schema: &Value, depth: usize) -> BoxedStrategy<Value> {
+        let min_items = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let max_items = schema.get("maxItems").and_then(Value::as_u64).map_or(usize::MAX, |max| max as usize);
+        let max_items = max_items.min(min_items + ARRAY_SPREAD).max(min_items);
+        let Some(items) = schema.get("items") else {
+            return Just(Value::Array(Vec::new())).boxed();
+        };
+        let values = proptest::collection::vec(self.strategy(items, depth + 1), min_items..=max_items);
+        if schema.get("uniqueItems") != Some(&Value::Bool(true)) {
+            return values.prop_map(Value::Array).boxed();
+        }
+        values
+            .prop_map(|values| {
+                let mut unique = Vec::with_capacity(values.len());
+                for value in values {
+                    if !unique.contains(&value) {
+                        unique.push(value);
+                    }
+                }
+                unique
+            })
+            .prop_filter("too few unique items", move |unique| unique.len() >= min_items)
+            .prop_map(Value::Array)
+            .boxed()
+    }
+
+    fn string(&self, schema: &Value) -> BoxedStrategy<Value> {
+        let pattern = schema.get("pattern").and_then(Value::as_str);
+        let format = schema.get("format").and_then(Value::as_str);
+        let regex = match (pattern, format) {
+            (Some(pattern), _) => unanchored(pattern),
+            (None, Some(format)) => FORMATS.iter().find(|(name, _)| *name == format).map(|(_, regex)| regex.to_string()),
+            (None, None) => {
+                let min_length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0);
+                let max_length = schema.get("maxLength").and_then(Value::as_u64).unwrap_or(min_length + 16);
+                Some(format!("\\PC{{{},{}}}", min_length, max_length.max(min_length)))
+            }
+        };
+        match regex.and_then(|regex| proptest::string::string_regex(&regex).ok()) {
+            Some(strings) => strings.prop_map(Value::String).boxed(),
+            // Formats and patterns without a strategy take the schema's fixed example
+            None => Just(self.example(schema)).boxed(),
+        }
+    }
+
+    fn example(&self, schema: &Value) -> Value {
+        PayloadGenerator::new(self.document).direction(self.direction).generate(schema)
+    }
+
+    /// Follows local `$ref`s
+    fn resolve<'s>(&'s self, schema: &'s Value) -> &'s Value {
+        let mut current = schema;
+        // Bounded, in case references form a cycle
+        for _ in 0..MAX_DEPTH {
+            let Some(target) = current
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| self.document.pointer(pointer))
+            else {
+                break;
+            };
+            current = target;
+        }
+        current
+    }
+}
+
+/// Merges the objects generated for `allOf` branches; a branch that
+/// isn't an object is the value when nothing was merged before it
+fn merge_objects(branches: Vec<Value>) -> Value {
+    let mut merged = Map::new();
+    for branch in branches {
+        match branch {
+            Value::Object(object) => merged.extend(object),
+            other if merged.is_empty() => return other,
+            _ => {}
+        }
+    }
+    Value::Object(merged)
+}
+
+fn integer(schema: &Value) -> BoxedStrategy<Value> {
+    let (low, high) = integer_bounds(schema);
+    let (low, high) = (low.unwrap_or(i64::MIN), high.unwrap_or(i64::MAX));
+    match schema.get("multipleOf").and_then(Value::as_i64).filter(|step| *step > 0) {
+        Some(step) => {
+            let (first, last) = (low.div_euclid(step) + i64::from(low.rem_euclid(step) != 0), high.div_euclid(step));
+            (first..=last.max(first)).prop_map(move |k| Value::from(k.saturating_mul(step))).boxed()
+        }
+        None => (low..=high.max(low)).prop_map(Value::from).boxed(),
+    }
+}
+
+fn number(schema: &Value) -> BoxedStrategy<Value> {
+    // Multiples are generated as whole numbers, of whole steps only
+    if schema.get("multipleOf").is_some() {
+        return integer(schema).prop_map(|value| Value::from(value.as_f64().unwrap_or_default())).boxed();
+    }
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    let exclusive = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+    let low = bound("minimum").map(|low| (low, exclusive("exclusiveMinimum"))).or(bound("exclusiveMinimum").map(|low| (low, true)));
+    let high = bound("maximum").map(|high| (high, exclusive("exclusiveMaximum"))).or(bound("exclusiveMaximum").map(|high| (high, true)));
+    let (low, low_exclusive) = low.unwrap_or((-1e9, false));
+    let (high, _) = high.unwrap_or((low.max(0.0) + 1e9, false));
+    // A half-open range excludes `high`; an exclusive `low` is filtered out
+    (low..high.max(low + f64::EPSILON))
+        .prop_filter("on an exclusive minimum", move |value| !low_exclusive || *value > low)
+        .prop_map(Value::from)
+        .boxed()
+}
+
+/// The smallest and largest whole numbers a schema's bounds allow
+fn integer_bounds(schema: &Value) -> (Option<i64>, Option<i64>) {
+    let exclusive = |keyword: &str| schema.get(keyword) == Some(&Value::Bool(true));
+    let low = match (schema.get("minimum").and_then(Value::as_f64), schema.get("exclusiveMinimum").and_then(Value::as_f64)) {
+        (_, Some(bound)) => Some(bound.floor() as i64 + 1),
+        (Some(minimum), None) if exclusive("exclusiveMinimum") => Some(minimum.floor() as i64 + 1),
+        (Some(minimum), None) => Some(minimum.ceil() as i64),
+        (None, None) => None,
+    };
+    let high = match (schema.get("maximum").and_then(Value::as_f64), schema.get("exclusiveMaximum").and_then(Value::as_f64)) {
+        (_, Some(bound)) => Some(bound.ceil() as i64 - 1),
+        (Some(maximum), None) if exclusive("exclusiveMaximum") => Some(maximum.ceil() as i64 - 1),
+        (Some(maximum), None) => Some(maximum.floor() as i64),
+        (None, None) => None,
+    };
+    (low, high)
+}
+
+/// The schema's `type`, inferred from `properties` or `items` when not declared
+fn declared_type(schema: &Value) -> Option<&str> {
+    match schema.get("type") {
+        Some(Value::String(name)) => Some(name.as_str()),
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).find(|name| *name != "null"),
+        _ if schema.get("properties").is_some() => Some("object"),
+        _ if schema.get("items").is_some() => Some("array"),
+        _ => None,
+    }
+}
+
+/// A `pattern` as a regex for generating whole strings, without its `^...$`
+/// anchors; `None` when it has anchors elsewhere, which can't be generated
+fn unanchored(pattern: &str) -> Option<String> {
+    let pattern = pattern.strip_prefix('^').unwrap_or(pattern);
+    let pattern = match pattern.strip_suffix('$') {
+        Some(stripped) if !stripped.ends_with('\\') => stripped,
+        _ => pattern,
+    };
+    (!pattern.contains(['^', '$'])).then(|| pattern.to_string())
+}
+
+/// An operation with a request body (if it takes one) and a response body drawn for it
+fn exchanges() -> impl Strategy<Value = (usize, usize, Option<Value>, Value)> {
+    let operations: Vec<(usize, usize)> = specs()
+        .iter()
+        .enumerate()
+        .flat_map(|(s, spec)| (0..spec.operations.len()).map(move |o| (s, o)))
+        .collect();
+    proptest::sample::select(operations).prop_flat_map(|(s, o)| {
+        let spec = &specs()[s];
+        let operation = &spec.operations[o];
+        let schemas = |direction| Schemas {
+            document: &spec.document,
+            direction,
+        };
+        let request = match &operation.request_schema {
+            Some(schema) => schemas(Direction::Request).strategy(schema, 0).prop_map(Some).boxed(),
+            None => Just(None).boxed(),
+        };
+        (Just(s), Just(o), request, schemas(Direction::Response).strategy(&operation.response_schema, 0))
+    })
+}
+
+fn exchange(operation: &Operation, request_body: Option<&Value>, response_body: &Value) -> Exchange {
+    let mut request = ObservedRequest::new(operation.method, &operation.path);
+    if let Some(body) = request_body {
+        request = request.with_header("content-type", "application/json").with_body(body.to_string());
+    }
+    let response = ObservedResponse::new(operation.status)
+        .with_header("content-type", "application/json")
+        .with_body(response_body.to_string());
+    Exchange::new(request, response)
+}
+
+fn body_drift(validator: &ApiValidator, exchange: &Exchange) -> Vec<DriftEvent> {
+    let events = validator.validate_exchange(exchange).expect("operation routes");
+    events
+        .into_iter()
+        .filter(|event| event.drift_type.as_str().starts_with("REQUEST_BODY") || event.drift_type.as_str().starts_with("RESPONSE_BODY"))
+        .collect()
+}
+
+#[test]
+fn generated_examples_conform() {
+    for spec in specs() {
+        for operation in &spec.operations {
+            let example = |schema, direction| PayloadGenerator::new(&spec.document).direction(direction).generate(schema);
+            let request_body = operation.request_schema.as_ref().map(|schema| example(schema, Direction::Request));
+            let response_body = example(&operation.response_schema, Direction::Response);
+            let drift = body_drift(&spec.validator, &exchange(operation, request_body.as_ref(), &response_body));
+            assert!(drift.is_empty(), "{}: examples drift: {:?}", operation.label(spec), drift);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn generated_payloads_conform((s, o, request_body, response_body) in exchanges()) {
+        let spec = &specs()[s];
+        let operation = &spec.operations[o];
+        let drift = body_drift(&spec.validator, &exchange(operation, request_body.as_ref(), &response_body));
+        prop_assert!(
+            drift.is_empty(),
+            "{}: generated payloads drift: {:?}",
+            operation.label(spec),
+            drift.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn mutations_are_reported((s, o, request_body, response_body) in exchanges()) {
+        let spec = &specs()[s];
+        let operation = &spec.operations[o];
+
+        let responses = PayloadGenerator::new(&spec.document).direction(Direction::Response);
+        for mutation in responses.mutations(&operation.response_schema, &response_body) {
+            let expected = mutation.expected_drift(ValidationContext::ResponseBody).expect("response drift");
+            let drift = body_drift(&spec.validator, &exchange(operation, request_body.as_ref(), &mutation.payload));
+            prop_assert!(
+                drift.iter().any(|event| event.drift_type == expected),
+                "{}: {:?} at '{}' should be reported as {:?}, got {:?}\nresponse: {}",
+                operation.label(spec),
+                mutation.kind,
+                mutation.pointer,
+                expected,
+                drift.iter().map(|event| event.drift_type).collect::<Vec<_>>(),
+                mutation.payload
+            );
+        }
+
+        if let (Some(schema), Some(body)) = (&operation.request_schema, &request_body) {
+            let requests = PayloadGenerator::new(&spec.document).direction(Direction::Request);
+            for mutation in requests.mutations(schema, body) {
+                let expected = mutation.expected_drift(ValidationContext::RequestBody).expect("request drift");
+                let drift = body_drift(&spec.validator, &exchange(operation, Some(&mutation.payload), &response_body));
+                prop_assert!(
+                    drift.iter().any(|event| event.drift_type == expected),
+                    "{}: {:?} at '{}' should be reported as {:?}, got {:?}\nrequest: {}",
+                    operation.label(spec),
+                    mutation.kind,
+                    mutation.pointer,
+                    expected,
+                    drift.iter().map(|event| event.drift_type).collect::<Vec<_>>(),
+                    mutation.payload
+                );
+            }
+        }
+    }
+}
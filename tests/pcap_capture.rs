@@ -0,0 +1,81 @@
+//! Corrupt packet captures end the stream with an error rather than an
+//! allocation sized by the corruption
+
+use api_spec_drift_monitor_poc::{PcapReader, ValidationError};
+
+/// A little-endian pcap file header for Ethernet with the given snapshot length
+fn pcap_header(snaplen: u32) -> Vec<u8> {
+    let mut header = 0xa1b2_c3d4u32.to_le_bytes().to_vec();
+    header.extend(2u16.to_le_bytes());
+    header.extend(4u16.to_le_bytes());
+    header.extend([0; 8]);
+    header.extend(snaplen.to_le_bytes());
+    header.extend(1u32.to_le_bytes());
+    header
+}
+
+/// A pcap record header claiming `length` captured bytes
+fn record_header(length: u32) -> Vec<u8> {
+    let mut header = vec![0; 8];
+    header.extend(length.to_le_bytes());
+    header.extend(length.to_le_bytes());
+    header
+}
+
+/// A little-endian pcapng section header block with no options
+fn section_header() -> Vec<u8> {
+    let mut block = 0x0a0d_0d0au32.to_le_bytes().to_vec();
+    block.extend(28u32.to_le_bytes());
+    block.extend(0x1a2b_3c4du32.to_le_bytes());
+    block.extend(1u16.to_le_bytes());
+    block.extend(0u16.to_le_bytes());
+    block.extend((-1i64).to_le_bytes());
+    block.extend(28u32.to_le_bytes());
+    block
+}
+
+fn only_error(capture: Vec<u8>) -> String {
+    let records: Vec<_> = PcapReader::new(capture.as_slice()).expect("file header reads").collect();
+    match records.as_slice() {
+        [Err(ValidationError::TrafficError(message))] => message.clone(),
+        _ => panic!("expected a single traffic error, got {:?}", records),
+    }
+}
+
+#[test]
+fn oversized_pcap_record_is_rejected() {
+    let mut capture = pcap_header(65535);
+    capture.extend(record_header(0xffff_fff0));
+    assert!(only_error(capture).contains("corrupt pcap record length 4294967280"));
+
+    // Above the snapshot length but well within the cap
+    let mut capture = pcap_header(128);
+    capture.extend(record_header(129));
+    capture.extend([0; 129]);
+    assert!(only_error(capture).contains("snapshot length 128"));
+
+    // A hostile snapshot length doesn't raise the cap
+    let mut capture = pcap_header(u32::MAX);
+    capture.extend(record_header(512 * 1024));
+    assert!(only_error(capture).contains("corrupt pcap record length"));
+}
+
+#[test]
+fn truncated_pcap_record_is_rejected() {
+    let mut capture = pcap_header(65535);
+    capture.extend(record_header(100));
+    capture.extend([0; 10]);
+    assert_eq!(only_error(capture), "capture ends mid-record");
+
+    let mut capture = pcap_header(65535);
+    capture.extend(&record_header(100)[..7]);
+    assert_eq!(only_error(capture), "capture ends mid-record");
+}
+
+#[test]
+fn oversized_pcapng_block_is_rejected() {
+    let mut capture = section_header();
+    capture.extend(6u32.to_le_bytes());
+    capture.extend(0xffff_fff0u32.to_le_bytes());
+    assert!(only_error(capture).contains("corrupt pcapng block length 4294967280"));
+}